@@ -1,15 +1,24 @@
 #[cfg(target_os = "android")]
 pub mod proot {
+    use std::collections::{BTreeMap, HashSet};
     use std::fs::{self, File};
     use std::io::{self, BufReader, Write};
     use std::path::{Path, PathBuf};
+    use std::sync::{Condvar, Mutex, MutexGuard};
 
+    use once_cell::sync::OnceCell;
     use serde::{Deserialize, Serialize};
     use sha2::{Digest, Sha256};
     use tauri::path::BaseDirectory;
     use tauri::{AppHandle, Emitter, Manager};
     use xz2::bufread::XzDecoder;
 
+    use crate::environment::{
+        disk_usage, load_environment_storage, load_proot_settings, save_environment_storage,
+        EnvironmentStorage, ProotSettings,
+    };
+    use crate::fs_utils::is_cross_device_error;
+
     #[cfg(unix)]
     use std::os::unix::fs::PermissionsExt;
 
@@ -17,6 +26,27 @@ pub mod proot {
     const GITHUB_REPO: &str = "shadow3aaa/TruidIDE-Public";
     const RELEASE_TAG: &str = "proot-assets"; // proot 和 rootfs 资源包 tag
 
+    // 重建 rootfs 期间暂存容器内的 /root，与 proot 目录同级，便于直接重命名
+    const PRESERVED_HOME_DIR: &str = "proot-home";
+    const EVENT_STORAGE_MIGRATION: &str = "truidide://environment/migration-progress";
+    // 校验时检查的 rootfs 关键文件，相对于架构根目录
+    const CRITICAL_ROOTFS_FILES: &[&str] = &[
+        "bin",
+        "lib",
+        "usr/bin/bash",
+        "usr/bin/sh",
+        "usr/bin/env",
+        "usr/bin/pacman",
+        "usr/lib/libc.so.6",
+        "etc/passwd",
+        "etc/group",
+        "etc/pacman.conf",
+    ];
+    // 导出快照时只保留目录本身、不打包内容的缓存目录（相对于架构根目录）
+    const SNAPSHOT_EXCLUDED_DIRS: &[&str] = &["var/cache", "var/tmp", "tmp", "root/.cache"];
+    // 已安装的资源版本，记录在 files/proot 下
+    const INSTALLED_ASSETS_FILE: &str = "installed-assets.json";
+
     // 镜像站点列表（按优先级排序）
     const MIRRORS: &[&str] = &[
         "https://github.com",
@@ -38,22 +68,142 @@ pub mod proot {
             #[serde(skip_serializing_if = "Option::is_none")]
             percentage: Option<u8>,
         },
+        /// 用户暂停下载，已下载部分保留在磁盘上
+        Paused {
+            file: String,
+            downloaded: u64,
+            total: Option<u64>,
+        },
         Completed,
+        Cancelled,
         Error {
             message: String,
         },
     }
 
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    enum DownloadState {
+        #[default]
+        Idle,
+        Running,
+        Paused,
+        Cancelled,
+    }
+
+    /// 下载任务与暂停/继续/取消命令共享的控制状态
+    #[derive(Default)]
+    struct DownloadControl {
+        state: Mutex<DownloadState>,
+        changed: Condvar,
+    }
+
+    static DOWNLOAD_CONTROL: OnceCell<DownloadControl> = OnceCell::new();
+
+    fn download_control() -> &'static DownloadControl {
+        DOWNLOAD_CONTROL.get_or_init(DownloadControl::default)
+    }
+
+    impl DownloadControl {
+        fn lock(&self) -> MutexGuard<'_, DownloadState> {
+            self.state
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+        }
+
+        fn state(&self) -> DownloadState {
+            *self.lock()
+        }
+
+        fn set(&self, state: DownloadState) {
+            *self.lock() = state;
+            self.changed.notify_all();
+        }
+
+        /// 开始下载或重建 rootfs，同一时间只允许一个任务
+        fn begin(&self) -> Result<(), String> {
+            let mut state = self.lock();
+            if *state != DownloadState::Idle {
+                return Err("proot 资源正在下载中".to_string());
+            }
+            *state = DownloadState::Running;
+            Ok(())
+        }
+
+        /// 仅在有下载任务时切换状态
+        fn transition(&self, from: &[DownloadState], to: DownloadState) -> Result<(), String> {
+            let mut state = self.lock();
+            if *state == DownloadState::Idle {
+                return Err("当前没有正在进行的下载".to_string());
+            }
+            if !from.contains(&*state) {
+                return Ok(());
+            }
+            *state = to;
+            self.changed.notify_all();
+            Ok(())
+        }
+
+        /// 暂停时阻塞直到继续；已取消则返回错误
+        fn checkpoint(&self) -> io::Result<()> {
+            let mut state = self.lock();
+            while *state == DownloadState::Paused {
+                state = self
+                    .changed
+                    .wait(state)
+                    .unwrap_or_else(|poisoned| poisoned.into_inner());
+            }
+            if *state == DownloadState::Cancelled {
+                return Err(io::Error::new(io::ErrorKind::Interrupted, "下载已取消"));
+            }
+            Ok(())
+        }
+    }
+
     #[derive(Clone, Debug)]
     pub struct ProotEnv {
         pub base_dir: PathBuf,
         pub proot_bin: PathBuf,
         pub rootfs_root: PathBuf,
+        /// 容器的根目录，由 `rootfs_arch_dir` 在运行时确定
         pub rootfs_dir: PathBuf,
         pub tmp_dir: PathBuf,
+        pub settings: ProotSettings,
+    }
+
+    impl ProotEnv {
+        /// 所有 proot 调用共用的环境变量
+        pub fn base_env(&self) -> Vec<(&'static str, String)> {
+            let mut env = vec![("PROOT_TMP_DIR", self.tmp_dir.to_string_lossy().to_string())];
+            if self.settings.no_seccomp {
+                env.push(("PROOT_NO_SECCOMP", "1".to_string()));
+            }
+            env
+        }
+
+        /// 所有 proot 调用共用的参数：rootfs、基础绑定以及用户配置的挂载和参数
+        ///
+        /// 调用方在此之后追加 `--cwd`、自身需要的绑定和要执行的程序。
+        pub fn base_args(&self) -> Vec<String> {
+            let mut args = vec![
+                format!("--rootfs={}", self.rootfs_dir.to_string_lossy()),
+                "--root-id".to_string(),
+                "--kill-on-exit".to_string(),
+                "--link2symlink".to_string(),
+                "--bind=/dev".to_string(),
+                "--bind=/proc".to_string(),
+                "--bind=/sys".to_string(),
+                "--bind=/dev/urandom:/dev/random".to_string(),
+            ];
+            args.extend(self.settings.args());
+            args
+        }
     }
 
-    /// 从 GitHub Release 下载文件（支持进度回调和镜像重试）
+    /// 从 GitHub Release 下载文件（支持进度回调和断点续传）
+    ///
+    /// 数据先写入 `{dest}.part`，连接中断后保留已下载部分，下次通过 Range
+    /// 请求继续；完整下载后才重命名为 `dest`。暂停时断开连接，继续后同样
+    /// 从断点重新请求。
     fn download_from_github(
         app: &AppHandle,
         url: &str,
@@ -68,12 +218,48 @@ pub mod proot {
             .build()
             .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
 
-        // 发送请求获取文件大小
-        let response = client
-            .get(url)
+        let control = download_control();
+        loop {
+            control.checkpoint()?;
+            if download_range(app, &client, url, dest, file_name)? {
+                return Ok(());
+            }
+            // 暂停：等待继续或取消
+            control.checkpoint()?;
+            eprintln!("继续下载 {}", file_name);
+        }
+    }
+
+    /// 下载 `.part` 文件剩余部分；返回 `false` 表示因暂停而中止
+    fn download_range(
+        app: &AppHandle,
+        client: &reqwest::blocking::Client,
+        url: &str,
+        dest: &Path,
+        file_name: &str,
+    ) -> io::Result<bool> {
+        use reqwest::header::RANGE;
+        use reqwest::StatusCode;
+        use std::fs::OpenOptions;
+
+        let partial_path = partial_download_path(dest);
+        let resume_from = fs::metadata(&partial_path).map(|m| m.len()).unwrap_or(0);
+
+        let mut request = client.get(url);
+        if resume_from > 0 {
+            eprintln!("从 {} 字节处继续下载 {}", resume_from, file_name);
+            request = request.header(RANGE, format!("bytes={resume_from}-"));
+        }
+        let response = request
             .send()
             .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
 
+        // 已下载部分就是完整文件
+        if resume_from > 0 && response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+            fs::rename(&partial_path, dest)?;
+            return Ok(true);
+        }
+
         if !response.status().is_success() {
             return Err(io::Error::new(
                 io::ErrorKind::Other,
@@ -81,17 +267,43 @@ pub mod proot {
             ));
         }
 
-        let total_size = response.content_length();
-        let mut downloaded: u64 = 0;
-        let mut file = File::create(dest)?;
+        // 服务器不支持 Range 时返回 200 和完整内容，需要从头写入
+        let resumed = resume_from > 0 && response.status() == StatusCode::PARTIAL_CONTENT;
+        let mut downloaded: u64 = if resumed { resume_from } else { 0 };
+        let total_size = response.content_length().map(|len| len + downloaded);
+        let mut file = if resumed {
+            OpenOptions::new().append(true).open(&partial_path)?
+        } else {
+            File::create(&partial_path)?
+        };
 
         // 使用 response.bytes() 流式读取
         use std::io::Read;
         let mut reader = response;
         let mut buffer = [0u8; 8192];
         let mut last_report_time = std::time::Instant::now();
+        let control = download_control();
 
         loop {
+            match control.state() {
+                DownloadState::Cancelled => {
+                    return Err(io::Error::new(io::ErrorKind::Interrupted, "下载已取消"));
+                }
+                DownloadState::Paused => {
+                    file.flush()?;
+                    let _ = app.emit(
+                        "proot-download-progress",
+                        DownloadProgress::Paused {
+                            file: file_name.to_string(),
+                            downloaded,
+                            total: total_size,
+                        },
+                    );
+                    return Ok(false);
+                }
+                DownloadState::Idle | DownloadState::Running => {}
+            }
+
             match reader.read(&mut buffer) {
                 Ok(0) => break, // EOF
                 Ok(n) => {
@@ -118,6 +330,16 @@ pub mod proot {
                 Err(e) => return Err(e),
             }
         }
+        file.flush()?;
+        drop(file);
+
+        if total_size.is_some_and(|total| downloaded < total) {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("下载中断: 已接收 {downloaded} 字节"),
+            ));
+        }
+        fs::rename(&partial_path, dest)?;
 
         // 发送完成进度
         let _ = app.emit(
@@ -130,7 +352,13 @@ pub mod proot {
             },
         );
 
-        Ok(())
+        Ok(true)
+    }
+
+    fn partial_download_path(dest: &Path) -> PathBuf {
+        let mut name = dest.file_name().unwrap_or_default().to_os_string();
+        name.push(".part");
+        dest.with_file_name(name)
     }
 
     /// 尝试从多个镜像下载文件
@@ -149,6 +377,8 @@ pub mod proot {
 
             match download_from_github(app, &url, dest, filename) {
                 Ok(_) => return Ok(()),
+                // 用户取消，不再尝试其他镜像
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => return Err(e),
                 Err(e) => {
                     eprintln!("从镜像 {} 下载失败: {}", mirror, e);
                     last_error = Some(e);
@@ -182,19 +412,24 @@ pub mod proot {
         return "x86";
     }
 
+    /// 当前设备架构对应的 Android ABI，用于资源文件名
+    fn asset_abi() -> &'static str {
+        match get_arch_suffix() {
+            "aarch64" => "arm64-v8a",
+            "armv7" => "armeabi-v7a",
+            "x86_64" => "x86_64",
+            "x86" => "x86",
+            arch => arch,
+        }
+    }
+
     /// 从 GitHub Release 下载并提取 proot 和 rootfs
     fn download_and_extract_assets(app: &AppHandle, dest: &Path) -> Result<(), String> {
         let arch = get_arch_suffix();
 
         // 下载 proot-assets-{abi}.zip
         // 这个 ZIP 包含 proot/ 目录和 rootfs.tar.xz 文件
-        let abi = match arch {
-            "aarch64" => "arm64-v8a",
-            "armv7" => "armeabi-v7a",
-            "x86_64" => "x86_64",
-            "x86" => "x86",
-            _ => arch,
-        };
+        let abi = asset_abi();
         let assets_filename = format!("proot-assets-{}.zip", abi);
         let sha256_filename = format!("proot-assets-{}.zip.sha256", abi);
 
@@ -208,6 +443,7 @@ pub mod proot {
         let assets_zip_path = temp_dir.join(&assets_filename);
         let sha256_path = temp_dir.join(&sha256_filename);
 
+        // 中断的下载保留在 temp_download 中，再次调用时从断点继续
         if !assets_zip_path.exists() {
             eprintln!("正在从 GitHub 下载资源包 ({})...", abi);
             download_with_mirrors(
@@ -218,7 +454,9 @@ pub mod proot {
                 &assets_zip_path,
             )
             .map_err(|e| format!("下载资源包失败: {}", e))?;
+        }
 
+        if !sha256_path.exists() {
             // 下载 SHA256 校验文件
             eprintln!("正在下载 SHA256 校验文件...");
             download_with_mirrors(
@@ -229,35 +467,323 @@ pub mod proot {
                 &sha256_path,
             )
             .map_err(|e| format!("下载 SHA256 文件失败: {}", e))?;
+        }
 
-            // 读取期望的 SHA256 值
-            let expected_hash = fs::read_to_string(&sha256_path)
-                .map_err(|e| format!("读取 SHA256 文件失败: {}", e))?
-                .trim()
-                .to_lowercase();
+        // 读取期望的 SHA256 值
+        let expected_hash = fs::read_to_string(&sha256_path)
+            .map_err(|e| format!("读取 SHA256 文件失败: {}", e))?
+            .trim()
+            .to_lowercase();
 
-            // 验证文件完整性
-            eprintln!("正在验证文件完整性...");
-            if !verify_sha256(&assets_zip_path, &expected_hash)
-                .map_err(|e| format!("SHA256 校验失败: {}", e))?
-            {
-                // 校验失败，删除下载的文件
-                let _ = fs::remove_file(&assets_zip_path);
-                let _ = fs::remove_file(&sha256_path);
-                return Err("文件校验失败，SHA256 不匹配！文件可能已损坏或被篡改。".to_string());
-            }
-            eprintln!("文件校验通过！");
+        // 验证文件完整性（续传的文件同样需要校验）
+        eprintln!("正在验证文件完整性...");
+        if !verify_sha256(&assets_zip_path, &expected_hash)
+            .map_err(|e| format!("SHA256 校验失败: {}", e))?
+        {
+            // 校验失败，删除下载的文件
+            let _ = fs::remove_file(&assets_zip_path);
+            let _ = fs::remove_file(&sha256_path);
+            return Err("文件校验失败，SHA256 不匹配！文件可能已损坏或被篡改。".to_string());
         }
+        eprintln!("文件校验通过！");
 
         // 解压资源包到目标目录
         eprintln!("正在解压资源包...");
 
-        let file = File::open(&assets_zip_path).map_err(|e| format!("打开资源包失败: {e}"))?;
+        extract_zip(app, &assets_zip_path, dest, &assets_filename)?;
+
+        // 删除压缩包以节省空间
+        let _ = fs::remove_file(&assets_zip_path);
+
+        // 清理临时目录
+        let _ = fs::remove_dir_all(&temp_dir);
+
+        mark_proot_executable(dest);
+
+        Ok(())
+    }
+
+    /// Release 中的资源清单 `proot-assets-manifest-{abi}.json`
+    ///
+    /// 资源拆分为多个压缩包（proot 本身、rootfs 基础层、工具链叠加层等），
+    /// 更新时只下载 sha256 变化的压缩包。
+    #[derive(Clone, Debug, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct AssetsManifest {
+        version: String,
+        archives: Vec<AssetArchive>,
+    }
+
+    #[derive(Clone, Debug, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct AssetArchive {
+        /// 稳定的标识，例如 `proot`、`rootfs`
+        name: String,
+        /// Release 中的文件名，`.zip` 或 `.tar.xz`
+        file: String,
+        sha256: String,
+        #[serde(default)]
+        size: Option<u64>,
+        /// 解压到的目录，相对于 files/proot；为空表示 files/proot 本身
+        #[serde(default)]
+        destination: String,
+        /// 为 `true` 时先清空目标目录；否则叠加到已有内容上
+        #[serde(default)]
+        replace: bool,
+    }
+
+    #[derive(Debug, Default, Deserialize, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    struct InstalledAssets {
+        #[serde(default)]
+        version: Option<String>,
+        /// 压缩包名 -> 已应用的 sha256
+        #[serde(default)]
+        archives: BTreeMap<String, String>,
+    }
+
+    /// 可用的资源更新
+    #[derive(Clone, Debug, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct ProotUpdateInfo {
+        /// 通过资源清单安装的版本；完整资源包安装时为空
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub installed_version: Option<String>,
+        pub latest_version: String,
+        pub pending: Vec<PendingAssetArchive>,
+        /// 需要下载的总字节数（清单未提供大小的压缩包不计入）
+        pub download_size: u64,
+    }
+
+    #[derive(Clone, Debug, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct PendingAssetArchive {
+        pub name: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub size: Option<u64>,
+    }
+
+    fn load_installed_assets(base_dir: &Path) -> InstalledAssets {
+        fs::read_to_string(base_dir.join(INSTALLED_ASSETS_FILE))
+            .ok()
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default()
+    }
+
+    fn save_installed_assets(base_dir: &Path, installed: &InstalledAssets) -> Result<(), String> {
+        let data = serde_json::to_string_pretty(installed)
+            .map_err(|e| format!("序列化资源版本失败: {e}"))?;
+        fs::write(base_dir.join(INSTALLED_ASSETS_FILE), data)
+            .map_err(|e| format!("保存资源版本失败: {e}"))
+    }
+
+    /// 下载最新的资源清单；每次都重新获取
+    fn fetch_assets_manifest(app: &AppHandle, base_dir: &Path) -> Result<AssetsManifest, String> {
+        let filename = format!("proot-assets-manifest-{}.json", asset_abi());
+        let temp_dir = base_dir.join("temp_download");
+        fs::create_dir_all(&temp_dir).map_err(|e| format!("创建临时目录失败: {e}"))?;
+        let path = temp_dir.join(&filename);
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_file(partial_download_path(&path));
+
+        download_with_mirrors(app, GITHUB_REPO, RELEASE_TAG, &filename, &path)
+            .map_err(|e| format!("下载资源清单失败: {e}"))?;
+        let data = fs::read_to_string(&path).map_err(|e| format!("读取资源清单失败: {e}"))?;
+        let _ = fs::remove_file(&path);
+        let manifest: AssetsManifest =
+            serde_json::from_str(&data).map_err(|e| format!("解析资源清单失败: {e}"))?;
+
+        for archive in &manifest.archives {
+            asset_destination(base_dir, archive)?;
+            if Path::new(&archive.file).file_name() != Some(archive.file.as_ref()) {
+                return Err(format!("资源清单中的文件名无效: {}", archive.file));
+            }
+        }
+        Ok(manifest)
+    }
+
+    fn asset_destination(base_dir: &Path, archive: &AssetArchive) -> Result<PathBuf, String> {
+        let relative = Path::new(&archive.destination);
+        if relative
+            .components()
+            .any(|component| !matches!(component, std::path::Component::Normal(_)))
+        {
+            return Err(format!(
+                "资源清单中 {} 的目标目录无效: {}",
+                archive.name, archive.destination
+            ));
+        }
+        Ok(base_dir.join(relative))
+    }
+
+    /// 需要重新应用的压缩包：sha256 变化、目标目录缺失，或目标目录位于
+    /// 本次会被清空的目录之下
+    fn pending_archives<'a>(
+        base_dir: &Path,
+        manifest: &'a AssetsManifest,
+        installed: &InstalledAssets,
+    ) -> Result<Vec<&'a AssetArchive>, String> {
+        let mut replaced: Vec<PathBuf> = Vec::new();
+        let mut pending = Vec::new();
+        for archive in &manifest.archives {
+            let destination = asset_destination(base_dir, archive)?;
+            let changed = installed.archives.get(&archive.name) != Some(&archive.sha256)
+                || !destination.exists()
+                || replaced.iter().any(|dir| destination.starts_with(dir));
+            if changed {
+                if archive.replace {
+                    replaced.push(destination);
+                }
+                pending.push(archive);
+            }
+        }
+        Ok(pending)
+    }
+
+    /// 下载并应用资源清单中变化的压缩包
+    fn apply_assets_manifest(
+        app: &AppHandle,
+        base_dir: &Path,
+        manifest: &AssetsManifest,
+    ) -> Result<(), String> {
+        let mut installed = load_installed_assets(base_dir);
+        let pending = pending_archives(base_dir, manifest, &installed)?;
+        let rootfs_root = base_dir.join("rootfs");
+        let temp_dir = base_dir.join("temp_download");
+        fs::create_dir_all(&temp_dir).map_err(|e| format!("创建临时目录失败: {e}"))?;
+
+        for archive in pending {
+            let destination = asset_destination(base_dir, archive)?;
+            let archive_path = temp_dir.join(&archive.file);
+            if !archive_path.exists() {
+                eprintln!("正在下载资源 {} ({})...", archive.name, archive.file);
+                download_with_mirrors(app, GITHUB_REPO, RELEASE_TAG, &archive.file, &archive_path)
+                    .map_err(|e| format!("下载 {} 失败: {e}", archive.file))?;
+            }
+            let verified = verify_sha256(&archive_path, &archive.sha256.to_lowercase())
+                .map_err(|e| format!("SHA256 校验失败: {e}"))?;
+            if !verified {
+                let _ = fs::remove_file(&archive_path);
+                return Err(format!("{} 校验失败，SHA256 不匹配！", archive.file));
+            }
+
+            // 应用前先移除记录，中断后下次会重新应用
+            installed.archives.remove(&archive.name);
+            save_installed_assets(base_dir, &installed)?;
+
+            if archive.replace {
+                if destination == base_dir {
+                    return Err(format!("资源 {} 不能清空 proot 根目录", archive.name));
+                }
+                // 清空 rootfs 前移出 /root 中的项目
+                if rootfs_root.starts_with(&destination) || destination.starts_with(&rootfs_root) {
+                    preserve_home(app, base_dir)?;
+                    // 旧的完整 rootfs 压缩包已过期，不能再用于重置
+                    let _ = fs::remove_file(base_dir.join("rootfs.tar.xz"));
+                }
+                if destination.exists() {
+                    fs::remove_dir_all(&destination)
+                        .map_err(|e| format!("清理 {} 失败: {e}", archive.destination))?;
+                }
+            }
+            fs::create_dir_all(&destination).map_err(|e| format!("创建目录失败: {e}"))?;
+
+            eprintln!("正在应用资源 {}...", archive.name);
+            if archive.file.ends_with(".zip") {
+                extract_zip(app, &archive_path, &destination, &archive.file)?;
+            } else {
+                decompress_tar_xz(app, &archive_path, &destination)
+                    .map_err(|e| format!("解压 {} 失败: {e:?}", archive.file))?;
+            }
+            let _ = fs::remove_file(&archive_path);
+
+            installed
+                .archives
+                .insert(archive.name.clone(), archive.sha256.clone());
+            save_installed_assets(base_dir, &installed)?;
+        }
+
+        mark_proot_executable(base_dir);
+        restore_home(app, &rootfs_arch_dir(&rootfs_root))?;
+        installed.version = Some(manifest.version.clone());
+        save_installed_assets(base_dir, &installed)?;
+        let _ = fs::remove_dir_all(&temp_dir);
+        Ok(())
+    }
+
+    /// 对比最新资源清单与已安装版本，不下载压缩包
+    pub async fn check_proot_updates(app: AppHandle) -> Result<ProotUpdateInfo, String> {
+        tauri::async_runtime::spawn_blocking(move || {
+            let base_dir = proot_base_dir(&app)?;
+            let manifest = fetch_assets_manifest(&app, &base_dir)?;
+            let installed = load_installed_assets(&base_dir);
+            let pending: Vec<PendingAssetArchive> =
+                pending_archives(&base_dir, &manifest, &installed)?
+                    .into_iter()
+                    .map(|archive| PendingAssetArchive {
+                        name: archive.name.clone(),
+                        size: archive.size,
+                    })
+                    .collect();
+            Ok(ProotUpdateInfo {
+                installed_version: installed.version,
+                latest_version: manifest.version,
+                download_size: pending.iter().filter_map(|archive| archive.size).sum(),
+                pending,
+            })
+        })
+        .await
+        .map_err(|e| format!("后台任务执行失败: {e}"))?
+    }
+
+    /// 下载并应用变化的资源压缩包，保留 /root 中的项目
+    pub async fn apply_proot_updates(app: AppHandle) -> Result<(), String> {
+        tauri::async_runtime::spawn_blocking(move || {
+            let base_dir = proot_base_dir(&app)?;
+            let control = download_control();
+            control.begin()?;
+            let result = fetch_assets_manifest(&app, &base_dir)
+                .and_then(|manifest| apply_assets_manifest(&app, &base_dir, &manifest));
+            let cancelled = control.state() == DownloadState::Cancelled;
+            control.set(DownloadState::Idle);
+
+            match result {
+                Ok(()) => {
+                    let _ = app.emit("proot-download-progress", DownloadProgress::Completed);
+                    Ok(())
+                }
+                Err(_) if cancelled => {
+                    // 已应用的压缩包保持不变，下次更新时继续
+                    let _ = app.emit("proot-download-progress", DownloadProgress::Cancelled);
+                    Err("下载已取消".to_string())
+                }
+                Err(e) => {
+                    let _ = app.emit(
+                        "proot-download-progress",
+                        DownloadProgress::Error { message: e.clone() },
+                    );
+                    Err(e)
+                }
+            }
+        })
+        .await
+        .map_err(|e| format!("后台任务执行失败: {e}"))?
+    }
+
+    /// 解压 zip 资源包到 `dest`，逐个文件报告进度
+    fn extract_zip(
+        app: &AppHandle,
+        archive_path: &Path,
+        dest: &Path,
+        label: &str,
+    ) -> Result<(), String> {
+        let file = File::open(archive_path).map_err(|e| format!("打开资源包失败: {e}"))?;
         let mut archive = zip::ZipArchive::new(file).map_err(|e| format!("读取资源包失败: {e}"))?;
 
         // 逐个文件解压，显示进度
         let total_files = archive.len();
         for i in 0..total_files {
+            download_control().checkpoint().map_err(|e| e.to_string())?;
             let mut file = archive
                 .by_index(i)
                 .map_err(|e| format!("读取压缩包条目失败: {e}"))?;
@@ -278,6 +804,11 @@ pub mod proot {
                 let mut outfile =
                     File::create(&outpath).map_err(|e| format!("创建文件失败: {e}"))?;
                 io::copy(&mut file, &mut outfile).map_err(|e| format!("解压文件失败: {e}"))?;
+                #[cfg(unix)]
+                if let Some(mode) = file.unix_mode() {
+                    let _ =
+                        fs::set_permissions(&outpath, fs::Permissions::from_mode(mode & 0o7777));
+                }
             }
 
             // 每处理一个文件就发送进度
@@ -285,19 +816,16 @@ pub mod proot {
             let _ = app.emit(
                 "proot-download-progress",
                 DownloadProgress::Extracting {
-                    file: format!("{} ({}/{})", assets_filename, i + 1, total_files),
+                    file: format!("{} ({}/{})", label, i + 1, total_files),
                     percentage: Some(percentage),
                 },
             );
         }
+        Ok(())
+    }
 
-        // 删除压缩包以节省空间
-        let _ = fs::remove_file(&assets_zip_path);
-
-        // 清理临时目录
-        let _ = fs::remove_dir_all(&temp_dir);
-
-        // 设置 proot 二进制文件的可执行权限
+    /// 设置 proot 二进制文件的可执行权限
+    fn mark_proot_executable(dest: &Path) {
         #[cfg(unix)]
         {
             let proot_path = dest.join("proot/bin/proot");
@@ -319,8 +847,6 @@ pub mod proot {
                 }
             }
         }
-
-        Ok(())
     }
 
     fn decompress_tar_xz(app: &AppHandle, src: &Path, dest: &Path) -> io::Result<()> {
@@ -344,6 +870,7 @@ pub mod proot {
         let mut last_report_time = std::time::Instant::now();
 
         for entry in archive.entries()? {
+            download_control().checkpoint()?;
             let mut file = entry?;
 
             if file.header().entry_type() == tar::EntryType::Directory {
@@ -375,13 +902,39 @@ pub mod proot {
         Ok(())
     }
 
+    /// proot 资源及 rootfs 所在目录，可通过 `migrate_environment_storage` 迁移
+    pub fn proot_base_dir(app: &AppHandle) -> Result<PathBuf, String> {
+        let storage = load_environment_storage(app).unwrap_or_else(|err| {
+            eprintln!("[truidide::proot] 读取存储位置失败，使用内部存储: {err}");
+            EnvironmentStorage::default()
+        });
+        match storage.location {
+            Some(location) => Ok(Path::new(&location).join("proot")),
+            None => default_proot_base_dir(app),
+        }
+    }
+
+    fn default_proot_base_dir(app: &AppHandle) -> Result<PathBuf, String> {
+        app.path()
+            .resolve("files/proot", BaseDirectory::AppData)
+            .map_err(|e| e.to_string())
+    }
+
+    /// 已解压的 rootfs 架构，取自 `rootfs/archlinux-{arch}` 目录名；
+    /// 其他命名的镜像返回完整目录名
+    pub fn extracted_rootfs_arch(base_dir: &Path) -> Option<String> {
+        let rootfs_dir = rootfs_arch_dir(&base_dir.join("rootfs"));
+        if !rootfs_dir.is_dir() {
+            return None;
+        }
+        let name = rootfs_dir.file_name()?.to_str()?;
+        Some(name.strip_prefix("archlinux-").unwrap_or(name).to_string())
+    }
+
     /// 检查 proot 资源状态（不下载）
     pub async fn check_proot_status(app: AppHandle) -> Result<bool, String> {
         tauri::async_runtime::spawn_blocking(move || {
-            let appdata_base = app
-                .path()
-                .resolve("files/proot", BaseDirectory::AppData)
-                .map_err(|e| e.to_string())?;
+            let appdata_base = proot_base_dir(&app)?;
 
             // 检查是否已下载
             if !appdata_base.exists()
@@ -407,10 +960,7 @@ pub mod proot {
     /// 下载并准备 proot 环境（用户手动触发）
     pub async fn download_and_prepare_proot(app: AppHandle) -> Result<(), String> {
         tauri::async_runtime::spawn_blocking(move || {
-            let appdata_base = app
-                .path()
-                .resolve("files/proot", BaseDirectory::AppData)
-                .map_err(|e| e.to_string())?;
+            let appdata_base = proot_base_dir(&app)?;
 
             // 如果已经存在，跳过下载
             if appdata_base.exists()
@@ -419,101 +969,1349 @@ pub mod proot {
                     .unwrap_or(false)
             {
                 let rootfs_root = appdata_base.join("rootfs");
-                if rootfs_root.exists() {
+                // 导入的快照只有 rootfs，仍需下载 proot 本身
+                if rootfs_root.exists() && appdata_base.join("proot/bin/proot").exists() {
                     // 已经存在且准备就绪，直接返回，不发送事件
                     return Ok(());
                 }
             }
 
-            fs::create_dir_all(&appdata_base).map_err(|e| format!("创建 proot 目录失败: {e}"))?;
+            let control = download_control();
+            control.begin()?;
 
-            eprintln!("正在从 GitHub 下载 proot 和 rootfs...");
+            let result = prepare_proot_assets(&app, &appdata_base);
+            let cancelled = control.state() == DownloadState::Cancelled;
+            control.set(DownloadState::Idle);
 
-            // 下载资源
-            if let Err(e) = download_and_extract_assets(&app, &appdata_base) {
-                let _ = app.emit(
-                    "proot-download-progress",
-                    DownloadProgress::Error { message: e.clone() },
-                );
-                return Err(e);
-            }
-
-            // 检查是否需要解压 rootfs
-            let rootfs_root = appdata_base.join("rootfs");
-            if !rootfs_root.exists() {
-                let compressed = appdata_base.join("rootfs.tar.xz");
-                if compressed.exists() {
-                    eprintln!("正在解压 rootfs (首次运行可能需要几分钟)...");
-
-                    decompress_tar_xz(&app, &compressed, &rootfs_root)
-                        .map_err(|e| format!("解压 rootfs 失败: {e:?}"))?;
-
-                    // 解压成功后可以删除压缩包以节省空间
-                    let _ = fs::remove_file(&compressed);
+            match result {
+                Ok(()) => {
+                    // 发送完成事件
+                    let _ = app.emit("proot-download-progress", DownloadProgress::Completed);
+                    Ok(())
+                }
+                Err(_) if cancelled => {
+                    // 只删除本次下载的临时文件（包括 .part），已有的 rootfs 和
+                    // /root 保持不变；解压中断的 rootfs 由 extract_rootfs 清理
+                    let _ = fs::remove_dir_all(appdata_base.join("temp_download"));
+                    let _ = app.emit("proot-download-progress", DownloadProgress::Cancelled);
+                    Err("下载已取消".to_string())
+                }
+                Err(e) => {
+                    let _ = app.emit(
+                        "proot-download-progress",
+                        DownloadProgress::Error { message: e.clone() },
+                    );
+                    Err(e)
                 }
             }
-
-            // 发送完成事件
-            let _ = app.emit("proot-download-progress", DownloadProgress::Completed);
-
-            Ok(())
         })
         .await
         .map_err(|e| format!("后台任务执行失败: {e}"))?
     }
 
-    pub fn prepare_proot_env(app: &AppHandle) -> Result<ProotEnv, String> {
-        let appdata_base = app
-            .path()
-            .resolve("files/proot", BaseDirectory::AppData)
-            .map_err(|e| e.to_string())?;
+    fn prepare_proot_assets(app: &AppHandle, appdata_base: &Path) -> Result<(), String> {
+        fs::create_dir_all(appdata_base).map_err(|e| format!("创建 proot 目录失败: {e}"))?;
 
-        // 如果资源还没下载，直接返回错误（不在这里下载）
-        if !appdata_base.exists()
-            || fs::read_dir(&appdata_base)
-                .map(|mut d| d.next().is_none())
-                .unwrap_or(true)
-        {
-            return Err("Proot 资源尚未准备就绪，请等待下载完成".to_string());
+        eprintln!("正在从 GitHub 下载 proot 和 rootfs...");
+
+        // 发布了资源清单时只下载变化的部分，否则下载完整资源包
+        match fetch_assets_manifest(app, appdata_base) {
+            Ok(manifest) => return apply_assets_manifest(app, appdata_base, &manifest),
+            Err(e) => eprintln!("未获取到资源清单，改为下载完整资源包: {e}"),
         }
 
-        let dest = appdata_base;
-        let rootfs_root = dest.join("rootfs");
+        // 下载资源
+        download_and_extract_assets(app, appdata_base)?;
 
-        // 如果 rootfs 还未解压，也返回错误
+        // 检查是否需要解压 rootfs
+        let rootfs_root = appdata_base.join("rootfs");
         if !rootfs_root.exists() {
-            return Err("Rootfs 尚未解压完成，请等待初始化完成".to_string());
+            let compressed = appdata_base.join("rootfs.tar.xz");
+            if compressed.exists() {
+                eprintln!("正在解压 rootfs (首次运行可能需要几分钟)...");
+
+                extract_rootfs(app, &compressed, &rootfs_root)?;
+            }
         }
 
-        // 权限设置已在下载时完成，无需再次检查
-        let proot_path = dest.join("proot/bin/proot");
-        if !proot_path.exists() {
-            return Err(format!(
-                "必需的文件未找到: {}，请确保资源已正确下载",
-                proot_path.to_string_lossy()
-            ));
+        Ok(())
+    }
+
+    /// 解压 rootfs 并放回保留的主目录
+    ///
+    /// 压缩包保留在磁盘上，重置 rootfs 时无需重新下载。
+    fn extract_rootfs(app: &AppHandle, archive: &Path, rootfs_root: &Path) -> Result<(), String> {
+        if let Err(e) = decompress_tar_xz(app, archive, rootfs_root) {
+            // 不留下不完整的 rootfs
+            let _ = fs::remove_dir_all(rootfs_root);
+            return Err(format!("解压 rootfs 失败: {e:?}"));
         }
+        restore_home(app, &rootfs_arch_dir(rootfs_root))
+    }
 
-        let mut rootfs_dir = rootfs_root.clone();
-        #[cfg(target_arch = "aarch64")]
-        {
-            rootfs_dir = rootfs_root.join("archlinux-aarch64");
+    /// 容器内的 /root 存放用户项目，重建 rootfs 前移出，解压后放回
+    fn preserved_home_dir(app: &AppHandle) -> Result<PathBuf, String> {
+        Ok(proot_base_dir(app)?.with_file_name(PRESERVED_HOME_DIR))
+    }
+
+    fn preserve_home(app: &AppHandle, base_dir: &Path) -> Result<(), String> {
+        let home = rootfs_arch_dir(&base_dir.join("rootfs")).join("root");
+        if !home.exists() {
+            return Ok(());
         }
-        #[cfg(target_arch = "arm")]
-        {
-            rootfs_dir = rootfs_root.join("archlinux-armv7l");
+        let preserved = preserved_home_dir(app)?;
+        if preserved.exists() {
+            return Err(format!(
+                "已存在尚未恢复的主目录备份: {}",
+                preserved.to_string_lossy()
+            ));
         }
-        #[cfg(target_arch = "x86_64")]
-        {
-            rootfs_dir = rootfs_root.join("archlinux-x86_64");
+        if let Some(parent) = preserved.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("创建目录失败: {e}"))?;
         }
-        #[cfg(target_arch = "x86")]
-        {
-            rootfs_dir = rootfs_root.join("archlinux-x86");
+        fs::rename(&home, &preserved).map_err(|e| format!("备份主目录失败: {e}"))
+    }
+
+    fn restore_home(app: &AppHandle, rootfs_dir: &Path) -> Result<(), String> {
+        let preserved = preserved_home_dir(app)?;
+        if !preserved.exists() || !rootfs_dir.exists() {
+            return Ok(());
         }
+        let home = rootfs_dir.join("root");
+        if home.exists() {
+            fs::remove_dir_all(&home).map_err(|e| format!("清理主目录失败: {e}"))?;
+        }
+        fs::rename(&preserved, &home).map_err(|e| format!("恢复主目录失败: {e}"))
+    }
 
-        if !rootfs_dir.exists() {
-            return Err(format!(
+    /// 删除 proot 资源和 rootfs，保留 /root 中的项目，之后需重新下载
+    pub async fn delete_rootfs(app: AppHandle) -> Result<(), String> {
+        tauri::async_runtime::spawn_blocking(move || {
+            let base_dir = proot_base_dir(&app)?;
+            let control = download_control();
+            control.begin()?;
+            let result = preserve_home(&app, &base_dir).and_then(|_| {
+                if base_dir.exists() {
+                    fs::remove_dir_all(&base_dir).map_err(|e| format!("删除 rootfs 失败: {e}"))?;
+                }
+                Ok(())
+            });
+            control.set(DownloadState::Idle);
+            result
+        })
+        .await
+        .map_err(|e| format!("后台任务执行失败: {e}"))?
+    }
+
+    /// 重建 rootfs：有保留的压缩包时重新解压，否则重新下载
+    pub async fn reset_rootfs(app: AppHandle) -> Result<(), String> {
+        let task_app = app.clone();
+        let needs_download = tauri::async_runtime::spawn_blocking(move || {
+            let app = task_app;
+            let base_dir = proot_base_dir(&app)?;
+            let control = download_control();
+            control.begin()?;
+            let result = reextract_rootfs(&app, &base_dir);
+            control.set(DownloadState::Idle);
+            if let Err(e) = &result {
+                let _ = app.emit(
+                    "proot-download-progress",
+                    DownloadProgress::Error { message: e.clone() },
+                );
+            }
+            result
+        })
+        .await
+        .map_err(|e| format!("后台任务执行失败: {e}"))??;
+
+        if needs_download {
+            return download_and_prepare_proot(app).await;
+        }
+        Ok(())
+    }
+
+    // 返回 `true` 表示没有可用的压缩包，需要重新下载
+    fn reextract_rootfs(app: &AppHandle, base_dir: &Path) -> Result<bool, String> {
+        preserve_home(app, base_dir)?;
+        let rootfs_root = base_dir.join("rootfs");
+        if rootfs_root.exists() {
+            fs::remove_dir_all(&rootfs_root).map_err(|e| format!("删除 rootfs 失败: {e}"))?;
+        }
+
+        let archive = base_dir.join("rootfs.tar.xz");
+        if !archive.exists() {
+            return Ok(true);
+        }
+        eprintln!("正在从保留的压缩包重新解压 rootfs...");
+        extract_rootfs(app, &archive, &rootfs_root)?;
+        let _ = app.emit("proot-download-progress", DownloadProgress::Completed);
+        Ok(false)
+    }
+
+    /// 运行环境的校验结果及修复计划
+    #[derive(Clone, Debug, Default, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct EnvironmentReport {
+        pub healthy: bool,
+        /// 发现的问题，用于展示
+        pub issues: Vec<String>,
+        /// 按执行顺序排列的修复步骤
+        pub repairs: Vec<RepairAction>,
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+    #[serde(tag = "action", rename_all = "camelCase")]
+    pub enum RepairAction {
+        /// 补上 proot 及 loader 的可执行权限
+        FixPermissions,
+        /// 重新获取 proot 本身，不动 rootfs
+        ReinstallProot,
+        /// 放回上次重建 rootfs 时暂存、尚未恢复的主目录
+        RestoreHome,
+        /// 从保留的 rootfs.tar.xz 中只解压缺失或损坏的文件
+        RestoreRootfsFiles { paths: Vec<String> },
+        /// 整体重建 rootfs：架构目录缺失，或没有可用的压缩包
+        RebuildRootfs,
+    }
+
+    /// 检查 proot 二进制、loader 权限、架构目录和 rootfs 关键文件
+    pub async fn verify_environment(app: AppHandle) -> Result<EnvironmentReport, String> {
+        tauri::async_runtime::spawn_blocking(move || {
+            let base_dir = proot_base_dir(&app)?;
+            inspect_environment(&app, &base_dir)
+        })
+        .await
+        .map_err(|e| format!("后台任务执行失败: {e}"))?
+    }
+
+    fn inspect_environment(app: &AppHandle, base_dir: &Path) -> Result<EnvironmentReport, String> {
+        let mut issues = Vec::new();
+        let mut repairs = Vec::new();
+
+        let mut proot_broken = false;
+        let mut needs_chmod = false;
+        for relative in [
+            "proot/bin/proot",
+            "proot/libexec/proot/loader",
+            "proot/libexec/proot/loader32",
+        ] {
+            match fs::metadata(base_dir.join(relative)) {
+                Ok(metadata) if metadata.len() == 0 => {
+                    issues.push(format!("{relative} 已损坏"));
+                    proot_broken = true;
+                }
+                Ok(metadata) if metadata.permissions().mode() & 0o100 == 0 => {
+                    issues.push(format!("{relative} 缺少可执行权限"));
+                    needs_chmod = true;
+                }
+                Ok(_) => {}
+                // 32 位 loader 只在部分架构上提供
+                Err(_) if relative.ends_with("loader32") => {}
+                Err(_) => {
+                    issues.push(format!("缺少 {relative}"));
+                    proot_broken = true;
+                }
+            }
+        }
+
+        let rootfs_dir = rootfs_arch_dir(&base_dir.join("rootfs"));
+        let has_archive = base_dir.join("rootfs.tar.xz").exists();
+        let mut rootfs_repair = None;
+        if !rootfs_dir.is_dir() {
+            issues.push(format!(
+                "缺少 rootfs 目录 {}",
+                rootfs_dir.file_name().unwrap_or_default().to_string_lossy()
+            ));
+            rootfs_repair = Some(RepairAction::RebuildRootfs);
+        } else {
+            // 符号链接按自身判断，rootfs 中的链接多为绝对路径
+            let broken: Vec<String> = CRITICAL_ROOTFS_FILES
+                .iter()
+                .filter(
+                    |relative| match fs::symlink_metadata(rootfs_dir.join(relative)) {
+                        Ok(metadata) => metadata.is_file() && metadata.len() == 0,
+                        Err(_) => true,
+                    },
+                )
+                .map(|relative| relative.to_string())
+                .collect();
+            for relative in &broken {
+                issues.push(format!("rootfs 中的 /{relative} 缺失或已损坏"));
+            }
+            if !broken.is_empty() {
+                rootfs_repair = Some(if has_archive {
+                    RepairAction::RestoreRootfsFiles { paths: broken }
+                } else {
+                    RepairAction::RebuildRootfs
+                });
+            }
+        }
+
+        // 重新下载会一并取回 proot
+        let redownload = rootfs_repair == Some(RepairAction::RebuildRootfs) && !has_archive;
+        if proot_broken && !redownload {
+            repairs.push(RepairAction::ReinstallProot);
+        } else if needs_chmod && !redownload {
+            repairs.push(RepairAction::FixPermissions);
+        }
+        // 架构目录缺失时由重建流程放回主目录
+        if preserved_home_dir(app)?.exists() && rootfs_dir.is_dir() {
+            issues.push("存在尚未恢复的主目录备份".to_string());
+            repairs.push(RepairAction::RestoreHome);
+        }
+        repairs.extend(rootfs_repair);
+
+        Ok(EnvironmentReport {
+            healthy: issues.is_empty(),
+            issues,
+            repairs,
+        })
+    }
+
+    /// 按校验结果只修复损坏的部分，返回修复后的校验结果
+    pub async fn repair_environment(app: AppHandle) -> Result<EnvironmentReport, String> {
+        let task_app = app.clone();
+        let needs_download = tauri::async_runtime::spawn_blocking(move || {
+            let app = task_app;
+            let base_dir = proot_base_dir(&app)?;
+            let control = download_control();
+            control.begin()?;
+            let result = apply_repairs(&app, &base_dir);
+            control.set(DownloadState::Idle);
+            if let Err(e) = &result {
+                let _ = app.emit(
+                    "proot-download-progress",
+                    DownloadProgress::Error { message: e.clone() },
+                );
+            }
+            result
+        })
+        .await
+        .map_err(|e| format!("后台任务执行失败: {e}"))??;
+
+        if needs_download {
+            download_and_prepare_proot(app.clone()).await?;
+        }
+        verify_environment(app).await
+    }
+
+    // 返回 `true` 表示 rootfs 需要重新下载
+    fn apply_repairs(app: &AppHandle, base_dir: &Path) -> Result<bool, String> {
+        let report = inspect_environment(app, base_dir)?;
+        if report.repairs.is_empty() {
+            return Ok(false);
+        }
+        for repair in &report.repairs {
+            eprintln!("[truidide::proot] 修复运行环境: {repair:?}");
+            match repair {
+                RepairAction::FixPermissions => mark_proot_executable(base_dir),
+                RepairAction::ReinstallProot => reinstall_proot(app, base_dir)?,
+                RepairAction::RestoreHome => {
+                    restore_home(app, &rootfs_arch_dir(&base_dir.join("rootfs")))?
+                }
+                RepairAction::RestoreRootfsFiles { paths } => {
+                    restore_rootfs_files(app, base_dir, paths)?
+                }
+                // 总是最后一步，reextract_rootfs 自行发送完成事件
+                RepairAction::RebuildRootfs => return reextract_rootfs(app, base_dir),
+            }
+        }
+        let _ = app.emit("proot-download-progress", DownloadProgress::Completed);
+        Ok(false)
+    }
+
+    fn reinstall_proot(app: &AppHandle, base_dir: &Path) -> Result<(), String> {
+        let proot_dir = base_dir.join("proot");
+        if proot_dir.exists() {
+            fs::remove_dir_all(&proot_dir).map_err(|e| format!("删除 proot 失败: {e}"))?;
+        }
+        match fetch_assets_manifest(app, base_dir) {
+            Ok(manifest) => {
+                // 强制重新应用包含 proot 的压缩包，其余保持不变
+                let mut installed = load_installed_assets(base_dir);
+                for archive in &manifest.archives {
+                    if proot_dir.starts_with(asset_destination(base_dir, archive)?) {
+                        installed.archives.remove(&archive.name);
+                    }
+                }
+                save_installed_assets(base_dir, &installed)?;
+                apply_assets_manifest(app, base_dir, &manifest)
+            }
+            Err(e) => {
+                eprintln!("未获取到资源清单，改为下载完整资源包: {e}");
+                download_and_extract_assets(app, base_dir)
+            }
+        }
+    }
+
+    /// 从 rootfs.tar.xz 中只解压 `paths`（相对于架构根目录）
+    fn restore_rootfs_files(
+        app: &AppHandle,
+        base_dir: &Path,
+        paths: &[String],
+    ) -> Result<(), String> {
+        let rootfs_root = base_dir.join("rootfs");
+        let arch_dir = rootfs_arch_dir(&rootfs_root);
+        let prefix = arch_dir.strip_prefix(&rootfs_root).unwrap_or(&arch_dir);
+        let mut wanted: HashSet<PathBuf> = paths.iter().map(|path| prefix.join(path)).collect();
+
+        let _ = app.emit(
+            "proot-download-progress",
+            DownloadProgress::Extracting {
+                file: format!("rootfs.tar.xz ({} 个文件)", wanted.len()),
+                percentage: None,
+            },
+        );
+        let file = File::open(base_dir.join("rootfs.tar.xz"))
+            .map_err(|e| format!("打开 rootfs 压缩包失败: {e}"))?;
+        let mut archive = tar::Archive::new(XzDecoder::new(BufReader::new(file)));
+        let entries = archive
+            .entries()
+            .map_err(|e| format!("读取 rootfs 压缩包失败: {e}"))?;
+        for entry in entries {
+            if wanted.is_empty() {
+                break;
+            }
+            download_control().checkpoint().map_err(|e| e.to_string())?;
+            let mut entry = entry.map_err(|e| format!("读取 rootfs 压缩包失败: {e}"))?;
+            let path: PathBuf = entry
+                .path()
+                .map_err(|e| format!("读取 rootfs 压缩包失败: {e}"))?
+                .components()
+                .filter(|component| !matches!(component, std::path::Component::CurDir))
+                .collect();
+            if !wanted.remove(&path) {
+                continue;
+            }
+            // 损坏的文件可能是只读的，先删除再解压
+            let target = rootfs_root.join(&path);
+            if fs::symlink_metadata(&target).is_ok_and(|metadata| !metadata.is_dir()) {
+                let _ = fs::remove_file(&target);
+            }
+            entry
+                .unpack_in(&rootfs_root)
+                .map_err(|e| format!("解压 {} 失败: {e}", path.to_string_lossy()))?;
+        }
+
+        if !wanted.is_empty() {
+            return Err("rootfs 压缩包中缺少部分文件，请重置 rootfs".to_string());
+        }
+        Ok(())
+    }
+
+    /// 环境诊断中的一项发现
+    #[derive(Clone, Debug, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct DiagnosticFinding {
+        /// 稳定的标识，例如 `seccomp`、`loader-missing`
+        pub id: String,
+        pub severity: DiagnosticSeverity,
+        pub message: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub suggestion: Option<String>,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+    #[serde(rename_all = "lowercase")]
+    pub enum DiagnosticSeverity {
+        Error,
+        Warning,
+        Info,
+    }
+
+    #[derive(Clone, Debug, Default, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct DiagnosticsReport {
+        /// 测试命令在 proot 中成功执行
+        pub passed: bool,
+        pub findings: Vec<DiagnosticFinding>,
+        /// 测试命令的输出，便于附在问题反馈中
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub output: Option<String>,
+    }
+
+    const DIAGNOSTIC_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(15);
+    const DIAGNOSTIC_MARKER: &str = "truidide-proot-ok";
+
+    fn finding(
+        id: &str,
+        severity: DiagnosticSeverity,
+        message: impl Into<String>,
+        suggestion: Option<&str>,
+    ) -> DiagnosticFinding {
+        DiagnosticFinding {
+            id: id.to_string(),
+            severity,
+            message: message.into(),
+            suggestion: suggestion.map(str::to_string),
+        }
+    }
+
+    /// 在 proot 中执行一条简单命令，并对常见的失败原因给出说明和建议
+    pub async fn run_environment_diagnostics(app: AppHandle) -> Result<DiagnosticsReport, String> {
+        tauri::async_runtime::spawn_blocking(move || Ok(diagnose_environment(&app)))
+            .await
+            .map_err(|e| format!("后台任务执行失败: {e}"))?
+    }
+
+    fn diagnose_environment(app: &AppHandle) -> DiagnosticsReport {
+        let mut report = DiagnosticsReport::default();
+        check_device_abi(&mut report.findings);
+
+        let env = match prepare_proot_env(app) {
+            Ok(env) => env,
+            Err(e) => {
+                report.findings.push(finding(
+                    "environment-missing",
+                    DiagnosticSeverity::Error,
+                    e,
+                    Some("下载运行环境，或运行校验与修复"),
+                ));
+                return report;
+            }
+        };
+
+        let loader = env.base_dir.join("proot/libexec/proot/loader");
+        match fs::metadata(&loader) {
+            Ok(metadata) if metadata.permissions().mode() & 0o100 == 0 => {
+                report.findings.push(finding(
+                    "loader-not-executable",
+                    DiagnosticSeverity::Error,
+                    "proot loader 缺少可执行权限",
+                    Some("运行校验与修复以恢复权限"),
+                ));
+            }
+            Ok(_) => {}
+            Err(_) => report.findings.push(finding(
+                "loader-missing",
+                DiagnosticSeverity::Error,
+                "缺少 proot loader，proot 无法启动容器内的程序",
+                Some("运行校验与修复以重新获取 proot"),
+            )),
+        }
+
+        let probe = match run_probe(&env, false) {
+            Ok(probe) => probe,
+            Err(e) => {
+                report
+                    .findings
+                    .push(classify_spawn_error(&e, &env.proot_bin));
+                return report;
+            }
+        };
+        report.output = Some(probe.output.clone()).filter(|output| !output.is_empty());
+        if probe.passed {
+            report.passed = true;
+            return report;
+        }
+
+        let mut classified = classify_probe_failure(&probe);
+        // 内核拒绝 seccomp 过滤器时，关闭 proot 的 seccomp 加速通常可以绕过
+        if classified.id == "seccomp" && !env.settings.no_seccomp {
+            if let Ok(retry) = run_probe(&env, true) {
+                if retry.passed {
+                    classified.suggestion = Some(
+                        "关闭 seccomp 加速后测试通过，在 proot 设置中启用\"禁用 seccomp\""
+                            .to_string(),
+                    );
+                }
+            }
+        }
+        report.findings.push(classified);
+        report
+    }
+
+    // 64 位设备上安装了 32 位版本，或设备只支持 32 位
+    fn check_device_abi(findings: &mut Vec<DiagnosticFinding>) {
+        let abilist64 = std::process::Command::new("/system/bin/getprop")
+            .arg("ro.product.cpu.abilist64")
+            .output()
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+            .unwrap_or_default();
+        let app_is_32_bit = matches!(get_arch_suffix(), "armv7" | "x86");
+        if abilist64.is_empty() {
+            findings.push(finding(
+                "32-bit-device",
+                DiagnosticSeverity::Warning,
+                "设备只支持 32 位程序，rootfs 中的部分软件包可能没有 32 位版本",
+                None,
+            ));
+        } else if app_is_32_bit {
+            findings.push(finding(
+                "32-bit-build",
+                DiagnosticSeverity::Warning,
+                format!(
+                    "设备支持 64 位 ({abilist64})，但安装的是 {} 版本",
+                    asset_abi()
+                ),
+                Some("安装与设备匹配的 64 位版本以获得完整的 rootfs"),
+            ));
+        }
+    }
+
+    struct ProbeResult {
+        passed: bool,
+        exit_code: Option<i32>,
+        signal: Option<i32>,
+        timed_out: bool,
+        output: String,
+    }
+
+    fn run_probe(env: &ProotEnv, no_seccomp: bool) -> io::Result<ProbeResult> {
+        use std::os::unix::process::ExitStatusExt;
+        use std::process::Stdio;
+
+        let mut command = proot_command(env, "/");
+        command
+            .args(["/bin/sh", "-c", &format!("echo {DIAGNOSTIC_MARKER}")])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        if no_seccomp {
+            command.env("PROOT_NO_SECCOMP", "1");
+        }
+        let child = command.spawn()?;
+        let pid = child.id();
+
+        // 卡住的 proot 不能拖住诊断，超时后强制结束
+        let (tx, rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = tx.send(child.wait_with_output());
+        });
+        let (output, timed_out) = match rx.recv_timeout(DIAGNOSTIC_TIMEOUT) {
+            Ok(output) => (output?, false),
+            Err(_) => {
+                unsafe {
+                    libc::kill(pid as libc::pid_t, libc::SIGKILL);
+                }
+                let output = rx
+                    .recv()
+                    .map_err(|e| io::Error::new(io::ErrorKind::Other, e))??;
+                (output, true)
+            }
+        };
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Ok(ProbeResult {
+            passed: !timed_out && output.status.success() && stdout.contains(DIAGNOSTIC_MARKER),
+            exit_code: output.status.code(),
+            signal: output.status.signal(),
+            timed_out,
+            output: format!("{}{}", stdout, stderr).trim().to_string(),
+        })
+    }
+
+    fn classify_probe_failure(probe: &ProbeResult) -> DiagnosticFinding {
+        let output = probe.output.to_lowercase();
+        if probe.timed_out {
+            return finding(
+                "timeout",
+                DiagnosticSeverity::Error,
+                format!(
+                    "proot 在 {} 秒内没有完成测试命令",
+                    DIAGNOSTIC_TIMEOUT.as_secs()
+                ),
+                Some("关闭省电或后台限制后重试；如仍无响应，请附上诊断输出反馈"),
+            );
+        }
+        if output.contains("seccomp") || probe.signal == Some(libc::SIGSYS) {
+            return finding(
+                "seccomp",
+                DiagnosticSeverity::Error,
+                "系统的 seccomp 策略拦截了 proot 使用的系统调用",
+                Some("在 proot 设置中启用\"禁用 seccomp\"后重试"),
+            );
+        }
+        if output.contains("loader") {
+            return finding(
+                "loader-failed",
+                DiagnosticSeverity::Error,
+                "proot 无法执行 loader",
+                Some("运行校验与修复以重新获取 proot"),
+            );
+        }
+        if output.contains("ptrace") {
+            return finding(
+                "ptrace-denied",
+                DiagnosticSeverity::Error,
+                "系统不允许 proot 跟踪子进程 (ptrace)",
+                Some("部分厂商系统或安全软件会禁用 ptrace，请关闭相关限制"),
+            );
+        }
+        if output.contains("permission denied") {
+            return finding(
+                "selinux-exec",
+                DiagnosticSeverity::Error,
+                "系统禁止执行应用数据目录中的程序 (SELinux)",
+                Some("如果运行环境位于外部存储，请迁回内部存储"),
+            );
+        }
+        if output.contains("no such file") {
+            return finding(
+                "rootfs-incomplete",
+                DiagnosticSeverity::Error,
+                "rootfs 中缺少测试所需的 /bin/sh",
+                Some("运行校验与修复"),
+            );
+        }
+        finding(
+            "unknown",
+            DiagnosticSeverity::Error,
+            match (probe.exit_code, probe.signal) {
+                (Some(code), _) => format!("测试命令失败，退出码 {code}"),
+                (None, Some(signal)) => format!("测试命令被信号 {signal} 终止"),
+                (None, None) => "测试命令失败".to_string(),
+            },
+            Some("请附上诊断输出反馈"),
+        )
+    }
+
+    /// 启动 proot 失败时给出可读的原因，代替原始的系统错误
+    pub fn describe_spawn_error(err: &io::Error, proot_bin: &Path) -> String {
+        let finding = classify_spawn_error(err, proot_bin);
+        match finding.suggestion {
+            Some(suggestion) => format!("{}，{suggestion}", finding.message),
+            None => finding.message,
+        }
+    }
+
+    fn classify_spawn_error(err: &io::Error, proot_bin: &Path) -> DiagnosticFinding {
+        match err.raw_os_error() {
+            Some(libc::EACCES) | Some(libc::EPERM) => finding(
+                "selinux-exec",
+                DiagnosticSeverity::Error,
+                format!("系统禁止执行 {} (SELinux)", proot_bin.to_string_lossy()),
+                Some("如果运行环境位于外部存储，请迁回内部存储；否则运行校验与修复"),
+            ),
+            Some(libc::ENOEXEC) => finding(
+                "wrong-arch",
+                DiagnosticSeverity::Error,
+                "proot 与设备架构不兼容",
+                Some("删除运行环境后重新下载"),
+            ),
+            Some(libc::ENOENT) => finding(
+                "proot-missing",
+                DiagnosticSeverity::Error,
+                "找不到 proot 可执行文件",
+                Some("运行校验与修复以重新获取 proot"),
+            ),
+            _ => finding(
+                "spawn-failed",
+                DiagnosticSeverity::Error,
+                format!("无法启动 proot: {err}"),
+                None,
+            ),
+        }
+    }
+
+    /// 下载和快照导出使用的临时目录
+    pub fn temp_download_dirs(app: &AppHandle) -> Result<Vec<PathBuf>, String> {
+        Ok(vec![
+            proot_base_dir(app)?.join("temp_download"),
+            app.path()
+                .resolve("rootfs_snapshot_temp", BaseDirectory::Cache)
+                .map_err(|e| e.to_string())?,
+        ])
+    }
+
+    /// 删除中断下载留下的文件和快照临时文件，返回释放的字节数
+    ///
+    /// 与下载互斥，进行中的下载不会被破坏；已暂停的下载之后需从头开始。
+    pub fn clear_temp_downloads(app: &AppHandle) -> Result<u64, String> {
+        let dirs = temp_download_dirs(app)?;
+        let control = download_control();
+        control.begin()?;
+        let mut freed = 0;
+        let mut result = Ok(());
+        for dir in dirs.iter().filter(|dir| dir.exists()) {
+            let size = disk_usage(dir);
+            match fs::remove_dir_all(dir) {
+                Ok(()) => freed += size,
+                Err(e) => {
+                    result = Err(format!("删除 {} 失败: {e}", dir.to_string_lossy()));
+                    break;
+                }
+            }
+        }
+        control.set(DownloadState::Idle);
+        result.map(|_| freed)
+    }
+
+    /// 暂停正在进行的下载，已下载部分保留
+    pub fn pause_proot_download() -> Result<(), String> {
+        download_control().transition(&[DownloadState::Running], DownloadState::Paused)
+    }
+
+    pub fn resume_proot_download() -> Result<(), String> {
+        download_control().transition(&[DownloadState::Paused], DownloadState::Running)
+    }
+
+    /// 取消下载，删除本次下载的临时文件
+    pub fn cancel_proot_download() -> Result<(), String> {
+        download_control().transition(
+            &[DownloadState::Running, DownloadState::Paused],
+            DownloadState::Cancelled,
+        )
+    }
+
+    /// rootfs 中实际使用的根目录
+    ///
+    /// 优先使用当前架构的默认目录名；不存在时取 `rootfs` 下唯一一个带有
+    /// `usr` 和 `etc` 的目录，兼容其他命名的镜像。都找不到时返回默认目录，
+    /// 供调用方判断是否已解压。
+    pub fn rootfs_arch_dir(rootfs_root: &Path) -> PathBuf {
+        let expected = rootfs_root.join(default_rootfs_dir_name());
+        if expected.is_dir() {
+            return expected;
+        }
+        let mut candidates = fs::read_dir(rootfs_root)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.join("usr").is_dir() && path.join("etc").is_dir());
+        match (candidates.next(), candidates.next()) {
+            (Some(only), None) => only,
+            _ => expected,
+        }
+    }
+
+    /// 官方 rootfs 镜像中当前架构的目录名
+    fn default_rootfs_dir_name() -> &'static str {
+        #[cfg(target_arch = "aarch64")]
+        return "archlinux-aarch64";
+        #[cfg(target_arch = "arm")]
+        return "archlinux-armv7l";
+        #[cfg(target_arch = "x86_64")]
+        return "archlinux-x86_64";
+        #[cfg(target_arch = "x86")]
+        return "archlinux-x86";
+    }
+
+    /// 将 rootfs（不含缓存）打包为 tar.xz 写入 `target`
+    ///
+    /// `target` 可以是 SAF 返回的 Content URI 或普通路径。快照格式与下载的
+    /// `rootfs.tar.xz` 相同，返回写入的字节数。
+    pub async fn export_rootfs_snapshot(app: AppHandle, target: String) -> Result<u64, String> {
+        tauri::async_runtime::spawn_blocking(move || {
+            let control = download_control();
+            control.begin()?;
+            let result = write_rootfs_snapshot(&app, target.trim());
+            control.set(DownloadState::Idle);
+            result
+        })
+        .await
+        .map_err(|e| format!("后台任务执行失败: {e}"))?
+    }
+
+    fn write_rootfs_snapshot(app: &AppHandle, target: &str) -> Result<u64, String> {
+        use tauri_plugin_file_picker::{FilePickerExt, WriteContentUriRequest};
+
+        if target.is_empty() {
+            return Err("请选择快照保存位置".to_string());
+        }
+        let rootfs_root = proot_base_dir(app)?.join("rootfs");
+        let rootfs_dir = rootfs_arch_dir(&rootfs_root);
+        if !rootfs_dir.exists() {
+            return Err("rootfs 尚未准备就绪，无法导出".to_string());
+        }
+
+        let is_content_uri = target.starts_with("content://");
+        let archive_path = if is_content_uri {
+            let cache_dir = app
+                .path()
+                .resolve("rootfs_snapshot_temp", BaseDirectory::Cache)
+                .map_err(|e| format!("无法获取缓存目录: {e}"))?;
+            fs::create_dir_all(&cache_dir).map_err(|e| format!("无法创建缓存目录: {e}"))?;
+            cache_dir.join("rootfs-snapshot.tar.xz")
+        } else {
+            PathBuf::from(target)
+        };
+
+        eprintln!("正在导出 rootfs 快照到 {}...", target);
+        let written = archive_rootfs(&rootfs_root, &rootfs_dir, &archive_path)
+            .map_err(|e| format!("打包 rootfs 失败: {e}"));
+        let written = match written {
+            Ok(size) if is_content_uri => app
+                .file_picker()
+                .write_content_uri(WriteContentUriRequest {
+                    content_uri: target.to_string(),
+                    source_path: Some(archive_path.to_string_lossy().to_string()),
+                    content: None,
+                })
+                .map_err(|e| format!("写入 Content URI 失败 ({target}): {e}"))
+                .map(|response| response.size.unwrap_or(size)),
+            other => other,
+        };
+        if is_content_uri || written.is_err() {
+            let _ = fs::remove_file(&archive_path);
+        }
+        written
+    }
+
+    fn archive_rootfs(rootfs_root: &Path, rootfs_dir: &Path, output: &Path) -> io::Result<u64> {
+        let file = File::create(output)?;
+        let mut builder = tar::Builder::new(xz2::write::XzEncoder::new(file, 6));
+        // rootfs 中大量使用符号链接，按链接本身打包
+        builder.follow_symlinks(false);
+
+        let excluded: Vec<PathBuf> = SNAPSHOT_EXCLUDED_DIRS
+            .iter()
+            .map(|dir| rootfs_dir.join(dir))
+            .collect();
+        let name = rootfs_dir
+            .strip_prefix(rootfs_root)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        builder.append_path_with_name(rootfs_dir, name)?;
+        append_snapshot_entries(&mut builder, rootfs_root, rootfs_dir, &excluded)?;
+
+        let file = builder.into_inner()?.finish()?;
+        file.sync_all()?;
+        Ok(file.metadata()?.len())
+    }
+
+    fn append_snapshot_entries<W: Write>(
+        builder: &mut tar::Builder<W>,
+        rootfs_root: &Path,
+        dir: &Path,
+        excluded: &[PathBuf],
+    ) -> io::Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            let name = path
+                .strip_prefix(rootfs_root)
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+            builder.append_path_with_name(&path, name)?;
+            if entry.file_type()?.is_dir() && !excluded.contains(&path) {
+                append_snapshot_entries(builder, rootfs_root, &path, excluded)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 用快照替换当前 rootfs（包括其中的 /root）
+    ///
+    /// `source` 可以是 Content URI 或普通路径。快照必须与设备架构一致；
+    /// 如果本机还没有 proot 本身，导入后只下载 proot，不会覆盖导入的 rootfs。
+    pub async fn import_rootfs_snapshot(app: AppHandle, source: String) -> Result<(), String> {
+        tauri::async_runtime::spawn_blocking(move || {
+            let control = download_control();
+            control.begin()?;
+            let result = restore_rootfs_snapshot(&app, source.trim())
+                .and_then(|_| finish_snapshot_import(&app));
+            let cancelled = control.state() == DownloadState::Cancelled;
+            control.set(DownloadState::Idle);
+
+            match result {
+                Ok(()) => {
+                    let _ = app.emit("proot-download-progress", DownloadProgress::Completed);
+                    Ok(())
+                }
+                Err(_) if cancelled => {
+                    let _ = app.emit("proot-download-progress", DownloadProgress::Cancelled);
+                    Err("下载已取消".to_string())
+                }
+                Err(e) => {
+                    let _ = app.emit(
+                        "proot-download-progress",
+                        DownloadProgress::Error { message: e.clone() },
+                    );
+                    Err(e)
+                }
+            }
+        })
+        .await
+        .map_err(|e| format!("后台任务执行失败: {e}"))?
+    }
+
+    /// 快照取代了清单中解压到 rootfs 下的压缩包：将它们记为已安装，
+    /// 缺少 proot 时只应用包含 proot 的压缩包
+    fn finish_snapshot_import(app: &AppHandle) -> Result<(), String> {
+        let base_dir = proot_base_dir(app)?;
+        let proot_dir = base_dir.join("proot");
+        let has_proot = proot_dir.join("bin/proot").exists();
+        let manifest = match fetch_assets_manifest(app, &base_dir) {
+            Ok(manifest) => manifest,
+            Err(e) if has_proot => {
+                eprintln!("未获取到资源清单，跳过记录 rootfs 版本: {e}");
+                return Ok(());
+            }
+            Err(e) => {
+                // 完整资源包只解压出 proot/ 和 rootfs.tar.xz，不会覆盖已导入的 rootfs
+                eprintln!("未获取到资源清单，改为下载完整资源包: {e}");
+                return download_and_extract_assets(app, &base_dir);
+            }
+        };
+
+        let rootfs_root = base_dir.join("rootfs");
+        let mut installed = load_installed_assets(&base_dir);
+        let mut proot_archives = Vec::new();
+        for archive in manifest.archives {
+            let destination = asset_destination(&base_dir, &archive)?;
+            if destination.starts_with(&rootfs_root) {
+                installed
+                    .archives
+                    .insert(archive.name.clone(), archive.sha256.clone());
+            } else if proot_dir.starts_with(&destination) {
+                proot_archives.push(archive);
+            }
+        }
+        save_installed_assets(&base_dir, &installed)?;
+
+        if has_proot {
+            return Ok(());
+        }
+        let proot_manifest = AssetsManifest {
+            version: manifest.version,
+            archives: proot_archives,
+        };
+        apply_assets_manifest(app, &base_dir, &proot_manifest)
+    }
+
+    fn restore_rootfs_snapshot(app: &AppHandle, source: &str) -> Result<(), String> {
+        use tauri_plugin_file_picker::{FilePickerExt, ReadContentUriRequest};
+
+        if source.is_empty() {
+            return Err("请选择要导入的快照".to_string());
+        }
+        let base_dir = proot_base_dir(app)?;
+        fs::create_dir_all(&base_dir).map_err(|e| format!("创建 proot 目录失败: {e}"))?;
+
+        let is_content_uri = source.starts_with("content://");
+        let archive_path = if is_content_uri {
+            let temp_path = base_dir.join("rootfs-snapshot.tar.xz");
+            let response = app
+                .file_picker()
+                .read_content_uri(ReadContentUriRequest {
+                    content_uri: source.to_string(),
+                    target_path: Some(temp_path.to_string_lossy().to_string()),
+                })
+                .map_err(|e| format!("无法读取 Content URI ({source}): {e}"))?;
+            if !response.success {
+                return Err(format!("读取 Content URI 失败: {source}"));
+            }
+            temp_path
+        } else {
+            PathBuf::from(source)
+        };
+        if !archive_path.is_file() {
+            return Err(format!("快照文件不存在: {source}"));
+        }
+
+        // 先解压到临时目录，校验通过后再替换，失败时保留原有 rootfs
+        let staging = base_dir.join("rootfs.importing");
+        if staging.exists() {
+            fs::remove_dir_all(&staging).map_err(|e| format!("清理临时目录失败: {e}"))?;
+        }
+        eprintln!("正在从快照恢复 rootfs...");
+        let extracted = decompress_tar_xz(app, &archive_path, &staging)
+            .map_err(|e| format!("解压快照失败: {e:?}"))
+            .and_then(|_| {
+                if rootfs_arch_dir(&staging).exists() {
+                    Ok(())
+                } else {
+                    Err("快照与当前设备架构不匹配".to_string())
+                }
+            });
+        if is_content_uri {
+            let _ = fs::remove_file(&archive_path);
+        }
+        if let Err(e) = extracted {
+            let _ = fs::remove_dir_all(&staging);
+            return Err(e);
+        }
+
+        let rootfs_root = base_dir.join("rootfs");
+        let previous = base_dir.join("rootfs.old");
+        if rootfs_root.exists() {
+            if previous.exists() {
+                fs::remove_dir_all(&previous).map_err(|e| format!("清理旧 rootfs 失败: {e}"))?;
+            }
+            fs::rename(&rootfs_root, &previous).map_err(|e| format!("替换 rootfs 失败: {e}"))?;
+        }
+        fs::rename(&staging, &rootfs_root).map_err(|e| format!("替换 rootfs 失败: {e}"))?;
+        let _ = fs::remove_dir_all(&previous);
+        Ok(())
+    }
+
+    /// 迁移进度，通过 `truidide://environment/migration-progress` 发送
+    #[derive(Clone, Debug, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct StorageMigrationProgress {
+        pub copied_bytes: u64,
+        pub total_bytes: u64,
+        pub percentage: u8,
+    }
+
+    /// 将 proot 目录迁移到 `target`（为空时迁回内部存储）
+    ///
+    /// 数据放在 `{target}/proot`。目标需支持符号链接和可执行文件，例如应用
+    /// 专属的外部存储或已合并为内部存储的 SD 卡。迁移前应先关闭终端和插件
+    /// 进程，它们仍在使用旧路径。
+    pub async fn migrate_environment_storage(
+        app: AppHandle,
+        target: Option<String>,
+    ) -> Result<EnvironmentStorage, String> {
+        tauri::async_runtime::spawn_blocking(move || {
+            let control = download_control();
+            control.begin()?;
+            let result = move_environment(&app, target);
+            control.set(DownloadState::Idle);
+            result
+        })
+        .await
+        .map_err(|e| format!("后台任务执行失败: {e}"))?
+    }
+
+    fn move_environment(
+        app: &AppHandle,
+        target: Option<String>,
+    ) -> Result<EnvironmentStorage, String> {
+        let location = target
+            .map(|target| target.trim().trim_end_matches('/').to_string())
+            .filter(|target| !target.is_empty());
+        if location
+            .as_ref()
+            .is_some_and(|target| !target.starts_with('/'))
+        {
+            return Err("存储位置必须是绝对路径".to_string());
+        }
+        let storage = EnvironmentStorage { location };
+
+        let source = proot_base_dir(app)?;
+        let destination = match &storage.location {
+            Some(location) => Path::new(location).join("proot"),
+            None => default_proot_base_dir(app)?,
+        };
+        if destination == source {
+            return Ok(storage);
+        }
+        if destination.starts_with(&source) || source.starts_with(&destination) {
+            return Err("新位置不能与当前位置互相包含".to_string());
+        }
+        if fs::read_dir(&destination).is_ok_and(|mut entries| entries.next().is_some()) {
+            return Err(format!(
+                "目标目录已存在且不为空: {}",
+                destination.to_string_lossy()
+            ));
+        }
+        let parent = destination
+            .parent()
+            .ok_or_else(|| "无效的存储位置".to_string())?;
+        fs::create_dir_all(parent).map_err(|e| format!("创建存储目录失败: {e}"))?;
+        probe_storage(parent)?;
+
+        // 一并迁移重建 rootfs 时暂存的主目录
+        let moves: Vec<(PathBuf, PathBuf)> = [
+            (source.clone(), destination.clone()),
+            (
+                source.with_file_name(PRESERVED_HOME_DIR),
+                destination.with_file_name(PRESERVED_HOME_DIR),
+            ),
+        ]
+        .into_iter()
+        .filter(|(from, _)| from.exists())
+        .collect();
+
+        let total_bytes: u64 = moves.iter().map(|(from, _)| disk_usage(from)).sum();
+        if let Some(available) = available_space(parent) {
+            if available < total_bytes {
+                return Err(format!(
+                    "目标存储空间不足: 需要 {} MB，可用 {} MB",
+                    total_bytes / 1024 / 1024,
+                    available / 1024 / 1024
+                ));
+            }
+        }
+
+        let mut progress = MigrationProgress::new(app, total_bytes);
+        let mut copied = Vec::new();
+        for (from, to) in &moves {
+            match fs::rename(from, to) {
+                Ok(()) => progress.advance(disk_usage(to)),
+                Err(err) if is_cross_device_error(&err) => {
+                    if let Err(err) = copy_preserving(from, to, &mut progress) {
+                        let _ = fs::remove_dir_all(to);
+                        // 已复制完的部分也撤销，旧位置仍然完整
+                        for (_, done) in &copied {
+                            let _ = fs::remove_dir_all(done);
+                        }
+                        return Err(format!("复制运行环境失败: {err}"));
+                    }
+                    copied.push((from.clone(), to.clone()));
+                }
+                Err(err) => return Err(format!("移动运行环境失败: {err}")),
+            }
+        }
+        progress.finish();
+
+        save_environment_storage(app, &storage)?;
+        for (from, _) in &copied {
+            if let Err(err) = fs::remove_dir_all(from) {
+                eprintln!(
+                    "[truidide::proot] 清理旧的运行环境 {} 失败: {err}",
+                    from.to_string_lossy()
+                );
+            }
+        }
+        eprintln!(
+            "[truidide::proot] 运行环境已迁移到 {}",
+            destination.to_string_lossy()
+        );
+        Ok(storage)
+    }
+
+    struct MigrationProgress<'a> {
+        app: &'a AppHandle,
+        copied_bytes: u64,
+        total_bytes: u64,
+        last_report: std::time::Instant,
+    }
+
+    impl<'a> MigrationProgress<'a> {
+        fn new(app: &'a AppHandle, total_bytes: u64) -> Self {
+            Self {
+                app,
+                copied_bytes: 0,
+                total_bytes,
+                last_report: std::time::Instant::now(),
+            }
+        }
+
+        fn advance(&mut self, bytes: u64) {
+            self.copied_bytes += bytes;
+            if self.last_report.elapsed().as_millis() > 500 {
+                self.report();
+            }
+        }
+
+        fn finish(&mut self) {
+            self.copied_bytes = self.total_bytes;
+            self.report();
+        }
+
+        fn report(&mut self) {
+            let percentage = if self.total_bytes == 0 {
+                100
+            } else {
+                (self.copied_bytes.min(self.total_bytes) * 100 / self.total_bytes) as u8
+            };
+            let _ = self.app.emit(
+                EVENT_STORAGE_MIGRATION,
+                StorageMigrationProgress {
+                    copied_bytes: self.copied_bytes,
+                    total_bytes: self.total_bytes,
+                    percentage,
+                },
+            );
+            self.last_report = std::time::Instant::now();
+        }
+    }
+
+    // 按原样复制符号链接和权限，rootfs 依赖两者
+    fn copy_preserving(
+        source: &Path,
+        destination: &Path,
+        progress: &mut MigrationProgress<'_>,
+    ) -> io::Result<()> {
+        let metadata = fs::symlink_metadata(source)?;
+        let file_type = metadata.file_type();
+        if file_type.is_symlink() {
+            std::os::unix::fs::symlink(fs::read_link(source)?, destination)?;
+        } else if file_type.is_dir() {
+            fs::create_dir(destination)?;
+            for entry in fs::read_dir(source)? {
+                let entry = entry?;
+                copy_preserving(
+                    &entry.path(),
+                    &destination.join(entry.file_name()),
+                    progress,
+                )?;
+            }
+            fs::set_permissions(destination, metadata.permissions())?;
+        } else if file_type.is_file() {
+            // fs::copy 同时复制权限位
+            progress.advance(fs::copy(source, destination)?);
+        }
+        Ok(())
+    }
+
+    // rootfs 需要符号链接和可执行文件，FAT/exFAT 和 noexec 挂载都不行
+    fn probe_storage(dir: &Path) -> Result<(), String> {
+        if statvfs(dir).is_some_and(|stat| stat.f_flag & libc::ST_NOEXEC != 0) {
+            return Err("目标存储禁止执行程序，无法存放 rootfs".to_string());
+        }
+
+        let probe = dir.join(".truidide-storage-probe");
+        let _ = fs::remove_dir_all(&probe);
+        let result = (|| -> io::Result<bool> {
+            fs::create_dir(&probe)?;
+            let file = probe.join("exec");
+            fs::write(&file, b"#!/bin/sh\n")?;
+            fs::set_permissions(&file, fs::Permissions::from_mode(0o755))?;
+            let executable = fs::metadata(&file)?.permissions().mode() & 0o111 != 0;
+            std::os::unix::fs::symlink("exec", probe.join("link"))?;
+            Ok(executable)
+        })();
+        let _ = fs::remove_dir_all(&probe);
+        match result {
+            Ok(true) => Ok(()),
+            Ok(false) => Err("目标存储不支持可执行权限，无法存放 rootfs".to_string()),
+            Err(e) => Err(format!("目标存储不支持 rootfs 所需的符号链接或权限: {e}")),
+        }
+    }
+
+    fn statvfs(path: &Path) -> Option<libc::statvfs> {
+        use std::ffi::CString;
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+        let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+        if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+            return None;
+        }
+        Some(stat)
+    }
+
+    fn available_space(path: &Path) -> Option<u64> {
+        statvfs(path).map(|stat| stat.f_bavail as u64 * stat.f_frsize as u64)
+    }
+
+    pub fn prepare_proot_env(app: &AppHandle) -> Result<ProotEnv, String> {
+        let appdata_base = proot_base_dir(app)?;
+
+        // 如果资源还没下载，直接返回错误（不在这里下载）
+        if !appdata_base.exists()
+            || fs::read_dir(&appdata_base)
+                .map(|mut d| d.next().is_none())
+                .unwrap_or(true)
+        {
+            return Err("Proot 资源尚未准备就绪，请等待下载完成".to_string());
+        }
+
+        let dest = appdata_base;
+        let rootfs_root = dest.join("rootfs");
+
+        // 如果 rootfs 还未解压，也返回错误
+        if !rootfs_root.exists() {
+            return Err("Rootfs 尚未解压完成，请等待初始化完成".to_string());
+        }
+
+        // 权限设置已在下载时完成，无需再次检查
+        let proot_path = dest.join("proot/bin/proot");
+        if !proot_path.exists() {
+            return Err(format!(
+                "必需的文件未找到: {}，请确保资源已正确下载",
+                proot_path.to_string_lossy()
+            ));
+        }
+
+        let rootfs_dir = rootfs_arch_dir(&rootfs_root);
+
+        if !rootfs_dir.exists() {
+            return Err(format!(
                 "rootfs 未解压或架构目录缺失: {}",
                 rootfs_dir.to_string_lossy()
             ));
@@ -529,15 +2327,38 @@ pub mod proot {
             })?;
         }
 
+        let settings = load_proot_settings(app).unwrap_or_else(|err| {
+            eprintln!("[truidide::proot] 读取 proot 设置失败，使用默认设置: {err}");
+            ProotSettings::default()
+        });
+
         Ok(ProotEnv {
             base_dir: dest,
             proot_bin: proot_path,
             rootfs_root,
             rootfs_dir,
             tmp_dir,
+            settings,
         })
     }
 
+    /// 构建在 proot 容器内执行非交互命令的基础命令
+    ///
+    /// 调用方只需追加要执行的程序及其参数。与终端不同，这里不绑定
+    /// `/proc/self/fd/*`，因为子进程使用 pipe 而不是 PTY。
+    pub fn proot_command(env: &ProotEnv, guest_cwd: &str) -> std::process::Command {
+        let mut command = std::process::Command::new(&env.proot_bin);
+        command.args(env.base_args());
+        command.arg(format!("--cwd={guest_cwd}"));
+        command.envs(env.base_env());
+        command.env("HOME", "/root");
+        command.env(
+            "PATH",
+            "/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin",
+        );
+        command
+    }
+
     pub fn resolve_guest_path(app: &AppHandle, guest_path: &str) -> Result<PathBuf, String> {
         let env = prepare_proot_env(app)?;
         let trimmed = guest_path.trim();
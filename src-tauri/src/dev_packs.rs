@@ -0,0 +1,330 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use crate::exec::{self, RunCommandArgs};
+use crate::plugins::{PluginHost, PluginKind};
+use crate::tasks::{self, RunConfiguration, TaskDefinition};
+
+const EVENT_DEV_PACK_PROGRESS: &str = "truidide://dev-pack/progress";
+const PACKAGE_INSTALL_TIMEOUT_SECS: u64 = 30 * 60;
+const VENV_TIMEOUT_SECS: u64 = 5 * 60;
+
+/// A development pack bundles everything needed to start working in a language.
+struct DevPack {
+    id: &'static str,
+    /// Shell script that installs the guest packages with whichever package
+    /// manager the rootfs provides.
+    install_script: &'static str,
+    /// Command used to check the toolchain is usable afterwards.
+    probe_command: &'static str,
+    /// Language id an LSP plugin must serve for the pack to be complete.
+    language_id: &'static str,
+    /// Shell command creating the per-project environment, if any.
+    project_setup: Option<&'static str>,
+    run_configuration: (&'static str, &'static str, &'static str),
+}
+
+const DEV_PACKS: &[DevPack] = &[DevPack {
+    id: "python",
+    install_script: "if command -v apt-get >/dev/null 2>&1; then \
+        export DEBIAN_FRONTEND=noninteractive; \
+        apt-get update && apt-get install -y python3 python3-pip python3-venv; \
+    elif command -v apk >/dev/null 2>&1; then \
+        apk add --no-cache python3 py3-pip; \
+    elif command -v pacman >/dev/null 2>&1; then \
+        pacman -Sy --noconfirm python python-pip; \
+    else \
+        echo '未找到受支持的包管理器' >&2; exit 1; \
+    fi",
+    probe_command: "python3 --version",
+    language_id: "python",
+    project_setup: Some("python3 -m venv .venv"),
+    run_configuration: ("python", "运行 main.py", ".venv/bin/python main.py"),
+}];
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallDevPackArgs {
+    pub pack_id: String,
+    /// Project that receives the virtual environment and run configuration.
+    #[serde(default)]
+    pub project_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DevPackStep {
+    InstallPackages,
+    LanguageServer,
+    ProjectEnvironment,
+    RunConfiguration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DevPackStepStatus {
+    Started,
+    Output,
+    Completed,
+    Skipped,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DevPackProgress {
+    pub pack_id: String,
+    pub step: DevPackStep,
+    pub status: DevPackStepStatus,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DevPackReport {
+    pub pack_id: String,
+    pub success: bool,
+    /// Final status of each step, in execution order.
+    pub steps: Vec<DevPackProgress>,
+}
+
+struct Installer<'a> {
+    app: &'a AppHandle,
+    pack: &'static DevPack,
+    steps: Vec<DevPackProgress>,
+}
+
+impl Installer<'_> {
+    fn report(&mut self, step: DevPackStep, status: DevPackStepStatus, message: impl Into<String>) {
+        let progress = DevPackProgress {
+            pack_id: self.pack.id.to_string(),
+            step,
+            status,
+            message: message.into(),
+        };
+        let _ = self.app.emit(EVENT_DEV_PACK_PROGRESS, &progress);
+        if !matches!(
+            status,
+            DevPackStepStatus::Started | DevPackStepStatus::Output
+        ) {
+            self.steps.push(progress);
+        }
+    }
+
+    async fn run_shell(
+        &mut self,
+        step: DevPackStep,
+        cwd: &str,
+        script: &str,
+        timeout_secs: u64,
+    ) -> Result<(), String> {
+        let (program, args) = tasks::shell_invocation(script);
+        let app = self.app.clone();
+        let pack_id = self.pack.id.to_string();
+        let outcome = exec::execute(
+            self.app,
+            RunCommandArgs {
+                run_id: None,
+                cwd: cwd.to_string(),
+                program,
+                args,
+                env: HashMap::new(),
+                timeout_secs: Some(timeout_secs),
+            },
+            move |_, line| {
+                let _ = app.emit(
+                    EVENT_DEV_PACK_PROGRESS,
+                    DevPackProgress {
+                        pack_id: pack_id.clone(),
+                        step,
+                        status: DevPackStepStatus::Output,
+                        message: line.to_string(),
+                    },
+                );
+            },
+        )
+        .await?;
+
+        if outcome.success {
+            Ok(())
+        } else if outcome.timed_out {
+            Err(format!("执行超时 ({timeout_secs} 秒)"))
+        } else {
+            Err(format!(
+                "命令退出码 {}",
+                outcome
+                    .exit_code
+                    .map(|code| code.to_string())
+                    .unwrap_or_else(|| "未知".into())
+            ))
+        }
+    }
+
+    async fn install_packages(&mut self) -> bool {
+        let step = DevPackStep::InstallPackages;
+        self.report(step, DevPackStepStatus::Started, "正在安装系统软件包");
+
+        // Only the proot guest is managed by us; on desktop the host
+        // toolchain is used as-is and merely probed.
+        let script = if cfg!(target_os = "android") {
+            self.pack.install_script
+        } else {
+            self.pack.probe_command
+        };
+        match self
+            .run_shell(step, &home_dir(), script, PACKAGE_INSTALL_TIMEOUT_SECS)
+            .await
+        {
+            Ok(()) => {
+                self.report(step, DevPackStepStatus::Completed, "软件包已就绪");
+                true
+            }
+            Err(err) => {
+                self.report(step, DevPackStepStatus::Failed, err);
+                false
+            }
+        }
+    }
+
+    async fn check_language_server(&mut self) {
+        let step = DevPackStep::LanguageServer;
+        self.report(step, DevPackStepStatus::Started, "正在检查语言服务插件");
+
+        let installed = match PluginHost::obtain(self.app) {
+            Ok(host) => host.list_plugins().await.into_iter().find(|plugin| {
+                plugin.is_usable()
+                    && matches!(&plugin.manifest.kind, PluginKind::Lsp(manifest)
+                        if manifest.language_ids.iter().any(|id| id == self.pack.language_id))
+            }),
+            Err(err) => {
+                self.report(step, DevPackStepStatus::Failed, err);
+                return;
+            }
+        };
+
+        match installed {
+            Some(plugin) => self.report(
+                step,
+                DevPackStepStatus::Completed,
+                format!("已安装语言服务插件 {}", plugin.manifest.id),
+            ),
+            // There is no plugin marketplace to download from yet.
+            None => self.report(
+                step,
+                DevPackStepStatus::Skipped,
+                format!(
+                    "未找到 {} 语言服务插件，请手动导入（插件市场尚不可用）",
+                    self.pack.language_id
+                ),
+            ),
+        }
+    }
+
+    async fn setup_project(&mut self, project_path: Option<&str>) -> bool {
+        let step = DevPackStep::ProjectEnvironment;
+        let (Some(project_path), Some(script)) = (project_path, self.pack.project_setup) else {
+            self.report(step, DevPackStepStatus::Skipped, "未指定项目");
+            return true;
+        };
+
+        self.report(step, DevPackStepStatus::Started, "正在创建项目环境");
+        match self
+            .run_shell(step, project_path, script, VENV_TIMEOUT_SECS)
+            .await
+        {
+            Ok(()) => {
+                self.report(step, DevPackStepStatus::Completed, "项目环境已创建");
+                true
+            }
+            Err(err) => {
+                self.report(step, DevPackStepStatus::Failed, err);
+                false
+            }
+        }
+    }
+
+    fn add_run_configuration(&mut self, project_path: Option<&str>) -> bool {
+        let step = DevPackStep::RunConfiguration;
+        let Some(project_path) = project_path else {
+            self.report(step, DevPackStepStatus::Skipped, "未指定项目");
+            return true;
+        };
+
+        let (id, name, command) = self.pack.run_configuration;
+        let configuration = RunConfiguration {
+            id: id.to_string(),
+            task: TaskDefinition {
+                name: name.to_string(),
+                command: command.to_string(),
+                cwd: None,
+                env: HashMap::new(),
+                timeout_secs: None,
+                problem_matcher: None,
+            },
+            default: true,
+            detected: false,
+        };
+        match tasks::ensure_run_configuration(self.app, project_path, configuration) {
+            Ok(true) => {
+                self.report(step, DevPackStepStatus::Completed, "已添加默认运行配置");
+                true
+            }
+            Ok(false) => {
+                self.report(step, DevPackStepStatus::Skipped, "运行配置已存在");
+                true
+            }
+            Err(err) => {
+                self.report(step, DevPackStepStatus::Failed, err);
+                false
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "android")]
+pub(crate) fn home_dir() -> String {
+    "/root".to_string()
+}
+
+#[cfg(not(target_os = "android"))]
+pub(crate) fn home_dir() -> String {
+    std::env::temp_dir().to_string_lossy().into_owned()
+}
+
+#[tauri::command]
+pub async fn install_dev_pack(
+    app: AppHandle,
+    args: InstallDevPackArgs,
+) -> Result<DevPackReport, String> {
+    let pack = DEV_PACKS
+        .iter()
+        .find(|pack| pack.id == args.pack_id)
+        .ok_or_else(|| format!("未知的开发包 {}", args.pack_id))?;
+    let project_path = args
+        .project_path
+        .as_deref()
+        .filter(|path| !path.trim().is_empty());
+
+    let mut installer = Installer {
+        app: &app,
+        pack,
+        steps: Vec::new(),
+    };
+
+    let mut success = installer.install_packages().await;
+    installer.check_language_server().await;
+    if success {
+        success = installer.setup_project(project_path).await;
+    }
+    if success {
+        success = installer.add_run_configuration(project_path);
+    }
+
+    Ok(DevPackReport {
+        pack_id: pack.id.to_string(),
+        success,
+        steps: installer.steps,
+    })
+}
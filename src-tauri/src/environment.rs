@@ -0,0 +1,492 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tauri::path::BaseDirectory;
+use tauri::{AppHandle, Manager};
+
+const PROOT_SETTINGS_FILENAME: &str = "proot-settings.json";
+const ENVIRONMENT_STORAGE_FILENAME: &str = "environment-storage.json";
+// proot flags that would swap out the rootfs under the IDE.
+const RESERVED_PROOT_FLAGS: &[&str] = &["-r", "-R", "-S", "--rootfs"];
+
+/// State of the Linux environment commands run in, for the onboarding flow.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvironmentStatus {
+    /// Whether this platform needs the proot environment at all. Desktop
+    /// builds run commands on the host and report everything else empty.
+    pub required: bool,
+    /// Commands can run: not required, or downloaded and extracted.
+    pub ready: bool,
+    /// The proot assets have been downloaded.
+    pub downloaded: bool,
+    /// The rootfs has been extracted from the downloaded archive.
+    pub extracted: bool,
+    /// Architecture of the extracted rootfs, e.g. `aarch64`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rootfs_arch: Option<String>,
+    /// Bytes used by the assets and rootfs on disk.
+    pub disk_usage: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_dir: Option<String>,
+}
+
+/// A host directory made visible inside proot.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BindMount {
+    /// Absolute host path, e.g. `/sdcard/Download`.
+    pub host: String,
+    /// Absolute guest path, e.g. `/mnt/downloads`.
+    pub guest: String,
+}
+
+/// User additions to every proot invocation: terminals, commands and
+/// plugin processes.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProotSettings {
+    #[serde(default)]
+    pub bind_mounts: Vec<BindMount>,
+    /// Extra flags appended after the binds, e.g. `--sysvipc`.
+    #[serde(default)]
+    pub extra_args: Vec<String>,
+    /// Turn off proot's seccomp acceleration, for kernels whose seccomp
+    /// policy kills it.
+    #[serde(default)]
+    pub no_seccomp: bool,
+}
+
+impl ProotSettings {
+    /// `--bind` arguments followed by the extra flags.
+    pub fn args(&self) -> Vec<String> {
+        self.bind_mounts
+            .iter()
+            .map(|mount| format!("--bind={}:{}", mount.host, mount.guest))
+            .chain(self.extra_args.iter().cloned())
+            .collect()
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        for mount in &self.bind_mounts {
+            for path in [&mount.host, &mount.guest] {
+                if !path.starts_with('/') || path.contains(':') {
+                    return Err(format!("挂载路径必须是绝对路径且不含冒号: {path}"));
+                }
+            }
+            if mount.guest.trim_end_matches('/').is_empty() {
+                return Err("不能挂载到容器根目录".into());
+            }
+            if !Path::new(&mount.host).exists() {
+                return Err(format!("主机路径不存在: {}", mount.host));
+            }
+        }
+        for arg in &self.extra_args {
+            if !arg.starts_with('-') {
+                return Err(format!("proot 参数必须以 - 开头: {arg}"));
+            }
+            let flag = arg.split('=').next().unwrap_or(arg);
+            if RESERVED_PROOT_FLAGS.contains(&flag) {
+                return Err(format!("不允许修改 rootfs 的参数: {arg}"));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn proot_settings_path(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .resolve(PROOT_SETTINGS_FILENAME, BaseDirectory::AppData)
+        .map_err(|e| e.to_string())
+}
+
+pub fn load_proot_settings(app: &AppHandle) -> Result<ProotSettings, String> {
+    let path = proot_settings_path(app)?;
+    if !path.exists() {
+        return Ok(ProotSettings::default());
+    }
+    let data = fs::read_to_string(&path).map_err(|e| format!("读取 proot 设置失败: {e}"))?;
+    serde_json::from_str(&data).map_err(|e| format!("解析 proot 设置失败: {e}"))
+}
+
+fn save_proot_settings(app: &AppHandle, settings: &ProotSettings) -> Result<(), String> {
+    let path = proot_settings_path(app)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("创建配置目录失败: {e}"))?;
+    }
+    let data = serde_json::to_string_pretty(settings)
+        .map_err(|e| format!("序列化 proot 设置失败: {e}"))?;
+    fs::write(&path, data).map_err(|e| format!("保存 proot 设置失败: {e}"))
+}
+
+#[tauri::command]
+pub fn get_proot_settings(app: AppHandle) -> Result<ProotSettings, String> {
+    load_proot_settings(&app)
+}
+
+/// Takes effect for sessions and processes started afterwards.
+#[tauri::command]
+pub fn set_proot_settings(app: AppHandle, settings: ProotSettings) -> Result<(), String> {
+    settings.validate()?;
+    save_proot_settings(&app, &settings)
+}
+
+/// Where the proot environment lives.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvironmentStorage {
+    /// Directory holding `proot/`, e.g. external app-specific storage or an
+    /// adopted SD card. `None` is the app's internal storage.
+    #[serde(default)]
+    pub location: Option<String>,
+}
+
+fn environment_storage_path(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .resolve(ENVIRONMENT_STORAGE_FILENAME, BaseDirectory::AppData)
+        .map_err(|e| e.to_string())
+}
+
+pub fn load_environment_storage(app: &AppHandle) -> Result<EnvironmentStorage, String> {
+    let path = environment_storage_path(app)?;
+    if !path.exists() {
+        return Ok(EnvironmentStorage::default());
+    }
+    let data = fs::read_to_string(&path).map_err(|e| format!("读取存储位置设置失败: {e}"))?;
+    serde_json::from_str(&data).map_err(|e| format!("解析存储位置设置失败: {e}"))
+}
+
+// Only `migrate_environment_storage` changes the location, after moving the data.
+#[cfg(target_os = "android")]
+pub(crate) fn save_environment_storage(
+    app: &AppHandle,
+    storage: &EnvironmentStorage,
+) -> Result<(), String> {
+    let path = environment_storage_path(app)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("创建配置目录失败: {e}"))?;
+    }
+    let data = serde_json::to_string_pretty(storage)
+        .map_err(|e| format!("序列化存储位置设置失败: {e}"))?;
+    fs::write(&path, data).map_err(|e| format!("保存存储位置设置失败: {e}"))
+}
+
+#[tauri::command]
+pub fn get_environment_storage(app: AppHandle) -> Result<EnvironmentStorage, String> {
+    load_environment_storage(&app)
+}
+
+/// Move the environment to `target` (back to internal storage when `None`),
+/// reporting progress on `truidide://environment/migration-progress`.
+#[cfg(target_os = "android")]
+#[tauri::command]
+pub async fn migrate_environment_storage(
+    app: AppHandle,
+    target: Option<String>,
+) -> Result<EnvironmentStorage, String> {
+    crate::android::proot::migrate_environment_storage(app, target).await
+}
+
+#[cfg(not(target_os = "android"))]
+#[tauri::command]
+pub async fn migrate_environment_storage(
+    _app: AppHandle,
+    _target: Option<String>,
+) -> Result<EnvironmentStorage, String> {
+    Err("当前平台没有 proot 运行环境".to_string())
+}
+
+/// Disk space taken by the proot environment, in bytes.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RootfsDiskUsage {
+    /// Everything under the environment directory.
+    pub total: u64,
+    pub rootfs: u64,
+    /// The guest's `/root`, where projects live; part of `rootfs`.
+    pub home: u64,
+    /// The rootfs archive kept for `reset_rootfs`.
+    pub archive: u64,
+}
+
+// Symlinks are counted as themselves, the rootfs is full of absolute ones.
+pub(crate) fn disk_usage(path: &Path) -> u64 {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| disk_usage(&entry.path()))
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+#[cfg(target_os = "android")]
+fn environment_status(app: &AppHandle) -> Result<EnvironmentStatus, String> {
+    use crate::android::proot::{extracted_rootfs_arch, proot_base_dir};
+
+    let base_dir = proot_base_dir(app)?;
+    let downloaded = fs::read_dir(&base_dir)
+        .map(|mut entries| entries.next().is_some())
+        .unwrap_or(false);
+    let extracted = base_dir.join("rootfs").exists();
+    Ok(EnvironmentStatus {
+        required: true,
+        ready: downloaded && extracted,
+        downloaded,
+        extracted,
+        rootfs_arch: extracted_rootfs_arch(&base_dir),
+        disk_usage: disk_usage(&base_dir),
+        base_dir: Some(base_dir.to_string_lossy().into_owned()),
+    })
+}
+
+#[cfg(not(target_os = "android"))]
+fn environment_status(_app: &AppHandle) -> Result<EnvironmentStatus, String> {
+    Ok(EnvironmentStatus {
+        ready: true,
+        ..Default::default()
+    })
+}
+
+#[tauri::command]
+pub async fn get_environment_status(app: AppHandle) -> Result<EnvironmentStatus, String> {
+    // Walking the rootfs takes a while on first launch.
+    tauri::async_runtime::spawn_blocking(move || environment_status(&app))
+        .await
+        .map_err(|e| format!("检查运行环境失败: {e}"))?
+}
+
+#[cfg(target_os = "android")]
+fn rootfs_disk_usage(app: &AppHandle) -> Result<RootfsDiskUsage, String> {
+    use crate::android::proot::{proot_base_dir, rootfs_arch_dir};
+
+    let base_dir = proot_base_dir(app)?;
+    let rootfs_root = base_dir.join("rootfs");
+    let home = disk_usage(&rootfs_arch_dir(&rootfs_root).join("root"));
+    Ok(RootfsDiskUsage {
+        total: disk_usage(&base_dir),
+        rootfs: disk_usage(&rootfs_root),
+        home,
+        archive: disk_usage(&base_dir.join("rootfs.tar.xz")),
+    })
+}
+
+#[cfg(not(target_os = "android"))]
+fn rootfs_disk_usage(_app: &AppHandle) -> Result<RootfsDiskUsage, String> {
+    Ok(RootfsDiskUsage::default())
+}
+
+#[tauri::command]
+pub async fn get_rootfs_disk_usage(app: AppHandle) -> Result<RootfsDiskUsage, String> {
+    tauri::async_runtime::spawn_blocking(move || rootfs_disk_usage(&app))
+        .await
+        .map_err(|e| format!("统计磁盘占用失败: {e}"))?
+}
+
+/// Remove the environment but keep the projects in the guest's `/root`.
+#[cfg(target_os = "android")]
+#[tauri::command]
+pub async fn delete_rootfs(app: AppHandle) -> Result<(), String> {
+    crate::android::proot::delete_rootfs(app).await
+}
+
+#[cfg(not(target_os = "android"))]
+#[tauri::command]
+pub async fn delete_rootfs(_app: AppHandle) -> Result<(), String> {
+    Ok(())
+}
+
+/// Rebuild a broken rootfs from the kept archive, or download it again.
+#[cfg(target_os = "android")]
+#[tauri::command]
+pub async fn reset_rootfs(app: AppHandle) -> Result<(), String> {
+    crate::android::proot::reset_rootfs(app).await
+}
+
+#[cfg(not(target_os = "android"))]
+#[tauri::command]
+pub async fn reset_rootfs(_app: AppHandle) -> Result<(), String> {
+    Ok(())
+}
+
+/// Pack the rootfs, minus caches, into a tar.xz at `target_uri` (a SAF
+/// content URI or a path) so a provisioned toolchain can move to another
+/// device. Returns the snapshot size in bytes.
+#[cfg(target_os = "android")]
+#[tauri::command]
+pub async fn export_rootfs_snapshot(app: AppHandle, target_uri: String) -> Result<u64, String> {
+    let result = crate::android::proot::export_rootfs_snapshot(app.clone(), target_uri).await;
+    crate::notifications::export_finished(&app, &result);
+    result
+}
+
+#[cfg(not(target_os = "android"))]
+#[tauri::command]
+pub async fn export_rootfs_snapshot(_app: AppHandle, _target_uri: String) -> Result<u64, String> {
+    Err("当前平台没有 proot 运行环境".to_string())
+}
+
+/// Replace the rootfs with a snapshot made by `export_rootfs_snapshot`.
+#[cfg(target_os = "android")]
+#[tauri::command]
+pub async fn import_rootfs_snapshot(app: AppHandle, source: String) -> Result<(), String> {
+    crate::android::proot::import_rootfs_snapshot(app, source).await
+}
+
+#[cfg(not(target_os = "android"))]
+#[tauri::command]
+pub async fn import_rootfs_snapshot(_app: AppHandle, _source: String) -> Result<(), String> {
+    Err("当前平台没有 proot 运行环境".to_string())
+}
+
+/// Check the proot binaries, the rootfs architecture directory and a set
+/// of critical rootfs files. `None` on platforms without proot.
+#[cfg(target_os = "android")]
+#[tauri::command]
+pub async fn verify_environment(
+    app: AppHandle,
+) -> Result<Option<crate::android::proot::EnvironmentReport>, String> {
+    crate::android::proot::verify_environment(app)
+        .await
+        .map(Some)
+}
+
+#[cfg(not(target_os = "android"))]
+#[tauri::command]
+pub async fn verify_environment(_app: AppHandle) -> Result<Option<()>, String> {
+    Ok(None)
+}
+
+/// Carry out the repair plan from `verify_environment`, re-extracting only
+/// what is broken, and return the report afterwards.
+#[cfg(target_os = "android")]
+#[tauri::command]
+pub async fn repair_environment(
+    app: AppHandle,
+) -> Result<crate::android::proot::EnvironmentReport, String> {
+    crate::android::proot::repair_environment(app).await
+}
+
+#[cfg(not(target_os = "android"))]
+#[tauri::command]
+pub async fn repair_environment(_app: AppHandle) -> Result<(), String> {
+    Err("当前平台没有 proot 运行环境".to_string())
+}
+
+/// Run a trivial command under proot and explain common failures: seccomp
+/// denials, a missing loader, SELinux exec restrictions, 32-bit devices.
+/// `None` on platforms without proot.
+#[cfg(target_os = "android")]
+#[tauri::command]
+pub async fn run_environment_diagnostics(
+    app: AppHandle,
+) -> Result<Option<crate::android::proot::DiagnosticsReport>, String> {
+    crate::android::proot::run_environment_diagnostics(app)
+        .await
+        .map(Some)
+}
+
+#[cfg(not(target_os = "android"))]
+#[tauri::command]
+pub async fn run_environment_diagnostics(_app: AppHandle) -> Result<Option<()>, String> {
+    Ok(None)
+}
+
+/// Compare the published asset manifest with what is installed, without
+/// downloading any archive.
+#[cfg(target_os = "android")]
+#[tauri::command]
+pub async fn check_proot_updates(
+    app: AppHandle,
+) -> Result<Option<crate::android::proot::ProotUpdateInfo>, String> {
+    crate::android::proot::check_proot_updates(app)
+        .await
+        .map(Some)
+}
+
+#[cfg(not(target_os = "android"))]
+#[tauri::command]
+pub async fn check_proot_updates(_app: AppHandle) -> Result<Option<()>, String> {
+    Ok(None)
+}
+
+/// Download and apply only the archives that changed since the last update.
+#[cfg(target_os = "android")]
+#[tauri::command]
+pub async fn apply_proot_updates(app: AppHandle) -> Result<(), String> {
+    crate::android::proot::apply_proot_updates(app).await
+}
+
+#[cfg(not(target_os = "android"))]
+#[tauri::command]
+pub async fn apply_proot_updates(_app: AppHandle) -> Result<(), String> {
+    Ok(())
+}
+
+#[cfg(target_os = "android")]
+#[tauri::command]
+pub async fn check_proot_status(app: AppHandle) -> Result<bool, String> {
+    crate::android::proot::check_proot_status(app).await
+}
+
+#[cfg(not(target_os = "android"))]
+#[tauri::command]
+pub async fn check_proot_status(_app: AppHandle) -> Result<bool, String> {
+    Ok(true)
+}
+
+#[cfg(target_os = "android")]
+#[tauri::command]
+pub async fn download_proot_assets(app: AppHandle) -> Result<(), String> {
+    crate::android::proot::download_and_prepare_proot(app).await
+}
+
+#[cfg(not(target_os = "android"))]
+#[tauri::command]
+pub async fn download_proot_assets(_app: AppHandle) -> Result<(), String> {
+    Ok(())
+}
+
+#[cfg(target_os = "android")]
+#[tauri::command]
+pub fn pause_proot_download() -> Result<(), String> {
+    crate::android::proot::pause_proot_download()
+}
+
+#[cfg(not(target_os = "android"))]
+#[tauri::command]
+pub fn pause_proot_download() -> Result<(), String> {
+    Ok(())
+}
+
+#[cfg(target_os = "android")]
+#[tauri::command]
+pub fn resume_proot_download() -> Result<(), String> {
+    crate::android::proot::resume_proot_download()
+}
+
+#[cfg(not(target_os = "android"))]
+#[tauri::command]
+pub fn resume_proot_download() -> Result<(), String> {
+    Ok(())
+}
+
+#[cfg(target_os = "android")]
+#[tauri::command]
+pub fn cancel_proot_download() -> Result<(), String> {
+    crate::android::proot::cancel_proot_download()
+}
+
+#[cfg(not(target_os = "android"))]
+#[tauri::command]
+pub fn cancel_proot_download() -> Result<(), String> {
+    Ok(())
+}
@@ -0,0 +1,248 @@
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::Command;
+use tokio::sync::oneshot;
+use uuid::Uuid;
+
+const EVENT_EXEC_OUTPUT: &str = "truidide://exec/output";
+const MAX_CAPTURED_BYTES: usize = 1024 * 1024;
+
+static RUNNING: OnceCell<Mutex<HashMap<String, oneshot::Sender<()>>>> = OnceCell::new();
+
+fn running_map() -> &'static Mutex<HashMap<String, oneshot::Sender<()>>> {
+    RUNNING.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunCommandArgs {
+    /// Optional caller-chosen id, so output events can be matched before the command returns.
+    #[serde(default)]
+    pub run_id: Option<String>,
+    /// Working directory; a guest path inside proot on Android.
+    pub cwd: String,
+    pub program: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunCommandResult {
+    pub run_id: String,
+    pub exit_code: Option<i32>,
+    pub success: bool,
+    pub timed_out: bool,
+    pub cancelled: bool,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExecOutputPayload<'a> {
+    run_id: &'a str,
+    stream: OutputStream,
+    data: &'a str,
+}
+
+/// Build a command that runs `program` in `cwd`, inside proot on Android.
+#[cfg(target_os = "android")]
+pub fn build_command(app: &AppHandle, cwd: &str, program: &str) -> Result<Command, String> {
+    let env = crate::android::proot::prepare_proot_env(app)?;
+    let guest_cwd = if cwd.trim().starts_with('/') {
+        cwd.trim().to_string()
+    } else {
+        "/root".to_string()
+    };
+    let mut command = crate::android::proot::proot_command(&env, &guest_cwd);
+    command.arg(program);
+    Ok(Command::from(command))
+}
+
+/// Build a command that runs `program` in `cwd`, inside proot on Android.
+#[cfg(not(target_os = "android"))]
+pub fn build_command(_app: &AppHandle, cwd: &str, program: &str) -> Result<Command, String> {
+    let cwd_path = std::path::PathBuf::from(cwd);
+    if !cwd_path.is_dir() {
+        return Err("工作目录不存在或不是目录".into());
+    }
+    let mut command = Command::new(program);
+    command.current_dir(cwd_path);
+    Ok(command)
+}
+
+// Forward a child pipe line by line to `on_line` while keeping a bounded copy.
+async fn pump<R, F>(reader: R, mut on_line: F) -> String
+where
+    R: AsyncRead + Unpin,
+    F: FnMut(&str),
+{
+    let mut reader = BufReader::new(reader);
+    let mut captured = String::new();
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        match reader.read_until(b'\n', &mut line).await {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {
+                let text = String::from_utf8_lossy(&line);
+                on_line(&text);
+                if captured.len() + text.len() <= MAX_CAPTURED_BYTES {
+                    captured.push_str(&text);
+                }
+            }
+        }
+    }
+    captured
+}
+
+/// Run `args` to completion, reporting every output line through `on_output`.
+///
+/// Shared by `run_command` and any backend feature that needs captured output
+/// without a visible terminal.
+pub async fn execute<F>(
+    app: &AppHandle,
+    args: RunCommandArgs,
+    on_output: F,
+) -> Result<RunCommandResult, String>
+where
+    F: Fn(OutputStream, &str) + Send + Sync + 'static,
+{
+    let run_id = args
+        .run_id
+        .clone()
+        .filter(|id| !id.trim().is_empty())
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+    let mut command = build_command(app, &args.cwd, &args.program)?;
+    command
+        .args(&args.args)
+        .envs(&args.env)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("启动命令 {} 失败: {e}", args.program))?;
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| "无法获取标准输出".to_string())?;
+    let stderr = child
+        .stderr
+        .take()
+        .ok_or_else(|| "无法获取标准错误".to_string())?;
+
+    let on_output = Arc::new(on_output);
+    let stdout_sink = on_output.clone();
+    let stdout_task = tokio::spawn(pump(stdout, move |line| {
+        stdout_sink(OutputStream::Stdout, line)
+    }));
+    let stderr_sink = on_output.clone();
+    let stderr_task = tokio::spawn(pump(stderr, move |line| {
+        stderr_sink(OutputStream::Stderr, line)
+    }));
+
+    let (cancel_tx, cancel_rx) = oneshot::channel::<()>();
+    running_map()
+        .lock()
+        .map_err(|e| format!("锁错误: {e}"))?
+        .insert(run_id.clone(), cancel_tx);
+
+    let timeout = async {
+        match args.timeout_secs {
+            Some(secs) => tokio::time::sleep(Duration::from_secs(secs)).await,
+            None => std::future::pending().await,
+        }
+    };
+
+    let mut timed_out = false;
+    let mut cancelled = false;
+    let status = tokio::select! {
+        status = child.wait() => status.ok(),
+        _ = timeout => {
+            timed_out = true;
+            let _ = child.kill().await;
+            None
+        }
+        _ = cancel_rx => {
+            cancelled = true;
+            let _ = child.kill().await;
+            None
+        }
+    };
+
+    if let Ok(mut running) = running_map().lock() {
+        running.remove(&run_id);
+    }
+
+    let stdout = stdout_task.await.unwrap_or_default();
+    let stderr = stderr_task.await.unwrap_or_default();
+    let exit_code = status.and_then(|status| status.code());
+
+    Ok(RunCommandResult {
+        run_id,
+        exit_code,
+        success: status.is_some_and(|status| status.success()),
+        timed_out,
+        cancelled,
+        stdout,
+        stderr,
+    })
+}
+
+#[tauri::command]
+pub async fn run_command(app: AppHandle, args: RunCommandArgs) -> Result<RunCommandResult, String> {
+    let mut args = args;
+    let run_id = args
+        .run_id
+        .clone()
+        .filter(|id| !id.trim().is_empty())
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    args.run_id = Some(run_id.clone());
+
+    let emitter = app.clone();
+    execute(&app, args, move |stream, data| {
+        let _ = emitter.emit(
+            EVENT_EXEC_OUTPUT,
+            ExecOutputPayload {
+                run_id: &run_id,
+                stream,
+                data,
+            },
+        );
+    })
+    .await
+}
+
+#[tauri::command]
+pub fn cancel_command(run_id: String) -> Result<(), String> {
+    let cancel_tx = running_map()
+        .lock()
+        .map_err(|e| format!("锁错误: {e}"))?
+        .remove(&run_id)
+        .ok_or_else(|| format!("未找到正在运行的命令 {run_id}"))?;
+    let _ = cancel_tx.send(());
+    Ok(())
+}
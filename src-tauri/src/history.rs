@@ -0,0 +1,231 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use tauri::path::BaseDirectory;
+use tauri::{AppHandle, Emitter, Manager};
+
+const HISTORY_FILENAME: &str = "command-history.json";
+const EVENT_HISTORY_UPDATED: &str = "truidide://history/updated";
+const MAX_ENTRIES_PER_PROJECT: usize = 1000;
+const DEFAULT_LIMIT: usize = 200;
+
+static HISTORY: OnceCell<Mutex<HashMap<String, Vec<HistoryEntry>>>> = OnceCell::new();
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry {
+    pub command: String,
+    pub timestamp_secs: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct HistoryUpdatedPayload<'a> {
+    project_path: &'a str,
+    entry: &'a HistoryEntry,
+}
+
+/// Reconstructs command lines from raw terminal input.
+///
+/// Editing keys that the shell interprets on its own (arrows, tab completion,
+/// history recall) make the typed bytes diverge from what actually runs, so a
+/// line touched by them is discarded instead of being recorded wrongly.
+#[derive(Default)]
+pub struct CommandLineTracker {
+    line: String,
+    pending: Vec<u8>,
+    uncertain: bool,
+    in_escape: bool,
+}
+
+impl CommandLineTracker {
+    /// Forget the line typed so far: input arrived that bypassed the editor.
+    pub fn invalidate(&mut self) {
+        self.uncertain = true;
+    }
+
+    /// Feed input bytes and return every command line completed by them.
+    pub fn feed(&mut self, data: &[u8]) -> Vec<String> {
+        let mut completed = Vec::new();
+        for &byte in data {
+            if self.in_escape {
+                // CSI/SS3 sequences end with a byte in the 0x40..=0x7e range.
+                if byte != b'[' && byte != b'O' && (0x40..=0x7e).contains(&byte) {
+                    self.in_escape = false;
+                }
+                continue;
+            }
+
+            match byte {
+                b'\r' | b'\n' => {
+                    let line = std::mem::take(&mut self.line);
+                    let uncertain = std::mem::take(&mut self.uncertain);
+                    self.pending.clear();
+                    let command = line.trim();
+                    if !uncertain && !command.is_empty() {
+                        completed.push(command.to_string());
+                    }
+                }
+                0x1b => {
+                    self.in_escape = true;
+                    self.uncertain = true;
+                }
+                // Backspace / DEL
+                0x7f | 0x08 => {
+                    self.pending.clear();
+                    self.line.pop();
+                }
+                // Ctrl-C, Ctrl-U: the line is abandoned.
+                0x03 | 0x15 => {
+                    self.line.clear();
+                    self.pending.clear();
+                    self.uncertain = false;
+                }
+                b'\t' => self.uncertain = true,
+                byte if byte < 0x20 => self.uncertain = true,
+                byte => {
+                    self.pending.push(byte);
+                    if let Ok(text) = std::str::from_utf8(&self.pending) {
+                        self.line.push_str(text);
+                        self.pending.clear();
+                    } else if self.pending.len() >= 4 {
+                        self.pending.clear();
+                    }
+                }
+            }
+        }
+        completed
+    }
+}
+
+fn history_path(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .resolve(HISTORY_FILENAME, BaseDirectory::AppData)
+        .map_err(|e| e.to_string())
+}
+
+fn history_map(app: &AppHandle) -> &'static Mutex<HashMap<String, Vec<HistoryEntry>>> {
+    HISTORY.get_or_init(|| {
+        let loaded = history_path(app)
+            .ok()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default();
+        Mutex::new(loaded)
+    })
+}
+
+fn persist(app: &AppHandle, history: &HashMap<String, Vec<HistoryEntry>>) -> Result<(), String> {
+    let path = history_path(app)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("创建历史记录目录失败: {e}"))?;
+    }
+    let data = serde_json::to_string(history).map_err(|e| format!("序列化命令历史失败: {e}"))?;
+    fs::write(&path, data).map_err(|e| format!("保存命令历史失败: {e}"))
+}
+
+/// Append a command to the history of `project_path` and broadcast it.
+pub fn record_command(app: &AppHandle, project_path: &str, session_id: &str, command: &str) {
+    let entry = HistoryEntry {
+        command: command.to_string(),
+        timestamp_secs: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default(),
+        session_id: Some(session_id.to_string()),
+    };
+
+    let result = {
+        let Ok(mut history) = history_map(app).lock() else {
+            return;
+        };
+        let entries = history.entry(project_path.to_string()).or_default();
+        // Collapse immediate repeats, like `HISTCONTROL=ignoredups`.
+        if entries
+            .last()
+            .is_some_and(|last| last.command == entry.command)
+        {
+            entries.pop();
+        }
+        entries.push(entry.clone());
+        if entries.len() > MAX_ENTRIES_PER_PROJECT {
+            let excess = entries.len() - MAX_ENTRIES_PER_PROJECT;
+            entries.drain(..excess);
+        }
+        persist(app, &history)
+    };
+    if let Err(err) = result {
+        eprintln!("[truidide::history] {}", err);
+    }
+
+    let _ = app.emit(
+        EVENT_HISTORY_UPDATED,
+        HistoryUpdatedPayload {
+            project_path,
+            entry: &entry,
+        },
+    );
+}
+
+fn query_history(
+    app: &AppHandle,
+    project_path: &str,
+    query: Option<&str>,
+    limit: Option<usize>,
+) -> Result<Vec<HistoryEntry>, String> {
+    let history = history_map(app)
+        .lock()
+        .map_err(|e| format!("锁错误: {e}"))?;
+    let needle = query.map(str::to_lowercase);
+    // Most recent first.
+    Ok(history
+        .get(project_path)
+        .map(|entries| {
+            entries
+                .iter()
+                .rev()
+                .filter(|entry| match &needle {
+                    Some(needle) => entry.command.to_lowercase().contains(needle),
+                    None => true,
+                })
+                .take(limit.unwrap_or(DEFAULT_LIMIT))
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+#[tauri::command]
+pub fn get_command_history(
+    app: AppHandle,
+    project_path: String,
+    limit: Option<usize>,
+) -> Result<Vec<HistoryEntry>, String> {
+    query_history(&app, &project_path, None, limit)
+}
+
+#[tauri::command]
+pub fn search_command_history(
+    app: AppHandle,
+    project_path: String,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<HistoryEntry>, String> {
+    query_history(&app, &project_path, Some(query.trim()), limit)
+}
+
+#[tauri::command]
+pub fn clear_command_history(app: AppHandle, project_path: String) -> Result<(), String> {
+    let mut history = history_map(&app)
+        .lock()
+        .map_err(|e| format!("锁错误: {e}"))?;
+    history.remove(&project_path);
+    persist(&app, &history)
+}
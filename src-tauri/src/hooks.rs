@@ -0,0 +1,374 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::path::BaseDirectory;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::process::Command;
+
+use crate::plugins::PluginHost;
+
+const HOOKS_FILENAME: &str = "hooks.json";
+const EVENT_HOOK_FINISHED: &str = "truidide://hooks/finished";
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+const MAX_CAPTURED_OUTPUT: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HookEvent {
+    ProjectOpen,
+    FileSave,
+    TaskFinish,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HookDefinition {
+    pub id: String,
+    pub event: HookEvent,
+    /// Glob matched against the saved file path (only for `file-save`).
+    #[serde(default)]
+    pub glob: Option<String>,
+    /// Program to execute. Must be listed in `allowedPrograms`, unless it is
+    /// a tool of `pluginId`.
+    pub program: String,
+    /// Run `program` from this plugin's directory with the permissions the
+    /// user granted the plugin, instead of looking it up in the environment.
+    #[serde(default)]
+    pub plugin_id: Option<String>,
+    /// Arguments; `${project}`, `${file}` and `${task}` are substituted.
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HooksConfig {
+    /// Programs that hooks are allowed to execute, matched by file name.
+    #[serde(default)]
+    pub allowed_programs: Vec<String>,
+    #[serde(default)]
+    pub hooks: Vec<HookDefinition>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HookContext {
+    #[serde(default)]
+    pub project_path: Option<String>,
+    #[serde(default)]
+    pub file_path: Option<String>,
+    #[serde(default)]
+    pub task_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HookRunResult {
+    pub hook_id: String,
+    pub event: HookEvent,
+    pub success: bool,
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    pub stdout: String,
+    pub stderr: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+fn hooks_config_path(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .resolve(HOOKS_FILENAME, BaseDirectory::AppData)
+        .map_err(|e| e.to_string())
+}
+
+fn load_hooks_config(app: &AppHandle) -> Result<HooksConfig, String> {
+    let path = hooks_config_path(app)?;
+    if !path.exists() {
+        return Ok(HooksConfig::default());
+    }
+    let data = fs::read_to_string(&path).map_err(|e| format!("读取钩子配置失败: {e}"))?;
+    serde_json::from_str(&data).map_err(|e| format!("解析钩子配置失败: {e}"))
+}
+
+fn validate_hooks_config(config: &HooksConfig) -> Result<(), String> {
+    for hook in &config.hooks {
+        if hook.id.trim().is_empty() {
+            return Err("钩子标识不能为空".into());
+        }
+        if hook.program.trim().is_empty() {
+            return Err(format!("钩子 {} 未指定要执行的程序", hook.id));
+        }
+        if hook
+            .plugin_id
+            .as_deref()
+            .is_some_and(|plugin_id| plugin_id.trim().is_empty())
+        {
+            return Err(format!("钩子 {} 的插件标识不能为空", hook.id));
+        }
+        if let Some(pattern) = &hook.glob {
+            glob::Pattern::new(pattern)
+                .map_err(|e| format!("钩子 {} 的 glob 无效: {e}", hook.id))?;
+        }
+    }
+    Ok(())
+}
+
+fn is_program_allowed(config: &HooksConfig, hook: &HookDefinition) -> bool {
+    // Plugin tools are covered by the permissions granted on install.
+    if hook.plugin_id.is_some() {
+        return true;
+    }
+    let program = hook.program.as_str();
+    let name = PathBuf::from(program)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| program.to_string());
+    config
+        .allowed_programs
+        .iter()
+        .any(|allowed| allowed == program || *allowed == name)
+}
+
+fn hook_matches(hook: &HookDefinition, event: HookEvent, context: &HookContext) -> bool {
+    if !hook.enabled || hook.event != event {
+        return false;
+    }
+
+    match (&hook.glob, event) {
+        (Some(pattern), HookEvent::FileSave) => {
+            let Some(file_path) = context.file_path.as_deref() else {
+                return false;
+            };
+            let Ok(pattern) = glob::Pattern::new(pattern) else {
+                return false;
+            };
+            let file_name = PathBuf::from(file_path)
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            pattern.matches(file_path) || pattern.matches(&file_name)
+        }
+        _ => true,
+    }
+}
+
+fn substitute(raw: &str, context: &HookContext) -> String {
+    raw.replace("${project}", context.project_path.as_deref().unwrap_or(""))
+        .replace("${file}", context.file_path.as_deref().unwrap_or(""))
+        .replace("${task}", context.task_name.as_deref().unwrap_or(""))
+}
+
+fn truncate_output(bytes: &[u8]) -> String {
+    let slice = if bytes.len() > MAX_CAPTURED_OUTPUT {
+        &bytes[bytes.len() - MAX_CAPTURED_OUTPUT..]
+    } else {
+        bytes
+    };
+    String::from_utf8_lossy(slice).into_owned()
+}
+
+#[cfg(target_os = "android")]
+fn build_hook_command(
+    app: &AppHandle,
+    hook: &HookDefinition,
+    context: &HookContext,
+) -> Result<Command, String> {
+    let env = crate::android::proot::prepare_proot_env(app)?;
+    let cwd = context
+        .project_path
+        .clone()
+        .filter(|path| path.starts_with('/'))
+        .unwrap_or_else(|| "/root".to_string());
+    let mut command = crate::android::proot::proot_command(&env, &cwd);
+    command.arg(&hook.program);
+    Ok(Command::from(command))
+}
+
+#[cfg(not(target_os = "android"))]
+fn build_hook_command(
+    _app: &AppHandle,
+    hook: &HookDefinition,
+    context: &HookContext,
+) -> Result<Command, String> {
+    let mut command = Command::new(&hook.program);
+    if let Some(project_path) = context.project_path.as_deref() {
+        let cwd = PathBuf::from(project_path);
+        if cwd.is_dir() {
+            command.current_dir(cwd);
+        }
+    }
+    Ok(command)
+}
+
+async fn run_hook(
+    app: &AppHandle,
+    hook: &HookDefinition,
+    event: HookEvent,
+    context: &HookContext,
+) -> Result<HookRunResult, String> {
+    let mut command = match hook.plugin_id.as_deref() {
+        Some(plugin_id) => {
+            PluginHost::obtain(app)?
+                .plugin_tool_command(plugin_id, &hook.program)
+                .await?
+        }
+        None => build_hook_command(app, hook, context)?,
+    };
+    command.args(hook.args.iter().map(|arg| substitute(arg, context)));
+    for (key, value) in &hook.env {
+        command.env(key, substitute(value, context));
+    }
+    command.env("TRUIDIDE_HOOK_ID", &hook.id);
+    command.env(
+        "TRUIDIDE_HOOK_EVENT",
+        serde_json::to_value(event)
+            .ok()
+            .and_then(|value| value.as_str().map(str::to_string))
+            .unwrap_or_default(),
+    );
+    if let Some(project_path) = &context.project_path {
+        command.env("TRUIDIDE_PROJECT_PATH", project_path);
+    }
+    if let Some(file_path) = &context.file_path {
+        command.env("TRUIDIDE_FILE_PATH", file_path);
+    }
+    if let Some(task_name) = &context.task_name {
+        command.env("TRUIDIDE_TASK_NAME", task_name);
+    }
+
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    let child = command
+        .spawn()
+        .map_err(|e| format!("启动钩子 {} 失败: {e}", hook.id))?;
+
+    let timeout = Duration::from_secs(hook.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS));
+    match tokio::time::timeout(timeout, child.wait_with_output()).await {
+        Ok(Ok(output)) => Ok(HookRunResult {
+            hook_id: hook.id.clone(),
+            event,
+            success: output.status.success(),
+            exit_code: output.status.code(),
+            timed_out: false,
+            stdout: truncate_output(&output.stdout),
+            stderr: truncate_output(&output.stderr),
+            error: None,
+        }),
+        Ok(Err(err)) => Err(format!("等待钩子 {} 结束失败: {err}", hook.id)),
+        // Dropping the future drops the child, which kills it.
+        Err(_) => Ok(HookRunResult {
+            hook_id: hook.id.clone(),
+            event,
+            success: false,
+            exit_code: None,
+            timed_out: true,
+            stdout: String::new(),
+            stderr: String::new(),
+            error: Some(format!("钩子执行超时 ({} 秒)", timeout.as_secs())),
+        }),
+    }
+}
+
+/// Run every enabled hook registered for `event` and report the results.
+pub async fn fire_event(
+    app: &AppHandle,
+    event: HookEvent,
+    context: &HookContext,
+) -> Result<Vec<HookRunResult>, String> {
+    let config = load_hooks_config(app)?;
+    let mut results = Vec::new();
+
+    for hook in config
+        .hooks
+        .iter()
+        .filter(|hook| hook_matches(hook, event, context))
+    {
+        let result = if !is_program_allowed(&config, hook) {
+            HookRunResult {
+                hook_id: hook.id.clone(),
+                event,
+                success: false,
+                exit_code: None,
+                timed_out: false,
+                stdout: String::new(),
+                stderr: String::new(),
+                error: Some(format!("程序 {} 未在白名单中", hook.program)),
+            }
+        } else {
+            match run_hook(app, hook, event, context).await {
+                Ok(result) => result,
+                Err(err) => HookRunResult {
+                    hook_id: hook.id.clone(),
+                    event,
+                    success: false,
+                    exit_code: None,
+                    timed_out: false,
+                    stdout: String::new(),
+                    stderr: String::new(),
+                    error: Some(err),
+                },
+            }
+        };
+
+        if let Err(err) = app.emit(EVENT_HOOK_FINISHED, &result) {
+            eprintln!("[truidide::hooks] 广播钩子结果失败: {}", err);
+        }
+        results.push(result);
+    }
+
+    Ok(results)
+}
+
+/// Fire `event` in the background, for callers that don't wait for hooks.
+pub fn spawn_event(app: &AppHandle, event: HookEvent, context: HookContext) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(err) = fire_event(&app, event, &context).await {
+            eprintln!("[truidide::hooks] 执行钩子失败: {}", err);
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_hooks_config(app: AppHandle) -> Result<HooksConfig, String> {
+    load_hooks_config(&app)
+}
+
+#[tauri::command]
+pub fn save_hooks_config(app: AppHandle, config: HooksConfig) -> Result<(), String> {
+    validate_hooks_config(&config)?;
+    let path = hooks_config_path(&app)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("创建配置目录失败: {e}"))?;
+    }
+    let data =
+        serde_json::to_string_pretty(&config).map_err(|e| format!("序列化钩子配置失败: {e}"))?;
+    fs::write(&path, data).map_err(|e| format!("保存钩子配置失败: {e}"))
+}
+
+#[tauri::command]
+pub async fn trigger_hooks(
+    app: AppHandle,
+    event: HookEvent,
+    context: HookContext,
+) -> Result<Vec<HookRunResult>, String> {
+    fire_event(&app, event, &context).await
+}
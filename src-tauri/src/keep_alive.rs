@@ -0,0 +1,64 @@
+use once_cell::sync::OnceCell;
+use tauri::AppHandle;
+use tauri_plugin_keep_alive::{KeepAliveExt, ServiceStatus, StartServiceRequest};
+use tokio::sync::watch;
+
+// Live terminal and LSP sessions. A task started by `init` follows the count
+// and runs the Android foreground service while it is non-zero.
+static ACTIVE_SESSIONS: OnceCell<watch::Sender<usize>> = OnceCell::new();
+
+fn active_sessions() -> &'static watch::Sender<usize> {
+    ACTIVE_SESSIONS.get_or_init(|| watch::channel(0).0)
+}
+
+/// Held by every running session; the foreground service stops once the last
+/// guard is dropped.
+pub struct KeepAliveGuard(());
+
+impl KeepAliveGuard {
+    pub fn acquire() -> Self {
+        active_sessions().send_modify(|count| *count += 1);
+        Self(())
+    }
+}
+
+impl Drop for KeepAliveGuard {
+    fn drop(&mut self) {
+        active_sessions().send_modify(|count| *count = count.saturating_sub(1));
+    }
+}
+
+/// Start following the session count.
+pub fn init(app: &AppHandle) {
+    let app = app.clone();
+    let mut rx = active_sessions().subscribe();
+    tauri::async_runtime::spawn(async move {
+        let mut running = false;
+        // Only the latest count matters, bursts of changes collapse into one update.
+        while rx.changed().await.is_ok() {
+            let count = *rx.borrow_and_update();
+            if count == 0 && !running {
+                continue;
+            }
+            let app = app.clone();
+            match tauri::async_runtime::spawn_blocking(move || update_service(&app, count)).await {
+                Ok(Ok(status)) => running = status.running,
+                Ok(Err(err)) => eprintln!("[truidide::keep_alive] 更新前台服务失败: {err}"),
+                Err(err) => eprintln!("[truidide::keep_alive] 后台任务执行失败: {err}"),
+            }
+        }
+    });
+}
+
+fn update_service(app: &AppHandle, count: usize) -> Result<ServiceStatus, String> {
+    let keep_alive = app.keep_alive();
+    let result = if count == 0 {
+        keep_alive.stop()
+    } else {
+        keep_alive.start(StartServiceRequest {
+            title: "TruidIDE".to_string(),
+            text: format!("{count} 个终端或语言服务器会话正在运行"),
+        })
+    };
+    result.map_err(|e| e.to_string())
+}
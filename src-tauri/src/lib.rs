@@ -1,23 +1,26 @@
+mod dev_packs;
+mod environment;
+mod exec;
 mod fs_utils;
+mod history;
+mod hooks;
+mod keep_alive;
+mod notifications;
+mod open_with;
+mod packages;
 mod plugins;
+mod port_forward;
+mod process_stats;
 mod projects;
+mod sandbox;
+mod ssh;
+mod storage;
+mod tasks;
 mod terminal;
 
 #[cfg(target_os = "android")]
 mod android;
 
-#[cfg(target_os = "android")]
-#[tauri::command]
-async fn check_proot_status(app: tauri::AppHandle) -> Result<bool, String> {
-    android::proot::check_proot_status(app).await
-}
-
-#[cfg(target_os = "android")]
-#[tauri::command]
-async fn download_proot_assets(app: tauri::AppHandle) -> Result<(), String> {
-    android::proot::download_and_prepare_proot(app).await
-}
-
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
@@ -26,14 +29,32 @@ pub fn run() {
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_os::init())
         .plugin(tauri_plugin_file_picker::init())
+        .plugin(tauri_plugin_keep_alive::init())
+        .plugin(tauri_plugin_toast::init())
         .plugin(tauri_plugin_safe_area_insets_css::init())
+        .register_asynchronous_uri_scheme_protocol(
+            plugins::web_extension::PLUGIN_PROTOCOL,
+            |ctx, request, responder| {
+                let app = ctx.app_handle().clone();
+                tauri::async_runtime::spawn(async move {
+                    let response = plugins::web_extension::serve_plugin_asset(&app, &request).await;
+                    responder.respond(response);
+                });
+            },
+        )
         .setup(|app| {
             let app_handle = app.handle();
+            keep_alive::init(&app_handle);
+            notifications::init(&app_handle);
+            terminal::prune_saved_buffers(&app_handle);
 
             // 不再自动下载，让用户手动触发
 
             match plugins::PluginHost::obtain(&app_handle) {
                 Ok(host) => {
+                    if let Err(err) = plugins::watch_user_plugins(&host) {
+                        eprintln!("[truidide::plugins] 无法自动重新加载插件: {}", err);
+                    }
                     let refresh_host = host.clone();
                     tauri::async_runtime::spawn(async move {
                         if let Err(err) = refresh_host.reload_registry().await {
@@ -51,6 +72,7 @@ pub fn run() {
             projects::get_projects_root,
             projects::list_projects,
             projects::list_project_tree,
+            projects::open_project,
             projects::read_project_file,
             projects::save_project_file,
             projects::create_project_entry,
@@ -61,24 +83,127 @@ pub fn run() {
             projects::resolve_preview_entry,
             projects::create_project,
             terminal::start_terminal_session,
+            terminal::start_ssh_session,
+            ssh::probe_ssh_host_key,
+            ssh::trust_ssh_host_key,
+            ssh::forget_ssh_host_key,
+            port_forward::create_port_forward,
+            port_forward::list_port_forwards,
+            port_forward::stop_port_forward,
             terminal::list_terminal_sessions,
+            terminal::list_all_terminal_sessions,
             terminal::send_terminal_input,
+            terminal::paste_terminal_input,
             terminal::attach_terminal_session,
             terminal::detach_terminal_session,
+            terminal::clear_terminal_buffer,
+            terminal::search_terminal_buffer,
+            terminal::export_terminal_buffer,
+            terminal::ack_terminal_output,
+            terminal::pause_terminal_output,
+            terminal::resume_terminal_output,
+            terminal::start_terminal_recording,
+            terminal::stop_terminal_recording,
             terminal::resize_terminal,
+            terminal::send_terminal_signal,
             terminal::set_terminal_session_title,
             terminal::stop_terminal_session,
+            terminal::get_terminal_session_stats,
+            terminal::get_terminal_settings,
+            sandbox::get_project_sandbox,
+            sandbox::set_project_sandbox,
+            terminal::save_terminal_settings,
+            terminal::take_saved_terminal_buffer,
+            history::get_command_history,
+            history::search_command_history,
+            history::clear_command_history,
+            hooks::get_hooks_config,
+            hooks::save_hooks_config,
+            hooks::trigger_hooks,
+            exec::run_command,
+            exec::cancel_command,
+            tasks::list_tasks,
+            tasks::run_task,
+            tasks::stop_task,
+            tasks::get_run_configurations,
+            tasks::run_configuration,
+            dev_packs::install_dev_pack,
+            packages::search_packages,
+            packages::list_installed,
+            packages::install_packages,
+            packages::remove_packages,
             plugins::api::list_plugins,
+            plugins::api::set_plugin_locale,
             plugins::api::refresh_plugins,
+            plugins::api::refresh_plugin,
+            plugins::api::set_plugin_enabled,
+            plugins::api::get_plugin_settings,
+            plugins::api::set_plugin_settings,
+            plugins::api::get_project_plugins,
+            plugins::api::set_project_plugins,
+            plugins::api::plugin_for_language,
+            plugins::api::plugins_for_language,
+            plugins::api::get_language_plugin_defaults,
+            plugins::api::set_language_plugin_default,
+            plugins::api::ensure_lsp_for_file,
             plugins::api::start_lsp_session,
             plugins::api::send_lsp_payload,
+            plugins::api::cancel_lsp_request,
+            plugins::api::list_lsp_sessions,
             plugins::api::stop_lsp_session,
+            plugins::api::get_lsp_trace_settings,
+            plugins::api::set_lsp_trace_settings,
+            plugins::api::get_lsp_session_log,
+            plugins::api::get_lsp_idle_settings,
+            plugins::api::set_lsp_idle_settings,
+            plugins::api::start_dap_session,
+            plugins::api::send_dap_payload,
+            plugins::api::stop_dap_session,
+            plugins::api::format_document,
+            plugins::api::lint_document,
+            plugins::api::list_snippets,
+            plugins::api::list_grammars,
+            plugins::api::get_plugin_asset,
+            plugins::api::run_current_file,
+            plugins::api::load_web_extension,
+            plugins::api::unload_web_extension,
+            plugins::api::plugin_broker_call,
+            plugins::api::validate_plugin_manifest,
             plugins::api::import_plugin,
             plugins::api::remove_plugin,
-            #[cfg(target_os = "android")]
-            check_proot_status,
-            #[cfg(target_os = "android")]
-            download_proot_assets
+            plugins::api::export_plugin,
+            plugins::api::search_marketplace,
+            plugins::api::get_plugin_listing,
+            plugins::api::install_plugin_from_url,
+            plugins::api::get_marketplace_settings,
+            plugins::api::set_marketplace_settings,
+            plugins::api::get_plugin_trust_settings,
+            plugins::api::set_unsigned_plugin_policy,
+            plugins::api::add_trusted_plugin_key,
+            plugins::api::remove_trusted_plugin_key,
+            environment::check_proot_status,
+            environment::download_proot_assets,
+            environment::pause_proot_download,
+            environment::resume_proot_download,
+            environment::cancel_proot_download,
+            environment::get_environment_status,
+            environment::get_rootfs_disk_usage,
+            environment::delete_rootfs,
+            environment::reset_rootfs,
+            environment::export_rootfs_snapshot,
+            environment::import_rootfs_snapshot,
+            environment::check_proot_updates,
+            environment::apply_proot_updates,
+            environment::get_proot_settings,
+            environment::set_proot_settings,
+            environment::get_environment_storage,
+            environment::migrate_environment_storage,
+            environment::verify_environment,
+            environment::repair_environment,
+            environment::run_environment_diagnostics,
+            storage::get_storage_breakdown,
+            storage::clean_caches,
+            open_with::receive_incoming_intent,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
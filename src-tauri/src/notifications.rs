@@ -0,0 +1,185 @@
+use std::time::Duration;
+
+use tauri::AppHandle;
+use tauri_plugin_toast::{NotifyRequest, ToastExt};
+
+// One id per kind of work, so a new run replaces the previous notification.
+#[cfg(target_os = "android")]
+const DOWNLOAD_NOTIFICATION_ID: i32 = 1;
+#[cfg(target_os = "android")]
+const EXPORT_NOTIFICATION_ID: i32 = 2;
+const TASK_NOTIFICATION_ID: i32 = 3;
+
+// Frontend routes handed back when a notification is tapped.
+#[cfg(target_os = "android")]
+const HOME_ROUTE: &str = "/";
+const WORKSPACE_ROUTE: &str = "/projects/current";
+
+/// Tasks finishing faster than this are assumed to be watched in the app.
+const LONG_TASK_THRESHOLD: Duration = Duration::from_secs(30);
+
+/// Mirror the rootfs download progress into a system notification.
+#[cfg(target_os = "android")]
+pub fn init(app: &AppHandle) {
+    use std::sync::Mutex;
+
+    use tauri::Listener;
+
+    use crate::android::proot::DownloadProgress;
+
+    let handle = app.clone();
+    // Last percentage posted, progress events arrive far more often than the
+    // notification needs to change.
+    let last_percentage = Mutex::new(None::<u8>);
+    app.listen("proot-download-progress", move |event| {
+        let Ok(progress) = serde_json::from_str::<DownloadProgress>(event.payload()) else {
+            return;
+        };
+        let request = match progress {
+            DownloadProgress::Downloading {
+                file, percentage, ..
+            } => {
+                if !percentage_changed(&last_percentage, percentage) {
+                    return;
+                }
+                ongoing_progress(format!("正在下载 {file}"), percentage)
+            }
+            DownloadProgress::Extracting { file, percentage } => {
+                if !percentage_changed(&last_percentage, percentage) {
+                    return;
+                }
+                ongoing_progress(format!("正在解压 {file}"), percentage)
+            }
+            DownloadProgress::Paused { file, .. } => NotifyRequest {
+                id: DOWNLOAD_NOTIFICATION_ID,
+                title: "运行环境下载已暂停".to_string(),
+                text: file,
+                deep_link: Some(HOME_ROUTE.to_string()),
+                ..Default::default()
+            },
+            DownloadProgress::Completed => NotifyRequest {
+                id: DOWNLOAD_NOTIFICATION_ID,
+                title: "运行环境已就绪".to_string(),
+                text: "proot 与 rootfs 已准备完成".to_string(),
+                deep_link: Some(HOME_ROUTE.to_string()),
+                ..Default::default()
+            },
+            DownloadProgress::Error { message } => NotifyRequest {
+                id: DOWNLOAD_NOTIFICATION_ID,
+                title: "运行环境准备失败".to_string(),
+                text: message,
+                deep_link: Some(HOME_ROUTE.to_string()),
+                ..Default::default()
+            },
+            DownloadProgress::Cancelled => {
+                *last_percentage.lock().unwrap() = None;
+                cancel(&handle, DOWNLOAD_NOTIFICATION_ID);
+                return;
+            }
+        };
+        if !request.ongoing {
+            *last_percentage.lock().unwrap() = None;
+        }
+        post(&handle, request);
+    });
+}
+
+#[cfg(not(target_os = "android"))]
+pub fn init(_app: &AppHandle) {}
+
+#[cfg(target_os = "android")]
+fn percentage_changed(last: &std::sync::Mutex<Option<u8>>, percentage: Option<u8>) -> bool {
+    let mut last = last.lock().unwrap();
+    if percentage.is_some() && *last == percentage {
+        return false;
+    }
+    *last = percentage;
+    true
+}
+
+#[cfg(target_os = "android")]
+fn ongoing_progress(text: String, percentage: Option<u8>) -> NotifyRequest {
+    use tauri_plugin_toast::NotificationProgress;
+
+    NotifyRequest {
+        id: DOWNLOAD_NOTIFICATION_ID,
+        title: "正在准备运行环境".to_string(),
+        text,
+        progress: Some(NotificationProgress {
+            current: percentage.unwrap_or(0).into(),
+            max: 100,
+            indeterminate: percentage.is_none(),
+        }),
+        ongoing: true,
+        deep_link: Some(HOME_ROUTE.to_string()),
+    }
+}
+
+/// Report the outcome of a rootfs snapshot export.
+#[cfg(target_os = "android")]
+pub fn export_finished(app: &AppHandle, result: &Result<u64, String>) {
+    let (title, text) = match result {
+        Ok(size) => (
+            "快照已导出".to_string(),
+            format!("共 {:.1} MB", *size as f64 / 1024.0 / 1024.0),
+        ),
+        Err(err) => ("导出快照失败".to_string(), err.clone()),
+    };
+    post(
+        app,
+        NotifyRequest {
+            id: EXPORT_NOTIFICATION_ID,
+            title,
+            text,
+            deep_link: Some(HOME_ROUTE.to_string()),
+            ..Default::default()
+        },
+    );
+}
+
+/// Report a finished task, unless it was short enough to be watched in the app.
+pub fn task_finished(app: &AppHandle, task_name: &str, success: bool, elapsed: Duration) {
+    if elapsed < LONG_TASK_THRESHOLD {
+        return;
+    }
+    let title = if success {
+        format!("任务 {task_name} 已完成")
+    } else {
+        format!("任务 {task_name} 失败")
+    };
+    post(
+        app,
+        NotifyRequest {
+            id: TASK_NOTIFICATION_ID,
+            title,
+            text: format!("耗时 {} 秒", elapsed.as_secs()),
+            deep_link: Some(WORKSPACE_ROUTE.to_string()),
+            ..Default::default()
+        },
+    );
+}
+
+// The plugin call blocks on the Android main thread, never make it from there.
+fn post(app: &AppHandle, request: NotifyRequest) {
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Err(err) = app.toast().notify(request) {
+            eprintln!("[truidide::notifications] 发送通知失败: {err}");
+        }
+    });
+}
+
+#[cfg(target_os = "android")]
+fn cancel(app: &AppHandle, id: i32) {
+    use tauri_plugin_toast::CancelNotificationRequest;
+
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Err(err) = app
+            .toast()
+            .cancel_notification(CancelNotificationRequest { id })
+        {
+            eprintln!("[truidide::notifications] 取消通知失败: {err}");
+        }
+    });
+}
@@ -0,0 +1,180 @@
+use tauri::AppHandle;
+
+/// Import the file or folder the app was opened with ("Open with" or share)
+/// and emit `truidide://open-with`. Does nothing when there is none.
+#[cfg(target_os = "android")]
+#[tauri::command]
+pub async fn receive_incoming_intent(app: AppHandle) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || android::receive(&app))
+        .await
+        .map_err(|e| format!("后台任务执行失败: {e}"))?
+}
+
+#[cfg(not(target_os = "android"))]
+#[tauri::command]
+pub async fn receive_incoming_intent(_app: AppHandle) -> Result<(), String> {
+    Ok(())
+}
+
+#[cfg(target_os = "android")]
+mod android {
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    use serde::Serialize;
+    use tauri::{AppHandle, Emitter, Url};
+    use tauri_plugin_file_picker::{
+        FilePickerExt, IncomingIntent, ListTreeUriRequest, ReadContentUriRequest,
+    };
+
+    use crate::android::proot::resolve_guest_path;
+    use crate::fs_utils::{copy_entry_recursive, normalize_entry_name};
+
+    /// Emitted with an [`OpenWithTarget`] once an incoming file or folder has
+    /// been imported.
+    const EVENT_OPEN_WITH: &str = "truidide://open-with";
+
+    /// Guest directory collecting single files opened from other apps.
+    const INBOX_DIR: &str = "/root/Inbox";
+    /// Guest directory imported folders are copied into, next to projects.
+    const PROJECTS_DIR: &str = "/root";
+
+    /// Where a file or folder opened from another app ended up.
+    #[derive(Debug, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    struct OpenWithTarget {
+        /// Folder to open as the workspace.
+        project_path: String,
+        /// File to focus inside it, `None` when a whole folder was opened.
+        file_path: Option<String>,
+    }
+
+    pub(super) fn receive(app: &AppHandle) -> Result<(), String> {
+        let incoming = app
+            .file_picker()
+            .take_incoming_intent()
+            .map_err(|e| format!("读取传入文件失败: {e}"))?
+            .incoming;
+        let Some(incoming) = incoming else {
+            return Ok(());
+        };
+
+        eprintln!("[truidide::open_with] 收到传入文件: {}", incoming.uri);
+        let target = if incoming.is_directory {
+            import_directory(app, &incoming)?
+        } else {
+            import_file(app, &incoming)?
+        };
+        app.emit(EVENT_OPEN_WITH, &target)
+            .map_err(|e| format!("通知前端打开文件失败: {e}"))
+    }
+
+    fn import_file(app: &AppHandle, incoming: &IncomingIntent) -> Result<OpenWithTarget, String> {
+        let inbox = resolve_guest_path(app, PROJECTS_DIR)?.join("Inbox");
+        fs::create_dir_all(&inbox).map_err(|e| format!("创建 Inbox 目录失败: {e}"))?;
+
+        let name = unique_name(&inbox, &entry_name(incoming.name.as_deref()));
+        let destination = inbox.join(&name);
+        match local_path(&incoming.uri) {
+            Some(source) => fs::copy(&source, &destination)
+                .map(|_| ())
+                .map_err(|e| format!("复制文件失败: {e}"))?,
+            None => copy_content_uri(app, &incoming.uri, &destination)?,
+        }
+
+        Ok(OpenWithTarget {
+            project_path: INBOX_DIR.to_string(),
+            file_path: Some(format!("{INBOX_DIR}/{name}")),
+        })
+    }
+
+    fn import_directory(
+        app: &AppHandle,
+        incoming: &IncomingIntent,
+    ) -> Result<OpenWithTarget, String> {
+        let projects = resolve_guest_path(app, PROJECTS_DIR)?;
+        let name = unique_name(&projects, &entry_name(incoming.name.as_deref()));
+        let destination = projects.join(&name);
+
+        let result = match local_path(&incoming.uri) {
+            Some(source) => copy_entry_recursive(&source, &destination),
+            None => copy_tree_uri(app, &incoming.uri, &destination),
+        };
+        if let Err(err) = result {
+            // Don't leave a half-imported folder behind.
+            let _ = fs::remove_dir_all(&destination);
+            return Err(err);
+        }
+
+        Ok(OpenWithTarget {
+            project_path: format!("{PROJECTS_DIR}/{name}"),
+            file_path: None,
+        })
+    }
+
+    fn copy_tree_uri(app: &AppHandle, uri: &str, destination: &Path) -> Result<(), String> {
+        fs::create_dir(destination).map_err(|e| format!("复制目录失败: {e}"))?;
+        let entries = app
+            .file_picker()
+            .list_tree_uri(ListTreeUriRequest {
+                uri: uri.to_string(),
+            })
+            .map_err(|e| format!("无法列出目录 ({uri}): {e}"))?
+            .entries;
+
+        for entry in entries {
+            let Ok(name) = normalize_entry_name(&entry.name) else {
+                eprintln!("[truidide::open_with] 跳过无法导入的文件名: {}", entry.name);
+                continue;
+            };
+            let path = destination.join(name);
+            if entry.is_directory {
+                copy_tree_uri(app, &entry.uri, &path)?;
+            } else {
+                copy_content_uri(app, &entry.uri, &path)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn copy_content_uri(app: &AppHandle, uri: &str, destination: &Path) -> Result<(), String> {
+        let response = app
+            .file_picker()
+            .read_content_uri(ReadContentUriRequest {
+                content_uri: uri.to_string(),
+                target_path: Some(destination.to_string_lossy().to_string()),
+            })
+            .map_err(|e| format!("无法读取 Content URI ({uri}): {e}"))?;
+        if !response.success {
+            return Err(format!("读取 Content URI 失败: {uri}"));
+        }
+        Ok(())
+    }
+
+    fn local_path(uri: &str) -> Option<PathBuf> {
+        Url::parse(uri)
+            .ok()
+            .filter(|url| url.scheme() == "file")
+            .and_then(|url| url.to_file_path().ok())
+    }
+
+    fn entry_name(name: Option<&str>) -> String {
+        name.and_then(|name| normalize_entry_name(name).ok())
+            .unwrap_or_else(|| "imported".to_string())
+    }
+
+    // Append a counter instead of overwriting an existing entry.
+    fn unique_name(dir: &Path, name: &str) -> String {
+        if !dir.join(name).exists() {
+            return name.to_string();
+        }
+        let (stem, extension) = match name.rsplit_once('.') {
+            Some((stem, extension)) if !stem.is_empty() => (stem, format!(".{extension}")),
+            _ => (name, String::new()),
+        };
+        (1..)
+            .map(|index| format!("{stem} ({index}){extension}"))
+            .find(|candidate| !dir.join(candidate).exists())
+            .expect("unbounded range always yields a free name")
+    }
+}
@@ -0,0 +1,301 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+use uuid::Uuid;
+
+use crate::dev_packs::home_dir;
+use crate::exec::{self, OutputStream, RunCommandArgs, RunCommandResult};
+
+const EVENT_PACKAGE_PROGRESS: &str = "truidide://packages/progress";
+const PACKAGE_TRANSACTION_TIMEOUT_SECS: u64 = 30 * 60;
+const PACKAGE_QUERY_TIMEOUT_SECS: u64 = 2 * 60;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageInfo {
+    pub name: String,
+    pub version: String,
+    pub repository: String,
+    pub description: String,
+    pub installed: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstalledPackage {
+    pub name: String,
+    pub version: String,
+    /// Installed on request rather than pulled in as a dependency.
+    pub explicit: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageTransactionArgs {
+    pub names: Vec<String>,
+    /// Optional caller-chosen id, so progress events can be matched before
+    /// the command returns; also accepted by `cancel_command`.
+    #[serde(default)]
+    pub operation_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PackageOperation {
+    Install,
+    Remove,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PackageProgressStatus {
+    Started,
+    Output,
+    Completed,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageProgress {
+    pub operation_id: String,
+    pub operation: PackageOperation,
+    pub status: PackageProgressStatus,
+    pub message: String,
+    /// From pacman's `(n/m)` counters; they restart for each phase
+    /// (download, check, install), so the value may go back down.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub percentage: Option<u8>,
+}
+
+// pacman names are lowercase alphanumerics plus `@._+-`, and may not start
+// with a hyphen, which would make them an option.
+fn validate_package_names(names: &[String]) -> Result<Vec<String>, String> {
+    let names: Vec<String> = names
+        .iter()
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect();
+    if names.is_empty() {
+        return Err("请指定软件包".into());
+    }
+    for name in &names {
+        let valid = !name.starts_with('-')
+            && name
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "@._+-".contains(c));
+        if !valid {
+            return Err(format!("无效的软件包名称: {name}"));
+        }
+    }
+    Ok(names)
+}
+
+async fn pacman(
+    app: &AppHandle,
+    run_id: Option<String>,
+    args: Vec<String>,
+    timeout_secs: u64,
+    on_output: impl Fn(OutputStream, &str) + Send + Sync + 'static,
+) -> Result<RunCommandResult, String> {
+    let mut env = HashMap::new();
+    // Keep the output parseable whatever the guest locale is.
+    env.insert("LC_ALL".to_string(), "C".to_string());
+    exec::execute(
+        app,
+        RunCommandArgs {
+            run_id,
+            cwd: home_dir(),
+            program: "pacman".to_string(),
+            args,
+            env,
+            timeout_secs: Some(timeout_secs),
+        },
+        on_output,
+    )
+    .await
+}
+
+async fn run_query(app: &AppHandle, args: &[&str]) -> Result<String, String> {
+    let outcome = pacman(
+        app,
+        None,
+        args.iter().map(|arg| arg.to_string()).collect(),
+        PACKAGE_QUERY_TIMEOUT_SECS,
+        |_, _| {},
+    )
+    .await?;
+    // pacman exits with 1 when nothing matches.
+    if outcome.success || (outcome.exit_code == Some(1) && outcome.stderr.trim().is_empty()) {
+        Ok(outcome.stdout)
+    } else {
+        Err(format!("pacman 执行失败: {}", outcome.stderr.trim()))
+    }
+}
+
+/// Parse `pacman -Ss` output: a `repo/name version [installed]` line
+/// followed by an indented description.
+fn parse_search_output(output: &str) -> Vec<PackageInfo> {
+    let mut packages: Vec<PackageInfo> = Vec::new();
+    for line in output.lines() {
+        if line.starts_with(char::is_whitespace) {
+            if let Some(package) = packages.last_mut() {
+                if !package.description.is_empty() {
+                    package.description.push(' ');
+                }
+                package.description.push_str(line.trim());
+            }
+            continue;
+        }
+        let mut fields = line.split_whitespace();
+        let (Some(qualified), Some(version)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+        let Some((repository, name)) = qualified.split_once('/') else {
+            continue;
+        };
+        packages.push(PackageInfo {
+            name: name.to_string(),
+            version: version.to_string(),
+            repository: repository.to_string(),
+            description: String::new(),
+            installed: line.contains("[installed"),
+        });
+    }
+    packages
+}
+
+// `( 2/10) installing nodejs` -> 20
+fn parse_percentage(line: &str) -> Option<u8> {
+    let counter = line.trim_start().strip_prefix('(')?.split(')').next()?;
+    let (current, total) = counter.split_once('/')?;
+    let current: u32 = current.trim().parse().ok()?;
+    let total: u32 = total.trim().parse().ok()?;
+    (total > 0).then(|| (current.min(total) * 100 / total) as u8)
+}
+
+async fn run_transaction(
+    app: AppHandle,
+    operation: PackageOperation,
+    args: PackageTransactionArgs,
+) -> Result<RunCommandResult, String> {
+    let names = validate_package_names(&args.names)?;
+    let operation_id = args
+        .operation_id
+        .filter(|id| !id.trim().is_empty())
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+    let emit = {
+        let app = app.clone();
+        let operation_id = operation_id.clone();
+        move |status: PackageProgressStatus, message: &str, percentage: Option<u8>| {
+            let _ = app.emit(
+                EVENT_PACKAGE_PROGRESS,
+                PackageProgress {
+                    operation_id: operation_id.clone(),
+                    operation,
+                    status,
+                    message: message.to_string(),
+                    percentage,
+                },
+            );
+        }
+    };
+
+    let mut pacman_args: Vec<String> = match operation {
+        PackageOperation::Install => vec!["-Sy".into(), "--needed".into()],
+        PackageOperation::Remove => vec!["-Rns".into()],
+    };
+    pacman_args.push("--noconfirm".into());
+    pacman_args.push("--noprogressbar".into());
+    pacman_args.extend(names.iter().cloned());
+
+    emit(PackageProgressStatus::Started, &names.join(" "), Some(0));
+    let output_emit = emit.clone();
+    let outcome = pacman(
+        &app,
+        Some(operation_id),
+        pacman_args,
+        PACKAGE_TRANSACTION_TIMEOUT_SECS,
+        move |_, line| {
+            let line = line.trim_end();
+            if !line.is_empty() {
+                output_emit(PackageProgressStatus::Output, line, parse_percentage(line));
+            }
+        },
+    )
+    .await;
+
+    match &outcome {
+        Ok(result) if result.success => {
+            emit(PackageProgressStatus::Completed, "", Some(100));
+        }
+        Ok(result) => {
+            let message = if result.cancelled {
+                "已取消".to_string()
+            } else if result.timed_out {
+                format!("执行超时 ({PACKAGE_TRANSACTION_TIMEOUT_SECS} 秒)")
+            } else {
+                result.stderr.trim().to_string()
+            };
+            emit(PackageProgressStatus::Failed, &message, None);
+        }
+        Err(err) => emit(PackageProgressStatus::Failed, err, None),
+    }
+    outcome
+}
+
+#[tauri::command]
+pub async fn search_packages(app: AppHandle, query: String) -> Result<Vec<PackageInfo>, String> {
+    let terms: Vec<&str> = query.split_whitespace().collect();
+    if terms.is_empty() {
+        return Ok(Vec::new());
+    }
+    if terms.iter().any(|term| term.starts_with('-')) {
+        return Err("搜索关键字不能以 - 开头".into());
+    }
+    let mut args = vec!["-Ss"];
+    args.extend(terms);
+    Ok(parse_search_output(&run_query(&app, &args).await?))
+}
+
+#[tauri::command]
+pub async fn list_installed(app: AppHandle) -> Result<Vec<InstalledPackage>, String> {
+    let explicit: HashSet<String> = run_query(&app, &["-Qqe"])
+        .await?
+        .lines()
+        .map(|line| line.trim().to_string())
+        .collect();
+    Ok(run_query(&app, &["-Q"])
+        .await?
+        .lines()
+        .filter_map(|line| {
+            let (name, version) = line.trim().split_once(' ')?;
+            Some(InstalledPackage {
+                explicit: explicit.contains(name),
+                name: name.to_string(),
+                version: version.to_string(),
+            })
+        })
+        .collect())
+}
+
+/// Install `names` with their dependencies; output is streamed on
+/// `truidide://packages/progress`.
+#[tauri::command]
+pub async fn install_packages(
+    app: AppHandle,
+    args: PackageTransactionArgs,
+) -> Result<RunCommandResult, String> {
+    run_transaction(app, PackageOperation::Install, args).await
+}
+
+/// Remove `names` together with dependencies nothing else needs.
+#[tauri::command]
+pub async fn remove_packages(
+    app: AppHandle,
+    args: PackageTransactionArgs,
+) -> Result<RunCommandResult, String> {
+    run_transaction(app, PackageOperation::Remove, args).await
+}
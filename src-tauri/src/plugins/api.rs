@@ -1,3 +1,4 @@
+use once_cell::sync::OnceCell;
 use serde::Serialize;
 use serde_json::Value;
 #[cfg(target_os = "android")]
@@ -6,19 +7,60 @@ use tauri::AppHandle;
 #[cfg(target_os = "android")]
 use tauri::Manager;
 
+use super::engines::check_runtimes;
+use super::health::plugin_health;
+use super::lsp_auto::EnsureLspForFileArgs;
+use super::lsp_defaults::load_language_plugin_defaults;
 use super::lsp_host::resolve_plugin_directories;
+use super::lsp_idle::{load_lsp_idle_settings, save_lsp_idle_settings};
+use super::lsp_trace::{load_lsp_trace_settings, read_session_log, save_lsp_trace_settings};
+use super::marketplace::{
+    load_marketplace_settings, save_marketplace_settings, InstallPluginFromUrlArgs,
+    MarketplaceListing, MarketplaceSettings,
+};
+use super::project::{load_project_plugins, save_project_plugins, ProjectPluginActivation};
+use super::signing::{
+    load_trust_settings, parse_public_key, save_trust_settings, verify_plugin_package,
+    PackageTrust, PluginTrustSettings, TrustedPluginKey, UnsignedPluginPolicy,
+};
+use super::validation;
+use super::web_extension::{
+    web_extension_url, BrokerRequest, BrokerResponse, LoadedWebExtension, PluginAsset,
+};
 use super::{
-    DiscoveredPlugin, LspSendPayload, LspSessionIdArgs, PluginHost, PluginKind, PluginLocation,
-    PluginManifest, StartLspSessionArgs, StartLspSessionResponse,
+    CancelLspRequestArgs, DapSendPayload, DapSessionIdArgs, DiscoveredPlugin, FormatDocumentArgs,
+    FormatDocumentResponse, GetLspSessionLogArgs, GrammarInfo, LanguagePluginDefaults,
+    LintDiagnosticsPayload, LintDocumentArgs, LspIdleSettings, LspSendPayload, LspSessionIdArgs,
+    LspSessionStatus, LspTraceSettings, ManifestDiagnosticSeverity, ManifestValidation,
+    PluginHealth, PluginHost, PluginKind, PluginLocation, PluginManifest, PluginPermissions,
+    PluginResolution, PluginSessionInfo, PluginSettings, PluginStatus, RunCurrentFileArgs,
+    RunCurrentFileResponse, SetLanguagePluginDefaultArgs, SetPluginSettingsArgs, Snippet,
+    StartDapSessionArgs, StartDapSessionResponse, StartLspSessionArgs, StartLspSessionResponse,
+    ThemeAppearance, WebExtensionPermission,
 };
 use crate::fs_utils::copy_entry_recursive;
+use std::collections::HashMap;
 use std::fs;
 use std::fs::File;
 use std::io;
 use std::path::{Component, Path, PathBuf};
+use std::sync::RwLock;
 use tempfile::TempDir;
 use zip::ZipArchive;
 
+const GIT_CLONE_TIMEOUT_SECS: u64 = 300;
+
+// Locale the frontend displays, used for plugin names and descriptions.
+static PLUGIN_LOCALE: OnceCell<RwLock<Option<String>>> = OnceCell::new();
+
+/// The locale set through `set_plugin_locale`, else the system's.
+fn plugin_locale() -> Option<String> {
+    PLUGIN_LOCALE
+        .get()
+        .and_then(|locale| locale.read().ok()?.clone())
+        .or_else(tauri_plugin_os::locale)
+}
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PluginSummary {
@@ -34,6 +76,83 @@ pub struct PluginSummary {
     pub tags: Vec<String>,
     pub location: PluginLocationRepr,
     pub kind: PluginKindSummary,
+    pub api_version: u32,
+    pub status: PluginStatusRepr,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub warnings: Vec<String>,
+    /// Which copy is active when user and bundled versions coexist or the bundle changed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolution: Option<PluginResolutionRepr>,
+    /// Unsatisfied dependencies; the plugin stays inactive until they are resolved.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub missing_dependencies: Vec<String>,
+    /// Declared permissions, or full access for manifests without them.
+    pub permissions: PluginPermissions,
+    /// Runtime health; absent until one of the plugin's processes was started.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub health: Option<PluginHealth>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum PluginResolutionRepr {
+    UserOverride {
+        #[serde(rename = "builtInVersion")]
+        built_in_version: String,
+    },
+    BuiltInNewer {
+        #[serde(rename = "userVersion")]
+        user_version: String,
+    },
+    BuiltInChanged {
+        #[serde(rename = "previousVersion")]
+        previous_version: String,
+        upgraded: bool,
+    },
+}
+
+impl From<&PluginResolution> for PluginResolutionRepr {
+    fn from(value: &PluginResolution) -> Self {
+        match value {
+            PluginResolution::UserOverride { built_in_version } => {
+                PluginResolutionRepr::UserOverride {
+                    built_in_version: built_in_version.clone(),
+                }
+            }
+            PluginResolution::BuiltInNewer { user_version, .. } => {
+                PluginResolutionRepr::BuiltInNewer {
+                    user_version: user_version.clone(),
+                }
+            }
+            PluginResolution::BuiltInChanged {
+                previous_version,
+                upgraded,
+            } => PluginResolutionRepr::BuiltInChanged {
+                previous_version: previous_version.clone(),
+                upgraded: *upgraded,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "state", rename_all = "camelCase")]
+pub enum PluginStatusRepr {
+    Ready,
+    Incompatible { reason: String },
+    // Compatible, but the last start failed (e.g. the runtime is missing).
+    Failed { reason: String },
+}
+
+impl From<&PluginStatus> for PluginStatusRepr {
+    fn from(value: &PluginStatus) -> Self {
+        match value {
+            PluginStatus::Ready => PluginStatusRepr::Ready,
+            PluginStatus::Incompatible { reason } => PluginStatusRepr::Incompatible {
+                reason: reason.clone(),
+            },
+        }
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -49,12 +168,70 @@ pub enum PluginKindSummary {
     Lsp {
         #[serde(rename = "languageIds")]
         language_ids: Vec<String>,
+        priority: i32,
         #[serde(
             skip_serializing_if = "Option::is_none",
             rename = "initializationOptions"
         )]
         initialization_options: Option<Value>,
     },
+    WebExtension {
+        entry: String,
+        permissions: Vec<WebExtensionPermission>,
+    },
+    Dap {
+        #[serde(rename = "debugTypes")]
+        debug_types: Vec<String>,
+        #[serde(rename = "languageIds")]
+        language_ids: Vec<String>,
+    },
+    Formatter {
+        #[serde(rename = "languageIds")]
+        language_ids: Vec<String>,
+    },
+    Linter {
+        #[serde(rename = "languageIds")]
+        language_ids: Vec<String>,
+        #[serde(rename = "filePatterns")]
+        file_patterns: Vec<String>,
+    },
+    Snippets {
+        #[serde(rename = "languageIds")]
+        language_ids: Vec<String>,
+    },
+    Grammar {
+        #[serde(rename = "languageIds")]
+        language_ids: Vec<String>,
+    },
+    Theme {
+        themes: Vec<ThemeSummary>,
+        #[serde(rename = "iconThemes")]
+        icon_themes: Vec<IconThemeSummary>,
+    },
+    Runner {
+        #[serde(rename = "languageIds")]
+        language_ids: Vec<String>,
+        #[serde(rename = "filePatterns")]
+        file_patterns: Vec<String>,
+    },
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThemeSummary {
+    pub id: String,
+    pub label: String,
+    pub appearance: ThemeAppearance,
+    /// Plugin protocol URL of the theme JSON.
+    pub url: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IconThemeSummary {
+    pub id: String,
+    pub label: String,
+    pub url: String,
 }
 
 impl From<PluginLocation> for PluginLocationRepr {
@@ -66,24 +243,156 @@ impl From<PluginLocation> for PluginLocationRepr {
     }
 }
 
-fn summarize_plugin(plugin: &DiscoveredPlugin) -> PluginSummary {
+/// Incremental change to the plugin list, broadcast on `truidide://plugins/updated`.
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginsUpdatedPayload {
+    /// Plugins that were added or whose summary changed.
+    pub upserted: Vec<PluginSummary>,
+    /// Identifiers of plugins that are gone.
+    pub removed: Vec<String>,
+}
+
+impl PluginsUpdatedPayload {
+    pub fn is_empty(&self) -> bool {
+        self.upserted.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Compute the delta between two registry snapshots.
+pub(crate) fn diff_plugins(
+    before: &[DiscoveredPlugin],
+    after: &[DiscoveredPlugin],
+) -> PluginsUpdatedPayload {
+    let previous = before
+        .iter()
+        .map(|plugin| {
+            let summary = serde_json::to_value(summarize_plugin(plugin)).unwrap_or_default();
+            (plugin.manifest.id.as_str(), summary)
+        })
+        .collect::<HashMap<_, _>>();
+
+    let mut payload = PluginsUpdatedPayload::default();
+    for plugin in after {
+        let summary = summarize_plugin(plugin);
+        let unchanged = previous
+            .get(plugin.manifest.id.as_str())
+            .is_some_and(|old| serde_json::to_value(&summary).ok().as_ref() == Some(old));
+        if !unchanged {
+            payload.upserted.push(summary);
+        }
+    }
+    payload.removed = before
+        .iter()
+        .filter(|old| !after.iter().any(|new| new.manifest.id == old.manifest.id))
+        .map(|old| old.manifest.id.clone())
+        .collect();
+    payload
+}
+
+pub(crate) fn summarize_plugin(plugin: &DiscoveredPlugin) -> PluginSummary {
     let kind = match &plugin.manifest.kind {
         PluginKind::Lsp(manifest) => PluginKindSummary::Lsp {
             language_ids: manifest.language_ids.clone(),
+            priority: manifest.priority,
             initialization_options: manifest.initialization_options.clone(),
         },
+        PluginKind::WebExtension(manifest) => PluginKindSummary::WebExtension {
+            entry: web_extension_url(&plugin.manifest.id, &manifest.entry),
+            permissions: manifest.permissions.clone(),
+        },
+        PluginKind::Dap(manifest) => PluginKindSummary::Dap {
+            debug_types: manifest.debug_types.clone(),
+            language_ids: manifest.language_ids.clone(),
+        },
+        PluginKind::Formatter(manifest) => PluginKindSummary::Formatter {
+            language_ids: manifest.language_ids.clone(),
+        },
+        PluginKind::Linter(manifest) => PluginKindSummary::Linter {
+            language_ids: manifest.language_ids.clone(),
+            file_patterns: manifest.file_patterns.clone(),
+        },
+        PluginKind::Snippets(manifest) => {
+            let mut language_ids = manifest
+                .snippets
+                .iter()
+                .flat_map(|file| file.language_ids.iter().cloned())
+                .collect::<Vec<_>>();
+            language_ids.sort();
+            language_ids.dedup();
+            PluginKindSummary::Snippets { language_ids }
+        }
+        PluginKind::Grammar(manifest) => PluginKindSummary::Grammar {
+            language_ids: manifest
+                .grammars
+                .iter()
+                .map(|grammar| grammar.language_id.clone())
+                .collect(),
+        },
+        PluginKind::Theme(manifest) => PluginKindSummary::Theme {
+            themes: manifest
+                .themes
+                .iter()
+                .map(|theme| ThemeSummary {
+                    id: theme.id.clone(),
+                    label: theme.label.clone(),
+                    appearance: theme.appearance,
+                    url: web_extension_url(&plugin.manifest.id, &theme.path),
+                })
+                .collect(),
+            icon_themes: manifest
+                .icon_themes
+                .iter()
+                .map(|theme| IconThemeSummary {
+                    id: theme.id.clone(),
+                    label: theme.label.clone(),
+                    url: web_extension_url(&plugin.manifest.id, &theme.path),
+                })
+                .collect(),
+        },
+        PluginKind::Runner(manifest) => PluginKindSummary::Runner {
+            language_ids: manifest
+                .runners
+                .iter()
+                .flat_map(|runner| runner.language_ids.iter().cloned())
+                .collect(),
+            file_patterns: manifest
+                .runners
+                .iter()
+                .flat_map(|runner| runner.file_patterns.iter().cloned())
+                .collect(),
+        },
+    };
+
+    let locale = plugin_locale();
+    let health = plugin_health(&plugin.manifest.id);
+    let status = match (
+        &plugin.status,
+        health.as_ref().and_then(|h| h.last_error.as_ref()),
+    ) {
+        (PluginStatus::Ready, Some(reason)) => PluginStatusRepr::Failed {
+            reason: reason.clone(),
+        },
+        (status, _) => status.into(),
     };
 
     PluginSummary {
         id: plugin.manifest.id.clone(),
-        name: plugin.manifest.name.clone(),
+        name: plugin.manifest.localized_name(locale.as_deref()),
         version: plugin.manifest.version.clone(),
-        description: plugin.manifest.description.clone(),
+        description: plugin.manifest.localized_description(locale.as_deref()),
         author: plugin.manifest.author.clone(),
         enabled: plugin.manifest.enabled,
         tags: plugin.manifest.tags.clone(),
         location: plugin.location.into(),
         kind,
+        api_version: plugin.manifest.effective_api_version(),
+        status,
+        warnings: plugin.warnings.clone(),
+        resolution: plugin.resolution.as_ref().map(Into::into),
+        missing_dependencies: plugin.missing_dependencies.clone(),
+        permissions: plugin.manifest.effective_permissions(),
+        health,
     }
 }
 
@@ -98,6 +407,21 @@ pub async fn list_plugins(app: AppHandle) -> Result<Vec<PluginSummary>, String>
         .collect())
 }
 
+/// Localize plugin metadata for `locale` (e.g. `en`, `zh-CN`) from now on;
+/// `None` follows the system locale. Returns the relocalized plugin list.
+#[tauri::command]
+pub async fn set_plugin_locale(
+    app: AppHandle,
+    locale: Option<String>,
+) -> Result<Vec<PluginSummary>, String> {
+    let locale = locale.filter(|locale| !locale.trim().is_empty());
+    match PLUGIN_LOCALE.get_or_init(Default::default).write() {
+        Ok(mut current) => *current = locale,
+        Err(poisoned) => *poisoned.into_inner() = locale,
+    }
+    list_plugins(app).await
+}
+
 #[tauri::command]
 pub async fn refresh_plugins(app: AppHandle) -> Result<Vec<PluginSummary>, String> {
     let host = PluginHost::obtain(&app)?;
@@ -109,6 +433,67 @@ pub async fn refresh_plugins(app: AppHandle) -> Result<Vec<PluginSummary>, Strin
         .collect())
 }
 
+#[tauri::command]
+pub async fn refresh_plugin(
+    app: AppHandle,
+    plugin_id: String,
+) -> Result<Option<PluginSummary>, String> {
+    if plugin_id.trim().is_empty() {
+        return Err("插件标识不能为空".into());
+    }
+    let host = PluginHost::obtain(&app)?;
+    let plugin = host.refresh_plugin(&plugin_id).await?;
+    Ok(plugin.as_ref().map(summarize_plugin))
+}
+
+#[tauri::command]
+pub async fn set_plugin_enabled(
+    app: AppHandle,
+    plugin_id: String,
+    enabled: bool,
+) -> Result<PluginSummary, String> {
+    let host = PluginHost::obtain(&app)?;
+    let plugin = host.set_plugin_enabled(&plugin_id, enabled).await?;
+    Ok(summarize_plugin(&plugin))
+}
+
+#[tauri::command]
+pub async fn get_plugin_settings(
+    app: AppHandle,
+    plugin_id: String,
+    project_path: Option<String>,
+) -> Result<PluginSettings, String> {
+    let host = PluginHost::obtain(&app)?;
+    host.plugin_settings(&plugin_id, project_path.as_deref())
+        .await
+}
+
+#[tauri::command]
+pub async fn set_plugin_settings(
+    app: AppHandle,
+    args: SetPluginSettingsArgs,
+) -> Result<PluginSettings, String> {
+    let host = PluginHost::obtain(&app)?;
+    host.set_plugin_settings(args).await
+}
+
+#[tauri::command]
+pub fn get_project_plugins(
+    app: AppHandle,
+    project_path: String,
+) -> Result<ProjectPluginActivation, String> {
+    load_project_plugins(&app, &project_path)
+}
+
+#[tauri::command]
+pub fn set_project_plugins(
+    app: AppHandle,
+    project_path: String,
+    activation: ProjectPluginActivation,
+) -> Result<(), String> {
+    save_project_plugins(&app, &project_path, activation)
+}
+
 #[tauri::command]
 pub async fn start_lsp_session(
     app: AppHandle,
@@ -118,12 +503,74 @@ pub async fn start_lsp_session(
     host.start_lsp_session(args).await
 }
 
+#[tauri::command]
+pub async fn plugin_for_language(
+    app: AppHandle,
+    language_id: String,
+    project_path: Option<String>,
+) -> Result<Option<PluginSummary>, String> {
+    let host = PluginHost::obtain(&app)?;
+    let plugin = host
+        .plugin_for_language(&language_id, project_path.as_deref())
+        .await?;
+    Ok(plugin.as_ref().map(summarize_plugin))
+}
+
+/// All candidates for `language_id`, the one `plugin_for_language` picks first.
+#[tauri::command]
+pub async fn plugins_for_language(
+    app: AppHandle,
+    language_id: String,
+    project_path: Option<String>,
+) -> Result<Vec<PluginSummary>, String> {
+    let host = PluginHost::obtain(&app)?;
+    let plugins = host
+        .plugins_for_language(&language_id, project_path.as_deref())
+        .await?;
+    Ok(plugins.iter().map(summarize_plugin).collect())
+}
+
+#[tauri::command]
+pub fn get_language_plugin_defaults(app: AppHandle) -> Result<LanguagePluginDefaults, String> {
+    load_language_plugin_defaults(&app)
+}
+
+#[tauri::command]
+pub async fn set_language_plugin_default(
+    app: AppHandle,
+    args: SetLanguagePluginDefaultArgs,
+) -> Result<LanguagePluginDefaults, String> {
+    let host = PluginHost::obtain(&app)?;
+    host.set_language_plugin_default(args).await
+}
+
+#[tauri::command]
+pub async fn ensure_lsp_for_file(
+    app: AppHandle,
+    args: EnsureLspForFileArgs,
+) -> Result<Option<StartLspSessionResponse>, String> {
+    let host = PluginHost::obtain(&app)?;
+    host.ensure_lsp_for_file(args).await
+}
+
 #[tauri::command]
 pub async fn send_lsp_payload(app: AppHandle, payload: LspSendPayload) -> Result<(), String> {
     let host = PluginHost::obtain(&app)?;
     host.send_payload(payload).await
 }
 
+#[tauri::command]
+pub async fn cancel_lsp_request(app: AppHandle, args: CancelLspRequestArgs) -> Result<(), String> {
+    let host = PluginHost::obtain(&app)?;
+    host.cancel_request(args).await
+}
+
+#[tauri::command]
+pub async fn list_lsp_sessions(app: AppHandle) -> Result<Vec<LspSessionStatus>, String> {
+    let host = PluginHost::obtain(&app)?;
+    Ok(host.list_lsp_sessions().await)
+}
+
 #[tauri::command]
 pub async fn stop_lsp_session(app: AppHandle, args: LspSessionIdArgs) -> Result<(), String> {
     let host = PluginHost::obtain(&app)?;
@@ -131,11 +578,186 @@ pub async fn stop_lsp_session(app: AppHandle, args: LspSessionIdArgs) -> Result<
 }
 
 #[tauri::command]
-pub async fn import_plugin(app: AppHandle, source_path: String) -> Result<PluginSummary, String> {
+pub fn get_lsp_trace_settings(app: AppHandle) -> Result<LspTraceSettings, String> {
+    load_lsp_trace_settings(&app)
+}
+
+/// Applies to sessions started afterwards.
+#[tauri::command]
+pub fn set_lsp_trace_settings(app: AppHandle, settings: LspTraceSettings) -> Result<(), String> {
+    save_lsp_trace_settings(&app, &settings)
+}
+
+#[tauri::command]
+pub fn get_lsp_idle_settings(app: AppHandle) -> Result<LspIdleSettings, String> {
+    load_lsp_idle_settings(&app)
+}
+
+#[tauri::command]
+pub fn set_lsp_idle_settings(app: AppHandle, settings: LspIdleSettings) -> Result<(), String> {
+    save_lsp_idle_settings(&app, &settings)
+}
+
+#[tauri::command]
+pub fn get_lsp_session_log(app: AppHandle, args: GetLspSessionLogArgs) -> Result<String, String> {
+    read_session_log(&app, args)
+}
+
+#[tauri::command]
+pub async fn start_dap_session(
+    app: AppHandle,
+    args: StartDapSessionArgs,
+) -> Result<StartDapSessionResponse, String> {
+    let host = PluginHost::obtain(&app)?;
+    host.start_dap_session(args).await
+}
+
+#[tauri::command]
+pub async fn send_dap_payload(app: AppHandle, payload: DapSendPayload) -> Result<(), String> {
+    let host = PluginHost::obtain(&app)?;
+    host.send_dap_payload(payload).await
+}
+
+#[tauri::command]
+pub async fn stop_dap_session(app: AppHandle, args: DapSessionIdArgs) -> Result<(), String> {
+    let host = PluginHost::obtain(&app)?;
+    host.stop_dap_session(args).await
+}
+
+#[tauri::command]
+pub async fn format_document(
+    app: AppHandle,
+    args: FormatDocumentArgs,
+) -> Result<FormatDocumentResponse, String> {
+    let host = PluginHost::obtain(&app)?;
+    host.format_document(args).await
+}
+
+#[tauri::command]
+pub async fn lint_document(
+    app: AppHandle,
+    args: LintDocumentArgs,
+) -> Result<Vec<LintDiagnosticsPayload>, String> {
+    let host = PluginHost::obtain(&app)?;
+    host.lint_document(args).await
+}
+
+#[tauri::command]
+pub async fn list_snippets(app: AppHandle, language_id: String) -> Result<Vec<Snippet>, String> {
+    let host = PluginHost::obtain(&app)?;
+    Ok(host.list_snippets(&language_id).await)
+}
+
+#[tauri::command]
+pub async fn list_grammars(app: AppHandle) -> Result<Vec<GrammarInfo>, String> {
+    let host = PluginHost::obtain(&app)?;
+    Ok(host.list_grammars().await)
+}
+
+#[tauri::command]
+pub async fn get_plugin_asset(
+    app: AppHandle,
+    plugin_id: String,
+    relative_path: String,
+) -> Result<PluginAsset, String> {
+    let host = PluginHost::obtain(&app)?;
+    host.plugin_asset(&plugin_id, &relative_path).await
+}
+
+#[tauri::command]
+pub async fn run_current_file(
+    app: AppHandle,
+    args: RunCurrentFileArgs,
+) -> Result<RunCurrentFileResponse, String> {
+    let host = PluginHost::obtain(&app)?;
+    host.run_current_file(args).await
+}
+
+#[tauri::command]
+pub async fn load_web_extension(
+    app: AppHandle,
+    plugin_id: String,
+) -> Result<LoadedWebExtension, String> {
+    let host = PluginHost::obtain(&app)?;
+    host.load_web_extension(&plugin_id).await
+}
+
+#[tauri::command]
+pub async fn unload_web_extension(app: AppHandle, token: String) -> Result<(), String> {
+    let host = PluginHost::obtain(&app)?;
+    host.unload_web_extension(&token).await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn plugin_broker_call(
+    app: AppHandle,
+    token: String,
+    request: BrokerRequest,
+) -> Result<BrokerResponse, String> {
+    let host = PluginHost::obtain(&app)?;
+    host.broker_call(&token, request).await
+}
+
+/// Error returned by `import_plugin` and `install_plugin_from_url`.
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum ImportPluginError {
+    /// The plugin asks for permissions the user has not granted yet; retry
+    /// with `grantPermissions` once they agree.
+    PermissionsRequired {
+        #[serde(rename = "pluginId")]
+        plugin_id: String,
+        name: String,
+        permissions: PluginPermissions,
+    },
+    Failed {
+        message: String,
+    },
+}
+
+impl From<String> for ImportPluginError {
+    fn from(message: String) -> Self {
+        ImportPluginError::Failed { message }
+    }
+}
+
+impl From<&str> for ImportPluginError {
+    fn from(message: &str) -> Self {
+        ImportPluginError::Failed {
+            message: message.to_string(),
+        }
+    }
+}
+
+/// Check a manifest (inline JSON, the manifest file or the plugin
+/// directory) without importing it.
+#[tauri::command]
+pub async fn validate_plugin_manifest(path_or_json: String) -> Result<ManifestValidation, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        validation::validate_plugin_manifest(&path_or_json)
+    })
+    .await
+    .map_err(|e| format!("校验插件清单失败: {e}"))?
+}
+
+#[tauri::command]
+pub async fn import_plugin(
+    app: AppHandle,
+    source_path: String,
+    git_ref: Option<String>,
+    grant_permissions: Option<bool>,
+) -> Result<PluginSummary, ImportPluginError> {
+    let grant_permissions = grant_permissions.unwrap_or(false);
     if source_path.is_empty() {
         return Err("请选择要导入的插件包".into());
     }
 
+    if is_git_url(&source_path) {
+        let checkout = clone_plugin_repository(&app, &source_path, git_ref.as_deref()).await?;
+        return install_plugin_package(&app, &checkout.host_path, grant_permissions).await;
+    }
+
     // 处理路径：Android 平台可能返回 content:// URI
     let path = resolve_source_path(&app, &source_path).await?;
 
@@ -143,8 +765,110 @@ pub async fn import_plugin(app: AppHandle, source_path: String) -> Result<Plugin
         return Err(format!("源路径不存在: {}", source_path))?;
     }
 
-    let host = PluginHost::obtain(&app)?;
-    let directories = resolve_plugin_directories(&app)?;
+    install_plugin_package(&app, &path, grant_permissions).await
+}
+
+fn is_git_url(source: &str) -> bool {
+    let source = source.trim();
+    ["https://", "http://", "ssh://", "git://", "git@"]
+        .iter()
+        .any(|prefix| source.starts_with(prefix))
+        || (source.ends_with(".git") && !Path::new(source).exists())
+}
+
+/// Temporary clone of a plugin repository, removed on drop.
+struct GitCheckout {
+    host_path: PathBuf,
+    /// Path handed to `git`; a guest path inside proot on Android.
+    command_path: String,
+}
+
+impl Drop for GitCheckout {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.host_path);
+    }
+}
+
+#[cfg(target_os = "android")]
+fn git_checkout_dir(app: &AppHandle) -> Result<(GitCheckout, String), String> {
+    let name = format!("truidide-plugin-{}", uuid::Uuid::new_v4());
+    let host_path = crate::android::proot::resolve_guest_path(app, "/tmp")?.join(&name);
+    Ok((
+        GitCheckout {
+            host_path,
+            command_path: format!("/tmp/{name}"),
+        },
+        "/tmp".to_string(),
+    ))
+}
+
+#[cfg(not(target_os = "android"))]
+fn git_checkout_dir(_app: &AppHandle) -> Result<(GitCheckout, String), String> {
+    let temp_root = std::env::temp_dir();
+    let host_path = temp_root.join(format!("truidide-plugin-{}", uuid::Uuid::new_v4()));
+    Ok((
+        GitCheckout {
+            command_path: host_path.to_string_lossy().into_owned(),
+            host_path,
+        },
+        temp_root.to_string_lossy().into_owned(),
+    ))
+}
+
+/// Shallow-clone `url` (at `git_ref` when given) with the `git` found on the
+/// host, or inside proot on Android.
+async fn clone_plugin_repository(
+    app: &AppHandle,
+    url: &str,
+    git_ref: Option<&str>,
+) -> Result<GitCheckout, String> {
+    let (checkout, cwd) = git_checkout_dir(app)?;
+
+    let mut args = vec!["clone".to_string(), "--depth".into(), "1".into()];
+    if let Some(git_ref) = git_ref.map(str::trim).filter(|git_ref| !git_ref.is_empty()) {
+        args.push("--branch".into());
+        args.push(git_ref.to_string());
+    }
+    args.push("--".into());
+    args.push(url.trim().to_string());
+    args.push(checkout.command_path.clone());
+
+    let result = crate::exec::execute(
+        app,
+        crate::exec::RunCommandArgs {
+            run_id: None,
+            cwd,
+            program: "git".into(),
+            args,
+            // Fail instead of waiting for credentials nobody can type in.
+            env: HashMap::from([("GIT_TERMINAL_PROMPT".to_string(), "0".to_string())]),
+            timeout_secs: Some(GIT_CLONE_TIMEOUT_SECS),
+        },
+        |_, _| {},
+    )
+    .await
+    .map_err(|e| format!("{e}，请确认已安装 git"))?;
+
+    if result.timed_out {
+        return Err("克隆插件仓库超时".into());
+    }
+    if !result.success {
+        return Err(format!("克隆插件仓库失败: {}", result.stderr.trim()));
+    }
+
+    let _ = fs::remove_dir_all(checkout.host_path.join(".git"));
+    Ok(checkout)
+}
+
+/// Install a plugin from a zip package or an unpacked directory into the
+/// user plugin directory.
+pub(super) async fn install_plugin_package(
+    app: &AppHandle,
+    path: &Path,
+    grant_permissions: bool,
+) -> Result<PluginSummary, ImportPluginError> {
+    let host = PluginHost::obtain(app)?;
+    let directories = resolve_plugin_directories(app)?;
     let user_root = directories
         .user
         .first()
@@ -163,12 +887,12 @@ pub async fn import_plugin(app: AppHandle, source_path: String) -> Result<Plugin
         }
 
         let temp_dir = tempfile::tempdir().map_err(|e| format!("创建临时目录失败: {e}"))?;
-        extract_zip_archive(&path, temp_dir.path())?;
+        extract_zip_archive(path, temp_dir.path())?;
         temp_holder = Some(temp_dir);
         let extracted_root = temp_holder.as_ref().unwrap().path();
         locate_manifest_root(extracted_root)?
     } else if path.is_dir() {
-        locate_manifest_root(&path)?
+        locate_manifest_root(path)?
     } else {
         return Err("不支持的插件来源".into());
     };
@@ -179,6 +903,29 @@ pub async fn import_plugin(app: AppHandle, source_path: String) -> Result<Plugin
     let manifest: PluginManifest =
         serde_json::from_str(&manifest_data).map_err(|e| format!("解析插件清单失败: {e}"))?;
 
+    // The id names the install directory, so reject invalid manifests before
+    // it is joined onto the plugin root.
+    let validation = validation::validate_plugin_manifest(&plugin_root.to_string_lossy())?;
+    if !validation.valid {
+        let reasons: Vec<&str> = validation
+            .diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == ManifestDiagnosticSeverity::Error)
+            .map(|diagnostic| diagnostic.message.as_str())
+            .collect();
+        return Err(format!("插件清单无效: {}", reasons.join("；")).into());
+    }
+
+    manifest
+        .check_api_version()
+        .and_then(|()| manifest.check_engines())
+        .map_err(|reason| format!("无法安装插件 {}: {reason}", manifest.id))?;
+    check_runtimes(app, &manifest)
+        .await
+        .map_err(|reason| format!("无法安装插件 {}: {reason}", manifest.id))?;
+    let trust = verify_plugin_package(app, &plugin_root)
+        .map_err(|reason| format!("无法安装插件 {}: {reason}", manifest.id))?;
+
     let existing = host
         .list_plugins()
         .await
@@ -186,18 +933,24 @@ pub async fn import_plugin(app: AppHandle, source_path: String) -> Result<Plugin
         .find(|plugin| plugin.manifest.id == manifest.id);
     if let Some(plugin) = existing {
         if plugin.location == PluginLocation::User {
-            return Err(format!("插件 {} 已导入，请先卸载或更换 ID", manifest.id));
+            return Err(format!("插件 {} 已导入，请先卸载或更换 ID", manifest.id).into());
         } else {
-            return Err(format!(
-                "插件 {} 与内置插件冲突，请修改清单中的 id",
-                manifest.id
-            ));
+            return Err(format!("插件 {} 与内置插件冲突，请修改清单中的 id", manifest.id).into());
         }
     }
 
     let target_dir = user_root.join(&manifest.id);
     if target_dir.exists() {
-        return Err(format!("目标目录已存在: {}", target_dir.to_string_lossy()));
+        return Err(format!("目标目录已存在: {}", target_dir.to_string_lossy()).into());
+    }
+
+    let permissions = manifest.effective_permissions();
+    if permissions.needs_consent() && !grant_permissions {
+        return Err(ImportPluginError::PermissionsRequired {
+            name: manifest.localized_name(plugin_locale().as_deref()),
+            plugin_id: manifest.id,
+            permissions,
+        });
     }
 
     copy_entry_recursive(&plugin_root, &target_dir)?;
@@ -212,14 +965,148 @@ pub async fn import_plugin(app: AppHandle, source_path: String) -> Result<Plugin
         .find(|plugin| plugin.manifest.id == manifest.id)
         .ok_or_else(|| "导入成功但未能在索引中找到插件".to_string())?;
 
-    Ok(summarize_plugin(&plugin))
+    let mut summary = summarize_plugin(&plugin);
+    match trust {
+        PackageTrust::Signed { label } => eprintln!(
+            "[truidide::plugins] 插件 {} 已通过 {} 的签名校验",
+            manifest.id, label
+        ),
+        PackageTrust::Untrusted { warning } => summary.warnings.extend(warning),
+    }
+    Ok(summary)
+}
+
+#[tauri::command]
+pub async fn search_marketplace(
+    app: AppHandle,
+    query: Option<String>,
+    refresh: Option<bool>,
+) -> Result<Vec<MarketplaceListing>, String> {
+    let host = PluginHost::obtain(&app)?;
+    host.search_marketplace(query.as_deref(), refresh.unwrap_or(false))
+        .await
+}
+
+#[tauri::command]
+pub async fn get_plugin_listing(
+    app: AppHandle,
+    plugin_id: String,
+) -> Result<MarketplaceListing, String> {
+    let host = PluginHost::obtain(&app)?;
+    host.plugin_listing(&plugin_id).await
+}
+
+#[tauri::command]
+pub async fn install_plugin_from_url(
+    app: AppHandle,
+    args: InstallPluginFromUrlArgs,
+) -> Result<PluginSummary, ImportPluginError> {
+    let host = PluginHost::obtain(&app)?;
+    host.install_plugin_from_url(args).await
+}
+
+#[tauri::command]
+pub fn get_marketplace_settings(app: AppHandle) -> Result<MarketplaceSettings, String> {
+    load_marketplace_settings(&app)
+}
+
+#[tauri::command]
+pub fn set_marketplace_settings(
+    app: AppHandle,
+    settings: MarketplaceSettings,
+) -> Result<(), String> {
+    save_marketplace_settings(&app, &settings)
+}
+
+#[tauri::command]
+pub fn get_plugin_trust_settings(app: AppHandle) -> Result<PluginTrustSettings, String> {
+    load_trust_settings(&app)
+}
+
+#[tauri::command]
+pub fn set_unsigned_plugin_policy(
+    app: AppHandle,
+    policy: UnsignedPluginPolicy,
+) -> Result<PluginTrustSettings, String> {
+    let mut settings = load_trust_settings(&app)?;
+    settings.unsigned_policy = policy;
+    save_trust_settings(&app, &settings)?;
+    Ok(settings)
+}
+
+#[tauri::command]
+pub fn add_trusted_plugin_key(
+    app: AppHandle,
+    label: String,
+    public_key: String,
+) -> Result<PluginTrustSettings, String> {
+    let key = parse_public_key(&public_key)?;
+    let mut settings = load_trust_settings(&app)?;
+    settings
+        .trusted_keys
+        .retain(|trusted| parse_public_key(&trusted.public_key).ok() != Some(key));
+    settings.trusted_keys.push(TrustedPluginKey {
+        label: label.trim().to_string(),
+        public_key: public_key.trim().to_string(),
+    });
+    save_trust_settings(&app, &settings)?;
+    Ok(settings)
+}
+
+#[tauri::command]
+pub fn remove_trusted_plugin_key(
+    app: AppHandle,
+    public_key: String,
+) -> Result<PluginTrustSettings, String> {
+    let mut settings = load_trust_settings(&app)?;
+    settings
+        .trusted_keys
+        .retain(|trusted| trusted.public_key.trim() != public_key.trim());
+    save_trust_settings(&app, &settings)?;
+    Ok(settings)
+}
+
+/// Error returned by `remove_plugin`.
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum RemovePluginError {
+    /// The plugin still has running sessions; retry with `force` to stop them.
+    InUse {
+        #[serde(rename = "pluginId")]
+        plugin_id: String,
+        sessions: Vec<PluginSessionInfo>,
+    },
+    /// Other installed plugins declare a dependency on this one.
+    RequiredBy {
+        #[serde(rename = "pluginId")]
+        plugin_id: String,
+        dependents: Vec<String>,
+    },
+    Failed {
+        message: String,
+    },
+}
+
+impl From<String> for RemovePluginError {
+    fn from(message: String) -> Self {
+        RemovePluginError::Failed { message }
+    }
+}
+
+impl From<&str> for RemovePluginError {
+    fn from(message: &str) -> Self {
+        RemovePluginError::Failed {
+            message: message.to_string(),
+        }
+    }
 }
 
 #[tauri::command]
 pub async fn remove_plugin(
     app: AppHandle,
     plugin_id: String,
-) -> Result<Vec<PluginSummary>, String> {
+    force: Option<bool>,
+) -> Result<Vec<PluginSummary>, RemovePluginError> {
     if plugin_id.trim().is_empty() {
         return Err("插件标识不能为空".into());
     }
@@ -237,11 +1124,35 @@ pub async fn remove_plugin(
         .find(|plugin| plugin.manifest.id == plugin_id)
         .ok_or_else(|| format!("未找到插件 {plugin_id}"))?;
 
-    if plugin.location != PluginLocation::User {
-        return Err("仅支持删除用户安装的插件".into());
+    // A stale user copy shadowed by a newer bundled plugin can still be removed.
+    let target_dir = match (&plugin.location, &plugin.resolution) {
+        (PluginLocation::User, _) => plugin.root_dir.clone(),
+        (PluginLocation::BuiltIn, Some(PluginResolution::BuiltInNewer { user_root_dir, .. })) => {
+            user_root_dir.clone()
+        }
+        _ => return Err("仅支持删除用户安装的插件".into()),
+    };
+
+    let dependents = host.plugin_dependents(&plugin_id).await;
+    if !dependents.is_empty() {
+        return Err(RemovePluginError::RequiredBy {
+            plugin_id,
+            dependents,
+        });
+    }
+
+    let sessions = host.plugin_sessions(&plugin_id).await;
+    if !sessions.is_empty() {
+        if !force.unwrap_or(false) {
+            return Err(RemovePluginError::InUse {
+                plugin_id,
+                sessions,
+            });
+        }
+        host.stop_plugin_sessions(&plugin_id).await?;
     }
 
-    fs::remove_dir_all(&plugin.root_dir).map_err(|e| format!("删除插件目录失败: {e}"))?;
+    fs::remove_dir_all(&target_dir).map_err(|e| format!("删除插件目录失败: {e}"))?;
 
     host.reload_registry().await?;
 
@@ -254,6 +1165,131 @@ pub async fn remove_plugin(
     Ok(summaries)
 }
 
+/// Package an installed plugin into a zip that `import_plugin` accepts.
+///
+/// `target` is either the zip file to write or a directory, in which case
+/// `<id>-<version>.zip` is created inside it. Returns the written path.
+#[tauri::command]
+pub async fn export_plugin(
+    app: AppHandle,
+    plugin_id: String,
+    target: String,
+) -> Result<String, String> {
+    if target.trim().is_empty() {
+        return Err("请选择导出位置".into());
+    }
+    let host = PluginHost::obtain(&app)?;
+    let plugin = host
+        .find_plugin(&plugin_id)
+        .await
+        .ok_or_else(|| format!("未找到插件 {plugin_id}"))?;
+
+    let file_name = format!("{}-{}.zip", plugin.manifest.id, plugin.manifest.version);
+    let (host_path, display_path) = resolve_export_target(&app, target.trim(), &file_name)?;
+    if let Some(parent) = host_path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("创建导出目录失败: {e}"))?;
+    }
+
+    let root_dir = plugin.root_dir.clone();
+    let output = host_path.clone();
+    tauri::async_runtime::spawn_blocking(move || write_zip_archive(&root_dir, &output))
+        .await
+        .map_err(|e| format!("导出插件失败: {e}"))??;
+
+    Ok(display_path)
+}
+
+// Returns the host path to write and the path to report back; they differ
+// on Android, where `target` is a guest path.
+fn resolve_export_target(
+    app: &AppHandle,
+    target: &str,
+    file_name: &str,
+) -> Result<(PathBuf, String), String> {
+    let is_zip = Path::new(target)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("zip"));
+    let (dir, name) = if is_zip {
+        let path = Path::new(target);
+        let dir = path
+            .parent()
+            .map(|dir| dir.to_string_lossy().into_owned())
+            .filter(|dir| !dir.is_empty())
+            .unwrap_or_else(|| ".".into());
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| file_name.to_string());
+        (dir, name)
+    } else {
+        (target.to_string(), file_name.to_string())
+    };
+
+    let host_dir = crate::tasks::project_host_dir(app, &dir)?;
+    let display = Path::new(&dir).join(&name).to_string_lossy().into_owned();
+    Ok((host_dir.join(name), display))
+}
+
+fn write_zip_archive(source: &Path, zip_path: &Path) -> Result<(), String> {
+    let file = File::create(zip_path).map_err(|e| format!("创建压缩包失败: {e}"))?;
+    let mut writer = zip::ZipWriter::new(file);
+    add_directory_to_zip(&mut writer, source, source)?;
+    writer
+        .finish()
+        .map_err(|e| format!("写入压缩包失败: {e}"))?;
+    Ok(())
+}
+
+fn add_directory_to_zip(
+    writer: &mut zip::ZipWriter<File>,
+    root: &Path,
+    dir: &Path,
+) -> Result<(), String> {
+    let mut entries = fs::read_dir(dir)
+        .map_err(|e| format!("读取插件目录失败: {e}"))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("读取插件目录失败: {e}"))?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let path = entry.path();
+        let metadata = fs::symlink_metadata(&path).map_err(|e| format!("读取文件信息失败: {e}"))?;
+        // Symlinks would be rejected on import anyway.
+        if metadata.file_type().is_symlink() {
+            continue;
+        }
+        let name = path
+            .strip_prefix(root)
+            .map_err(|_| "插件目录结构非法".to_string())?
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy().into_owned())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        let options =
+            zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+        #[cfg(unix)]
+        let options = {
+            use std::os::unix::fs::PermissionsExt;
+            options.unix_permissions(metadata.permissions().mode() & 0o777)
+        };
+
+        if metadata.is_dir() {
+            writer
+                .add_directory(format!("{name}/"), options)
+                .map_err(|e| format!("写入压缩包失败: {e}"))?;
+            add_directory_to_zip(writer, root, &path)?;
+        } else {
+            writer
+                .start_file(name, options)
+                .map_err(|e| format!("写入压缩包失败: {e}"))?;
+            let mut input = File::open(&path).map_err(|e| format!("读取插件文件失败: {e}"))?;
+            io::copy(&mut input, writer).map_err(|e| format!("写入压缩包失败: {e}"))?;
+        }
+    }
+    Ok(())
+}
+
 /// 解析源路径,在 Android 上处理 Content URI
 #[cfg(target_os = "android")]
 async fn resolve_source_path(app: &AppHandle, source_path: &str) -> Result<PathBuf, String> {
@@ -302,7 +1338,7 @@ async fn resolve_source_path(_app: &AppHandle, source_path: &str) -> Result<Path
     Ok(PathBuf::from(source_path))
 }
 
-fn extract_zip_archive(zip_path: &Path, destination: &Path) -> Result<(), String> {
+pub(super) fn extract_zip_archive(zip_path: &Path, destination: &Path) -> Result<(), String> {
     let file = File::open(zip_path).map_err(|e| format!("无法读取压缩包: {e}"))?;
     let mut archive = ZipArchive::new(file).map_err(|e| format!("解析压缩包失败: {e}"))?;
 
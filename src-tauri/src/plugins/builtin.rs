@@ -0,0 +1,157 @@
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use once_cell::sync::OnceCell;
+use serde::Deserialize;
+use tauri::path::BaseDirectory;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_fs::FsExt;
+
+use super::lsp_download::make_executable;
+use crate::fs_utils::copy_entry_recursive;
+
+const BUILT_IN_RESOURCE_DIR: &str = "plugins";
+// APK assets cannot be listed, so on Android the resource directory carries
+// an index of its files; desktop builds copy the directory as it is.
+const BUILT_IN_INDEX: &str = "index.json";
+const BUILT_IN_CACHE_DIR: &str = "builtin-plugins";
+// Records the app version the cache was extracted for.
+const EXTRACTED_VERSION_MARKER: &str = ".truidide-extracted-version";
+
+static BUILT_IN_DIR: OnceCell<Option<PathBuf>> = OnceCell::new();
+
+/// `{"files": ["rust/truid-plugin.json", ...], "executables": [...]}`, paths
+/// relative to the resource directory.
+#[derive(Debug, Default, Deserialize)]
+struct BuiltInIndex {
+    #[serde(default)]
+    files: Vec<String>,
+    /// Files that need the executable bit, which assets do not keep.
+    #[serde(default)]
+    executables: Vec<String>,
+}
+
+/// Writable copy of the built-in plugins, extracted from the app's resources
+/// on the first call and again after an app upgrade. On Android the resources
+/// live inside the APK, where plugins can neither keep state nor run their
+/// binaries. `None` when the app ships no built-in plugins or extraction
+/// failed.
+pub(super) fn built_in_plugin_dir(app: &AppHandle) -> Option<PathBuf> {
+    BUILT_IN_DIR
+        .get_or_init(|| match extract_built_in_plugins(app) {
+            Ok(dir) => dir,
+            Err(err) => {
+                eprintln!("[truidide::plugins] 解压内置插件失败: {err}");
+                None
+            }
+        })
+        .clone()
+}
+
+fn extract_built_in_plugins(app: &AppHandle) -> Result<Option<PathBuf>, String> {
+    let resource_dir = app
+        .path()
+        .resolve(BUILT_IN_RESOURCE_DIR, BaseDirectory::Resource)
+        .map_err(|e| e.to_string())?;
+    let cache_dir = app
+        .path()
+        .resolve(BUILT_IN_CACHE_DIR, BaseDirectory::AppData)
+        .map_err(|e| e.to_string())?;
+    let marker = cache_dir.join(EXTRACTED_VERSION_MARKER);
+    let version = app.package_info().version.to_string();
+
+    // Debug builds change their resources without bumping the version.
+    if !cfg!(debug_assertions)
+        && fs::read_to_string(&marker).is_ok_and(|extracted| extracted.trim() == version)
+    {
+        return Ok(Some(cache_dir));
+    }
+
+    let staging_dir = cache_dir.with_extension("partial");
+    if staging_dir.exists() {
+        fs::remove_dir_all(&staging_dir).map_err(|e| format!("清理临时目录失败: {e}"))?;
+    }
+    fs::create_dir_all(&staging_dir).map_err(|e| format!("创建内置插件目录失败: {e}"))?;
+
+    let extracted = if resource_dir.is_dir() {
+        copy_resource_dir(&resource_dir, &staging_dir)
+    } else {
+        copy_indexed_assets(app, &resource_dir, &staging_dir)
+    };
+    match extracted {
+        Ok(true) => {}
+        Ok(false) => {
+            let _ = fs::remove_dir_all(&staging_dir);
+            return Ok(None);
+        }
+        Err(err) => {
+            let _ = fs::remove_dir_all(&staging_dir);
+            return Err(err);
+        }
+    }
+
+    fs::write(staging_dir.join(EXTRACTED_VERSION_MARKER), &version)
+        .map_err(|e| format!("记录内置插件版本失败: {e}"))?;
+    if cache_dir.exists() {
+        fs::remove_dir_all(&cache_dir).map_err(|e| format!("清理旧的内置插件失败: {e}"))?;
+    }
+    fs::rename(&staging_dir, &cache_dir).map_err(|e| format!("替换内置插件目录失败: {e}"))?;
+
+    eprintln!(
+        "[truidide::plugins] 内置插件已解压到 {} (版本 {version})",
+        cache_dir.display()
+    );
+    Ok(Some(cache_dir))
+}
+
+fn copy_resource_dir(resource_dir: &Path, destination: &Path) -> Result<bool, String> {
+    let entries = fs::read_dir(resource_dir).map_err(|e| format!("读取内置插件目录失败: {e}"))?;
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("读取内置插件目录失败: {e}"))?;
+        if entry.file_name() == BUILT_IN_INDEX {
+            continue;
+        }
+        copy_entry_recursive(&entry.path(), &destination.join(entry.file_name()))?;
+    }
+    Ok(true)
+}
+
+// Returns `false` when the resources contain no index, i.e. no built-ins.
+fn copy_indexed_assets(
+    app: &AppHandle,
+    resource_dir: &Path,
+    destination: &Path,
+) -> Result<bool, String> {
+    let Ok(index) = app.fs().read(resource_dir.join(BUILT_IN_INDEX)) else {
+        return Ok(false);
+    };
+    let index: BuiltInIndex =
+        serde_json::from_slice(&index).map_err(|e| format!("解析内置插件索引失败: {e}"))?;
+
+    for file in &index.files {
+        let target = destination.join(index_relative_path(file)?);
+        let data = app
+            .fs()
+            .read(resource_dir.join(file))
+            .map_err(|e| format!("读取内置插件文件 {file} 失败: {e}"))?;
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("创建目录失败: {e}"))?;
+        }
+        fs::write(&target, data).map_err(|e| format!("写入内置插件文件 {file} 失败: {e}"))?;
+    }
+    for file in &index.executables {
+        make_executable(&destination.join(index_relative_path(file)?))?;
+    }
+    Ok(true)
+}
+
+fn index_relative_path(raw: &str) -> Result<&Path, String> {
+    let path = Path::new(raw);
+    if path
+        .components()
+        .any(|component| !matches!(component, Component::Normal(_)))
+    {
+        return Err(format!("内置插件索引中的路径无效: {raw}"));
+    }
+    Ok(path)
+}
@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Emitter};
+use tokio::sync::{mpsc, RwLock};
+
+use super::PluginManifest;
+
+const EVENT_BUS_MESSAGE: &str = "truidide://plugins/bus";
+
+/// Topics a plugin may use besides its own `<plugin-id>/` namespace.
+///
+/// Patterns match a topic exactly, or by prefix when they end with `*`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BusPermissions {
+    #[serde(default)]
+    pub publish: Vec<String>,
+    #[serde(default)]
+    pub subscribe: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BusMessage {
+    pub topic: String,
+    pub source_plugin_id: String,
+    pub payload: Value,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct WebExtensionBusPayload<'a> {
+    target_plugin_id: &'a str,
+    #[serde(flatten)]
+    message: &'a BusMessage,
+}
+
+/// Where messages for a subscriber are delivered.
+#[derive(Clone)]
+pub enum BusSink {
+    /// Written to the language server as a `truidide/message` notification.
+    LspSession(mpsc::Sender<Vec<u8>>),
+    /// Emitted to the frontend, which forwards it to the extension webview.
+    WebExtension,
+}
+
+struct Subscriber {
+    plugin_id: String,
+    topics: Vec<String>,
+    sink: BusSink,
+}
+
+/// Topic based publish/subscribe bus shared by all plugin sessions.
+#[derive(Default)]
+pub struct MessageBus {
+    subscribers: RwLock<HashMap<String, Subscriber>>,
+}
+
+fn topic_matches(pattern: &str, topic: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => topic.starts_with(prefix),
+        None => pattern == topic,
+    }
+}
+
+fn is_allowed(plugin_id: &str, patterns: &[String], topic: &str) -> bool {
+    topic.starts_with(&format!("{plugin_id}/"))
+        || patterns.iter().any(|pattern| topic_matches(pattern, topic))
+}
+
+fn validate_topic(topic: &str) -> Result<(), String> {
+    let trimmed = topic.trim();
+    if trimmed.is_empty() || trimmed != topic {
+        return Err("主题不能为空或包含首尾空白".into());
+    }
+    if topic.strip_suffix('*').unwrap_or(topic).contains('*') {
+        return Err(format!("主题 {topic} 仅允许在末尾使用通配符"));
+    }
+    Ok(())
+}
+
+impl MessageBus {
+    /// Subscribe `subscriber_key` (a session or extension) to `topic`.
+    pub async fn subscribe(
+        &self,
+        manifest: &PluginManifest,
+        subscriber_key: &str,
+        topic: &str,
+        sink: BusSink,
+    ) -> Result<(), String> {
+        validate_topic(topic)?;
+        if !is_allowed(&manifest.id, &manifest.bus.subscribe, topic) {
+            return Err(format!("插件 {} 无权订阅主题 {}", manifest.id, topic));
+        }
+
+        let mut subscribers = self.subscribers.write().await;
+        let entry = subscribers
+            .entry(subscriber_key.to_string())
+            .or_insert_with(|| Subscriber {
+                plugin_id: manifest.id.clone(),
+                topics: Vec::new(),
+                sink,
+            });
+        if !entry.topics.iter().any(|existing| existing == topic) {
+            entry.topics.push(topic.to_string());
+        }
+        Ok(())
+    }
+
+    pub async fn unsubscribe(&self, subscriber_key: &str, topic: &str) {
+        let mut subscribers = self.subscribers.write().await;
+        if let Some(subscriber) = subscribers.get_mut(subscriber_key) {
+            subscriber.topics.retain(|existing| existing != topic);
+            if subscriber.topics.is_empty() {
+                subscribers.remove(subscriber_key);
+            }
+        }
+    }
+
+    /// Drop every subscription held by `subscriber_key`.
+    pub async fn remove_subscriber(&self, subscriber_key: &str) {
+        self.subscribers.write().await.remove(subscriber_key);
+    }
+
+    /// Publish a message and return how many subscribers received it.
+    pub async fn publish(
+        &self,
+        app: &AppHandle,
+        manifest: &PluginManifest,
+        topic: &str,
+        payload: Value,
+    ) -> Result<usize, String> {
+        validate_topic(topic)?;
+        if topic.ends_with('*') {
+            return Err("发布的主题不能包含通配符".into());
+        }
+        if !is_allowed(&manifest.id, &manifest.bus.publish, topic) {
+            return Err(format!("插件 {} 无权发布主题 {}", manifest.id, topic));
+        }
+
+        let message = BusMessage {
+            topic: topic.to_string(),
+            source_plugin_id: manifest.id.clone(),
+            payload,
+        };
+        let targets = {
+            let subscribers = self.subscribers.read().await;
+            subscribers
+                .values()
+                .filter(|subscriber| {
+                    subscriber
+                        .topics
+                        .iter()
+                        .any(|pattern| topic_matches(pattern, topic))
+                })
+                .map(|subscriber| (subscriber.plugin_id.clone(), subscriber.sink.clone()))
+                .collect::<Vec<_>>()
+        };
+
+        let mut delivered = 0;
+        for (plugin_id, sink) in targets {
+            let ok = match sink {
+                BusSink::LspSession(write_tx) => {
+                    let body = json!({
+                        "jsonrpc": "2.0",
+                        "method": "truidide/message",
+                        "params": &message,
+                    });
+                    let payload = serde_json::to_vec(&body).unwrap_or_default();
+                    let mut framed =
+                        format!("Content-Length: {}\r\n\r\n", payload.len()).into_bytes();
+                    framed.extend_from_slice(&payload);
+                    // Never block the publisher on a slow subscriber.
+                    write_tx.try_send(framed).is_ok()
+                }
+                BusSink::WebExtension => app
+                    .emit(
+                        EVENT_BUS_MESSAGE,
+                        &WebExtensionBusPayload {
+                            target_plugin_id: &plugin_id,
+                            message: &message,
+                        },
+                    )
+                    .is_ok(),
+            };
+            if ok {
+                delivered += 1;
+            } else {
+                eprintln!(
+                    "[truidide::plugins] 消息总线投递失败 (topic {} -> {})",
+                    topic, plugin_id
+                );
+            }
+        }
+
+        Ok(delivered)
+    }
+}
@@ -0,0 +1,416 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::Emitter;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, BufWriter};
+use tokio::process::{Child, ChildStderr, ChildStdin, ChildStdout};
+use tokio::sync::{mpsc, oneshot};
+use uuid::Uuid;
+
+use super::health::record_stderr;
+use super::lsp_host::{
+    extract_exit_details, frame_message, read_framed_message, resolve_workspace_path,
+    spawn_plugin_process, PluginProcessSpec, ReadMessageError,
+};
+use super::{DapPluginManifest, PathMapping, PluginHost, PluginKind, PluginStatus};
+
+const EVENT_DAP_MESSAGE: &str = "truidide://dap/message";
+const EVENT_DAP_STDERR: &str = "truidide://dap/stderr";
+const EVENT_DAP_EXIT: &str = "truidide://dap/exit";
+
+pub(super) struct DapSessionRecord {
+    pub plugin_id: String,
+    pub debug_type: String,
+    pub workspace_path: PathBuf,
+    write_tx: Option<mpsc::Sender<Vec<u8>>>,
+    kill_tx: Option<oneshot::Sender<()>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartDapSessionArgs {
+    pub plugin_id: String,
+    /// Debug type of the launch configuration; defaults to the first one declared.
+    #[serde(default)]
+    pub debug_type: Option<String>,
+    /// Absolute path to the workspace/project folder.
+    pub workspace_path: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartDapSessionResponse {
+    pub session_id: String,
+    pub plugin_id: String,
+    pub debug_type: String,
+    /// Needed to translate `program`, `cwd` and breakpoint paths on Android.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path_mapping: Option<PathMapping>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DapSessionIdArgs {
+    pub session_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DapSendPayload {
+    pub session_id: String,
+    pub payload: Value,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DapMessagePayload {
+    session_id: String,
+    plugin_id: String,
+    debug_type: String,
+    body: Value,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DapStderrPayload {
+    session_id: String,
+    plugin_id: String,
+    debug_type: String,
+    data: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DapExitPayload {
+    session_id: String,
+    plugin_id: String,
+    debug_type: String,
+    status_code: Option<i32>,
+    signal: Option<i32>,
+}
+
+impl<'a> From<&'a DapPluginManifest> for PluginProcessSpec<'a> {
+    fn from(manifest: &'a DapPluginManifest) -> Self {
+        Self {
+            label: "DAP",
+            command: &manifest.command,
+            args: &manifest.args,
+            env: &manifest.env,
+            cwd: manifest.cwd.as_deref(),
+            plugin_mount_path: manifest.plugin_mount_path.as_deref(),
+            workspace_mount_path: manifest.workspace_mount_path.as_deref(),
+            workspace_cwd: false,
+            ipc_fd: None,
+            additional_workspaces: &[],
+        }
+    }
+}
+
+/// Request asking an adapter to end the debug session and its debuggee.
+pub(super) fn disconnect_request() -> Value {
+    json!({
+        "seq": 0,
+        "type": "request",
+        "command": "disconnect",
+        "arguments": { "terminateDebuggee": true },
+    })
+}
+
+impl PluginHost {
+    /// Spawn a debug adapter. The adapter speaks DAP over stdio; the frontend
+    /// drives the `initialize`/`launch` handshake itself.
+    pub async fn start_dap_session(
+        &self,
+        args: StartDapSessionArgs,
+    ) -> Result<StartDapSessionResponse, String> {
+        let plugin = self
+            .find_plugin(&args.plugin_id)
+            .await
+            .ok_or_else(|| format!("未找到插件 {}", args.plugin_id))?;
+        let PluginKind::Dap(manifest) = &plugin.manifest.kind else {
+            return Err(format!("插件 {} 不是调试适配器", args.plugin_id));
+        };
+
+        if !plugin.manifest.enabled {
+            return Err(format!("插件 {} 当前被禁用", plugin.manifest.id));
+        }
+        if let PluginStatus::Incompatible { reason } = &plugin.status {
+            return Err(format!("插件 {} 不可用: {}", plugin.manifest.id, reason));
+        }
+
+        let debug_type = match args.debug_type {
+            Some(debug_type) => {
+                if !manifest.debug_types.contains(&debug_type) {
+                    return Err(format!(
+                        "插件 {} 不支持调试类型 {}",
+                        plugin.manifest.id, debug_type
+                    ));
+                }
+                debug_type
+            }
+            None => manifest
+                .debug_types
+                .first()
+                .cloned()
+                .ok_or_else(|| "插件未声明调试类型".to_string())?,
+        };
+
+        let workspace_path = resolve_workspace_path(self.app(), &args.workspace_path)?;
+        let session_id = Uuid::new_v4().to_string();
+
+        let (mut child, path_mapping) = spawn_plugin_process(
+            self.app(),
+            &plugin,
+            &PluginProcessSpec::from(manifest),
+            &workspace_path,
+            &session_id,
+        )
+        .await?;
+
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| "无法获取调试适配器的标准输入".to_string())?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| "无法获取调试适配器的标准输出".to_string())?;
+        let stderr = child
+            .stderr
+            .take()
+            .ok_or_else(|| "无法获取调试适配器的标准错误".to_string())?;
+
+        let (write_tx, write_rx) = mpsc::channel::<Vec<u8>>(32);
+        let (kill_tx, kill_rx) = oneshot::channel::<()>();
+
+        let plugin_id = plugin.manifest.id.clone();
+        self.dap_sessions().write().await.insert(
+            session_id.clone(),
+            DapSessionRecord {
+                plugin_id: plugin_id.clone(),
+                debug_type: debug_type.clone(),
+                workspace_path,
+                write_tx: Some(write_tx),
+                kill_tx: Some(kill_tx),
+            },
+        );
+
+        #[cfg(debug_assertions)]
+        eprintln!(
+            "[truidide::dap] session {} started (plugin: {} type: {})",
+            session_id, plugin_id, debug_type
+        );
+
+        self.spawn_dap_writer_task(&session_id, stdin, write_rx);
+        self.spawn_dap_reader_task(&session_id, &plugin_id, &debug_type, stdout);
+        self.spawn_dap_stderr_task(&session_id, &plugin_id, &debug_type, stderr);
+        self.spawn_dap_wait_task(&session_id, &plugin_id, &debug_type, child, kill_rx);
+
+        Ok(StartDapSessionResponse {
+            session_id,
+            plugin_id,
+            debug_type,
+            path_mapping,
+        })
+    }
+
+    pub async fn send_dap_payload(&self, args: DapSendPayload) -> Result<(), String> {
+        let tx = {
+            let sessions = self.dap_sessions().read().await;
+            let Some(record) = sessions.get(&args.session_id) else {
+                return Err(format!("找不到调试会话 {}", args.session_id));
+            };
+            let Some(write_tx) = record.write_tx.as_ref() else {
+                return Err("调试会话正在关闭，无法发送消息".into());
+            };
+            write_tx.clone()
+        };
+
+        let payload =
+            serde_json::to_vec(&args.payload).map_err(|e| format!("序列化 DAP 负载失败: {e}"))?;
+        tx.send(frame_message(&payload))
+            .await
+            .map_err(|e| format!("发送 DAP 消息失败: {e}"))
+    }
+
+    pub async fn stop_dap_session(&self, args: DapSessionIdArgs) -> Result<(), String> {
+        let kill_tx = {
+            let mut sessions = self.dap_sessions().write().await;
+            let Some(record) = sessions.get_mut(&args.session_id) else {
+                return Ok(());
+            };
+            record.write_tx.take();
+            record.kill_tx.take()
+        };
+
+        if let Some(kill_tx) = kill_tx {
+            let _ = kill_tx.send(());
+        }
+        Ok(())
+    }
+
+    fn spawn_dap_writer_task(
+        &self,
+        session_id: &str,
+        stdin: ChildStdin,
+        mut write_rx: mpsc::Receiver<Vec<u8>>,
+    ) {
+        let mut writer = BufWriter::new(stdin);
+        let session_id = session_id.to_string();
+
+        tokio::spawn(async move {
+            while let Some(message) = write_rx.recv().await {
+                let result = match writer.write_all(&message).await {
+                    Ok(()) => writer.flush().await,
+                    Err(err) => Err(err),
+                };
+                if let Err(err) = result {
+                    eprintln!("[truidide::dap] 调试会话 {} 写入失败: {}", session_id, err);
+                    break;
+                }
+            }
+            let _ = writer.shutdown().await;
+        });
+    }
+
+    fn spawn_dap_reader_task(
+        &self,
+        session_id: &str,
+        plugin_id: &str,
+        debug_type: &str,
+        stdout: ChildStdout,
+    ) {
+        let app = self.app().clone();
+        let session_id = session_id.to_string();
+        let plugin_id = plugin_id.to_string();
+        let debug_type = debug_type.to_string();
+
+        tokio::spawn(async move {
+            let mut reader = BufReader::new(stdout);
+            loop {
+                match read_framed_message(&mut reader).await {
+                    Ok(body) => match serde_json::from_slice::<Value>(&body) {
+                        Ok(value) => {
+                            let payload = DapMessagePayload {
+                                session_id: session_id.clone(),
+                                plugin_id: plugin_id.clone(),
+                                debug_type: debug_type.clone(),
+                                body: value,
+                            };
+                            if let Err(err) = app.emit(EVENT_DAP_MESSAGE, &payload) {
+                                eprintln!(
+                                    "[truidide::dap] 广播 DAP 消息失败 (session {}): {}",
+                                    session_id, err
+                                );
+                            }
+                        }
+                        Err(_) => eprintln!(
+                            "[truidide::dap] 无法解析 DAP 消息 (session {}): {}",
+                            session_id,
+                            String::from_utf8_lossy(&body)
+                        ),
+                    },
+                    Err(ReadMessageError::Eof) => break,
+                    Err(ReadMessageError::Io(err)) => {
+                        eprintln!(
+                            "[truidide::dap] 读取 DAP 消息失败 (session {}): {}",
+                            session_id, err
+                        );
+                        break;
+                    }
+                    Err(ReadMessageError::Malformed(headers)) => {
+                        eprintln!(
+                            "[truidide::dap] 收到格式错误的 DAP 消息 (session {}): {}",
+                            session_id, headers
+                        );
+                    }
+                }
+            }
+        });
+    }
+
+    fn spawn_dap_stderr_task(
+        &self,
+        session_id: &str,
+        plugin_id: &str,
+        debug_type: &str,
+        stderr: ChildStderr,
+    ) {
+        let app = self.app().clone();
+        let session_id = session_id.to_string();
+        let plugin_id = plugin_id.to_string();
+        let debug_type = debug_type.to_string();
+
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(stderr).lines();
+            loop {
+                match lines.next_line().await {
+                    Ok(Some(line)) => {
+                        record_stderr(&plugin_id, &line);
+                        let payload = DapStderrPayload {
+                            session_id: session_id.clone(),
+                            plugin_id: plugin_id.clone(),
+                            debug_type: debug_type.clone(),
+                            data: line,
+                        };
+                        let _ = app.emit(EVENT_DAP_STDERR, &payload);
+                    }
+                    Ok(None) => break,
+                    Err(err) => {
+                        eprintln!(
+                            "[truidide::dap] 读取 DAP stderr 失败 (session {}): {}",
+                            session_id, err
+                        );
+                        break;
+                    }
+                }
+            }
+        });
+    }
+
+    fn spawn_dap_wait_task(
+        &self,
+        session_id: &str,
+        plugin_id: &str,
+        debug_type: &str,
+        mut child: Child,
+        mut kill_rx: oneshot::Receiver<()>,
+    ) {
+        let host = self.clone();
+        let session_id = session_id.to_string();
+        let plugin_id = plugin_id.to_string();
+        let debug_type = debug_type.to_string();
+
+        tokio::spawn(async move {
+            let status = tokio::select! {
+                _ = &mut kill_rx => {
+                    if let Err(err) = child.kill().await {
+                        eprintln!(
+                            "[truidide::dap] 终止调试适配器失败 (session {}): {}",
+                            session_id, err
+                        );
+                    }
+                    child.wait().await
+                }
+                status = child.wait() => status,
+            };
+
+            host.dap_sessions().write().await.remove(&session_id);
+            let (status_code, signal) = extract_exit_details(status.ok().as_ref());
+            let payload = DapExitPayload {
+                session_id: session_id.clone(),
+                plugin_id,
+                debug_type,
+                status_code,
+                signal,
+            };
+            if let Err(err) = host.app().emit(EVENT_DAP_EXIT, &payload) {
+                eprintln!(
+                    "[truidide::dap] 广播调试会话退出失败 (session {}): {}",
+                    session_id, err
+                );
+            }
+        });
+    }
+}
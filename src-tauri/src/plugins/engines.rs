@@ -0,0 +1,95 @@
+use regex::Regex;
+use tauri::AppHandle;
+
+use super::manifest::parse_version_requirement;
+use super::PluginManifest;
+use crate::exec::{self, RunCommandArgs};
+
+const RUNTIME_PROBE_TIMEOUT_SECS: u64 = 15;
+
+// Directory the `--version` probes run in; a guest path on Android.
+#[cfg(target_os = "android")]
+fn probe_cwd() -> String {
+    "/root".to_string()
+}
+
+#[cfg(not(target_os = "android"))]
+fn probe_cwd() -> String {
+    std::env::temp_dir().to_string_lossy().into_owned()
+}
+
+// Pull `18.17.1` out of output such as `v18.17.1` or `Python 3.11`, padding
+// missing components so semver can parse it.
+fn extract_version(output: &str) -> Option<semver::Version> {
+    let pattern = Regex::new(r"(\d+)(?:\.(\d+))?(?:\.(\d+))?").ok()?;
+    let captures = pattern.captures(output)?;
+    let part = |index: usize| {
+        captures
+            .get(index)
+            .map_or("0", |part| part.as_str())
+            .to_string()
+    };
+    semver::Version::parse(&format!("{}.{}.{}", part(1), part(2), part(3))).ok()
+}
+
+async fn check_runtime(app: &AppHandle, name: &str, raw_requirement: &str) -> Result<(), String> {
+    let requirement = parse_version_requirement(raw_requirement)
+        .map_err(|e| format!("插件清单中 engines.runtimes.{name} 的版本要求无效: {e}"))?;
+
+    let result = exec::execute(
+        app,
+        RunCommandArgs {
+            run_id: None,
+            cwd: probe_cwd(),
+            program: name.to_string(),
+            args: vec!["--version".into()],
+            env: Default::default(),
+            timeout_secs: Some(RUNTIME_PROBE_TIMEOUT_SECS),
+        },
+        |_, _| {},
+    )
+    .await;
+    let result = match result {
+        Ok(result) if result.success => result,
+        Ok(result) => {
+            let detail = result.stderr.trim();
+            return Err(format!(
+                "插件需要 {name} {raw_requirement}，但运行 {name} --version 失败{}；请先在终端中安装 {name}",
+                if detail.is_empty() {
+                    String::new()
+                } else {
+                    format!(": {detail}")
+                }
+            ));
+        }
+        Err(err) => {
+            return Err(format!(
+                "插件需要 {name} {raw_requirement}，但未找到 {name} ({err})；请先在终端中安装 {name}"
+            ));
+        }
+    };
+
+    // Some tools (e.g. older `java`) print their version to stderr.
+    let version = extract_version(&result.stdout).or_else(|| extract_version(&result.stderr));
+    match version {
+        Some(version) if requirement.matches(&version) => Ok(()),
+        Some(version) => Err(format!(
+            "插件需要 {name} {raw_requirement}，当前版本为 {version}；请升级 {name}"
+        )),
+        None => Err(format!(
+            "无法识别 {name} 的版本，插件需要 {raw_requirement}"
+        )),
+    }
+}
+
+/// Check the runtimes listed in `engines.runtimes` by running each with
+/// `--version`, inside proot on Android.
+pub(super) async fn check_runtimes(
+    app: &AppHandle,
+    manifest: &PluginManifest,
+) -> Result<(), String> {
+    for (name, requirement) in &manifest.engines.runtimes {
+        check_runtime(app, name, requirement).await?;
+    }
+    Ok(())
+}
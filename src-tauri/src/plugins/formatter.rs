@@ -0,0 +1,222 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use super::lsp_host::{resolve_workspace_path, run_plugin_process, PluginProcessSpec};
+use super::{FormatterPluginManifest, PluginHost, PluginKind, PluginStatus};
+
+const DEFAULT_FORMAT_TIMEOUT_SECS: u64 = 30;
+const MAX_STDERR_CHARS: usize = 2000;
+/// Placeholder in formatter args replaced by the document path.
+const FILE_PLACEHOLDER: &str = "${file}";
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormatDocumentArgs {
+    pub language_id: String,
+    /// File to format. Its current content on disk is used unless `content`
+    /// is given, which allows formatting unsaved buffers.
+    #[serde(default)]
+    pub path: Option<String>,
+    #[serde(default)]
+    pub content: Option<String>,
+    /// Project root; formatters run there so they pick up its config files.
+    #[serde(default)]
+    pub workspace_path: Option<String>,
+    /// Use this formatter instead of the first one registered for the language.
+    #[serde(default)]
+    pub plugin_id: Option<String>,
+}
+
+/// A single replacement, in UTF-16 offsets as used by the editor.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormatEdit {
+    pub from: usize,
+    pub to: usize,
+    pub insert: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormatDocumentResponse {
+    pub plugin_id: String,
+    pub formatted: String,
+    /// Minimal edit turning the input into `formatted`; `None` if unchanged.
+    pub edit: Option<FormatEdit>,
+}
+
+impl<'a> From<&'a FormatterPluginManifest> for PluginProcessSpec<'a> {
+    fn from(manifest: &'a FormatterPluginManifest) -> Self {
+        Self {
+            label: "Formatter",
+            command: &manifest.command,
+            args: &manifest.args,
+            env: &manifest.env,
+            cwd: manifest.cwd.as_deref(),
+            plugin_mount_path: manifest.plugin_mount_path.as_deref(),
+            workspace_mount_path: manifest.workspace_mount_path.as_deref(),
+            workspace_cwd: true,
+            ipc_fd: None,
+            additional_workspaces: &[],
+        }
+    }
+}
+
+#[cfg(target_os = "android")]
+fn resolve_document_path(app: &tauri::AppHandle, raw: &str) -> PathBuf {
+    crate::android::proot::resolve_guest_path(app, raw).unwrap_or_else(|_| PathBuf::from(raw))
+}
+
+#[cfg(not(target_os = "android"))]
+fn resolve_document_path(_app: &tauri::AppHandle, raw: &str) -> PathBuf {
+    PathBuf::from(raw)
+}
+
+// Value for `${file}`: the document path relative to the workspace, which is
+// also the formatter's working directory on every platform.
+fn file_argument(document: Option<&Path>, workspace: &Path) -> String {
+    let Some(document) = document else {
+        return "untitled".to_string();
+    };
+    document
+        .strip_prefix(workspace)
+        .unwrap_or(document)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+// Trim the common prefix and suffix so the editor only replaces what changed
+// and keeps cursor and folding state elsewhere.
+fn minimal_edit(original: &str, formatted: &str) -> Option<FormatEdit> {
+    if original == formatted {
+        return None;
+    }
+    let prefix = original
+        .char_indices()
+        .zip(formatted.chars())
+        .take_while(|((_, a), b)| a == b)
+        .last()
+        .map_or(0, |((index, ch), _)| index + ch.len_utf8());
+    let suffix = original[prefix..]
+        .chars()
+        .rev()
+        .zip(formatted[prefix..].chars().rev())
+        .take_while(|(a, b)| a == b)
+        .map(|(ch, _)| ch.len_utf8())
+        .sum::<usize>();
+
+    let utf16_len = |text: &str| text.encode_utf16().count();
+    let from = utf16_len(&original[..prefix]);
+    Some(FormatEdit {
+        from,
+        to: from + utf16_len(&original[prefix..original.len() - suffix]),
+        insert: formatted[prefix..formatted.len() - suffix].to_string(),
+    })
+}
+
+impl PluginHost {
+    /// Pipe a document through a formatter plugin: content goes to stdin and
+    /// the formatted text is read from stdout.
+    pub async fn format_document(
+        &self,
+        args: FormatDocumentArgs,
+    ) -> Result<FormatDocumentResponse, String> {
+        let plugin = match &args.plugin_id {
+            Some(plugin_id) => self
+                .find_plugin(plugin_id)
+                .await
+                .ok_or_else(|| format!("未找到插件 {plugin_id}"))?,
+            None => self
+                .list_plugins()
+                .await
+                .into_iter()
+                .find(|plugin| {
+                    plugin.manifest.enabled
+                        && plugin.status == PluginStatus::Ready
+                        && matches!(&plugin.manifest.kind, PluginKind::Formatter(manifest)
+                            if manifest.language_ids.contains(&args.language_id))
+                })
+                .ok_or_else(|| format!("没有可用于 {} 的格式化插件", args.language_id))?,
+        };
+        let PluginKind::Formatter(manifest) = &plugin.manifest.kind else {
+            return Err(format!("插件 {} 不是格式化插件", plugin.manifest.id));
+        };
+        if !plugin.manifest.enabled {
+            return Err(format!("插件 {} 当前被禁用", plugin.manifest.id));
+        }
+        if let PluginStatus::Incompatible { reason } = &plugin.status {
+            return Err(format!("插件 {} 不可用: {}", plugin.manifest.id, reason));
+        }
+
+        let document = args
+            .path
+            .as_deref()
+            .map(|raw| resolve_document_path(self.app(), raw.trim()))
+            .map(|path| path.canonicalize().unwrap_or(path));
+        let original = match (args.content, &document) {
+            (Some(content), _) => content,
+            (None, Some(path)) => tokio::fs::read_to_string(path)
+                .await
+                .map_err(|e| format!("读取文件失败: {e}"))?,
+            (None, None) => return Err("需要提供文件路径或内容".into()),
+        };
+
+        let workspace = match &args.workspace_path {
+            Some(raw) => resolve_workspace_path(self.app(), raw)?,
+            None => document
+                .as_deref()
+                .and_then(Path::parent)
+                .map(Path::to_path_buf)
+                .unwrap_or_else(|| plugin.root_dir.clone()),
+        };
+
+        let file_arg = file_argument(document.as_deref(), &workspace);
+        let process_args = manifest
+            .args
+            .iter()
+            .map(|arg| arg.replace(FILE_PLACEHOLDER, &file_arg))
+            .collect::<Vec<_>>();
+        let spec = PluginProcessSpec {
+            args: &process_args,
+            ..PluginProcessSpec::from(manifest)
+        };
+        let timeout =
+            Duration::from_secs(manifest.timeout_secs.unwrap_or(DEFAULT_FORMAT_TIMEOUT_SECS));
+        let output = run_plugin_process(
+            self.app(),
+            &plugin,
+            &spec,
+            &workspace,
+            Some(original.as_bytes()),
+            timeout,
+        )
+        .await?;
+        let status = output.status;
+
+        if !status.success() {
+            let message = String::from_utf8_lossy(&output.stderr);
+            let message = message.trim();
+            let message = match message.char_indices().nth(MAX_STDERR_CHARS) {
+                Some((index, _)) => &message[..index],
+                None => message,
+            };
+            return Err(format!(
+                "格式化失败 ({}): {}",
+                status
+                    .code()
+                    .map_or_else(|| "signal".to_string(), |code| code.to_string()),
+                message
+            ));
+        }
+
+        let formatted = String::from_utf8(output.stdout)
+            .map_err(|_| "格式化输出不是有效的 UTF-8".to_string())?;
+        Ok(FormatDocumentResponse {
+            plugin_id: plugin.manifest.id.clone(),
+            edit: minimal_edit(&original, &formatted),
+            formatted,
+        })
+    }
+}
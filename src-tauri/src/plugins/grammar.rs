@@ -0,0 +1,79 @@
+use serde::Serialize;
+
+use super::web_extension::{resolve_inside, web_extension_url};
+use super::{DiscoveredPlugin, GrammarContribution, PluginHost, PluginKind};
+
+/// A grammar ready to be fetched by the editor over the plugin protocol.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GrammarInfo {
+    pub plugin_id: String,
+    pub language_id: String,
+    pub file_extensions: Vec<String>,
+    pub wasm_url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub highlights_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locals_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub injections_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags_url: Option<String>,
+}
+
+fn grammar_info(
+    plugin: &DiscoveredPlugin,
+    grammar: &GrammarContribution,
+) -> Result<GrammarInfo, String> {
+    let plugin_id = &plugin.manifest.id;
+    // Check up front so a broken pack is reported here rather than as a
+    // failed fetch inside the editor.
+    let asset_url = |relative: &str| -> Result<String, String> {
+        resolve_inside(&plugin.root_dir, relative)?;
+        Ok(web_extension_url(plugin_id, relative))
+    };
+    let optional_url = |relative: &Option<String>| -> Result<Option<String>, String> {
+        relative.as_deref().map(asset_url).transpose()
+    };
+
+    Ok(GrammarInfo {
+        plugin_id: plugin_id.clone(),
+        language_id: grammar.language_id.clone(),
+        file_extensions: grammar
+            .file_extensions
+            .iter()
+            .map(|ext| ext.trim_start_matches('.').to_ascii_lowercase())
+            .collect(),
+        wasm_url: asset_url(&grammar.wasm)?,
+        highlights_url: optional_url(&grammar.queries.highlights)?,
+        locals_url: optional_url(&grammar.queries.locals)?,
+        injections_url: optional_url(&grammar.queries.injections)?,
+        tags_url: optional_url(&grammar.queries.tags)?,
+    })
+}
+
+impl PluginHost {
+    /// Grammars contributed by enabled grammar plugins. Languages built into
+    /// the editor take precedence over these on the frontend.
+    pub async fn list_grammars(&self) -> Vec<GrammarInfo> {
+        let mut grammars = Vec::new();
+        for plugin in self.list_plugins().await {
+            if !plugin.is_usable() {
+                continue;
+            }
+            let PluginKind::Grammar(manifest) = &plugin.manifest.kind else {
+                continue;
+            };
+            for grammar in &manifest.grammars {
+                match grammar_info(&plugin, grammar) {
+                    Ok(info) => grammars.push(info),
+                    Err(err) => eprintln!(
+                        "[truidide::plugins] 插件 {} 的语法 {} 无法加载: {}",
+                        plugin.manifest.id, grammar.language_id, err
+                    ),
+                }
+            }
+        }
+        grammars
+    }
+}
@@ -0,0 +1,94 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+use tauri::AppHandle;
+
+use super::PluginHost;
+
+// Enough for the usual "command not found" or stack trace tail.
+const STDERR_TAIL_LINES: usize = 20;
+
+static HEALTH: OnceCell<Mutex<HashMap<String, PluginHealth>>> = OnceCell::new();
+
+/// Runtime health of a plugin's processes since the app started.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginHealth {
+    /// Why the latest start failed; cleared by the next successful start.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    /// Times a process of the plugin exited without being asked to.
+    pub crash_count: u32,
+    /// Last stderr lines of the plugin's processes, oldest first.
+    pub recent_stderr: VecDeque<String>,
+}
+
+fn update(plugin_id: &str, apply: impl FnOnce(&mut PluginHealth) -> bool) -> bool {
+    let mut health = HEALTH
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    apply(health.entry(plugin_id.to_string()).or_default())
+}
+
+// Rebroadcast the plugin's summary so the manager shows the new status.
+fn publish(app: &AppHandle, plugin_id: &str) {
+    let app = app.clone();
+    let plugin_id = plugin_id.to_string();
+    tauri::async_runtime::spawn(async move {
+        let host = match PluginHost::obtain(&app) {
+            Ok(host) => host,
+            Err(_) => return,
+        };
+        if let Err(err) = host.publish_plugin_summary(&plugin_id).await {
+            eprintln!("[truidide::health] 广播插件 {plugin_id} 状态失败: {err}");
+        }
+    });
+}
+
+/// Record the outcome of starting one of `plugin_id`'s processes.
+pub(super) fn record_start<T>(app: &AppHandle, plugin_id: &str, result: &Result<T, String>) {
+    let changed = update(plugin_id, |health| {
+        let last_error = result.as_ref().err().cloned();
+        let changed = health.last_error != last_error;
+        health.last_error = last_error;
+        changed
+    });
+    if changed {
+        publish(app, plugin_id);
+    }
+}
+
+pub(super) fn record_crash(app: &AppHandle, plugin_id: &str) {
+    update(plugin_id, |health| {
+        health.crash_count += 1;
+        true
+    });
+    publish(app, plugin_id);
+}
+
+pub(super) fn record_stderr(plugin_id: &str, line: &str) {
+    let line = line.trim_end();
+    if line.is_empty() {
+        return;
+    }
+    update(plugin_id, |health| {
+        if health.recent_stderr.len() == STDERR_TAIL_LINES {
+            health.recent_stderr.pop_front();
+        }
+        health.recent_stderr.push_back(line.to_string());
+        false
+    });
+}
+
+/// `None` until one of the plugin's processes has been started.
+pub(crate) fn plugin_health(plugin_id: &str) -> Option<PluginHealth> {
+    HEALTH
+        .get()?
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .get(plugin_id)
+        .cloned()
+}
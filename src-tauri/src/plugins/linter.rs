@@ -0,0 +1,457 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Emitter};
+
+use super::lsp_host::{resolve_workspace_path, run_plugin_process, PluginProcessSpec};
+use super::{
+    DiagnosticSeverity, DiscoveredPlugin, LinterOutput, LinterPluginManifest, PathMapping,
+    PluginHost, PluginKind, PluginStatus, ProblemMatcher,
+};
+
+const EVENT_LINT_DIAGNOSTICS: &str = "truidide://lint/diagnostics";
+const DEFAULT_LINT_TIMEOUT_SECS: u64 = 30;
+const FILE_PLACEHOLDER: &str = "${file}";
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LintDocumentArgs {
+    pub path: String,
+    #[serde(default)]
+    pub language_id: Option<String>,
+    /// Unsaved content, used by linters that read stdin.
+    #[serde(default)]
+    pub content: Option<String>,
+    #[serde(default)]
+    pub workspace_path: Option<String>,
+    /// Run only this linter instead of every one matching the document.
+    #[serde(default)]
+    pub plugin_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LintDiagnostic {
+    /// 1-based, as reported by the linter.
+    pub line: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_line: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_column: Option<u32>,
+    pub severity: DiagnosticSeverity,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+}
+
+/// Diagnostics of one linter for one file. They replace whatever the same
+/// linter reported for that file before; an empty list clears them.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LintDiagnosticsPayload {
+    pub plugin_id: String,
+    pub file: String,
+    pub diagnostics: Vec<LintDiagnostic>,
+}
+
+impl<'a> From<&'a LinterPluginManifest> for PluginProcessSpec<'a> {
+    fn from(manifest: &'a LinterPluginManifest) -> Self {
+        Self {
+            label: "Linter",
+            command: &manifest.command,
+            args: &manifest.args,
+            env: &manifest.env,
+            cwd: manifest.cwd.as_deref(),
+            plugin_mount_path: manifest.plugin_mount_path.as_deref(),
+            workspace_mount_path: manifest.workspace_mount_path.as_deref(),
+            workspace_cwd: true,
+            ipc_fd: None,
+            additional_workspaces: &[],
+        }
+    }
+}
+
+fn matches_file_patterns(manifest: &LinterPluginManifest, path: &str) -> bool {
+    let file_name = Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    manifest.file_patterns.iter().any(|pattern| {
+        glob::Pattern::new(pattern)
+            .is_ok_and(|pattern| pattern.matches(path) || pattern.matches(&file_name))
+    })
+}
+
+fn parse_severity(raw: &str, fallback: DiagnosticSeverity) -> DiagnosticSeverity {
+    // Numeric levels follow eslint: 2 is an error, 1 a warning.
+    match raw.trim().to_ascii_lowercase().as_str() {
+        "2" | "error" | "err" | "e" | "fatal" => DiagnosticSeverity::Error,
+        "1" | "warning" | "warn" | "w" => DiagnosticSeverity::Warning,
+        "info" | "information" | "note" | "i" | "n" => DiagnosticSeverity::Info,
+        "hint" | "style" | "h" => DiagnosticSeverity::Hint,
+        _ => fallback,
+    }
+}
+
+// Problems keyed by the file path as printed by the linter, if any.
+type RawProblems = Vec<(Option<String>, LintDiagnostic)>;
+
+fn match_regex(
+    pattern: &str,
+    groups: RegexGroups,
+    output: &str,
+    fallback: DiagnosticSeverity,
+) -> Result<RawProblems, String> {
+    let regex = Regex::new(pattern).map_err(|e| format!("问题匹配正则无效: {e}"))?;
+    let mut problems = Vec::new();
+    for line in output.lines() {
+        let Some(captures) = regex.captures(line) else {
+            continue;
+        };
+        let group = |index: Option<usize>| {
+            index
+                .and_then(|index| captures.get(index))
+                .map(|m| m.as_str().to_string())
+        };
+        let number = |index: Option<usize>| group(index).and_then(|value| value.parse().ok());
+        let Some(line_number) = number(Some(groups.line)) else {
+            continue;
+        };
+        problems.push((
+            group(groups.file),
+            LintDiagnostic {
+                line: line_number,
+                column: number(groups.column),
+                end_line: number(groups.end_line),
+                end_column: number(groups.end_column),
+                severity: group(groups.severity)
+                    .map_or(fallback, |raw| parse_severity(&raw, fallback)),
+                message: group(Some(groups.message)).unwrap_or_default(),
+                code: group(groups.code),
+            },
+        ));
+    }
+    Ok(problems)
+}
+
+struct RegexGroups {
+    file: Option<usize>,
+    line: usize,
+    column: Option<usize>,
+    end_line: Option<usize>,
+    end_column: Option<usize>,
+    severity: Option<usize>,
+    message: usize,
+    code: Option<usize>,
+}
+
+fn json_string(value: &Value, pointer: Option<&str>) -> Option<String> {
+    match value.pointer(pointer?)? {
+        Value::String(text) => Some(text.clone()),
+        Value::Null => None,
+        other => Some(other.to_string()),
+    }
+}
+
+fn json_number(value: &Value, pointer: Option<&str>) -> Option<u32> {
+    let found = value.pointer(pointer?)?;
+    found
+        .as_u64()
+        .map(|number| number as u32)
+        .or_else(|| found.as_str()?.parse().ok())
+}
+
+fn match_json(
+    matcher: &ProblemMatcher,
+    output: &str,
+    fallback: DiagnosticSeverity,
+) -> Result<RawProblems, String> {
+    let ProblemMatcher::Json {
+        items,
+        messages,
+        file,
+        line,
+        column,
+        end_line,
+        end_column,
+        severity,
+        message,
+        code,
+    } = matcher
+    else {
+        return Ok(Vec::new());
+    };
+    if output.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let root: Value =
+        serde_json::from_str(output).map_err(|e| format!("解析 linter JSON 输出失败: {e}"))?;
+    let Some(entries) = root.pointer(items).and_then(Value::as_array) else {
+        return Err(format!("linter 输出中 {items:?} 不是数组"));
+    };
+
+    let to_diagnostic = |problem: &Value| -> Option<LintDiagnostic> {
+        Some(LintDiagnostic {
+            line: json_number(problem, Some(line.as_str()))?,
+            column: json_number(problem, column.as_deref()),
+            end_line: json_number(problem, end_line.as_deref()),
+            end_column: json_number(problem, end_column.as_deref()),
+            severity: json_string(problem, severity.as_deref())
+                .map_or(fallback, |raw| parse_severity(&raw, fallback)),
+            message: json_string(problem, Some(message.as_str())).unwrap_or_default(),
+            code: json_string(problem, code.as_deref()),
+        })
+    };
+
+    let mut problems = Vec::new();
+    for entry in entries {
+        match messages {
+            Some(pointer) => {
+                let file = json_string(entry, file.as_deref());
+                for problem in entry
+                    .pointer(pointer)
+                    .and_then(Value::as_array)
+                    .into_iter()
+                    .flatten()
+                {
+                    if let Some(diagnostic) = to_diagnostic(problem) {
+                        problems.push((file.clone(), diagnostic));
+                    }
+                }
+            }
+            None => {
+                if let Some(diagnostic) = to_diagnostic(entry) {
+                    problems.push((json_string(entry, file.as_deref()), diagnostic));
+                }
+            }
+        }
+    }
+    Ok(problems)
+}
+
+// Map a path printed by the linter back to the host. Relative paths are
+// relative to the workspace; on Android absolute ones may be guest paths.
+fn host_path(raw: &str, workspace: &Path, mapping: Option<&PathMapping>) -> PathBuf {
+    if let Some(mapping) = mapping {
+        if let Ok(rest) = Path::new(raw).strip_prefix(&mapping.guest_workspace) {
+            return Path::new(&mapping.host_workspace).join(rest);
+        }
+    }
+    let path = Path::new(raw);
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        workspace.join(path)
+    }
+}
+
+impl PluginHost {
+    /// Run the linters matching a document and emit their diagnostics on
+    /// `truidide://lint/diagnostics`, one event per linter and file.
+    pub async fn lint_document(
+        &self,
+        args: LintDocumentArgs,
+    ) -> Result<Vec<LintDiagnosticsPayload>, String> {
+        let linters = self
+            .list_plugins()
+            .await
+            .into_iter()
+            .filter(|plugin| match &args.plugin_id {
+                Some(plugin_id) => plugin.manifest.id == *plugin_id,
+                None => plugin.manifest.enabled && plugin.status == PluginStatus::Ready,
+            })
+            .filter(|plugin| match &plugin.manifest.kind {
+                PluginKind::Linter(manifest) => {
+                    args.plugin_id.is_some()
+                        || args
+                            .language_id
+                            .as_ref()
+                            .is_some_and(|id| manifest.language_ids.contains(id))
+                        || matches_file_patterns(manifest, &args.path)
+                }
+                _ => false,
+            })
+            .collect::<Vec<_>>();
+        if let Some(plugin_id) = &args.plugin_id {
+            if linters.is_empty() {
+                return Err(format!("插件 {plugin_id} 不是可用的 linter"));
+            }
+        }
+
+        let mut results = Vec::new();
+        for plugin in linters {
+            if !plugin.manifest.enabled {
+                return Err(format!("插件 {} 当前被禁用", plugin.manifest.id));
+            }
+            if let PluginStatus::Incompatible { reason } = &plugin.status {
+                return Err(format!("插件 {} 不可用: {}", plugin.manifest.id, reason));
+            }
+            let payloads = self.run_linter(&plugin, &args).await?;
+            for payload in payloads {
+                if let Err(err) = self.app().emit(EVENT_LINT_DIAGNOSTICS, &payload) {
+                    eprintln!("[truidide::lint] 广播诊断信息失败: {}", err);
+                }
+                results.push(payload);
+            }
+        }
+        Ok(results)
+    }
+
+    async fn run_linter(
+        &self,
+        plugin: &DiscoveredPlugin,
+        args: &LintDocumentArgs,
+    ) -> Result<Vec<LintDiagnosticsPayload>, String> {
+        let PluginKind::Linter(manifest) = &plugin.manifest.kind else {
+            return Ok(Vec::new());
+        };
+
+        #[cfg(target_os = "android")]
+        let document = crate::android::proot::resolve_guest_path(self.app(), args.path.trim())
+            .unwrap_or_else(|_| PathBuf::from(args.path.trim()));
+        #[cfg(not(target_os = "android"))]
+        let document = PathBuf::from(args.path.trim());
+        let document = document.canonicalize().unwrap_or(document);
+
+        let workspace = match &args.workspace_path {
+            Some(raw) => resolve_workspace_path(self.app(), raw)?,
+            None => document
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_else(|| plugin.root_dir.clone()),
+        };
+
+        let file_arg = document
+            .strip_prefix(&workspace)
+            .unwrap_or(&document)
+            .to_string_lossy()
+            .replace('\\', "/");
+        let process_args = manifest
+            .args
+            .iter()
+            .map(|arg| arg.replace(FILE_PLACEHOLDER, &file_arg))
+            .collect::<Vec<_>>();
+        let spec = PluginProcessSpec {
+            args: &process_args,
+            ..PluginProcessSpec::from(manifest)
+        };
+
+        let content = match (&args.content, manifest.stdin) {
+            (Some(content), true) => Some(content.clone().into_bytes()),
+            (None, true) => Some(
+                tokio::fs::read(&document)
+                    .await
+                    .map_err(|e| format!("读取文件失败: {e}"))?,
+            ),
+            (_, false) => None,
+        };
+        let timeout =
+            Duration::from_secs(manifest.timeout_secs.unwrap_or(DEFAULT_LINT_TIMEOUT_SECS));
+        let output = run_plugin_process(
+            self.app(),
+            plugin,
+            &spec,
+            &workspace,
+            content.as_deref(),
+            timeout,
+        )
+        .await?;
+
+        // Linters usually exit non-zero when they find problems, so the exit
+        // status is not treated as a failure.
+        let stream = match manifest.output {
+            LinterOutput::Stdout => &output.stdout,
+            LinterOutput::Stderr => &output.stderr,
+        };
+        let text = String::from_utf8_lossy(stream);
+        let fallback = manifest.default_severity;
+        let problems = match &manifest.problem_matcher {
+            ProblemMatcher::Regex {
+                pattern,
+                file,
+                line,
+                column,
+                end_line,
+                end_column,
+                severity,
+                message,
+                code,
+            } => match_regex(
+                pattern,
+                RegexGroups {
+                    file: *file,
+                    line: *line,
+                    column: *column,
+                    end_line: *end_line,
+                    end_column: *end_column,
+                    severity: *severity,
+                    message: *message,
+                    code: *code,
+                },
+                &text,
+                fallback,
+            )?,
+            matcher @ ProblemMatcher::Json { .. } => match_json(matcher, &text, fallback)?,
+        };
+
+        // The linted document always gets an entry so stale diagnostics clear.
+        let mut payloads = vec![LintDiagnosticsPayload {
+            plugin_id: plugin.manifest.id.clone(),
+            file: args.path.clone(),
+            diagnostics: Vec::new(),
+        }];
+        for (file, diagnostic) in problems {
+            let key = match file {
+                Some(raw) => {
+                    let path = host_path(&raw, &workspace, output.path_mapping.as_ref());
+                    let path = path.canonicalize().unwrap_or(path);
+                    if path == document {
+                        args.path.clone()
+                    } else {
+                        path.to_string_lossy().into_owned()
+                    }
+                }
+                None => args.path.clone(),
+            };
+            match payloads.iter_mut().find(|payload| payload.file == key) {
+                Some(payload) => payload.diagnostics.push(diagnostic),
+                None => payloads.push(LintDiagnosticsPayload {
+                    plugin_id: plugin.manifest.id.clone(),
+                    file: key,
+                    diagnostics: vec![diagnostic],
+                }),
+            }
+        }
+        Ok(payloads)
+    }
+}
+
+/// Lint a file that was just saved with every linter whose `filePatterns`
+/// match it. Runs in the background; failures are only logged.
+pub fn lint_on_save(app: &AppHandle, path: String) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let host = match PluginHost::obtain(&app) {
+            Ok(host) => host,
+            Err(err) => {
+                eprintln!("[truidide::lint] {}", err);
+                return;
+            }
+        };
+        let args = LintDocumentArgs {
+            path,
+            language_id: None,
+            content: None,
+            workspace_path: None,
+            plugin_id: None,
+        };
+        if let Err(err) = host.lint_document(args).await {
+            eprintln!("[truidide::lint] 保存后检查失败: {}", err);
+        }
+    });
+}
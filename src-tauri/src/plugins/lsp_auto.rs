@@ -0,0 +1,88 @@
+use std::path::Path;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::{PluginHost, StartLspSessionArgs, StartLspSessionResponse};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnsureLspForFileArgs {
+    pub path: String,
+    pub project_path: String,
+    /// Overrides detection from the file extension.
+    #[serde(default)]
+    pub language_id: Option<String>,
+    /// Further roots of a multi-root workspace.
+    #[serde(default)]
+    pub additional_workspace_paths: Vec<String>,
+    #[serde(default)]
+    pub client_capabilities: Option<Value>,
+    #[serde(default)]
+    pub workspace_folders: Option<Value>,
+}
+
+/// Language id for `path`, matching the ids the editor uses.
+pub fn language_id_for_path(path: &str) -> &'static str {
+    let extension = Path::new(path)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "ts" | "tsx" | "mts" | "cts" => "typescript",
+        "js" | "jsx" | "mjs" | "cjs" => "javascript",
+        "json" => "json",
+        "jsonc" => "jsonc",
+        "css" => "css",
+        "html" | "htm" => "html",
+        "md" | "markdown" => "markdown",
+        "xml" => "xml",
+        "yml" | "yaml" => "yaml",
+        "toml" => "toml",
+        "py" | "pyi" => "python",
+        "java" => "java",
+        "kt" | "kts" => "kotlin",
+        "rs" => "rust",
+        "go" => "go",
+        "c" | "h" => "c",
+        "cc" | "cpp" | "cxx" | "hpp" | "hh" => "cpp",
+        "sh" | "bash" => "shellscript",
+        "lua" => "lua",
+        _ => "plaintext",
+    }
+}
+
+impl PluginHost {
+    /// Start the language server for a file that was just opened, or attach
+    /// to the one already serving the project (see `reused`).
+    ///
+    /// Resolves to `None` when no enabled plugin handles the language.
+    pub async fn ensure_lsp_for_file(
+        &self,
+        args: EnsureLspForFileArgs,
+    ) -> Result<Option<StartLspSessionResponse>, String> {
+        let language_id = args
+            .language_id
+            .clone()
+            .filter(|id| !id.trim().is_empty())
+            .unwrap_or_else(|| language_id_for_path(&args.path).to_string());
+        let Some(plugin) = self
+            .plugin_for_language(&language_id, Some(&args.project_path))
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        self.start_lsp_session(StartLspSessionArgs {
+            plugin_id: plugin.manifest.id.clone(),
+            language_id: Some(language_id),
+            workspace_path: args.project_path,
+            additional_workspace_paths: args.additional_workspace_paths,
+            client_capabilities: args.client_capabilities,
+            workspace_folders: args.workspace_folders,
+            initialization_options: None,
+        })
+        .await
+        .map(Some)
+    }
+}
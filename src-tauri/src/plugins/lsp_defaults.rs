@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use tauri::path::BaseDirectory;
+use tauri::{AppHandle, Manager};
+
+use super::PluginHost;
+
+const LANGUAGE_DEFAULTS_FILENAME: &str = "language-plugins.json";
+
+/// Plugins the user picked for languages that several LSP plugins serve.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LanguagePluginDefaults {
+    /// Language id -> plugin id.
+    #[serde(default)]
+    pub defaults: HashMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetLanguagePluginDefaultArgs {
+    pub language_id: String,
+    /// `None` falls back to plugin priority again.
+    #[serde(default)]
+    pub plugin_id: Option<String>,
+}
+
+fn defaults_path(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .resolve(LANGUAGE_DEFAULTS_FILENAME, BaseDirectory::AppData)
+        .map_err(|e| e.to_string())
+}
+
+pub fn load_language_plugin_defaults(app: &AppHandle) -> Result<LanguagePluginDefaults, String> {
+    let path = defaults_path(app)?;
+    if !path.exists() {
+        return Ok(LanguagePluginDefaults::default());
+    }
+    let data = fs::read_to_string(&path).map_err(|e| format!("读取语言默认插件失败: {e}"))?;
+    serde_json::from_str(&data).map_err(|e| format!("解析语言默认插件失败: {e}"))
+}
+
+fn save_language_plugin_defaults(
+    app: &AppHandle,
+    defaults: &LanguagePluginDefaults,
+) -> Result<(), String> {
+    let path = defaults_path(app)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("创建配置目录失败: {e}"))?;
+    }
+    let data = serde_json::to_string_pretty(defaults)
+        .map_err(|e| format!("序列化语言默认插件失败: {e}"))?;
+    fs::write(&path, data).map_err(|e| format!("保存语言默认插件失败: {e}"))
+}
+
+impl PluginHost {
+    /// Remember `plugin_id` as the server for `language_id`, or forget the
+    /// choice when it is `None`.
+    pub async fn set_language_plugin_default(
+        &self,
+        args: SetLanguagePluginDefaultArgs,
+    ) -> Result<LanguagePluginDefaults, String> {
+        let mut defaults = load_language_plugin_defaults(self.app())?;
+        match args.plugin_id {
+            Some(plugin_id) => {
+                let serves_language = self
+                    .plugins_for_language(&args.language_id, None)
+                    .await?
+                    .iter()
+                    .any(|plugin| plugin.manifest.id == plugin_id);
+                if !serves_language {
+                    return Err(format!(
+                        "插件 {plugin_id} 不提供 {} 的语言服务",
+                        args.language_id
+                    ));
+                }
+                defaults.defaults.insert(args.language_id, plugin_id);
+            }
+            None => {
+                defaults.defaults.remove(&args.language_id);
+            }
+        }
+        save_language_plugin_defaults(self.app(), &defaults)?;
+        Ok(defaults)
+    }
+}
@@ -0,0 +1,143 @@
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::{Component, Path, PathBuf};
+
+use tauri::AppHandle;
+
+use super::api::extract_zip_archive;
+use super::marketplace::download_package;
+use super::{DiscoveredPlugin, LspPluginManifest};
+
+const EVENT_LSP_DOWNLOAD_PROGRESS: &str = "truidide://lsp/download-progress";
+// Records the URL the installed server came from, so a manifest pointing at
+// a new release downloads it again.
+const DOWNLOAD_MARKER: &str = ".truidide-server-download";
+
+enum ArchiveKind {
+    Zip,
+    TarXz,
+    Tar,
+    Binary,
+}
+
+fn archive_kind(url: &str) -> ArchiveKind {
+    let path = url.split(['?', '#']).next().unwrap_or(url).to_lowercase();
+    if path.ends_with(".zip") {
+        ArchiveKind::Zip
+    } else if path.ends_with(".tar.xz") || path.ends_with(".txz") {
+        ArchiveKind::TarXz
+    } else if path.ends_with(".tar") {
+        ArchiveKind::Tar
+    } else {
+        ArchiveKind::Binary
+    }
+}
+
+fn plugin_relative_path(root: &Path, relative: &str) -> Result<PathBuf, String> {
+    let relative = Path::new(relative);
+    if relative
+        .components()
+        .any(|component| !matches!(component, Component::Normal(_) | Component::CurDir))
+    {
+        return Err(format!("无效的解压路径: {}", relative.display()));
+    }
+    Ok(root.join(relative))
+}
+
+#[cfg(unix)]
+pub(super) fn make_executable(path: &Path) -> Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mut permissions = fs::metadata(path)
+        .map_err(|e| format!("读取文件权限失败: {e}"))?
+        .permissions();
+    permissions.set_mode(permissions.mode() | 0o755);
+    fs::set_permissions(path, permissions).map_err(|e| format!("设置可执行权限失败: {e}"))
+}
+
+#[cfg(not(unix))]
+pub(super) fn make_executable(_path: &Path) -> Result<(), String> {
+    Ok(())
+}
+
+fn unpack(kind: ArchiveKind, package: &Path, destination: &Path) -> Result<(), String> {
+    match kind {
+        ArchiveKind::Zip => extract_zip_archive(package, destination),
+        ArchiveKind::TarXz | ArchiveKind::Tar => {
+            fs::create_dir_all(destination).map_err(|e| format!("创建目录失败: {e}"))?;
+            let file = File::open(package).map_err(|e| format!("无法读取压缩包: {e}"))?;
+            let result = match kind {
+                ArchiveKind::TarXz => {
+                    tar::Archive::new(xz2::bufread::XzDecoder::new(BufReader::new(file)))
+                        .unpack(destination)
+                }
+                _ => tar::Archive::new(file).unpack(destination),
+            };
+            result.map_err(|e| format!("解压语言服务器失败: {e}"))
+        }
+        ArchiveKind::Binary => {
+            if let Some(parent) = destination.parent() {
+                fs::create_dir_all(parent).map_err(|e| format!("创建目录失败: {e}"))?;
+            }
+            fs::copy(package, destination).map_err(|e| format!("写入语言服务器失败: {e}"))?;
+            make_executable(destination)
+        }
+    }
+}
+
+/// Download and unpack the server of `manifest` into the plugin directory
+/// unless the current release is already there. Progress is reported as
+/// `lsp/download-progress`, keyed by URL like marketplace installs.
+pub(super) async fn ensure_server_binary(
+    app: &AppHandle,
+    plugin: &DiscoveredPlugin,
+    manifest: &LspPluginManifest,
+) -> Result<(), String> {
+    let Some(download) = &manifest.download else {
+        return Ok(());
+    };
+    let arch = std::env::consts::ARCH;
+    let target = download.targets.get(arch).ok_or_else(|| {
+        format!(
+            "插件 {} 没有提供适用于 {arch} 架构的语言服务器",
+            plugin.manifest.id
+        )
+    })?;
+    let marker = plugin.root_dir.join(DOWNLOAD_MARKER);
+    if fs::read_to_string(&marker).is_ok_and(|url| url.trim() == target.url) {
+        return Ok(());
+    }
+
+    let kind = archive_kind(&target.url);
+    let destination = match (&download.unpack_path, &kind) {
+        (Some(path), _) => plugin_relative_path(&plugin.root_dir, path)?,
+        (None, ArchiveKind::Binary) => plugin_relative_path(&plugin.root_dir, &manifest.command)?,
+        (None, _) => plugin.root_dir.clone(),
+    };
+
+    eprintln!(
+        "[truidide::lsp] 正在下载插件 {} 的语言服务器: {}",
+        plugin.manifest.id, target.url
+    );
+    let package = download_package(
+        app,
+        &target.url,
+        target.sha256.as_deref(),
+        EVENT_LSP_DOWNLOAD_PROGRESS,
+    )
+    .await?;
+
+    let command = plugin.root_dir.join(&manifest.command);
+    tauri::async_runtime::spawn_blocking(move || {
+        unpack(kind, package.path(), &destination)?;
+        // Zip entries lose their mode; the server has to be runnable either way.
+        if command.is_file() {
+            make_executable(&command)?;
+        }
+        Ok::<_, String>(())
+    })
+    .await
+    .map_err(|e| format!("解压语言服务器失败: {e}"))??;
+
+    fs::write(&marker, &target.url).map_err(|e| format!("记录语言服务器版本失败: {e}"))
+}
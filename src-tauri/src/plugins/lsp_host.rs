@@ -1,11 +1,12 @@
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use once_cell::sync::OnceCell;
-use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader, BufWriter};
-use tokio::process::{Child, ChildStderr, ChildStdin, ChildStdout, Command};
-use tokio::sync::{mpsc, oneshot, RwLock};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader, BufWriter};
+use tokio::process::{Child, Command};
+use tokio::sync::{mpsc, oneshot, Mutex, OwnedMutexGuard, RwLock};
 use uuid::Uuid;
 
 use serde::{Deserialize, Serialize};
@@ -13,16 +14,61 @@ use serde_json::{json, Value};
 use tauri::path::BaseDirectory;
 use tauri::{AppHandle, Emitter, Manager};
 
+use crate::keep_alive::KeepAliveGuard;
+use crate::plugins::api::{diff_plugins, summarize_plugin, PluginsUpdatedPayload};
+use crate::plugins::builtin::built_in_plugin_dir;
+use crate::plugins::dap_host::{
+    disconnect_request, DapSendPayload, DapSessionIdArgs, DapSessionRecord,
+};
+use crate::plugins::engines::check_runtimes;
+use crate::plugins::health::{record_crash, record_start, record_stderr};
+use crate::plugins::lsp_defaults::load_language_plugin_defaults;
+use crate::plugins::lsp_download::ensure_server_binary;
+use crate::plugins::lsp_init::compose_initialize_params;
+use crate::plugins::lsp_trace::{open_session_trace, SessionTrace};
+use crate::plugins::lsp_transport::{
+    encode_message, message_body, prepare_transport, read_message, Framing, LspReader, LspWriter,
+};
+use crate::plugins::lsp_uri::{translate_uris, UriDirection};
+use crate::plugins::lsp_watch::{WatchedFiles, WATCHED_FILES_METHOD};
+use crate::plugins::permissions::apply_permission_env;
+#[cfg(target_os = "android")]
+use crate::plugins::permissions::proot_permission_args;
+use crate::plugins::project::{load_project_plugins, plugin_allowed_in_project};
 use crate::plugins::registry::DiscoveredPlugin;
-use crate::plugins::{LspPluginManifest, PluginDirectoriesConfig, PluginManifest, PluginRegistry};
+use crate::plugins::settings::initialization_options_with_settings;
+use crate::plugins::{
+    BusSink, LspPluginManifest, LspRestartPolicy, MessageBus, PluginDirectoriesConfig,
+    PluginManifest, PluginRegistry,
+};
 
 #[cfg(target_os = "android")]
 use crate::android::proot::prepare_proot_env;
+#[cfg(unix)]
+use crate::plugins::lsp_transport::expose_ipc_fd;
 
+// Suffixed with the session id, so only the windows listening for that
+// session receive its traffic.
 const EVENT_LSP_MESSAGE: &str = "truidide://lsp/message";
 const EVENT_LSP_STDERR: &str = "truidide://lsp/stderr";
 const EVENT_LSP_EXIT: &str = "truidide://lsp/exit";
+const EVENT_LSP_RESTARTED: &str = "truidide://lsp/restarted";
+const EVENT_LSP_REQUEST_TIMEOUT: &str = "truidide://lsp/request-timeout";
 const EVENT_PLUGINS_UPDATED: &str = "truidide://plugins/updated";
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(3);
+const KILL_GRACE: Duration = Duration::from_secs(2);
+// Id of the `initialize` request the host sends itself; its response is
+// consumed by the host instead of reaching the editor.
+const HOST_INITIALIZE_ID: &str = "truidide/initialize";
+const HOST_SHUTDOWN_ID: &str = "truidide/shutdown";
+const INITIALIZE_TIMEOUT: Duration = Duration::from_secs(60);
+const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+// Editor messages held back while a server initializes; more means the
+// server is stuck and the editor should see an error.
+const MAX_QUEUED_MESSAGES: usize = 256;
+const MAX_RESTART_DELAY: Duration = Duration::from_secs(30);
+// A server that stayed up this long no longer counts as crash-looping.
+const RESTART_STABLE_UPTIME: Duration = Duration::from_secs(60);
 
 #[derive(Clone)]
 pub struct PluginHost {
@@ -33,14 +79,81 @@ struct PluginHostInner {
     app: AppHandle,
     registry: RwLock<PluginRegistry>,
     sessions: RwLock<HashMap<String, SessionRecord>>,
+    // Held while starting an LSP session so concurrent starts for the same
+    // plugin and workspace share one server instead of racing to spawn two.
+    // One lock per key, so a slow server never holds up the others.
+    lsp_start_locks: Mutex<HashMap<LspStartKey, Arc<Mutex<()>>>>,
+    dap_sessions: RwLock<HashMap<String, DapSessionRecord>>,
+    bus: MessageBus,
+    /// Broker tokens of loaded web extensions, mapped to their plugin id.
+    web_extensions: RwLock<HashMap<String, String>>,
+}
+
+/// Plugin id and workspace roots an LSP session is shared for.
+type LspStartKey = (String, PathBuf, Vec<PathBuf>);
+
+type InitializeResult = Result<StartLspSessionResponse, String>;
+
+/// What a start found when a session for its plugin and workspace existed.
+enum AttachedSession {
+    Ready(StartLspSessionResponse),
+    /// The server is still answering `initialize`; resolves with the result.
+    Initializing(oneshot::Receiver<InitializeResult>),
 }
 
 struct SessionRecord {
     pub plugin_id: String,
     pub language_id: String,
     pub workspace_path: PathBuf,
+    /// Further roots of a multi-root workspace, mounted next to the first one.
+    additional_workspaces: Vec<PathBuf>,
+    /// What `start_lsp_session` returned, handed out again when the session is reused.
+    started: StartLspSessionResponse,
+    /// Clients attached to the session; the server stops when the last one detaches.
+    clients: usize,
     write_tx: Option<mpsc::Sender<Vec<u8>>>,
     kill_tx: Option<oneshot::Sender<()>>,
+    /// Params of the host's `initialize` request, sent again after a restart.
+    initialize_params: Value,
+    /// Answered once the server replies to `initialize` while starting.
+    initialize_tx: Option<oneshot::Sender<InitializeResult>>,
+    /// Clients that attached while the server was still initializing.
+    initialize_waiters: Vec<oneshot::Sender<InitializeResult>>,
+    restart: LspRestartPolicy,
+    /// Restarts in a row so far, including the one that spawned this server.
+    restarts: u32,
+    spawned_at: Instant,
+    /// Process id of the server, or of proot wrapping it on Android.
+    pid: Option<u32>,
+    /// The server exited on its own and is waiting to be respawned.
+    restarting: bool,
+    /// Emitted once the server answers the replayed `initialize`.
+    pending_restart: Option<LspRestartedPayload>,
+    /// The server answered `initialize` and was sent `initialized`.
+    initialized: bool,
+    /// Framed editor messages sent before `initialized`, flushed in order.
+    queued_messages: Vec<Vec<u8>>,
+    /// Editor requests still waiting for a response, keyed by serialized id.
+    pending_requests: HashMap<String, PendingRequest>,
+    request_timeout: Duration,
+    shutdown_grace: Duration,
+    /// Last message exchanged in either direction.
+    last_activity: Instant,
+    /// Set when the host stops the server, reported once it exits.
+    stop_reason: Option<LspExitReason>,
+    /// Answered once the server replies to the host's `shutdown`.
+    shutdown_tx: Option<oneshot::Sender<()>>,
+    /// Files the server registered for `workspace/didChangeWatchedFiles`.
+    watched_files: Option<WatchedFiles>,
+    /// Keeps the app alive in the background while the session exists,
+    /// carried over across restarts.
+    _keep_alive: KeepAliveGuard,
+}
+
+struct PendingRequest {
+    id: Value,
+    method: String,
+    sent_at: Instant,
 }
 
 static HOST: OnceCell<Arc<PluginHostInner>> = OnceCell::new();
@@ -53,6 +166,10 @@ pub struct StartLspSessionArgs {
     pub language_id: Option<String>,
     /// Absolute path to the workspace/project folder.
     pub workspace_path: String,
+    /// Further roots for multi-root workspaces such as monorepos; each one
+    /// is mounted and listed in `workspaceFolders`.
+    #[serde(default)]
+    pub additional_workspace_paths: Vec<String>,
     #[serde(default)]
     pub client_capabilities: Option<Value>,
     #[serde(default)]
@@ -61,7 +178,7 @@ pub struct StartLspSessionArgs {
     pub initialization_options: Option<Value>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct StartLspSessionResponse {
     pub session_id: String,
@@ -72,6 +189,12 @@ pub struct StartLspSessionResponse {
     pub workspace_folders: Option<Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub path_mapping: Option<PathMapping>,
+    /// `capabilities` from the server's `initialize` result; the host has
+    /// already completed the handshake.
+    pub server_capabilities: Value,
+    pub server_info: Option<Value>,
+    /// The server was already running for this plugin and workspace.
+    pub reused: bool,
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -85,6 +208,60 @@ pub struct PathMapping {
     pub host_plugin: String,
     /// Guest plugin path inside proot (e.g., /opt/truidide/plugins/plugin-id)
     pub guest_plugin: String,
+    /// Mounts of the additional workspace roots, in the order they were given.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub additional_workspaces: Vec<WorkspaceMount>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceMount {
+    pub host_workspace: String,
+    /// Guest path inside proot (e.g., /mnt/workspace-2)
+    pub guest_workspace: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PluginSessionKind {
+    Lsp,
+    Dap,
+}
+
+/// An LSP or DAP session that belongs to a plugin, reported when the plugin is in use.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginSessionInfo {
+    pub session_id: String,
+    pub kind: PluginSessionKind,
+    /// Language id for LSP sessions, debug type for DAP sessions.
+    pub language_id: String,
+    pub workspace_path: String,
+}
+
+/// A row of the host's LSP session table, for managing running servers.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LspSessionStatus {
+    pub session_id: String,
+    pub plugin_id: String,
+    pub language_id: String,
+    pub workspace_path: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub additional_workspace_paths: Vec<String>,
+    /// Milliseconds since the current server process was spawned.
+    pub uptime_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pid: Option<u32>,
+    /// Whether the server process is running; false while a crashed server
+    /// waits to be restarted.
+    pub alive: bool,
+    pub restarting: bool,
+    /// The server has not answered `initialize` yet; editor messages are
+    /// queued until it does.
+    pub initializing: bool,
+    /// Editor clients attached to the session.
+    pub clients: usize,
 }
 
 #[derive(Debug, Deserialize)]
@@ -100,6 +277,14 @@ pub struct LspSendPayload {
     pub payload: Value,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CancelLspRequestArgs {
+    pub session_id: String,
+    /// JSON-RPC id of the request, as sent in `send_lsp_payload`.
+    pub id: Value,
+}
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 struct LspMessagePayload {
@@ -126,6 +311,40 @@ struct LspExitPayload {
     language_id: String,
     status_code: Option<i32>,
     signal: Option<i32>,
+    /// The server will be respawned; a `lsp/restarted` event follows.
+    restarting: bool,
+    reason: LspExitReason,
+}
+
+/// Why an LSP server went away, reported with `lsp/exit`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub(super) enum LspExitReason {
+    /// The process ended without being asked to.
+    Exited,
+    /// The editor stopped the session.
+    Stopped,
+    /// The host stopped a session that was idle for too long.
+    Idle,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LspRequestTimeoutPayload {
+    session_id: String,
+    id: Value,
+    method: String,
+    timeout_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LspRestartedPayload {
+    previous_session_id: String,
+    session_id: String,
+    plugin_id: String,
+    language_id: String,
+    attempt: u32,
 }
 
 impl PluginHost {
@@ -141,6 +360,10 @@ impl PluginHost {
                 app: app_clone.clone(),
                 registry: RwLock::new(registry),
                 sessions: RwLock::new(HashMap::new()),
+                lsp_start_locks: Mutex::new(HashMap::new()),
+                dap_sessions: RwLock::new(HashMap::new()),
+                bus: MessageBus::default(),
+                web_extensions: RwLock::new(HashMap::new()),
             }))
         })?;
 
@@ -151,26 +374,105 @@ impl PluginHost {
 
     pub async fn reload_registry(&self) -> Result<Vec<PluginManifest>, String> {
         let directories = resolve_plugin_directories(&self.inner.app)?;
+        let before = self.list_plugins().await;
         {
             let mut registry = self.inner.registry.write().await;
             *registry = PluginRegistry::with_directories(directories);
             registry.refresh()?;
         }
 
-        let manifests = {
-            let registry = self.inner.registry.read().await;
-            registry
-                .all_plugins()
-                .map(|(_, plugin)| plugin.manifest.clone())
-                .collect::<Vec<_>>()
+        let after = self.list_plugins().await;
+        self.emit_plugins_updated(diff_plugins(&before, &after))?;
+
+        Ok(after.into_iter().map(|plugin| plugin.manifest).collect())
+    }
+
+    /// Re-read a single plugin's manifest without rescanning every directory.
+    pub async fn refresh_plugin(
+        &self,
+        plugin_id: &str,
+    ) -> Result<Option<DiscoveredPlugin>, String> {
+        // Dependents may change too, so diff the whole index.
+        let before = self.list_plugins().await;
+        let refreshed = {
+            let mut registry = self.inner.registry.write().await;
+            registry.refresh_plugin(plugin_id)?
         };
 
+        let delta = diff_plugins(&before, &self.list_plugins().await);
+        self.emit_plugins_updated(delta)?;
+
+        Ok(refreshed)
+    }
+
+    /// Enable or disable a plugin for this installation. Disabling stops its
+    /// running sessions.
+    pub async fn set_plugin_enabled(
+        &self,
+        plugin_id: &str,
+        enabled: bool,
+    ) -> Result<DiscoveredPlugin, String> {
+        let before = self.list_plugins().await;
+        let updated = {
+            let mut registry = self.inner.registry.write().await;
+            registry.set_enabled(plugin_id, enabled)?
+        };
+        self.emit_plugins_updated(diff_plugins(&before, &self.list_plugins().await))?;
+
+        if !enabled {
+            self.stop_plugin_sessions(plugin_id).await?;
+        }
+        Ok(updated)
+    }
+
+    fn emit_plugins_updated(&self, delta: PluginsUpdatedPayload) -> Result<(), String> {
+        if delta.is_empty() {
+            return Ok(());
+        }
         self.inner
             .app
-            .emit(EVENT_PLUGINS_UPDATED, &manifests)
-            .map_err(|e: tauri::Error| e.to_string())?;
+            .emit(EVENT_PLUGINS_UPDATED, &delta)
+            .map_err(|e: tauri::Error| e.to_string())
+    }
+
+    /// Broadcast the current summary of `plugin_id`, e.g. after its health changed.
+    pub(super) async fn publish_plugin_summary(&self, plugin_id: &str) -> Result<(), String> {
+        let Some(plugin) = self.find_plugin(plugin_id).await else {
+            return Ok(());
+        };
+        self.emit_plugins_updated(PluginsUpdatedPayload {
+            upserted: vec![summarize_plugin(&plugin)],
+            removed: Vec::new(),
+        })
+    }
+
+    pub(crate) fn app(&self) -> &AppHandle {
+        &self.inner.app
+    }
 
-        Ok(manifests)
+    pub(crate) fn bus(&self) -> &MessageBus {
+        &self.inner.bus
+    }
+
+    pub(super) fn web_extensions(&self) -> &RwLock<HashMap<String, String>> {
+        &self.inner.web_extensions
+    }
+
+    pub(super) fn dap_sessions(&self) -> &RwLock<HashMap<String, DapSessionRecord>> {
+        &self.inner.dap_sessions
+    }
+
+    /// Ids of plugins that declare a dependency on `plugin_id`.
+    pub async fn plugin_dependents(&self, plugin_id: &str) -> Vec<String> {
+        self.inner.registry.read().await.dependents_of(plugin_id)
+    }
+
+    pub async fn find_plugin(&self, plugin_id: &str) -> Option<DiscoveredPlugin> {
+        let registry = self.inner.registry.read().await;
+        registry
+            .all_plugins()
+            .find(|(id, _)| id.as_str() == plugin_id)
+            .map(|(_, plugin)| plugin.clone())
     }
 
     pub async fn list_plugins(&self) -> Vec<DiscoveredPlugin> {
@@ -196,159 +498,709 @@ impl PluginHost {
         if !plugin.manifest.enabled {
             return Err(format!("插件 {} 当前被禁用", plugin.manifest.id));
         }
+        if let PluginStatus::Incompatible { reason } = &plugin.status {
+            return Err(format!("插件 {} 不可用: {}", plugin.manifest.id, reason));
+        }
+        if !plugin_allowed_in_project(&self.inner.app, &args.workspace_path, &plugin.manifest.id) {
+            return Err(format!(
+                "插件 {} 在当前项目中未启用，可在项目插件设置中允许",
+                plugin.manifest.id
+            ));
+        }
+        if !plugin.missing_dependencies.is_empty() {
+            return Err(format!(
+                "插件 {} 无法启动: {}",
+                plugin.manifest.id,
+                plugin.missing_dependencies.join("；")
+            ));
+        }
 
         let language_id = args
             .language_id
             .or_else(|| manifest.language_ids.first().cloned())
             .ok_or_else(|| "插件未声明语言标识".to_string())?;
 
-        let workspace_path = {
-            let raw = args.workspace_path.trim();
-            if raw.is_empty() {
-                return Err("工作区路径不能为空".into());
-            }
+        let workspace_path = resolve_workspace_path(&self.inner.app, &args.workspace_path)?;
+        let additional_workspaces = args
+            .additional_workspace_paths
+            .iter()
+            .map(|raw| resolve_workspace_path(&self.inner.app, raw))
+            .collect::<Result<Vec<_>, _>>()?;
 
-            #[cfg(target_os = "android")]
-            {
-                let candidate = if raw.starts_with('/') {
-                    match crate::android::proot::resolve_guest_path(&self.inner.app, raw) {
-                        Ok(path) => path,
-                        Err(_) => PathBuf::from(raw),
+        let start_guard = self
+            .lsp_start_lock(&plugin.manifest.id, &workspace_path, &additional_workspaces)
+            .await
+            .lock_owned()
+            .await;
+        if let Some(attached) = self
+            .attach_lsp_session(&plugin.manifest.id, &workspace_path, &additional_workspaces)
+            .await
+        {
+            drop(start_guard);
+            return match attached {
+                AttachedSession::Ready(started) => Ok(started),
+                AttachedSession::Initializing(initialize_rx) => {
+                    match tokio::time::timeout(INITIALIZE_TIMEOUT, initialize_rx).await {
+                        Ok(Ok(result)) => {
+                            result.map_err(|reason| format!("语言服务器初始化失败: {reason}"))
+                        }
+                        Ok(Err(_)) => Err("语言服务器在初始化完成前退出".into()),
+                        Err(_) => Err("语言服务器初始化超时".into()),
                     }
-                } else {
-                    PathBuf::from(raw)
-                };
-
-                if !candidate.exists() {
-                    return Err(format!("工作区路径不存在: {}", candidate.to_string_lossy()));
                 }
+            };
+        }
 
-                candidate.canonicalize().unwrap_or(candidate)
-            }
-
-            #[cfg(not(target_os = "android"))]
-            {
-                let candidate = PathBuf::from(raw);
-                if !candidate.exists() {
-                    return Err(format!("工作区路径不存在: {}", candidate.to_string_lossy()));
-                }
-                candidate.canonicalize().unwrap_or(candidate)
-            }
-        };
+        let result = self
+            .spawn_lsp_session(
+                &plugin,
+                &manifest,
+                args,
+                language_id,
+                workspace_path,
+                additional_workspaces,
+                start_guard,
+            )
+            .await;
+        record_start(&self.inner.app, &plugin.manifest.id, &result);
+        result
+    }
 
-        let initialization_options = args
-            .initialization_options
+    // Lock serializing starts and restarts for one plugin and workspace.
+    async fn lsp_start_lock(
+        &self,
+        plugin_id: &str,
+        workspace_path: &Path,
+        additional_workspaces: &[PathBuf],
+    ) -> Arc<Mutex<()>> {
+        let mut locks = self.inner.lsp_start_locks.lock().await;
+        // Drop locks nobody is holding or waiting for.
+        locks.retain(|_, lock| Arc::strong_count(lock) > 1);
+        locks
+            .entry((
+                plugin_id.to_string(),
+                workspace_path.to_path_buf(),
+                additional_workspaces.to_vec(),
+            ))
+            .or_default()
             .clone()
-            .or_else(|| manifest.initialization_options.clone());
+    }
+
+    // Check runtimes, spawn the server and wait for `initialize`. The start
+    // lock is released once the session is registered, later starts for the
+    // same workspace attach to it and wait for the same `initialize`.
+    #[allow(clippy::too_many_arguments)]
+    async fn spawn_lsp_session(
+        &self,
+        plugin: &DiscoveredPlugin,
+        manifest: &LspPluginManifest,
+        args: StartLspSessionArgs,
+        language_id: String,
+        workspace_path: PathBuf,
+        additional_workspaces: Vec<PathBuf>,
+        start_guard: OwnedMutexGuard<()>,
+    ) -> Result<StartLspSessionResponse, String> {
+        check_runtimes(&self.inner.app, &plugin.manifest)
+            .await
+            .map_err(|reason| format!("插件 {} 无法启动: {reason}", plugin.manifest.id))?;
+
+        let initialization_options = initialization_options_with_settings(
+            &self.inner.app,
+            plugin,
+            &args.workspace_path,
+            args.initialization_options
+                .clone()
+                .or_else(|| manifest.initialization_options.clone()),
+        );
         let client_capabilities = args.client_capabilities.clone();
         let workspace_folders = args.workspace_folders.clone();
 
+        let (initialize_tx, initialize_rx) = oneshot::channel();
+        let started = self
+            .launch_lsp_server(
+                plugin,
+                manifest,
+                &workspace_path,
+                &additional_workspaces,
+                |session_id, path_mapping| {
+                    let initialize_params = compose_initialize_params(
+                        &workspace_path,
+                        &additional_workspaces,
+                        path_mapping.as_ref(),
+                        client_capabilities,
+                        workspace_folders,
+                        initialization_options.clone(),
+                    );
+                    SessionRecord {
+                        plugin_id: plugin.manifest.id.clone(),
+                        language_id: language_id.clone(),
+                        workspace_path: workspace_path.clone(),
+                        additional_workspaces: additional_workspaces.clone(),
+                        started: StartLspSessionResponse {
+                            session_id: session_id.to_string(),
+                            plugin_id: plugin.manifest.id.clone(),
+                            language_id: language_id.clone(),
+                            initialization_options,
+                            client_capabilities: initialize_params.get("capabilities").cloned(),
+                            workspace_folders: initialize_params.get("workspaceFolders").cloned(),
+                            path_mapping,
+                            server_capabilities: Value::Null,
+                            server_info: None,
+                            reused: false,
+                        },
+                        clients: 1,
+                        write_tx: None,
+                        kill_tx: None,
+                        initialize_params,
+                        initialize_tx: Some(initialize_tx),
+                        initialize_waiters: Vec::new(),
+                        restart: manifest.restart,
+                        restarts: 0,
+                        spawned_at: Instant::now(),
+                        pid: None,
+                        restarting: false,
+                        pending_restart: None,
+                        initialized: false,
+                        queued_messages: Vec::new(),
+                        pending_requests: HashMap::new(),
+                        request_timeout: manifest
+                            .request_timeout_secs
+                            .map_or(DEFAULT_REQUEST_TIMEOUT, Duration::from_secs),
+                        shutdown_grace: manifest
+                            .shutdown_grace_secs
+                            .map_or(SHUTDOWN_GRACE, Duration::from_secs),
+                        shutdown_tx: None,
+                        last_activity: Instant::now(),
+                        stop_reason: None,
+                        watched_files: None,
+                        _keep_alive: KeepAliveGuard::acquire(),
+                    }
+                },
+            )
+            .await?;
+        drop(start_guard);
+
+        match tokio::time::timeout(INITIALIZE_TIMEOUT, initialize_rx).await {
+            Ok(Ok(Ok(started))) => Ok(started),
+            Ok(Ok(Err(reason))) => {
+                self.terminate_session(&started.session_id).await?;
+                Err(format!("语言服务器初始化失败: {reason}"))
+            }
+            Ok(Err(_)) => Err("语言服务器在初始化完成前退出".into()),
+            Err(_) => {
+                self.terminate_session(&started.session_id).await?;
+                Err("语言服务器初始化超时".into())
+            }
+        }
+    }
+
+    // Spawn the server for `plugin`, store the record built by `build_record`
+    // under a fresh session id, wire up the I/O tasks and send `initialize`.
+    async fn launch_lsp_server(
+        &self,
+        plugin: &DiscoveredPlugin,
+        manifest: &LspPluginManifest,
+        workspace_path: &Path,
+        additional_workspaces: &[PathBuf],
+        build_record: impl FnOnce(&str, Option<PathMapping>) -> SessionRecord,
+    ) -> Result<StartLspSessionResponse, String> {
+        ensure_server_binary(&self.inner.app, plugin, manifest).await?;
         let session_id = Uuid::new_v4().to_string();
 
-        let (mut child, path_mapping) = spawn_lsp_process(
-            &self.inner.app,
-            &plugin,
-            &manifest,
-            &workspace_path,
-            &session_id,
-        )
-        .await?;
+        let transport = prepare_transport(&manifest.transport, &manifest.args, &manifest.env)?;
+        let spec = PluginProcessSpec {
+            args: &transport.args,
+            env: &transport.env,
+            ipc_fd: transport.ipc_fd(),
+            additional_workspaces,
+            ..PluginProcessSpec::from(manifest)
+        };
+        let (mut child, path_mapping) =
+            spawn_plugin_process(&self.inner.app, plugin, &spec, workspace_path, &session_id)
+                .await?;
 
-        let stdin = child
-            .stdin
-            .take()
-            .ok_or_else(|| "无法获取 LSP 进程的标准输入".to_string())?;
-        let stdout = child
-            .stdout
-            .take()
-            .ok_or_else(|| "无法获取 LSP 进程的标准输出".to_string())?;
         let stderr = child
             .stderr
             .take()
             .ok_or_else(|| "无法获取 LSP 进程的标准错误".to_string())?;
+        let connection = match transport.connect(&mut child).await {
+            Ok(connection) => connection,
+            Err(err) => {
+                let _ = child.kill().await;
+                return Err(err);
+            }
+        };
 
         let (write_tx, write_rx) = mpsc::channel::<Vec<u8>>(32);
         let (kill_tx, kill_rx) = oneshot::channel::<()>();
 
-        let record = SessionRecord {
-            plugin_id: plugin.manifest.id.clone(),
-            language_id: language_id.clone(),
-            workspace_path: workspace_path.clone(),
-            write_tx: Some(write_tx.clone()),
-            kill_tx: Some(kill_tx),
-        };
+        let mut record = build_record(&session_id, path_mapping);
+        record.write_tx = Some(write_tx.clone());
+        record.kill_tx = Some(kill_tx);
+        record.pid = child.id();
+        let started = record.started.clone();
+        let plugin_id = record.plugin_id.clone();
+        let language_id = record.language_id.clone();
+        let path_mapping = record.started.path_mapping.clone();
+        let initialize = json!({
+            "jsonrpc": "2.0",
+            "id": HOST_INITIALIZE_ID,
+            "method": "initialize",
+            "params": record.initialize_params,
+        });
 
         {
             let mut sessions = self.inner.sessions.write().await;
             sessions.insert(session_id.clone(), record);
         }
 
-        let plugin_id = plugin.manifest.id.clone();
         #[cfg(debug_assertions)]
         eprintln!(
             "[truidide::lsp] session {} started (plugin: {} language: {})",
             session_id, plugin_id, language_id
         );
 
-        self.spawn_writer_task(&session_id, stdin, write_rx);
-        self.spawn_reader_task(&session_id, plugin_id.clone(), language_id.clone(), stdout);
-        self.spawn_stderr_task(&session_id, plugin_id.clone(), language_id.clone(), stderr);
-        self.spawn_wait_task(
-            session_id.clone(),
+        let trace = open_session_trace(&self.inner.app, &session_id);
+        self.spawn_writer_task(
+            &session_id,
+            connection.writer,
+            connection.framing,
+            write_rx,
+            trace.clone(),
+        );
+        self.spawn_reader_task(
+            &session_id,
+            plugin_id.clone(),
+            language_id.clone(),
+            connection.reader,
+            connection.framing,
+            write_tx.clone(),
+            path_mapping,
+            trace.clone(),
+        );
+        self.spawn_stderr_task(
+            &session_id,
             plugin_id.clone(),
             language_id.clone(),
-            child,
-            kill_rx,
+            stderr,
+            trace.clone(),
         );
+        if let Some(stdout) = connection.stdout {
+            // The server talks over another channel; its stdout is just logging.
+            self.spawn_stderr_task(
+                &session_id,
+                plugin_id.clone(),
+                language_id.clone(),
+                stdout,
+                trace,
+            );
+        }
+        self.spawn_idle_monitor(&session_id, &plugin_id);
+        self.spawn_wait_task(session_id, plugin_id, language_id, child, kill_rx);
 
-        Ok(StartLspSessionResponse {
-            session_id,
-            plugin_id,
-            language_id,
-            initialization_options,
-            client_capabilities,
-            workspace_folders,
-            path_mapping,
-        })
+        let payload =
+            serde_json::to_vec(&initialize).map_err(|e| format!("序列化 LSP 负载失败: {e}"))?;
+        write_tx
+            .send(frame_message(&payload))
+            .await
+            .map_err(|e| format!("发送 LSP 消息失败: {e}"))?;
+
+        Ok(started)
     }
 
-    pub async fn send_payload(&self, args: LspSendPayload) -> Result<(), String> {
-        let tx = {
-            let sessions = self.inner.sessions.read().await;
-            let Some(record) = sessions.get(&args.session_id) else {
+    // Respawn a server that exited on its own with the same `initialize`
+    // params, so attached editors only need to reopen their documents.
+    async fn restart_lsp_session(
+        &self,
+        previous_session_id: &str,
+        attempt: u32,
+    ) -> Result<(), String> {
+        let Some(start_key) = self
+            .inner
+            .sessions
+            .read()
+            .await
+            .get(previous_session_id)
+            .map(|record| {
+                (
+                    record.plugin_id.clone(),
+                    record.workspace_path.clone(),
+                    record.additional_workspaces.clone(),
+                )
+            })
+        else {
+            // Every client detached while the restart was pending.
+            return Ok(());
+        };
+        let start_lock = self
+            .lsp_start_lock(&start_key.0, &start_key.1, &start_key.2)
+            .await;
+        let _start_guard = start_lock.lock().await;
+        let Some(previous) = self
+            .inner
+            .sessions
+            .write()
+            .await
+            .remove(previous_session_id)
+        else {
+            return Ok(());
+        };
+        let plugin_id = previous.plugin_id.clone();
+        let language_id = previous.language_id.clone();
+
+        let result = self
+            .respawn_lsp_session(previous_session_id, previous, attempt)
+            .await;
+        if result.is_err() {
+            // Nothing will follow the `restarting` exit event, so report the
+            // session as gone for good.
+            let _ = self.inner.app.emit(
+                EVENT_LSP_EXIT,
+                &LspExitPayload {
+                    session_id: previous_session_id.to_string(),
+                    plugin_id,
+                    language_id,
+                    status_code: None,
+                    signal: None,
+                    restarting: false,
+                    reason: LspExitReason::Exited,
+                },
+            );
+        }
+        result
+    }
+
+    async fn respawn_lsp_session(
+        &self,
+        previous_session_id: &str,
+        previous: SessionRecord,
+        attempt: u32,
+    ) -> Result<(), String> {
+        let (plugin, manifest) = {
+            let registry = self.inner.registry.read().await;
+            registry
+                .get_lsp_manifest(&previous.plugin_id)
+                .map(|(plugin, manifest)| (plugin.clone(), manifest.clone()))
+                .ok_or_else(|| format!("未找到插件 {}", previous.plugin_id))?
+        };
+        if !plugin.is_usable() {
+            return Err(format!("插件 {} 当前不可用，放弃重启", plugin.manifest.id));
+        }
+
+        let workspace_path = previous.workspace_path.clone();
+        let additional_workspaces = previous.additional_workspaces.clone();
+        let started = self
+            .launch_lsp_server(
+                &plugin,
+                &manifest,
+                &workspace_path,
+                &additional_workspaces,
+                |session_id, path_mapping| {
+                    let restarted = LspRestartedPayload {
+                        previous_session_id: previous_session_id.to_string(),
+                        session_id: session_id.to_string(),
+                        plugin_id: previous.plugin_id.clone(),
+                        language_id: previous.language_id.clone(),
+                        attempt,
+                    };
+                    SessionRecord {
+                        started: StartLspSessionResponse {
+                            session_id: session_id.to_string(),
+                            path_mapping,
+                            ..previous.started.clone()
+                        },
+                        write_tx: None,
+                        kill_tx: None,
+                        initialize_tx: None,
+                        restart: manifest.restart,
+                        restarts: attempt,
+                        spawned_at: Instant::now(),
+                        restarting: false,
+                        pending_restart: Some(restarted),
+                        initialized: false,
+                        queued_messages: Vec::new(),
+                        // Requests sent to the old server will never be answered.
+                        pending_requests: HashMap::new(),
+                        // The new server registers its watchers again.
+                        watched_files: None,
+                        last_activity: Instant::now(),
+                        ..previous
+                    }
+                },
+            )
+            .await?;
+
+        eprintln!(
+            "[truidide::lsp] LSP 会话 {} 已重启为 {} (第 {} 次)",
+            previous_session_id, started.session_id, attempt
+        );
+
+        Ok(())
+    }
+
+    pub async fn send_payload(&self, mut args: LspSendPayload) -> Result<(), String> {
+        let (tx, framed) = {
+            let mut sessions = self.inner.sessions.write().await;
+            let Some(record) = sessions.get_mut(&args.session_id) else {
                 return Err(format!("找不到会话 {}", args.session_id));
             };
 
-            let Some(write_tx) = record.write_tx.as_ref() else {
+            let Some(write_tx) = record.write_tx.clone() else {
                 return Err("会话正在关闭，无法发送消息".into());
             };
+            // Some servers reject anything before `initialized`. Answers to
+            // the server's own requests (e.g. `window/workDoneProgress/create`)
+            // still go straight through.
+            let queue = !record.initialized && args.payload.get("method").is_some();
+            if queue && record.queued_messages.len() >= MAX_QUEUED_MESSAGES {
+                return Err("语言服务器仍在初始化，排队的消息过多".into());
+            }
+
+            if let Some(mapping) = &record.started.path_mapping {
+                translate_uris(&mut args.payload, mapping, UriDirection::ToGuest);
+            }
+            let payload = serde_json::to_vec(&args.payload)
+                .map_err(|e| format!("序列化 LSP 负载失败: {e}"))?;
+            let framed = frame_message(&payload);
+            #[cfg(debug_assertions)]
+            eprintln!(
+                "[truidide::lsp] <= (session {}{}) {}",
+                args.session_id,
+                if queue { ", queued" } else { "" },
+                describe_message(&args.payload)
+            );
+
+            record.last_activity = Instant::now();
+            if let (Some(id), Some(method)) = (
+                args.payload.get("id"),
+                args.payload.get("method").and_then(Value::as_str),
+            ) {
+                let sent_at = Instant::now();
+                record.pending_requests.insert(
+                    id.to_string(),
+                    PendingRequest {
+                        id: id.clone(),
+                        method: method.to_string(),
+                        sent_at,
+                    },
+                );
+                self.spawn_request_timeout(
+                    &args.session_id,
+                    id.to_string(),
+                    sent_at,
+                    record.request_timeout,
+                );
+            }
 
-            write_tx.clone()
+            if queue {
+                record.queued_messages.push(framed);
+                return Ok(());
+            }
+            (write_tx, framed)
         };
 
-        let payload =
-            serde_json::to_vec(&args.payload).map_err(|e| format!("序列化 LSP 负载失败: {e}"))?;
+        tx.send(framed)
+            .await
+            .map_err(|e| format!("发送 LSP 消息失败: {e}"))
+    }
+
+    /// Send `$/cancelRequest` for a request the editor no longer needs and
+    /// stop tracking it. Requests that were already answered are ignored.
+    pub async fn cancel_request(&self, args: CancelLspRequestArgs) -> Result<(), String> {
+        let cancel = json!({
+            "jsonrpc": "2.0",
+            "method": "$/cancelRequest",
+            "params": { "id": args.id },
+        });
+        let payload =
+            serde_json::to_vec(&cancel).map_err(|e| format!("序列化 LSP 负载失败: {e}"))?;
+        let framed = frame_message(&payload);
+
+        let tx = {
+            let mut sessions = self.inner.sessions.write().await;
+            let Some(record) = sessions.get_mut(&args.session_id) else {
+                return Err(format!("找不到会话 {}", args.session_id));
+            };
+            if record
+                .pending_requests
+                .remove(&args.id.to_string())
+                .is_none()
+            {
+                return Ok(());
+            }
+            let Some(write_tx) = record.write_tx.clone() else {
+                return Ok(());
+            };
+            if !record.initialized {
+                // Keep it behind the queued request it cancels.
+                record.queued_messages.push(framed);
+                return Ok(());
+            }
+            write_tx
+        };
+
+        tx.send(framed)
+            .await
+            .map_err(|e| format!("发送 LSP 消息失败: {e}"))
+    }
+
+    // Report the request as timed out if it is still pending after `timeout`.
+    fn spawn_request_timeout(
+        &self,
+        session_id: &str,
+        key: String,
+        sent_at: Instant,
+        timeout: Duration,
+    ) {
+        let inner = self.inner.clone();
+        let session_id = session_id.to_string();
+
+        tokio::spawn(async move {
+            tokio::time::sleep(timeout).await;
+            let request = {
+                let mut sessions = inner.sessions.write().await;
+                let Some(record) = sessions.get_mut(&session_id) else {
+                    return;
+                };
+                // The id may have been reused by a later request.
+                if !record
+                    .pending_requests
+                    .get(&key)
+                    .is_some_and(|request| request.sent_at == sent_at)
+                {
+                    return;
+                }
+                record.pending_requests.remove(&key)
+            };
+            let Some(request) = request else {
+                return;
+            };
+
+            eprintln!(
+                "[truidide::lsp] LSP 请求 {} ({}) 超时 (session {})",
+                request.id, request.method, session_id
+            );
+            let payload = LspRequestTimeoutPayload {
+                session_id,
+                id: request.id,
+                method: request.method,
+                timeout_ms: timeout.as_millis() as u64,
+            };
+            let _ = inner.app.emit(EVENT_LSP_REQUEST_TIMEOUT, &payload);
+        });
+    }
+
+    /// Detach one client from a session, stopping the server once no client
+    /// is left.
+    pub async fn stop_session(&self, args: LspSessionIdArgs) -> Result<(), String> {
+        {
+            let mut sessions = self.inner.sessions.write().await;
+            let Some(record) = sessions.get_mut(&args.session_id) else {
+                return Ok(());
+            };
+            record.clients = record.clients.saturating_sub(1);
+            if record.clients > 0 {
+                return Ok(());
+            }
+        }
+        self.shutdown_session(&args.session_id, LspExitReason::Stopped)
+            .await
+    }
+
+    /// Stop a session the way the protocol asks: `shutdown`, then `exit`,
+    /// killing the server only if it is still running after its grace
+    /// period. Servers use the time to flush caches and indexes.
+    pub(super) async fn shutdown_session(
+        &self,
+        session_id: &str,
+        reason: LspExitReason,
+    ) -> Result<(), String> {
+        let (write_tx, kill_tx, shutdown_rx, grace) = {
+            let mut sessions = self.inner.sessions.write().await;
+            let Some(record) = sessions.get_mut(session_id) else {
+                return Ok(());
+            };
+            if record.restarting {
+                // No process to stop; dropping the record cancels the restart.
+                sessions.remove(session_id);
+                return Ok(());
+            }
+            let (shutdown_tx, shutdown_rx) = oneshot::channel();
+            record.shutdown_tx = Some(shutdown_tx);
+            record.stop_reason = Some(reason);
+            (
+                record.write_tx.take(),
+                record.kill_tx.take(),
+                shutdown_rx,
+                record.shutdown_grace,
+            )
+        };
+        let Some(mut kill_tx) = kill_tx else {
+            return Ok(());
+        };
+        let Some(write_tx) = write_tx else {
+            let _ = kill_tx.send(());
+            return Ok(());
+        };
+
+        let session_id = session_id.to_string();
+        tauri::async_runtime::spawn(async move {
+            let deadline = tokio::time::Instant::now() + grace;
+            let shutdown =
+                json!({ "jsonrpc": "2.0", "id": HOST_SHUTDOWN_ID, "method": "shutdown" });
+            let payload = serde_json::to_vec(&shutdown).unwrap_or_default();
+            if write_tx.send(frame_message(&payload)).await.is_ok() {
+                let _ = tokio::time::timeout_at(deadline, shutdown_rx).await;
+                let exit = json!({ "jsonrpc": "2.0", "method": "exit" });
+                let payload = serde_json::to_vec(&exit).unwrap_or_default();
+                let _ = write_tx.send(frame_message(&payload)).await;
+            }
+            // Closes the server's input once the writer drains.
+            drop(write_tx);
+
+            // The wait task drops the receiver when the process exits.
+            if tokio::time::timeout_at(deadline, kill_tx.closed())
+                .await
+                .is_err()
+            {
+                eprintln!(
+                    "[truidide::lsp] LSP 会话 {} 未在 {} 秒内退出，强制终止",
+                    session_id,
+                    grace.as_secs()
+                );
+                let _ = kill_tx.send(());
+            }
+        });
 
-        let mut framed = format!("Content-Length: {}\r\n\r\n", payload.len()).into_bytes();
-        framed.extend_from_slice(&payload);
-        #[cfg(debug_assertions)]
-        eprintln!(
-            "[truidide::lsp] <= (session {}) {}",
-            args.session_id,
-            describe_message(&args.payload)
-        );
+        Ok(())
+    }
 
-        tx.send(framed)
-            .await
-            .map_err(|e| format!("发送 LSP 消息失败: {e}"))
+    /// How long a running session has gone without messages; `None` once it
+    /// is gone or stopping. Sessions with requests in flight count as busy.
+    pub(super) async fn session_idle_time(&self, session_id: &str) -> Option<Duration> {
+        let sessions = self.inner.sessions.read().await;
+        let record = sessions.get(session_id)?;
+        if record.write_tx.is_none() || record.restarting {
+            return None;
+        }
+        if !record.pending_requests.is_empty() {
+            return Some(Duration::ZERO);
+        }
+        Some(record.last_activity.elapsed())
     }
 
-    pub async fn stop_session(&self, args: LspSessionIdArgs) -> Result<(), String> {
+    /// Stop a session regardless of how many clients are attached.
+    async fn terminate_session(&self, session_id: &str) -> Result<(), String> {
         let kill_tx = {
             let mut sessions = self.inner.sessions.write().await;
-            let Some(record) = sessions.get_mut(&args.session_id) else {
+            let Some(record) = sessions.get_mut(session_id) else {
                 return Ok(());
             };
+            if record.restarting {
+                // No process to stop; dropping the record cancels the restart.
+                sessions.remove(session_id);
+                return Ok(());
+            }
 
             if let Some(write_tx) = record.write_tx.take() {
                 drop(write_tx);
@@ -364,18 +1216,228 @@ impl PluginHost {
         Ok(())
     }
 
+    // Attach another client to a live session of `plugin_id` serving the
+    // same workspace roots, including one waiting to be respawned.
+    async fn attach_lsp_session(
+        &self,
+        plugin_id: &str,
+        workspace_path: &Path,
+        additional_workspaces: &[PathBuf],
+    ) -> Option<AttachedSession> {
+        let mut sessions = self.inner.sessions.write().await;
+        let record = sessions.values_mut().find(|record| {
+            // Sessions being stopped have already dropped their writer.
+            (record.write_tx.is_some() || record.restarting)
+                && record.plugin_id == plugin_id
+                && record.workspace_path == workspace_path
+                && record.additional_workspaces == additional_workspaces
+        })?;
+        record.clients += 1;
+        // Waiters move to the respawned record and resolve with its id once
+        // the new server answers `initialize`.
+        if record.initialize_tx.is_some() || record.restarting || record.pending_restart.is_some() {
+            let (initialize_tx, initialize_rx) = oneshot::channel();
+            record.initialize_waiters.push(initialize_tx);
+            return Some(AttachedSession::Initializing(initialize_rx));
+        }
+        Some(AttachedSession::Ready(StartLspSessionResponse {
+            reused: true,
+            ..record.started.clone()
+        }))
+    }
+
+    /// Preferred usable LSP plugin for `language_id`, honouring the
+    /// activation lists of `project_path` when given.
+    pub async fn plugin_for_language(
+        &self,
+        language_id: &str,
+        project_path: Option<&str>,
+    ) -> Result<Option<DiscoveredPlugin>, String> {
+        Ok(self
+            .plugins_for_language(language_id, project_path)
+            .await?
+            .into_iter()
+            .next())
+    }
+
+    /// Every usable LSP plugin for `language_id`: the user's default for the
+    /// language first, the rest by descending priority.
+    pub async fn plugins_for_language(
+        &self,
+        language_id: &str,
+        project_path: Option<&str>,
+    ) -> Result<Vec<DiscoveredPlugin>, String> {
+        let activation = project_path
+            .filter(|path| !path.trim().is_empty())
+            .map(|path| load_project_plugins(&self.inner.app, path))
+            .transpose()?;
+        let mut candidates = {
+            let registry = self.inner.registry.read().await;
+            registry
+                .plugins_for_language(language_id, activation.as_ref())
+                .into_iter()
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        let defaults = load_language_plugin_defaults(&self.inner.app)?;
+        if let Some(index) = defaults.defaults.get(language_id).and_then(|default| {
+            candidates
+                .iter()
+                .position(|plugin| &plugin.manifest.id == default)
+        }) {
+            let preferred = candidates.remove(index);
+            candidates.insert(0, preferred);
+        }
+        Ok(candidates)
+    }
+
+    pub async fn list_lsp_sessions(&self) -> Vec<LspSessionStatus> {
+        let sessions = self.inner.sessions.read().await;
+        let mut statuses = sessions
+            .iter()
+            .map(|(session_id, record)| LspSessionStatus {
+                session_id: session_id.clone(),
+                plugin_id: record.plugin_id.clone(),
+                language_id: record.language_id.clone(),
+                workspace_path: record.workspace_path.to_string_lossy().into_owned(),
+                additional_workspace_paths: record
+                    .additional_workspaces
+                    .iter()
+                    .map(|path| path.to_string_lossy().into_owned())
+                    .collect(),
+                uptime_ms: record.spawned_at.elapsed().as_millis() as u64,
+                pid: record.pid.filter(|_| !record.restarting),
+                alive: record.write_tx.is_some(),
+                restarting: record.restarting,
+                initializing: record.write_tx.is_some() && !record.initialized,
+                clients: record.clients,
+            })
+            .collect::<Vec<_>>();
+        statuses.sort_by(|a, b| b.uptime_ms.cmp(&a.uptime_ms));
+        statuses
+    }
+
+    pub async fn plugin_sessions(&self, plugin_id: &str) -> Vec<PluginSessionInfo> {
+        let mut infos = {
+            let sessions = self.inner.sessions.read().await;
+            sessions
+                .iter()
+                .filter(|(_, record)| record.plugin_id == plugin_id)
+                .map(|(session_id, record)| PluginSessionInfo {
+                    session_id: session_id.clone(),
+                    kind: PluginSessionKind::Lsp,
+                    language_id: record.language_id.clone(),
+                    workspace_path: record.workspace_path.to_string_lossy().into_owned(),
+                })
+                .collect::<Vec<_>>()
+        };
+        let dap_sessions = self.inner.dap_sessions.read().await;
+        infos.extend(
+            dap_sessions
+                .iter()
+                .filter(|(_, record)| record.plugin_id == plugin_id)
+                .map(|(session_id, record)| PluginSessionInfo {
+                    session_id: session_id.clone(),
+                    kind: PluginSessionKind::Dap,
+                    language_id: record.debug_type.clone(),
+                    workspace_path: record.workspace_path.to_string_lossy().into_owned(),
+                }),
+        );
+        infos
+    }
+
+    /// Stop every session of `plugin_id`: ask the servers and adapters to shut
+    /// down, then kill whatever is still running after a grace period.
+    pub async fn stop_plugin_sessions(&self, plugin_id: &str) -> Result<(), String> {
+        let sessions = self.plugin_sessions(plugin_id).await;
+        if sessions.is_empty() {
+            return Ok(());
+        }
+
+        for session in &sessions {
+            if session.kind == PluginSessionKind::Dap {
+                let _ = self
+                    .send_dap_payload(DapSendPayload {
+                        session_id: session.session_id.clone(),
+                        payload: disconnect_request(),
+                    })
+                    .await;
+                continue;
+            }
+            for payload in [
+                json!({ "jsonrpc": "2.0", "id": "truidide-uninstall", "method": "shutdown" }),
+                json!({ "jsonrpc": "2.0", "method": "exit" }),
+            ] {
+                let _ = self
+                    .send_payload(LspSendPayload {
+                        session_id: session.session_id.clone(),
+                        payload,
+                    })
+                    .await;
+            }
+        }
+
+        if self
+            .wait_for_plugin_sessions(plugin_id, SHUTDOWN_GRACE)
+            .await
+        {
+            return Ok(());
+        }
+
+        for session in self.plugin_sessions(plugin_id).await {
+            eprintln!(
+                "[truidide::lsp] 插件会话 {} 未在限定时间内退出，强制终止",
+                session.session_id
+            );
+            match session.kind {
+                PluginSessionKind::Lsp => self.terminate_session(&session.session_id).await?,
+                PluginSessionKind::Dap => {
+                    self.stop_dap_session(DapSessionIdArgs {
+                        session_id: session.session_id,
+                    })
+                    .await?
+                }
+            }
+        }
+
+        if self.wait_for_plugin_sessions(plugin_id, KILL_GRACE).await {
+            Ok(())
+        } else {
+            Err(format!("无法停止插件 {plugin_id} 的会话"))
+        }
+    }
+
+    async fn wait_for_plugin_sessions(&self, plugin_id: &str, timeout: Duration) -> bool {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            if self.plugin_sessions(plugin_id).await.is_empty() {
+                return true;
+            }
+            if tokio::time::Instant::now() >= deadline {
+                return false;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+
     fn spawn_writer_task(
         &self,
         session_id: &str,
-        stdin: ChildStdin,
+        writer: LspWriter,
+        framing: Framing,
         mut write_rx: mpsc::Receiver<Vec<u8>>,
+        trace: Option<Arc<SessionTrace>>,
     ) {
-        let mut writer = BufWriter::new(stdin);
+        let mut writer = BufWriter::new(writer);
         let app = self.inner.app.clone();
         let session_id = session_id.to_string();
 
         tokio::spawn(async move {
             while let Some(message) = write_rx.recv().await {
+                if let Some(trace) = &trace {
+                    trace.record("send", &String::from_utf8_lossy(message_body(&message)));
+                }
+                let message = encode_message(message, framing);
                 if let Err(err) = writer.write_all(&message).await {
                     let _ = writer.shutdown().await;
                     eprintln!("[truidide::lsp] LSP 会话 {} 写入失败: {}", session_id, err);
@@ -399,24 +1461,71 @@ impl PluginHost {
         });
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn spawn_reader_task(
         &self,
         session_id: &str,
         plugin_id: String,
         language_id: String,
-        stdout: ChildStdout,
+        reader: LspReader,
+        framing: Framing,
+        write_tx: mpsc::Sender<Vec<u8>>,
+        path_mapping: Option<PathMapping>,
+        trace: Option<Arc<SessionTrace>>,
     ) {
+        let inner = self.inner.clone();
         let app = self.inner.app.clone();
         let session_id = session_id.to_string();
         let plugin_id_clone = plugin_id.clone();
         let language_id_clone = language_id.clone();
+        let message_event = format!("{EVENT_LSP_MESSAGE}/{session_id}");
 
         tokio::spawn(async move {
-            let mut reader = BufReader::new(stdout);
+            let mut reader = BufReader::new(reader);
             loop {
-                match read_lsp_message(&mut reader).await {
+                match read_message(&mut reader, framing).await {
                     Ok(body) => {
-                        if let Ok(value) = serde_json::from_slice::<Value>(&body) {
+                        if let Some(trace) = &trace {
+                            trace.record("recv", &String::from_utf8_lossy(&body));
+                        }
+                        inner.touch_session(&session_id).await;
+                        if let Ok(mut value) = serde_json::from_slice::<Value>(&body) {
+                            if value.get("id").and_then(Value::as_str) == Some(HOST_SHUTDOWN_ID) {
+                                inner.finish_shutdown(&session_id).await;
+                                continue;
+                            }
+                            if value.get("id").and_then(Value::as_str) == Some(HOST_INITIALIZE_ID) {
+                                inner
+                                    .finish_initialize(&session_id, &write_tx, &value)
+                                    .await;
+                                continue;
+                            }
+                            if is_bus_message(&value) {
+                                inner
+                                    .handle_bus_message(&session_id, &plugin_id, &write_tx, &value)
+                                    .await;
+                                continue;
+                            }
+                            if is_registration_request(&value)
+                                && inner
+                                    .handle_watched_files_registration(
+                                        &session_id,
+                                        &write_tx,
+                                        &mut value,
+                                    )
+                                    .await
+                            {
+                                continue;
+                            }
+                            if let Some(mapping) = &path_mapping {
+                                translate_uris(&mut value, mapping, UriDirection::ToHost);
+                            }
+                            if value.get("method").is_none() {
+                                if let Some(id) = value.get("id") {
+                                    inner.complete_request(&session_id, id).await;
+                                }
+                            }
+
                             let payload = LspMessagePayload {
                                 session_id: session_id.clone(),
                                 plugin_id: plugin_id_clone.clone(),
@@ -424,7 +1533,7 @@ impl PluginHost {
                                 body: value,
                             };
 
-                            if let Err(err) = app.emit(EVENT_LSP_MESSAGE, &payload) {
+                            if let Err(err) = app.emit(&message_event, &payload) {
                                 eprintln!(
                                     "[truidide::lsp] 广播 LSP 消息失败 (session {}): {}",
                                     session_id, err
@@ -462,7 +1571,8 @@ impl PluginHost {
         session_id: &str,
         plugin_id: String,
         language_id: String,
-        stderr: ChildStderr,
+        stderr: impl AsyncRead + Send + Unpin + 'static,
+        trace: Option<Arc<SessionTrace>>,
     ) {
         let app = self.inner.app.clone();
         let session_id = session_id.to_string();
@@ -475,6 +1585,10 @@ impl PluginHost {
                 match reader.read_line(&mut buffer).await {
                     Ok(0) => break,
                     Ok(_) => {
+                        if let Some(trace) = &trace {
+                            trace.record("stderr", &buffer);
+                        }
+                        record_stderr(&plugin_id, &buffer);
                         let payload = LspStderrPayload {
                             session_id: session_id.clone(),
                             plugin_id: plugin_id.clone(),
@@ -525,47 +1639,275 @@ impl PluginHost {
                 status = child.wait() => status,
             };
 
-            if let Err(err) = inner
+            let restart = match inner
                 .handle_session_exit(&session_id, &plugin_id, &language_id, status.ok())
                 .await
             {
-                eprintln!(
-                    "[truidide::lsp] 处理 LSP 会话退出失败 (session {}): {}",
-                    session_id, err
-                );
+                Ok(restart) => restart,
+                Err(err) => {
+                    eprintln!(
+                        "[truidide::lsp] 处理 LSP 会话退出失败 (session {}): {}",
+                        session_id, err
+                    );
+                    None
+                }
+            };
+
+            if let Some((attempt, delay)) = restart {
+                tokio::time::sleep(delay).await;
+                let host = PluginHost { inner };
+                if let Err(err) = host.restart_lsp_session(&session_id, attempt).await {
+                    eprintln!(
+                        "[truidide::lsp] 重启 LSP 会话失败 (session {}): {}",
+                        session_id, err
+                    );
+                }
             }
         });
     }
 }
 
+fn is_bus_message(value: &Value) -> bool {
+    value
+        .get("method")
+        .and_then(|m| m.as_str())
+        .is_some_and(|method| {
+            matches!(
+                method,
+                "truidide/publish" | "truidide/subscribe" | "truidide/unsubscribe"
+            )
+        })
+}
+
+fn is_registration_request(value: &Value) -> bool {
+    value
+        .get("method")
+        .and_then(Value::as_str)
+        .is_some_and(|method| {
+            matches!(
+                method,
+                "client/registerCapability" | "client/unregisterCapability"
+            )
+        })
+}
+
 impl PluginHostInner {
+    // Servers register `workspace/didChangeWatchedFiles` dynamically; the host
+    // watches the workspace for them and strips those registrations from the
+    // request. Returns true when nothing is left for the editor, in which case
+    // the request has been answered here.
+    async fn handle_watched_files_registration(
+        &self,
+        session_id: &str,
+        write_tx: &mpsc::Sender<Vec<u8>>,
+        value: &mut Value,
+    ) -> bool {
+        let registering =
+            value.get("method").and_then(Value::as_str) == Some("client/registerCapability");
+        // Sic: the protocol spells it `unregisterations`.
+        let key = if registering {
+            "registrations"
+        } else {
+            "unregisterations"
+        };
+        let Some(entries) = value
+            .get_mut("params")
+            .and_then(|params| params.get_mut(key))
+            .and_then(Value::as_array_mut)
+        else {
+            return false;
+        };
+        let (watched, rest): (Vec<Value>, Vec<Value>) =
+            std::mem::take(entries).into_iter().partition(|entry| {
+                entry.get("method").and_then(Value::as_str) == Some(WATCHED_FILES_METHOD)
+            });
+        let forward = !rest.is_empty();
+        *entries = rest;
+        if watched.is_empty() {
+            return false;
+        }
+
+        {
+            let mut sessions = self.sessions.write().await;
+            if let Some(record) = sessions.get_mut(session_id) {
+                if registering && record.watched_files.is_none() {
+                    let roots = std::iter::once(record.workspace_path.clone())
+                        .chain(record.additional_workspaces.iter().cloned())
+                        .collect::<Vec<_>>();
+                    match WatchedFiles::start(&roots, record.started.path_mapping.clone(), write_tx)
+                    {
+                        Ok(watched_files) => record.watched_files = Some(watched_files),
+                        Err(err) => eprintln!(
+                            "[truidide::lsp] 无法为 LSP 会话 {} 监听工作区文件: {}",
+                            session_id, err
+                        ),
+                    }
+                }
+                if let Some(watched_files) = &record.watched_files {
+                    for entry in &watched {
+                        let id = entry.get("id").and_then(Value::as_str).unwrap_or_default();
+                        if registering {
+                            watched_files.register(id, entry.get("registerOptions"));
+                        } else {
+                            watched_files.unregister(id);
+                        }
+                    }
+                }
+            }
+        }
+
+        if forward {
+            return false;
+        }
+        let Some(id) = value.get("id") else {
+            return true;
+        };
+        let response = json!({ "jsonrpc": "2.0", "id": id, "result": null });
+        let payload = serde_json::to_vec(&response).unwrap_or_default();
+        let _ = write_tx.send(frame_message(&payload)).await;
+        true
+    }
+
+    // Handle message bus requests sent by a language server over stdio. Requests
+    // (with an `id`) receive a JSON-RPC response; notifications are fire-and-forget.
+    async fn handle_bus_message(
+        &self,
+        session_id: &str,
+        plugin_id: &str,
+        write_tx: &mpsc::Sender<Vec<u8>>,
+        value: &Value,
+    ) {
+        let method = value.get("method").and_then(|m| m.as_str()).unwrap_or("");
+        let params = value.get("params").cloned().unwrap_or(Value::Null);
+        let topic = params
+            .get("topic")
+            .and_then(|t| t.as_str())
+            .unwrap_or("")
+            .to_string();
+        let subscriber_key = format!("lsp:{session_id}");
+
+        let manifest = {
+            let registry = self.registry.read().await;
+            registry
+                .all_plugins()
+                .find(|(id, _)| id.as_str() == plugin_id)
+                .map(|(_, plugin)| plugin.manifest.clone())
+        };
+
+        let result = match manifest {
+            None => Err(format!("未找到插件 {plugin_id}")),
+            Some(manifest) => match method {
+                "truidide/publish" => self
+                    .bus
+                    .publish(
+                        &self.app,
+                        &manifest,
+                        &topic,
+                        params.get("payload").cloned().unwrap_or(Value::Null),
+                    )
+                    .await
+                    .map(|delivered| json!({ "delivered": delivered })),
+                "truidide/subscribe" => self
+                    .bus
+                    .subscribe(
+                        &manifest,
+                        &subscriber_key,
+                        &topic,
+                        BusSink::LspSession(write_tx.clone()),
+                    )
+                    .await
+                    .map(|_| Value::Null),
+                _ => {
+                    self.bus.unsubscribe(&subscriber_key, &topic).await;
+                    Ok(Value::Null)
+                }
+            },
+        };
+
+        let Some(id) = value.get("id") else {
+            if let Err(err) = result {
+                eprintln!(
+                    "[truidide::lsp] 消息总线请求失败 (session {}): {}",
+                    session_id, err
+                );
+            }
+            return;
+        };
+
+        let response = match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(message) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": -32600, "message": message },
+            }),
+        };
+        let payload = serde_json::to_vec(&response).unwrap_or_default();
+        let _ = write_tx.send(frame_message(&payload)).await;
+    }
+
+    // Returns the attempt number and delay when the server should be
+    // respawned; the record is then kept until the restart runs.
     async fn handle_session_exit(
         &self,
         session_id: &str,
         fallback_plugin_id: &str,
         fallback_language_id: &str,
         status: Option<std::process::ExitStatus>,
-    ) -> Result<(), String> {
-        let record = {
+    ) -> Result<Option<(u32, Duration)>, String> {
+        let (plugin_id, language_id, restart, reason, crashed) = {
             let mut sessions = self.sessions.write().await;
-            sessions.remove(session_id)
-        };
-
-        let (plugin_id, language_id) = if let Some(mut record) = record {
-            if let Some(write_tx) = record.write_tx.take() {
-                drop(write_tx);
-            }
-            if let Some(kill_tx) = record.kill_tx.take() {
-                let _ = kill_tx.send(());
+            match sessions.get_mut(session_id) {
+                Some(record) => {
+                    // Requested stops drop the writer first, so a live writer
+                    // means the server exited on its own.
+                    let unexpected = record.write_tx.take().is_some();
+                    let reason = record.stop_reason.take().unwrap_or(if unexpected {
+                        LspExitReason::Exited
+                    } else {
+                        LspExitReason::Stopped
+                    });
+                    if let Some(kill_tx) = record.kill_tx.take() {
+                        let _ = kill_tx.send(());
+                    }
+                    // A server that dies during `initialize` fails the start
+                    // instead of being respawned.
+                    let initializing = record.initialize_tx.take().is_some();
+                    let restart = if unexpected && !initializing {
+                        restart_plan(record)
+                    } else {
+                        None
+                    };
+                    if unexpected && restart.is_none() && record.restart.max_retries > 0 {
+                        eprintln!(
+                            "[truidide::lsp] LSP 会话 {} 连续崩溃 {} 次，不再重启",
+                            session_id, record.restarts
+                        );
+                    }
+                    let ids = (record.plugin_id.clone(), record.language_id.clone());
+                    if restart.is_some() {
+                        record.restarting = true;
+                        record.watched_files = None;
+                    } else {
+                        sessions.remove(session_id);
+                    }
+                    (ids.0, ids.1, restart, reason, unexpected)
+                }
+                None => (
+                    fallback_plugin_id.to_string(),
+                    fallback_language_id.to_string(),
+                    None,
+                    LspExitReason::Exited,
+                    false,
+                ),
             }
-
-            (record.plugin_id, record.language_id)
-        } else {
-            (
-                fallback_plugin_id.to_string(),
-                fallback_language_id.to_string(),
-            )
         };
+        self.bus
+            .remove_subscriber(&format!("lsp:{session_id}"))
+            .await;
+        if crashed {
+            record_crash(&self.app, &plugin_id);
+        }
 
         let (status_code, signal) = extract_exit_details(status.as_ref());
 
@@ -575,18 +1917,148 @@ impl PluginHostInner {
             language_id,
             status_code,
             signal,
+            restarting: restart.is_some(),
+            reason,
         };
 
         self.app
             .emit(EVENT_LSP_EXIT, &exit_payload)
             .map_err(|e: tauri::Error| e.to_string())?;
 
-        Ok(())
+        Ok(restart)
+    }
+
+    async fn touch_session(&self, session_id: &str) {
+        let mut sessions = self.sessions.write().await;
+        if let Some(record) = sessions.get_mut(session_id) {
+            record.last_activity = Instant::now();
+        }
+    }
+
+    async fn finish_shutdown(&self, session_id: &str) {
+        let mut sessions = self.sessions.write().await;
+        if let Some(shutdown_tx) = sessions
+            .get_mut(session_id)
+            .and_then(|record| record.shutdown_tx.take())
+        {
+            let _ = shutdown_tx.send(());
+        }
+    }
+
+    async fn complete_request(&self, session_id: &str, id: &Value) {
+        let mut sessions = self.sessions.write().await;
+        if let Some(record) = sessions.get_mut(session_id) {
+            record.pending_requests.remove(&id.to_string());
+        }
+    }
+
+    // The server answered the host's `initialize`: complete the handshake,
+    // record its capabilities and wake whoever waits on the session.
+    async fn finish_initialize(
+        &self,
+        session_id: &str,
+        write_tx: &mpsc::Sender<Vec<u8>>,
+        response: &Value,
+    ) {
+        let result = match response.get("error") {
+            Some(error) => Err(error
+                .get("message")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .unwrap_or_else(|| error.to_string())),
+            None => Ok(response.get("result").cloned().unwrap_or(Value::Null)),
+        };
+        if result.is_ok() {
+            let initialized = json!({ "jsonrpc": "2.0", "method": "initialized", "params": {} });
+            let payload = serde_json::to_vec(&initialized).unwrap_or_default();
+            let _ = write_tx.send(frame_message(&payload)).await;
+        }
+
+        let (initialize_tx, initialize_waiters, started, restarted) = {
+            let mut sessions = self.sessions.write().await;
+            let Some(record) = sessions.get_mut(session_id) else {
+                return;
+            };
+            let queued = std::mem::take(&mut record.queued_messages);
+            if result.is_ok() {
+                record.initialized = true;
+                // Flushed under the lock so later messages cannot overtake
+                // the queued ones.
+                for message in queued {
+                    if write_tx.send(message).await.is_err() {
+                        break;
+                    }
+                }
+            }
+            let started = result.as_ref().map(|result| {
+                record.started.server_capabilities = result
+                    .get("capabilities")
+                    .cloned()
+                    .unwrap_or_else(|| json!({}));
+                record.started.server_info = result.get("serverInfo").cloned();
+                record.started.clone()
+            });
+            (
+                record.initialize_tx.take(),
+                std::mem::take(&mut record.initialize_waiters),
+                started.map_err(String::clone),
+                record.pending_restart.take(),
+            )
+        };
+
+        for waiter in initialize_waiters {
+            let _ = waiter.send(started.clone().map(|started| StartLspSessionResponse {
+                reused: true,
+                ..started
+            }));
+        }
+        if let Some(initialize_tx) = initialize_tx {
+            let _ = initialize_tx.send(started);
+            return;
+        }
+        match (restarted, started) {
+            (Some(restarted), Ok(_)) => {
+                if let Err(err) = self.app.emit(EVENT_LSP_RESTARTED, &restarted) {
+                    eprintln!(
+                        "[truidide::lsp] 广播 LSP 重启事件失败 (session {}): {}",
+                        session_id, err
+                    );
+                }
+            }
+            (Some(_), Err(reason)) => {
+                eprintln!(
+                    "[truidide::lsp] 重启后的 initialize 失败 (session {}): {}",
+                    session_id, reason
+                );
+            }
+            (None, _) => {}
+        }
+    }
+}
+
+// Attempt number and backoff for respawning `record`, or `None` once it has
+// used up its retries without staying up for `RESTART_STABLE_UPTIME`.
+fn restart_plan(record: &SessionRecord) -> Option<(u32, Duration)> {
+    let previous = if record.spawned_at.elapsed() >= RESTART_STABLE_UPTIME {
+        0
+    } else {
+        record.restarts
+    };
+    if previous >= record.restart.max_retries {
+        return None;
     }
+    let delay = Duration::from_millis(
+        record
+            .restart
+            .initial_delay_ms
+            .saturating_mul(1 << previous.min(16)),
+    )
+    .min(MAX_RESTART_DELAY);
+    Some((previous + 1, delay))
 }
 
 #[derive(Debug)]
-enum ReadMessageError {
+pub(super) enum ReadMessageError {
     Eof,
     Io(std::io::Error),
     Malformed(String),
@@ -602,7 +2074,9 @@ impl From<std::io::Error> for ReadMessageError {
     }
 }
 
-async fn read_lsp_message<R>(reader: &mut BufReader<R>) -> Result<Vec<u8>, ReadMessageError>
+pub(super) async fn read_framed_message<R>(
+    reader: &mut BufReader<R>,
+) -> Result<Vec<u8>, ReadMessageError>
 where
     R: tokio::io::AsyncRead + Unpin,
 {
@@ -651,6 +2125,50 @@ where
     }
 }
 
+/// Validate and canonicalize a workspace path sent by the frontend. On Android
+/// guest paths such as `/root/project` are mapped to the host directory.
+#[cfg_attr(not(target_os = "android"), allow(unused_variables))]
+pub(super) fn resolve_workspace_path(app: &AppHandle, raw: &str) -> Result<PathBuf, String> {
+    let raw = raw.trim();
+    if raw.is_empty() {
+        return Err("工作区路径不能为空".into());
+    }
+
+    #[cfg(target_os = "android")]
+    {
+        let candidate = if raw.starts_with('/') {
+            match crate::android::proot::resolve_guest_path(app, raw) {
+                Ok(path) => path,
+                Err(_) => PathBuf::from(raw),
+            }
+        } else {
+            PathBuf::from(raw)
+        };
+
+        if !candidate.exists() {
+            return Err(format!("工作区路径不存在: {}", candidate.to_string_lossy()));
+        }
+
+        Ok(candidate.canonicalize().unwrap_or(candidate))
+    }
+
+    #[cfg(not(target_os = "android"))]
+    {
+        let candidate = PathBuf::from(raw);
+        if !candidate.exists() {
+            return Err(format!("工作区路径不存在: {}", candidate.to_string_lossy()));
+        }
+        Ok(candidate.canonicalize().unwrap_or(candidate))
+    }
+}
+
+/// Wrap a JSON body in the `Content-Length` framing shared by LSP and DAP.
+pub(super) fn frame_message(payload: &[u8]) -> Vec<u8> {
+    let mut framed = format!("Content-Length: {}\r\n\r\n", payload.len()).into_bytes();
+    framed.extend_from_slice(payload);
+    framed
+}
+
 pub(crate) fn resolve_plugin_directories(
     app: &AppHandle,
 ) -> Result<PluginDirectoriesConfig, String> {
@@ -664,15 +2182,27 @@ pub(crate) fn resolve_plugin_directories(
         std::fs::create_dir_all(&user_dir).map_err(|e| format!("创建用户插件目录失败: {e}"))?;
     }
     config.user.push(user_dir);
+    config.state_file = app
+        .path()
+        .resolve("plugins-state.json", BaseDirectory::AppData)
+        .ok();
+    config.enabled_overrides_file = app
+        .path()
+        .resolve("plugins-enabled.json", BaseDirectory::AppData)
+        .ok();
 
-    if let Ok(built_in_dir) = app.path().resolve("plugins", BaseDirectory::Resource) {
+    if let Some(built_in_dir) = built_in_plugin_dir(app) {
+        config.built_in.push(built_in_dir);
+    } else if let Ok(built_in_dir) = app.path().resolve("plugins", BaseDirectory::Resource) {
         config.built_in.push(built_in_dir);
     }
 
     Ok(config)
 }
 
-fn extract_exit_details(status: Option<&std::process::ExitStatus>) -> (Option<i32>, Option<i32>) {
+pub(super) fn extract_exit_details(
+    status: Option<&std::process::ExitStatus>,
+) -> (Option<i32>, Option<i32>) {
     if let Some(status) = status {
         let code = status.code();
         #[cfg(unix)]
@@ -688,41 +2218,143 @@ fn extract_exit_details(status: Option<&std::process::ExitStatus>) -> (Option<i3
     (None, None)
 }
 
+/// Process settings shared by the plugin kinds that run as a stdio child.
+pub(super) struct PluginProcessSpec<'a> {
+    /// Protocol name used in logs and error messages.
+    pub label: &'static str,
+    pub command: &'a str,
+    pub args: &'a [String],
+    pub env: &'a HashMap<String, String>,
+    pub cwd: Option<&'a str>,
+    pub plugin_mount_path: Option<&'a str>,
+    pub workspace_mount_path: Option<&'a str>,
+    /// Start in the workspace instead of the plugin root when `cwd` is unset.
+    pub workspace_cwd: bool,
+    /// Descriptor passed to the process as its Node.js IPC channel.
+    pub ipc_fd: Option<i32>,
+    /// Extra workspace roots to mount, for multi-root LSP sessions.
+    pub additional_workspaces: &'a [PathBuf],
+}
+
+impl<'a> From<&'a LspPluginManifest> for PluginProcessSpec<'a> {
+    fn from(manifest: &'a LspPluginManifest) -> Self {
+        Self {
+            label: "LSP",
+            command: &manifest.command,
+            args: &manifest.args,
+            env: &manifest.env,
+            cwd: manifest.cwd.as_deref(),
+            plugin_mount_path: manifest.plugin_mount_path.as_deref(),
+            workspace_mount_path: manifest.workspace_mount_path.as_deref(),
+            workspace_cwd: false,
+            ipc_fd: None,
+            additional_workspaces: &[],
+        }
+    }
+}
+
+/// Result of a plugin command that was run to completion.
+pub(super) struct PluginProcessOutput {
+    pub status: std::process::ExitStatus,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+    pub path_mapping: Option<PathMapping>,
+}
+
+/// Run a short-lived plugin command, feeding `input` on stdin and collecting
+/// its output. The process is killed once `timeout` elapses.
+pub(super) async fn run_plugin_process(
+    app: &AppHandle,
+    plugin: &DiscoveredPlugin,
+    spec: &PluginProcessSpec<'_>,
+    workspace_path: &Path,
+    input: Option<&[u8]>,
+    timeout: Duration,
+) -> Result<PluginProcessOutput, String> {
+    let session_id = Uuid::new_v4().to_string();
+    let (mut child, path_mapping) =
+        spawn_plugin_process(app, plugin, spec, workspace_path, &session_id).await?;
+
+    let mut stdin = child
+        .stdin
+        .take()
+        .ok_or_else(|| format!("无法获取 {} 进程的标准输入", spec.label))?;
+    let mut stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| format!("无法获取 {} 进程的标准输出", spec.label))?;
+    let mut stderr = child
+        .stderr
+        .take()
+        .ok_or_else(|| format!("无法获取 {} 进程的标准错误", spec.label))?;
+
+    // Feed stdin concurrently so a process that streams its output cannot
+    // deadlock on a full pipe.
+    let input = input.unwrap_or_default().to_vec();
+    let writer = async move {
+        let _ = stdin.write_all(&input).await;
+        let _ = stdin.shutdown().await;
+    };
+    let mut out = Vec::new();
+    let mut err = Vec::new();
+    let run = async {
+        let (_, out_result, err_result) = tokio::join!(
+            writer,
+            stdout.read_to_end(&mut out),
+            stderr.read_to_end(&mut err)
+        );
+        out_result.and(err_result)?;
+        child.wait().await
+    };
+    let result = tokio::time::timeout(timeout, run).await;
+    let status = match result {
+        Ok(status) => status.map_err(|e| format!("运行 {} 插件失败: {e}", spec.label))?,
+        Err(_) => {
+            let _ = child.kill().await;
+            return Err(format!(
+                "{} 插件运行超时（{} 秒）",
+                spec.label,
+                timeout.as_secs()
+            ));
+        }
+    };
+
+    Ok(PluginProcessOutput {
+        status,
+        stdout: out,
+        stderr: err,
+        path_mapping,
+    })
+}
+
 #[cfg(target_os = "android")]
-async fn spawn_lsp_process(
+pub(super) async fn spawn_plugin_process(
     app: &AppHandle,
     plugin: &DiscoveredPlugin,
-    manifest: &LspPluginManifest,
+    spec: &PluginProcessSpec<'_>,
     workspace_path: &Path,
     session_id: &str,
 ) -> Result<(Child, Option<PathMapping>), String> {
     let env = prepare_proot_env(app)?;
     let default_plugin_mount = format!("/opt/truidide/plugins/{}", plugin.manifest.id);
-    let plugin_mount_path = manifest
+    let plugin_mount_path = spec
         .plugin_mount_path
-        .clone()
         .filter(|p| p.starts_with('/'))
+        .map(str::to_string)
         .unwrap_or(default_plugin_mount.clone());
 
     let default_workspace_mount = "/mnt/workspace".to_string();
-    let workspace_mount_path = manifest
+    let workspace_mount_path = spec
         .workspace_mount_path
-        .clone()
         .filter(|p| p.starts_with('/'))
+        .map(str::to_string)
         .unwrap_or(default_workspace_mount.clone());
 
     // ensure host plugin dir is accessible
     let mut command = Command::new(&env.proot_bin);
-    command.arg(format!("--rootfs={}", env.rootfs_dir.to_string_lossy()));
-    command.arg("--kill-on-exit");
-    command.arg("--link2symlink");
-    command.arg("--root-id");
-    command.arg("--bind=/dev");
-    command.arg("--bind=/proc");
-    command.arg("--bind=/sys");
-    command.arg("--bind=/dev/urandom:/dev/random");
-
-    // 注意：不要绑定 /proc/self/fd/* 因为 LSP 使用 pipes 而不是 PTY
+    command.args(env.base_args());
+
+    // 注意：不要绑定 /proc/self/fd/* 因为插件进程使用 pipes 而不是 PTY
     // 这些绑定在 PTY 环境（如终端）中有效，但在 pipe 环境中会失败
 
     command.arg(format!(
@@ -737,7 +2369,24 @@ async fn spawn_lsp_process(
         workspace_mount_path
     ));
 
-    command.env("PROOT_TMP_DIR", env.tmp_dir.to_string_lossy().to_string());
+    let additional_workspaces: Vec<WorkspaceMount> = spec
+        .additional_workspaces
+        .iter()
+        .enumerate()
+        .map(|(index, host)| WorkspaceMount {
+            host_workspace: host.to_string_lossy().to_string(),
+            guest_workspace: format!("{}-{}", workspace_mount_path, index + 2),
+        })
+        .collect();
+    for mount in &additional_workspaces {
+        command.arg(format!(
+            "--bind={}:{}",
+            mount.host_workspace, mount.guest_workspace
+        ));
+    }
+    command.args(proot_permission_args(app, plugin)?);
+
+    command.envs(env.base_env());
     command.env("TERM", "xterm-256color");
     command.env("COLORTERM", "truecolor");
     command.env("TRUIDIDE_SESSION_ID", session_id);
@@ -754,14 +2403,14 @@ async fn spawn_lsp_process(
     );
 
     // 先应用插件定义的环境变量
-    for (key, value) in &manifest.env {
+    for (key, value) in spec.env {
         command.env(key, value);
     }
 
     // 然后设置 PATH（确保不会被插件覆盖）
     // 如果插件已经设置了 PATH，我们追加到它后面；否则使用默认值
     let default_path = "/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin";
-    if let Some(plugin_path) = manifest.env.get("PATH") {
+    if let Some(plugin_path) = spec.env.get("PATH") {
         if !plugin_path.is_empty() {
             command.env("PATH", format!("{}:{}", plugin_path, default_path));
         } else {
@@ -770,84 +2419,100 @@ async fn spawn_lsp_process(
     } else {
         command.env("PATH", default_path);
     }
+    apply_permission_env(app, plugin, &mut command, true)?;
 
     // 处理命令路径
     // 1. 如果以 / 开头，是绝对路径，直接使用
     // 2. 如果包含 / 但不以 / 开头，是相对于插件目录的路径
     // 3. 如果不包含 /，可能是系统命令（如 node、python）或插件目录下的文件
-    let guest_command_path = if manifest.command.starts_with('/') {
+    let guest_command_path = if spec.command.starts_with('/') {
         // 绝对路径
-        manifest.command.clone()
-    } else if manifest.command.contains('/') {
+        spec.command.to_string()
+    } else if spec.command.contains('/') {
         // 相对路径（如 bin/server）
-        format!("{}/{}", plugin_mount_path, manifest.command)
+        format!("{}/{}", plugin_mount_path, spec.command)
     } else {
         // 可能是系统命令（如 node）或插件目录下的文件
         // 先检查插件目录下是否存在该文件
-        let plugin_file = plugin.root_dir.join(&manifest.command);
+        let plugin_file = plugin.root_dir.join(spec.command);
         if plugin_file.exists() {
             // 插件目录下有该文件
-            format!("{}/{}", plugin_mount_path, manifest.command)
+            format!("{}/{}", plugin_mount_path, spec.command)
         } else {
             // 当作系统命令，直接使用（依赖 PATH）
-            manifest.command.clone()
+            spec.command.to_string()
         }
     };
 
-    let guest_cwd = manifest
+    let guest_cwd = spec
         .cwd
-        .clone()
         .map(|cwd| {
             if cwd.starts_with('/') {
-                cwd
+                cwd.to_string()
             } else {
                 format!("{}/{}", plugin_mount_path, cwd)
             }
         })
-        .unwrap_or(plugin_mount_path.clone());
+        .unwrap_or_else(|| {
+            if spec.workspace_cwd {
+                workspace_mount_path.clone()
+            } else {
+                plugin_mount_path.clone()
+            }
+        });
     command.arg(format!("--cwd={}", guest_cwd));
 
     // 直接添加要执行的命令（不需要 -- 分隔符）
     command.arg(&guest_command_path);
-    for arg in &manifest.args {
+    for arg in spec.args {
         command.arg(arg);
     }
 
     // 调试日志：打印完整的 PRoot 命令
-    eprintln!("[LSP] Spawning PRoot command:");
+    eprintln!("[{}] Spawning PRoot command:", spec.label);
     eprintln!("  Program: {}", env.proot_bin.to_string_lossy());
     eprintln!("  Command: {}", guest_command_path);
-    eprintln!("  Args: {:?}", manifest.args);
+    eprintln!("  Args: {:?}", spec.args);
     eprintln!("  CWD: {}", guest_cwd);
 
     command
         .stdin(std::process::Stdio::piped())
         .stdout(std::process::Stdio::piped())
         .stderr(std::process::Stdio::piped());
+    if let Some(fd) = spec.ipc_fd {
+        expose_ipc_fd(&mut command, fd);
+    }
 
-    let child = command
-        .spawn()
-        .map_err(|e| format!("启动 LSP 插件失败 (proot): {e}"))?;
+    let child = command.spawn().map_err(|e| {
+        format!(
+            "启动 {} 插件失败 (proot): {}",
+            spec.label,
+            crate::android::proot::describe_spawn_error(&e, &env.proot_bin)
+        )
+    });
+    record_start(app, &plugin.manifest.id, &child);
+    let child = child?;
 
     let path_mapping = PathMapping {
         host_workspace: workspace_path.to_string_lossy().to_string(),
         guest_workspace: workspace_mount_path,
         host_plugin: plugin.root_dir.to_string_lossy().to_string(),
         guest_plugin: plugin_mount_path,
+        additional_workspaces,
     };
 
     Ok((child, Some(path_mapping)))
 }
 
 #[cfg(not(target_os = "android"))]
-async fn spawn_lsp_process(
-    _app: &AppHandle,
+pub(super) async fn spawn_plugin_process(
+    app: &AppHandle,
     plugin: &DiscoveredPlugin,
-    manifest: &LspPluginManifest,
+    spec: &PluginProcessSpec<'_>,
     workspace_path: &Path,
     session_id: &str,
 ) -> Result<(Child, Option<PathMapping>), String> {
-    let command_candidate = PathBuf::from(&manifest.command);
+    let command_candidate = PathBuf::from(spec.command);
     let (mut command, program_display) = if command_candidate.is_absolute() {
         (
             Command::new(&command_candidate),
@@ -858,17 +2523,17 @@ async fn spawn_lsp_process(
         if joined.exists() {
             (Command::new(&joined), joined.to_string_lossy().to_string())
         } else {
-            (Command::new(&manifest.command), manifest.command.clone())
+            (Command::new(spec.command), spec.command.to_string())
         }
     };
-    command.args(&manifest.args);
+    command.args(spec.args);
 
-    // 清除 Yarn PnP 相关的环境变量，防止干扰 LSP 进程
+    // 清除 Yarn PnP 相关的环境变量，防止干扰插件进程
     command.env_remove("NODE_OPTIONS");
     // 设置 YARN_IGNORE_PATH 告诉 Node.js 不要使用 Yarn PnP
     command.env("YARN_IGNORE_PATH", "1");
 
-    for (key, value) in &manifest.env {
+    for (key, value) in spec.env {
         command.env(key, value);
     }
 
@@ -890,10 +2555,10 @@ async fn spawn_lsp_process(
     );
     command.env("TRUIDIDE_SESSION_ID", session_id);
     command.env("TRUIDIDE_PLUGIN_ID", &plugin.manifest.id);
+    apply_permission_env(app, plugin, &mut command, false)?;
 
-    let working_dir = manifest
+    let working_dir = spec
         .cwd
-        .as_ref()
         .map(|cwd| {
             let cwd_path = PathBuf::from(cwd);
             if cwd_path.is_absolute() {
@@ -902,13 +2567,19 @@ async fn spawn_lsp_process(
                 plugin.root_dir.join(cwd_path)
             }
         })
-        .unwrap_or_else(|| plugin.root_dir.clone());
+        .unwrap_or_else(|| {
+            if spec.workspace_cwd {
+                workspace_path.to_path_buf()
+            } else {
+                plugin.root_dir.clone()
+            }
+        });
     let working_dir_display = working_dir.to_string_lossy().to_string();
     command.current_dir(&working_dir);
 
     eprintln!(
-        "[truidide::lsp] spawning plugin {} => program: {} cwd: {} args: {:?}",
-        plugin.manifest.id, program_display, working_dir_display, manifest.args,
+        "[truidide::plugins] spawning {} plugin {} => program: {} cwd: {} args: {:?}",
+        spec.label, plugin.manifest.id, program_display, working_dir_display, spec.args,
     );
 
     command
@@ -916,13 +2587,19 @@ async fn spawn_lsp_process(
         .stdout(std::process::Stdio::piped())
         .stderr(std::process::Stdio::piped())
         .kill_on_drop(true);
+    #[cfg(unix)]
+    if let Some(fd) = spec.ipc_fd {
+        expose_ipc_fd(&mut command, fd);
+    }
 
     let child = command.spawn().map_err(|e| {
         format!(
-            "启动 LSP 插件失败: {e} (program: {} cwd: {})",
-            program_display, working_dir_display
+            "启动 {} 插件失败: {e} (program: {} cwd: {})",
+            spec.label, program_display, working_dir_display
         )
-    })?;
+    });
+    record_start(app, &plugin.manifest.id, &child);
+    let child = child?;
 
     // Desktop platforms don't need path mapping
     Ok((child, None))
@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::path::BaseDirectory;
+use tauri::{AppHandle, Manager};
+
+use super::lsp_host::LspExitReason;
+use super::PluginHost;
+
+const LSP_IDLE_SETTINGS_FILENAME: &str = "lsp-idle.json";
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// When to stop language servers nobody has talked to for a while. Servers
+/// are the largest memory consumers on phones; an idle one is started again
+/// the next time the editor needs it.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LspIdleSettings {
+    /// Minutes without messages before a server is stopped; unset or 0
+    /// keeps servers running.
+    #[serde(default)]
+    pub idle_timeout_minutes: Option<u64>,
+    /// Plugin id -> minutes, overriding the global value; 0 disables it for
+    /// that plugin.
+    #[serde(default)]
+    pub plugin_overrides: HashMap<String, u64>,
+}
+
+impl LspIdleSettings {
+    fn timeout_for(&self, plugin_id: &str) -> Option<Duration> {
+        self.plugin_overrides
+            .get(plugin_id)
+            .copied()
+            .or(self.idle_timeout_minutes)
+            .filter(|minutes| *minutes > 0)
+            .map(|minutes| Duration::from_secs(minutes * 60))
+    }
+}
+
+fn settings_path(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .resolve(LSP_IDLE_SETTINGS_FILENAME, BaseDirectory::AppData)
+        .map_err(|e| e.to_string())
+}
+
+pub fn load_lsp_idle_settings(app: &AppHandle) -> Result<LspIdleSettings, String> {
+    let path = settings_path(app)?;
+    if !path.exists() {
+        return Ok(LspIdleSettings::default());
+    }
+    let data = fs::read_to_string(&path).map_err(|e| format!("读取 LSP 空闲设置失败: {e}"))?;
+    serde_json::from_str(&data).map_err(|e| format!("解析 LSP 空闲设置失败: {e}"))
+}
+
+pub fn save_lsp_idle_settings(app: &AppHandle, settings: &LspIdleSettings) -> Result<(), String> {
+    let path = settings_path(app)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("创建配置目录失败: {e}"))?;
+    }
+    let data = serde_json::to_string_pretty(settings)
+        .map_err(|e| format!("序列化 LSP 空闲设置失败: {e}"))?;
+    fs::write(&path, data).map_err(|e| format!("保存 LSP 空闲设置失败: {e}"))
+}
+
+impl PluginHost {
+    /// Stop `session_id` once it has been idle longer than the configured
+    /// timeout. The settings are read on every check, so changes apply to
+    /// running sessions too.
+    pub(super) fn spawn_idle_monitor(&self, session_id: &str, plugin_id: &str) {
+        let host = self.clone();
+        let session_id = session_id.to_string();
+        let plugin_id = plugin_id.to_string();
+        tauri::async_runtime::spawn(async move {
+            loop {
+                tokio::time::sleep(IDLE_CHECK_INTERVAL).await;
+                let Some(idle) = host.session_idle_time(&session_id).await else {
+                    break;
+                };
+                let timeout = load_lsp_idle_settings(host.app())
+                    .ok()
+                    .and_then(|settings| settings.timeout_for(&plugin_id));
+                if timeout.is_some_and(|timeout| idle >= timeout) {
+                    eprintln!(
+                        "[truidide::lsp] LSP 会话 {} 空闲 {} 分钟，正在停止",
+                        session_id,
+                        idle.as_secs() / 60
+                    );
+                    if let Err(err) = host
+                        .shutdown_session(&session_id, LspExitReason::Idle)
+                        .await
+                    {
+                        eprintln!(
+                            "[truidide::lsp] 停止空闲 LSP 会话失败 (session {}): {}",
+                            session_id, err
+                        );
+                    }
+                    break;
+                }
+            }
+        });
+    }
+}
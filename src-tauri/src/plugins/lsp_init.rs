@@ -0,0 +1,108 @@
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+
+use super::lsp_uri::{server_uri, translate_uri, UriDirection};
+use super::settings::merge_json;
+use super::PathMapping;
+
+// Capabilities of the editor's LSP client; plugins and callers can extend
+// them through `clientCapabilities`.
+fn default_client_capabilities() -> Value {
+    json!({
+        "general": { "positionEncodings": ["utf-16"] },
+        "textDocument": {
+            "synchronization": { "dynamicRegistration": false, "didSave": true },
+            "completion": {
+                "completionItem": {
+                    "snippetSupport": true,
+                    "documentationFormat": ["markdown", "plaintext"],
+                },
+            },
+            "hover": { "contentFormat": ["markdown", "plaintext"] },
+            "signatureHelp": {
+                "signatureInformation": { "documentationFormat": ["markdown", "plaintext"] },
+            },
+            "definition": {},
+            "declaration": {},
+            "typeDefinition": {},
+            "implementation": {},
+            "references": {},
+            "formatting": {},
+            "rename": { "prepareSupport": true },
+            "publishDiagnostics": { "relatedInformation": true },
+        },
+        "workspace": {
+            "workspaceFolders": true,
+            "applyEdit": true,
+            "didChangeWatchedFiles": { "dynamicRegistration": true, "relativePatternSupport": true },
+        },
+    })
+}
+
+// Rewrite a `file://` URI supplied by the editor into the server's view.
+fn map_client_uri(uri: &str, path_mapping: Option<&PathMapping>) -> String {
+    match path_mapping {
+        Some(mapping) => translate_uri(uri, mapping, UriDirection::ToGuest),
+        None => uri.to_string(),
+    }
+}
+
+/// Params of the `initialize` request the host sends for a new server.
+pub(super) fn compose_initialize_params(
+    workspace_path: &Path,
+    additional_workspaces: &[PathBuf],
+    path_mapping: Option<&PathMapping>,
+    client_capabilities: Option<Value>,
+    workspace_folders: Option<Value>,
+    initialization_options: Option<Value>,
+) -> Value {
+    let root_uri = server_uri(workspace_path, path_mapping);
+
+    let mut capabilities = default_client_capabilities();
+    if let Some(overlay) = client_capabilities {
+        merge_json(&mut capabilities, overlay);
+    }
+
+    let workspace_folders = match workspace_folders {
+        Some(Value::Array(folders)) => Value::Array(
+            folders
+                .into_iter()
+                .map(|mut folder| {
+                    let mapped = folder
+                        .get("uri")
+                        .and_then(Value::as_str)
+                        .map(|uri| map_client_uri(uri, path_mapping));
+                    if let Some(mapped) = mapped {
+                        folder["uri"] = Value::String(mapped);
+                    }
+                    folder
+                })
+                .collect(),
+        ),
+        _ => std::iter::once(workspace_path)
+            .chain(additional_workspaces.iter().map(PathBuf::as_path))
+            .map(|root| {
+                json!({
+                    "uri": server_uri(root, path_mapping),
+                    "name": root
+                        .file_name()
+                        .map(|name| name.to_string_lossy().into_owned())
+                        .unwrap_or_default(),
+                })
+            })
+            .collect(),
+    };
+
+    let mut params = json!({
+        "processId": null,
+        "clientInfo": { "name": "TruidIDE", "version": env!("CARGO_PKG_VERSION") },
+        "rootUri": root_uri,
+        "capabilities": capabilities,
+        "workspaceFolders": workspace_folders,
+    });
+    if let Some(options) = initialization_options {
+        params["initializationOptions"] = options;
+    }
+    params
+}
@@ -0,0 +1,208 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tauri::path::BaseDirectory;
+use tauri::{AppHandle, Manager};
+
+const LSP_TRACE_SETTINGS_FILENAME: &str = "lsp-trace.json";
+const LSP_LOGS_DIR: &str = "lsp-logs";
+const MAX_LOG_BYTES: u64 = 1024 * 1024;
+/// Rotated files kept next to the live one: `<id>.log.1`, `<id>.log.2`.
+const ROTATED_LOGS: usize = 2;
+const LOG_RETENTION: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+const DEFAULT_TAIL_LINES: usize = 200;
+
+/// Whether LSP sessions write their traffic to log files. Off by default:
+/// the logs contain document contents.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LspTraceSettings {
+    pub enabled: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetLspSessionLogArgs {
+    pub session_id: String,
+    #[serde(default)]
+    pub tail_lines: Option<usize>,
+}
+
+fn settings_path(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .resolve(LSP_TRACE_SETTINGS_FILENAME, BaseDirectory::AppData)
+        .map_err(|e| e.to_string())
+}
+
+fn logs_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .resolve(LSP_LOGS_DIR, BaseDirectory::AppData)
+        .map_err(|e| e.to_string())
+}
+
+pub fn load_lsp_trace_settings(app: &AppHandle) -> Result<LspTraceSettings, String> {
+    let path = settings_path(app)?;
+    if !path.exists() {
+        return Ok(LspTraceSettings::default());
+    }
+    let data = fs::read_to_string(&path).map_err(|e| format!("读取 LSP 日志设置失败: {e}"))?;
+    serde_json::from_str(&data).map_err(|e| format!("解析 LSP 日志设置失败: {e}"))
+}
+
+pub fn save_lsp_trace_settings(app: &AppHandle, settings: &LspTraceSettings) -> Result<(), String> {
+    let path = settings_path(app)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("创建配置目录失败: {e}"))?;
+    }
+    let data = serde_json::to_string_pretty(settings)
+        .map_err(|e| format!("序列化 LSP 日志设置失败: {e}"))?;
+    fs::write(&path, data).map_err(|e| format!("保存 LSP 日志设置失败: {e}"))
+}
+
+// Session ids become file names, so only accept what the host generates.
+fn log_path(dir: &Path, session_id: &str) -> Result<PathBuf, String> {
+    if session_id.is_empty()
+        || !session_id
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '-')
+    {
+        return Err(format!("无效的会话 ID: {session_id}"));
+    }
+    Ok(dir.join(format!("{session_id}.log")))
+}
+
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{index}"));
+    PathBuf::from(name)
+}
+
+fn prune_old_logs(dir: &Path) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let expired = entry
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age > LOG_RETENTION);
+        if expired {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
+
+struct TraceFile {
+    file: File,
+    written: u64,
+}
+
+/// Log file of one LSP session: framed messages in both directions and the
+/// server's stderr, rotated once it grows past `MAX_LOG_BYTES`.
+pub(super) struct SessionTrace {
+    path: PathBuf,
+    file: Mutex<TraceFile>,
+}
+
+impl SessionTrace {
+    fn open(path: PathBuf) -> Result<Self, String> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| format!("创建 LSP 日志文件失败: {e}"))?;
+        let written = file.metadata().map(|m| m.len()).unwrap_or(0);
+        Ok(Self {
+            path,
+            file: Mutex::new(TraceFile { file, written }),
+        })
+    }
+
+    /// Append one entry; `channel` is `send`, `recv` or `stderr`.
+    pub fn record(&self, channel: &str, text: &str) {
+        let Ok(mut trace) = self.file.lock() else {
+            return;
+        };
+        if trace.written >= MAX_LOG_BYTES {
+            if let Err(err) = self.rotate(&mut trace) {
+                eprintln!("[truidide::lsp] 轮转 LSP 日志失败: {}", err);
+                return;
+            }
+        }
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_millis())
+            .unwrap_or_default();
+        let line = format!("{millis} [{channel}] {}\n", text.trim_end());
+        if trace.file.write_all(line.as_bytes()).is_ok() {
+            trace.written += line.len() as u64;
+        }
+    }
+
+    fn rotate(&self, trace: &mut TraceFile) -> std::io::Result<()> {
+        for index in (1..ROTATED_LOGS).rev() {
+            let from = rotated_path(&self.path, index);
+            if from.exists() {
+                fs::rename(&from, rotated_path(&self.path, index + 1))?;
+            }
+        }
+        fs::rename(&self.path, rotated_path(&self.path, 1))?;
+        trace.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        trace.written = 0;
+        Ok(())
+    }
+}
+
+/// Open the trace log for a new session when tracing is enabled.
+pub(super) fn open_session_trace(app: &AppHandle, session_id: &str) -> Option<Arc<SessionTrace>> {
+    if !load_lsp_trace_settings(app).is_ok_and(|settings| settings.enabled) {
+        return None;
+    }
+    let opened = logs_dir(app).and_then(|dir| {
+        fs::create_dir_all(&dir).map_err(|e| format!("创建 LSP 日志目录失败: {e}"))?;
+        prune_old_logs(&dir);
+        SessionTrace::open(log_path(&dir, session_id)?)
+    });
+    match opened {
+        Ok(trace) => Some(Arc::new(trace)),
+        Err(err) => {
+            eprintln!(
+                "[truidide::lsp] 无法记录 LSP 会话 {} 的日志: {}",
+                session_id, err
+            );
+            None
+        }
+    }
+}
+
+/// Last `tail_lines` lines logged for a session, across rotated files.
+pub fn read_session_log(app: &AppHandle, args: GetLspSessionLogArgs) -> Result<String, String> {
+    let path = log_path(&logs_dir(app)?, &args.session_id)?;
+    let files = (1..=ROTATED_LOGS)
+        .rev()
+        .map(|index| rotated_path(&path, index))
+        .chain(std::iter::once(path))
+        .filter(|path| path.exists())
+        .collect::<Vec<_>>();
+    if files.is_empty() {
+        return Err(format!("没有找到 LSP 会话 {} 的日志", args.session_id));
+    }
+
+    let mut content = String::new();
+    for file in files {
+        let data = fs::read(&file).map_err(|e| format!("读取 LSP 日志失败: {e}"))?;
+        content.push_str(&String::from_utf8_lossy(&data));
+    }
+    let lines = content.lines().collect::<Vec<_>>();
+    let tail = args.tail_lines.unwrap_or(DEFAULT_TAIL_LINES);
+    Ok(lines[lines.len().saturating_sub(tail)..].join("\n"))
+}
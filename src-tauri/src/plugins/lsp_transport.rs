@@ -0,0 +1,248 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, BufReader};
+use tokio::net::TcpStream;
+use tokio::process::{Child, ChildStdout};
+
+use super::lsp_host::ReadMessageError;
+use super::LspTransport;
+
+const TCP_CONNECT_TIMEOUT: Duration = Duration::from_secs(20);
+const TCP_CONNECT_INTERVAL: Duration = Duration::from_millis(100);
+/// Descriptor the Node.js IPC channel is exposed on in the server process.
+#[cfg(unix)]
+const NODE_IPC_FD: i32 = 3;
+
+pub(super) type LspReader = Box<dyn AsyncRead + Send + Unpin>;
+pub(super) type LspWriter = Box<dyn AsyncWrite + Send + Unpin>;
+
+/// How messages are delimited on the wire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Framing {
+    /// `Content-Length` headers, used on stdio and TCP.
+    ContentLength,
+    /// One JSON document per line, as Node's `process.send` writes them.
+    JsonLines,
+}
+
+/// A transport set up before the server is spawned: the arguments and
+/// environment the server needs for it, plus what the host keeps to connect
+/// afterwards.
+pub(super) struct PreparedTransport {
+    pub args: Vec<String>,
+    pub env: HashMap<String, String>,
+    target: Target,
+}
+
+enum Target {
+    Stdio,
+    Tcp {
+        host: String,
+        port: u16,
+    },
+    #[cfg(unix)]
+    NodeIpc {
+        parent: std::os::unix::net::UnixStream,
+        child: std::os::unix::net::UnixStream,
+    },
+}
+
+/// Streams of a connected server. `stdout` is handed back when the server
+/// talks over another channel, so its output can still be logged.
+pub(super) struct LspConnection {
+    pub reader: LspReader,
+    pub writer: LspWriter,
+    pub framing: Framing,
+    pub stdout: Option<ChildStdout>,
+}
+
+fn free_tcp_port() -> Result<u16, String> {
+    std::net::TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .map(|addr| addr.port())
+        .map_err(|e| format!("无法分配 LSP 端口: {e}"))
+}
+
+pub(super) fn prepare_transport(
+    transport: &LspTransport,
+    args: &[String],
+    env: &HashMap<String, String>,
+) -> Result<PreparedTransport, String> {
+    let mut args = args.to_vec();
+    let mut env = env.clone();
+    let target = match transport {
+        LspTransport::Stdio => Target::Stdio,
+        LspTransport::Tcp { port, host } => {
+            let port = match port {
+                Some(port) => *port,
+                None => free_tcp_port()?,
+            };
+            for arg in &mut args {
+                *arg = arg.replace("${port}", &port.to_string());
+            }
+            Target::Tcp {
+                host: host.clone(),
+                port,
+            }
+        }
+        #[cfg(unix)]
+        LspTransport::NodeIpc => {
+            let (parent, child) = std::os::unix::net::UnixStream::pair()
+                .map_err(|e| format!("创建 node-ipc 通道失败: {e}"))?;
+            args.push("--node-ipc".into());
+            env.insert("NODE_CHANNEL_FD".into(), NODE_IPC_FD.to_string());
+            env.insert("NODE_CHANNEL_SERIALIZATION_MODE".into(), "json".into());
+            Target::NodeIpc { parent, child }
+        }
+        #[cfg(not(unix))]
+        LspTransport::NodeIpc => {
+            return Err("node-ipc 传输仅支持类 Unix 系统".into());
+        }
+    };
+    Ok(PreparedTransport { args, env, target })
+}
+
+impl PreparedTransport {
+    /// Descriptor to expose to the server as its IPC channel, if any.
+    pub fn ipc_fd(&self) -> Option<i32> {
+        match &self.target {
+            #[cfg(unix)]
+            Target::NodeIpc { child, .. } => {
+                use std::os::fd::AsRawFd;
+                Some(child.as_raw_fd())
+            }
+            _ => None,
+        }
+    }
+
+    /// Connect to the freshly spawned `child` over the prepared channel.
+    pub async fn connect(self, child: &mut Child) -> Result<LspConnection, String> {
+        match self.target {
+            Target::Stdio => {
+                let stdin = child
+                    .stdin
+                    .take()
+                    .ok_or_else(|| "无法获取 LSP 进程的标准输入".to_string())?;
+                let stdout = child
+                    .stdout
+                    .take()
+                    .ok_or_else(|| "无法获取 LSP 进程的标准输出".to_string())?;
+                Ok(LspConnection {
+                    reader: Box::new(stdout),
+                    writer: Box::new(stdin),
+                    framing: Framing::ContentLength,
+                    stdout: None,
+                })
+            }
+            Target::Tcp { host, port } => {
+                let stream = connect_tcp(child, &host, port).await?;
+                let (reader, writer) = stream.into_split();
+                Ok(LspConnection {
+                    reader: Box::new(reader),
+                    writer: Box::new(writer),
+                    framing: Framing::ContentLength,
+                    stdout: child.stdout.take(),
+                })
+            }
+            #[cfg(unix)]
+            Target::NodeIpc { parent, child: ipc } => {
+                // The server holds its own copy now.
+                drop(ipc);
+                parent
+                    .set_nonblocking(true)
+                    .map_err(|e| format!("配置 node-ipc 通道失败: {e}"))?;
+                let stream = tokio::net::UnixStream::from_std(parent)
+                    .map_err(|e| format!("配置 node-ipc 通道失败: {e}"))?;
+                let (reader, writer) = stream.into_split();
+                Ok(LspConnection {
+                    reader: Box::new(reader),
+                    writer: Box::new(writer),
+                    framing: Framing::JsonLines,
+                    stdout: child.stdout.take(),
+                })
+            }
+        }
+    }
+}
+
+// Servers need a moment to start listening, so retry until they accept or
+// exit.
+async fn connect_tcp(child: &mut Child, host: &str, port: u16) -> Result<TcpStream, String> {
+    let deadline = Instant::now() + TCP_CONNECT_TIMEOUT;
+    loop {
+        match TcpStream::connect((host, port)).await {
+            Ok(stream) => return Ok(stream),
+            Err(err) => {
+                if let Ok(Some(status)) = child.try_wait() {
+                    return Err(format!("语言服务器在建立 TCP 连接前退出 ({status})"));
+                }
+                if Instant::now() >= deadline {
+                    return Err(format!("无法连接到语言服务器 {host}:{port}: {err}"));
+                }
+            }
+        }
+        tokio::time::sleep(TCP_CONNECT_INTERVAL).await;
+    }
+}
+
+/// Make `fd` the server's Node.js IPC descriptor.
+#[cfg(unix)]
+pub(super) fn expose_ipc_fd(command: &mut tokio::process::Command, fd: i32) {
+    // SAFETY: the hook only makes async-signal-safe libc calls.
+    unsafe {
+        command.pre_exec(move || {
+            if fd == NODE_IPC_FD {
+                // dup2 onto itself would keep FD_CLOEXEC set.
+                let flags = libc::fcntl(fd, libc::F_GETFD);
+                if flags < 0 || libc::fcntl(fd, libc::F_SETFD, flags & !libc::FD_CLOEXEC) < 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+            } else if libc::dup2(fd, NODE_IPC_FD) < 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+}
+
+/// Read one message framed as `framing`.
+pub(super) async fn read_message(
+    reader: &mut BufReader<LspReader>,
+    framing: Framing,
+) -> Result<Vec<u8>, ReadMessageError> {
+    match framing {
+        Framing::ContentLength => super::lsp_host::read_framed_message(reader).await,
+        Framing::JsonLines => loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).await? == 0 {
+                return Err(ReadMessageError::Eof);
+            }
+            let line = line.trim();
+            if !line.is_empty() {
+                return Ok(line.as_bytes().to_vec());
+            }
+        },
+    }
+}
+
+/// JSON body of a `Content-Length` framed message.
+pub(super) fn message_body(message: &[u8]) -> &[u8] {
+    message
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .map_or(message, |end| &message[end + 4..])
+}
+
+/// Convert a `Content-Length` framed message, as queued by the host, into
+/// what goes on the wire for `framing`.
+pub(super) fn encode_message(message: Vec<u8>, framing: Framing) -> Vec<u8> {
+    match framing {
+        Framing::ContentLength => message,
+        Framing::JsonLines => {
+            let mut line = message_body(&message).to_vec();
+            line.push(b'\n');
+            line
+        }
+    }
+}
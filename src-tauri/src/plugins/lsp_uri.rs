@@ -0,0 +1,104 @@
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+use tauri::Url;
+
+use super::PathMapping;
+
+/// Which side of the proot mount a translated URI should point to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum UriDirection {
+    /// Editor paths to the paths the server sees inside the guest.
+    ToGuest,
+    /// Server paths back to the editor's host paths.
+    ToHost,
+}
+
+fn map_path(path: &Path, mapping: &PathMapping, direction: UriDirection) -> Option<PathBuf> {
+    let mut prefixes = [
+        (&mapping.host_workspace, &mapping.guest_workspace),
+        (&mapping.host_plugin, &mapping.guest_plugin),
+    ]
+    .into_iter()
+    .chain(
+        mapping
+            .additional_workspaces
+            .iter()
+            .map(|mount| (&mount.host_workspace, &mount.guest_workspace)),
+    );
+    prefixes.find_map(|(host, guest)| {
+        let (from, to) = match direction {
+            UriDirection::ToGuest => (host, guest),
+            UriDirection::ToHost => (guest, host),
+        };
+        path.strip_prefix(from)
+            .ok()
+            .map(|relative| Path::new(to).join(relative))
+    })
+}
+
+fn file_uri(path: &Path) -> String {
+    Url::from_file_path(path)
+        .map(String::from)
+        .unwrap_or_else(|_| format!("file://{}", path.display()))
+}
+
+/// `path` as the server sees it, i.e. inside the guest when the server runs
+/// in proot.
+pub(super) fn server_path(path: &Path, path_mapping: Option<&PathMapping>) -> PathBuf {
+    path_mapping
+        .and_then(|mapping| map_path(path, mapping, UriDirection::ToGuest))
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+/// `file://` URI of `path` as the server sees it, i.e. inside the guest when
+/// the server runs in proot.
+pub(super) fn server_uri(path: &Path, path_mapping: Option<&PathMapping>) -> String {
+    file_uri(&server_path(path, path_mapping))
+}
+
+/// Translate one `file://` URI; anything outside the mapped directories or
+/// not a file URI is returned unchanged.
+pub(super) fn translate_uri(uri: &str, mapping: &PathMapping, direction: UriDirection) -> String {
+    Url::parse(uri)
+        .ok()
+        .filter(|url| url.scheme() == "file")
+        .and_then(|url| url.to_file_path().ok())
+        .and_then(|path| map_path(&path, mapping, direction))
+        .map(|path| file_uri(&path))
+        .unwrap_or_else(|| uri.to_string())
+}
+
+fn is_uri_key(key: &str) -> bool {
+    key == "uri" || key == "target" || key.ends_with("Uri")
+}
+
+/// Rewrite every document URI in an LSP message: `uri`/`*Uri`/`target`
+/// fields, and the keys of `WorkspaceEdit.changes`, which are URIs too.
+pub(super) fn translate_uris(value: &mut Value, mapping: &PathMapping, direction: UriDirection) {
+    match value {
+        Value::Array(items) => {
+            for item in items {
+                translate_uris(item, mapping, direction);
+            }
+        }
+        Value::Object(object) => {
+            for (key, field) in object.iter_mut() {
+                match field {
+                    Value::String(uri) if is_uri_key(key) => {
+                        *uri = translate_uri(uri, mapping, direction);
+                    }
+                    Value::Object(changes) if key == "changes" => {
+                        let entries = std::mem::take(changes);
+                        for (uri, mut edits) in entries {
+                            translate_uris(&mut edits, mapping, direction);
+                            changes.insert(translate_uri(&uri, mapping, direction), edits);
+                        }
+                    }
+                    _ => translate_uris(field, mapping, direction),
+                }
+            }
+        }
+        _ => {}
+    }
+}
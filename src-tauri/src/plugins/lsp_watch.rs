@@ -0,0 +1,280 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use glob::{MatchOptions, Pattern};
+use notify::event::{ModifyKind, RenameMode};
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use serde_json::{json, Value};
+use tauri::Url;
+use tokio::sync::mpsc;
+
+use super::lsp_host::frame_message;
+use super::lsp_uri::{server_path, server_uri};
+use super::PathMapping;
+
+pub(super) const WATCHED_FILES_METHOD: &str = "workspace/didChangeWatchedFiles";
+// Builds and `git checkout` touch many files at once; send them as one batch.
+const CHANGE_DEBOUNCE: Duration = Duration::from_millis(200);
+
+// `FileChangeType` values.
+const FILE_CREATED: u8 = 1;
+const FILE_CHANGED: u8 = 2;
+const FILE_DELETED: u8 = 3;
+
+// One `FileSystemWatcher` from a registration, with paths as the server sees
+// them.
+struct FileWatcher {
+    patterns: Vec<Pattern>,
+    base: Option<PathBuf>,
+    /// `WatchKind` bit mask: create = 1, change = 2, delete = 4.
+    kind: u8,
+}
+
+impl FileWatcher {
+    fn parse(value: &Value) -> Option<Self> {
+        let glob = value.get("globPattern")?;
+        let (pattern, base) = match glob {
+            Value::String(pattern) => (pattern.as_str(), None),
+            Value::Object(relative) => {
+                let base_uri = match relative.get("baseUri")? {
+                    Value::String(uri) => uri.as_str(),
+                    folder => folder.get("uri")?.as_str()?,
+                };
+                let base = Url::parse(base_uri).ok()?.to_file_path().ok()?;
+                (relative.get("pattern")?.as_str()?, Some(base))
+            }
+            _ => return None,
+        };
+        let patterns = expand_braces(pattern)
+            .iter()
+            .filter_map(|pattern| Pattern::new(pattern).ok())
+            .collect::<Vec<_>>();
+        if patterns.is_empty() {
+            return None;
+        }
+        let kind = value
+            .get("kind")
+            .and_then(Value::as_u64)
+            .map_or(7, |kind| kind as u8);
+        Some(Self {
+            patterns,
+            base,
+            kind,
+        })
+    }
+
+    fn matches(&self, path: &Path, roots: &[PathBuf], change: u8) -> bool {
+        let mask = match change {
+            FILE_CREATED => 1,
+            FILE_CHANGED => 2,
+            _ => 4,
+        };
+        if self.kind & mask == 0 {
+            return false;
+        }
+
+        let options = MatchOptions {
+            require_literal_separator: true,
+            ..MatchOptions::new()
+        };
+        let mut candidates = Vec::new();
+        match &self.base {
+            Some(base) => candidates.extend(path.strip_prefix(base).ok()),
+            None => {
+                candidates.push(path);
+                candidates.extend(roots.iter().filter_map(|root| path.strip_prefix(root).ok()));
+            }
+        }
+        candidates.iter().any(|candidate| {
+            self.patterns
+                .iter()
+                .any(|pattern| pattern.matches_path_with(candidate, options))
+        })
+    }
+}
+
+// `glob` has no `{a,b}` alternation, which servers use a lot (`**/*.{ts,tsx}`).
+fn expand_braces(pattern: &str) -> Vec<String> {
+    let Some(open) = pattern.find('{') else {
+        return vec![pattern.to_string()];
+    };
+    let mut depth = 0;
+    let mut close = None;
+    let mut splits = Vec::new();
+    for (index, ch) in pattern[open..].char_indices() {
+        match ch {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    close = Some(open + index);
+                    break;
+                }
+            }
+            ',' if depth == 1 => splits.push(open + index),
+            _ => {}
+        }
+    }
+    let Some(close) = close else {
+        return vec![pattern.to_string()];
+    };
+
+    let mut bounds = vec![open];
+    bounds.extend(splits);
+    bounds.push(close);
+    let (prefix, suffix) = (&pattern[..open], &pattern[close + 1..]);
+    bounds
+        .windows(2)
+        .flat_map(|pair| {
+            expand_braces(&format!(
+                "{prefix}{}{suffix}",
+                &pattern[pair[0] + 1..pair[1]]
+            ))
+        })
+        .collect()
+}
+
+fn classify(event: &Event) -> Vec<(PathBuf, u8)> {
+    let existing = |path: &PathBuf| {
+        if path.exists() {
+            FILE_CREATED
+        } else {
+            FILE_DELETED
+        }
+    };
+    match &event.kind {
+        EventKind::Create(_) => event
+            .paths
+            .iter()
+            .map(|p| (p.clone(), FILE_CREATED))
+            .collect(),
+        EventKind::Remove(_) => event
+            .paths
+            .iter()
+            .map(|p| (p.clone(), FILE_DELETED))
+            .collect(),
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if event.paths.len() == 2 => vec![
+            (event.paths[0].clone(), FILE_DELETED),
+            (event.paths[1].clone(), FILE_CREATED),
+        ],
+        EventKind::Modify(ModifyKind::Name(_)) => event
+            .paths
+            .iter()
+            .map(|p| (p.clone(), existing(p)))
+            .collect(),
+        EventKind::Modify(_) => event
+            .paths
+            .iter()
+            .map(|p| (p.clone(), FILE_CHANGED))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Files an LSP server asked to be told about through dynamic registration
+/// of `workspace/didChangeWatchedFiles`, and the watcher feeding it.
+pub(super) struct WatchedFiles {
+    registrations: Arc<Mutex<HashMap<String, Vec<FileWatcher>>>>,
+    // Dropping the watcher ends the forwarding task.
+    _watcher: notify::RecommendedWatcher,
+}
+
+impl WatchedFiles {
+    /// Watch `roots` (host paths) and forward matching changes to the server
+    /// through `write_tx`.
+    pub fn start(
+        roots: &[PathBuf],
+        path_mapping: Option<PathMapping>,
+        write_tx: &mpsc::Sender<Vec<u8>>,
+    ) -> Result<Self, String> {
+        let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+        let mut watcher =
+            notify::recommended_watcher(move |result: notify::Result<Event>| match result {
+                Ok(event) => {
+                    let _ = tx.send(event);
+                }
+                Err(err) => eprintln!("[truidide::lsp] 监听工作区文件出错: {}", err),
+            })
+            .map_err(|e| format!("创建工作区文件监听失败: {e}"))?;
+        for root in roots {
+            watcher
+                .watch(root, RecursiveMode::Recursive)
+                .map_err(|e| format!("监听工作区 {} 失败: {e}", root.display()))?;
+        }
+
+        let registrations: Arc<Mutex<HashMap<String, Vec<FileWatcher>>>> = Default::default();
+        let task_registrations = registrations.clone();
+        let guest_roots = roots
+            .iter()
+            .map(|root| server_path(root, path_mapping.as_ref()))
+            .collect::<Vec<_>>();
+        // Weak, so a stopping session is not kept alive by its watcher.
+        let write_tx = write_tx.downgrade();
+        tauri::async_runtime::spawn(async move {
+            while let Some(event) = rx.recv().await {
+                let mut changes: HashMap<PathBuf, u8> = classify(&event).into_iter().collect();
+                while let Ok(Some(event)) = tokio::time::timeout(CHANGE_DEBOUNCE, rx.recv()).await {
+                    changes.extend(classify(&event));
+                }
+
+                let changes = {
+                    let registrations = task_registrations.lock().unwrap();
+                    changes
+                        .into_iter()
+                        .filter(|(path, change)| {
+                            let guest = server_path(path, path_mapping.as_ref());
+                            registrations
+                                .values()
+                                .flatten()
+                                .any(|watcher| watcher.matches(&guest, &guest_roots, *change))
+                        })
+                        .map(|(path, change)| {
+                            json!({
+                                "uri": server_uri(&path, path_mapping.as_ref()),
+                                "type": change,
+                            })
+                        })
+                        .collect::<Vec<_>>()
+                };
+                if changes.is_empty() {
+                    continue;
+                }
+                let Some(write_tx) = write_tx.upgrade() else {
+                    break;
+                };
+                let notification = json!({
+                    "jsonrpc": "2.0",
+                    "method": WATCHED_FILES_METHOD,
+                    "params": { "changes": changes },
+                });
+                let payload = serde_json::to_vec(&notification).unwrap_or_default();
+                if write_tx.send(frame_message(&payload)).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Self {
+            registrations,
+            _watcher: watcher,
+        })
+    }
+
+    pub fn register(&self, id: &str, register_options: Option<&Value>) {
+        let watchers = register_options
+            .and_then(|options| options.get("watchers"))
+            .and_then(Value::as_array)
+            .map(|watchers| watchers.iter().filter_map(FileWatcher::parse).collect())
+            .unwrap_or_default();
+        self.registrations
+            .lock()
+            .unwrap()
+            .insert(id.to_string(), watchers);
+    }
+
+    pub fn unregister(&self, id: &str) {
+        self.registrations.lock().unwrap().remove(id);
+    }
+}
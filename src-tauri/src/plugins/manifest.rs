@@ -1,14 +1,24 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+
+use super::bus::BusPermissions;
+
+/// Oldest plugin API version the host can still load.
+pub const MIN_PLUGIN_API_VERSION: u32 = 1;
+/// Plugin API version implemented by this host.
+pub const CURRENT_PLUGIN_API_VERSION: u32 = 2;
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PluginManifest {
+    /// Plugin API version the manifest targets. Manifests without it are treated as version 1.
+    #[serde(default)]
+    pub api_version: Option<u32>,
     pub id: String,
-    pub name: String,
+    pub name: LocalizedText,
     pub version: String,
     #[serde(default)]
-    pub description: Option<String>,
+    pub description: Option<LocalizedText>,
     #[serde(default)]
     pub author: Option<String>,
     #[serde(default = "default_enabled")]
@@ -16,18 +26,330 @@ pub struct PluginManifest {
     #[serde(default)]
     pub tags: Vec<String>,
     pub kind: PluginKind,
+    /// Message bus topics the plugin may publish to or subscribe to.
+    #[serde(default)]
+    pub bus: BusPermissions,
+    /// User-editable settings the plugin understands.
+    #[serde(default)]
+    pub configuration: PluginConfiguration,
+    /// Host requirements checked before the plugin is installed or started.
+    #[serde(default)]
+    pub engines: PluginEngines,
+    /// What the plugin's processes may access. Manifests without it keep the
+    /// unrestricted access plugins had before permissions existed.
+    #[serde(default)]
+    pub permissions: Option<PluginPermissions>,
+    /// Other plugins this one needs, by id, with a version requirement
+    /// (`*` for any version).
+    #[serde(default)]
+    pub dependencies: BTreeMap<String, String>,
     #[serde(default)]
     pub extra: HashMap<String, serde_json::Value>,
+    /// Localized variants of top-level fields, e.g. `name@zh-CN` or `description@en`.
+    #[serde(flatten)]
+    pub localized_fields: HashMap<String, serde_json::Value>,
 }
 
 fn default_enabled() -> bool {
     true
 }
 
+/// Plain text, or locale tags mapped to text such as
+/// `{"en": "Rust", "zh-CN": "Rust 语言"}`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum LocalizedText {
+    Plain(String),
+    Localized(BTreeMap<String, String>),
+}
+
+impl LocalizedText {
+    /// Text shown when no variant matches the locale: English if present,
+    /// otherwise the first entry.
+    pub fn fallback(&self) -> &str {
+        match self {
+            LocalizedText::Plain(text) => text,
+            LocalizedText::Localized(variants) => {
+                let variants = variants
+                    .iter()
+                    .map(|(tag, text)| (tag.as_str(), text.as_str()))
+                    .collect::<Vec<_>>();
+                match_locale(&variants, "en")
+                    .or_else(|| variants.first().map(|(_, text)| *text))
+                    .unwrap_or_default()
+            }
+        }
+    }
+}
+
+/// Pick the variant for `locale` (e.g. `zh-CN`): the exact tag first, then
+/// the bare language (`zh`), then any other region of the same language.
+fn match_locale<'a>(variants: &[(&str, &'a str)], locale: &str) -> Option<&'a str> {
+    let locale = locale.replace('_', "-");
+    let language = locale.split('-').next().unwrap_or(&locale).to_string();
+
+    let exact = variants
+        .iter()
+        .find(|(tag, _)| tag.eq_ignore_ascii_case(&locale));
+    let bare = || {
+        variants
+            .iter()
+            .find(|(tag, _)| tag.eq_ignore_ascii_case(&language))
+    };
+    let same_language = || {
+        variants.iter().find(|(tag, _)| {
+            tag.split('-')
+                .next()
+                .is_some_and(|lang| lang.eq_ignore_ascii_case(&language))
+        })
+    };
+
+    exact
+        .or_else(bare)
+        .or_else(same_language)
+        .map(|(_, text)| *text)
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginEngines {
+    /// Minimum TruidIDE version (`0.2.0`) or a semver requirement (`>=0.2, <0.4`).
+    #[serde(default)]
+    pub truidide: Option<String>,
+    /// CPU architectures (`aarch64`) or Android ABIs (`arm64-v8a`) the
+    /// plugin's binaries run on. Any architecture when empty.
+    #[serde(default)]
+    pub arch: Vec<String>,
+    /// Commands that must be installed, with a version requirement such as
+    /// `{"node": ">=18"}`. Checked by running `<command> --version`.
+    #[serde(default)]
+    pub runtimes: BTreeMap<String, String>,
+}
+
+/// Access granted to a plugin's processes, shown to the user for consent
+/// before installation.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginPermissions {
+    #[serde(default)]
+    pub filesystem: FilesystemScope,
+    /// Network access. Denied by pointing the proxy variables at a closed
+    /// port, which tools that honour them respect; it is not a firewall.
+    #[serde(default)]
+    pub network: bool,
+    /// Starting programs through the host, such as a web extension's
+    /// `run-tool` requests.
+    #[serde(default)]
+    pub spawn_processes: bool,
+}
+
+impl PluginPermissions {
+    /// Whether installing needs the user's consent, i.e. anything beyond
+    /// the workspace without network or extra processes.
+    pub fn needs_consent(&self) -> bool {
+        *self != PluginPermissions::default()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FilesystemScope {
+    /// The workspace and the plugin directory, with a private home instead
+    /// of the user's.
+    #[default]
+    Workspace,
+    /// Everything the app's Linux environment can reach, including `/root`.
+    Full,
+}
+
+/// Parse a requirement, treating a bare version as a minimum.
+pub fn parse_version_requirement(raw: &str) -> Result<semver::VersionReq, semver::Error> {
+    let raw = raw.trim();
+    match semver::Version::parse(raw) {
+        Ok(version) => semver::VersionReq::parse(&format!(">={version}")),
+        Err(_) => semver::VersionReq::parse(raw),
+    }
+}
+
+fn normalize_arch(arch: &str) -> &str {
+    match arch.trim() {
+        "arm64-v8a" | "arm64" => "aarch64",
+        "armeabi-v7a" | "armv7" => "arm",
+        "amd64" => "x86_64",
+        "i686" | "i386" => "x86",
+        other => other,
+    }
+}
+
+/// Settings schema, modelled after VSCode's `contributes.configuration`.
+///
+/// Keys are dotted names such as `check.command`; they become nested objects
+/// when merged into LSP initialization options.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginConfiguration {
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub properties: BTreeMap<String, SettingSchema>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingSchema {
+    #[serde(rename = "type")]
+    pub value_type: SettingType,
+    #[serde(default)]
+    pub default: Option<serde_json::Value>,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Allowed values; any value of `type` is accepted when absent.
+    #[serde(default, rename = "enum")]
+    pub allowed_values: Option<Vec<serde_json::Value>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SettingType {
+    String,
+    Number,
+    Integer,
+    Boolean,
+    Array,
+    Object,
+}
+
+impl PluginManifest {
+    pub fn effective_api_version(&self) -> u32 {
+        self.api_version.unwrap_or(MIN_PLUGIN_API_VERSION)
+    }
+
+    /// Declared permissions, or full access for manifests that predate them.
+    pub fn effective_permissions(&self) -> PluginPermissions {
+        self.permissions.clone().unwrap_or(PluginPermissions {
+            filesystem: FilesystemScope::Full,
+            network: true,
+            spawn_processes: true,
+        })
+    }
+
+    /// Check the declared API version against the range supported by the host.
+    pub fn check_api_version(&self) -> Result<(), String> {
+        let version = self.effective_api_version();
+        if version < MIN_PLUGIN_API_VERSION {
+            return Err(format!(
+                "插件 API 版本 {version} 过旧，宿主最低支持 {MIN_PLUGIN_API_VERSION}"
+            ));
+        }
+        if version > CURRENT_PLUGIN_API_VERSION {
+            return Err(format!(
+                "插件 API 版本 {version} 高于宿主支持的 {CURRENT_PLUGIN_API_VERSION}，请升级应用"
+            ));
+        }
+        Ok(())
+    }
+
+    /// Check the host version and architecture against `engines`. Runtimes
+    /// need a process to be spawned and are checked separately.
+    pub fn check_engines(&self) -> Result<(), String> {
+        if let Some(raw) = &self.engines.truidide {
+            let requirement = parse_version_requirement(raw)
+                .map_err(|e| format!("插件清单中的 engines.truidide 无效: {e}"))?;
+            let current = env!("CARGO_PKG_VERSION");
+            let satisfied =
+                semver::Version::parse(current).is_ok_and(|version| requirement.matches(&version));
+            if !satisfied {
+                return Err(format!(
+                    "插件需要 TruidIDE {raw}，当前版本为 {current}，请升级应用"
+                ));
+            }
+        }
+
+        let arch = std::env::consts::ARCH;
+        if !self.engines.arch.is_empty()
+            && !self
+                .engines
+                .arch
+                .iter()
+                .any(|supported| normalize_arch(supported) == arch)
+        {
+            return Err(format!(
+                "插件仅支持 {} 架构，当前设备为 {arch}",
+                self.engines.arch.join(", ")
+            ));
+        }
+        Ok(())
+    }
+
+    /// Resolve a localized variant of `field` for `locale` (e.g. `zh-CN`),
+    /// from either a `{field}@{tag}` key or a localization map in `field`.
+    pub fn localized(&self, field: &str, locale: Option<&str>) -> Option<String> {
+        let locale = locale?;
+        let prefix = format!("{field}@");
+
+        let mut variants = self
+            .localized_fields
+            .iter()
+            .filter_map(|(key, value)| {
+                let tag = key.strip_prefix(&prefix)?;
+                Some((tag, value.as_str()?))
+            })
+            .collect::<Vec<_>>();
+        let text = match field {
+            "name" => Some(&self.name),
+            "description" => self.description.as_ref(),
+            _ => None,
+        };
+        if let Some(LocalizedText::Localized(map)) = text {
+            variants.extend(map.iter().map(|(tag, text)| (tag.as_str(), text.as_str())));
+        }
+
+        match_locale(&variants, locale).map(str::to_string)
+    }
+
+    pub fn localized_name(&self, locale: Option<&str>) -> String {
+        self.localized("name", locale)
+            .unwrap_or_else(|| self.name.fallback().to_string())
+    }
+
+    pub fn localized_description(&self, locale: Option<&str>) -> Option<String> {
+        self.localized("description", locale).or_else(|| {
+            self.description
+                .as_ref()
+                .map(|description| description.fallback().to_string())
+        })
+    }
+
+    /// Collect deprecation warnings for manifest fields that are still accepted
+    /// but scheduled for removal.
+    pub fn deprecation_warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if self.api_version.is_none() {
+            warnings.push(format!(
+                "清单未声明 apiVersion，按版本 {MIN_PLUGIN_API_VERSION} 处理；请声明 \"apiVersion\": {CURRENT_PLUGIN_API_VERSION}"
+            ));
+        }
+        if let PluginKind::Lsp(manifest) = &self.kind {
+            if manifest.force_proot {
+                warnings.push("字段 kind.forceProot 已弃用且不再生效".to_string());
+            }
+        }
+        warnings
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(tag = "type", rename_all = "kebab-case")]
 pub enum PluginKind {
     Lsp(LspPluginManifest),
+    WebExtension(WebExtensionManifest),
+    Dap(DapPluginManifest),
+    Formatter(FormatterPluginManifest),
+    Linter(LinterPluginManifest),
+    Snippets(SnippetsPluginManifest),
+    Grammar(GrammarPluginManifest),
+    Theme(ThemePluginManifest),
+    Runner(RunnerPluginManifest),
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -49,7 +371,7 @@ pub struct LspPluginManifest {
     /// User-provided initialization options that will be forwarded to the language server.
     #[serde(default)]
     pub initialization_options: Option<serde_json::Value>,
-    /// Android-specific flag to force proot usage even on host platforms (mainly for testing).
+    /// Deprecated: Android-specific flag to force proot usage even on host platforms.
     #[serde(default)]
     pub force_proot: bool,
     /// Optional absolute path inside the guest rootfs (proot) to mount the plugin directory to.
@@ -58,4 +380,413 @@ pub struct LspPluginManifest {
     /// Optional absolute path inside the guest rootfs (proot) to mount the workspace/project to.
     #[serde(default)]
     pub workspace_mount_path: Option<String>,
+    /// Whether the server is respawned after exiting on its own.
+    #[serde(default)]
+    pub restart: LspRestartPolicy,
+    /// Seconds to wait for a response before reporting a request as timed out.
+    #[serde(default)]
+    pub request_timeout_secs: Option<u64>,
+    /// Seconds a stopping server gets to handle `shutdown`/`exit` before it
+    /// is killed.
+    #[serde(default)]
+    pub shutdown_grace_secs: Option<u64>,
+    /// How the host exchanges messages with the server.
+    #[serde(default)]
+    pub transport: LspTransport,
+    /// Server binary downloaded on first start instead of shipped in the package.
+    #[serde(default)]
+    pub download: Option<LspServerDownload>,
+    /// Order among plugins serving the same language; higher comes first
+    /// unless the user picked a default.
+    #[serde(default)]
+    pub priority: i32,
+}
+
+/// Where to fetch a language server from, per CPU architecture.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LspServerDownload {
+    /// Keyed by Rust's architecture names (`aarch64`, `x86_64`, `arm`, `x86`).
+    pub targets: HashMap<String, LspDownloadTarget>,
+    /// Relative to the plugin root: the directory `.zip`/`.tar.xz`/`.tar`
+    /// archives are unpacked into, or the file a bare binary is saved as.
+    /// Defaults to the plugin root itself for archives.
+    #[serde(default)]
+    pub unpack_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LspDownloadTarget {
+    pub url: String,
+    #[serde(default)]
+    pub sha256: Option<String>,
+}
+
+/// Channel an LSP server speaks JSON-RPC over.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum LspTransport {
+    /// `Content-Length` framed messages on stdin/stdout.
+    #[default]
+    Stdio,
+    /// The server listens on a TCP port and the host connects once it is up.
+    /// Without `port` the host picks a free one; `${port}` in `args` is
+    /// replaced with the chosen port.
+    Tcp {
+        #[serde(default)]
+        port: Option<u16>,
+        #[serde(default = "default_tcp_host")]
+        host: String,
+    },
+    /// Node.js IPC channel (`--node-ipc`), as used by vscode-languageserver.
+    NodeIpc,
+}
+
+fn default_tcp_host() -> String {
+    "127.0.0.1".to_string()
+}
+
+/// Respawn policy for a language server that exits unexpectedly.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LspRestartPolicy {
+    /// Respawns in a row before giving up; 0 disables automatic restarts.
+    #[serde(default)]
+    pub max_retries: u32,
+    /// Delay before the first respawn, doubled for each following attempt.
+    #[serde(default = "default_restart_delay_ms")]
+    pub initial_delay_ms: u64,
+}
+
+impl Default for LspRestartPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 0,
+            initial_delay_ms: default_restart_delay_ms(),
+        }
+    }
+}
+
+fn default_restart_delay_ms() -> u64 {
+    1000
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DapPluginManifest {
+    /// Debug types (the `type` of a launch configuration) served by the adapter.
+    pub debug_types: Vec<String>,
+    /// Languages the adapter can debug, used to suggest it for open files.
+    #[serde(default)]
+    pub language_ids: Vec<String>,
+    /// Command or executable to spawn. Relative paths resolve against the plugin root.
+    pub command: String,
+    /// Additional command-line arguments.
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Environment variables to inject when spawning the adapter.
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Optional working directory. Relative paths resolve against the plugin root.
+    #[serde(default)]
+    pub cwd: Option<String>,
+    /// Optional absolute path inside the guest rootfs (proot) to mount the plugin directory to.
+    #[serde(default)]
+    pub plugin_mount_path: Option<String>,
+    /// Optional absolute path inside the guest rootfs (proot) to mount the workspace/project to.
+    #[serde(default)]
+    pub workspace_mount_path: Option<String>,
+}
+
+/// A formatter reads the document on stdin and writes the formatted text to
+/// stdout, exiting non-zero on failure.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormatterPluginManifest {
+    /// Languages the formatter handles.
+    pub language_ids: Vec<String>,
+    /// Command or executable to spawn. Relative paths resolve against the plugin root.
+    pub command: String,
+    /// Additional command-line arguments. `${file}` expands to the document
+    /// path relative to the workspace, e.g. for `--stdin-filepath`.
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Environment variables to inject when spawning the formatter.
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Optional working directory. Defaults to the workspace so project config is found.
+    #[serde(default)]
+    pub cwd: Option<String>,
+    /// Seconds to wait before the formatter is killed.
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    /// Optional absolute path inside the guest rootfs (proot) to mount the plugin directory to.
+    #[serde(default)]
+    pub plugin_mount_path: Option<String>,
+    /// Optional absolute path inside the guest rootfs (proot) to mount the workspace/project to.
+    #[serde(default)]
+    pub workspace_mount_path: Option<String>,
+}
+
+/// A command-line linter whose output is turned into diagnostics by a
+/// problem matcher, for languages without a language server.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LinterPluginManifest {
+    /// Languages the linter handles when run on demand.
+    pub language_ids: Vec<String>,
+    /// Globs of files that are linted automatically after being saved.
+    #[serde(default)]
+    pub file_patterns: Vec<String>,
+    /// Command or executable to spawn. Relative paths resolve against the plugin root.
+    pub command: String,
+    /// Additional command-line arguments. `${file}` expands to the document
+    /// path relative to the workspace.
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Environment variables to inject when spawning the linter.
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Optional working directory. Defaults to the workspace.
+    #[serde(default)]
+    pub cwd: Option<String>,
+    /// Pipe the document content to stdin, e.g. to lint unsaved buffers.
+    #[serde(default)]
+    pub stdin: bool,
+    /// Stream the problem matcher reads.
+    #[serde(default)]
+    pub output: LinterOutput,
+    pub problem_matcher: ProblemMatcher,
+    /// Severity used when the matcher captures none or an unknown one.
+    #[serde(default = "default_lint_severity")]
+    pub default_severity: DiagnosticSeverity,
+    /// Seconds to wait before the linter is killed.
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    /// Optional absolute path inside the guest rootfs (proot) to mount the plugin directory to.
+    #[serde(default)]
+    pub plugin_mount_path: Option<String>,
+    /// Optional absolute path inside the guest rootfs (proot) to mount the workspace/project to.
+    #[serde(default)]
+    pub workspace_mount_path: Option<String>,
+}
+
+fn default_lint_severity() -> DiagnosticSeverity {
+    DiagnosticSeverity::Warning
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinterOutput {
+    #[default]
+    Stdout,
+    Stderr,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiagnosticSeverity {
+    Error,
+    Warning,
+    Info,
+    Hint,
+}
+
+/// How linter output is turned into diagnostics. Lines and columns are
+/// expected to be 1-based, as printed by most tools.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum ProblemMatcher {
+    /// Applied to each output line; fields name capture group indices.
+    #[serde(rename_all = "camelCase")]
+    Regex {
+        pattern: String,
+        #[serde(default)]
+        file: Option<usize>,
+        line: usize,
+        #[serde(default)]
+        column: Option<usize>,
+        #[serde(default)]
+        end_line: Option<usize>,
+        #[serde(default)]
+        end_column: Option<usize>,
+        #[serde(default)]
+        severity: Option<usize>,
+        message: usize,
+        #[serde(default)]
+        code: Option<usize>,
+    },
+    /// Parses the whole output as JSON; fields are JSON pointers relative to
+    /// each problem.
+    #[serde(rename_all = "camelCase")]
+    Json {
+        /// Pointer to the array of problems, or of files when `messages` is set.
+        #[serde(default)]
+        items: String,
+        /// Pointer from a file entry to its problems, for tools such as eslint
+        /// that group output by file.
+        #[serde(default)]
+        messages: Option<String>,
+        /// Resolved against the file entry when `messages` is set.
+        #[serde(default)]
+        file: Option<String>,
+        line: String,
+        #[serde(default)]
+        column: Option<String>,
+        #[serde(default)]
+        end_line: Option<String>,
+        #[serde(default)]
+        end_column: Option<String>,
+        #[serde(default)]
+        severity: Option<String>,
+        message: String,
+        #[serde(default)]
+        code: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetsPluginManifest {
+    pub snippets: Vec<SnippetFile>,
+}
+
+/// A VSCode-style snippet file shipped by the plugin.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetFile {
+    /// Languages the snippets apply to. Empty means every language, in which
+    /// case a snippet's own `scope` may still restrict it.
+    #[serde(default)]
+    pub language_ids: Vec<String>,
+    /// JSON file relative to the plugin root.
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GrammarPluginManifest {
+    pub grammars: Vec<GrammarContribution>,
+}
+
+/// A tree-sitter grammar compiled to WebAssembly, loaded by the editor with
+/// web-tree-sitter. Paths are relative to the plugin root.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GrammarContribution {
+    pub language_id: String,
+    /// File extensions (without the dot) the language is detected by.
+    #[serde(default)]
+    pub file_extensions: Vec<String>,
+    /// The `tree-sitter-<lang>.wasm` file.
+    pub wasm: String,
+    #[serde(default)]
+    pub queries: GrammarQueries,
+}
+
+/// Tree-sitter query files used for highlighting and the symbol outline.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GrammarQueries {
+    #[serde(default)]
+    pub highlights: Option<String>,
+    #[serde(default)]
+    pub locals: Option<String>,
+    #[serde(default)]
+    pub injections: Option<String>,
+    /// Definition captures (`@definition.function` etc.) for the outline.
+    #[serde(default)]
+    pub tags: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThemePluginManifest {
+    #[serde(default)]
+    pub themes: Vec<ThemeContribution>,
+    #[serde(default)]
+    pub icon_themes: Vec<IconThemeContribution>,
+}
+
+/// An editor color theme. Paths are relative to the plugin root.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThemeContribution {
+    pub id: String,
+    pub label: String,
+    #[serde(default)]
+    pub appearance: ThemeAppearance,
+    /// JSON file with the theme colors.
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemeAppearance {
+    Light,
+    #[default]
+    Dark,
+}
+
+/// A file icon set: a JSON file mapping file names and extensions to icons
+/// stored next to it.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IconThemeContribution {
+    pub id: String,
+    pub label: String,
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunnerPluginManifest {
+    pub runners: Vec<RunnerContribution>,
+}
+
+/// How to run a file, matched by language id or file glob.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunnerContribution {
+    #[serde(default)]
+    pub language_ids: Vec<String>,
+    #[serde(default)]
+    pub file_patterns: Vec<String>,
+    /// Shell command line typed into the terminal. `${file}`, `${fileDir}`,
+    /// `${fileBasename}`, `${fileBasenameNoExtension}` and `${workspace}` are
+    /// substituted with shell-quoted values.
+    pub command: String,
+    #[serde(default)]
+    pub cwd: RunnerCwd,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RunnerCwd {
+    /// Directory containing the file.
+    #[default]
+    File,
+    Workspace,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebExtensionManifest {
+    /// HTML entry point relative to the plugin root, served via the plugin protocol.
+    pub entry: String,
+    /// Broker capabilities the extension is allowed to use.
+    #[serde(default)]
+    pub permissions: Vec<WebExtensionPermission>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WebExtensionPermission {
+    /// Read files inside the current workspace.
+    FsRead,
+    /// Run executables shipped inside the plugin directory.
+    RunTool,
+    /// Show toast messages in the host UI.
+    ShowToast,
 }
@@ -0,0 +1,324 @@
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::path::BaseDirectory;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::Mutex;
+
+use super::api::{install_plugin_package, ImportPluginError, PluginSummary};
+use super::PluginHost;
+
+const MARKETPLACE_SETTINGS_FILENAME: &str = "plugin-marketplace.json";
+/// Index published with each release of the official plugin repository.
+const DEFAULT_INDEX_URL: &str =
+    "https://github.com/shadow3aaa/TruidIDE-plugins/releases/latest/download/index.json";
+const EVENT_INSTALL_PROGRESS: &str = "truidide://plugins/install-progress";
+const INDEX_CACHE_TTL: Duration = Duration::from_secs(300);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(600);
+
+static INDEX_CACHE: OnceCell<Mutex<Option<CachedIndex>>> = OnceCell::new();
+
+struct CachedIndex {
+    url: String,
+    fetched_at: Instant,
+    index: MarketplaceIndex,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MarketplaceSettings {
+    /// Index location; the official index is used when unset.
+    #[serde(default)]
+    pub index_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MarketplaceIndex {
+    #[serde(default)]
+    plugins: Vec<MarketplaceListing>,
+}
+
+/// A plugin published in the marketplace index.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MarketplaceListing {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub author: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Plugin kind as written in the manifest, e.g. `lsp`.
+    #[serde(default)]
+    pub kind: Option<String>,
+    #[serde(default)]
+    pub api_version: Option<u32>,
+    #[serde(default)]
+    pub homepage: Option<String>,
+    /// Zip package of the plugin.
+    pub download_url: String,
+    /// Hex digest of the package, checked after download when present.
+    #[serde(default)]
+    pub sha256: Option<String>,
+    /// Version of the locally installed copy, filled in by the host.
+    #[serde(default, skip_deserializing)]
+    pub installed_version: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallPluginFromUrlArgs {
+    pub url: String,
+    #[serde(default)]
+    pub sha256: Option<String>,
+    /// The user agreed to the permissions the plugin requests.
+    #[serde(default)]
+    pub grant_permissions: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallProgress {
+    pub url: String,
+    pub downloaded: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub percentage: Option<u8>,
+}
+
+fn settings_path(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .resolve(MARKETPLACE_SETTINGS_FILENAME, BaseDirectory::AppData)
+        .map_err(|e| e.to_string())
+}
+
+pub fn load_marketplace_settings(app: &AppHandle) -> Result<MarketplaceSettings, String> {
+    let path = settings_path(app)?;
+    if !path.exists() {
+        return Ok(MarketplaceSettings::default());
+    }
+    let data = fs::read_to_string(&path).map_err(|e| format!("读取插件市场设置失败: {e}"))?;
+    serde_json::from_str(&data).map_err(|e| format!("解析插件市场设置失败: {e}"))
+}
+
+pub fn save_marketplace_settings(
+    app: &AppHandle,
+    settings: &MarketplaceSettings,
+) -> Result<(), String> {
+    let path = settings_path(app)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("创建配置目录失败: {e}"))?;
+    }
+    let data = serde_json::to_string_pretty(settings)
+        .map_err(|e| format!("序列化插件市场设置失败: {e}"))?;
+    fs::write(&path, data).map_err(|e| format!("保存插件市场设置失败: {e}"))
+}
+
+fn index_url(app: &AppHandle) -> Result<String, String> {
+    Ok(load_marketplace_settings(app)?
+        .index_url
+        .map(|url| url.trim().to_string())
+        .filter(|url| !url.is_empty())
+        .unwrap_or_else(|| DEFAULT_INDEX_URL.to_string()))
+}
+
+fn http_client() -> Result<reqwest::Client, String> {
+    reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| format!("创建网络客户端失败: {e}"))
+}
+
+fn check_download_url(url: &str) -> Result<(), String> {
+    if url.starts_with("https://") || url.starts_with("http://") {
+        Ok(())
+    } else {
+        Err(format!("不支持的下载地址: {url}"))
+    }
+}
+
+async fn fetch_index(app: &AppHandle, force: bool) -> Result<MarketplaceIndex, String> {
+    let url = index_url(app)?;
+    let mut cache = INDEX_CACHE.get_or_init(|| Mutex::new(None)).lock().await;
+    if let Some(cached) = cache.as_ref() {
+        if !force && cached.url == url && cached.fetched_at.elapsed() < INDEX_CACHE_TTL {
+            return Ok(cached.index.clone());
+        }
+    }
+
+    check_download_url(&url)?;
+    let response = http_client()?
+        .get(&url)
+        .send()
+        .await
+        .map_err(|e| format!("获取插件市场索引失败: {e}"))?;
+    if !response.status().is_success() {
+        return Err(format!("获取插件市场索引失败: HTTP {}", response.status()));
+    }
+    let body = response
+        .text()
+        .await
+        .map_err(|e| format!("获取插件市场索引失败: {e}"))?;
+    let index: MarketplaceIndex =
+        serde_json::from_str(&body).map_err(|e| format!("解析插件市场索引失败: {e}"))?;
+
+    *cache = Some(CachedIndex {
+        url,
+        fetched_at: Instant::now(),
+        index: index.clone(),
+    });
+    Ok(index)
+}
+
+fn listing_matches(listing: &MarketplaceListing, query: &str) -> bool {
+    let query = query.to_lowercase();
+    std::iter::once(&listing.id)
+        .chain(std::iter::once(&listing.name))
+        .chain(listing.description.as_ref())
+        .chain(listing.tags.iter())
+        .any(|field| field.to_lowercase().contains(&query))
+}
+
+fn percentage(downloaded: u64, total: Option<u64>) -> Option<u8> {
+    total
+        .filter(|total| *total > 0)
+        .map(|total| (downloaded.saturating_mul(100) / total).min(100) as u8)
+}
+
+/// Download `url` into a temporary file, reporting progress as `event` and
+/// checking the digest when one is given.
+pub(super) async fn download_package(
+    app: &AppHandle,
+    url: &str,
+    sha256: Option<&str>,
+    event: &str,
+) -> Result<tempfile::NamedTempFile, String> {
+    check_download_url(url)?;
+    let mut response = http_client()?
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| format!("下载插件失败: {e}"))?;
+    if !response.status().is_success() {
+        return Err(format!("下载插件失败: HTTP {}", response.status()));
+    }
+
+    let total = response.content_length();
+    let mut file = tempfile::Builder::new()
+        .suffix(".zip")
+        .tempfile()
+        .map_err(|e| format!("创建临时文件失败: {e}"))?;
+    let mut hasher = Sha256::new();
+    let mut downloaded = 0u64;
+    let mut last_report = Instant::now();
+
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("下载插件失败: {e}"))?
+    {
+        file.write_all(&chunk)
+            .map_err(|e| format!("写入临时文件失败: {e}"))?;
+        hasher.update(&chunk);
+        downloaded += chunk.len() as u64;
+
+        if last_report.elapsed() > Duration::from_millis(500) {
+            let _ = app.emit(
+                event,
+                InstallProgress {
+                    url: url.to_string(),
+                    downloaded,
+                    total,
+                    percentage: percentage(downloaded, total),
+                },
+            );
+            last_report = Instant::now();
+        }
+    }
+    file.flush().map_err(|e| format!("写入临时文件失败: {e}"))?;
+    let _ = app.emit(
+        event,
+        InstallProgress {
+            url: url.to_string(),
+            downloaded,
+            total,
+            percentage: Some(100),
+        },
+    );
+
+    if let Some(expected) = sha256.map(str::trim).filter(|hash| !hash.is_empty()) {
+        let actual = hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>();
+        if !actual.eq_ignore_ascii_case(expected) {
+            return Err("插件包校验失败，文件可能已损坏或被篡改".into());
+        }
+    }
+    Ok(file)
+}
+
+impl PluginHost {
+    /// Marketplace listings matching `query`, annotated with installed versions.
+    pub async fn search_marketplace(
+        &self,
+        query: Option<&str>,
+        refresh: bool,
+    ) -> Result<Vec<MarketplaceListing>, String> {
+        let index = fetch_index(self.app(), refresh).await?;
+        let query = query.map(str::trim).filter(|query| !query.is_empty());
+        let mut listings = Vec::new();
+        for mut listing in index.plugins {
+            if query.is_some_and(|query| !listing_matches(&listing, query)) {
+                continue;
+            }
+            listing.installed_version = self
+                .find_plugin(&listing.id)
+                .await
+                .map(|plugin| plugin.manifest.version);
+            listings.push(listing);
+        }
+        Ok(listings)
+    }
+
+    pub async fn plugin_listing(&self, plugin_id: &str) -> Result<MarketplaceListing, String> {
+        let mut listing = fetch_index(self.app(), false)
+            .await?
+            .plugins
+            .into_iter()
+            .find(|listing| listing.id == plugin_id)
+            .ok_or_else(|| format!("插件市场中没有 {plugin_id}"))?;
+        listing.installed_version = self
+            .find_plugin(plugin_id)
+            .await
+            .map(|plugin| plugin.manifest.version);
+        Ok(listing)
+    }
+
+    /// Download a plugin package and install it like a manually imported zip.
+    pub async fn install_plugin_from_url(
+        &self,
+        args: InstallPluginFromUrlArgs,
+    ) -> Result<PluginSummary, ImportPluginError> {
+        let url = args.url.trim();
+        let package = download_package(
+            self.app(),
+            url,
+            args.sha256.as_deref(),
+            EVENT_INSTALL_PROGRESS,
+        )
+        .await?;
+        install_plugin_package(self.app(), package.path(), args.grant_permissions).await
+    }
+}
@@ -1,10 +1,73 @@
 pub mod api;
+mod builtin;
+mod bus;
+mod dap_host;
+mod engines;
+mod formatter;
+mod grammar;
+mod health;
+mod linter;
+mod lsp_auto;
+mod lsp_defaults;
+mod lsp_download;
 mod lsp_host;
+mod lsp_idle;
+mod lsp_init;
+mod lsp_trace;
+mod lsp_transport;
+mod lsp_uri;
+mod lsp_watch;
 mod manifest;
+mod marketplace;
+mod permissions;
+mod project;
 mod registry;
+mod runner;
+mod settings;
+mod signing;
+mod snippets;
+mod validation;
+mod watcher;
+pub mod web_extension;
 
+pub use bus::{BusSink, MessageBus};
+pub use dap_host::{
+    DapSendPayload, DapSessionIdArgs, StartDapSessionArgs, StartDapSessionResponse,
+};
+pub use formatter::{FormatDocumentArgs, FormatDocumentResponse, FormatEdit};
+pub use grammar::GrammarInfo;
+pub use health::PluginHealth;
+pub use linter::{lint_on_save, LintDiagnostic, LintDiagnosticsPayload, LintDocumentArgs};
+pub use lsp_auto::{language_id_for_path, EnsureLspForFileArgs};
+pub use lsp_defaults::{LanguagePluginDefaults, SetLanguagePluginDefaultArgs};
 pub use lsp_host::{
-    LspSendPayload, LspSessionIdArgs, PluginHost, StartLspSessionArgs, StartLspSessionResponse,
+    CancelLspRequestArgs, LspSendPayload, LspSessionIdArgs, LspSessionStatus, PathMapping,
+    PluginHost, PluginSessionInfo, PluginSessionKind, StartLspSessionArgs, StartLspSessionResponse,
+    WorkspaceMount,
+};
+pub use lsp_idle::LspIdleSettings;
+pub use lsp_trace::{GetLspSessionLogArgs, LspTraceSettings};
+pub use manifest::{
+    DapPluginManifest, DiagnosticSeverity, FilesystemScope, FormatterPluginManifest,
+    GrammarContribution, GrammarPluginManifest, GrammarQueries, IconThemeContribution,
+    LinterOutput, LinterPluginManifest, LocalizedText, LspDownloadTarget, LspPluginManifest,
+    LspRestartPolicy, LspServerDownload, LspTransport, PluginConfiguration, PluginEngines,
+    PluginKind, PluginManifest, PluginPermissions, ProblemMatcher, RunnerContribution, RunnerCwd,
+    RunnerPluginManifest, SettingSchema, SettingType, SnippetFile, SnippetsPluginManifest,
+    ThemeAppearance, ThemeContribution, ThemePluginManifest, WebExtensionManifest,
+    WebExtensionPermission,
+};
+pub use marketplace::{
+    InstallPluginFromUrlArgs, InstallProgress, MarketplaceListing, MarketplaceSettings,
+};
+pub use project::ProjectPluginActivation;
+pub use registry::{
+    DiscoveredPlugin, PluginDirectoriesConfig, PluginLocation, PluginRegistry, PluginResolution,
+    PluginStatus,
 };
-pub use manifest::{LspPluginManifest, PluginKind, PluginManifest};
-pub use registry::{DiscoveredPlugin, PluginDirectoriesConfig, PluginLocation, PluginRegistry};
+pub use runner::{RunCurrentFileArgs, RunCurrentFileResponse};
+pub use settings::{PluginSettings, SetPluginSettingsArgs, SettingsValues};
+pub use signing::{PluginTrustSettings, TrustedPluginKey, UnsignedPluginPolicy};
+pub use snippets::Snippet;
+pub use validation::{ManifestDiagnostic, ManifestDiagnosticSeverity, ManifestValidation};
+pub use watcher::watch_user_plugins;
@@ -0,0 +1,75 @@
+use std::fs;
+use std::path::PathBuf;
+
+use tauri::path::BaseDirectory;
+use tauri::{AppHandle, Manager};
+use tokio::process::Command;
+
+use super::{DiscoveredPlugin, FilesystemScope};
+
+const PLUGIN_HOMES_DIR: &str = "plugin-homes";
+// Nothing listens on the discard port, so proxied connections fail fast.
+const BLOCKED_PROXY: &str = "http://127.0.0.1:9";
+pub(super) const PROXY_VARIABLES: [&str; 6] = [
+    "http_proxy",
+    "https_proxy",
+    "all_proxy",
+    "HTTP_PROXY",
+    "HTTPS_PROXY",
+    "ALL_PROXY",
+];
+
+/// Private home directory for a plugin confined to the workspace.
+fn plugin_home_dir(app: &AppHandle, plugin_id: &str) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .resolve(
+            format!("{PLUGIN_HOMES_DIR}/{plugin_id}"),
+            BaseDirectory::AppData,
+        )
+        .map_err(|e| e.to_string())?;
+    fs::create_dir_all(&dir).map_err(|e| format!("创建插件主目录失败: {e}"))?;
+    Ok(dir)
+}
+
+/// Extra proot arguments for `plugin`; they must precede the program.
+pub(super) fn proot_permission_args(
+    app: &AppHandle,
+    plugin: &DiscoveredPlugin,
+) -> Result<Vec<String>, String> {
+    let permissions = plugin.manifest.effective_permissions();
+    if permissions.filesystem == FilesystemScope::Full {
+        return Ok(Vec::new());
+    }
+    // Hides the terminal's `/root` (SSH keys, git credentials) from the plugin.
+    let home = plugin_home_dir(app, &plugin.manifest.id)?;
+    Ok(vec![format!("--bind={}:/root", home.to_string_lossy())])
+}
+
+/// Environment enforcing `plugin`'s permissions. Applied after the
+/// manifest's own `env`, so plugins cannot lift the restrictions.
+pub(super) fn apply_permission_env(
+    app: &AppHandle,
+    plugin: &DiscoveredPlugin,
+    command: &mut Command,
+    in_proot: bool,
+) -> Result<(), String> {
+    let permissions = plugin.manifest.effective_permissions();
+    if permissions.filesystem == FilesystemScope::Workspace {
+        if in_proot {
+            command.env("HOME", "/root");
+        } else {
+            // Not an isolation boundary on desktop, but keeps tools from
+            // picking up the user's dotfiles and credentials by default.
+            command.env("HOME", plugin_home_dir(app, &plugin.manifest.id)?);
+        }
+    }
+    if !permissions.network {
+        for variable in PROXY_VARIABLES {
+            command.env(variable, BLOCKED_PROXY);
+        }
+        command.env_remove("no_proxy").env_remove("NO_PROXY");
+        command.env("npm_config_offline", "true");
+    }
+    Ok(())
+}
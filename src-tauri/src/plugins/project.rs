@@ -0,0 +1,87 @@
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tauri::AppHandle;
+
+const PROJECT_FILE: &str = ".truidide/project.json";
+
+/// Which plugins may run in a project. The deny list wins; an empty allow
+/// list allows every plugin that is not denied.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectPluginActivation {
+    #[serde(default)]
+    pub allow: Vec<String>,
+    #[serde(default)]
+    pub deny: Vec<String>,
+}
+
+impl ProjectPluginActivation {
+    pub fn allows(&self, plugin_id: &str) -> bool {
+        !self.deny.iter().any(|id| id == plugin_id)
+            && (self.allow.is_empty() || self.allow.iter().any(|id| id == plugin_id))
+    }
+}
+
+// `.truidide/project.json`; keys other than `plugins` are kept as-is when
+// the file is rewritten.
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct ProjectFile {
+    #[serde(default)]
+    plugins: ProjectPluginActivation,
+    #[serde(flatten)]
+    other: Map<String, Value>,
+}
+
+fn load_project_file(app: &AppHandle, project_path: &str) -> Result<ProjectFile, String> {
+    let path = crate::tasks::project_host_dir(app, project_path)?.join(PROJECT_FILE);
+    if !path.exists() {
+        return Ok(ProjectFile::default());
+    }
+    let data = fs::read_to_string(&path).map_err(|e| format!("读取项目配置失败: {e}"))?;
+    serde_json::from_str(&data).map_err(|e| format!("解析项目配置失败: {e}"))
+}
+
+pub fn load_project_plugins(
+    app: &AppHandle,
+    project_path: &str,
+) -> Result<ProjectPluginActivation, String> {
+    load_project_file(app, project_path).map(|file| file.plugins)
+}
+
+pub fn save_project_plugins(
+    app: &AppHandle,
+    project_path: &str,
+    activation: ProjectPluginActivation,
+) -> Result<(), String> {
+    let mut file = load_project_file(app, project_path)?;
+    file.plugins = activation;
+
+    let path = crate::tasks::project_host_dir(app, project_path)?.join(PROJECT_FILE);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("创建项目配置目录失败: {e}"))?;
+    }
+    let data =
+        serde_json::to_string_pretty(&file).map_err(|e| format!("序列化项目配置失败: {e}"))?;
+    fs::write(&path, data).map_err(|e| format!("保存项目配置失败: {e}"))
+}
+
+/// Whether `plugin_id` may run in `project_path`. An unreadable project file
+/// is logged and treated as allowing everything.
+pub(super) fn plugin_allowed_in_project(
+    app: &AppHandle,
+    project_path: &str,
+    plugin_id: &str,
+) -> bool {
+    if project_path.trim().is_empty() {
+        return true;
+    }
+    match load_project_plugins(app, project_path) {
+        Ok(activation) => activation.allows(plugin_id),
+        Err(err) => {
+            eprintln!("[truidide::plugins] 读取项目插件配置失败: {}", err);
+            true
+        }
+    }
+}
@@ -1,16 +1,42 @@
-use crate::plugins::{LspPluginManifest, PluginKind, PluginManifest};
-use serde::Deserialize;
+use crate::plugins::manifest::parse_version_requirement;
+use crate::plugins::project::ProjectPluginActivation;
+use crate::plugins::{
+    DapPluginManifest, LspPluginManifest, PluginKind, PluginManifest, WebExtensionManifest,
+};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
-const MANIFEST_FILENAME: &str = "truid-plugin.json";
+pub(super) const MANIFEST_FILENAME: &str = "truid-plugin.json";
 
 #[derive(Debug, Clone)]
 pub struct DiscoveredPlugin {
     pub manifest: PluginManifest,
     pub root_dir: PathBuf,
     pub location: PluginLocation,
+    pub status: PluginStatus,
+    /// Deprecation notices collected while loading the manifest.
+    pub warnings: Vec<String>,
+    /// Outcome of reconciling user and bundled copies, when there was anything to reconcile.
+    pub resolution: Option<PluginResolution>,
+    /// Declared dependencies that are absent, disabled or of the wrong version.
+    pub missing_dependencies: Vec<String>,
+}
+
+impl DiscoveredPlugin {
+    pub fn is_usable(&self) -> bool {
+        self.manifest.enabled
+            && self.status == PluginStatus::Ready
+            && self.missing_dependencies.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PluginStatus {
+    Ready,
+    Incompatible { reason: String },
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -19,6 +45,48 @@ pub enum PluginLocation {
     BuiltIn,
 }
 
+/// How a plugin present both as a user copy and a bundled built-in was resolved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PluginResolution {
+    /// The user copy is newer than (or as new as) the bundled one and stays active.
+    UserOverride { built_in_version: String },
+    /// The bundled copy is newer than the stale user copy and takes over.
+    BuiltInNewer {
+        user_version: String,
+        user_root_dir: PathBuf,
+    },
+    /// The bundled copy changed version with an app update.
+    BuiltInChanged {
+        previous_version: String,
+        upgraded: bool,
+    },
+}
+
+/// Bundled plugin version persisted across launches to detect app updates.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BuiltInVersionRecord {
+    version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    previous_version: Option<String>,
+}
+
+fn record_built_in_version(
+    versions: &mut HashMap<String, BuiltInVersionRecord>,
+    plugin_id: &str,
+    version: &str,
+) {
+    let record = versions
+        .entry(plugin_id.to_string())
+        .or_insert_with(|| BuiltInVersionRecord {
+            version: version.to_string(),
+            previous_version: None,
+        });
+    if record.version != version {
+        record.previous_version = Some(std::mem::replace(&mut record.version, version.to_string()));
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PluginDirectoriesConfig {
@@ -26,6 +94,12 @@ pub struct PluginDirectoriesConfig {
     pub user: Vec<PathBuf>,
     #[serde(default)]
     pub built_in: Vec<PathBuf>,
+    /// File recording the built-in versions seen on the previous refresh.
+    #[serde(default)]
+    pub state_file: Option<PathBuf>,
+    /// File holding enable/disable choices that override the manifests.
+    #[serde(default)]
+    pub enabled_overrides_file: Option<PathBuf>,
 }
 
 impl Default for PluginDirectoriesConfig {
@@ -33,6 +107,8 @@ impl Default for PluginDirectoriesConfig {
         Self {
             user: vec![],
             built_in: vec![],
+            state_file: None,
+            enabled_overrides_file: None,
         }
     }
 }
@@ -41,6 +117,8 @@ impl Default for PluginDirectoriesConfig {
 pub struct PluginRegistry {
     user_dirs: Vec<PathBuf>,
     built_in_dirs: Vec<PathBuf>,
+    state_file: Option<PathBuf>,
+    enabled_overrides_file: Option<PathBuf>,
     plugins: HashMap<String, DiscoveredPlugin>,
 }
 
@@ -49,82 +127,251 @@ impl PluginRegistry {
         Self {
             user_dirs: config.user,
             built_in_dirs: config.built_in,
+            state_file: config.state_file,
+            enabled_overrides_file: config.enabled_overrides_file,
             plugins: HashMap::new(),
         }
     }
 
     pub fn refresh(&mut self) -> Result<(), String> {
-        let mut seen = HashMap::<String, DiscoveredPlugin>::new();
-
-        for (location, dirs) in [
-            (PluginLocation::User, self.user_dirs.clone()),
-            (PluginLocation::BuiltIn, self.built_in_dirs.clone()),
-        ] {
-            for dir in dirs {
-                self.scan_directory(location, &dir, &mut seen)?;
+        let mut user = HashMap::<String, DiscoveredPlugin>::new();
+        let mut built_in = HashMap::<String, DiscoveredPlugin>::new();
+
+        for dir in &self.user_dirs {
+            scan_directory(PluginLocation::User, dir, &mut user)?;
+        }
+        for dir in &self.built_in_dirs {
+            scan_directory(PluginLocation::BuiltIn, dir, &mut built_in)?;
+        }
+
+        let mut versions = self.load_built_in_versions();
+        versions.retain(|id, _| built_in.contains_key(id));
+        for (id, plugin) in &built_in {
+            record_built_in_version(&mut versions, id, &plugin.manifest.version);
+        }
+        self.save_built_in_versions(&versions);
+
+        let overrides = self.load_enabled_overrides();
+        let mut seen = HashMap::new();
+        let mut ids = user
+            .keys()
+            .chain(built_in.keys())
+            .cloned()
+            .collect::<Vec<_>>();
+        ids.sort();
+        ids.dedup();
+        for id in ids {
+            let previous_version = versions
+                .get(&id)
+                .and_then(|record| record.previous_version.as_deref());
+            if let Some(mut plugin) =
+                reconcile(user.remove(&id), built_in.remove(&id), previous_version)
+            {
+                apply_enabled_override(&mut plugin, &overrides);
+                seen.insert(id, plugin);
             }
         }
 
         self.plugins = seen;
+        self.resolve_dependencies();
         Ok(())
     }
 
-    fn scan_directory(
-        &self,
-        location: PluginLocation,
-        dir: &Path,
-        seen: &mut HashMap<String, DiscoveredPlugin>,
-    ) -> Result<(), String> {
-        if !dir.exists() {
-            return Ok(());
-        }
+    /// Re-read the manifest of a single plugin and patch the index.
+    ///
+    /// Returns the refreshed entry, or `None` if the plugin no longer exists.
+    pub fn refresh_plugin(&mut self, plugin_id: &str) -> Result<Option<DiscoveredPlugin>, String> {
+        let user = find_in_directories(PluginLocation::User, &self.user_dirs, plugin_id)?;
+        let built_in =
+            find_in_directories(PluginLocation::BuiltIn, &self.built_in_dirs, plugin_id)?;
 
-        for entry in fs::read_dir(dir).map_err(|e| format!("读取插件目录失败: {e}"))? {
-            let entry = entry.map_err(|e| format!("读取插件目录项失败: {e}"))?;
-            let path = entry.path();
-            if !path.is_dir() {
-                continue;
+        let mut versions = self.load_built_in_versions();
+        match &built_in {
+            Some(plugin) => {
+                record_built_in_version(&mut versions, plugin_id, &plugin.manifest.version)
             }
+            None => {
+                versions.remove(plugin_id);
+            }
+        }
+        self.save_built_in_versions(&versions);
+
+        let previous_version = versions
+            .get(plugin_id)
+            .and_then(|record| record.previous_version.as_deref());
+        let mut resolved = reconcile(user, built_in, previous_version);
+        if let Some(plugin) = &mut resolved {
+            apply_enabled_override(plugin, &self.load_enabled_overrides());
+        }
 
-            let manifest_path = path.join(MANIFEST_FILENAME);
-            if !manifest_path.exists() {
-                continue;
+        match &resolved {
+            Some(plugin) => {
+                self.plugins.insert(plugin_id.to_string(), plugin.clone());
             }
+            None => {
+                self.plugins.remove(plugin_id);
+            }
+        }
+        self.resolve_dependencies();
+        Ok(self.plugins.get(plugin_id).cloned())
+    }
 
-            let manifest_str = fs::read_to_string(&manifest_path)
-                .map_err(|e| format!("读取插件清单失败 ({}): {e}", manifest_path.display()))?;
-            let manifest: PluginManifest = serde_json::from_str(&manifest_str)
-                .map_err(|e| format!("解析插件清单失败 ({}): {e}", manifest_path.display()))?;
+    /// Persist an enable/disable choice for `plugin_id` and apply it to the index.
+    ///
+    /// The plugin's own manifest is left untouched, so the choice survives
+    /// plugin updates and also works for read-only built-in plugins.
+    pub fn set_enabled(
+        &mut self,
+        plugin_id: &str,
+        enabled: bool,
+    ) -> Result<DiscoveredPlugin, String> {
+        let plugin = self
+            .plugins
+            .get_mut(plugin_id)
+            .ok_or_else(|| format!("未找到插件 {plugin_id}"))?;
+        let Some(path) = &self.enabled_overrides_file else {
+            return Err("未配置插件启用状态文件".into());
+        };
 
-            if let Some(existing) = seen.get(&manifest.id) {
-                // Prefer user-installed plugins over built-in ones.
-                // Here we simply skip duplicates, but this can be extended later.
-                if existing.location == PluginLocation::User {
-                    continue;
-                }
+        let mut overrides = read_enabled_overrides(path);
+        overrides.insert(plugin_id.to_string(), enabled);
+        let data = serde_json::to_string_pretty(&overrides)
+            .map_err(|e| format!("序列化插件启用状态失败: {e}"))?;
+        fs::write(path, data).map_err(|e| format!("保存插件启用状态失败: {e}"))?;
+
+        plugin.manifest.enabled = enabled;
+        self.resolve_dependencies();
+        Ok(self.plugins[plugin_id].clone())
+    }
+
+    /// Ids of plugins that declare a dependency on `plugin_id`.
+    pub fn dependents_of(&self, plugin_id: &str) -> Vec<String> {
+        let mut dependents = self
+            .plugins
+            .values()
+            .filter(|plugin| plugin.manifest.dependencies.contains_key(plugin_id))
+            .map(|plugin| plugin.manifest.id.clone())
+            .collect::<Vec<_>>();
+        dependents.sort();
+        dependents
+    }
+
+    // Recompute `missing_dependencies` for every plugin; a change to one
+    // plugin can affect any plugin that depends on it.
+    fn resolve_dependencies(&mut self) {
+        let missing = self
+            .plugins
+            .values()
+            .map(|plugin| {
+                let missing = plugin
+                    .manifest
+                    .dependencies
+                    .iter()
+                    .filter_map(|(id, requirement)| self.check_dependency(id, requirement).err())
+                    .collect::<Vec<_>>();
+                (plugin.manifest.id.clone(), missing)
+            })
+            .collect::<Vec<_>>();
+        for (id, missing) in missing {
+            if let Some(plugin) = self.plugins.get_mut(&id) {
+                plugin.missing_dependencies = missing;
             }
+        }
+    }
 
-            seen.insert(
-                manifest.id.clone(),
-                DiscoveredPlugin {
-                    manifest,
-                    root_dir: path,
-                    location,
-                },
-            );
+    fn check_dependency(&self, id: &str, requirement: &str) -> Result<(), String> {
+        let dependency = self
+            .plugins
+            .get(id)
+            .ok_or_else(|| format!("缺少依赖插件 {id}"))?;
+        if !dependency.manifest.enabled {
+            return Err(format!("依赖插件 {id} 已被禁用"));
+        }
+        if dependency.status != PluginStatus::Ready {
+            return Err(format!("依赖插件 {id} 不可用"));
         }
 
-        Ok(())
+        let requirement = requirement.trim();
+        if requirement.is_empty() || requirement == "*" {
+            return Ok(());
+        }
+        let satisfied = parse_version_requirement(requirement)
+            .ok()
+            .zip(semver::Version::parse(&dependency.manifest.version).ok())
+            .is_some_and(|(requirement, version)| requirement.matches(&version));
+        if satisfied {
+            Ok(())
+        } else {
+            Err(format!(
+                "依赖插件 {id} 需要版本 {requirement}，已安装 {}",
+                dependency.manifest.version
+            ))
+        }
     }
 
-    pub fn plugin_for_language(&self, language_id: &str) -> Option<&DiscoveredPlugin> {
-        self.plugins
+    fn load_enabled_overrides(&self) -> HashMap<String, bool> {
+        self.enabled_overrides_file
+            .as_deref()
+            .map(read_enabled_overrides)
+            .unwrap_or_default()
+    }
+
+    fn load_built_in_versions(&self) -> HashMap<String, BuiltInVersionRecord> {
+        self.state_file
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default()
+    }
+
+    fn save_built_in_versions(&self, versions: &HashMap<String, BuiltInVersionRecord>) {
+        let Some(path) = &self.state_file else {
+            return;
+        };
+        let result = serde_json::to_string_pretty(versions)
+            .map_err(|e| e.to_string())
+            .and_then(|data| fs::write(path, data).map_err(|e| e.to_string()));
+        if let Err(err) = result {
+            eprintln!("[truidide::plugins] 保存内置插件版本记录失败: {}", err);
+        }
+    }
+
+    /// Usable LSP plugins for `language_id` by descending priority, skipping
+    /// plugins the project's activation lists rule out. Ties are ordered by
+    /// id so the first candidate does not depend on map order.
+    pub fn plugins_for_language(
+        &self,
+        language_id: &str,
+        project: Option<&ProjectPluginActivation>,
+    ) -> Vec<&DiscoveredPlugin> {
+        let mut candidates = self
+            .plugins
             .values()
-            .find(|plugin| match &plugin.manifest.kind {
-                PluginKind::Lsp(manifest) => {
-                    manifest.language_ids.iter().any(|id| id == language_id)
+            .filter(|plugin| plugin.is_usable())
+            .filter(|plugin| project.is_none_or(|project| project.allows(&plugin.manifest.id)))
+            .filter_map(|plugin| match &plugin.manifest.kind {
+                PluginKind::Lsp(manifest)
+                    if manifest.language_ids.iter().any(|id| id == language_id) =>
+                {
+                    Some((manifest.priority, plugin))
                 }
+                PluginKind::Lsp(_)
+                | PluginKind::WebExtension(_)
+                | PluginKind::Dap(_)
+                | PluginKind::Formatter(_)
+                | PluginKind::Linter(_)
+                | PluginKind::Snippets(_)
+                | PluginKind::Grammar(_)
+                | PluginKind::Theme(_)
+                | PluginKind::Runner(_) => None,
             })
+            .collect::<Vec<_>>();
+        candidates.sort_by(|(a_priority, a), (b_priority, b)| {
+            b_priority
+                .cmp(a_priority)
+                .then_with(|| a.manifest.id.cmp(&b.manifest.id))
+        });
+        candidates.into_iter().map(|(_, plugin)| plugin).collect()
     }
 
     pub fn all_plugins(&self) -> impl Iterator<Item = (&String, &DiscoveredPlugin)> {
@@ -139,6 +386,187 @@ impl PluginRegistry {
             .get(plugin_id)
             .and_then(|plugin| match &plugin.manifest.kind {
                 PluginKind::Lsp(manifest) => Some((plugin, manifest)),
+                _ => None,
             })
     }
+
+    pub fn get_dap_manifest(
+        &self,
+        plugin_id: &str,
+    ) -> Option<(&DiscoveredPlugin, &DapPluginManifest)> {
+        self.plugins
+            .get(plugin_id)
+            .and_then(|plugin| match &plugin.manifest.kind {
+                PluginKind::Dap(manifest) => Some((plugin, manifest)),
+                _ => None,
+            })
+    }
+
+    pub fn get_web_extension_manifest(
+        &self,
+        plugin_id: &str,
+    ) -> Option<(&DiscoveredPlugin, &WebExtensionManifest)> {
+        self.plugins
+            .get(plugin_id)
+            .and_then(|plugin| match &plugin.manifest.kind {
+                PluginKind::WebExtension(manifest) => Some((plugin, manifest)),
+                _ => None,
+            })
+    }
+}
+
+fn read_enabled_overrides(path: &Path) -> HashMap<String, bool> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+fn apply_enabled_override(plugin: &mut DiscoveredPlugin, overrides: &HashMap<String, bool>) {
+    if let Some(enabled) = overrides.get(&plugin.manifest.id) {
+        plugin.manifest.enabled = *enabled;
+    }
+}
+
+fn load_plugin(location: PluginLocation, path: &Path) -> Result<Option<DiscoveredPlugin>, String> {
+    let manifest_path = path.join(MANIFEST_FILENAME);
+    if !manifest_path.exists() {
+        return Ok(None);
+    }
+
+    let manifest_str = fs::read_to_string(&manifest_path)
+        .map_err(|e| format!("读取插件清单失败 ({}): {e}", manifest_path.display()))?;
+    let manifest: PluginManifest = serde_json::from_str(&manifest_str)
+        .map_err(|e| format!("解析插件清单失败 ({}): {e}", manifest_path.display()))?;
+
+    let status = match manifest
+        .check_api_version()
+        .and_then(|()| manifest.check_engines())
+    {
+        Ok(()) => PluginStatus::Ready,
+        Err(reason) => {
+            eprintln!(
+                "[truidide::plugins] 插件 {} 与宿主不兼容: {}",
+                manifest.id, reason
+            );
+            PluginStatus::Incompatible { reason }
+        }
+    };
+    let warnings = manifest.deprecation_warnings();
+    for warning in &warnings {
+        eprintln!(
+            "[truidide::plugins] 插件 {} 弃用警告: {}",
+            manifest.id, warning
+        );
+    }
+
+    Ok(Some(DiscoveredPlugin {
+        manifest,
+        root_dir: path.to_path_buf(),
+        location,
+        status,
+        warnings,
+        resolution: None,
+        missing_dependencies: Vec::new(),
+    }))
+}
+
+fn scan_directory(
+    location: PluginLocation,
+    dir: &Path,
+    seen: &mut HashMap<String, DiscoveredPlugin>,
+) -> Result<(), String> {
+    if !dir.exists() {
+        return Ok(());
+    }
+
+    for entry in fs::read_dir(dir).map_err(|e| format!("读取插件目录失败: {e}"))? {
+        let entry = entry.map_err(|e| format!("读取插件目录项失败: {e}"))?;
+        let path = entry.path();
+        if !path.is_dir() {
+            continue;
+        }
+
+        let Some(plugin) = load_plugin(location, &path)? else {
+            continue;
+        };
+
+        // Within one location the first directory wins.
+        seen.entry(plugin.manifest.id.clone()).or_insert(plugin);
+    }
+
+    Ok(())
+}
+
+fn find_in_directories(
+    location: PluginLocation,
+    dirs: &[PathBuf],
+    plugin_id: &str,
+) -> Result<Option<DiscoveredPlugin>, String> {
+    for dir in dirs {
+        let path = dir.join(plugin_id);
+        if !path.is_dir() {
+            continue;
+        }
+        if let Some(plugin) = load_plugin(location, &path)? {
+            if plugin.manifest.id == plugin_id {
+                return Ok(Some(plugin));
+            }
+        }
+    }
+
+    // Directory names usually match the id, but fall back to a full scan.
+    let mut seen = HashMap::new();
+    for dir in dirs {
+        scan_directory(location, dir, &mut seen)?;
+    }
+    Ok(seen.remove(plugin_id))
+}
+
+fn compare_versions(left: &str, right: &str) -> Option<Ordering> {
+    let left = semver::Version::parse(left).ok()?;
+    let right = semver::Version::parse(right).ok()?;
+    Some(left.cmp(&right))
+}
+
+/// Pick the active copy of a plugin and record how the choice was made.
+fn reconcile(
+    user: Option<DiscoveredPlugin>,
+    built_in: Option<DiscoveredPlugin>,
+    previous_built_in_version: Option<&str>,
+) -> Option<DiscoveredPlugin> {
+    match (user, built_in) {
+        (None, None) => None,
+        (Some(user), None) => Some(user),
+        (None, Some(mut built_in)) => {
+            built_in.resolution = previous_built_in_version
+                .filter(|previous| *previous != built_in.manifest.version)
+                .map(|previous| PluginResolution::BuiltInChanged {
+                    previous_version: previous.to_string(),
+                    upgraded: compare_versions(&built_in.manifest.version, previous)
+                        != Some(Ordering::Less),
+                });
+            Some(built_in)
+        }
+        (Some(mut user), Some(mut built_in)) => {
+            if compare_versions(&built_in.manifest.version, &user.manifest.version)
+                == Some(Ordering::Greater)
+            {
+                eprintln!(
+                    "[truidide::plugins] 内置插件 {} ({}) 比用户副本 ({}) 更新，使用内置版本",
+                    built_in.manifest.id, built_in.manifest.version, user.manifest.version
+                );
+                built_in.resolution = Some(PluginResolution::BuiltInNewer {
+                    user_version: user.manifest.version,
+                    user_root_dir: user.root_dir,
+                });
+                Some(built_in)
+            } else {
+                user.resolution = Some(PluginResolution::UserOverride {
+                    built_in_version: built_in.manifest.version,
+                });
+                Some(user)
+            }
+        }
+    }
 }
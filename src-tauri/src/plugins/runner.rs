@@ -0,0 +1,159 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::{PluginHost, PluginKind, RunnerContribution, RunnerCwd};
+use crate::terminal::StartTerminalSessionArgs;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunCurrentFileArgs {
+    /// File to run, as shown in the explorer (a guest path on Android).
+    pub path: String,
+    #[serde(default)]
+    pub language_id: Option<String>,
+    #[serde(default)]
+    pub workspace_path: Option<String>,
+    /// Use this runner plugin instead of the first one matching the file.
+    #[serde(default)]
+    pub plugin_id: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunCurrentFileResponse {
+    /// Terminal session the command was typed into.
+    pub session_id: String,
+    pub plugin_id: String,
+    pub command_line: String,
+}
+
+fn runner_matches(runner: &RunnerContribution, path: &str, language_id: Option<&str>) -> bool {
+    if language_id.is_some_and(|id| runner.language_ids.iter().any(|known| known == id)) {
+        return true;
+    }
+    let file_name = Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    runner.file_patterns.iter().any(|pattern| {
+        glob::Pattern::new(pattern)
+            .is_ok_and(|pattern| pattern.matches(path) || pattern.matches(&file_name))
+    })
+}
+
+#[cfg(not(windows))]
+fn shell_quote(value: &str) -> String {
+    if !value.is_empty()
+        && value
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || "/._-+:@%".contains(ch))
+    {
+        return value.to_string();
+    }
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+#[cfg(windows)]
+fn shell_quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\\\""))
+}
+
+fn expand_command(template: &str, file: &Path, workspace: &Path) -> String {
+    let text = |path: &Path| path.to_string_lossy().into_owned();
+    let file_name = file.file_name().map(Path::new).unwrap_or(file);
+    let stem = file.file_stem().map(Path::new).unwrap_or(file_name);
+    let dir = file.parent().unwrap_or(workspace);
+    // Longer names first so `${file}` does not eat the others' prefix.
+    [
+        ("${fileBasenameNoExtension}", text(stem)),
+        ("${fileBasename}", text(file_name)),
+        ("${fileDir}", text(dir)),
+        ("${file}", text(file)),
+        ("${workspace}", text(workspace)),
+    ]
+    .iter()
+    .fold(template.to_string(), |command, (placeholder, value)| {
+        command.replace(placeholder, &shell_quote(value))
+    })
+}
+
+#[cfg(target_os = "android")]
+fn path_exists(app: &tauri::AppHandle, path: &str) -> bool {
+    crate::android::proot::resolve_guest_path(app, path).is_ok_and(|host| host.exists())
+}
+
+#[cfg(not(target_os = "android"))]
+fn path_exists(_app: &tauri::AppHandle, path: &str) -> bool {
+    Path::new(path).exists()
+}
+
+impl PluginHost {
+    /// Run a file with the matching runner plugin by typing its command into
+    /// a terminal session, so the program stays interactive. On Android the
+    /// terminal, and hence the program, lives inside proot.
+    pub async fn run_current_file(
+        &self,
+        args: RunCurrentFileArgs,
+    ) -> Result<RunCurrentFileResponse, String> {
+        let path = args.path.trim();
+        if path.is_empty() || !path_exists(self.app(), path) {
+            return Err(format!("文件不存在: {path}"));
+        }
+
+        let (plugin_id, runner) = self
+            .list_plugins()
+            .await
+            .into_iter()
+            .filter(|plugin| match &args.plugin_id {
+                Some(plugin_id) => plugin.manifest.id == *plugin_id,
+                None => plugin.is_usable(),
+            })
+            .find_map(|plugin| {
+                let PluginKind::Runner(manifest) = &plugin.manifest.kind else {
+                    return None;
+                };
+                let runner = manifest
+                    .runners
+                    .iter()
+                    .find(|runner| runner_matches(runner, path, args.language_id.as_deref()))?
+                    .clone();
+                Some((plugin.manifest.id.clone(), runner))
+            })
+            .ok_or_else(|| format!("没有可以运行 {path} 的插件"))?;
+
+        let file = Path::new(path);
+        let workspace = args
+            .workspace_path
+            .as_deref()
+            .map(str::trim)
+            .filter(|workspace| !workspace.is_empty())
+            .map(Path::new)
+            .or_else(|| file.parent())
+            .unwrap_or(file);
+        let cwd = match runner.cwd {
+            RunnerCwd::File => file.parent().unwrap_or(workspace),
+            RunnerCwd::Workspace => workspace,
+        };
+        let command_line = expand_command(&runner.command, file, workspace);
+
+        let session_id = crate::terminal::start_terminal_session(
+            self.app().clone(),
+            StartTerminalSessionArgs {
+                cwd: cwd.to_string_lossy().into_owned(),
+                force_new: false,
+                scrollback_bytes: None,
+                scrollback_lines: None,
+                clean_env: false,
+            },
+        )
+        .await?;
+        crate::terminal::run_line(&session_id, &command_line).await?;
+
+        Ok(RunCurrentFileResponse {
+            session_id,
+            plugin_id,
+            command_line,
+        })
+    }
+}
@@ -0,0 +1,269 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tauri::path::BaseDirectory;
+use tauri::{AppHandle, Manager};
+
+use super::{DiscoveredPlugin, PluginConfiguration, PluginHost, SettingSchema, SettingType};
+
+const USER_SETTINGS_FILENAME: &str = "plugin-settings.json";
+const PROJECT_SETTINGS_FILE: &str = ".truidide/plugin-settings.json";
+
+/// Saved values keyed by dotted setting name.
+pub type SettingsValues = Map<String, Value>;
+
+// Plugin id -> saved values. Both the AppData file and the per-project file
+// use this layout.
+type SettingsFile = BTreeMap<String, SettingsValues>;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetPluginSettingsArgs {
+    pub plugin_id: String,
+    /// Save for this project only instead of for every project.
+    #[serde(default)]
+    pub project_path: Option<String>,
+    /// Replaces the values stored at that scope; `null` entries are dropped.
+    pub values: SettingsValues,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginSettings {
+    pub plugin_id: String,
+    pub configuration: PluginConfiguration,
+    pub user: SettingsValues,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project: Option<SettingsValues>,
+    /// Schema defaults overlaid with the user values, then the project values.
+    pub effective: SettingsValues,
+}
+
+fn settings_file_path(app: &AppHandle, project_path: Option<&str>) -> Result<PathBuf, String> {
+    match project_path {
+        Some(project_path) => {
+            Ok(crate::tasks::project_host_dir(app, project_path)?.join(PROJECT_SETTINGS_FILE))
+        }
+        None => app
+            .path()
+            .resolve(USER_SETTINGS_FILENAME, BaseDirectory::AppData)
+            .map_err(|e| e.to_string()),
+    }
+}
+
+fn read_settings_file(path: &Path) -> Result<SettingsFile, String> {
+    if !path.exists() {
+        return Ok(SettingsFile::default());
+    }
+    let data = fs::read_to_string(path).map_err(|e| format!("读取插件设置失败: {e}"))?;
+    serde_json::from_str(&data).map_err(|e| format!("解析插件设置失败: {e}"))
+}
+
+fn write_settings_file(path: &Path, file: &SettingsFile) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("创建插件设置目录失败: {e}"))?;
+    }
+    let data =
+        serde_json::to_string_pretty(file).map_err(|e| format!("序列化插件设置失败: {e}"))?;
+    fs::write(path, data).map_err(|e| format!("保存插件设置失败: {e}"))
+}
+
+fn matches_type(value_type: SettingType, value: &Value) -> bool {
+    match value_type {
+        SettingType::String => value.is_string(),
+        SettingType::Number => value.is_number(),
+        SettingType::Integer => value.is_i64() || value.is_u64(),
+        SettingType::Boolean => value.is_boolean(),
+        SettingType::Array => value.is_array(),
+        SettingType::Object => value.is_object(),
+    }
+}
+
+fn validate_setting(key: &str, schema: &SettingSchema, value: &Value) -> Result<(), String> {
+    if !matches_type(schema.value_type, value) {
+        let expected = serde_json::to_value(schema.value_type).unwrap_or_default();
+        return Err(format!(
+            "设置项 {key} 的类型应为 {}",
+            expected.as_str().unwrap_or_default()
+        ));
+    }
+    if let Some(allowed) = &schema.allowed_values {
+        if !allowed.contains(value) {
+            return Err(format!("设置项 {key} 的取值不在允许范围内"));
+        }
+    }
+    Ok(())
+}
+
+fn validate_settings(
+    configuration: &PluginConfiguration,
+    values: &SettingsValues,
+) -> Result<(), String> {
+    // Plugins without a schema accept free-form values.
+    if configuration.properties.is_empty() {
+        return Ok(());
+    }
+    for (key, value) in values {
+        let schema = configuration
+            .properties
+            .get(key)
+            .ok_or_else(|| format!("未知的设置项 {key}"))?;
+        validate_setting(key, schema, value)?;
+    }
+    Ok(())
+}
+
+fn effective_settings(
+    configuration: &PluginConfiguration,
+    user: &SettingsValues,
+    project: Option<&SettingsValues>,
+) -> SettingsValues {
+    let mut effective = configuration
+        .properties
+        .iter()
+        .filter_map(|(key, schema)| Some((key.clone(), schema.default.clone()?)))
+        .collect::<SettingsValues>();
+    for values in std::iter::once(user).chain(project) {
+        effective.extend(
+            values
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone())),
+        );
+    }
+    effective
+}
+
+// Turn `{"check.command": "clippy"}` into `{"check": {"command": "clippy"}}`.
+fn nest_settings(values: &SettingsValues) -> Value {
+    let mut root = Value::Object(Map::new());
+    for (key, value) in values {
+        let mut nested = value.clone();
+        for segment in key.rsplit('.') {
+            let mut object = Map::new();
+            object.insert(segment.to_string(), nested);
+            nested = Value::Object(object);
+        }
+        merge_json(&mut root, nested);
+    }
+    root
+}
+
+pub(super) fn merge_json(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_json(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+fn load_plugin_settings(
+    app: &AppHandle,
+    plugin: &DiscoveredPlugin,
+    project_path: Option<&str>,
+) -> Result<PluginSettings, String> {
+    let plugin_id = &plugin.manifest.id;
+    let configuration = plugin.manifest.configuration.clone();
+    let user = read_settings_file(&settings_file_path(app, None)?)?
+        .remove(plugin_id)
+        .unwrap_or_default();
+    let project = project_path
+        .map(|project_path| {
+            read_settings_file(&settings_file_path(app, Some(project_path))?)
+                .map(|mut file| file.remove(plugin_id).unwrap_or_default())
+        })
+        .transpose()?;
+    let effective = effective_settings(&configuration, &user, project.as_ref());
+
+    Ok(PluginSettings {
+        plugin_id: plugin_id.clone(),
+        configuration,
+        user,
+        project,
+        effective,
+    })
+}
+
+impl PluginHost {
+    /// Settings schema and saved values of a plugin, optionally including the
+    /// values saved for `project_path`.
+    pub async fn plugin_settings(
+        &self,
+        plugin_id: &str,
+        project_path: Option<&str>,
+    ) -> Result<PluginSettings, String> {
+        let plugin = self
+            .find_plugin(plugin_id)
+            .await
+            .ok_or_else(|| format!("未找到插件 {plugin_id}"))?;
+        load_plugin_settings(self.app(), &plugin, project_path)
+    }
+
+    /// Validate `values` against the plugin's schema and store them. Running
+    /// language servers pick the change up on their next start.
+    pub async fn set_plugin_settings(
+        &self,
+        args: SetPluginSettingsArgs,
+    ) -> Result<PluginSettings, String> {
+        let plugin = self
+            .find_plugin(&args.plugin_id)
+            .await
+            .ok_or_else(|| format!("未找到插件 {}", args.plugin_id))?;
+        let values = args
+            .values
+            .into_iter()
+            .filter(|(_, value)| !value.is_null())
+            .collect::<SettingsValues>();
+        validate_settings(&plugin.manifest.configuration, &values)?;
+
+        let project_path = args.project_path.as_deref();
+        let path = settings_file_path(self.app(), project_path)?;
+        let mut file = read_settings_file(&path)?;
+        if values.is_empty() {
+            file.remove(&args.plugin_id);
+        } else {
+            file.insert(args.plugin_id.clone(), values);
+        }
+        write_settings_file(&path, &file)?;
+
+        load_plugin_settings(self.app(), &plugin, project_path)
+    }
+}
+
+/// Merge a plugin's effective settings into the initialization options sent
+/// to its language server. Saved settings win over `base`.
+pub(super) fn initialization_options_with_settings(
+    app: &AppHandle,
+    plugin: &DiscoveredPlugin,
+    workspace_path: &str,
+    base: Option<Value>,
+) -> Option<Value> {
+    let project_path = Some(workspace_path).filter(|path| !path.trim().is_empty());
+    let settings = match load_plugin_settings(app, plugin, project_path) {
+        Ok(settings) => settings.effective,
+        Err(err) => {
+            eprintln!(
+                "[truidide::plugins] 读取插件 {} 的设置失败: {}",
+                plugin.manifest.id, err
+            );
+            return base;
+        }
+    };
+    if settings.is_empty() {
+        return base;
+    }
+
+    let mut options = base.unwrap_or_else(|| Value::Object(Map::new()));
+    merge_json(&mut options, nest_settings(&settings));
+    Some(options)
+}
@@ -0,0 +1,185 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use base64::prelude::*;
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::path::BaseDirectory;
+use tauri::{AppHandle, Manager};
+
+/// Detached signature stored next to `truid-plugin.json`.
+pub const SIGNATURE_FILENAME: &str = "truid-plugin.sig";
+// Kept in app data so a plugin package cannot mark itself as trusted.
+const TRUST_FILENAME: &str = "plugin-trust.json";
+
+/// What to do with packages that are unsigned or signed by an unknown key.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UnsignedPluginPolicy {
+    Allow,
+    #[default]
+    Warn,
+    Refuse,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrustedPluginKey {
+    pub label: String,
+    /// Base64 encoded ed25519 public key.
+    pub public_key: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginTrustSettings {
+    #[serde(default)]
+    pub unsigned_policy: UnsignedPluginPolicy,
+    #[serde(default)]
+    pub trusted_keys: Vec<TrustedPluginKey>,
+}
+
+// Contents of `truid-plugin.sig`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SignatureFile {
+    /// Base64 encoded public key of the signer.
+    public_key: String,
+    /// Base64 encoded ed25519 signature over the package digest.
+    signature: String,
+}
+
+/// Outcome of checking a package that was not refused.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PackageTrust {
+    /// Signed by the trusted key with this label.
+    Signed { label: String },
+    /// Unsigned or signed by an unknown key, allowed by policy; carries the
+    /// warning to show.
+    Untrusted { warning: Option<String> },
+}
+
+fn trust_path(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .resolve(TRUST_FILENAME, BaseDirectory::AppData)
+        .map_err(|e| e.to_string())
+}
+
+pub fn load_trust_settings(app: &AppHandle) -> Result<PluginTrustSettings, String> {
+    let path = trust_path(app)?;
+    if !path.exists() {
+        return Ok(PluginTrustSettings::default());
+    }
+    let data = fs::read_to_string(&path).map_err(|e| format!("读取插件信任设置失败: {e}"))?;
+    serde_json::from_str(&data).map_err(|e| format!("解析插件信任设置失败: {e}"))
+}
+
+pub fn save_trust_settings(app: &AppHandle, settings: &PluginTrustSettings) -> Result<(), String> {
+    let path = trust_path(app)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("创建配置目录失败: {e}"))?;
+    }
+    let data = serde_json::to_string_pretty(settings)
+        .map_err(|e| format!("序列化插件信任设置失败: {e}"))?;
+    fs::write(&path, data).map_err(|e| format!("保存插件信任设置失败: {e}"))
+}
+
+pub fn parse_public_key(raw: &str) -> Result<VerifyingKey, String> {
+    let bytes = BASE64_STANDARD
+        .decode(raw.trim())
+        .map_err(|e| format!("公钥不是有效的 base64: {e}"))?;
+    let bytes: [u8; 32] = bytes
+        .try_into()
+        .map_err(|_| "公钥长度应为 32 字节".to_string())?;
+    VerifyingKey::from_bytes(&bytes).map_err(|e| format!("无效的 ed25519 公钥: {e}"))
+}
+
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<String>) -> Result<(), String> {
+    let entries = fs::read_dir(dir).map_err(|e| format!("读取插件包内容失败: {e}"))?;
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("读取插件包内容失败: {e}"))?;
+        let path = entry.path();
+        if path.is_dir() {
+            collect_files(root, &path, files)?;
+            continue;
+        }
+        let relative = path
+            .strip_prefix(root)
+            .map_err(|_| "插件包结构非法".to_string())?
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy().into_owned())
+            .collect::<Vec<_>>()
+            .join("/");
+        if relative != SIGNATURE_FILENAME {
+            files.push(relative);
+        }
+    }
+    Ok(())
+}
+
+/// Digest a signature covers: one `<path>\0<sha256 hex>\n` line per file,
+/// sorted by `/`-separated relative path, excluding the signature itself.
+pub fn package_digest(root: &Path) -> Result<Vec<u8>, String> {
+    let mut files = Vec::new();
+    collect_files(root, root, &mut files)?;
+    files.sort();
+
+    let mut digest = Sha256::new();
+    for relative in files {
+        let data = fs::read(root.join(&relative))
+            .map_err(|e| format!("读取插件文件 {relative} 失败: {e}"))?;
+        let file_hash = Sha256::digest(&data)
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>();
+        digest.update(format!("{relative}\0{file_hash}\n").as_bytes());
+    }
+    Ok(digest.finalize().to_vec())
+}
+
+fn untrusted(policy: UnsignedPluginPolicy, reason: &str) -> Result<PackageTrust, String> {
+    match policy {
+        UnsignedPluginPolicy::Allow => Ok(PackageTrust::Untrusted { warning: None }),
+        UnsignedPluginPolicy::Warn => Ok(PackageTrust::Untrusted {
+            warning: Some(format!("{reason}，请确认插件来源可信")),
+        }),
+        UnsignedPluginPolicy::Refuse => Err(format!("{reason}，已按设置拒绝安装")),
+    }
+}
+
+/// Check the package rooted at `root` against the trusted keys.
+///
+/// A signature that does not match the contents is always refused, whatever
+/// the policy, since it means the package was modified after signing.
+pub fn verify_plugin_package(app: &AppHandle, root: &Path) -> Result<PackageTrust, String> {
+    let settings = load_trust_settings(app)?;
+    let signature_path = root.join(SIGNATURE_FILENAME);
+    if !signature_path.is_file() {
+        return untrusted(settings.unsigned_policy, "插件包未签名");
+    }
+
+    let data = fs::read_to_string(&signature_path).map_err(|e| format!("读取插件签名失败: {e}"))?;
+    let signature_file: SignatureFile =
+        serde_json::from_str(&data).map_err(|e| format!("解析插件签名失败: {e}"))?;
+    let key = parse_public_key(&signature_file.public_key)?;
+    let signature_bytes: [u8; 64] = BASE64_STANDARD
+        .decode(signature_file.signature.trim())
+        .map_err(|e| format!("插件签名不是有效的 base64: {e}"))?
+        .try_into()
+        .map_err(|_| "插件签名长度应为 64 字节".to_string())?;
+    let signature = Signature::from_bytes(&signature_bytes);
+
+    key.verify(&package_digest(root)?, &signature)
+        .map_err(|_| "插件签名校验失败，插件包可能已被篡改".to_string())?;
+
+    let trusted = settings.trusted_keys.iter().find(|trusted| {
+        parse_public_key(&trusted.public_key).is_ok_and(|trusted_key| trusted_key == key)
+    });
+    match trusted {
+        Some(trusted) => Ok(PackageTrust::Signed {
+            label: trusted.label.clone(),
+        }),
+        None => untrusted(settings.unsigned_policy, "插件包的签名密钥不在信任列表中"),
+    }
+}
@@ -0,0 +1,162 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::web_extension::resolve_inside;
+use super::{DiscoveredPlugin, PluginHost, PluginKind, PluginStatus, SnippetFile};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Snippet {
+    pub plugin_id: String,
+    pub name: String,
+    pub prefixes: Vec<String>,
+    /// Snippet body in VSCode/TextMate syntax, lines joined with `\n`.
+    pub body: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+// Entry of a VSCode snippet file. `prefix` and `body` may be a string or an
+// array of strings.
+#[derive(Debug, Deserialize)]
+struct SnippetEntry {
+    #[serde(default)]
+    prefix: Value,
+    body: Value,
+    #[serde(default)]
+    description: Option<Value>,
+    #[serde(default)]
+    scope: Option<String>,
+}
+
+fn string_list(value: &Value) -> Vec<String> {
+    match value {
+        Value::String(text) => vec![text.clone()],
+        Value::Array(items) => items
+            .iter()
+            .filter_map(|item| item.as_str().map(str::to_string))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+// VSCode accepts comments in snippet files; drop them outside of strings so
+// serde_json can parse the rest.
+fn strip_json_comments(source: &str) -> String {
+    let mut out = String::with_capacity(source.len());
+    let mut chars = source.chars().peekable();
+    let mut in_string = false;
+    while let Some(ch) = chars.next() {
+        if in_string {
+            out.push(ch);
+            match ch {
+                '\\' => out.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match (ch, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                out.push(ch);
+            }
+            ('/', Some('/')) => {
+                for next in chars.by_ref() {
+                    if next == '\n' {
+                        out.push('\n');
+                        break;
+                    }
+                }
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                let mut previous = '\0';
+                for next in chars.by_ref() {
+                    if previous == '*' && next == '/' {
+                        break;
+                    }
+                    previous = next;
+                }
+            }
+            _ => out.push(ch),
+        }
+    }
+    out
+}
+
+fn file_applies(file: &SnippetFile, language_id: &str) -> bool {
+    file.language_ids.is_empty() || file.language_ids.iter().any(|id| id == language_id)
+}
+
+fn load_snippet_file(
+    plugin: &DiscoveredPlugin,
+    file: &SnippetFile,
+    language_id: &str,
+) -> Result<Vec<Snippet>, String> {
+    let path = resolve_inside(&plugin.root_dir, &file.path)?;
+    let source = std::fs::read_to_string(&path)
+        .map_err(|e| format!("读取片段文件 {} 失败: {e}", file.path))?;
+    let entries: serde_json::Map<String, Value> =
+        serde_json::from_str(&strip_json_comments(&source))
+            .map_err(|e| format!("解析片段文件 {} 失败: {e}", file.path))?;
+
+    let mut snippets = Vec::new();
+    for (name, value) in entries {
+        // Skip malformed entries instead of dropping the whole file.
+        let Ok(entry) = serde_json::from_value::<SnippetEntry>(value) else {
+            continue;
+        };
+        if let Some(scope) = &entry.scope {
+            if !scope.split(',').any(|id| id.trim() == language_id) {
+                continue;
+            }
+        }
+        let prefixes = string_list(&entry.prefix);
+        if prefixes.is_empty() {
+            continue;
+        }
+        snippets.push(Snippet {
+            plugin_id: plugin.manifest.id.clone(),
+            name,
+            prefixes,
+            body: string_list(&entry.body).join("\n"),
+            description: entry.description.map(|description| {
+                description
+                    .as_str()
+                    .map(str::to_string)
+                    .unwrap_or_else(|| string_list(&description).join("\n"))
+            }),
+        });
+    }
+    Ok(snippets)
+}
+
+impl PluginHost {
+    /// Snippets from every enabled snippet plugin that apply to `language_id`.
+    pub async fn list_snippets(&self, language_id: &str) -> Vec<Snippet> {
+        let mut snippets = Vec::new();
+        for plugin in self.list_plugins().await {
+            if !plugin.manifest.enabled || plugin.status != PluginStatus::Ready {
+                continue;
+            }
+            let PluginKind::Snippets(manifest) = &plugin.manifest.kind else {
+                continue;
+            };
+            for file in manifest
+                .snippets
+                .iter()
+                .filter(|file| file_applies(file, language_id))
+            {
+                match load_snippet_file(&plugin, file, language_id) {
+                    Ok(loaded) => snippets.extend(loaded),
+                    Err(err) => eprintln!(
+                        "[truidide::plugins] 插件 {} 的片段加载失败: {}",
+                        plugin.manifest.id, err
+                    ),
+                }
+            }
+        }
+        snippets
+    }
+}
@@ -0,0 +1,495 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use super::manifest::parse_version_requirement;
+use super::permissions::PROXY_VARIABLES;
+use super::registry::MANIFEST_FILENAME;
+use super::{FilesystemScope, PluginKind, PluginManifest};
+
+// Guest directories the host binds itself; mounting over them breaks proot.
+const RESERVED_MOUNT_PATHS: [&str; 5] = ["/", "/dev", "/proc", "/sys", "/root"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ManifestDiagnosticSeverity {
+    /// The plugin cannot be imported or will not start.
+    Error,
+    /// Accepted, but probably not what the author meant.
+    Warning,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestDiagnostic {
+    pub severity: ManifestDiagnosticSeverity,
+    /// Dotted path of the offending field, e.g. `kind.env.PATH`; absent for
+    /// problems with the manifest as a whole.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestValidation {
+    /// No errors; warnings do not block an import.
+    pub valid: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plugin_id: Option<String>,
+    /// The manifest file that was checked; absent for inline JSON.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub manifest_path: Option<String>,
+    pub diagnostics: Vec<ManifestDiagnostic>,
+}
+
+#[derive(Default)]
+struct Report {
+    diagnostics: Vec<ManifestDiagnostic>,
+}
+
+impl Report {
+    fn push(&mut self, severity: ManifestDiagnosticSeverity, field: &str, message: String) {
+        self.diagnostics.push(ManifestDiagnostic {
+            severity,
+            field: (!field.is_empty()).then(|| field.to_string()),
+            message,
+        });
+    }
+
+    fn error(&mut self, field: &str, message: impl Into<String>) {
+        self.push(ManifestDiagnosticSeverity::Error, field, message.into());
+    }
+
+    fn warning(&mut self, field: &str, message: impl Into<String>) {
+        self.push(ManifestDiagnosticSeverity::Warning, field, message.into());
+    }
+}
+
+/// Fields shared by the plugin kinds that spawn a process.
+struct ProcessFields<'a> {
+    command: &'a str,
+    env: &'a HashMap<String, String>,
+    cwd: Option<&'a str>,
+    plugin_mount_path: Option<&'a str>,
+    workspace_mount_path: Option<&'a str>,
+    // Downloaded on first start, so it may be missing from the package.
+    downloaded: bool,
+}
+
+/// Check a manifest before it is imported. `path_or_json` is the manifest's
+/// JSON text, the manifest file or the plugin directory containing it; file
+/// references are only checked when the plugin directory is known.
+pub fn validate_plugin_manifest(path_or_json: &str) -> Result<ManifestValidation, String> {
+    let trimmed = path_or_json.trim();
+    let (source, root, manifest_path) = if trimmed.starts_with('{') {
+        (trimmed.to_string(), None, None)
+    } else {
+        let path = PathBuf::from(trimmed);
+        let manifest_path = if path.is_dir() {
+            path.join(MANIFEST_FILENAME)
+        } else {
+            path
+        };
+        let source = fs::read_to_string(&manifest_path)
+            .map_err(|e| format!("读取插件清单失败 ({}): {e}", manifest_path.display()))?;
+        let root = manifest_path.parent().map(Path::to_path_buf);
+        (source, root, Some(manifest_path))
+    };
+
+    let mut report = Report::default();
+    let manifest = check_manifest(&source, root.as_deref(), &mut report);
+    Ok(ManifestValidation {
+        valid: !report
+            .diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity == ManifestDiagnosticSeverity::Error),
+        plugin_id: manifest.map(|manifest| manifest.id),
+        manifest_path: manifest_path.map(|path| path.to_string_lossy().to_string()),
+        diagnostics: report.diagnostics,
+    })
+}
+
+fn check_manifest(
+    source: &str,
+    root: Option<&Path>,
+    report: &mut Report,
+) -> Option<PluginManifest> {
+    let raw: Value = match serde_json::from_str(source) {
+        Ok(raw) => raw,
+        Err(e) => {
+            report.error("", format!("清单不是有效的 JSON: {e}"));
+            return None;
+        }
+    };
+    let manifest: PluginManifest = match serde_json::from_str(source) {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            report.error("", format!("清单不符合插件格式: {e}"));
+            return None;
+        }
+    };
+
+    check_unknown_fields(&raw, &manifest, report);
+    check_metadata(&manifest, report);
+    for warning in manifest.deprecation_warnings() {
+        report.warning("", warning);
+    }
+    if let Some(process) = process_fields(&manifest) {
+        check_process("kind", &process, &manifest, root, report);
+    }
+    if let Some(root) = root {
+        for (field, path) in asset_paths(&manifest) {
+            check_plugin_file(root, &field, path, report);
+        }
+    }
+    Some(manifest)
+}
+
+// Serialising the parsed manifest yields every field the host understands;
+// whatever else the author wrote is silently ignored at load time.
+fn check_unknown_fields(raw: &Value, manifest: &PluginManifest, report: &mut Report) {
+    // Unknown top-level keys end up in the flattened localized fields.
+    for key in manifest.localized_fields.keys() {
+        if !key.contains('@') {
+            report.warning(key, format!("未知字段 {key}，将被忽略"));
+        }
+    }
+    let sections = [
+        ("kind", serde_json::to_value(&manifest.kind)),
+        ("engines", serde_json::to_value(&manifest.engines)),
+        ("permissions", serde_json::to_value(&manifest.permissions)),
+        (
+            "configuration",
+            serde_json::to_value(&manifest.configuration),
+        ),
+        ("bus", serde_json::to_value(&manifest.bus)),
+    ];
+    for (field, known) in sections {
+        if let (Some(raw), Ok(known)) = (raw.get(field), known) {
+            compare_fields(raw, &known, field, report);
+        }
+    }
+}
+
+fn compare_fields(raw: &Value, known: &Value, path: &str, report: &mut Report) {
+    match (raw, known) {
+        (Value::Object(raw), Value::Object(known)) => {
+            for (key, value) in raw {
+                let field = format!("{path}.{key}");
+                match known.get(key) {
+                    Some(known) => compare_fields(value, known, &field, report),
+                    None => report.warning(&field, format!("未知字段 {field}，将被忽略")),
+                }
+            }
+        }
+        (Value::Array(raw), Value::Array(known)) => {
+            for (index, (value, known)) in raw.iter().zip(known).enumerate() {
+                compare_fields(value, known, &format!("{path}[{index}]"), report);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn check_metadata(manifest: &PluginManifest, report: &mut Report) {
+    let id = &manifest.id;
+    if id.trim().is_empty() {
+        report.error("id", "插件标识不能为空");
+    } else if id.starts_with('.')
+        || !id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
+    {
+        // The id names the install directory and appears in asset URLs.
+        report.error(
+            "id",
+            format!("插件标识 {id} 只能包含字母、数字、点、连字符和下划线，且不能以点开头"),
+        );
+    }
+    if manifest.name.fallback().trim().is_empty() {
+        report.error("name", "插件名称不能为空");
+    }
+    if let Err(e) = semver::Version::parse(&manifest.version) {
+        report.warning(
+            "version",
+            format!(
+                "版本 {} 不是语义化版本，更新检测和依赖检查将无法比较: {e}",
+                manifest.version
+            ),
+        );
+    }
+    if let Err(reason) = manifest.check_api_version() {
+        report.error("apiVersion", reason);
+    }
+    let truidide_error = manifest.engines.truidide.as_ref().and_then(|raw| {
+        parse_version_requirement(raw)
+            .err()
+            .map(|e| format!("版本要求 {raw} 无效: {e}"))
+    });
+    if let Some(message) = truidide_error {
+        report.error("engines.truidide", message);
+    } else if let Err(reason) = manifest.check_engines() {
+        // Also fails for a valid manifest built for another device.
+        report.warning("engines", format!("与当前设备不兼容: {reason}"));
+    }
+    for (runtime, raw) in &manifest.engines.runtimes {
+        if let Err(e) = parse_version_requirement(raw) {
+            report.error(
+                &format!("engines.runtimes.{runtime}"),
+                format!("版本要求 {raw} 无效: {e}"),
+            );
+        }
+    }
+    for (dependency, raw) in &manifest.dependencies {
+        if dependency == id {
+            report.error(&format!("dependencies.{dependency}"), "插件不能依赖自身");
+        }
+        if let Err(e) = parse_version_requirement(raw) {
+            report.error(
+                &format!("dependencies.{dependency}"),
+                format!("版本要求 {raw} 无效: {e}"),
+            );
+        }
+    }
+}
+
+fn process_fields(manifest: &PluginManifest) -> Option<ProcessFields<'_>> {
+    let process = match &manifest.kind {
+        PluginKind::Lsp(kind) => ProcessFields {
+            command: &kind.command,
+            env: &kind.env,
+            cwd: kind.cwd.as_deref(),
+            plugin_mount_path: kind.plugin_mount_path.as_deref(),
+            workspace_mount_path: kind.workspace_mount_path.as_deref(),
+            downloaded: kind.download.is_some(),
+        },
+        PluginKind::Dap(kind) => ProcessFields {
+            command: &kind.command,
+            env: &kind.env,
+            cwd: kind.cwd.as_deref(),
+            plugin_mount_path: kind.plugin_mount_path.as_deref(),
+            workspace_mount_path: kind.workspace_mount_path.as_deref(),
+            downloaded: false,
+        },
+        PluginKind::Formatter(kind) => ProcessFields {
+            command: &kind.command,
+            env: &kind.env,
+            cwd: kind.cwd.as_deref(),
+            plugin_mount_path: kind.plugin_mount_path.as_deref(),
+            workspace_mount_path: kind.workspace_mount_path.as_deref(),
+            downloaded: false,
+        },
+        PluginKind::Linter(kind) => ProcessFields {
+            command: &kind.command,
+            env: &kind.env,
+            cwd: kind.cwd.as_deref(),
+            plugin_mount_path: kind.plugin_mount_path.as_deref(),
+            workspace_mount_path: kind.workspace_mount_path.as_deref(),
+            downloaded: false,
+        },
+        PluginKind::WebExtension(_)
+        | PluginKind::Snippets(_)
+        | PluginKind::Grammar(_)
+        | PluginKind::Theme(_)
+        | PluginKind::Runner(_) => return None,
+    };
+    Some(process)
+}
+
+fn check_process(
+    field: &str,
+    process: &ProcessFields<'_>,
+    manifest: &PluginManifest,
+    root: Option<&Path>,
+    report: &mut Report,
+) {
+    let command = process.command.trim();
+    let command_field = format!("{field}.command");
+    if command.is_empty() {
+        report.error(&command_field, "未指定启动命令");
+    } else if command.contains('/') && !command.starts_with('/') {
+        // Relative paths with a separator always resolve against the plugin root.
+        if let (Some(root), false) = (root, process.downloaded) {
+            check_plugin_file(root, &command_field, command, report);
+        }
+    } else if !command.contains('/') {
+        let bundled = root.is_some_and(|root| root.join(command).is_file());
+        if !bundled && !manifest.engines.runtimes.contains_key(command) {
+            report.warning(
+                &command_field,
+                format!(
+                    "命令 {command} 需要在 Linux 环境中预先安装；可在 engines.runtimes 中声明以便启动前检查"
+                ),
+            );
+        }
+    }
+
+    if let Some(cwd) = process.cwd {
+        if !relative_to_root(cwd) && !cwd.starts_with('/') {
+            report.warning(
+                &format!("{field}.cwd"),
+                format!("工作目录 {cwd} 超出插件目录"),
+            );
+        }
+    }
+
+    let mounts = [
+        ("pluginMountPath", process.plugin_mount_path),
+        ("workspaceMountPath", process.workspace_mount_path),
+    ];
+    for (name, mount) in mounts {
+        if let Some(mount) = mount {
+            check_mount_path(&format!("{field}.{name}"), mount, report);
+        }
+    }
+    if let (Some(plugin_mount), Some(workspace_mount)) =
+        (process.plugin_mount_path, process.workspace_mount_path)
+    {
+        if plugin_mount.trim_end_matches('/') == workspace_mount.trim_end_matches('/') {
+            report.error(
+                &format!("{field}.workspaceMountPath"),
+                "工作区挂载路径不能与插件挂载路径相同",
+            );
+        }
+    }
+
+    check_env(field, process.env, manifest, report);
+}
+
+fn check_mount_path(field: &str, mount: &str, report: &mut Report) {
+    if !mount.starts_with('/') {
+        report.warning(
+            field,
+            format!("挂载路径 {mount} 不是绝对路径，将使用默认路径"),
+        );
+        return;
+    }
+    if Path::new(mount)
+        .components()
+        .any(|component| component == Component::ParentDir)
+    {
+        report.error(field, format!("挂载路径 {mount} 不能包含 .."));
+        return;
+    }
+    let normalized = match mount.trim_end_matches('/') {
+        "" => "/",
+        trimmed => trimmed,
+    };
+    if RESERVED_MOUNT_PATHS.contains(&normalized) {
+        report.error(field, format!("挂载路径 {mount} 与宿主保留的目录冲突"));
+    }
+}
+
+fn check_env(
+    field: &str,
+    env: &HashMap<String, String>,
+    manifest: &PluginManifest,
+    report: &mut Report,
+) {
+    let permissions = manifest.effective_permissions();
+    for (key, value) in env {
+        let key_field = format!("{field}.env.{key}");
+        if key.is_empty() || key.contains('=') || key.contains('\0') {
+            report.error(&key_field, format!("环境变量名 {key:?} 无效"));
+            continue;
+        }
+        if value.contains('\0') {
+            report.error(&key_field, "环境变量的值不能包含 NUL 字符");
+        }
+        let portable = key
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !portable {
+            report.warning(
+                &key_field,
+                format!("环境变量名 {key} 在部分 shell 中无法使用"),
+            );
+        }
+        if key.starts_with("TRUIDIDE_") {
+            report.warning(&key_field, format!("{key} 由宿主设置，插件的值会被覆盖"));
+        }
+        if key == "HOME" && permissions.filesystem == FilesystemScope::Workspace {
+            report.warning(
+                &key_field,
+                "HOME 由宿主按文件系统权限设置，插件的值会被覆盖",
+            );
+        }
+        if !permissions.network && PROXY_VARIABLES.contains(&key.as_str()) {
+            report.warning(&key_field, format!("未申请网络权限时 {key} 会被宿主覆盖"));
+        }
+        if key == "PATH" {
+            report.warning(
+                &key_field,
+                "PATH 在 Android 上会追加系统默认路径，在桌面端则完全替换系统 PATH",
+            );
+        }
+    }
+}
+
+fn asset_paths(manifest: &PluginManifest) -> Vec<(String, &str)> {
+    let mut paths = Vec::new();
+    match &manifest.kind {
+        PluginKind::WebExtension(kind) => {
+            paths.push(("kind.entry".to_string(), kind.entry.as_str()))
+        }
+        PluginKind::Snippets(kind) => {
+            for (index, file) in kind.snippets.iter().enumerate() {
+                paths.push((format!("kind.snippets[{index}].path"), file.path.as_str()));
+            }
+        }
+        PluginKind::Grammar(kind) => {
+            for (index, grammar) in kind.grammars.iter().enumerate() {
+                let field = format!("kind.grammars[{index}]");
+                paths.push((format!("{field}.wasm"), grammar.wasm.as_str()));
+                let queries = [
+                    ("highlights", &grammar.queries.highlights),
+                    ("locals", &grammar.queries.locals),
+                    ("injections", &grammar.queries.injections),
+                    ("tags", &grammar.queries.tags),
+                ];
+                for (name, query) in queries {
+                    if let Some(query) = query {
+                        paths.push((format!("{field}.queries.{name}"), query.as_str()));
+                    }
+                }
+            }
+        }
+        PluginKind::Theme(kind) => {
+            for (index, theme) in kind.themes.iter().enumerate() {
+                paths.push((format!("kind.themes[{index}].path"), theme.path.as_str()));
+            }
+            for (index, theme) in kind.icon_themes.iter().enumerate() {
+                paths.push((
+                    format!("kind.iconThemes[{index}].path"),
+                    theme.path.as_str(),
+                ));
+            }
+        }
+        PluginKind::Lsp(_)
+        | PluginKind::Dap(_)
+        | PluginKind::Formatter(_)
+        | PluginKind::Linter(_)
+        | PluginKind::Runner(_) => {}
+    }
+    paths
+}
+
+fn relative_to_root(path: &str) -> bool {
+    Path::new(path)
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+}
+
+fn check_plugin_file(root: &Path, field: &str, path: &str, report: &mut Report) {
+    if !relative_to_root(path) {
+        report.error(field, format!("{path} 必须是插件目录内的相对路径"));
+    } else if !root.join(path).exists() {
+        report.error(field, format!("插件目录中不存在 {path}"));
+    }
+}
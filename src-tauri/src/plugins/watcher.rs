@@ -0,0 +1,62 @@
+use std::time::Duration;
+
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use tokio::sync::mpsc;
+
+use super::lsp_host::resolve_plugin_directories;
+use super::registry::MANIFEST_FILENAME;
+use super::PluginHost;
+
+// Editors save in several steps and unpacking a plugin touches many files;
+// wait for the directory to settle before rescanning.
+const RELOAD_DEBOUNCE: Duration = Duration::from_millis(500);
+
+fn affects_registry(event: &Event) -> bool {
+    match event.kind {
+        EventKind::Access(_) => false,
+        // Plugin directories being added or removed.
+        EventKind::Create(_) | EventKind::Remove(_) => true,
+        _ => event.paths.iter().any(|path| {
+            path.file_name()
+                .is_some_and(|name| name == MANIFEST_FILENAME)
+        }),
+    }
+}
+
+/// Watch the user plugin directories and reload the registry when plugins
+/// are added, removed or have their manifest edited. Changes reach the
+/// frontend through the usual `plugins/updated` event.
+pub fn watch_user_plugins(host: &PluginHost) -> Result<(), String> {
+    let directories = resolve_plugin_directories(host.app())?;
+    let (tx, mut rx) = mpsc::unbounded_channel::<()>();
+
+    let mut watcher =
+        notify::recommended_watcher(move |result: notify::Result<Event>| match result {
+            Ok(event) if affects_registry(&event) => {
+                let _ = tx.send(());
+            }
+            Ok(_) => {}
+            Err(err) => eprintln!("[truidide::plugins] 监听插件目录出错: {}", err),
+        })
+        .map_err(|e| format!("创建插件目录监听失败: {e}"))?;
+
+    for dir in &directories.user {
+        std::fs::create_dir_all(dir).map_err(|e| format!("创建插件目录失败: {e}"))?;
+        watcher
+            .watch(dir, RecursiveMode::Recursive)
+            .map_err(|e| format!("监听插件目录 {} 失败: {e}", dir.display()))?;
+    }
+
+    let host = host.clone();
+    tauri::async_runtime::spawn(async move {
+        // Owning the watcher here keeps it alive for as long as the loop runs.
+        let _watcher = watcher;
+        while rx.recv().await.is_some() {
+            while let Ok(Some(())) = tokio::time::timeout(RELOAD_DEBOUNCE, rx.recv()).await {}
+            if let Err(err) = host.reload_registry().await {
+                eprintln!("[truidide::plugins] 自动重新加载插件失败: {}", err);
+            }
+        }
+    });
+    Ok(())
+}
@@ -0,0 +1,552 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+use base64::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::http::{header, Request, Response, StatusCode};
+use tauri::{AppHandle, Emitter};
+use tokio::process::Command;
+use uuid::Uuid;
+
+use super::permissions::apply_permission_env;
+#[cfg(target_os = "android")]
+use super::permissions::proot_permission_args;
+use super::{BusSink, DiscoveredPlugin, PluginHost, PluginKind, WebExtensionPermission};
+
+/// Custom URI scheme used to serve web extension assets.
+pub const PLUGIN_PROTOCOL: &str = "truid-plugin";
+
+const EVENT_PLUGIN_TOAST: &str = "truidide://plugins/toast";
+const RUN_TOOL_TIMEOUT_SECS: u64 = 60;
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum BrokerRequest {
+    #[serde(rename_all = "camelCase")]
+    ReadFile {
+        workspace_path: String,
+        path: String,
+    },
+    RunTool {
+        program: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+    ShowToast {
+        message: String,
+    },
+    Publish {
+        topic: String,
+        #[serde(default)]
+        payload: Value,
+    },
+    Subscribe {
+        topic: String,
+    },
+    Unsubscribe {
+        topic: String,
+    },
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum BrokerResponse {
+    File {
+        contents: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    ToolOutput {
+        exit_code: Option<i32>,
+        stdout: String,
+        stderr: String,
+    },
+    Published {
+        delivered: usize,
+    },
+    Done,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PluginToastPayload {
+    plugin_id: String,
+    message: String,
+}
+
+/// Handed to the frontend host when it loads a web extension.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadedWebExtension {
+    pub plugin_id: String,
+    /// Plugin protocol URL of the entry page.
+    pub entry: String,
+    /// Identifies the extension in broker calls. The host keeps it to itself,
+    /// the extension page never sees it.
+    pub token: String,
+}
+
+/// Build the URL under which the frontend can load a web extension asset.
+pub fn web_extension_url(plugin_id: &str, entry: &str) -> String {
+    let entry = entry.trim_start_matches('/');
+    // Windows and Android webviews expose custom schemes as http://<scheme>.localhost
+    if cfg!(any(windows, target_os = "android")) {
+        format!("http://{PLUGIN_PROTOCOL}.localhost/{plugin_id}/{entry}")
+    } else {
+        format!("{PLUGIN_PROTOCOL}://localhost/{plugin_id}/{entry}")
+    }
+}
+
+/// Whether the plugin ships assets the webview may load: web extensions,
+/// grammars and themes.
+pub(super) fn serves_assets(plugin: &DiscoveredPlugin) -> bool {
+    matches!(
+        plugin.manifest.kind,
+        PluginKind::WebExtension(_) | PluginKind::Grammar(_) | PluginKind::Theme(_)
+    )
+}
+
+/// Serve a file from the directory of a plugin that ships web assets.
+///
+/// The first path segment selects the plugin, the rest is resolved against the
+/// plugin root and must not escape it.
+pub async fn serve_plugin_asset(app: &AppHandle, request: &Request<Vec<u8>>) -> Response<Vec<u8>> {
+    let path = request.uri().path().trim_start_matches('/');
+    let decoded = percent_decode(path);
+    let Some((plugin_id, relative)) = decoded.split_once('/') else {
+        return error_response(StatusCode::NOT_FOUND, "缺少插件资源路径");
+    };
+
+    let host = match PluginHost::obtain(app) {
+        Ok(host) => host,
+        Err(err) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, &err),
+    };
+    let Some(plugin) = host.find_plugin(plugin_id).await else {
+        return error_response(StatusCode::NOT_FOUND, "插件不存在");
+    };
+    if !plugin.is_usable() || !serves_assets(&plugin) {
+        return error_response(StatusCode::FORBIDDEN, "插件未提供可加载的资源");
+    }
+
+    let file_path = match resolve_inside(&plugin.root_dir, relative) {
+        Ok(path) => path,
+        Err(err) => return error_response(StatusCode::FORBIDDEN, &err),
+    };
+    match fs::read(&file_path) {
+        Ok(data) => Response::builder()
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, guess_mime(&file_path))
+            .body(data)
+            .unwrap_or_else(|_| Response::new(Vec::new())),
+        Err(err) => error_response(StatusCode::NOT_FOUND, &format!("读取插件资源失败: {err}")),
+    }
+}
+
+/// Content of a plugin asset returned over IPC, for loaders that cannot use
+/// the plugin protocol URL.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginAsset {
+    pub mime_type: String,
+    /// `utf8` for text assets, `base64` for everything else.
+    pub encoding: &'static str,
+    pub content: String,
+}
+
+impl PluginHost {
+    pub async fn plugin_asset(
+        &self,
+        plugin_id: &str,
+        relative_path: &str,
+    ) -> Result<PluginAsset, String> {
+        let plugin = self
+            .find_plugin(plugin_id)
+            .await
+            .ok_or_else(|| format!("未找到插件 {plugin_id}"))?;
+        if !plugin.is_usable() || !serves_assets(&plugin) {
+            return Err(format!("插件 {plugin_id} 未提供可加载的资源"));
+        }
+        let path = resolve_inside(&plugin.root_dir, relative_path)?;
+        if !path.is_file() {
+            return Err(format!("{relative_path} 不是文件"));
+        }
+        let data = fs::read(&path).map_err(|e| format!("读取插件资源失败: {e}"))?;
+        let mime_type = guess_mime(&path);
+        let is_text = mime_type.starts_with("text/")
+            || mime_type == "application/json"
+            || mime_type == "image/svg+xml";
+        let (encoding, content) = match String::from_utf8(data) {
+            Ok(text) if is_text => ("utf8", text),
+            Ok(text) => ("base64", BASE64_STANDARD.encode(text)),
+            Err(err) => ("base64", BASE64_STANDARD.encode(err.into_bytes())),
+        };
+        Ok(PluginAsset {
+            mime_type: mime_type.to_string(),
+            encoding,
+            content,
+        })
+    }
+
+    /// Issue a broker token for a web extension about to be shown. Broker
+    /// calls are authorized by this token rather than a plugin id the caller
+    /// names, so an extension cannot borrow another plugin's permissions.
+    pub async fn load_web_extension(&self, plugin_id: &str) -> Result<LoadedWebExtension, String> {
+        let plugin = self
+            .find_plugin(plugin_id)
+            .await
+            .ok_or_else(|| format!("未找到插件 {plugin_id}"))?;
+        if !plugin.is_usable() {
+            return Err(format!("插件 {plugin_id} 当前被禁用或与宿主不兼容"));
+        }
+        let PluginKind::WebExtension(manifest) = &plugin.manifest.kind else {
+            return Err(format!("插件 {plugin_id} 不是 Web 扩展"));
+        };
+
+        let token = Uuid::new_v4().to_string();
+        self.web_extensions()
+            .write()
+            .await
+            .insert(token.clone(), plugin_id.to_string());
+        Ok(LoadedWebExtension {
+            plugin_id: plugin_id.to_string(),
+            entry: web_extension_url(plugin_id, &manifest.entry),
+            token,
+        })
+    }
+
+    /// Revoke a broker token once its extension page is closed.
+    pub async fn unload_web_extension(&self, token: &str) {
+        if self.web_extensions().write().await.remove(token).is_some() {
+            self.bus().remove_subscriber(&format!("web:{token}")).await;
+        }
+    }
+
+    /// Execute a broker request on behalf of the web extension `token` was
+    /// issued to, after checking the permissions declared in its manifest.
+    pub async fn broker_call(
+        &self,
+        token: &str,
+        request: BrokerRequest,
+    ) -> Result<BrokerResponse, String> {
+        let plugin_id = self
+            .web_extensions()
+            .read()
+            .await
+            .get(token)
+            .cloned()
+            .ok_or_else(|| "Web 扩展未加载或已关闭".to_string())?;
+        let plugin_id = plugin_id.as_str();
+        let plugin = self
+            .find_plugin(plugin_id)
+            .await
+            .ok_or_else(|| format!("未找到插件 {plugin_id}"))?;
+        if !plugin.is_usable() {
+            return Err(format!("插件 {plugin_id} 当前被禁用或与宿主不兼容"));
+        }
+        let permissions = web_extension_permissions(&plugin)
+            .ok_or_else(|| format!("插件 {plugin_id} 不是 Web 扩展"))?;
+
+        // Bus topics are checked against the manifest's `bus` section instead.
+        let required = match &request {
+            BrokerRequest::ReadFile { .. } => Some(WebExtensionPermission::FsRead),
+            BrokerRequest::RunTool { .. } => Some(WebExtensionPermission::RunTool),
+            BrokerRequest::ShowToast { .. } => Some(WebExtensionPermission::ShowToast),
+            BrokerRequest::Publish { .. }
+            | BrokerRequest::Subscribe { .. }
+            | BrokerRequest::Unsubscribe { .. } => None,
+        };
+        if let Some(required) = required {
+            if !permissions.contains(&required) {
+                return Err(format!("插件 {plugin_id} 未声明权限 {required:?}"));
+            }
+        }
+        if matches!(request, BrokerRequest::RunTool { .. })
+            && !plugin.manifest.effective_permissions().spawn_processes
+        {
+            return Err(format!("插件 {plugin_id} 未获准启动进程"));
+        }
+        let subscriber_key = format!("web:{token}");
+
+        match request {
+            BrokerRequest::ReadFile {
+                workspace_path,
+                path,
+            } => {
+                let file_path = resolve_workspace_file(self.app(), &workspace_path, &path)?;
+                let data = fs::read(&file_path).map_err(|e| format!("读取文件失败: {e}"))?;
+                Ok(BrokerResponse::File {
+                    contents: String::from_utf8_lossy(&data).into_owned(),
+                })
+            }
+            BrokerRequest::RunTool { program, args } => {
+                run_plugin_tool(self.app(), &plugin, &program, &args).await
+            }
+            BrokerRequest::ShowToast { message } => {
+                self.app()
+                    .emit(
+                        EVENT_PLUGIN_TOAST,
+                        &PluginToastPayload {
+                            plugin_id: plugin_id.to_string(),
+                            message,
+                        },
+                    )
+                    .map_err(|e| e.to_string())?;
+                Ok(BrokerResponse::Done)
+            }
+            BrokerRequest::Publish { topic, payload } => {
+                let delivered = self
+                    .bus()
+                    .publish(self.app(), &plugin.manifest, &topic, payload)
+                    .await?;
+                Ok(BrokerResponse::Published { delivered })
+            }
+            BrokerRequest::Subscribe { topic } => {
+                self.bus()
+                    .subscribe(
+                        &plugin.manifest,
+                        &subscriber_key,
+                        &topic,
+                        BusSink::WebExtension,
+                    )
+                    .await?;
+                Ok(BrokerResponse::Done)
+            }
+            BrokerRequest::Unsubscribe { topic } => {
+                self.bus().unsubscribe(&subscriber_key, &topic).await;
+                Ok(BrokerResponse::Done)
+            }
+        }
+    }
+
+    /// Command running `program` from the plugin's directory with the
+    /// plugin's permissions applied, for callers outside the broker such as
+    /// hooks.
+    pub async fn plugin_tool_command(
+        &self,
+        plugin_id: &str,
+        program: &str,
+    ) -> Result<Command, String> {
+        let plugin = self
+            .find_plugin(plugin_id)
+            .await
+            .ok_or_else(|| format!("未找到插件 {plugin_id}"))?;
+        if !plugin.is_usable() {
+            return Err(format!("插件 {plugin_id} 当前被禁用或与宿主不兼容"));
+        }
+        if !plugin.manifest.effective_permissions().spawn_processes {
+            return Err(format!("插件 {plugin_id} 未获准启动进程"));
+        }
+        tool_command(self.app(), &plugin, program)
+    }
+}
+
+fn web_extension_permissions(plugin: &DiscoveredPlugin) -> Option<Vec<WebExtensionPermission>> {
+    match &plugin.manifest.kind {
+        PluginKind::WebExtension(manifest) => Some(manifest.permissions.clone()),
+        _ => None,
+    }
+}
+
+pub(super) fn resolve_inside(root: &Path, relative: &str) -> Result<PathBuf, String> {
+    let root = root
+        .canonicalize()
+        .map_err(|e| format!("无法访问插件目录: {e}"))?;
+    let candidate = root
+        .join(relative)
+        .canonicalize()
+        .map_err(|e| format!("无法访问 {relative}: {e}"))?;
+    if !candidate.starts_with(&root) {
+        return Err(format!("路径 {relative} 超出允许的目录"));
+    }
+    Ok(candidate)
+}
+
+#[cfg(target_os = "android")]
+fn resolve_workspace_file(
+    app: &AppHandle,
+    workspace_path: &str,
+    path: &str,
+) -> Result<PathBuf, String> {
+    use crate::android::proot::resolve_guest_path;
+
+    // Projects live under the guest's /root, like the projects root on desktop.
+    let projects_root = resolve_guest_path(app, "/root")?;
+    let workspace = resolve_guest_path(app, workspace_path)?;
+    if !workspace.starts_with(&projects_root) {
+        return Err("工作区不在受信目录内".into());
+    }
+    let requested = if path.starts_with('/') {
+        let requested = resolve_guest_path(app, path)?;
+        if !requested.starts_with(&workspace) {
+            return Err(format!("文件 {path} 不在工作区内"));
+        }
+        requested
+    } else {
+        resolve_inside(&workspace, path)?
+    };
+    if !requested.is_file() {
+        return Err(format!("{path} 不是有效的文件"));
+    }
+    Ok(requested)
+}
+
+#[cfg(not(target_os = "android"))]
+fn resolve_workspace_file(
+    app: &AppHandle,
+    workspace_path: &str,
+    path: &str,
+) -> Result<PathBuf, String> {
+    let projects_root = crate::fs_utils::ensure_projects_dir(app)?
+        .canonicalize()
+        .map_err(|e| e.to_string())?;
+    let workspace = PathBuf::from(workspace_path)
+        .canonicalize()
+        .map_err(|e| format!("无法访问工作区: {e}"))?;
+    if !workspace.starts_with(&projects_root) {
+        return Err("工作区不在受信目录内".into());
+    }
+    let requested = resolve_inside(&workspace, path)?;
+    if !requested.is_file() {
+        return Err(format!("{path} 不是有效的文件"));
+    }
+    Ok(requested)
+}
+
+async fn run_plugin_tool(
+    app: &AppHandle,
+    plugin: &DiscoveredPlugin,
+    program: &str,
+    args: &[String],
+) -> Result<BrokerResponse, String> {
+    let mut command = tool_command(app, plugin, program)?;
+    command
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    let child = command
+        .spawn()
+        .map_err(|e| format!("启动插件工具失败: {e}"))?;
+    let output = tokio::time::timeout(
+        Duration::from_secs(RUN_TOOL_TIMEOUT_SECS),
+        child.wait_with_output(),
+    )
+    .await
+    .map_err(|_| format!("插件工具执行超时 ({RUN_TOOL_TIMEOUT_SECS} 秒)"))?
+    .map_err(|e| format!("等待插件工具结束失败: {e}"))?;
+
+    Ok(BrokerResponse::ToolOutput {
+        exit_code: output.status.code(),
+        stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+    })
+}
+
+fn tool_command(
+    app: &AppHandle,
+    plugin: &DiscoveredPlugin,
+    program: &str,
+) -> Result<Command, String> {
+    let program_path = resolve_inside(&plugin.root_dir, program)?;
+    if !program_path.is_file() {
+        return Err(format!("工具 {program} 不存在"));
+    }
+    build_tool_command(app, plugin, &program_path)
+}
+
+#[cfg(target_os = "android")]
+fn build_tool_command(
+    app: &AppHandle,
+    plugin: &DiscoveredPlugin,
+    program_path: &Path,
+) -> Result<Command, String> {
+    let env = crate::android::proot::prepare_proot_env(app)?;
+    let plugin_root = plugin
+        .root_dir
+        .canonicalize()
+        .map_err(|e| format!("无法访问插件目录: {e}"))?;
+    let guest_root = format!("/opt/truidide/plugins/{}", plugin.manifest.id);
+    let relative = program_path
+        .strip_prefix(&plugin_root)
+        .map_err(|_| "工具路径超出插件目录".to_string())?;
+
+    let mut command = crate::android::proot::proot_command(&env, &guest_root);
+    command.arg(format!(
+        "--bind={}:{}",
+        plugin_root.to_string_lossy(),
+        guest_root
+    ));
+    command.args(proot_permission_args(app, plugin)?);
+    command.arg(format!("{}/{}", guest_root, relative.to_string_lossy()));
+    command.env("TRUIDIDE_PLUGIN_ID", &plugin.manifest.id);
+    let mut command = Command::from(command);
+    apply_permission_env(app, plugin, &mut command, true)?;
+    Ok(command)
+}
+
+#[cfg(not(target_os = "android"))]
+fn build_tool_command(
+    app: &AppHandle,
+    plugin: &DiscoveredPlugin,
+    program_path: &Path,
+) -> Result<Command, String> {
+    let mut command = Command::new(program_path);
+    command.current_dir(&plugin.root_dir);
+    command.env("TRUIDIDE_PLUGIN_ID", &plugin.manifest.id);
+    apply_permission_env(app, plugin, &mut command, false)?;
+    Ok(command)
+}
+
+fn percent_decode(raw: &str) -> String {
+    let bytes = raw.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+            if let Ok(value) = u8::from_str_radix(hex, 16) {
+                out.push(value);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn guess_mime(path: &Path) -> &'static str {
+    let ext = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase())
+        .unwrap_or_default();
+    match ext.as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "json" => "application/json",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "woff2" => "font/woff2",
+        "wasm" => "application/wasm",
+        "scm" => "text/plain; charset=utf-8",
+        _ => "application/octet-stream",
+    }
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response<Vec<u8>> {
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "text/plain; charset=utf-8")
+        .body(message.as_bytes().to_vec())
+        .unwrap_or_else(|_| Response::new(Vec::new()))
+}
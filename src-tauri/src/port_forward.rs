@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use tauri::async_runtime::JoinHandle;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Mutex;
+use tokio::task::JoinSet;
+use uuid::Uuid;
+
+static FORWARDS: OnceCell<Mutex<HashMap<String, PortForward>>> = OnceCell::new();
+
+fn forwards() -> &'static Mutex<HashMap<String, PortForward>> {
+    FORWARDS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+struct PortForward {
+    info: PortForwardInfo,
+    /// Accept loop; aborting it also drops every relayed connection.
+    task: JoinHandle<()>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortForwardInfo {
+    pub id: String,
+    pub local_port: u16,
+    pub remote_host: String,
+    pub remote_port: u16,
+    /// SSH session the traffic is tunnelled through; `None` for a plain relay.
+    pub session_id: Option<String>,
+    /// Address to open in the webview.
+    pub url: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatePortForwardArgs {
+    /// Local port to listen on; 0 or missing picks a free one.
+    #[serde(default)]
+    pub local_port: Option<u16>,
+    #[serde(default)]
+    pub remote_host: Option<String>,
+    pub remote_port: u16,
+    /// Tunnel through this SSH terminal session instead of connecting directly.
+    /// Services inside proot share the host network, so they need no session.
+    #[serde(default)]
+    pub session_id: Option<String>,
+}
+
+// Where accepted connections are relayed to.
+#[derive(Clone)]
+enum Upstream {
+    Direct,
+    Ssh(crate::ssh::SshConnection),
+}
+
+async fn relay<S>(mut local: TcpStream, mut remote: S)
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let _ = tokio::io::copy_bidirectional(&mut local, &mut remote).await;
+}
+
+async fn accept_loop(
+    id: String,
+    listener: TcpListener,
+    upstream: Upstream,
+    remote_host: String,
+    remote_port: u16,
+) {
+    let mut connections = JoinSet::new();
+    loop {
+        let (local, _) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(err) => {
+                eprintln!("[truidide::port_forward] 转发 {} 接受连接失败: {}", id, err);
+                continue;
+            }
+        };
+        let upstream = upstream.clone();
+        let remote_host = remote_host.clone();
+        let id = id.clone();
+        connections.spawn(async move {
+            match upstream {
+                Upstream::Direct => {
+                    match TcpStream::connect((remote_host.as_str(), remote_port)).await {
+                        Ok(remote) => relay(local, remote).await,
+                        Err(err) => eprintln!(
+                            "[truidide::port_forward] 转发 {} 连接 {}:{} 失败: {}",
+                            id, remote_host, remote_port, err
+                        ),
+                    }
+                }
+                Upstream::Ssh(connection) => {
+                    match connection.open_tunnel(&remote_host, remote_port).await {
+                        Ok(remote) => relay(local, remote).await,
+                        Err(err) => eprintln!("[truidide::port_forward] 转发 {}: {}", id, err),
+                    }
+                }
+            }
+        });
+        // Reap finished relays so the set does not grow without bound.
+        while connections.try_join_next().is_some() {}
+    }
+}
+
+#[tauri::command]
+pub async fn create_port_forward(args: CreatePortForwardArgs) -> Result<PortForwardInfo, String> {
+    let remote_host = args
+        .remote_host
+        .map(|host| host.trim().to_string())
+        .filter(|host| !host.is_empty())
+        .unwrap_or_else(|| "127.0.0.1".to_string());
+    if args.remote_port == 0 {
+        return Err("远程端口无效".into());
+    }
+    let upstream = match &args.session_id {
+        Some(session_id) => Upstream::Ssh(crate::terminal::ssh_connection(session_id).await?),
+        None => Upstream::Direct,
+    };
+
+    // Only listen on loopback: the forward is meant for the app's own webview.
+    let listener = TcpListener::bind(("127.0.0.1", args.local_port.unwrap_or(0)))
+        .await
+        .map_err(|e| format!("监听本地端口失败: {e}"))?;
+    let local_port = listener
+        .local_addr()
+        .map_err(|e| format!("获取本地端口失败: {e}"))?
+        .port();
+
+    let id = Uuid::new_v4().to_string();
+    let info = PortForwardInfo {
+        id: id.clone(),
+        local_port,
+        remote_host: remote_host.clone(),
+        remote_port: args.remote_port,
+        session_id: args.session_id,
+        url: format!("http://127.0.0.1:{local_port}"),
+    };
+    let task = tauri::async_runtime::spawn(accept_loop(
+        id.clone(),
+        listener,
+        upstream,
+        remote_host,
+        args.remote_port,
+    ));
+    forwards().lock().await.insert(
+        id,
+        PortForward {
+            info: info.clone(),
+            task,
+        },
+    );
+    Ok(info)
+}
+
+#[tauri::command]
+pub async fn list_port_forwards() -> Result<Vec<PortForwardInfo>, String> {
+    let mut infos = forwards()
+        .lock()
+        .await
+        .values()
+        .map(|forward| forward.info.clone())
+        .collect::<Vec<_>>();
+    infos.sort_by_key(|info| info.local_port);
+    Ok(infos)
+}
+
+#[tauri::command]
+pub async fn stop_port_forward(id: String) -> Result<(), String> {
+    let forward = forwards()
+        .lock()
+        .await
+        .remove(&id)
+        .ok_or_else(|| format!("未找到端口转发 {id}"))?;
+    forward.task.abort();
+    Ok(())
+}
@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+/// Resource usage of a process together with all of its descendants.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessTreeStats {
+    /// User plus system CPU time consumed so far, in milliseconds.
+    pub cpu_time_ms: u64,
+    /// Resident set size, in bytes.
+    pub rss_bytes: u64,
+    /// Number of live descendants, not counting the root process.
+    pub child_processes: usize,
+}
+
+// Only populated where /proc exists.
+#[cfg_attr(not(any(target_os = "linux", target_os = "android")), allow(dead_code))]
+struct ProcEntry {
+    ppid: u32,
+    cpu_ticks: u64,
+    rss_pages: u64,
+}
+
+/// Snapshot of the processes visible to us, read from `/proc`.
+pub struct ProcessTable {
+    entries: HashMap<u32, ProcEntry>,
+    children: HashMap<u32, Vec<u32>>,
+    ticks_per_sec: u64,
+    page_size: u64,
+}
+
+// Fields of /proc/<pid>/stat after the parenthesised command name, which may
+// itself contain spaces.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn parse_stat(stat: &str) -> Option<ProcEntry> {
+    let rest = &stat[stat.rfind(')')? + 1..];
+    let fields = rest.split_whitespace().collect::<Vec<_>>();
+    // Indices are `man 5 proc` field numbers minus three.
+    let ppid = fields.get(1)?.parse().ok()?;
+    let utime: u64 = fields.get(11)?.parse().ok()?;
+    let stime: u64 = fields.get(12)?.parse().ok()?;
+    let rss_pages = fields.get(21)?.parse::<i64>().ok()?.max(0) as u64;
+    Some(ProcEntry {
+        ppid,
+        cpu_ticks: utime + stime,
+        rss_pages,
+    })
+}
+
+impl ProcessTable {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn read() -> Result<Self, String> {
+        let dir = std::fs::read_dir("/proc").map_err(|e| format!("读取 /proc 失败: {e}"))?;
+        let mut entries = HashMap::new();
+        let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+        for item in dir.flatten() {
+            let Some(pid) = item.file_name().to_str().and_then(|name| name.parse().ok()) else {
+                continue;
+            };
+            // Processes may exit between listing and reading; skip them.
+            let Some(entry) = std::fs::read_to_string(item.path().join("stat"))
+                .ok()
+                .and_then(|stat| parse_stat(&stat))
+            else {
+                continue;
+            };
+            children.entry(entry.ppid).or_default().push(pid);
+            entries.insert(pid, entry);
+        }
+
+        let ticks_per_sec = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+        let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+        Ok(Self {
+            entries,
+            children,
+            ticks_per_sec: if ticks_per_sec > 0 {
+                ticks_per_sec as u64
+            } else {
+                100
+            },
+            page_size: if page_size > 0 {
+                page_size as u64
+            } else {
+                4096
+            },
+        })
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    pub fn read() -> Result<Self, String> {
+        Err("当前平台不支持进程资源统计".into())
+    }
+
+    /// Aggregate usage of `root` and its descendants, or `None` if it is gone.
+    pub fn tree_stats(&self, root: u32) -> Option<ProcessTreeStats> {
+        self.entries.get(&root)?;
+        let mut stats = ProcessTreeStats::default();
+        let mut cpu_ticks = 0;
+        let mut pending = vec![root];
+        while let Some(pid) = pending.pop() {
+            let Some(entry) = self.entries.get(&pid) else {
+                continue;
+            };
+            cpu_ticks += entry.cpu_ticks;
+            stats.rss_bytes += entry.rss_pages * self.page_size;
+            if pid != root {
+                stats.child_processes += 1;
+            }
+            if let Some(children) = self.children.get(&pid) {
+                pending.extend(children);
+            }
+        }
+        stats.cpu_time_ms = cpu_ticks * 1000 / self.ticks_per_sec;
+        Some(stats)
+    }
+}
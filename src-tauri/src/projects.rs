@@ -2,6 +2,7 @@ use crate::fs_utils::{
     copy_entry_recursive, ensure_projects_dir, is_cross_device_error, normalize_entry_name,
     read_directory_entries, FileTreeEntry,
 };
+use crate::hooks::{self, HookContext, HookEvent};
 use serde::{Deserialize, Serialize};
 use std::fs::{self, File};
 use std::io::Write;
@@ -52,6 +53,33 @@ fn resolve_android_path(
     }
 }
 
+// Terminal sessions key their cwd by the path the frontend passed in: a guest
+// path inside proot on Android, a canonical host path elsewhere.
+#[cfg(target_os = "android")]
+fn terminal_key_for(app: &tauri::AppHandle, host_path: &Path) -> Option<PathBuf> {
+    let env = crate::android::proot::prepare_proot_env(app).ok()?;
+    Some(
+        host_path_to_guest(&env, host_path)
+            .map(PathBuf::from)
+            .unwrap_or_else(|| host_path.to_path_buf()),
+    )
+}
+
+#[cfg(not(target_os = "android"))]
+fn terminal_key_for(_app: &tauri::AppHandle, host_path: &Path) -> Option<PathBuf> {
+    Some(host_path.to_path_buf())
+}
+
+// Shut down shells still sitting in a directory that was removed or renamed.
+fn cleanup_terminal_sessions(app: &tauri::AppHandle, host_path: &Path) {
+    let Some(key) = terminal_key_for(app, host_path) else {
+        return;
+    };
+    tauri::async_runtime::spawn(async move {
+        crate::terminal::stop_sessions_under(&key).await;
+    });
+}
+
 #[derive(Serialize)]
 pub struct ProjectEntry {
     pub name: String,
@@ -253,6 +281,45 @@ pub fn list_project_tree(
     Ok(entries)
 }
 
+/// Called by the workspace once a project is opened; runs `project-open` hooks.
+#[tauri::command]
+pub fn open_project(app: tauri::AppHandle, project_path: String) -> Result<(), String> {
+    #[cfg(target_os = "android")]
+    let projects_root = ensure_projects_dir(&app)?
+        .canonicalize()
+        .map_err(|e| e.to_string())?;
+
+    #[cfg(target_os = "android")]
+    let (canonical_requested, is_guest_path) =
+        resolve_android_path(&app, &project_path, "无法访问项目目录")?;
+
+    #[cfg(not(target_os = "android"))]
+    let canonical_requested = PathBuf::from(&project_path)
+        .canonicalize()
+        .map_err(|e| format!("无法访问项目目录: {e}"))?;
+
+    #[cfg(target_os = "android")]
+    {
+        if !is_guest_path && !canonical_requested.starts_with(&projects_root) {
+            return Err("项目路径不在受信目录内".into());
+        }
+    }
+
+    if !canonical_requested.is_dir() {
+        return Err("目标路径不是有效的项目目录".into());
+    }
+
+    hooks::spawn_event(
+        &app,
+        HookEvent::ProjectOpen,
+        HookContext {
+            project_path: Some(project_path),
+            ..Default::default()
+        },
+    );
+    Ok(())
+}
+
 #[tauri::command]
 pub fn read_project_file(app: tauri::AppHandle, file_path: String) -> Result<String, String> {
     #[cfg(target_os = "android")]
@@ -290,6 +357,7 @@ pub fn save_project_file(
     app: tauri::AppHandle,
     file_path: String,
     contents: String,
+    project_path: Option<String>,
 ) -> Result<(), String> {
     #[cfg(target_os = "android")]
     let projects_root = ensure_projects_dir(&app)?
@@ -317,6 +385,16 @@ pub fn save_project_file(
     }
 
     fs::write(&canonical_requested, contents).map_err(|e| format!("保存文件失败: {e}"))?;
+    crate::plugins::lint_on_save(&app, file_path.clone());
+    hooks::spawn_event(
+        &app,
+        HookEvent::FileSave,
+        HookContext {
+            project_path,
+            file_path: Some(file_path),
+            task_name: None,
+        },
+    );
 
     Ok(())
 }
@@ -432,6 +510,7 @@ pub fn delete_project_entry(app: tauri::AppHandle, path: String) -> Result<(), S
 
     if canonical_entry.is_dir() {
         fs::remove_dir_all(&canonical_entry).map_err(|e| format!("删除目录失败: {e}"))?;
+        cleanup_terminal_sessions(&app, &canonical_entry);
     } else if canonical_entry.is_file() {
         fs::remove_file(&canonical_entry).map_err(|e| format!("删除文件失败: {e}"))?;
     } else {
@@ -504,6 +583,9 @@ pub fn rename_project_entry(
     }
 
     fs::rename(&canonical_entry, &destination).map_err(|e| format!("重命名失败: {e}"))?;
+    if destination.is_dir() {
+        cleanup_terminal_sessions(&app, &canonical_entry);
+    }
 
     Ok(())
 }
@@ -634,7 +716,8 @@ pub fn move_project_entry(
         return Err("无法将文件夹移动到其自身或子目录中".into());
     }
 
-    match fs::rename(&canonical_source, &destination) {
+    let was_dir = canonical_source.is_dir();
+    let result = match fs::rename(&canonical_source, &destination) {
         Ok(()) => Ok(()),
         Err(err) => {
             if !is_cross_device_error(&err) {
@@ -662,7 +745,12 @@ pub fn move_project_entry(
 
             Ok(())
         }
+    };
+
+    if result.is_ok() && was_dir {
+        cleanup_terminal_sessions(&app, &canonical_source);
     }
+    result
 }
 
 #[tauri::command]
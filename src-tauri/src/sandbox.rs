@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::path::BaseDirectory;
+use tauri::{AppHandle, Manager};
+
+// Kept in app data rather than in the project, so a cloned repository
+// cannot switch its own sandbox off.
+const SANDBOX_FILENAME: &str = "terminal-sandbox.json";
+const SANDBOX_HOMES_DIR: &str = "sandbox-homes";
+
+static SANDBOXES: OnceCell<Mutex<HashMap<String, SandboxConfig>>> = OnceCell::new();
+
+/// Per-project terminal restrictions for working on untrusted code.
+///
+/// On Android the proot session only sees the project directory and a
+/// private home instead of the real `/root`. On desktop the shell starts
+/// with a minimal environment and a private `HOME`; this limits accidental
+/// credential exposure but is not an isolation boundary.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SandboxConfig {
+    pub enabled: bool,
+    /// Extra environment variables passed through on desktop.
+    #[serde(default)]
+    pub allowed_env: Vec<String>,
+}
+
+fn sandbox_path(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .resolve(SANDBOX_FILENAME, BaseDirectory::AppData)
+        .map_err(|e| e.to_string())
+}
+
+fn sandbox_map(app: &AppHandle) -> &'static Mutex<HashMap<String, SandboxConfig>> {
+    SANDBOXES.get_or_init(|| {
+        let loaded = sandbox_path(app)
+            .ok()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default();
+        Mutex::new(loaded)
+    })
+}
+
+/// Sandbox settings for the project keyed by `project_key`, if enabled.
+pub fn enabled_for(app: &AppHandle, project_key: &str) -> Option<SandboxConfig> {
+    sandbox_map(app)
+        .lock()
+        .ok()?
+        .get(project_key)
+        .filter(|config| config.enabled)
+        .cloned()
+}
+
+/// Private home directory handed to sandboxed sessions of a project.
+pub fn home_dir(app: &AppHandle, project_key: &str) -> Result<PathBuf, String> {
+    let digest = Sha256::digest(project_key.as_bytes());
+    let name = digest[..8]
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<String>();
+    let dir = app
+        .path()
+        .resolve(
+            format!("{SANDBOX_HOMES_DIR}/{name}"),
+            BaseDirectory::AppData,
+        )
+        .map_err(|e| e.to_string())?;
+    fs::create_dir_all(&dir).map_err(|e| format!("创建沙箱主目录失败: {e}"))?;
+    Ok(dir)
+}
+
+#[tauri::command]
+pub fn get_project_sandbox(app: AppHandle, project_path: String) -> Result<SandboxConfig, String> {
+    let key = crate::terminal::cwd_key(&project_path);
+    let sandboxes = sandbox_map(&app)
+        .lock()
+        .map_err(|e| format!("锁错误: {e}"))?;
+    Ok(sandboxes.get(&key).cloned().unwrap_or_default())
+}
+
+/// Takes effect for terminal sessions started afterwards.
+#[tauri::command]
+pub fn set_project_sandbox(
+    app: AppHandle,
+    project_path: String,
+    config: SandboxConfig,
+) -> Result<(), String> {
+    let key = crate::terminal::cwd_key(&project_path);
+    let mut sandboxes = sandbox_map(&app)
+        .lock()
+        .map_err(|e| format!("锁错误: {e}"))?;
+    if config.enabled || !config.allowed_env.is_empty() {
+        sandboxes.insert(key, config);
+    } else {
+        sandboxes.remove(&key);
+    }
+
+    let path = sandbox_path(&app)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("创建配置目录失败: {e}"))?;
+    }
+    let data = serde_json::to_string_pretty(&*sandboxes)
+        .map_err(|e| format!("序列化沙箱配置失败: {e}"))?;
+    fs::write(&path, data).map_err(|e| format!("保存沙箱配置失败: {e}"))
+}
@@ -0,0 +1,428 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use russh::client;
+use russh::{ChannelMsg, Disconnect, Sig};
+use russh_keys::key;
+use serde::{Deserialize, Serialize};
+use tauri::async_runtime::JoinHandle;
+use tauri::path::BaseDirectory;
+use tauri::{AppHandle, Manager};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::sync::mpsc;
+
+const KNOWN_HOSTS_FILENAME: &str = "ssh-known-hosts.json";
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(20);
+const TERM: &str = "xterm-256color";
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum SshAuth {
+    Password {
+        password: String,
+    },
+    /// OpenSSH or PEM encoded private key content.
+    #[serde(rename_all = "camelCase")]
+    PrivateKey {
+        private_key: String,
+        #[serde(default)]
+        passphrase: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct SshTarget {
+    pub host: String,
+    pub port: u16,
+    pub user: String,
+}
+
+impl SshTarget {
+    fn for_host(host: String, port: Option<u16>) -> Self {
+        SshTarget {
+            host,
+            port: port.unwrap_or(22),
+            user: String::new(),
+        }
+    }
+
+    /// Key used for the known-hosts store and the terminal session cwd.
+    pub fn host_id(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+}
+
+enum RemoteRequest {
+    Resize { cols: u16, rows: u16 },
+    Signal(Sig),
+    Close,
+}
+
+/// An authenticated connection that further channels can be opened on.
+#[derive(Clone)]
+pub struct SshConnection(Arc<client::Handle<HostKeyCheck>>);
+
+impl SshConnection {
+    /// Open a `direct-tcpip` channel to `host:port` as seen from the server.
+    pub async fn open_tunnel(
+        &self,
+        host: &str,
+        port: u16,
+    ) -> Result<impl AsyncRead + AsyncWrite + Unpin + Send, String> {
+        let channel = self
+            .0
+            .channel_open_direct_tcpip(host, port.into(), "127.0.0.1", 0)
+            .await
+            .map_err(|e| format!("打开 SSH 隧道失败: {e}"))?;
+        Ok(channel.into_stream())
+    }
+}
+
+/// Control side of a remote shell channel driven by a background task.
+pub struct RemoteShell {
+    tx: mpsc::UnboundedSender<RemoteRequest>,
+    connection: SshConnection,
+    cols: u16,
+    rows: u16,
+}
+
+impl RemoteShell {
+    pub fn connection(&self) -> SshConnection {
+        self.connection.clone()
+    }
+
+    pub fn resize(&mut self, cols: u16, rows: u16) -> Result<(), String> {
+        self.tx
+            .send(RemoteRequest::Resize { cols, rows })
+            .map_err(|_| "SSH 连接已关闭".to_string())?;
+        self.cols = cols;
+        self.rows = rows;
+        Ok(())
+    }
+
+    pub fn size(&self) -> (u16, u16) {
+        (self.cols, self.rows)
+    }
+
+    pub fn signal(&self, raw: &str) -> Result<(), String> {
+        let upper = raw.trim().to_ascii_uppercase();
+        let sig = match upper.strip_prefix("SIG").unwrap_or(&upper) {
+            "INT" => Sig::INT,
+            "TERM" => Sig::TERM,
+            "KILL" => Sig::KILL,
+            "HUP" => Sig::HUP,
+            "QUIT" => Sig::QUIT,
+            "USR1" => Sig::USR1,
+            "USR2" => Sig::USR2,
+            _ => return Err(format!("不支持的信号: {raw}")),
+        };
+        self.tx
+            .send(RemoteRequest::Signal(sig))
+            .map_err(|_| "SSH 连接已关闭".to_string())
+    }
+
+    pub fn close(&self) {
+        let _ = self.tx.send(RemoteRequest::Close);
+    }
+}
+
+fn known_hosts_path(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .resolve(KNOWN_HOSTS_FILENAME, BaseDirectory::AppData)
+        .map_err(|e| e.to_string())
+}
+
+// Host id -> SHA-256 fingerprint of the host key seen on first connect.
+fn load_known_hosts(app: &AppHandle) -> Result<HashMap<String, String>, String> {
+    let path = known_hosts_path(app)?;
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+    let data = fs::read_to_string(&path).map_err(|e| format!("读取已知主机失败: {e}"))?;
+    serde_json::from_str(&data).map_err(|e| format!("解析已知主机失败: {e}"))
+}
+
+fn save_known_hosts(app: &AppHandle, hosts: &HashMap<String, String>) -> Result<(), String> {
+    let path = known_hosts_path(app)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("创建配置目录失败: {e}"))?;
+    }
+    let data =
+        serde_json::to_string_pretty(hosts).map_err(|e| format!("序列化已知主机失败: {e}"))?;
+    fs::write(&path, data).map_err(|e| format!("保存已知主机失败: {e}"))
+}
+
+/// Host key presented by a server, for the user to confirm before the first
+/// connection.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SshHostKey {
+    pub host_id: String,
+    pub fingerprint: String,
+    /// Fingerprint remembered for the host; differs from `fingerprint` when
+    /// the key changed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub known_fingerprint: Option<String>,
+}
+
+// Only the remembered key is accepted. Probes pass no expected key and accept
+// whatever is presented, but never authenticate.
+struct HostKeyCheck {
+    expected: Option<String>,
+    presented: Arc<Mutex<Option<String>>>,
+}
+
+#[async_trait]
+impl client::Handler for HostKeyCheck {
+    type Error = russh::Error;
+
+    async fn check_server_key(
+        &mut self,
+        server_public_key: &key::PublicKey,
+    ) -> Result<bool, Self::Error> {
+        let fingerprint = server_public_key.fingerprint();
+        let accepted = self
+            .expected
+            .as_ref()
+            .is_none_or(|expected| *expected == fingerprint);
+        if let Ok(mut presented) = self.presented.lock() {
+            *presented = Some(fingerprint);
+        }
+        Ok(accepted)
+    }
+}
+
+// The connection, or why it failed, and the fingerprint the server presented.
+type Handshake = (
+    Result<client::Handle<HostKeyCheck>, russh::Error>,
+    Option<String>,
+);
+
+// Run the key exchange with `target`.
+async fn handshake(target: &SshTarget, expected: Option<String>) -> Result<Handshake, String> {
+    let presented = Arc::new(Mutex::new(None));
+    let handler = HostKeyCheck {
+        expected,
+        presented: presented.clone(),
+    };
+
+    let config = Arc::new(client::Config::default());
+    let connecting = client::connect(config, (target.host.as_str(), target.port), handler);
+    let result = tokio::time::timeout(CONNECT_TIMEOUT, connecting)
+        .await
+        .map_err(|_| format!("连接 {} 超时", target.host_id()))?;
+    let presented = presented.lock().ok().and_then(|guard| guard.clone());
+    Ok((result, presented))
+}
+
+async fn connect(
+    app: &AppHandle,
+    target: &SshTarget,
+    auth: SshAuth,
+) -> Result<client::Handle<HostKeyCheck>, String> {
+    let host_id = target.host_id();
+    // Unknown hosts are confirmed through `probe_ssh_host_key` first.
+    let Some(expected) = load_known_hosts(app)?.remove(&host_id) else {
+        return Err(format!("尚未确认主机 {host_id} 的密钥，请先核对指纹"));
+    };
+    let (result, presented) = handshake(target, Some(expected.clone())).await?;
+    let mut handle = match result {
+        Ok(handle) => handle,
+        Err(err) => {
+            if let Some(presented) = presented.filter(|presented| *presented != expected) {
+                return Err(format!(
+                    "主机 {host_id} 的密钥已变更（当前指纹 {presented}），已拒绝连接"
+                ));
+            }
+            return Err(format!("连接 {host_id} 失败: {err}"));
+        }
+    };
+
+    let authenticated = match auth {
+        SshAuth::Password { password } => handle
+            .authenticate_password(target.user.as_str(), password)
+            .await
+            .map_err(|e| format!("认证失败: {e}"))?,
+        SshAuth::PrivateKey {
+            private_key,
+            passphrase,
+        } => {
+            let key_pair = russh_keys::decode_secret_key(&private_key, passphrase.as_deref())
+                .map_err(|e| format!("无法解析私钥: {e}"))?;
+            handle
+                .authenticate_publickey(target.user.as_str(), Arc::new(key_pair))
+                .await
+                .map_err(|e| format!("认证失败: {e}"))?
+        }
+    };
+    if !authenticated {
+        return Err(format!("用户 {} 认证被拒绝", target.user));
+    }
+    Ok(handle)
+}
+
+/// Connect, authenticate and open an interactive shell with a pty.
+///
+/// Bytes from `input_rx` are written to the channel and remote output is
+/// forwarded to `output_tx`; the returned handle resolves to the remote exit
+/// status once the channel closes.
+pub async fn open_shell(
+    app: &AppHandle,
+    target: &SshTarget,
+    auth: SshAuth,
+    (cols, rows): (u16, u16),
+    input_rx: mpsc::Receiver<Vec<u8>>,
+    output_tx: mpsc::Sender<Vec<u8>>,
+) -> Result<(RemoteShell, JoinHandle<Option<u32>>), String> {
+    let connection = SshConnection(Arc::new(connect(app, target, auth).await?));
+    let channel = connection
+        .0
+        .channel_open_session()
+        .await
+        .map_err(|e| format!("打开 SSH 通道失败: {e}"))?;
+    channel
+        .request_pty(false, TERM, cols.into(), rows.into(), 0, 0, &[])
+        .await
+        .map_err(|e| format!("请求远程 pty 失败: {e}"))?;
+    channel
+        .request_shell(false)
+        .await
+        .map_err(|e| format!("启动远程 shell 失败: {e}"))?;
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    let host_id = target.host_id();
+    let wait_handle = tauri::async_runtime::spawn(drive_channel(
+        host_id,
+        connection.clone(),
+        channel,
+        input_rx,
+        output_tx,
+        rx,
+    ));
+    Ok((
+        RemoteShell {
+            tx,
+            connection,
+            cols,
+            rows,
+        },
+        wait_handle,
+    ))
+}
+
+async fn drive_channel(
+    host_id: String,
+    connection: SshConnection,
+    mut channel: russh::Channel<client::Msg>,
+    mut input_rx: mpsc::Receiver<Vec<u8>>,
+    output_tx: mpsc::Sender<Vec<u8>>,
+    mut requests: mpsc::UnboundedReceiver<RemoteRequest>,
+) -> Option<u32> {
+    let mut exit_status = None;
+    loop {
+        tokio::select! {
+            msg = channel.wait() => match msg {
+                Some(ChannelMsg::Data { data }) | Some(ChannelMsg::ExtendedData { data, .. }) => {
+                    if output_tx.send(data.to_vec()).await.is_err() {
+                        break;
+                    }
+                }
+                Some(ChannelMsg::ExitStatus { exit_status: status }) => {
+                    exit_status = Some(status);
+                }
+                // The exit status may still follow EOF, so wait for Close.
+                Some(ChannelMsg::Close) | None => break,
+                Some(_) => {}
+            },
+            input = input_rx.recv() => match input {
+                Some(data) => {
+                    if let Err(err) = channel.data(&data[..]).await {
+                        eprintln!("[truidide::ssh] 向 {} 写入失败: {}", host_id, err);
+                        break;
+                    }
+                }
+                None => break,
+            },
+            request = requests.recv() => match request {
+                Some(RemoteRequest::Resize { cols, rows }) => {
+                    let _ = channel.window_change(cols.into(), rows.into(), 0, 0).await;
+                }
+                Some(RemoteRequest::Signal(sig)) => {
+                    let _ = channel.signal(sig).await;
+                }
+                Some(RemoteRequest::Close) | None => break,
+            },
+        }
+    }
+
+    let _ = channel.close().await;
+    // Tunnels share the connection and end with the shell.
+    let _ = connection
+        .0
+        .disconnect(Disconnect::ByApplication, "", "en")
+        .await;
+    exit_status
+}
+
+/// Fetch the host key of `host:port` without authenticating, so the user can
+/// compare its fingerprint before trusting it.
+#[tauri::command]
+pub async fn probe_ssh_host_key(
+    app: AppHandle,
+    host: String,
+    port: Option<u16>,
+) -> Result<SshHostKey, String> {
+    let target = SshTarget::for_host(host.trim().to_string(), port);
+    if target.host.is_empty() {
+        return Err("主机不能为空".into());
+    }
+    let host_id = target.host_id();
+    let (result, presented) = handshake(&target, None).await?;
+    let handle = result.map_err(|e| format!("连接 {host_id} 失败: {e}"))?;
+    let _ = handle.disconnect(Disconnect::ByApplication, "", "en").await;
+    let fingerprint = presented.ok_or_else(|| format!("未收到主机 {host_id} 的密钥"))?;
+    Ok(SshHostKey {
+        known_fingerprint: load_known_hosts(&app)?.remove(&host_id),
+        host_id,
+        fingerprint,
+    })
+}
+
+/// Remember `fingerprint` as the host key of `host:port` once the user
+/// confirmed it.
+#[tauri::command]
+pub fn trust_ssh_host_key(
+    app: AppHandle,
+    host: String,
+    port: Option<u16>,
+    fingerprint: String,
+) -> Result<(), String> {
+    let fingerprint = fingerprint.trim();
+    if fingerprint.is_empty() {
+        return Err("指纹不能为空".into());
+    }
+    let host_id = SshTarget::for_host(host.trim().to_string(), port).host_id();
+    let mut known_hosts = load_known_hosts(&app)?;
+    eprintln!(
+        "[truidide::ssh] 记录主机 {} 的密钥指纹 {}",
+        host_id, fingerprint
+    );
+    known_hosts.insert(host_id, fingerprint.to_string());
+    save_known_hosts(&app, &known_hosts)
+}
+
+/// Forget the remembered host key of `host:port`, e.g. after the server was
+/// legitimately reinstalled.
+#[tauri::command]
+pub fn forget_ssh_host_key(app: AppHandle, host: String, port: Option<u16>) -> Result<(), String> {
+    let host_id = SshTarget::for_host(host, port).host_id();
+    let mut known_hosts = load_known_hosts(&app)?;
+    if known_hosts.remove(&host_id).is_some() {
+        save_known_hosts(&app, &known_hosts)?;
+    }
+    Ok(())
+}
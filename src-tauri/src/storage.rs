@@ -0,0 +1,184 @@
+use std::fs;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use tauri::path::BaseDirectory;
+use tauri::{AppHandle, Manager};
+
+use crate::environment::disk_usage;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StorageCategory {
+    Projects,
+    /// The proot assets and rootfs, without interrupted downloads.
+    Environment,
+    Plugins,
+    /// Interrupted proot downloads, plugin imports and snapshot exports.
+    TempDownloads,
+    /// LSP traffic logs.
+    Logs,
+    Recordings,
+}
+
+impl StorageCategory {
+    const ALL: [StorageCategory; 6] = [
+        Self::Projects,
+        Self::Environment,
+        Self::Plugins,
+        Self::TempDownloads,
+        Self::Logs,
+        Self::Recordings,
+    ];
+
+    /// Whether `clean_caches` may delete it; the rest is user data.
+    fn cleanable(self) -> bool {
+        matches!(self, Self::TempDownloads | Self::Logs)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageUsage {
+    pub category: StorageCategory,
+    pub bytes: u64,
+    pub cleanable: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageBreakdown {
+    pub total: u64,
+    pub categories: Vec<StorageUsage>,
+}
+
+fn resolve(app: &AppHandle, path: &str, base: BaseDirectory) -> Result<PathBuf, String> {
+    app.path().resolve(path, base).map_err(|e| e.to_string())
+}
+
+// Directories owned by other modules, resolved the same way they do.
+fn category_dirs(app: &AppHandle, category: StorageCategory) -> Result<Vec<PathBuf>, String> {
+    Ok(match category {
+        StorageCategory::Projects => vec![resolve(app, "projects", BaseDirectory::AppData)?],
+        StorageCategory::Environment => environment_dirs(app)?,
+        StorageCategory::Plugins => vec![
+            resolve(app, "plugins", BaseDirectory::AppData)?,
+            resolve(app, "builtin-plugins", BaseDirectory::AppData)?,
+        ],
+        StorageCategory::TempDownloads => {
+            let mut dirs = vec![resolve(app, "plugin_import_temp", BaseDirectory::Cache)?];
+            dirs.extend(proot_temp_dirs(app)?);
+            dirs
+        }
+        StorageCategory::Logs => vec![resolve(app, "lsp-logs", BaseDirectory::AppData)?],
+        StorageCategory::Recordings => vec![resolve(app, "recordings", BaseDirectory::AppData)?],
+    })
+}
+
+#[cfg(target_os = "android")]
+fn environment_dirs(app: &AppHandle) -> Result<Vec<PathBuf>, String> {
+    let base_dir = crate::android::proot::proot_base_dir(app)?;
+    Ok(vec![base_dir.with_file_name("proot-home"), base_dir])
+}
+
+#[cfg(not(target_os = "android"))]
+fn environment_dirs(_app: &AppHandle) -> Result<Vec<PathBuf>, String> {
+    Ok(Vec::new())
+}
+
+#[cfg(target_os = "android")]
+fn proot_temp_dirs(app: &AppHandle) -> Result<Vec<PathBuf>, String> {
+    crate::android::proot::temp_download_dirs(app)
+}
+
+#[cfg(not(target_os = "android"))]
+fn proot_temp_dirs(_app: &AppHandle) -> Result<Vec<PathBuf>, String> {
+    Ok(Vec::new())
+}
+
+fn category_usage(app: &AppHandle, category: StorageCategory) -> Result<u64, String> {
+    let bytes: u64 = category_dirs(app, category)?
+        .iter()
+        .map(|dir| disk_usage(dir))
+        .sum();
+    // Interrupted downloads sit inside the proot directory but are reported
+    // on their own.
+    if category == StorageCategory::Environment {
+        let temp: u64 = proot_temp_dirs(app)?
+            .iter()
+            .map(|dir| disk_usage(dir))
+            .sum();
+        return Ok(bytes.saturating_sub(temp));
+    }
+    Ok(bytes)
+}
+
+#[tauri::command]
+pub async fn get_storage_breakdown(app: AppHandle) -> Result<StorageBreakdown, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let categories = StorageCategory::ALL
+            .into_iter()
+            .map(|category| {
+                Ok(StorageUsage {
+                    category,
+                    bytes: category_usage(&app, category)?,
+                    cleanable: category.cleanable(),
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(StorageBreakdown {
+            total: categories.iter().map(|usage| usage.bytes).sum(),
+            categories,
+        })
+    })
+    .await
+    .map_err(|e| format!("后台任务执行失败: {e}"))?
+}
+
+/// Delete the given cleanable categories and return the bytes freed.
+#[tauri::command]
+pub async fn clean_caches(app: AppHandle, categories: Vec<StorageCategory>) -> Result<u64, String> {
+    if let Some(category) = categories.iter().find(|category| !category.cleanable()) {
+        return Err(format!("{category:?} 中是用户数据，不能作为缓存清理"));
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut freed = 0;
+        for category in categories {
+            freed += clean_category(&app, category)?;
+        }
+        Ok(freed)
+    })
+    .await
+    .map_err(|e| format!("后台任务执行失败: {e}"))?
+}
+
+fn clean_category(app: &AppHandle, category: StorageCategory) -> Result<u64, String> {
+    let dirs = category_dirs(app, category)?;
+    // A running download uses the proot temp directories, so they are
+    // cleared under the download lock.
+    #[cfg(target_os = "android")]
+    let (dirs, mut freed) = if category == StorageCategory::TempDownloads {
+        let freed = crate::android::proot::clear_temp_downloads(app)?;
+        let locked = proot_temp_dirs(app)?;
+        let rest = dirs
+            .into_iter()
+            .filter(|dir| !locked.contains(dir))
+            .collect();
+        (rest, freed)
+    } else {
+        (dirs, 0)
+    };
+    #[cfg(not(target_os = "android"))]
+    let mut freed = 0;
+
+    for dir in dirs {
+        if !dir.exists() {
+            continue;
+        }
+        let size = disk_usage(&dir);
+        fs::remove_dir_all(&dir)
+            .map_err(|e| format!("删除 {} 失败: {e}", dir.to_string_lossy()))?;
+        freed += size;
+    }
+    Ok(freed)
+}
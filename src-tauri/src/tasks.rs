@@ -0,0 +1,466 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+use uuid::Uuid;
+
+use crate::exec::{self, OutputStream, RunCommandArgs};
+use crate::hooks::{self, HookContext, HookEvent};
+use crate::notifications;
+
+const TASKS_FILE: &str = ".truidide/tasks.json";
+const EVENT_TASK_OUTPUT: &str = "truidide://tasks/output";
+const EVENT_TASK_FINISHED: &str = "truidide://tasks/finished";
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TasksFile {
+    #[serde(default)]
+    pub tasks: Vec<TaskDefinition>,
+    #[serde(default)]
+    pub run_configurations: Vec<RunConfiguration>,
+}
+
+/// What the Run button launches. Declared in `tasks.json` or inferred from the project layout.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunConfiguration {
+    pub id: String,
+    #[serde(flatten)]
+    pub task: TaskDefinition,
+    #[serde(default)]
+    pub default: bool,
+    /// Whether the entry was inferred rather than read from `tasks.json`.
+    #[serde(default, skip_deserializing)]
+    pub detected: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskDefinition {
+    pub name: String,
+    /// Shell command line, executed with `sh -c` (`cmd /C` on Windows).
+    pub command: String,
+    /// Working directory relative to the project root.
+    #[serde(default)]
+    pub cwd: Option<String>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    #[serde(default)]
+    pub problem_matcher: Option<ProblemMatcher>,
+}
+
+/// Regex applied to every output line; capture group indices select the fields.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProblemMatcher {
+    pub pattern: String,
+    #[serde(default = "default_file_group")]
+    pub file: usize,
+    #[serde(default = "default_line_group")]
+    pub line: usize,
+    #[serde(default)]
+    pub column: Option<usize>,
+    #[serde(default)]
+    pub severity: Option<usize>,
+    /// Group holding the message; the whole line is used when absent.
+    #[serde(default)]
+    pub message: Option<usize>,
+}
+
+fn default_file_group() -> usize {
+    1
+}
+
+fn default_line_group() -> usize {
+    2
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiagnosticSeverity {
+    Error,
+    Warning,
+    Info,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskDiagnostic {
+    /// File path as seen by the editor (joined with the task cwd when relative).
+    pub file: String,
+    pub line: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<u32>,
+    pub severity: DiagnosticSeverity,
+    pub message: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunTaskArgs {
+    pub project_path: String,
+    pub task_name: String,
+    #[serde(default)]
+    pub run_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskRunResult {
+    pub run_id: String,
+    pub task_name: String,
+    pub exit_code: Option<i32>,
+    pub success: bool,
+    pub timed_out: bool,
+    pub cancelled: bool,
+    pub diagnostics: Vec<TaskDiagnostic>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TaskOutputPayload<'a> {
+    run_id: &'a str,
+    task_name: &'a str,
+    stream: OutputStream,
+    data: &'a str,
+}
+
+struct CompiledMatcher {
+    regex: Regex,
+    spec: ProblemMatcher,
+}
+
+impl CompiledMatcher {
+    fn new(spec: &ProblemMatcher) -> Result<Self, String> {
+        let regex = Regex::new(&spec.pattern).map_err(|e| format!("问题匹配器正则无效: {e}"))?;
+        Ok(Self {
+            regex,
+            spec: spec.clone(),
+        })
+    }
+
+    fn parse(&self, line: &str, cwd: &str) -> Option<TaskDiagnostic> {
+        let line = line.trim_end_matches(['\r', '\n']);
+        let captures = self.regex.captures(line)?;
+        let group = |index: usize| captures.get(index).map(|m| m.as_str().trim());
+
+        let file = group(self.spec.file).filter(|file| !file.is_empty())?;
+        let line_number = group(self.spec.line)?.parse().ok()?;
+        let column = self
+            .spec
+            .column
+            .and_then(group)
+            .and_then(|raw| raw.parse().ok());
+        let severity = match self.spec.severity.and_then(group) {
+            Some(raw) => parse_severity(raw),
+            None => DiagnosticSeverity::Error,
+        };
+        let message = self
+            .spec
+            .message
+            .and_then(group)
+            .unwrap_or(line)
+            .to_string();
+
+        Some(TaskDiagnostic {
+            file: join_path(cwd, file),
+            line: line_number,
+            column,
+            severity,
+            message,
+        })
+    }
+}
+
+fn parse_severity(raw: &str) -> DiagnosticSeverity {
+    let lower = raw.to_ascii_lowercase();
+    if lower.starts_with("warn") {
+        DiagnosticSeverity::Warning
+    } else if lower.starts_with("info") || lower.starts_with("note") || lower.starts_with("hint") {
+        DiagnosticSeverity::Info
+    } else {
+        DiagnosticSeverity::Error
+    }
+}
+
+fn is_absolute(path: &str) -> bool {
+    path.starts_with('/') || PathBuf::from(path).is_absolute()
+}
+
+fn join_path(base: &str, relative: &str) -> String {
+    if is_absolute(relative) {
+        return relative.to_string();
+    }
+    PathBuf::from(base)
+        .join(relative)
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[cfg(target_os = "android")]
+pub(crate) fn project_host_dir(app: &AppHandle, project_path: &str) -> Result<PathBuf, String> {
+    crate::android::proot::resolve_guest_path(app, project_path)
+}
+
+#[cfg(not(target_os = "android"))]
+pub(crate) fn project_host_dir(_app: &AppHandle, project_path: &str) -> Result<PathBuf, String> {
+    Ok(PathBuf::from(project_path))
+}
+
+fn load_tasks(app: &AppHandle, project_path: &str) -> Result<TasksFile, String> {
+    let path = project_host_dir(app, project_path)?.join(TASKS_FILE);
+    if !path.exists() {
+        return Ok(TasksFile::default());
+    }
+    let data = fs::read_to_string(&path).map_err(|e| format!("读取任务配置失败: {e}"))?;
+    serde_json::from_str(&data).map_err(|e| format!("解析任务配置失败: {e}"))
+}
+
+/// Add `configuration` to the project's `tasks.json` unless one with the same id exists.
+///
+/// Returns whether the file was changed.
+pub(crate) fn ensure_run_configuration(
+    app: &AppHandle,
+    project_path: &str,
+    configuration: RunConfiguration,
+) -> Result<bool, String> {
+    let mut file = load_tasks(app, project_path)?;
+    if file
+        .run_configurations
+        .iter()
+        .any(|existing| existing.id == configuration.id)
+    {
+        return Ok(false);
+    }
+    file.run_configurations.push(configuration);
+
+    let path = project_host_dir(app, project_path)?.join(TASKS_FILE);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("创建任务配置目录失败: {e}"))?;
+    }
+    let data =
+        serde_json::to_string_pretty(&file).map_err(|e| format!("序列化任务配置失败: {e}"))?;
+    fs::write(&path, data).map_err(|e| format!("保存任务配置失败: {e}"))?;
+    Ok(true)
+}
+
+pub(crate) fn shell_invocation(command: &str) -> (String, Vec<String>) {
+    if cfg!(windows) {
+        ("cmd".into(), vec!["/C".into(), command.into()])
+    } else {
+        ("sh".into(), vec!["-c".into(), command.into()])
+    }
+}
+
+#[tauri::command]
+pub fn list_tasks(app: AppHandle, project_path: String) -> Result<Vec<TaskDefinition>, String> {
+    Ok(load_tasks(&app, &project_path)?.tasks)
+}
+
+#[tauri::command]
+pub async fn run_task(app: AppHandle, args: RunTaskArgs) -> Result<TaskRunResult, String> {
+    let task = load_tasks(&app, &args.project_path)?
+        .tasks
+        .into_iter()
+        .find(|task| task.name == args.task_name)
+        .ok_or_else(|| format!("未找到任务 {}", args.task_name))?;
+
+    execute_task(&app, args.project_path, task, args.run_id).await
+}
+
+async fn execute_task(
+    app: &AppHandle,
+    project_path: String,
+    task: TaskDefinition,
+    run_id: Option<String>,
+) -> Result<TaskRunResult, String> {
+    let matcher = task
+        .problem_matcher
+        .as_ref()
+        .map(CompiledMatcher::new)
+        .transpose()?;
+    let cwd = match task.cwd.as_deref().filter(|cwd| !cwd.trim().is_empty()) {
+        Some(cwd) => join_path(&project_path, cwd),
+        None => project_path.clone(),
+    };
+    let run_id = run_id
+        .filter(|id| !id.trim().is_empty())
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+    let (program, shell_args) = shell_invocation(&task.command);
+    let diagnostics = Arc::new(Mutex::new(Vec::new()));
+    let started = Instant::now();
+
+    let emitter = app.clone();
+    let sink_run_id = run_id.clone();
+    let sink_task_name = task.name.clone();
+    let sink_cwd = cwd.clone();
+    let sink_diagnostics = diagnostics.clone();
+    let outcome = exec::execute(
+        app,
+        RunCommandArgs {
+            run_id: Some(run_id.clone()),
+            cwd,
+            program,
+            args: shell_args,
+            env: task.env.clone(),
+            timeout_secs: task.timeout_secs,
+        },
+        move |stream, data| {
+            let _ = emitter.emit(
+                EVENT_TASK_OUTPUT,
+                TaskOutputPayload {
+                    run_id: &sink_run_id,
+                    task_name: &sink_task_name,
+                    stream,
+                    data,
+                },
+            );
+            if let Some(diagnostic) = matcher
+                .as_ref()
+                .and_then(|matcher| matcher.parse(data, &sink_cwd))
+            {
+                if let Ok(mut diagnostics) = sink_diagnostics.lock() {
+                    diagnostics.push(diagnostic);
+                }
+            }
+        },
+    )
+    .await?;
+
+    let diagnostics = diagnostics
+        .lock()
+        .map(|diagnostics| diagnostics.clone())
+        .unwrap_or_default();
+    let result = TaskRunResult {
+        run_id,
+        task_name: task.name.clone(),
+        exit_code: outcome.exit_code,
+        success: outcome.success,
+        timed_out: outcome.timed_out,
+        cancelled: outcome.cancelled,
+        diagnostics,
+    };
+
+    if let Err(err) = app.emit(EVENT_TASK_FINISHED, &result) {
+        eprintln!("[truidide::tasks] 广播任务结果失败: {}", err);
+    }
+    if !result.cancelled {
+        notifications::task_finished(app, &task.name, result.success, started.elapsed());
+    }
+
+    hooks::spawn_event(
+        app,
+        HookEvent::TaskFinish,
+        HookContext {
+            project_path: Some(project_path),
+            file_path: None,
+            task_name: Some(task.name),
+        },
+    );
+
+    Ok(result)
+}
+
+// Entry points recognised when a project declares no run configuration,
+// checked in order; the first match becomes the default.
+const DETECTED_RUN_CONFIGURATIONS: &[(&str, &str, &str, &str)] = &[
+    (
+        "python-server",
+        "server.py",
+        "启动预览服务器",
+        "python3 server.py",
+    ),
+    ("python-main", "main.py", "运行 main.py", "python3 main.py"),
+    ("npm-start", "package.json", "npm start", "npm start"),
+    ("cargo-run", "Cargo.toml", "cargo run", "cargo run"),
+    ("go-run", "go.mod", "go run .", "go run ."),
+];
+
+fn detect_run_configurations(project_dir: &Path) -> Vec<RunConfiguration> {
+    DETECTED_RUN_CONFIGURATIONS
+        .iter()
+        .filter(|(_, marker, _, _)| project_dir.join(marker).is_file())
+        .map(|(id, _, name, command)| RunConfiguration {
+            id: (*id).to_string(),
+            task: TaskDefinition {
+                name: (*name).to_string(),
+                command: (*command).to_string(),
+                cwd: None,
+                env: HashMap::new(),
+                timeout_secs: None,
+                problem_matcher: None,
+            },
+            default: false,
+            detected: true,
+        })
+        .collect()
+}
+
+fn load_run_configurations(
+    app: &AppHandle,
+    project_path: &str,
+) -> Result<Vec<RunConfiguration>, String> {
+    let mut configurations = load_tasks(app, project_path)?.run_configurations;
+    if configurations.is_empty() {
+        configurations = detect_run_configurations(&project_host_dir(app, project_path)?);
+    }
+    if !configurations.iter().any(|config| config.default) {
+        if let Some(first) = configurations.first_mut() {
+            first.default = true;
+        }
+    }
+    Ok(configurations)
+}
+
+#[tauri::command]
+pub fn get_run_configurations(
+    app: AppHandle,
+    project_path: String,
+) -> Result<Vec<RunConfiguration>, String> {
+    load_run_configurations(&app, &project_path)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunConfigurationArgs {
+    pub project_path: String,
+    /// Configuration to run; the default one when omitted.
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub run_id: Option<String>,
+}
+
+#[tauri::command]
+pub async fn run_configuration(
+    app: AppHandle,
+    args: RunConfigurationArgs,
+) -> Result<TaskRunResult, String> {
+    let configurations = load_run_configurations(&app, &args.project_path)?;
+    let configuration = match args.id.as_deref() {
+        Some(id) => configurations
+            .into_iter()
+            .find(|config| config.id == id)
+            .ok_or_else(|| format!("未找到运行配置 {id}"))?,
+        None => configurations
+            .into_iter()
+            .find(|config| config.default)
+            .ok_or_else(|| "项目没有可用的运行配置".to_string())?,
+    };
+
+    execute_task(&app, args.project_path, configuration.task, args.run_id).await
+}
+
+#[tauri::command]
+pub fn stop_task(run_id: String) -> Result<(), String> {
+    exec::cancel_command(run_id)
+}
@@ -1,44 +1,142 @@
+use crate::history::CommandLineTracker;
+use crate::keep_alive::KeepAliveGuard;
+use crate::process_stats::{ProcessTable, ProcessTreeStats};
+use base64::prelude::*;
 use once_cell::sync::OnceCell;
-use portable_pty::{native_pty_system, CommandBuilder, PtySize};
+use portable_pty::{native_pty_system, ChildKiller, CommandBuilder, MasterPty, PtySize};
+use regex::{Regex, RegexBuilder};
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet, VecDeque};
-use std::io::prelude::*;
-use std::path::PathBuf;
+use std::io::{prelude::*, BufWriter};
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Mutex;
-use std::thread;
-use tauri::{Emitter, Manager};
-
-static SESSIONS: OnceCell<
-    Mutex<
-        HashMap<
-            String,
-            (
-                Box<dyn portable_pty::MasterPty + Send>,
-                Box<dyn Write + Send>,
-                Box<dyn portable_pty::Child + Send>,
-            ),
-        >,
-    >,
-> = OnceCell::new();
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::async_runtime::JoinHandle;
+use tauri::path::BaseDirectory;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::{mpsc, oneshot, RwLock};
+use uuid::Uuid;
+
+// Bounded channel sizes: a full output channel blocks the pty reader, which
+// in turn makes the kernel apply backpressure to the child process.
+const OUTPUT_CHANNEL_CAPACITY: usize = 64;
+const INPUT_CHANNEL_CAPACITY: usize = 64;
+const COMMAND_CHANNEL_CAPACITY: usize = 64;
+// How often an actor checks whether it has been idle for too long.
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+const SAVED_BUFFERS_DIR: &str = "terminal-buffers";
+// Saved buffers nobody collected within this time are deleted on startup.
+const SAVED_BUFFER_MAX_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+const RECORDINGS_DIR: &str = "recordings";
+const DEFAULT_SEARCH_LIMIT: usize = 1000;
+const DEFAULT_STATS_INTERVAL_SECS: u64 = 10;
+// Pastes are written in pieces so one call never floods the pty.
+const PASTE_CHUNK_BYTES: usize = 4096;
+const BRACKETED_PASTE_ON: &str = "\x1b[?2004h";
+const BRACKETED_PASTE_OFF: &str = "\x1b[?2004l";
+const PASTE_START: &str = "\x1b[200~";
+const PASTE_END: &str = "\x1b[201~";
+// Once the frontend acknowledges rendered output, pty reads stop while more
+// than HIGH_WATERMARK bytes are in flight and resume below LOW_WATERMARK.
+const FLOW_HIGH_WATERMARK: usize = 512 * 1024;
+const FLOW_LOW_WATERMARK: usize = 128 * 1024;
+
+static SESSIONS: OnceCell<RwLock<HashMap<String, SessionHandle>>> = OnceCell::new();
 static SESSION_COUNTER: AtomicU64 = AtomicU64::new(1);
+static STATS_MONITOR: OnceCell<()> = OnceCell::new();
 
-fn sessions_map() -> &'static Mutex<
-    HashMap<
-        String,
-        (
-            Box<dyn portable_pty::MasterPty + Send>,
-            Box<dyn Write + Send>,
-            Box<dyn portable_pty::Child + Send>,
-        ),
-    >,
-> {
-    SESSIONS.get_or_init(|| Mutex::new(HashMap::new()))
+fn sessions_map() -> &'static RwLock<HashMap<String, SessionHandle>> {
+    SESSIONS.get_or_init(|| RwLock::new(HashMap::new()))
 }
 
-fn generate_session_id() -> String {
+fn generate_session_id() -> (String, u64) {
     let n = SESSION_COUNTER.fetch_add(1, Ordering::SeqCst);
-    format!("s{}", n)
+    (format!("s{}", n), n)
+}
+
+// Cheap, cloneable handle to a running session actor. All pty state is owned
+// by the actor task; callers talk to it exclusively through `tx`.
+#[derive(Clone)]
+struct SessionHandle {
+    order: u64,
+    cwd: String,
+    cwd_key: String,
+    pid: Option<u32>,
+    created_at_secs: u64,
+    /// Started with the project's sandbox restrictions applied.
+    sandboxed: bool,
+    tx: mpsc::Sender<SessionCommand>,
+}
+
+enum SessionCommand {
+    Input(Vec<u8>),
+    Paste(String),
+    Resize {
+        cols: u16,
+        rows: u16,
+        reply: oneshot::Sender<Result<(), String>>,
+    },
+    Attach {
+        label: String,
+        reply: oneshot::Sender<Vec<TerminalOutput>>,
+    },
+    Detach {
+        label: String,
+    },
+    SetTitle(Option<String>),
+    ClearBuffer,
+    /// Bytes of output the frontend has finished rendering.
+    Ack(usize),
+    SetPaused(bool),
+    StartRecording {
+        path: PathBuf,
+        reply: oneshot::Sender<Result<(), String>>,
+    },
+    StopRecording {
+        reply: oneshot::Sender<Option<PathBuf>>,
+    },
+    SshConnection {
+        reply: oneshot::Sender<Option<crate::ssh::SshConnection>>,
+    },
+    Signal {
+        signal: String,
+        reply: oneshot::Sender<Result<(), String>>,
+    },
+    Snapshot {
+        reply: oneshot::Sender<Vec<TerminalOutput>>,
+    },
+    Search {
+        pattern: Regex,
+        limit: usize,
+        reply: oneshot::Sender<Vec<TerminalSearchMatch>>,
+    },
+    Describe {
+        reply: oneshot::Sender<SessionDescription>,
+    },
+    Stop {
+        /// Emit `terminal-exit-{id}` so attached tabs notice the shutdown.
+        notify: bool,
+        reply: oneshot::Sender<()>,
+    },
+}
+
+const DEFAULT_SCROLLBACK_BYTES: usize = 1024 * 1024;
+
+// Limits applied to the replay buffer of a session. Bytes are always
+// enforced; the line limit is optional and counts newline characters.
+#[derive(Clone, Copy)]
+struct ScrollbackLimits {
+    max_bytes: usize,
+    max_lines: Option<usize>,
+}
+
+impl Default for ScrollbackLimits {
+    fn default() -> Self {
+        Self {
+            max_bytes: DEFAULT_SCROLLBACK_BYTES,
+            max_lines: None,
+        }
+    }
 }
 
 // Per-session aggregated state: keeps the incremental sequence,
@@ -47,9 +145,57 @@ fn generate_session_id() -> String {
 struct SessionState {
     seq: u64,
     buffer: VecDeque<TerminalOutput>,
+    buffer_bytes: usize,
+    buffer_lines: usize,
+    limits: ScrollbackLimits,
     subscribers: HashSet<String>,
     title: Option<String>,
-    cwd: String,
+    /// Working directory last reported by the shell through OSC 7.
+    current_cwd: Option<String>,
+    flow: FlowControl,
+}
+
+// Decides whether the actor keeps draining the pty. While reads are
+// suspended the bounded output channel fills up and the reader thread
+// blocks, so the kernel throttles the child process.
+#[derive(Clone, Default)]
+struct FlowControl {
+    /// Paused explicitly through `pause_terminal_output`.
+    paused: bool,
+    /// Set by the first acknowledgement; frontends that never ack are not throttled.
+    acks_enabled: bool,
+    unacked_bytes: usize,
+    throttled: bool,
+}
+
+impl FlowControl {
+    fn reading(&self) -> bool {
+        !self.paused && !self.throttled
+    }
+
+    fn on_emit(&mut self, bytes: usize) {
+        if !self.acks_enabled {
+            return;
+        }
+        self.unacked_bytes = self.unacked_bytes.saturating_add(bytes);
+        if self.unacked_bytes > FLOW_HIGH_WATERMARK {
+            self.throttled = true;
+        }
+    }
+
+    fn on_ack(&mut self, bytes: usize) {
+        self.acks_enabled = true;
+        self.unacked_bytes = self.unacked_bytes.saturating_sub(bytes);
+        if self.unacked_bytes <= FLOW_LOW_WATERMARK {
+            self.throttled = false;
+        }
+    }
+
+    // Nothing is in flight towards a window that just (de)attached.
+    fn reset_in_flight(&mut self) {
+        self.unacked_bytes = 0;
+        self.throttled = false;
+    }
 }
 
 impl Default for SessionState {
@@ -57,128 +203,1498 @@ impl Default for SessionState {
         Self {
             seq: 0,
             buffer: VecDeque::new(),
+            buffer_bytes: 0,
+            buffer_lines: 0,
+            limits: ScrollbackLimits::default(),
             subscribers: HashSet::new(),
             title: None,
-            cwd: String::new(),
+            current_cwd: None,
+            flow: FlowControl::default(),
         }
     }
 }
 
-#[derive(Clone, Serialize)]
+impl SessionState {
+    // Append a chunk of output to the replay buffer and evict the oldest
+    // chunks until the buffer fits within the configured limits again.
+    fn push_output(&mut self, data: String) -> TerminalOutput {
+        self.seq = self.seq.saturating_add(1);
+        let out = TerminalOutput {
+            seq: self.seq,
+            data,
+        };
+        self.buffer_bytes += out.data.len();
+        self.buffer_lines += count_lines(&out.data);
+        self.buffer.push_back(out.clone());
+
+        while self.buffer.len() > 1 && self.exceeds_limits() {
+            if let Some(evicted) = self.buffer.pop_front() {
+                self.buffer_bytes -= evicted.data.len();
+                self.buffer_lines -= count_lines(&evicted.data);
+            }
+        }
+
+        out
+    }
+
+    fn exceeds_limits(&self) -> bool {
+        self.buffer_bytes > self.limits.max_bytes
+            || self
+                .limits
+                .max_lines
+                .is_some_and(|max| self.buffer_lines > max)
+    }
+
+    fn clear_buffer(&mut self) {
+        self.buffer.clear();
+        self.buffer_bytes = 0;
+        self.buffer_lines = 0;
+    }
+}
+
+// Follows DECSET/DECRST 2004 in the output to know whether the application
+// wants pastes wrapped in bracketed-paste markers.
+#[derive(Default)]
+struct BracketedPasteMode {
+    enabled: bool,
+    /// Tail of the previous chunk, in case a sequence straddles two reads.
+    tail: String,
+}
+
+impl BracketedPasteMode {
+    fn feed(&mut self, data: &str) {
+        let mut window = std::mem::take(&mut self.tail);
+        window.push_str(data);
+        let on = window.rfind(BRACKETED_PASTE_ON);
+        let off = window.rfind(BRACKETED_PASTE_OFF);
+        match (on, off) {
+            (Some(on), Some(off)) => self.enabled = on > off,
+            (Some(_), None) => self.enabled = true,
+            (None, Some(_)) => self.enabled = false,
+            (None, None) => {}
+        }
+        let keep = BRACKETED_PASTE_ON.len() - 1;
+        let mut start = window.len().saturating_sub(keep);
+        while !window.is_char_boundary(start) {
+            start += 1;
+        }
+        self.tail = window.split_off(start);
+    }
+}
+
+// Prepare pasted text for the pty: newlines become carriage returns like a
+// typed Enter, and with bracketed paste the content is wrapped in markers.
+// An embedded end marker is stripped so the paste cannot break out early.
+fn prepare_paste(text: &str, bracketed: bool) -> String {
+    let body = text.replace("\r\n", "\r").replace('\n', "\r");
+    if bracketed {
+        format!("{PASTE_START}{}{PASTE_END}", body.replace(PASTE_END, ""))
+    } else {
+        body
+    }
+}
+
+// Split `data` into pieces of at most `max` bytes without cutting a character.
+fn chunk_str(data: &str, max: usize) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut rest = data;
+    while !rest.is_empty() {
+        let mut end = rest.len().min(max);
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        if end == 0 {
+            end = rest
+                .chars()
+                .next()
+                .map(char::len_utf8)
+                .unwrap_or(rest.len());
+        }
+        let (chunk, tail) = rest.split_at(end);
+        chunks.push(chunk);
+        rest = tail;
+    }
+    chunks
+}
+
+// Writes session output as an asciicast v2 file: a JSON header line followed
+// by one `[elapsed, code, data]` event per line.
+struct Recorder {
+    path: PathBuf,
+    writer: BufWriter<std::fs::File>,
+    started: Instant,
+}
+
+impl Recorder {
+    fn create(path: PathBuf, size: PtySize, title: Option<&str>) -> Result<Self, String> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("创建录制目录失败: {e}"))?;
+        }
+        let file = std::fs::File::create(&path).map_err(|e| format!("创建录制文件失败: {e}"))?;
+        let mut header = serde_json::json!({
+            "version": 2,
+            "width": size.cols,
+            "height": size.rows,
+            "timestamp": SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or_default(),
+            "env": { "TERM": "xterm-256color" },
+        });
+        if let Some(title) = title {
+            header["title"] = title.into();
+        }
+        let mut recorder = Self {
+            path,
+            writer: BufWriter::new(file),
+            started: Instant::now(),
+        };
+        recorder.write_line(&header)?;
+        Ok(recorder)
+    }
+
+    fn write_line(&mut self, value: &serde_json::Value) -> Result<(), String> {
+        serde_json::to_writer(&mut self.writer, value)
+            .map_err(|e| e.to_string())
+            .and_then(|_| self.writer.write_all(b"\n").map_err(|e| e.to_string()))
+            .map_err(|e| format!("写入录制文件失败: {e}"))
+    }
+
+    fn event(&mut self, code: &str, data: &str) -> Result<(), String> {
+        let elapsed = self.started.elapsed().as_secs_f64();
+        self.write_line(&serde_json::json!([elapsed, code, data]))
+    }
+
+    fn finish(mut self) -> PathBuf {
+        if let Err(err) = self.writer.flush() {
+            eprintln!("[truidide::terminal] 写入录制文件失败: {}", err);
+        }
+        self.path
+    }
+}
+
+// Decode a chunk of pty output, carrying an incomplete trailing UTF-8
+// sequence over to the next read instead of replacing it with U+FFFD.
+fn decode_utf8_chunk(pending: &mut Vec<u8>, chunk: &[u8]) -> String {
+    pending.extend_from_slice(chunk);
+    let mut out = String::with_capacity(pending.len());
+    let mut rest: &[u8] = pending;
+
+    loop {
+        match std::str::from_utf8(rest) {
+            Ok(valid) => {
+                out.push_str(valid);
+                rest = &[];
+                break;
+            }
+            Err(err) => {
+                let valid_up_to = err.valid_up_to();
+                // SAFETY: `from_utf8` verified the prefix up to `valid_up_to`.
+                out.push_str(unsafe { std::str::from_utf8_unchecked(&rest[..valid_up_to]) });
+                match err.error_len() {
+                    Some(len) => {
+                        out.push(char::REPLACEMENT_CHARACTER);
+                        rest = &rest[valid_up_to + len..];
+                    }
+                    None => {
+                        rest = &rest[valid_up_to..];
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    *pending = rest.to_vec();
+    out
+}
+
+// Large enough for base64-encoded OSC 52 clipboard payloads.
+const MAX_OSC_LEN: usize = 1024 * 1024;
+
+enum OscState {
+    Ground,
+    Escape,
+    Osc(String),
+    OscEscape(String),
+}
+
+// Incremental scanner for OSC sequences (`ESC ] Ps ; Pt BEL` or `... ESC \`)
+// in decoded pty output. Sequences may span several reads; the output itself
+// is forwarded untouched so the frontend terminal still sees them.
+struct OscParser {
+    state: OscState,
+}
+
+impl Default for OscParser {
+    fn default() -> Self {
+        Self {
+            state: OscState::Ground,
+        }
+    }
+}
+
+impl OscParser {
+    // Returns `(Ps, Pt)` for every sequence terminated within `data`.
+    fn feed(&mut self, data: &str) -> Vec<(u32, String)> {
+        let mut sequences = Vec::new();
+        for ch in data.chars() {
+            let state = std::mem::replace(&mut self.state, OscState::Ground);
+            self.state = match state {
+                OscState::Ground | OscState::Escape if ch == '\x1b' => OscState::Escape,
+                OscState::Ground => OscState::Ground,
+                OscState::Escape if ch == ']' => OscState::Osc(String::new()),
+                OscState::Escape => OscState::Ground,
+                OscState::Osc(body) if ch == '\x07' || ch == '\u{9c}' => {
+                    sequences.extend(split_osc(&body));
+                    OscState::Ground
+                }
+                OscState::Osc(body) if ch == '\x1b' => OscState::OscEscape(body),
+                OscState::Osc(mut body) => {
+                    body.push(ch);
+                    if body.len() > MAX_OSC_LEN {
+                        OscState::Ground
+                    } else {
+                        OscState::Osc(body)
+                    }
+                }
+                OscState::OscEscape(body) if ch == '\\' => {
+                    sequences.extend(split_osc(&body));
+                    OscState::Ground
+                }
+                // Any other escape aborts the sequence and may start a new one.
+                OscState::OscEscape(_) if ch == ']' => OscState::Osc(String::new()),
+                OscState::OscEscape(_) if ch == '\x1b' => OscState::Escape,
+                OscState::OscEscape(_) => OscState::Ground,
+            };
+        }
+        sequences
+    }
+}
+
+fn split_osc(body: &str) -> Option<(u32, String)> {
+    let (code, payload) = body.split_once(';').unwrap_or((body, ""));
+    Some((code.parse().ok()?, payload.to_string()))
+}
+
+const TERMINAL_SETTINGS_FILENAME: &str = "terminal-settings.json";
+const DEFAULT_CLIPBOARD_MAX_BYTES: usize = 100 * 1024;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TerminalSettings {
+    /// Let programs copy to the system clipboard with OSC 52.
+    #[serde(default = "default_clipboard_enabled")]
+    pub osc52_enabled: bool,
+    /// Largest decoded OSC 52 payload accepted, in bytes.
+    #[serde(default = "default_clipboard_max_bytes")]
+    pub osc52_max_bytes: usize,
+    /// Stop sessions nobody is attached to after this many minutes without
+    /// input or output. Disabled when unset.
+    #[serde(default)]
+    pub idle_timeout_minutes: Option<u64>,
+    /// Seconds between `terminal-session-stats` events; 0 turns them off.
+    #[serde(default = "default_stats_interval_secs")]
+    pub stats_interval_secs: u64,
+}
+
+fn default_clipboard_enabled() -> bool {
+    true
+}
+
+fn default_clipboard_max_bytes() -> usize {
+    DEFAULT_CLIPBOARD_MAX_BYTES
+}
+
+fn default_stats_interval_secs() -> u64 {
+    DEFAULT_STATS_INTERVAL_SECS
+}
+
+impl Default for TerminalSettings {
+    fn default() -> Self {
+        Self {
+            osc52_enabled: default_clipboard_enabled(),
+            osc52_max_bytes: default_clipboard_max_bytes(),
+            idle_timeout_minutes: None,
+            stats_interval_secs: default_stats_interval_secs(),
+        }
+    }
+}
+
+fn terminal_settings_path(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .resolve(TERMINAL_SETTINGS_FILENAME, BaseDirectory::AppData)
+        .map_err(|e| e.to_string())
+}
+
+fn saved_buffers_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .resolve(SAVED_BUFFERS_DIR, BaseDirectory::AppData)
+        .map_err(|e| e.to_string())
+}
+
+// Keyed by a fresh id rather than the session id, which restarts at `s1` on
+// every launch and would collide with buffers saved by a previous run.
+fn saved_buffer_path(app: &AppHandle, buffer_id: &str) -> Result<PathBuf, String> {
+    Ok(saved_buffers_dir(app)?.join(format!("{buffer_id}.json")))
+}
+
+/// Delete saved buffers that are too old to be collected, and any left in
+/// the session-id keyed layout of earlier versions.
+pub fn prune_saved_buffers(app: &AppHandle) {
+    let Ok(dir) = saved_buffers_dir(app) else {
+        return;
+    };
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let keyed_by_id = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .is_some_and(|stem| Uuid::parse_str(stem).is_ok());
+        let expired = entry
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age > SAVED_BUFFER_MAX_AGE);
+        if !keyed_by_id || expired {
+            if let Err(err) = std::fs::remove_file(&path) {
+                eprintln!(
+                    "[truidide::terminal] 删除过期输出缓冲 {} 失败: {}",
+                    path.display(),
+                    err
+                );
+            }
+        }
+    }
+}
+
+fn load_terminal_settings(app: &AppHandle) -> Result<TerminalSettings, String> {
+    let path = terminal_settings_path(app)?;
+    if !path.exists() {
+        return Ok(TerminalSettings::default());
+    }
+    let data = std::fs::read_to_string(&path).map_err(|e| format!("读取终端设置失败: {e}"))?;
+    serde_json::from_str(&data).map_err(|e| format!("解析终端设置失败: {e}"))
+}
+
+// Decode the `Pc;Pd` payload of an OSC 52 write. Queries (`Pd == "?"`) are
+// never answered so programs cannot read the clipboard back.
+fn parse_osc52_text(payload: &str, max_bytes: usize) -> Result<Option<String>, String> {
+    let (_, data) = payload.split_once(';').unwrap_or(("", payload));
+    if data == "?" {
+        return Ok(None);
+    }
+    // Every 4 base64 characters encode 3 bytes.
+    if data.len() / 4 * 3 > max_bytes {
+        return Err(format!("剪贴板内容超过 {max_bytes} 字节限制"));
+    }
+    let bytes = BASE64_STANDARD
+        .decode(data.trim())
+        .map_err(|e| format!("剪贴板内容不是有效的 base64: {e}"))?;
+    if bytes.len() > max_bytes {
+        return Err(format!("剪贴板内容超过 {max_bytes} 字节限制"));
+    }
+    Ok(Some(String::from_utf8_lossy(&bytes).into_owned()))
+}
+
+// Extract the path from an OSC 7 `file://host/path` URL.
+fn parse_osc7_path(payload: &str) -> Option<String> {
+    let rest = payload.strip_prefix("file://")?;
+    let path = &rest[rest.find('/')?..];
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+            if let Some(byte) = hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8(decoded).ok()
+}
+
+// Removes escape sequences from terminal output so searches only see the
+// printed text. Stateful because a sequence may straddle two chunks.
+#[derive(Default)]
+enum AnsiStripper {
+    #[default]
+    Ground,
+    Escape,
+    Csi,
+    Osc,
+    OscEscape,
+}
+
+impl AnsiStripper {
+    fn strip(&mut self, data: &str, out: &mut String) {
+        for ch in data.chars() {
+            *self = match std::mem::take(self) {
+                Self::Ground if ch == '\x1b' => Self::Escape,
+                Self::Ground => {
+                    if ch != '\r' && (ch == '\n' || ch == '\t' || !ch.is_control()) {
+                        out.push(ch);
+                    }
+                    Self::Ground
+                }
+                Self::Escape if ch == '[' => Self::Csi,
+                Self::Escape if ch == ']' => Self::Osc,
+                Self::Escape => Self::Ground,
+                Self::Csi if ('\x40'..='\x7e').contains(&ch) => Self::Ground,
+                Self::Csi => Self::Csi,
+                Self::Osc if ch == '\x07' => Self::Ground,
+                Self::Osc if ch == '\x1b' => Self::OscEscape,
+                Self::Osc => Self::Osc,
+                Self::OscEscape => Self::Ground,
+            };
+        }
+    }
+}
+
+// Find `pattern` in the printed text of `buffer`, line by line.
+fn search_buffer(
+    buffer: &VecDeque<TerminalOutput>,
+    pattern: &Regex,
+    limit: usize,
+) -> Vec<TerminalSearchMatch> {
+    let mut text = String::new();
+    // Offset in `text` at which each chunk starts, with the chunk's seq.
+    let mut starts = Vec::with_capacity(buffer.len());
+    let mut stripper = AnsiStripper::default();
+    for out in buffer {
+        starts.push((text.len(), out.seq));
+        stripper.strip(&out.data, &mut text);
+    }
+    let seq_at = |offset: usize| {
+        let index = starts.partition_point(|(start, _)| *start <= offset);
+        starts[index.saturating_sub(1)].1
+    };
+
+    let mut matches = Vec::new();
+    let mut line_start = 0;
+    for (line_index, line) in text.split('\n').enumerate() {
+        for found in pattern.find_iter(line) {
+            if matches.len() >= limit {
+                return matches;
+            }
+            if found.as_str().is_empty() {
+                continue;
+            }
+            matches.push(TerminalSearchMatch {
+                seq: seq_at(line_start + found.start()),
+                line: line_index,
+                column: line[..found.start()].chars().count(),
+                length: found.as_str().chars().count(),
+                text: found.as_str().to_string(),
+            });
+        }
+        line_start += line.len() + 1;
+    }
+    matches
+}
+
+fn count_lines(data: &str) -> usize {
+    data.bytes().filter(|b| *b == b'\n').count()
+}
+
+#[derive(Clone, Deserialize, Serialize)]
 pub struct TerminalOutput {
     pub seq: u64,
     pub data: String,
 }
 
+/// A search hit in the scrollback. `line` counts lines from the start of the
+/// buffered output and `column`/`length` are measured in characters, both on
+/// the text with escape sequences removed.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TerminalSearchMatch {
+    /// Output chunk the match starts in.
+    pub seq: u64,
+    pub line: usize,
+    pub column: usize,
+    pub length: usize,
+    pub text: String,
+}
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TerminalSessionInfo {
     pub session_id: String,
     pub cwd: String,
     pub title: Option<String>,
+    /// Directory the shell is currently in, when it reports it via OSC 7.
+    pub current_cwd: Option<String>,
+    pub pid: Option<u32>,
+    pub created_at_secs: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartTerminalSessionArgs {
+    pub cwd: String,
+    #[serde(default)]
+    pub force_new: bool,
+    /// Maximum size of the replay buffer in bytes.
+    #[serde(default)]
+    pub scrollback_bytes: Option<usize>,
+    /// Optional maximum number of lines kept in the replay buffer.
+    #[serde(default)]
+    pub scrollback_lines: Option<usize>,
+    /// Desktop only: drop variables the IDE process itself was started with
+    /// (Node/Python/toolchain state) so the shell behaves like a fresh one.
+    #[serde(default)]
+    pub clean_env: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartSshSessionArgs {
+    pub host: String,
+    #[serde(default)]
+    pub port: Option<u16>,
+    pub user: String,
+    pub auth: crate::ssh::SshAuth,
+    #[serde(default)]
+    pub cols: Option<u16>,
+    #[serde(default)]
+    pub rows: Option<u16>,
+    #[serde(default)]
+    pub scrollback_bytes: Option<usize>,
+    #[serde(default)]
+    pub scrollback_lines: Option<usize>,
+}
+
+impl StartTerminalSessionArgs {
+    fn scrollback_limits(&self) -> ScrollbackLimits {
+        let defaults = ScrollbackLimits::default();
+        ScrollbackLimits {
+            max_bytes: self
+                .scrollback_bytes
+                .filter(|bytes| *bytes > 0)
+                .unwrap_or(defaults.max_bytes),
+            max_lines: self.scrollback_lines.filter(|lines| *lines > 0),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionIdArgs {
+    session_id: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionAckArgs {
+    session_id: String,
+    bytes: usize,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartRecordingArgs {
+    session_id: String,
+    /// Save under `.truidide/recordings` of the session's project instead
+    /// of the app data directory. Not available for SSH sessions.
+    #[serde(default)]
+    in_project: bool,
 }
 
-#[derive(Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct StartTerminalSessionArgs {
-    pub cwd: String,
-    #[serde(default)]
-    pub force_new: bool,
-}
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionIdTitleArgs {
+    session_id: String,
+    title: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionInputArgs {
+    session_id: String,
+    input: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionPasteArgs {
+    session_id: String,
+    text: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchTerminalBufferArgs {
+    session_id: String,
+    query: String,
+    /// Treat `query` as a regular expression instead of literal text.
+    #[serde(default)]
+    regex: bool,
+    #[serde(default)]
+    case_sensitive: bool,
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportTerminalBufferArgs {
+    session_id: String,
+    target_path: String,
+    /// Drop escape sequences so the file reads as a plain log.
+    #[serde(default)]
+    strip_ansi: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSignalArgs {
+    session_id: String,
+    signal: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResizeArgs {
+    session_id: String,
+    cols: u32,
+    rows: u32,
+}
+
+struct SessionDescription {
+    title: Option<String>,
+    current_cwd: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TerminalTitleChanged<'a> {
+    session_id: &'a str,
+    title: Option<&'a str>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TerminalCwdChanged<'a> {
+    session_id: &'a str,
+    cwd: &'a str,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TerminalClipboard<'a> {
+    session_id: &'a str,
+    text: &'a str,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TerminalExit {
+    exit_code: Option<u32>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TerminalSessionStats<'a> {
+    session_id: &'a str,
+    #[serde(flatten)]
+    stats: ProcessTreeStats,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TerminalReaped<'a> {
+    session_id: &'a str,
+    cwd: &'a str,
+    idle_secs: u64,
+    /// Pass to `take_saved_terminal_buffer`; absent if saving failed.
+    buffer_id: Option<String>,
+}
+
+/// Scrollback of a session that was stopped for being idle.
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedTerminalBuffer {
+    pub buffer_id: String,
+    pub session_id: String,
+    pub cwd: String,
+    pub title: Option<String>,
+    pub current_cwd: Option<String>,
+    pub saved_at_secs: u64,
+    pub outputs: Vec<TerminalOutput>,
+}
+
+// The process side of a session: a local pty or a remote SSH shell.
+enum SessionBackend {
+    Pty {
+        master: Box<dyn MasterPty + Send>,
+        killer: Box<dyn ChildKiller + Send + Sync>,
+        pid: Option<u32>,
+    },
+    Ssh(crate::ssh::RemoteShell),
+}
+
+impl SessionBackend {
+    fn resize(&mut self, size: PtySize) -> Result<(), String> {
+        match self {
+            Self::Pty { master, .. } => master
+                .resize(size)
+                .map_err(|e| format!("调整大小失败: {e}")),
+            Self::Ssh(remote) => remote.resize(size.cols, size.rows),
+        }
+    }
+
+    fn size(&self) -> Option<PtySize> {
+        match self {
+            Self::Pty { master, .. } => master.get_size().ok(),
+            Self::Ssh(remote) => {
+                let (cols, rows) = remote.size();
+                Some(PtySize {
+                    rows,
+                    cols,
+                    pixel_width: 0,
+                    pixel_height: 0,
+                })
+            }
+        }
+    }
+
+    fn kill(&mut self) {
+        match self {
+            Self::Pty { killer, .. } => {
+                let _ = killer.kill();
+            }
+            Self::Ssh(remote) => remote.close(),
+        }
+    }
+}
+
+// Everything `launch_session` needs from a freshly started process.
+struct SessionIo {
+    backend: SessionBackend,
+    pid: Option<u32>,
+    input_tx: mpsc::Sender<Vec<u8>>,
+    output_rx: mpsc::Receiver<Vec<u8>>,
+    /// Resolves to the exit code once the process is gone.
+    wait_handle: JoinHandle<Option<u32>>,
+    sandboxed: bool,
+}
+
+// Owns everything belonging to one session. Runs until the process output
+// reaches EOF or the session is stopped explicitly.
+struct SessionActor {
+    id: String,
+    app: AppHandle,
+    backend: SessionBackend,
+    /// Feeds the input forwarder; never blocks, so a process that stops
+    /// reading its input cannot stall acks, resizes or stops.
+    input_tx: mpsc::UnboundedSender<Vec<u8>>,
+    state: SessionState,
+    pending: Vec<u8>,
+    /// Directory the session was started in; command history is keyed by it.
+    cwd: String,
+    command_line: CommandLineTracker,
+    osc: OscParser,
+    last_activity: Instant,
+    recorder: Option<Recorder>,
+    bracketed_paste: BracketedPasteMode,
+    /// Keeps the app alive in the background until the actor finishes.
+    _keep_alive: KeepAliveGuard,
+}
+
+impl SessionActor {
+    async fn run(
+        mut self,
+        mut cmd_rx: mpsc::Receiver<SessionCommand>,
+        mut output_rx: mpsc::Receiver<Vec<u8>>,
+        wait_handle: JoinHandle<Option<u32>>,
+    ) {
+        let mut idle_check = tokio::time::interval(IDLE_CHECK_INTERVAL);
+        loop {
+            tokio::select! {
+                chunk = output_rx.recv(), if self.state.flow.reading() => match chunk {
+                    Some(chunk) => self.handle_output(&chunk),
+                    None => break,
+                },
+                _ = idle_check.tick() => {
+                    if self.reap_if_idle().await {
+                        return;
+                    }
+                }
+                cmd = cmd_rx.recv() => match cmd {
+                    Some(SessionCommand::Stop { notify, reply }) => {
+                        self.backend.kill();
+                        unregister_session(&self.id).await;
+                        if notify {
+                            self.emit_to_subscribers(
+                                &format!("terminal-exit-{}", self.id),
+                                TerminalExit { exit_code: None },
+                            );
+                        }
+                        let _ = reply.send(());
+                        return;
+                    }
+                    Some(cmd) => self.handle_command(cmd).await,
+                    None => break,
+                },
+            }
+        }
+
+        // The pty reached EOF: the shell has exited (or is about to).
+        let exit_code = wait_handle.await.ok().flatten();
+        unregister_session(&self.id).await;
+        self.emit_to_subscribers(
+            &format!("terminal-exit-{}", self.id),
+            TerminalExit { exit_code },
+        );
+    }
+
+    // Stop the session when nobody is attached and it has been quiet for
+    // longer than the configured idle timeout. Returns true if it was stopped.
+    async fn reap_if_idle(&mut self) -> bool {
+        if !self.state.subscribers.is_empty() {
+            return false;
+        }
+        let Some(minutes) = load_terminal_settings(&self.app)
+            .ok()
+            .and_then(|settings| settings.idle_timeout_minutes)
+            .filter(|minutes| *minutes > 0)
+        else {
+            return false;
+        };
+        let idle = self.last_activity.elapsed();
+        if idle < Duration::from_secs(minutes * 60) {
+            return false;
+        }
+
+        let buffer_id = match self.save_buffer() {
+            Ok(buffer_id) => Some(buffer_id),
+            Err(err) => {
+                eprintln!(
+                    "[truidide::terminal] 保存会话 {} 的输出缓冲失败: {}",
+                    self.id, err
+                );
+                None
+            }
+        };
+        eprintln!(
+            "[truidide::terminal] 会话 {} 已空闲 {} 分钟，正在终止",
+            self.id,
+            idle.as_secs() / 60
+        );
+        self.backend.kill();
+        unregister_session(&self.id).await;
+        let _ = self.app.emit(
+            "terminal-session-reaped",
+            TerminalReaped {
+                session_id: &self.id,
+                cwd: &self.cwd,
+                idle_secs: idle.as_secs(),
+                buffer_id,
+            },
+        );
+        true
+    }
+
+    // Returns the id the buffer was saved under.
+    fn save_buffer(&self) -> Result<String, String> {
+        let buffer_id = Uuid::new_v4().to_string();
+        let path = saved_buffer_path(&self.app, &buffer_id)?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("创建缓冲目录失败: {e}"))?;
+        }
+        let saved = SavedTerminalBuffer {
+            buffer_id: buffer_id.clone(),
+            session_id: self.id.clone(),
+            cwd: self.cwd.clone(),
+            title: self.state.title.clone(),
+            current_cwd: self.state.current_cwd.clone(),
+            saved_at_secs: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or_default(),
+            outputs: self.state.buffer.iter().cloned().collect(),
+        };
+        let data = serde_json::to_string(&saved).map_err(|e| format!("序列化输出缓冲失败: {e}"))?;
+        std::fs::write(&path, data).map_err(|e| format!("写入输出缓冲失败: {e}"))?;
+        Ok(buffer_id)
+    }
+
+    fn handle_output(&mut self, chunk: &[u8]) {
+        self.last_activity = Instant::now();
+        let data = decode_utf8_chunk(&mut self.pending, chunk);
+        if data.is_empty() {
+            return;
+        }
+        self.bracketed_paste.feed(&data);
+        for (code, payload) in self.osc.feed(&data) {
+            self.handle_osc(code, payload);
+        }
+        self.record("o", &data);
+        let out = self.state.push_output(data);
+        if !self.state.subscribers.is_empty() {
+            self.state.flow.on_emit(out.data.len());
+        }
+        self.emit_to_subscribers(&format!("terminal-output-{}", self.id), out);
+    }
+
+    fn handle_osc(&mut self, code: u32, payload: String) {
+        match code {
+            0 | 2 => {
+                let title = Some(payload).filter(|title| !title.trim().is_empty());
+                if title == self.state.title {
+                    return;
+                }
+                self.state.title = title;
+                let _ = self.app.emit(
+                    "terminal-title-changed",
+                    TerminalTitleChanged {
+                        session_id: &self.id,
+                        title: self.state.title.as_deref(),
+                    },
+                );
+            }
+            7 => {
+                let Some(cwd) = parse_osc7_path(&payload) else {
+                    return;
+                };
+                if self.state.current_cwd.as_deref() == Some(cwd.as_str()) {
+                    return;
+                }
+                let _ = self.app.emit(
+                    "terminal-cwd-changed",
+                    TerminalCwdChanged {
+                        session_id: &self.id,
+                        cwd: &cwd,
+                    },
+                );
+                self.state.current_cwd = Some(cwd);
+            }
+            52 => {
+                let settings = load_terminal_settings(&self.app).unwrap_or_default();
+                if !settings.osc52_enabled {
+                    return;
+                }
+                match parse_osc52_text(&payload, settings.osc52_max_bytes) {
+                    // The frontend owns the clipboard and writes the text for us.
+                    Ok(Some(text)) => {
+                        let _ = self.app.emit(
+                            "terminal-clipboard",
+                            TerminalClipboard {
+                                session_id: &self.id,
+                                text: &text,
+                            },
+                        );
+                    }
+                    Ok(None) => {}
+                    Err(err) => {
+                        eprintln!(
+                            "[truidide::terminal] 会话 {} 的 OSC 52 请求被忽略: {}",
+                            self.id, err
+                        );
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Append an event to the active recording; a failed write ends it.
+    fn record(&mut self, code: &str, data: &str) {
+        let Some(recorder) = self.recorder.as_mut() else {
+            return;
+        };
+        if let Err(err) = recorder.event(code, data) {
+            eprintln!(
+                "[truidide::terminal] 会话 {} 的录制已中止: {}",
+                self.id, err
+            );
+            self.recorder = None;
+        }
+    }
+
+    fn emit_to_subscribers<S: Serialize + Clone>(&self, event: &str, payload: S) {
+        for label in &self.state.subscribers {
+            if let Some(window) = self.app.get_webview_window(label) {
+                let _ = window.emit(event, payload.clone());
+            }
+        }
+    }
+
+    async fn handle_command(&mut self, cmd: SessionCommand) {
+        match cmd {
+            SessionCommand::Input(data) => {
+                self.last_activity = Instant::now();
+                for command in self.command_line.feed(&data) {
+                    crate::history::record_command(&self.app, &self.cwd, &self.id, &command);
+                }
+                if self.input_tx.send(data).is_err() {
+                    eprintln!("[truidide::terminal] 会话 {} 的输入管道已关闭", self.id);
+                }
+            }
+            SessionCommand::Paste(text) => {
+                self.last_activity = Instant::now();
+                // Pasted text does not go through the line editor key by key.
+                self.command_line.invalidate();
+                let data = prepare_paste(&text, self.bracketed_paste.enabled);
+                for chunk in chunk_str(&data, PASTE_CHUNK_BYTES) {
+                    if self.input_tx.send(chunk.as_bytes().to_vec()).is_err() {
+                        eprintln!("[truidide::terminal] 会话 {} 的输入管道已关闭", self.id);
+                        break;
+                    }
+                }
+            }
+            SessionCommand::Resize { cols, rows, reply } => {
+                let result = self.backend.resize(PtySize {
+                    rows,
+                    cols,
+                    pixel_width: 0,
+                    pixel_height: 0,
+                });
+                if result.is_ok() {
+                    self.record("r", &format!("{cols}x{rows}"));
+                }
+                let _ = reply.send(result);
+            }
+            SessionCommand::Attach { label, reply } => {
+                self.state.subscribers.insert(label);
+                self.state.flow.reset_in_flight();
+                let _ = reply.send(self.state.buffer.iter().cloned().collect());
+            }
+            SessionCommand::Detach { label } => {
+                self.state.subscribers.remove(&label);
+                if self.state.subscribers.is_empty() {
+                    self.state.flow.reset_in_flight();
+                }
+            }
+            SessionCommand::Ack(bytes) => {
+                self.state.flow.on_ack(bytes);
+            }
+            SessionCommand::SetPaused(paused) => {
+                self.state.flow.paused = paused;
+            }
+            SessionCommand::StartRecording { path, reply } => {
+                if self.recorder.is_some() {
+                    let _ = reply.send(Err("会话已在录制中".into()));
+                    return;
+                }
+                let size = self.backend.size().unwrap_or(PtySize {
+                    rows: 24,
+                    cols: 80,
+                    pixel_width: 0,
+                    pixel_height: 0,
+                });
+                let result = Recorder::create(path, size, self.state.title.as_deref())
+                    .map(|recorder| self.recorder = Some(recorder));
+                let _ = reply.send(result);
+            }
+            SessionCommand::StopRecording { reply } => {
+                let _ = reply.send(self.recorder.take().map(Recorder::finish));
+            }
+            SessionCommand::SshConnection { reply } => {
+                let connection = match &self.backend {
+                    SessionBackend::Ssh(remote) => Some(remote.connection()),
+                    SessionBackend::Pty { .. } => None,
+                };
+                let _ = reply.send(connection);
+            }
+            SessionCommand::SetTitle(title) => {
+                self.state.title = title;
+            }
+            SessionCommand::ClearBuffer => {
+                self.state.clear_buffer();
+            }
+            SessionCommand::Signal { signal, reply } => {
+                let _ = reply.send(self.send_signal(&signal).await);
+            }
+            SessionCommand::Snapshot { reply } => {
+                let _ = reply.send(self.state.buffer.iter().cloned().collect());
+            }
+            SessionCommand::Search {
+                pattern,
+                limit,
+                reply,
+            } => {
+                let _ = reply.send(search_buffer(&self.state.buffer, &pattern, limit));
+            }
+            SessionCommand::Describe { reply } => {
+                let _ = reply.send(SessionDescription {
+                    title: self.state.title.clone(),
+                    current_cwd: self.state.current_cwd.clone(),
+                });
+            }
+            SessionCommand::Stop { .. } => unreachable!("handled by the actor loop"),
+        }
+    }
+
+    #[cfg(unix)]
+    async fn send_signal(&mut self, raw: &str) -> Result<(), String> {
+        let (master, pid) = match &self.backend {
+            SessionBackend::Pty { master, pid, .. } => (master, *pid),
+            SessionBackend::Ssh(remote) => return remote.signal(raw),
+        };
+        let signal = parse_signal(raw)?;
+        // Prefer the foreground process group of the pty so that the job currently
+        // running in the shell receives the signal; fall back to the shell itself.
+        let pgid = master
+            .process_group_leader()
+            .filter(|pid| *pid > 0)
+            .or_else(|| pid.map(|pid| pid as libc::pid_t))
+            .ok_or_else(|| "无法确定会话进程".to_string())?;
+
+        let ret = unsafe { libc::kill(-pgid, signal) };
+        if ret != 0 {
+            return Err(format!("发送信号失败: {}", std::io::Error::last_os_error()));
+        }
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    async fn send_signal(&mut self, raw: &str) -> Result<(), String> {
+        if let SessionBackend::Ssh(remote) = &self.backend {
+            return remote.signal(raw);
+        }
+        // Windows has no process groups we can signal; approximate the common cases.
+        match raw.trim().to_ascii_uppercase().trim_start_matches("SIG") {
+            "INT" => self
+                .input_tx
+                .send(b"\x03".to_vec())
+                .map_err(|e| format!("写入 pty 失败: {e}")),
+            "TERM" | "KILL" | "HUP" => {
+                self.backend.kill();
+                Ok(())
+            }
+            _ => Err(format!("不支持的信号: {raw}")),
+        }
+    }
+}
+
+async fn unregister_session(session_id: &str) {
+    sessions_map().write().await.remove(session_id);
+}
+
+async fn session_handle(session_id: &str) -> Result<SessionHandle, String> {
+    sessions_map()
+        .read()
+        .await
+        .get(session_id)
+        .cloned()
+        .ok_or_else(|| "会话未找到".to_string())
+}
+
+async fn send_command(session_id: &str, cmd: SessionCommand) -> Result<(), String> {
+    let handle = session_handle(session_id).await?;
+    handle
+        .tx
+        .send(cmd)
+        .await
+        .map_err(|_| "会话已结束".to_string())
+}
+
+async fn request<T>(
+    session_id: &str,
+    build: impl FnOnce(oneshot::Sender<T>) -> SessionCommand,
+) -> Result<T, String> {
+    let (reply_tx, reply_rx) = oneshot::channel();
+    send_command(session_id, build(reply_tx)).await?;
+    reply_rx.await.map_err(|_| "会话已结束".to_string())
+}
+
+/// The SSH connection behind `session_id`, for opening extra channels on it.
+pub async fn ssh_connection(session_id: &str) -> Result<crate::ssh::SshConnection, String> {
+    request(session_id, |reply| SessionCommand::SshConnection { reply })
+        .await?
+        .ok_or_else(|| format!("会话 {session_id} 不是 SSH 会话"))
+}
+
+/// Type `line` into the shell of `session_id` and submit it.
+pub async fn run_line(session_id: &str, line: &str) -> Result<(), String> {
+    send_command(
+        session_id,
+        SessionCommand::Input(format!("{line}\r").into_bytes()),
+    )
+    .await
+}
+
+/// Stop every session whose working directory is `root` or lies beneath it,
+/// e.g. after the project directory was deleted or renamed.
+pub async fn stop_sessions_under(root: &Path) -> usize {
+    let session_ids = sessions_map()
+        .read()
+        .await
+        .iter()
+        .filter(|(_, handle)| Path::new(&handle.cwd_key).starts_with(root))
+        .map(|(id, _)| id.clone())
+        .collect::<Vec<_>>();
+
+    for session_id in &session_ids {
+        eprintln!(
+            "[truidide::terminal] 会话 {} 的工作目录已不存在，正在终止",
+            session_id
+        );
+        let _ = request(session_id, |reply| SessionCommand::Stop {
+            notify: true,
+            reply,
+        })
+        .await;
+    }
+    session_ids.len()
+}
+
+// Use a canonicalized path as the reuse key so string differences
+// (slashes, casing, symlinks) don't prevent reuse.
+pub(crate) fn cwd_key(cwd: &str) -> String {
+    let cwd_path = PathBuf::from(cwd);
+    match cwd_path.canonicalize() {
+        Ok(p) => p.to_string_lossy().to_string(),
+        Err(_) => cwd_path.to_string_lossy().to_string(),
+    }
+}
+
+// Open a pty, spawn `cmd` in it and start the session actor together with its
+// blocking reader, writer and wait tasks.
+async fn spawn_session(
+    app: AppHandle,
+    cmd: CommandBuilder,
+    cwd: String,
+    cwd_key: String,
+    limits: ScrollbackLimits,
+    sandboxed: bool,
+) -> Result<String, String> {
+    let pty_system = native_pty_system();
+    let pair = pty_system
+        .openpty(PtySize {
+            rows: 24,
+            cols: 80,
+            pixel_width: 0,
+            pixel_height: 0,
+        })
+        .map_err(|e| format!("无法打开 pty: {e}"))?;
+    let mut child = pair
+        .slave
+        .spawn_command(cmd)
+        .map_err(|e| format!("spawn 失败: {e}"))?;
+    // Drop our copy of the slave so the reader sees EOF once the child exits.
+    drop(pair.slave);
+    let master = pair.master;
+
+    let mut reader = master
+        .try_clone_reader()
+        .map_err(|e| format!("无法克隆 reader: {e}"))?;
+    let mut writer = master
+        .take_writer()
+        .map_err(|e| format!("无法获取 writer: {e}"))?;
+    let killer = child.clone_killer();
+    let pid = child.process_id();
+
+    let (id, order) = generate_session_id();
+    let (output_tx, output_rx) = mpsc::channel::<Vec<u8>>(OUTPUT_CHANNEL_CAPACITY);
+    let (input_tx, mut input_rx) = mpsc::channel::<Vec<u8>>(INPUT_CHANNEL_CAPACITY);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut buf = [0u8; 4096];
+        loop {
+            match reader.read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    if output_tx.blocking_send(buf[..n].to_vec()).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+
+    let writer_id = id.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        while let Some(data) = input_rx.blocking_recv() {
+            if let Err(err) = writer.write_all(&data).and_then(|_| writer.flush()) {
+                eprintln!(
+                    "[truidide::terminal] 会话 {} 写入 pty 失败: {}",
+                    writer_id, err
+                );
+                break;
+            }
+        }
+    });
 
-#[derive(Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct SessionIdArgs {
-    session_id: String,
-}
+    let wait_handle = tauri::async_runtime::spawn_blocking(move || {
+        child.wait().ok().map(|status| status.exit_code())
+    });
 
-#[derive(Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct SessionIdTitleArgs {
-    session_id: String,
-    title: Option<String>,
+    let io = SessionIo {
+        backend: SessionBackend::Pty {
+            master,
+            killer,
+            pid,
+        },
+        pid,
+        input_tx,
+        output_rx,
+        wait_handle,
+        sandboxed,
+    };
+    launch_session(app, id, order, io, cwd, cwd_key, limits).await
 }
 
-#[derive(Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct SessionInputArgs {
-    session_id: String,
-    input: String,
-}
+// Periodically broadcast the resource usage of every local session. A single
+// task serves all sessions so /proc is scanned once per round.
+fn ensure_stats_monitor(app: &AppHandle) {
+    if STATS_MONITOR.set(()).is_err() {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let interval = load_terminal_settings(&app)
+                .map(|settings| settings.stats_interval_secs)
+                .unwrap_or(DEFAULT_STATS_INTERVAL_SECS);
+            tokio::time::sleep(Duration::from_secs(if interval == 0 {
+                DEFAULT_STATS_INTERVAL_SECS
+            } else {
+                interval
+            }))
+            .await;
+            if interval == 0 {
+                continue;
+            }
 
-#[derive(Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct ResizeArgs {
-    session_id: String,
-    cols: u32,
-    rows: u32,
+            let pids = sessions_map()
+                .read()
+                .await
+                .iter()
+                .filter_map(|(id, handle)| handle.pid.map(|pid| (id.clone(), pid)))
+                .collect::<Vec<_>>();
+            if pids.is_empty() {
+                continue;
+            }
+            let Ok(Ok(table)) = tauri::async_runtime::spawn_blocking(ProcessTable::read).await
+            else {
+                continue;
+            };
+            for (session_id, pid) in pids {
+                if let Some(stats) = table.tree_stats(pid) {
+                    let _ = app.emit(
+                        "terminal-session-stats",
+                        TerminalSessionStats {
+                            session_id: &session_id,
+                            stats,
+                        },
+                    );
+                }
+            }
+        }
+    });
 }
 
-static SESSIONS_STATE: OnceCell<Mutex<HashMap<String, SessionState>>> = OnceCell::new();
-static SESSIONS_BY_CWD: OnceCell<Mutex<HashMap<String, Vec<String>>>> = OnceCell::new();
+// Register a session and start its actor.
+async fn launch_session(
+    app: AppHandle,
+    id: String,
+    order: u64,
+    io: SessionIo,
+    cwd: String,
+    cwd_key: String,
+    limits: ScrollbackLimits,
+) -> Result<String, String> {
+    let (cmd_tx, cmd_rx) = mpsc::channel::<SessionCommand>(COMMAND_CHANNEL_CAPACITY);
+    ensure_stats_monitor(&app);
+    let state = SessionState {
+        limits,
+        ..SessionState::default()
+    };
+    let actor = SessionActor {
+        id: id.clone(),
+        app,
+        backend: io.backend,
+        input_tx: spawn_input_forwarder(io.input_tx),
+        state,
+        pending: Vec::new(),
+        cwd: cwd.clone(),
+        command_line: CommandLineTracker::default(),
+        osc: OscParser::default(),
+        last_activity: Instant::now(),
+        recorder: None,
+        bracketed_paste: BracketedPasteMode::default(),
+        _keep_alive: KeepAliveGuard::acquire(),
+    };
+
+    sessions_map().write().await.insert(
+        id.clone(),
+        SessionHandle {
+            order,
+            cwd,
+            cwd_key,
+            pid: io.pid,
+            sandboxed: io.sandboxed,
+            created_at_secs: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or_default(),
+            tx: cmd_tx,
+        },
+    );
+    tauri::async_runtime::spawn(actor.run(cmd_rx, io.output_rx, io.wait_handle));
 
-fn sessions_state_map() -> &'static Mutex<HashMap<String, SessionState>> {
-    SESSIONS_STATE.get_or_init(|| Mutex::new(HashMap::new()))
+    Ok(id)
 }
 
-fn sessions_by_cwd_map() -> &'static Mutex<HashMap<String, Vec<String>>> {
-    SESSIONS_BY_CWD.get_or_init(|| Mutex::new(HashMap::new()))
+// Move input from the actor's queue into the bounded process input channel
+// on a task of its own, waiting there while the process is slow to read.
+fn spawn_input_forwarder(input_tx: mpsc::Sender<Vec<u8>>) -> mpsc::UnboundedSender<Vec<u8>> {
+    let (queue_tx, mut queue_rx) = mpsc::unbounded_channel::<Vec<u8>>();
+    tauri::async_runtime::spawn(async move {
+        while let Some(data) = queue_rx.recv().await {
+            if input_tx.send(data).await.is_err() {
+                break;
+            }
+        }
+    });
+    queue_tx
 }
 
 #[cfg(target_os = "android")]
-fn start_proot_session_internal(
-    app: tauri::AppHandle,
-    cwd_in_rootfs: Option<String>,
-) -> Result<String, String> {
+fn build_proot_command(
+    app: &AppHandle,
+    effective_cwd: &str,
+    sandboxed: bool,
+) -> Result<CommandBuilder, String> {
     use crate::android::proot::prepare_proot_env;
-    use std::fs;
 
     // 使用 android.rs 中的统一 prepare_proot_env 函数
-    let env = prepare_proot_env(&app)?;
-    let prepared = env.base_dir.to_string_lossy().into_owned();
+    let env = prepare_proot_env(app)?;
 
-    let prepared_path = PathBuf::from(prepared);
-    let proot = prepared_path.join("proot/bin/proot");
-
-    let rootfs_dir = prepared_path.join("rootfs");
-    // Extracted archive may contain arch-specific subdir
-    #[cfg(target_arch = "aarch64")]
-    let rootfs_dir = rootfs_dir.join("archlinux-aarch64");
-    #[cfg(target_arch = "arm")]
-    let rootfs_dir = rootfs_dir.join("archlinux-armv7l");
-    #[cfg(target_arch = "x86_64")]
-    let rootfs_dir = rootfs_dir.join("archlinux-x86_64");
-    #[cfg(target_arch = "x86")]
-    let rootfs_dir = rootfs_dir.join("archlinux-x86");
-
-    if !rootfs_dir.exists() {
-        return Err("rootfs 未解压".into());
+    let mut cmd = CommandBuilder::new(env.proot_bin.to_string_lossy().as_ref());
+    for (key, value) in env.base_env() {
+        cmd.env(key, value);
     }
-
-    let mut cmd = CommandBuilder::new(proot.to_string_lossy().as_ref());
-
-    let tmp_dir = prepared_path.join("proot_tmp");
-    let _ = fs::create_dir(&tmp_dir);
-    cmd.env("PROOT_TMP_DIR", tmp_dir.to_string_lossy().as_ref());
-
     cmd.env("TERM", "xterm-256color");
     cmd.env("COLORTERM", "truecolor");
 
-    cmd.arg(format!("--rootfs={}", rootfs_dir.to_string_lossy()));
-
-    let effective_cwd = cwd_in_rootfs
-        .filter(|cwd| !cwd.trim().is_empty())
-        .unwrap_or_else(|| String::from("/root"));
+    cmd.args(env.base_args());
     cmd.arg(format!("--cwd={effective_cwd}"));
+    if sandboxed {
+        // Hide the real home behind a private one and expose only the
+        // project directory on top of it.
+        let home = crate::sandbox::home_dir(app, &cwd_key(effective_cwd))?;
+        let project = crate::android::proot::resolve_guest_path(app, effective_cwd)?;
+        cmd.arg(format!("--bind={}:/root", home.to_string_lossy()));
+        cmd.arg(format!(
+            "--bind={}:{effective_cwd}",
+            project.to_string_lossy()
+        ));
+    }
 
     cmd.args(&[
-        "--root-id",
-        "--kill-on-exit",
-        "--link2symlink",
-        "--bind=/dev",
-        "--bind=/proc",
-        "--bind=/sys",
-        "--bind=/dev/urandom:/dev/random",
         "--bind=/proc/self/fd:/dev/fd",
         "--bind=/proc/self/fd/0:/dev/stdin",
         "--bind=/proc/self/fd/1:/dev/stdout",
@@ -187,118 +1703,113 @@ fn start_proot_session_internal(
         "--login",
     ]);
 
-    let pty_system = native_pty_system();
-    let pair = pty_system
-        .openpty(PtySize {
-            rows: 24,
-            cols: 80,
-            pixel_width: 0,
-            pixel_height: 0,
-        })
-        .map_err(|e| format!("无法打开 pty: {e}"))?;
-    let child = pair
-        .slave
-        .spawn_command(cmd)
-        .map_err(|e| format!("spawn proot 失败: {e}"))?;
-    let master = pair.master;
+    Ok(cmd)
+}
 
-    let reader = master
-        .try_clone_reader()
-        .map_err(|e| format!("无法克隆 reader: {e}"))?;
-    let writer = master
-        .take_writer()
-        .map_err(|e| format!("无法获取 writer: {e}"))?;
+// Variables a shell needs to work at all; everything else is dropped.
+#[cfg(not(target_os = "android"))]
+const SANDBOX_BASE_ENV: &[&str] = &[
+    "PATH",
+    "SHELL",
+    "LANG",
+    "LC_ALL",
+    "TZ",
+    "USER",
+    "LOGNAME",
+    "TMPDIR",
+    "TEMP",
+    "TMP",
+    "SystemRoot",
+    "windir",
+    "COMSPEC",
+    "PATHEXT",
+];
 
-    let session_id = generate_session_id();
+// Variables leaking the IDE's own runtime into user shells, by exact name
+// and by prefix.
+#[cfg(not(target_os = "android"))]
+const IDE_ENV_VARS: &[&str] = &[
+    "NODE_OPTIONS",
+    "NODE_PATH",
+    "INIT_CWD",
+    "VIRTUAL_ENV",
+    "VIRTUAL_ENV_PROMPT",
+    "PYTHONHOME",
+    "PYTHONPATH",
+    "CONDA_PREFIX",
+    "CONDA_DEFAULT_ENV",
+    "RUST_LOG",
+    "RUST_BACKTRACE",
+];
 
-    // spawn reader thread
-    // spawn reader thread which will push output into the per-session
-    // SessionState and broadcast typed TerminalOutput messages to the
-    // subscribed webview windows.
-    {
-        // ensure there is a session state entry before the reader runs
+#[cfg(not(target_os = "android"))]
+const IDE_ENV_PREFIXES: &[&str] = &["npm_", "YARN_", "PNPM_", "TAURI_", "CARGO_"];
+
+// Like the LSP spawner's Yarn PnP scrubbing, but for everything in
+// `IDE_ENV_VARS`. An activated virtualenv also loses its `bin` from PATH.
+#[cfg(not(target_os = "android"))]
+fn scrub_ide_env(cmd: &mut CommandBuilder) {
+    for (name, _) in std::env::vars_os() {
+        let Some(name) = name.to_str() else {
+            continue;
+        };
+        if IDE_ENV_VARS.contains(&name)
+            || IDE_ENV_PREFIXES
+                .iter()
+                .any(|prefix| name.starts_with(prefix))
         {
-            let mut ss = sessions_state_map()
-                .lock()
-                .map_err(|e| format!("锁错误: {e}"))?;
-            let mut state = SessionState::default();
-            state.cwd = effective_cwd.clone();
-            ss.insert(session_id.clone(), state);
-        }
-
-        let handle = app.clone();
-        let sid = session_id.clone();
-        thread::spawn(move || {
-            let mut reader = reader;
-            let mut buf = [0u8; 1024];
-            loop {
-                match reader.read(&mut buf) {
-                    Ok(0) => break,
-                    Ok(n) => {
-                        let s = String::from_utf8_lossy(&buf[..n]).to_string();
-                        // update session state: increment seq, append to buffer,
-                        // and snapshot subscribers while holding the session map
-                        // lock briefly.
-                        let (out, subs) = {
-                            let mut ss = sessions_state_map().lock().unwrap();
-                            let state = ss.entry(sid.clone()).or_insert(SessionState::default());
-                            state.seq = state.seq.saturating_add(1);
-                            let seq = state.seq;
-                            let out = TerminalOutput {
-                                seq,
-                                data: s.clone(),
-                            };
-                            state.buffer.push_back(out.clone());
-                            if state.buffer.len() > 1000 {
-                                state.buffer.pop_front();
-                            }
-                            let subs = state.subscribers.iter().cloned().collect::<Vec<_>>();
-                            (out, subs)
-                        };
-
-                        for label in subs {
-                            if let Some(window) = handle.get_webview_window(&label) {
-                                let _ =
-                                    window.emit(&format!("terminal-output-{}", sid), out.clone());
-                            }
-                        }
-                    }
-                    Err(_) => break,
-                }
-            }
-        });
+            cmd.env_remove(name);
+        }
     }
 
-    // store session
-    {
-        let mut map = sessions_map().lock().map_err(|e| format!("锁错误: {e}"))?;
-        map.insert(session_id.clone(), (master, writer, child));
+    let (Some(venv), Some(path)) = (std::env::var_os("VIRTUAL_ENV"), std::env::var_os("PATH"))
+    else {
+        return;
+    };
+    let venv = PathBuf::from(venv);
+    let kept = std::env::split_paths(&path).filter(|entry| !entry.starts_with(&venv));
+    if let Ok(joined) = std::env::join_paths(kept) {
+        cmd.env("PATH", joined);
     }
+}
 
-    // register mapping from provided cwd_in_rootfs (if any) -> session id
+#[cfg(not(target_os = "android"))]
+fn restrict_env(
+    app: &AppHandle,
+    cmd: &mut CommandBuilder,
+    cwd: &str,
+    config: &crate::sandbox::SandboxConfig,
+) -> Result<(), String> {
+    let home = crate::sandbox::home_dir(app, &cwd_key(cwd))?;
+    cmd.env_clear();
+    for name in SANDBOX_BASE_ENV
+        .iter()
+        .copied()
+        .chain(config.allowed_env.iter().map(String::as_str))
     {
-        let mut by_cwd = sessions_by_cwd_map()
-            .lock()
-            .map_err(|e| format!("锁错误: {e}"))?;
-        by_cwd
-            .entry(effective_cwd.clone())
-            .or_default()
-            .push(session_id.clone());
+        if let Ok(value) = std::env::var(name) {
+            cmd.env(name, value);
+        }
     }
-
-    Ok(session_id)
+    cmd.env("HOME", &home);
+    if cfg!(windows) {
+        cmd.env("USERPROFILE", &home);
+    }
+    cmd.env("TERM", "xterm-256color");
+    cmd.env("COLORTERM", "truecolor");
+    cmd.env("TRUIDIDE_SANDBOX", "1");
+    Ok(())
 }
 
 #[tauri::command]
-pub fn start_terminal_session(
+pub async fn start_terminal_session(
     app: tauri::AppHandle,
     args: StartTerminalSessionArgs,
 ) -> Result<String, String> {
-    let cwd = args.cwd.clone();
     #[cfg(target_os = "android")]
-    {
+    let (cmd, cwd, sandboxed) = {
         let desired = {
-            let trimmed = cwd.trim();
+            let trimmed = args.cwd.trim();
             if trimmed.is_empty() {
                 "/root".to_string()
             } else if trimmed.starts_with('/') {
@@ -312,345 +1823,512 @@ pub fn start_terminal_session(
                 "/root".to_string()
             }
         };
-        match start_proot_session_internal(app.clone(), Some(desired)) {
-            Ok(sid) => return Ok(sid),
-            Err(e) => return Err(format!("proot 启动失败: {e}")),
-        }
-    }
-
-    let cwd_path = PathBuf::from(&cwd);
-    if !cwd_path.exists() || !cwd_path.is_dir() {
-        return Err("工作目录不存在或不是目录".into());
-    }
+        // The fallback home directory is never sandboxed: it is what the
+        // sandbox hides.
+        let sandboxed =
+            desired != "/root" && crate::sandbox::enabled_for(&app, &cwd_key(&desired)).is_some();
+        let cmd = build_proot_command(&app, &desired, sandboxed)
+            .map_err(|e| format!("proot 启动失败: {e}"))?;
+        (cmd, desired, sandboxed)
+    };
 
-    // Use a canonicalized path as the reuse key so string differences
-    // (slashes, casing, symlinks) don't prevent reuse.
-    let canonical_key = match cwd_path.canonicalize() {
-        Ok(p) => p.to_string_lossy().to_string(),
-        Err(_) => cwd_path.to_string_lossy().to_string(),
+    #[cfg(not(target_os = "android"))]
+    let (cmd, cwd, sandboxed) = {
+        let cwd_path = PathBuf::from(&args.cwd);
+        if !cwd_path.exists() || !cwd_path.is_dir() {
+            return Err("工作目录不存在或不是目录".into());
+        }
+        let mut cmd = CommandBuilder::new_default_prog();
+        cmd.cwd(cwd_path);
+        if args.clean_env {
+            scrub_ide_env(&mut cmd);
+        }
+        let sandbox = crate::sandbox::enabled_for(&app, &cwd_key(&args.cwd));
+        if let Some(config) = &sandbox {
+            restrict_env(&app, &mut cmd, &args.cwd, config)?;
+        }
+        (cmd, args.cwd.clone(), sandbox.is_some())
     };
 
+    let key = cwd_key(&cwd);
+
     // Try to reuse an existing session for this canonicalized cwd.
     if !args.force_new {
-        if let Some(existing_sids) = {
-            let by_cwd = sessions_by_cwd_map()
-                .lock()
-                .map_err(|e| format!("锁错误: {e}"))?;
-            by_cwd.get(&canonical_key).cloned()
-        } {
-            let map = sessions_map().lock().map_err(|e| format!("锁错误: {e}"))?;
-            let mut alive: Vec<String> = Vec::new();
-            let mut first_valid: Option<String> = None;
-            for sid in existing_sids.iter() {
-                if map.contains_key(sid) {
-                    if first_valid.is_none() {
-                        first_valid = Some(sid.clone());
-                    }
-                    alive.push(sid.clone());
-                }
-            }
-            drop(map);
-            if let Some(found) = first_valid {
-                if alive.len() != existing_sids.len() {
-                    let mut by_cwd = sessions_by_cwd_map()
-                        .lock()
-                        .map_err(|e| format!("锁错误: {e}"))?;
-                    by_cwd.insert(canonical_key.clone(), alive);
-                }
-                return Ok(found);
-            } else {
-                let mut by_cwd = sessions_by_cwd_map()
-                    .lock()
-                    .map_err(|e| format!("锁错误: {e}"))?;
-                by_cwd.remove(&canonical_key);
-            }
+        let sessions = sessions_map().read().await;
+        if let Some((id, _)) = sessions
+            .iter()
+            .filter(|(_, handle)| handle.cwd_key == key && handle.sandboxed == sandboxed)
+            .min_by_key(|(_, handle)| handle.order)
+        {
+            return Ok(id.clone());
         }
     }
 
-    let pty_system = native_pty_system();
-    let pair = pty_system
-        .openpty(PtySize {
-            rows: 24,
-            cols: 80,
-            pixel_width: 0,
-            pixel_height: 0,
+    spawn_session(app, cmd, cwd, key, args.scrollback_limits(), sandboxed).await
+}
+
+// Snapshot the sessions accepted by `filter`, ordered by creation, and ask
+// each actor for its current title.
+async fn describe_sessions(
+    filter: impl Fn(&SessionHandle) -> bool,
+) -> Vec<(String, SessionHandle, SessionDescription)> {
+    let mut handles = sessions_map()
+        .read()
+        .await
+        .iter()
+        .filter(|(_, handle)| filter(handle))
+        .map(|(id, handle)| (id.clone(), handle.clone()))
+        .collect::<Vec<_>>();
+    handles.sort_by_key(|(_, handle)| handle.order);
+
+    let mut described = Vec::new();
+    for (session_id, handle) in handles {
+        // Sessions that exit while we are iterating are simply skipped.
+        let Ok(description) =
+            request(&session_id, |reply| SessionCommand::Describe { reply }).await
+        else {
+            continue;
+        };
+        described.push((session_id, handle, description));
+    }
+    described
+}
+
+/// Open an interactive shell on a remote host. The session behaves like a
+/// local one: it is attached, resized and stopped with the same commands.
+#[tauri::command]
+pub async fn start_ssh_session(
+    app: AppHandle,
+    args: StartSshSessionArgs,
+) -> Result<String, String> {
+    let host = args.host.trim().to_string();
+    let user = args.user.trim().to_string();
+    if host.is_empty() || user.is_empty() {
+        return Err("主机和用户名不能为空".into());
+    }
+    let target = crate::ssh::SshTarget {
+        host,
+        port: args.port.unwrap_or(22),
+        user,
+    };
+    let limits = StartTerminalSessionArgs {
+        cwd: String::new(),
+        force_new: true,
+        scrollback_bytes: args.scrollback_bytes,
+        scrollback_lines: args.scrollback_lines,
+        clean_env: false,
+    }
+    .scrollback_limits();
+    let size = (
+        args.cols.filter(|cols| *cols > 0).unwrap_or(80),
+        args.rows.filter(|rows| *rows > 0).unwrap_or(24),
+    );
+
+    let (output_tx, output_rx) = mpsc::channel::<Vec<u8>>(OUTPUT_CHANNEL_CAPACITY);
+    let (input_tx, input_rx) = mpsc::channel::<Vec<u8>>(INPUT_CHANNEL_CAPACITY);
+    let (remote, wait_handle) =
+        crate::ssh::open_shell(&app, &target, args.auth, size, input_rx, output_tx).await?;
+
+    // Remote sessions are never reused, so the key only has to be unique
+    // enough to stay out of local cwd matching.
+    let cwd = format!("ssh://{}@{}", target.user, target.host_id());
+    let (id, order) = generate_session_id();
+    let io = SessionIo {
+        backend: SessionBackend::Ssh(remote),
+        pid: None,
+        input_tx,
+        output_rx,
+        wait_handle,
+        sandboxed: false,
+    };
+    launch_session(app, id, order, io, cwd.clone(), cwd, limits).await
+}
+
+#[tauri::command]
+pub async fn list_terminal_sessions(cwd: String) -> Result<Vec<TerminalSessionInfo>, String> {
+    let key = cwd_key(&cwd);
+    let infos = describe_sessions(|handle| handle.cwd_key == key)
+        .await
+        .into_iter()
+        .map(|(session_id, handle, description)| TerminalSessionInfo {
+            session_id,
+            cwd: if handle.cwd.is_empty() {
+                cwd.clone()
+            } else {
+                handle.cwd
+            },
+            title: description.title,
+            current_cwd: description.current_cwd,
+            pid: handle.pid,
+            created_at_secs: handle.created_at_secs,
         })
-        .map_err(|e| format!("无法打开 pty: {e}"))?;
+        .collect();
 
-    let mut cmd = CommandBuilder::new_default_prog();
-    cmd.cwd(cwd_path);
+    Ok(infos)
+}
 
-    let child = pair
-        .slave
-        .spawn_command(cmd)
-        .map_err(|e| format!("spawn 失败: {e}"))?;
+#[tauri::command]
+pub async fn list_all_terminal_sessions() -> Result<Vec<TerminalSessionInfo>, String> {
+    let infos = describe_sessions(|_| true)
+        .await
+        .into_iter()
+        .map(|(session_id, handle, description)| TerminalSessionInfo {
+            session_id,
+            cwd: handle.cwd,
+            title: description.title,
+            current_cwd: description.current_cwd,
+            pid: handle.pid,
+            created_at_secs: handle.created_at_secs,
+        })
+        .collect();
 
-    let master = pair.master;
+    Ok(infos)
+}
 
-    let reader = master
-        .try_clone_reader()
-        .map_err(|e| format!("无法克隆 reader: {e}"))?;
-    let writer = master
-        .take_writer()
-        .map_err(|e| format!("无法获取 writer: {e}"))?;
+/// CPU time, resident memory and child process count of a local session's
+/// process tree.
+#[tauri::command]
+pub async fn get_terminal_session_stats(args: SessionIdArgs) -> Result<ProcessTreeStats, String> {
+    let pid = session_handle(&args.session_id)
+        .await?
+        .pid
+        .ok_or_else(|| "该会话没有本地进程".to_string())?;
+    let table = tauri::async_runtime::spawn_blocking(ProcessTable::read)
+        .await
+        .map_err(|e| e.to_string())??;
+    table
+        .tree_stats(pid)
+        .ok_or_else(|| "会话进程已退出".to_string())
+}
 
-    let session_id = generate_session_id();
+#[tauri::command]
+pub fn get_terminal_settings(app: AppHandle) -> Result<TerminalSettings, String> {
+    load_terminal_settings(&app)
+}
 
-    // initialize per-session state
-    {
-        let mut ss = sessions_state_map()
-            .lock()
-            .map_err(|e| format!("锁错误: {e}"))?;
-        let mut state = SessionState::default();
-        state.cwd = cwd.clone();
-        ss.insert(session_id.clone(), state);
+#[tauri::command]
+pub fn save_terminal_settings(app: AppHandle, settings: TerminalSettings) -> Result<(), String> {
+    if settings.osc52_max_bytes > MAX_OSC_LEN / 4 * 3 {
+        return Err(format!(
+            "剪贴板大小上限不能超过 {} 字节",
+            MAX_OSC_LEN / 4 * 3
+        ));
     }
-
-    {
-        let handle = app.clone();
-        let sid = session_id.clone();
-        thread::spawn(move || {
-            let mut reader = reader;
-            let mut buf = [0u8; 1024];
-            loop {
-                match reader.read(&mut buf) {
-                    Ok(0) => break,
-                    Ok(n) => {
-                        let s = String::from_utf8_lossy(&buf[..n]).to_string();
-                        let (out, subs) = {
-                            let mut ss = sessions_state_map().lock().unwrap();
-                            let state = ss.entry(sid.clone()).or_insert(SessionState::default());
-                            state.seq = state.seq.saturating_add(1);
-                            let seq = state.seq;
-                            let out = TerminalOutput {
-                                seq,
-                                data: s.clone(),
-                            };
-                            state.buffer.push_back(out.clone());
-                            if state.buffer.len() > 1000 {
-                                state.buffer.pop_front();
-                            }
-                            (out, state.subscribers.iter().cloned().collect::<Vec<_>>())
-                        };
-                        for label in subs {
-                            if let Some(window) = handle.get_webview_window(&label) {
-                                let _ =
-                                    window.emit(&format!("terminal-output-{}", sid), out.clone());
-                            }
-                        }
-                    }
-                    Err(_) => break,
-                }
-            }
-        });
+    let path = terminal_settings_path(&app)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("创建配置目录失败: {e}"))?;
     }
+    let data =
+        serde_json::to_string_pretty(&settings).map_err(|e| format!("序列化终端设置失败: {e}"))?;
+    std::fs::write(&path, data).map_err(|e| format!("保存终端设置失败: {e}"))
+}
 
-    {
-        let mut map = sessions_map().lock().map_err(|e| format!("锁错误: {e}"))?;
-        map.insert(session_id.clone(), (master, writer, child));
+/// Return the scrollback saved when an idle session was stopped, by the
+/// `bufferId` of its `terminal-session-reaped` event. The saved copy is
+/// removed once read unless `keep` is set.
+#[tauri::command]
+pub fn take_saved_terminal_buffer(
+    app: AppHandle,
+    buffer_id: String,
+    keep: Option<bool>,
+) -> Result<Option<SavedTerminalBuffer>, String> {
+    // Buffer ids are uuids we generated; reject anything that could escape the directory.
+    if Uuid::parse_str(&buffer_id).is_err() {
+        return Err("无效的缓冲 ID".into());
     }
-    // register mapping from canonicalized cwd -> session for future reuse
-    {
-        let mut by_cwd = sessions_by_cwd_map()
-            .lock()
-            .map_err(|e| format!("锁错误: {e}"))?;
-        let entry = by_cwd.entry(canonical_key.clone()).or_default();
-        entry.push(session_id.clone());
+    let path = saved_buffer_path(&app, &buffer_id)?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    let data = std::fs::read_to_string(&path).map_err(|e| format!("读取输出缓冲失败: {e}"))?;
+    let saved = serde_json::from_str(&data).map_err(|e| format!("解析输出缓冲失败: {e}"))?;
+    if !keep.unwrap_or(false) {
+        let _ = std::fs::remove_file(&path);
     }
+    Ok(Some(saved))
+}
+
+#[tauri::command]
+pub async fn set_terminal_session_title(args: SessionIdTitleArgs) -> Result<(), String> {
+    let title = args.title.and_then(|t| {
+        let trimmed = t.trim().to_string();
+        if trimmed.is_empty() {
+            None
+        } else {
+            Some(trimmed)
+        }
+    });
+    send_command(&args.session_id, SessionCommand::SetTitle(title)).await
+}
 
-    Ok(session_id)
+#[tauri::command]
+pub async fn send_terminal_input(
+    _app: tauri::AppHandle,
+    args: SessionInputArgs,
+) -> Result<(), String> {
+    send_command(
+        &args.session_id,
+        SessionCommand::Input(args.input.into_bytes()),
+    )
+    .await
 }
 
+/// Paste `text` into the session, honouring bracketed-paste mode and writing
+/// large content in chunks.
 #[tauri::command]
-pub fn list_terminal_sessions(cwd: String) -> Result<Vec<TerminalSessionInfo>, String> {
-    let cwd_path = PathBuf::from(&cwd);
-    let canonical_key = match cwd_path.canonicalize() {
-        Ok(p) => p.to_string_lossy().to_string(),
-        Err(_) => cwd_path.to_string_lossy().to_string(),
+pub async fn paste_terminal_input(args: SessionPasteArgs) -> Result<(), String> {
+    if args.text.is_empty() {
+        return Ok(());
+    }
+    send_command(&args.session_id, SessionCommand::Paste(args.text)).await
+}
+
+#[tauri::command]
+pub async fn search_terminal_buffer(
+    args: SearchTerminalBufferArgs,
+) -> Result<Vec<TerminalSearchMatch>, String> {
+    if args.query.is_empty() {
+        return Ok(Vec::new());
+    }
+    let source = if args.regex {
+        args.query
+    } else {
+        regex::escape(&args.query)
     };
+    let pattern = RegexBuilder::new(&source)
+        .case_insensitive(!args.case_sensitive)
+        .build()
+        .map_err(|e| format!("搜索表达式无效: {e}"))?;
+    let limit = args
+        .limit
+        .filter(|limit| *limit > 0)
+        .unwrap_or(DEFAULT_SEARCH_LIMIT);
+    request(&args.session_id, |reply| SessionCommand::Search {
+        pattern,
+        limit,
+        reply,
+    })
+    .await
+}
 
-    let session_ids = {
-        let by_cwd = sessions_by_cwd_map()
-            .lock()
-            .map_err(|e| format!("锁错误: {e}"))?;
-        by_cwd.get(&canonical_key).cloned().unwrap_or_default()
+/// Write the session's scrollback to `target_path` and return the number of
+/// bytes written. `target_path` is a project path or a content URI picked
+/// through the storage access framework.
+#[tauri::command]
+pub async fn export_terminal_buffer(
+    app: AppHandle,
+    args: ExportTerminalBufferArgs,
+) -> Result<usize, String> {
+    let target = args.target_path.trim();
+    if target.is_empty() {
+        return Err("导出路径不能为空".into());
+    }
+    let content_uri = target.starts_with("content://");
+    let path = if content_uri {
+        None
+    } else {
+        Some(export_target_path(&app, target)?)
     };
 
-    let map = sessions_map().lock().map_err(|e| format!("锁错误: {e}"))?;
-    let states = sessions_state_map()
-        .lock()
-        .map_err(|e| format!("锁错误: {e}"))?;
-    let mut infos: Vec<TerminalSessionInfo> = Vec::new();
-    let mut stale = false;
-
-    for sid in session_ids.iter() {
-        if map.contains_key(sid) {
-            let (title, stored_cwd) = if let Some(state) = states.get(sid) {
-                (
-                    state.title.clone(),
-                    if state.cwd.is_empty() {
-                        cwd.clone()
-                    } else {
-                        state.cwd.clone()
-                    },
-                )
-            } else {
-                (None, cwd.clone())
-            };
-            infos.push(TerminalSessionInfo {
-                session_id: sid.clone(),
-                cwd: stored_cwd,
-                title,
-            });
-        } else {
-            stale = true;
+    let outputs = request(&args.session_id, |reply| SessionCommand::Snapshot { reply }).await?;
+    let mut content = String::new();
+    if args.strip_ansi {
+        let mut stripper = AnsiStripper::default();
+        for out in &outputs {
+            stripper.strip(&out.data, &mut content);
+        }
+    } else {
+        for out in &outputs {
+            content.push_str(&out.data);
         }
     }
-    drop(map);
-    drop(states);
 
-    if stale {
-        let mut by_cwd = sessions_by_cwd_map()
-            .lock()
-            .map_err(|e| format!("锁错误: {e}"))?;
-        if infos.is_empty() {
-            by_cwd.remove(&canonical_key);
-        } else {
-            by_cwd.insert(
-                canonical_key,
-                infos.iter().map(|info| info.session_id.clone()).collect(),
-            );
-        }
+    let Some(path) = path else {
+        use tauri_plugin_file_picker::{FilePickerExt, WriteContentUriRequest};
+
+        app.file_picker()
+            .write_content_uri(WriteContentUriRequest {
+                content_uri: target.to_string(),
+                source_path: None,
+                content: Some(BASE64_STANDARD.encode(content.as_bytes())),
+            })
+            .map_err(|e| format!("写入 Content URI 失败 ({target}): {e}"))?;
+        return Ok(content.len());
+    };
+    std::fs::write(&path, &content).map_err(|e| format!("写入导出文件失败: {e}"))?;
+    Ok(content.len())
+}
+
+/// Host path of an export file that may not exist yet: the parent directory
+/// is resolved (and on Android confined to the rootfs), then the name joined.
+fn export_target_path(app: &AppHandle, target: &str) -> Result<PathBuf, String> {
+    let target = Path::new(target);
+    let file_name = target
+        .file_name()
+        .ok_or_else(|| "导出路径缺少文件名".to_string())?;
+    let parent = target
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .ok_or_else(|| "导出路径必须是绝对路径".to_string())?;
+    let parent = crate::tasks::project_host_dir(app, &parent.to_string_lossy())?;
+    if !parent.is_dir() {
+        return Err(format!("目录不存在: {}", parent.to_string_lossy()));
+    }
+    let path = parent.join(file_name);
+    if path.is_dir() {
+        return Err("导出路径是一个目录".into());
     }
+    Ok(path)
+}
 
-    Ok(infos)
+#[tauri::command]
+pub async fn clear_terminal_buffer(args: SessionIdArgs) -> Result<(), String> {
+    send_command(&args.session_id, SessionCommand::ClearBuffer).await
 }
 
+/// Report how many bytes of output the frontend has rendered. Sending acks
+/// opts the session into read throttling.
 #[tauri::command]
-pub fn set_terminal_session_title(args: SessionIdTitleArgs) -> Result<(), String> {
-    let session_id = args.session_id;
-    let title = args.title;
-    let mut ss = sessions_state_map()
-        .lock()
-        .map_err(|e| format!("锁错误: {e}"))?;
-    if let Some(state) = ss.get_mut(&session_id) {
-        state.title = title.and_then(|t| {
-            let trimmed = t.trim().to_string();
-            if trimmed.is_empty() {
-                None
-            } else {
-                Some(trimmed)
-            }
-        });
-        Ok(())
-    } else {
-        Err("会话未找到".into())
-    }
+pub async fn ack_terminal_output(args: SessionAckArgs) -> Result<(), String> {
+    send_command(&args.session_id, SessionCommand::Ack(args.bytes)).await
 }
 
+/// Stop reading from the pty; the child blocks once the kernel buffer fills.
 #[tauri::command]
-pub fn send_terminal_input(_app: tauri::AppHandle, args: SessionInputArgs) -> Result<(), String> {
-    let session_id = args.session_id;
-    let input = args.input;
-    let mut map = sessions_map().lock().map_err(|e| format!("锁错误: {e}"))?;
-    if let Some((_master, writer, _child)) = map.get_mut(&session_id) {
-        writer
-            .write_all(input.as_bytes())
-            .map_err(|e| format!("写入 pty 失败: {e}"))?;
-        Ok(())
+pub async fn pause_terminal_output(args: SessionIdArgs) -> Result<(), String> {
+    send_command(&args.session_id, SessionCommand::SetPaused(true)).await
+}
+
+#[tauri::command]
+pub async fn resume_terminal_output(args: SessionIdArgs) -> Result<(), String> {
+    send_command(&args.session_id, SessionCommand::SetPaused(false)).await
+}
+
+/// Start capturing the session's output into an asciicast v2 file and
+/// return its path.
+#[tauri::command]
+pub async fn start_terminal_recording(
+    app: AppHandle,
+    args: StartRecordingArgs,
+) -> Result<String, String> {
+    let handle = session_handle(&args.session_id).await?;
+    let file_name = format!(
+        "{}-{}.cast",
+        args.session_id,
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default()
+    );
+    let path = if args.in_project {
+        // The cwd of an SSH session names the remote host, not a project.
+        if handle.cwd.starts_with("ssh://") {
+            return Err("SSH 会话没有本地项目目录，无法录制到项目中".into());
+        }
+        crate::tasks::project_host_dir(&app, &handle.cwd)?
+            .join(".truidide")
+            .join(RECORDINGS_DIR)
+            .join(file_name)
     } else {
-        Err("会话未找到".into())
-    }
+        app.path()
+            .resolve(
+                format!("{RECORDINGS_DIR}/{file_name}"),
+                BaseDirectory::AppData,
+            )
+            .map_err(|e| e.to_string())?
+    };
+    let display = path.to_string_lossy().into_owned();
+    request(&args.session_id, |reply| SessionCommand::StartRecording {
+        path,
+        reply,
+    })
+    .await??;
+    Ok(display)
+}
+
+/// Finish the session's recording. Returns the file path, or `None` when the
+/// session was not being recorded.
+#[tauri::command]
+pub async fn stop_terminal_recording(args: SessionIdArgs) -> Result<Option<String>, String> {
+    let path = request(&args.session_id, |reply| SessionCommand::StopRecording {
+        reply,
+    })
+    .await?;
+    Ok(path.map(|path| path.to_string_lossy().into_owned()))
 }
 
 #[tauri::command]
-pub fn attach_terminal_session(
+pub async fn attach_terminal_session(
     window: tauri::Window,
     args: SessionIdArgs,
 ) -> Result<Vec<TerminalOutput>, String> {
-    let session_id = args.session_id;
     // register the window label as a subscriber and return the buffered
     // terminal outputs for replay.
     let label = window.label().to_string();
-    let items = {
-        let mut ss = sessions_state_map()
-            .lock()
-            .map_err(|e| format!("锁错误: {e}"))?;
-        let state = ss
-            .entry(session_id.clone())
-            .or_insert(SessionState::default());
-        state.subscribers.insert(label.clone());
-        let snapshot = state.buffer.iter().cloned().collect::<Vec<_>>();
-        snapshot
-    };
-    Ok(items)
+    request(&args.session_id, |reply| SessionCommand::Attach {
+        label,
+        reply,
+    })
+    .await
 }
 
 #[tauri::command]
-pub fn detach_terminal_session(window: tauri::Window, args: SessionIdArgs) -> Result<(), String> {
-    let session_id = args.session_id;
+pub async fn detach_terminal_session(
+    window: tauri::Window,
+    args: SessionIdArgs,
+) -> Result<(), String> {
     let label = window.label().to_string();
-    let mut ss = sessions_state_map()
-        .lock()
-        .map_err(|e| format!("锁错误: {e}"))?;
-    if let Some(state) = ss.get_mut(&session_id) {
-        state.subscribers.remove(&label);
-    }
+    // Detaching from a session that already ended is not an error.
+    let _ = send_command(&args.session_id, SessionCommand::Detach { label }).await;
     Ok(())
 }
 
 #[tauri::command]
-pub fn resize_terminal(_app: tauri::AppHandle, args: ResizeArgs) -> Result<(), String> {
-    let session_id = args.session_id;
-    let cols = args.cols;
-    let rows = args.rows;
-    let mut map = sessions_map().lock().map_err(|e| format!("锁错误: {e}"))?;
-    if let Some((master, _writer, _)) = map.get_mut(&session_id) {
-        master
-            .resize(PtySize {
-                rows: rows as u16,
-                cols: cols as u16,
-                pixel_width: 0,
-                pixel_height: 0,
-            })
-            .map_err(|e| format!("调整大小失败: {e}"))?;
-        Ok(())
-    } else {
-        Err("会话未找到".into())
+pub async fn resize_terminal(_app: tauri::AppHandle, args: ResizeArgs) -> Result<(), String> {
+    let cols = args.cols as u16;
+    let rows = args.rows as u16;
+    request(&args.session_id, |reply| SessionCommand::Resize {
+        cols,
+        rows,
+        reply,
+    })
+    .await?
+}
+
+#[cfg(unix)]
+fn parse_signal(raw: &str) -> Result<libc::c_int, String> {
+    let upper = raw.trim().to_ascii_uppercase();
+    let name = upper.strip_prefix("SIG").unwrap_or(&upper);
+    match name {
+        "INT" => Ok(libc::SIGINT),
+        "TERM" => Ok(libc::SIGTERM),
+        "KILL" => Ok(libc::SIGKILL),
+        "HUP" => Ok(libc::SIGHUP),
+        _ => Err(format!("不支持的信号: {raw}")),
     }
 }
 
 #[tauri::command]
-pub fn stop_terminal_session(_app: tauri::AppHandle, args: SessionIdArgs) -> Result<(), String> {
-    let session_id = args.session_id;
-    let mut map = sessions_map().lock().map_err(|e| format!("锁错误: {e}"))?;
-    if let Some((_master, _writer, mut child)) = map.remove(&session_id) {
-        let _ = child.kill();
-        let _ = child.wait();
-        // clean up state and cwd mapping
-        {
-            let mut ss = sessions_state_map()
-                .lock()
-                .map_err(|e| format!("锁错误: {e}"))?;
-            ss.remove(&session_id);
-        }
-        {
-            let mut by_cwd = sessions_by_cwd_map()
-                .lock()
-                .map_err(|e| format!("锁错误: {e}"))?;
-            let mut empty_keys: Vec<String> = Vec::new();
-            for (key, ids) in by_cwd.iter_mut() {
-                ids.retain(|sid| sid != &session_id);
-                if ids.is_empty() {
-                    empty_keys.push(key.clone());
-                }
-            }
-            for key in empty_keys {
-                by_cwd.remove(&key);
-            }
-        }
-        Ok(())
-    } else {
-        Err("会话未找到".into())
-    }
+pub async fn send_terminal_signal(
+    _app: tauri::AppHandle,
+    args: SessionSignalArgs,
+) -> Result<(), String> {
+    let signal = args.signal;
+    request(&args.session_id, |reply| SessionCommand::Signal {
+        signal,
+        reply,
+    })
+    .await?
+}
+
+#[tauri::command]
+pub async fn stop_terminal_session(
+    _app: tauri::AppHandle,
+    args: SessionIdArgs,
+) -> Result<(), String> {
+    request(&args.session_id, |reply| SessionCommand::Stop {
+        notify: false,
+        reply,
+    })
+    .await
 }
@@ -19,6 +19,12 @@ import ProjectWorkspace from "@/pages/ProjectWorkspace";
 import PluginsPage from "@/pages/PluginsPage";
 import TerminalPage from "@/pages/TerminalPage";
 import { ProotDownloadProgress } from "@/components/ProotDownloadProgress";
+import { onNotificationTapped } from "@/lib/notifications";
+import {
+  listenToOpenWith,
+  onIncomingIntent,
+  receiveIncomingIntent,
+} from "@/lib/open-with";
 import type { ProjectEntry } from "@/types/project";
 import { invoke } from "@tauri-apps/api/core";
 import { useCallback, useEffect, useMemo, useRef, useState } from "react";
@@ -151,6 +157,54 @@ function App() {
     }
   }, []);
 
+  useEffect(() => {
+    if (!isAndroid) {
+      return;
+    }
+
+    let unregister: (() => void) | undefined;
+
+    // 点击下载、导出、任务完成等系统通知后跳转到对应页面
+    onNotificationTapped((deepLink) => navigate(deepLink)).then((listener) => {
+      unregister = () => void listener.unregister();
+    });
+
+    return () => {
+      unregister?.();
+    };
+  }, [isAndroid, navigate]);
+
+  useEffect(() => {
+    if (!isAndroid) {
+      return;
+    }
+
+    let unlisten: (() => void) | undefined;
+    let unregister: (() => void) | undefined;
+
+    const receive = () => {
+      receiveIncomingIntent().catch((error) => {
+        console.error("导入传入文件失败", error);
+      });
+    };
+
+    // 导入完成后打开所在目录；先注册监听，再处理冷启动时传入的文件
+    listenToOpenWith((target) => openWorkspaceAtPath(target.projectPath)).then(
+      (fn) => {
+        unlisten = fn;
+        receive();
+      },
+    );
+    onIncomingIntent(receive).then((listener) => {
+      unregister = () => void listener.unregister();
+    });
+
+    return () => {
+      unlisten?.();
+      unregister?.();
+    };
+  }, [isAndroid, openWorkspaceAtPath]);
+
   useEffect(() => {
     if (typeof window === "undefined") {
       return;
@@ -9,6 +9,9 @@ import {
   listenToDownloadProgress,
   checkProotStatus,
   downloadProotAssets,
+  pauseProotDownload,
+  resumeProotDownload,
+  cancelProotDownload,
   formatBytes,
   type DownloadProgress,
 } from "@/lib/android-assets-download";
@@ -53,7 +56,9 @@ export function DownloadStatusProvider({ children }: { children: ReactNode }) {
     listenToDownloadProgress((prog) => {
       // 设置下载状态
       const downloading =
-        prog.stage === "downloading" || prog.stage === "extracting";
+        prog.stage === "downloading" ||
+        prog.stage === "extracting" ||
+        prog.stage === "paused";
       setIsDownloading(downloading);
 
       // 完成或出错后重置状态
@@ -66,6 +71,8 @@ export function DownloadStatusProvider({ children }: { children: ReactNode }) {
         setTimeout(() => {
           setIsDownloading(false);
         }, 3000);
+      } else if (prog.stage === "cancelled") {
+        setIsDownloading(false);
       }
     }).then((fn) => {
       unlisten = fn;
@@ -142,15 +149,26 @@ export function ProotDownloadProgress() {
       await downloadProotAssets();
     } catch (e) {
       console.error("下载失败:", e);
-      setProgress({
-        stage: "error",
-        message: String(e),
-      });
+      // 用户取消时保留“已取消”状态
+      setProgress((prev) =>
+        prev?.stage === "cancelled"
+          ? prev
+          : {
+              stage: "error",
+              message: String(e),
+            },
+      );
     } finally {
       setIsStarting(false);
     }
   };
 
+  const handleControl = (action: () => Promise<void>) => {
+    action().catch((e) => {
+      console.error("控制下载失败:", e);
+    });
+  };
+
   if (!isVisible) {
     return null;
   }
@@ -169,8 +187,10 @@ export function ProotDownloadProgress() {
             <h3 className="font-semibold">
               {!progress && !isReady && "需要下载运行环境"}
               {progress?.stage === "downloading" && "正在下载资源"}
+              {progress?.stage === "paused" && "下载已暂停"}
               {progress?.stage === "extracting" && "正在解压文件"}
               {progress?.stage === "completed" && "✓ 完成"}
+              {progress?.stage === "cancelled" && "已取消下载"}
               {progress?.stage === "error" && "✗ 错误"}
             </h3>
           </div>
@@ -216,6 +236,67 @@ export function ProotDownloadProgress() {
                 </span>
                 <span>{progress.percentage ?? 0}%</span>
               </div>
+
+              <div className="flex gap-2">
+                <Button
+                  className="flex-1"
+                  variant="outline"
+                  onClick={() => handleControl(pauseProotDownload)}
+                >
+                  暂停
+                </Button>
+                <Button
+                  className="flex-1"
+                  variant="outline"
+                  onClick={() => handleControl(cancelProotDownload)}
+                >
+                  取消
+                </Button>
+              </div>
+            </div>
+          )}
+
+          {/* 暂停状态 */}
+          {progress?.stage === "paused" && (
+            <div className="space-y-2">
+              <div className="text-sm text-muted-foreground">
+                {progress.file}
+              </div>
+              <div className="text-xs text-muted-foreground">
+                已下载 {formatBytes(progress.downloaded)}
+                {progress.total && ` / ${formatBytes(progress.total)}`}
+              </div>
+              <div className="flex gap-2">
+                <Button
+                  className="flex-1"
+                  onClick={() => handleControl(resumeProotDownload)}
+                >
+                  继续
+                </Button>
+                <Button
+                  className="flex-1"
+                  variant="outline"
+                  onClick={() => handleControl(cancelProotDownload)}
+                >
+                  取消
+                </Button>
+              </div>
+            </div>
+          )}
+
+          {/* 已取消 */}
+          {progress?.stage === "cancelled" && (
+            <div className="space-y-2">
+              <p className="text-sm text-muted-foreground">
+                已下载的文件已删除，可以稍后重新下载
+              </p>
+              <Button
+                className="w-full"
+                onClick={handleStartDownload}
+                disabled={isStarting}
+              >
+                重新下载
+              </Button>
             </div>
           )}
 
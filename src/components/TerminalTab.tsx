@@ -14,11 +14,16 @@ type Props = {
 const DEFAULT_TITLE = "终端";
 const MIN_FONT_SIZE = 8;
 const MAX_FONT_SIZE = 32;
+/** Rendered bytes batched into one `ack_terminal_output` call */
+const ACK_BATCH_BYTES = 64 * 1024;
+const utf8Encoder = new TextEncoder();
 
 type SessionInfo = {
   sessionId: string;
   title?: string | null;
   cwd: string;
+  /** Directory reported by the shell via OSC 7 */
+  currentCwd?: string | null;
 };
 
 type TerminalChunk = {
@@ -34,6 +39,7 @@ export default function TerminalTab({ projectPath }: Props) {
   const unlistenRef = useRef<UnlistenFn | null>(null);
   const attachTokenRef = useRef(0);
   const lastSeqRef = useRef<number>(0);
+  const pendingAckRef = useRef<number>(0);
   const [sessionIds, setSessionIds] = useState<string[]>([]);
   const [sessionTitles, setSessionTitles] = useState<Record<string, string>>(
     {},
@@ -389,6 +395,31 @@ export default function TerminalTab({ projectPath }: Props) {
     // eslint-disable-next-line react-hooks/exhaustive-deps
   }, []);
 
+  useEffect(() => {
+    // Programs such as tmux or vim copy through OSC 52; the backend decodes it
+    let disposed = false;
+    let unlisten: UnlistenFn | undefined;
+    listen<{ sessionId: string; text: string }>(
+      "terminal-clipboard",
+      (event) => {
+        if (event.payload.sessionId !== sessionIdRef.current) return;
+        navigator.clipboard?.writeText(event.payload.text).catch(() => {});
+      },
+    )
+      .then((dispose) => {
+        if (disposed) {
+          dispose();
+        } else {
+          unlisten = dispose;
+        }
+      })
+      .catch(() => {});
+    return () => {
+      disposed = true;
+      unlisten?.();
+    };
+  }, []);
+
   const getDisplayName = useCallback(
     (sessionId: string, index: number) => {
       const raw = sessionTitles[sessionId];
@@ -472,7 +503,19 @@ export default function TerminalTab({ projectPath }: Props) {
         const data =
           typeof payload.data === "string" ? payload.data : String(payload);
         if (!Number.isNaN(seq) && seq > lastSeqRef.current) {
-          term.write(data);
+          const bytes = utf8Encoder.encode(data).length;
+          // Acknowledge once xterm has parsed the chunk so the backend can
+          // throttle the pty while rendering lags behind.
+          term.write(data, () => {
+            if (attachTokenRef.current !== token) return;
+            pendingAckRef.current += bytes;
+            if (pendingAckRef.current < ACK_BATCH_BYTES) return;
+            const acked = pendingAckRef.current;
+            pendingAckRef.current = 0;
+            invoke("ack_terminal_output", {
+              args: { sessionId, bytes: acked },
+            }).catch(() => {});
+          });
           lastSeqRef.current = seq;
         }
       };
@@ -487,6 +530,7 @@ export default function TerminalTab({ projectPath }: Props) {
       sessionIdRef.current = sessionId;
       unlistenRef.current = unlisten;
       lastSeqRef.current = 0;
+      pendingAckRef.current = 0;
 
       const snapshot = await invoke<TerminalChunk[]>(
         "attach_terminal_session",
@@ -0,0 +1,197 @@
+import { useEffect, useRef, useState } from "react";
+import { listen } from "@tauri-apps/api/event";
+
+import {
+  loadWebExtension,
+  pluginBrokerCall,
+  unloadWebExtension,
+} from "@/lib/plugins";
+import type {
+  BrokerRequest,
+  PluginBusMessageEvent,
+  PluginToastEvent,
+} from "@/types/plugin";
+
+// 扩展页面与宿主之间的 postMessage 协议
+type ExtensionMessage = {
+  type: "truidide/broker";
+  id: number | string;
+  request: BrokerRequest;
+};
+
+function isExtensionMessage(data: unknown): data is ExtensionMessage {
+  return (
+    typeof data === "object" &&
+    data !== null &&
+    "type" in data &&
+    data.type === "truidide/broker" &&
+    "id" in data &&
+    "request" in data
+  );
+}
+
+function describeError(err: unknown): string {
+  return typeof err === "string"
+    ? err
+    : err instanceof Error
+      ? err.message
+      : "调用插件接口失败";
+}
+
+type WebExtensionHostProps = {
+  pluginId: string;
+  className?: string;
+};
+
+/**
+ * 在沙箱 iframe 中加载 Web 扩展，并代为转发其代理请求。
+ *
+ * 令牌只保存在宿主页面中，扩展无法通过自报插件 ID 获得其他插件的权限。
+ */
+export function WebExtensionHost({
+  pluginId,
+  className,
+}: WebExtensionHostProps) {
+  const iframeRef = useRef<HTMLIFrameElement | null>(null);
+  const [entry, setEntry] = useState<string | null>(null);
+  const [error, setError] = useState<string | null>(null);
+  const [toast, setToast] = useState<string | null>(null);
+
+  useEffect(() => {
+    let cancelled = false;
+    let token: string | null = null;
+    const disposers: (() => void)[] = [];
+
+    const post = (message: unknown) => {
+      // 沙箱页面的源是不透明的，只能以 "*" 投递
+      iframeRef.current?.contentWindow?.postMessage(message, "*");
+    };
+
+    const handleMessage = (event: MessageEvent) => {
+      const frame = iframeRef.current?.contentWindow;
+      if (
+        !frame ||
+        event.source !== frame ||
+        !isExtensionMessage(event.data)
+      ) {
+        return;
+      }
+      const { id, request } = event.data;
+      if (!token) {
+        post({
+          type: "truidide/broker-result",
+          id,
+          error: "扩展尚未加载完成",
+        });
+        return;
+      }
+      pluginBrokerCall(token, request)
+        .then((response) => {
+          post({ type: "truidide/broker-result", id, response });
+        })
+        .catch((err) => {
+          post({
+            type: "truidide/broker-result",
+            id,
+            error: describeError(err),
+          });
+        });
+    };
+    window.addEventListener("message", handleMessage);
+
+    loadWebExtension(pluginId)
+      .then((loaded) => {
+        if (cancelled) {
+          void unloadWebExtension(loaded.token);
+          return;
+        }
+        token = loaded.token;
+        setEntry(loaded.entry);
+      })
+      .catch((err) => {
+        if (!cancelled) {
+          setError(describeError(err));
+        }
+      });
+
+    listen<PluginBusMessageEvent>("truidide://plugins/bus", (event) => {
+      if (event.payload.targetPluginId !== pluginId) {
+        return;
+      }
+      const { topic, sourcePluginId, payload } = event.payload;
+      post({
+        type: "truidide/bus",
+        message: { topic, sourcePluginId, payload },
+      });
+    })
+      .then((dispose) => {
+        if (cancelled) {
+          dispose();
+        } else {
+          disposers.push(dispose);
+        }
+      })
+      .catch(() => {
+        // 浏览器环境中忽略事件订阅失败
+      });
+
+    listen<PluginToastEvent>("truidide://plugins/toast", (event) => {
+      if (event.payload.pluginId === pluginId) {
+        setToast(event.payload.message);
+      }
+    })
+      .then((dispose) => {
+        if (cancelled) {
+          dispose();
+        } else {
+          disposers.push(dispose);
+        }
+      })
+      .catch(() => {
+        // 浏览器环境中忽略事件订阅失败
+      });
+
+    return () => {
+      cancelled = true;
+      window.removeEventListener("message", handleMessage);
+      disposers.forEach((dispose) => dispose());
+      if (token) {
+        void unloadWebExtension(token);
+      }
+    };
+  }, [pluginId]);
+
+  useEffect(() => {
+    if (!toast) {
+      return;
+    }
+    const timer = window.setTimeout(() => setToast(null), 3000);
+    return () => window.clearTimeout(timer);
+  }, [toast]);
+
+  if (error) {
+    return <p className="text-sm text-destructive">{error}</p>;
+  }
+
+  return (
+    <div className="relative flex min-h-0 flex-1 flex-col">
+      {entry ? (
+        <iframe
+          ref={iframeRef}
+          src={entry}
+          title={pluginId}
+          // 不授予 allow-same-origin，扩展无法访问宿主页面及其 IPC
+          sandbox="allow-scripts allow-forms"
+          className={className ?? "h-[60vh] w-full rounded-md border"}
+        />
+      ) : (
+        <p className="text-sm text-muted-foreground">正在加载扩展…</p>
+      )}
+      {toast && (
+        <p className="absolute bottom-3 left-1/2 -translate-x-1/2 rounded-md bg-foreground px-3 py-1.5 text-xs text-background shadow">
+          {toast}
+        </p>
+      )}
+    </div>
+  );
+}
@@ -17,9 +17,18 @@ export type DownloadProgress =
       file: string;
       percentage?: number;
     }
+  | {
+      stage: "paused";
+      file: string;
+      downloaded: number;
+      total?: number;
+    }
   | {
       stage: "completed";
     }
+  | {
+      stage: "cancelled";
+    }
   | {
       stage: "error";
       message: string;
@@ -35,11 +44,185 @@ export async function checkProotStatus(): Promise<boolean> {
   }
 }
 
+export type EnvironmentStatus = {
+  required: boolean;
+  ready: boolean;
+  downloaded: boolean;
+  extracted: boolean;
+  rootfsArch?: string;
+  diskUsage: number;
+  baseDir?: string;
+};
+
+// 获取运行环境的详细状态（桌面端无需 proot，始终就绪）
+export async function getEnvironmentStatus(): Promise<EnvironmentStatus> {
+  return await invoke<EnvironmentStatus>("get_environment_status");
+}
+
+export type RootfsDiskUsage = {
+  total: number;
+  rootfs: number;
+  home: number;
+  archive: number;
+};
+
+export async function getRootfsDiskUsage(): Promise<RootfsDiskUsage> {
+  return await invoke<RootfsDiskUsage>("get_rootfs_disk_usage");
+}
+
+// 删除运行环境，/root 中的项目会保留
+export async function deleteRootfs(): Promise<void> {
+  await invoke("delete_rootfs");
+}
+
+// 重建运行环境：优先使用保留的压缩包，否则重新下载
+export async function resetRootfs(): Promise<void> {
+  await invoke("reset_rootfs");
+}
+
+// 将 rootfs（不含缓存）导出为 tar.xz 快照，targetUri 可以是 SAF 返回的 Content URI
+export async function exportRootfsSnapshot(targetUri: string): Promise<number> {
+  return await invoke<number>("export_rootfs_snapshot", { targetUri });
+}
+
+// 用快照替换当前 rootfs，用于迁移到新设备
+export async function importRootfsSnapshot(source: string): Promise<void> {
+  await invoke("import_rootfs_snapshot", { source });
+}
+
+export type RepairAction =
+  | { action: "fixPermissions" }
+  | { action: "reinstallProot" }
+  | { action: "restoreHome" }
+  | { action: "restoreRootfsFiles"; paths: string[] }
+  | { action: "rebuildRootfs" };
+
+export type EnvironmentReport = {
+  healthy: boolean;
+  issues: string[];
+  repairs: RepairAction[];
+};
+
+// 校验 proot 和 rootfs 的完整性；桌面端返回 null
+export async function verifyEnvironment(): Promise<EnvironmentReport | null> {
+  return await invoke<EnvironmentReport | null>("verify_environment");
+}
+
+// 只修复损坏的部分，返回修复后的校验结果
+export async function repairEnvironment(): Promise<EnvironmentReport> {
+  return await invoke<EnvironmentReport>("repair_environment");
+}
+
+export type DiagnosticFinding = {
+  id: string;
+  severity: "error" | "warning" | "info";
+  message: string;
+  suggestion?: string;
+};
+
+export type DiagnosticsReport = {
+  passed: boolean;
+  findings: DiagnosticFinding[];
+  output?: string;
+};
+
+// 在 proot 中执行测试命令并分析失败原因；桌面端返回 null
+export async function runEnvironmentDiagnostics(): Promise<DiagnosticsReport | null> {
+  return await invoke<DiagnosticsReport | null>("run_environment_diagnostics");
+}
+
+export type ProotUpdateInfo = {
+  installedVersion?: string;
+  latestVersion: string;
+  pending: { name: string; size?: number }[];
+  downloadSize: number;
+};
+
+// 检查运行环境资源更新；桌面端返回 null
+export async function checkProotUpdates(): Promise<ProotUpdateInfo | null> {
+  return await invoke<ProotUpdateInfo | null>("check_proot_updates");
+}
+
+// 只下载并应用有变化的资源压缩包，进度通过 proot-download-progress 事件报告
+export async function applyProotUpdates(): Promise<void> {
+  await invoke("apply_proot_updates");
+}
+
+export type BindMount = {
+  host: string;
+  guest: string;
+};
+
+export type ProotSettings = {
+  bindMounts: BindMount[];
+  extraArgs: string[];
+  // 关闭 proot 的 seccomp 加速，用于内核 seccomp 策略拦截 proot 的设备
+  noSeccomp?: boolean;
+};
+
+// 自定义挂载和 proot 参数，对之后启动的终端、命令和插件进程生效
+export async function getProotSettings(): Promise<ProotSettings> {
+  return await invoke<ProotSettings>("get_proot_settings");
+}
+
+export async function setProotSettings(settings: ProotSettings): Promise<void> {
+  await invoke("set_proot_settings", { settings });
+}
+
+export type EnvironmentStorage = {
+  // 存放 proot 目录的位置，null 表示内部存储
+  location: string | null;
+};
+
+export type StorageMigrationProgress = {
+  copiedBytes: number;
+  totalBytes: number;
+  percentage: number;
+};
+
+export async function getEnvironmentStorage(): Promise<EnvironmentStorage> {
+  return await invoke<EnvironmentStorage>("get_environment_storage");
+}
+
+// 迁移运行环境到指定目录（null 迁回内部存储），迁移前需关闭终端
+export async function migrateEnvironmentStorage(
+  target: string | null,
+): Promise<EnvironmentStorage> {
+  return await invoke<EnvironmentStorage>("migrate_environment_storage", {
+    target,
+  });
+}
+
+export async function listenToStorageMigrationProgress(
+  callback: (progress: StorageMigrationProgress) => void,
+): Promise<UnlistenFn> {
+  return await listen<StorageMigrationProgress>(
+    "truidide://environment/migration-progress",
+    (event) => {
+      callback(event.payload);
+    },
+  );
+}
+
 // 开始下载 proot 资源
 export async function downloadProotAssets(): Promise<void> {
   await invoke("download_proot_assets");
 }
 
+// 暂停下载，已下载部分保留，继续时从断点恢复
+export async function pauseProotDownload(): Promise<void> {
+  await invoke("pause_proot_download");
+}
+
+export async function resumeProotDownload(): Promise<void> {
+  await invoke("resume_proot_download");
+}
+
+// 取消下载并删除已下载的资源
+export async function cancelProotDownload(): Promise<void> {
+  await invoke("cancel_proot_download");
+}
+
 // 监听下载进度事件
 export async function listenToDownloadProgress(
   callback: (progress: DownloadProgress) => void,
@@ -3,19 +3,16 @@ import { listen } from "@tauri-apps/api/event";
 import {
   LSPClient,
   languageServerExtensions,
-  type LSPClientExtension,
   type Transport,
 } from "@codemirror/lsp-client";
 
 import { sendLspPayload, stopLspSession } from "@/lib/plugins";
-import { LspPathMapper, type PathMapping } from "@/lib/lsp-path-mapper";
 
 type MessageHandler = (value: string) => void;
 
 type TauriTransportOptions = {
-  initializationOptions?: unknown;
-  workspaceFolders?: unknown;
-  pathMapper?: LspPathMapper;
+  serverCapabilities?: Record<string, unknown>;
+  serverInfo?: unknown;
 };
 
 const ENABLE_LSP_DEBUG_LOGS =
@@ -51,34 +48,35 @@ function logLspDebug(sessionId: string, message: string, extra?: unknown) {
 }
 
 class TauriTransport implements Transport {
-  private readonly sessionId: string;
-  private readonly initializationOptions?: unknown;
-  private readonly workspaceFolders?: unknown;
-  private readonly pathMapper: LspPathMapper;
+  private sessionId: string;
+  private readonly serverCapabilities: Record<string, unknown>;
+  private readonly serverInfo?: unknown;
   private handlers = new Set<MessageHandler>();
   private unlisten: UnlistenFn | null = null;
   private disposed = false;
 
   constructor(sessionId: string, options?: TauriTransportOptions) {
     this.sessionId = sessionId;
-    this.initializationOptions = options?.initializationOptions;
-    this.workspaceFolders = options?.workspaceFolders;
-    this.pathMapper = options?.pathMapper || new LspPathMapper();
+    this.serverCapabilities = options?.serverCapabilities ?? {};
+    this.serverInfo = options?.serverInfo;
     logLspDebug(this.sessionId, "transport initialized");
+    this.listenToSession(sessionId);
+  }
+
+  // 每个会话的消息走独立事件，窗口只接收自己会话的消息
+  private listenToSession(sessionId: string): void {
     listen<{ sessionId?: string; body?: unknown }>(
-      "truidide://lsp/message",
+      `truidide://lsp/message/${sessionId}`,
       (event) => {
         if (event.payload?.sessionId !== this.sessionId) {
           return;
         }
         const body = event.payload.body;
 
-        // 解析并转换路径 (从 LSP 服务器接收的消息)
+        // 宿主已将 guest 路径转换为 host 路径
         let decoded: any;
         try {
           decoded = typeof body === "string" ? JSON.parse(body) : body;
-          // 转换 guest 路径为 host 路径
-          decoded = this.pathMapper.transformLspMessage(decoded, "toHost");
         } catch (error) {
           logLspDebug(this.sessionId, "<= message (failed to parse)", body);
           return;
@@ -100,59 +98,71 @@ class TauriTransport implements Transport {
       },
     )
       .then((unlisten) => {
-        if (this.disposed) {
+        // 监听建立前已销毁或已切换到其他会话
+        if (this.disposed || this.sessionId !== sessionId) {
           unlisten();
         } else {
           this.unlisten = unlisten;
         }
-        logLspDebug(this.sessionId, "transport listener attached");
+        logLspDebug(sessionId, "transport listener attached");
       })
       .catch((error) => {
         console.error("监听 LSP 消息失败", error);
       });
   }
 
+  // 宿主已完成 initialize 握手：用宿主返回的服务端能力在本地应答
+  private answerInitialize(id: unknown): void {
+    const raw = JSON.stringify({
+      jsonrpc: "2.0",
+      id,
+      result: {
+        capabilities: this.serverCapabilities,
+        serverInfo: this.serverInfo ?? undefined,
+      },
+    });
+    queueMicrotask(() => {
+      for (const handler of this.handlers) {
+        handler(raw);
+      }
+    });
+  }
+
   send(message: string): void {
     try {
       const payload = JSON.parse(message);
-
-      // 转换 host 路径为 guest 路径
-      const transformed = this.pathMapper.transformLspMessage(
-        payload,
-        "toGuest",
-      );
-
-      if (ENABLE_LSP_DEBUG_LOGS) {
-        logLspDebug(
-          this.sessionId,
-          `=> ${describePayload(transformed)}`,
-          transformed,
-        );
+      if (payload?.method === "initialize") {
+        logLspDebug(this.sessionId, "=> request:initialize (answered locally)");
+        this.answerInitialize(payload.id);
+        return;
+      }
+      if (payload?.method === "initialized") {
+        return;
       }
 
-      if (
-        transformed &&
-        typeof transformed === "object" &&
-        transformed.method === "initialize"
-      ) {
-        const params =
-          typeof transformed.params === "object" && transformed.params !== null
-            ? { ...transformed.params }
-            : {};
-        if (this.initializationOptions !== undefined) {
-          params.initializationOptions ??= this.initializationOptions;
-        }
-        if (this.workspaceFolders !== undefined) {
-          params.workspaceFolders ??= this.workspaceFolders;
-        }
-        transformed.params = params;
+      if (ENABLE_LSP_DEBUG_LOGS) {
+        logLspDebug(this.sessionId, `=> ${describePayload(payload)}`, payload);
       }
-      void sendLspPayload({ sessionId: this.sessionId, payload: transformed });
+      // host 路径由宿主转换为 guest 路径
+      void sendLspPayload({ sessionId: this.sessionId, payload });
     } catch (error) {
       console.error("发送 LSP 消息失败", error);
     }
   }
 
+  /** 语言服务器重启后改为收发新会话的消息 */
+  retarget(sessionId: string): void {
+    logLspDebug(this.sessionId, `transport retargeted to ${sessionId}`);
+    this.sessionId = sessionId;
+    if (this.unlisten) {
+      this.unlisten();
+      this.unlisten = null;
+    }
+    if (!this.disposed) {
+      this.listenToSession(sessionId);
+    }
+  }
+
   subscribe(handler: MessageHandler): void {
     this.handlers.add(handler);
   }
@@ -187,39 +197,32 @@ export type LspClientEntry = {
   sessionId: string;
 };
 
+/**
+ * 宿主重启语言服务器并完成 initialize 后调用：切换到新会话并重新打开文档，
+ * 让服务端恢复文档状态。
+ */
+export function resyncLspClient(entry: LspClientEntry, sessionId: string) {
+  entry.transport.retarget(sessionId);
+  entry.sessionId = sessionId;
+  for (const file of entry.client.workspace.files) {
+    entry.client.didOpen(file);
+  }
+}
+
 export async function createLspClient(options: {
   sessionId: string;
   rootUri: string;
-  clientCapabilities?: Record<string, unknown>;
-  initializationOptions?: unknown;
-  workspaceFolders?: unknown;
+  serverCapabilities?: Record<string, unknown>;
+  serverInfo?: unknown;
   timeoutMs?: number;
-  pathMapping?: PathMapping | null;
 }): Promise<LspClientEntry> {
-  const baseExtensions = languageServerExtensions();
-  const configExtensions: (
-    | (typeof baseExtensions)[number]
-    | LSPClientExtension
-  )[] = [...baseExtensions];
-
-  if (
-    options.clientCapabilities &&
-    Object.keys(options.clientCapabilities).length > 0
-  ) {
-    configExtensions.push({
-      clientCapabilities: options.clientCapabilities,
-    });
-  }
-
-  const pathMapper = new LspPathMapper(options.pathMapping || null);
   const transport = new TauriTransport(options.sessionId, {
-    initializationOptions: options.initializationOptions,
-    workspaceFolders: options.workspaceFolders,
-    pathMapper,
+    serverCapabilities: options.serverCapabilities,
+    serverInfo: options.serverInfo,
   });
   const client = new LSPClient({
     rootUri: options.rootUri,
-    extensions: configExtensions,
+    extensions: languageServerExtensions(),
     timeout: options.timeoutMs ?? 15_000,
   });
   client.connect(transport);
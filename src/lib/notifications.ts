@@ -0,0 +1,14 @@
+// 系统通知（由 tauri-plugin-toast 提供，仅 Android 生效）
+
+import { addPluginListener, type PluginListener } from "@tauri-apps/api/core";
+
+// 点击带 deepLink 的通知时触发，deepLink 为前端路由
+export async function onNotificationTapped(
+  handler: (deepLink: string) => void,
+): Promise<PluginListener> {
+  return await addPluginListener<{ deepLink: string }>(
+    "toast",
+    "notificationTapped",
+    (event) => handler(event.deepLink),
+  );
+}
@@ -0,0 +1,35 @@
+// 处理其他应用通过“打开方式”或分享传入的文件（仅 Android）
+
+import {
+  addPluginListener,
+  invoke,
+  type PluginListener,
+} from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+
+export type OpenWithTarget = {
+  // 作为工作区打开的目录（proot 内路径）
+  projectPath: string;
+  // 需要聚焦的文件，导入整个目录时为 null
+  filePath: string | null;
+};
+
+// 导入待处理的传入文件，完成后后端发出 truidide://open-with 事件
+export async function receiveIncomingIntent(): Promise<void> {
+  await invoke("receive_incoming_intent");
+}
+
+export async function listenToOpenWith(
+  callback: (target: OpenWithTarget) => void,
+): Promise<UnlistenFn> {
+  return await listen<OpenWithTarget>("truidide://open-with", (event) => {
+    callback(event.payload);
+  });
+}
+
+// 应用运行中收到新的传入文件时触发
+export async function onIncomingIntent(
+  handler: () => void,
+): Promise<PluginListener> {
+  return await addPluginListener("file-picker", "incomingIntent", handler);
+}
@@ -1,27 +1,317 @@
 import { invoke } from "@tauri-apps/api/core";
 
-import type { PluginSummary, StartLspSessionResult } from "@/types/plugin";
+import type {
+  BrokerRequest,
+  BrokerResponse,
+  LanguagePluginDefaults,
+  LoadedWebExtension,
+  LspIdleSettings,
+  LspSessionStatus,
+  LspTraceSettings,
+  ManifestValidation,
+  MarketplaceListing,
+  MarketplaceSettings,
+  PluginPermissions,
+  PluginSettings,
+  PluginSummary,
+  PluginTrustSettings,
+  ProjectPluginActivation,
+  PluginsUpdatedEvent,
+  StartLspSessionResult,
+  UnsignedPluginPolicy,
+} from "@/types/plugin";
 
 export async function listPlugins(): Promise<PluginSummary[]> {
   return invoke<PluginSummary[]>("list_plugins");
 }
 
+/**
+ * Localize plugin names and descriptions for `locale` from now on (`null`
+ * follows the system). Resolves with the relocalized plugin list.
+ */
+export async function setPluginLocale(
+  locale: string | null,
+): Promise<PluginSummary[]> {
+  return invoke<PluginSummary[]>("set_plugin_locale", { locale });
+}
+
 export async function refreshPlugins(): Promise<PluginSummary[]> {
   return invoke<PluginSummary[]>("refresh_plugins");
 }
 
-export async function importPlugin(sourcePath: string): Promise<PluginSummary> {
-  return invoke<PluginSummary>("import_plugin", { sourcePath });
+export async function refreshPlugin(
+  pluginId: string,
+): Promise<PluginSummary | null> {
+  return invoke<PluginSummary | null>("refresh_plugin", { pluginId });
+}
+
+export async function setPluginEnabled(
+  pluginId: string,
+  enabled: boolean,
+): Promise<PluginSummary> {
+  return invoke<PluginSummary>("set_plugin_enabled", { pluginId, enabled });
+}
+
+export async function getPluginSettings(
+  pluginId: string,
+  projectPath?: string,
+): Promise<PluginSettings> {
+  return invoke<PluginSettings>("get_plugin_settings", {
+    pluginId,
+    projectPath: projectPath ?? null,
+  });
+}
+
+export async function setPluginSettings(
+  pluginId: string,
+  values: Record<string, unknown>,
+  projectPath?: string,
+): Promise<PluginSettings> {
+  return invoke<PluginSettings>("set_plugin_settings", {
+    args: { pluginId, values, projectPath: projectPath ?? null },
+  });
+}
+
+export async function getProjectPlugins(
+  projectPath: string,
+): Promise<ProjectPluginActivation> {
+  return invoke<ProjectPluginActivation>("get_project_plugins", { projectPath });
+}
+
+export async function setProjectPlugins(
+  projectPath: string,
+  activation: ProjectPluginActivation,
+): Promise<void> {
+  await invoke("set_project_plugins", { projectPath, activation });
+}
+
+export function applyPluginsUpdate(
+  plugins: PluginSummary[],
+  update: PluginsUpdatedEvent,
+): PluginSummary[] {
+  const removed = new Set(update.removed);
+  const upserted = new Map(update.upserted.map((plugin) => [plugin.id, plugin]));
+  const next = plugins
+    .filter((plugin) => !removed.has(plugin.id))
+    .map((plugin) => {
+      const replacement = upserted.get(plugin.id);
+      if (replacement) {
+        upserted.delete(plugin.id);
+        return replacement;
+      }
+      return plugin;
+    });
+  return next.concat(Array.from(upserted.values()));
+}
+
+/**
+ * Check a manifest without importing it. Accepts the manifest's JSON text,
+ * the `truid-plugin.json` file or the plugin directory.
+ */
+export async function validatePluginManifest(
+  pathOrJson: string,
+): Promise<ManifestValidation> {
+  return invoke<ManifestValidation>("validate_plugin_manifest", { pathOrJson });
+}
+
+/**
+ * `sourcePath` may also be a git URL; `gitRef` then selects a tag or branch.
+ * Rejects with an `ImportPluginError`; `permissions-required` asks the user
+ * to consent, after which the import is retried with `grantPermissions`.
+ */
+export async function importPlugin(
+  sourcePath: string,
+  gitRef?: string,
+  grantPermissions = false,
+): Promise<PluginSummary> {
+  return invoke<PluginSummary>("import_plugin", {
+    sourcePath,
+    gitRef: gitRef ?? null,
+    grantPermissions,
+  });
+}
+
+/**
+ * Writes a zip that `importPlugin` accepts. `target` is a zip path or a
+ * directory; resolves with the written path.
+ */
+export async function exportPlugin(
+  pluginId: string,
+  target: string,
+): Promise<string> {
+  return invoke<string>("export_plugin", { pluginId, target });
+}
+
+export async function searchMarketplace(
+  query?: string,
+  refresh = false,
+): Promise<MarketplaceListing[]> {
+  return invoke<MarketplaceListing[]>("search_marketplace", {
+    query: query ?? null,
+    refresh,
+  });
+}
+
+export async function getPluginListing(
+  pluginId: string,
+): Promise<MarketplaceListing> {
+  return invoke<MarketplaceListing>("get_plugin_listing", { pluginId });
+}
+
+/** Progress is reported through `truidide://plugins/install-progress`. */
+export async function installPluginFromUrl(
+  url: string,
+  sha256?: string | null,
+  grantPermissions = false,
+): Promise<PluginSummary> {
+  return invoke<PluginSummary>("install_plugin_from_url", {
+    args: { url, sha256: sha256 ?? null, grantPermissions },
+  });
+}
+
+/** 为 Web 扩展签发调用代理接口所需的令牌 */
+export async function loadWebExtension(
+  pluginId: string,
+): Promise<LoadedWebExtension> {
+  return invoke<LoadedWebExtension>("load_web_extension", { pluginId });
+}
+
+export async function unloadWebExtension(token: string): Promise<void> {
+  await invoke("unload_web_extension", { token });
+}
+
+/** 以 `token` 对应的 Web 扩展身份执行代理请求 */
+export async function pluginBrokerCall(
+  token: string,
+  request: BrokerRequest,
+): Promise<BrokerResponse> {
+  return invoke<BrokerResponse>("plugin_broker_call", { token, request });
+}
+
+/** 用于向用户说明插件请求的权限 */
+export function describePluginPermissions(
+  permissions: PluginPermissions,
+): string[] {
+  const lines = [
+    permissions.filesystem === "full"
+      ? "访问整个 Linux 环境中的文件（包括 /root）"
+      : "仅访问当前工作区和插件目录",
+  ];
+  if (permissions.network) {
+    lines.push("访问网络");
+  }
+  if (permissions.spawnProcesses) {
+    lines.push("通过宿主启动其他程序");
+  }
+  return lines;
+}
+
+export async function getPluginTrustSettings(): Promise<PluginTrustSettings> {
+  return invoke<PluginTrustSettings>("get_plugin_trust_settings");
+}
+
+export async function setUnsignedPluginPolicy(
+  policy: UnsignedPluginPolicy,
+): Promise<PluginTrustSettings> {
+  return invoke<PluginTrustSettings>("set_unsigned_plugin_policy", { policy });
+}
+
+export async function addTrustedPluginKey(
+  label: string,
+  publicKey: string,
+): Promise<PluginTrustSettings> {
+  return invoke<PluginTrustSettings>("add_trusted_plugin_key", {
+    label,
+    publicKey,
+  });
+}
+
+export async function removeTrustedPluginKey(
+  publicKey: string,
+): Promise<PluginTrustSettings> {
+  return invoke<PluginTrustSettings>("remove_trusted_plugin_key", { publicKey });
+}
+
+export async function getMarketplaceSettings(): Promise<MarketplaceSettings> {
+  return invoke<MarketplaceSettings>("get_marketplace_settings");
+}
+
+export async function setMarketplaceSettings(
+  settings: MarketplaceSettings,
+): Promise<void> {
+  await invoke("set_marketplace_settings", { settings });
+}
+
+/**
+ * Rejects with a `RemovePluginError`; pass `force` to stop the plugin's
+ * running sessions instead of failing with `in-use`.
+ */
+export async function removePlugin(
+  pluginId: string,
+  force = false,
+): Promise<PluginSummary[]> {
+  return invoke<PluginSummary[]>("remove_plugin", { pluginId, force });
+}
+
+export async function pluginForLanguage(
+  languageId: string,
+  projectPath?: string,
+): Promise<PluginSummary | null> {
+  return invoke<PluginSummary | null>("plugin_for_language", {
+    languageId,
+    projectPath: projectPath ?? null,
+  });
+}
+
+/** All candidates for the language, the preferred one first */
+export async function pluginsForLanguage(
+  languageId: string,
+  projectPath?: string,
+): Promise<PluginSummary[]> {
+  return invoke<PluginSummary[]>("plugins_for_language", {
+    languageId,
+    projectPath: projectPath ?? null,
+  });
+}
+
+export async function getLanguagePluginDefaults(): Promise<LanguagePluginDefaults> {
+  return invoke<LanguagePluginDefaults>("get_language_plugin_defaults");
+}
+
+/** `pluginId` 为 `null` 时恢复按优先级选择 */
+export async function setLanguagePluginDefault(
+  languageId: string,
+  pluginId: string | null,
+): Promise<LanguagePluginDefaults> {
+  return invoke<LanguagePluginDefaults>("set_language_plugin_default", {
+    args: { languageId, pluginId },
+  });
 }
 
-export async function removePlugin(pluginId: string): Promise<PluginSummary[]> {
-  return invoke<PluginSummary[]>("remove_plugin", { pluginId });
+/**
+ * Start or reuse the language server for a file. Resolves with `null` when
+ * no enabled plugin handles the file's language.
+ */
+export async function ensureLspForFile(args: {
+  path: string;
+  projectPath: string;
+  languageId?: string;
+  /** 多根工作区的其他根目录 */
+  additionalWorkspacePaths?: string[];
+  clientCapabilities?: unknown;
+  workspaceFolders?: unknown;
+}): Promise<StartLspSessionResult | null> {
+  return invoke<StartLspSessionResult | null>("ensure_lsp_for_file", {
+    args,
+  });
 }
 
 export async function startLspSession(args: {
   pluginId: string;
   languageId?: string;
   workspacePath: string;
+  /** 多根工作区的其他根目录 */
+  additionalWorkspacePaths?: string[];
   clientCapabilities?: unknown;
   workspaceFolders?: unknown;
   initializationOptions?: unknown;
@@ -31,6 +321,7 @@ export async function startLspSession(args: {
       pluginId: args.pluginId,
       languageId: args.languageId,
       workspacePath: args.workspacePath,
+      additionalWorkspacePaths: args.additionalWorkspacePaths,
       clientCapabilities: args.clientCapabilities,
       workspaceFolders: args.workspaceFolders,
       initializationOptions: args.initializationOptions,
@@ -50,6 +341,49 @@ export async function sendLspPayload(args: {
   });
 }
 
+/** 取消仍在等待响应的 LSP 请求（发送 `$/cancelRequest`） */
+export async function cancelLspRequest(
+  sessionId: string,
+  id: number | string,
+): Promise<void> {
+  await invoke("cancel_lsp_request", { args: { sessionId, id } });
+}
+
+export async function getLspTraceSettings(): Promise<LspTraceSettings> {
+  return invoke<LspTraceSettings>("get_lsp_trace_settings");
+}
+
+/** 仅对之后启动的会话生效 */
+export async function setLspTraceSettings(
+  settings: LspTraceSettings,
+): Promise<void> {
+  await invoke("set_lsp_trace_settings", { settings });
+}
+
+export async function getLspIdleSettings(): Promise<LspIdleSettings> {
+  return invoke<LspIdleSettings>("get_lsp_idle_settings");
+}
+
+export async function setLspIdleSettings(
+  settings: LspIdleSettings,
+): Promise<void> {
+  await invoke("set_lsp_idle_settings", { settings });
+}
+
+/** 读取会话日志的最后 `tailLines` 行（默认 200） */
+export async function getLspSessionLog(
+  sessionId: string,
+  tailLines?: number,
+): Promise<string> {
+  return invoke<string>("get_lsp_session_log", {
+    args: { sessionId, tailLines },
+  });
+}
+
+export async function listLspSessions(): Promise<LspSessionStatus[]> {
+  return invoke<LspSessionStatus[]>("list_lsp_sessions");
+}
+
 export async function stopLspSession(sessionId: string): Promise<void> {
   await invoke("stop_lsp_session", { args: { sessionId } });
 }
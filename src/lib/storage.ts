@@ -0,0 +1,34 @@
+// 应用数据占用统计及缓存清理
+
+import { invoke } from "@tauri-apps/api/core";
+
+export type StorageCategory =
+  | "projects"
+  | "environment"
+  | "plugins"
+  | "tempDownloads"
+  | "logs"
+  | "recordings";
+
+export type StorageUsage = {
+  category: StorageCategory;
+  bytes: number;
+  // 只有可清理的类别能传给 cleanCaches
+  cleanable: boolean;
+};
+
+export type StorageBreakdown = {
+  total: number;
+  categories: StorageUsage[];
+};
+
+export async function getStorageBreakdown(): Promise<StorageBreakdown> {
+  return await invoke<StorageBreakdown>("get_storage_breakdown");
+}
+
+// 删除指定类别的缓存，返回释放的字节数
+export async function cleanCaches(
+  categories: StorageCategory[],
+): Promise<number> {
+  return await invoke<number>("clean_caches", { categories });
+}
@@ -3,6 +3,7 @@ import { useNavigate } from "react-router-dom";
 import { listen } from "@tauri-apps/api/event";
 import { open } from "@tauri-apps/plugin-dialog";
 
+import { WebExtensionHost } from "@/components/WebExtensionHost";
 import { Button } from "@/components/ui/button";
 import {
   Card,
@@ -12,8 +13,20 @@ import {
   CardHeader,
   CardTitle,
 } from "@/components/ui/card";
-import { importPlugin, listPlugins, removePlugin } from "@/lib/plugins";
-import type { PluginSummary } from "@/types/plugin";
+import {
+  applyPluginsUpdate,
+  describePluginPermissions,
+  importPlugin,
+  removePlugin,
+  setPluginLocale,
+} from "@/lib/plugins";
+import type {
+  ImportPluginError,
+  PluginSessionInfo,
+  PluginSummary,
+  PluginsUpdatedEvent,
+  RemovePluginError,
+} from "@/types/plugin";
 import {
   Dialog,
   DialogContent,
@@ -25,6 +38,40 @@ import {
 
 type PluginStatus = "idle" | "loading" | "ready" | "error";
 
+function describeResolution(plugin: PluginSummary): string | null {
+  const resolution = plugin.resolution;
+  if (!resolution) {
+    return null;
+  }
+  switch (resolution.kind) {
+    case "userOverride":
+      return `使用用户副本，覆盖内置 v${resolution.builtInVersion}`;
+    case "builtInNewer":
+      return `内置版本较新，已替代用户副本 v${resolution.userVersion}`;
+    case "builtInChanged":
+      return resolution.upgraded
+        ? `随应用从 v${resolution.previousVersion} 升级`
+        : `随应用从 v${resolution.previousVersion} 降级`;
+  }
+}
+
+function describeRuntimeStatus(plugin: PluginSummary): string | null {
+  switch (plugin.status.state) {
+    case "incompatible":
+      return `不可用: ${plugin.status.reason}`;
+    case "failed":
+      return `启动失败: ${plugin.status.reason}`;
+    default:
+      return null;
+  }
+}
+
+function canRemove(plugin: PluginSummary): boolean {
+  return (
+    plugin.location === "user" || plugin.resolution?.kind === "builtInNewer"
+  );
+}
+
 function PluginsPage() {
   const navigate = useNavigate();
   const [plugins, setPlugins] = useState<PluginSummary[]>([]);
@@ -34,8 +81,14 @@ function PluginsPage() {
   const [isActionDialogOpen, setActionDialogOpen] = useState(false);
   const [actionError, setActionError] = useState<string | null>(null);
   const [isRemovingPlugin, setRemovingPlugin] = useState(false);
+  const [busySessions, setBusySessions] = useState<PluginSessionInfo[] | null>(
+    null,
+  );
   const [pluginActionTarget, setPluginActionTarget] =
     useState<PluginSummary | null>(null);
+  const [openExtension, setOpenExtension] = useState<PluginSummary | null>(
+    null,
+  );
 
   const pluginLongPressTimerRef = useRef<number | null>(null);
   const pluginLongPressTriggeredRef = useRef(false);
@@ -53,7 +106,8 @@ function PluginsPage() {
     setStatus("loading");
     setError(null);
 
-    listPlugins()
+    // 让插件名称和描述跟随界面语言
+    setPluginLocale(navigator.language)
       .then((result) => {
         if (cancelled) {
           return;
@@ -78,15 +132,13 @@ function PluginsPage() {
 
     let unlisten: (() => void) | undefined;
     if (typeof window !== "undefined") {
-      listen<PluginSummary[]>("truidide://plugins/updated", (event) => {
+      listen<PluginsUpdatedEvent>("truidide://plugins/updated", (event) => {
         if (cancelled) {
           return;
         }
         const payload = event.payload;
-        if (Array.isArray(payload)) {
-          setPlugins(payload);
-          setStatus("ready");
-        }
+        setPlugins((prev) => applyPluginsUpdate(prev, payload));
+        setStatus("ready");
       })
         .then((dispose) => {
           unlisten = dispose;
@@ -118,6 +170,7 @@ function PluginsPage() {
   const openPluginActionDialog = useCallback((plugin: PluginSummary) => {
     setPluginActionTarget(plugin);
     setActionError(null);
+    setBusySessions(null);
     setActionDialogOpen(true);
   }, []);
 
@@ -127,6 +180,7 @@ function PluginsPage() {
     }
     setActionDialogOpen(false);
     setActionError(null);
+    setBusySessions(null);
     setPluginActionTarget(null);
   }, [isRemovingPlugin]);
 
@@ -156,7 +210,34 @@ function PluginsPage() {
       setImporting(true);
       setError(null);
 
-      const imported = await importPlugin(sourcePath);
+      let imported: PluginSummary;
+      try {
+        imported = await importPlugin(sourcePath);
+      } catch (err) {
+        const importError = err as ImportPluginError | string | Error;
+        if (
+          typeof importError !== "object" ||
+          !("kind" in importError) ||
+          importError.kind !== "permissions-required"
+        ) {
+          throw err;
+        }
+        const granted = window.confirm(
+          [
+            `插件「${importError.name}」请求以下权限：`,
+            ...describePluginPermissions(importError.permissions).map(
+              (line) => `• ${line}`,
+            ),
+            "",
+            "是否允许并继续导入？",
+          ].join("\n"),
+        );
+        if (!granted) {
+          setStatus("ready");
+          return;
+        }
+        imported = await importPlugin(sourcePath, undefined, true);
+      }
       setPlugins((prev) => {
         const exists = prev.some((plugin) => plugin.id === imported.id);
         if (exists) {
@@ -173,7 +254,13 @@ function PluginsPage() {
           ? err
           : err instanceof Error
             ? err.message
-            : "导入插件失败";
+            : typeof err === "object" &&
+                err !== null &&
+                "kind" in err &&
+                err.kind === "failed" &&
+                "message" in err
+              ? String(err.message)
+              : "导入插件失败";
       setError(message);
       setStatus("error");
     } finally {
@@ -215,7 +302,7 @@ function PluginsPage() {
       return;
     }
 
-    if (pluginActionTarget.location === "builtIn") {
+    if (!canRemove(pluginActionTarget)) {
       setActionError("内置插件暂不支持删除");
       return;
     }
@@ -223,22 +310,37 @@ function PluginsPage() {
     setRemovingPlugin(true);
     setActionError(null);
     try {
-      const nextPlugins = await removePlugin(pluginActionTarget.id);
+      const nextPlugins = await removePlugin(
+        pluginActionTarget.id,
+        busySessions !== null,
+      );
       setPlugins(nextPlugins);
       setActionDialogOpen(false);
+      setBusySessions(null);
       setPluginActionTarget(null);
     } catch (err) {
+      const removeError = err as RemovePluginError | string | Error;
+      if (
+        typeof removeError === "object" &&
+        "kind" in removeError &&
+        removeError.kind === "in-use"
+      ) {
+        setBusySessions(removeError.sessions);
+        return;
+      }
       const message =
-        typeof err === "string"
-          ? err
-          : err instanceof Error
-            ? err.message
-            : "删除插件失败";
+        typeof removeError === "string"
+          ? removeError
+          : removeError instanceof Error
+            ? removeError.message
+            : "kind" in removeError && removeError.kind === "failed"
+              ? removeError.message
+              : "删除插件失败";
       setActionError(message);
     } finally {
       setRemovingPlugin(false);
     }
-  }, [pluginActionTarget]);
+  }, [busySessions, pluginActionTarget]);
 
   const sortedPlugins = useMemo(() => {
     return [...plugins].sort((a, b) => a.name.localeCompare(b.name, "zh-CN"));
@@ -334,6 +436,22 @@ function PluginsPage() {
                     <dd className="text-foreground">
                       {enabled ? "已启用" : "已禁用"}
                     </dd>
+                    {describeRuntimeStatus(plugin) && (
+                      <>
+                        <dt>运行状态</dt>
+                        <dd className="text-destructive">
+                          {describeRuntimeStatus(plugin)}
+                        </dd>
+                      </>
+                    )}
+                    {(plugin.health?.crashCount ?? 0) > 0 && (
+                      <>
+                        <dt>崩溃次数</dt>
+                        <dd className="text-foreground">
+                          {plugin.health?.crashCount}
+                        </dd>
+                      </>
+                    )}
                     <dt>类型</dt>
                     <dd className="text-foreground">
                       {kindType === "lsp" ? "语言服务" : kindType}
@@ -348,11 +466,39 @@ function PluginsPage() {
                         </dd>
                       </>
                     )}
+                    {describeResolution(plugin) && (
+                      <>
+                        <dt>版本来源</dt>
+                        <dd className="text-foreground">
+                          {describeResolution(plugin)}
+                        </dd>
+                      </>
+                    )}
                     <dt>标签</dt>
                     <dd className="text-foreground">
                       {tags.length > 0 ? tags.join(", ") : "无"}
                     </dd>
                   </dl>
+                  {plugin.status.state === "failed" &&
+                    (plugin.health?.recentStderr.length ?? 0) > 0 && (
+                      <pre className="mt-4 max-h-32 overflow-auto rounded-md bg-muted px-3 py-2 text-xs text-muted-foreground">
+                        {plugin.health?.recentStderr.join("\n")}
+                      </pre>
+                    )}
+                  {kindType === "web-extension" &&
+                    enabled &&
+                    plugin.status.state === "ready" && (
+                      <Button
+                        type="button"
+                        variant="outline"
+                        size="sm"
+                        className="mt-4"
+                        onPointerDown={(event) => event.stopPropagation()}
+                        onClick={() => setOpenExtension(plugin)}
+                      >
+                        打开扩展
+                      </Button>
+                    )}
                   {tags.length > 0 && (
                     <div className="mt-4 flex flex-wrap gap-2">
                       {tags.map((tag) => (
@@ -371,6 +517,22 @@ function PluginsPage() {
           })}
         </section>
       )}
+      <Dialog
+        open={openExtension !== null}
+        onOpenChange={(open) => {
+          if (!open) {
+            setOpenExtension(null);
+          }
+        }}
+      >
+        <DialogContent className="sm:max-w-3xl">
+          <DialogHeader>
+            <DialogTitle>{openExtension?.name ?? "Web 扩展"}</DialogTitle>
+            <DialogDescription>{openExtension?.id}</DialogDescription>
+          </DialogHeader>
+          {openExtension && <WebExtensionHost pluginId={openExtension.id} />}
+        </DialogContent>
+      </Dialog>
       <Dialog
         open={isActionDialogOpen}
         onOpenChange={(open) => {
@@ -390,11 +552,22 @@ function PluginsPage() {
                 : "确定要删除选中的插件吗？"}
             </DialogDescription>
           </DialogHeader>
-          {pluginActionTarget?.location === "builtIn" && (
+          {pluginActionTarget?.resolution?.kind === "builtInNewer" && (
+            <p className="text-sm text-muted-foreground">
+              将删除被内置版本替代的旧用户副本。
+            </p>
+          )}
+          {pluginActionTarget && !canRemove(pluginActionTarget) && (
             <p className="text-sm text-muted-foreground">
               内置插件暂不支持删除。
             </p>
           )}
+          {busySessions && (
+            <p className="text-sm text-muted-foreground">
+              该插件仍有 {busySessions.length}{" "}
+              个正在运行的语言服务会话，继续删除将先停止这些会话。
+            </p>
+          )}
           {actionError && (
             <p className="text-sm text-destructive">{actionError}</p>
           )}
@@ -412,10 +585,16 @@ function PluginsPage() {
               variant="destructive"
               onClick={handleConfirmRemove}
               disabled={
-                isRemovingPlugin || pluginActionTarget?.location === "builtIn"
+                isRemovingPlugin ||
+                !pluginActionTarget ||
+                !canRemove(pluginActionTarget)
               }
             >
-              {isRemovingPlugin ? "正在删除…" : "删除插件"}
+              {isRemovingPlugin
+                ? "正在删除…"
+                : busySessions
+                  ? "停止会话并删除"
+                  : "删除插件"}
             </Button>
           </DialogFooter>
         </DialogContent>
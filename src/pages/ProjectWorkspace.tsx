@@ -19,11 +19,20 @@ import { EditorView } from "@codemirror/view";
 import { Home, Menu, Puzzle, X } from "lucide-react";
 
 import { Button } from "@/components/ui/button";
-import { createLspClient } from "@/lib/lsp";
-import { listPlugins, startLspSession } from "@/lib/plugins";
+import { createLspClient, resyncLspClient } from "@/lib/lsp";
+import {
+  applyPluginsUpdate,
+  listPlugins,
+  startLspSession,
+  stopLspSession,
+} from "@/lib/plugins";
 import { cn } from "@/lib/utils";
 import type { FileNode, ProjectEntry } from "@/types/project";
-import type { PluginSummary } from "@/types/plugin";
+import type {
+  LspRequestTimeoutEvent,
+  PluginSummary,
+  PluginsUpdatedEvent,
+} from "@/types/plugin";
 
 import { CreateEntryDialog } from "./project-workspace/CreateEntryDialog";
 import { EntryActionDialog } from "./project-workspace/EntryActionDialog";
@@ -73,6 +82,18 @@ type LspExitEventPayload = {
   languageId?: string;
   statusCode?: number | null;
   signal?: number | null;
+  /** 服务端异常退出，宿主即将重启它 */
+  restarting?: boolean;
+  /** `idle` 表示宿主停止了长时间空闲的会话 */
+  reason?: "exited" | "stopped" | "idle";
+};
+
+type LspRestartedEventPayload = {
+  previousSessionId: string;
+  sessionId: string;
+  pluginId: string;
+  languageId: string;
+  attempt: number;
 };
 
 const MAX_PLUGIN_LOG_ENTRIES = 500;
@@ -169,6 +190,12 @@ function ProjectWorkspace({
   useEffect(() => {
     setTerminalCwd(projectPath);
   }, [projectPath]);
+  useEffect(() => {
+    // 触发 project-open 钩子
+    invoke("open_project", { projectPath }).catch((error: unknown) => {
+      console.error("执行项目打开钩子失败", error);
+    });
+  }, [projectPath]);
   const isMountedRef = useRef(true);
   const detectLanguageId = useCallback((filePath: string) => {
     const lower = filePath.toLowerCase();
@@ -235,6 +262,16 @@ function ProjectWorkspace({
       });
     }
     lspSessionsRef.current.delete(key);
+    const shared = Array.from(lspSessionsRef.current.values()).some(
+      (other) => other.clientEntry === record.clientEntry,
+    );
+    if (shared) {
+      // 其他语言仍在使用同一会话，只释放本方的引用
+      void stopLspSession(record.clientEntry.sessionId).catch((error) => {
+        console.error("关闭 LSP 会话失败", error);
+      });
+      return;
+    }
     record.clientEntry.client.disconnect();
     void record.clientEntry.transport.shutdown();
   }, []);
@@ -284,24 +321,31 @@ function ProjectWorkspace({
             sessionId,
             pluginId: plugin.id,
             languageId: resolvedLanguageId,
-            message: "会话已启动，等待初始化响应…",
+            message: "会话已启动",
           });
 
-          const clientCapabilities =
-            session.clientCapabilities &&
-            typeof session.clientCapabilities === "object"
-              ? (session.clientCapabilities as Record<string, unknown>)
-              : undefined;
+          const attached = session.reused
+            ? Array.from(lspSessionsRef.current.values()).find(
+                (other) => other.clientEntry.sessionId === session.sessionId,
+              )
+            : undefined;
+          if (attached) {
+            // 服务端复用了已初始化的会话，共用现有客户端而不是再次 initialize
+            const record: LspSessionRecord = {
+              clientEntry: attached.clientEntry,
+              pluginId: plugin.id,
+              languageId: session.languageId,
+            };
+            lspSessionsRef.current.set(key, record);
+            return record;
+          }
 
           const clientEntry = await createLspClient({
             sessionId: session.sessionId,
             rootUri: workspaceUri,
-            clientCapabilities,
-            initializationOptions: session.initializationOptions,
-            workspaceFolders:
-              session.workspaceFolders ?? defaultWorkspaceFolders,
+            serverCapabilities: session.serverCapabilities,
+            serverInfo: session.serverInfo,
             timeoutMs: LSP_REQUEST_TIMEOUT_MS,
-            pathMapping: session.pathMapping || null,
           });
 
           appendPluginLog({
@@ -441,9 +485,9 @@ function ProjectWorkspace({
       });
 
     let unlisten: (() => void) | undefined;
-    listen<PluginSummary[]>("truidide://plugins/updated", (event) => {
-      if (!disposed && Array.isArray(event.payload)) {
-        setAvailablePlugins(event.payload);
+    listen<PluginsUpdatedEvent>("truidide://plugins/updated", (event) => {
+      if (!disposed) {
+        setAvailablePlugins((prev) => applyPluginsUpdate(prev, event.payload));
       }
     })
       .then((dispose) => {
@@ -507,6 +551,21 @@ function ProjectWorkspace({
             if (payload.signal !== undefined && payload.signal !== null) {
               detailParts.push(`信号 ${payload.signal}`);
             }
+            if (payload.reason === "idle" && payload.sessionId) {
+              // 丢弃客户端，下次需要该语言时重新启动会话
+              for (const [key, record] of lspSessionsRef.current) {
+                if (record.clientEntry.sessionId === payload.sessionId) {
+                  lspSessionsRef.current.delete(key);
+                  record.clientEntry.client.disconnect();
+                }
+              }
+            }
+            const summary =
+              payload.reason === "idle"
+                ? "会话空闲，已停止"
+                : payload.restarting
+                  ? "会话异常退出"
+                  : "会话结束";
             appendPluginLog({
               timestamp: Date.now(),
               level: "info",
@@ -514,9 +573,9 @@ function ProjectWorkspace({
               pluginId: payload.pluginId ?? "unknown",
               languageId: payload.languageId ?? undefined,
               message:
-                detailParts.length > 0
-                  ? `会话结束（${detailParts.join(" / ")}）`
-                  : "会话结束",
+                (detailParts.length > 0
+                  ? `${summary}（${detailParts.join(" / ")}）`
+                  : summary) + (payload.restarting ? "，正在重启…" : ""),
             });
           },
         );
@@ -524,6 +583,66 @@ function ProjectWorkspace({
       } catch (error) {
         console.error("监听插件退出失败", error);
       }
+
+      try {
+        const restartedUnlisten = await listen<LspRestartedEventPayload>(
+          "truidide://lsp/restarted",
+          (event) => {
+            if (disposed || !event.payload) {
+              return;
+            }
+            const payload = event.payload;
+            // 多个语言可能共用同一客户端，每个客户端只重新同步一次
+            const entries = new Set(
+              Array.from(lspSessionsRef.current.values())
+                .map((record) => record.clientEntry)
+                .filter(
+                  (entry) => entry.sessionId === payload.previousSessionId,
+                ),
+            );
+            for (const entry of entries) {
+              resyncLspClient(entry, payload.sessionId);
+            }
+            appendPluginLog({
+              timestamp: Date.now(),
+              level: "info",
+              sessionId: payload.sessionId,
+              pluginId: payload.pluginId,
+              languageId: payload.languageId,
+              message: `会话已重启（第 ${payload.attempt} 次）`,
+            });
+          },
+        );
+        unlistenCallbacks.push(restartedUnlisten);
+      } catch (error) {
+        console.error("监听插件重启失败", error);
+      }
+
+      try {
+        const timeoutUnlisten = await listen<LspRequestTimeoutEvent>(
+          "truidide://lsp/request-timeout",
+          (event) => {
+            if (disposed || !event.payload) {
+              return;
+            }
+            const payload = event.payload;
+            const record = Array.from(lspSessionsRef.current.values()).find(
+              (item) => item.clientEntry.sessionId === payload.sessionId,
+            );
+            appendPluginLog({
+              timestamp: Date.now(),
+              level: "stderr",
+              sessionId: payload.sessionId,
+              pluginId: record?.pluginId ?? "unknown",
+              languageId: record?.languageId,
+              message: `请求 ${payload.method} 在 ${Math.round(payload.timeoutMs / 1000)} 秒内未响应`,
+            });
+          },
+        );
+        unlistenCallbacks.push(timeoutUnlisten);
+      } catch (error) {
+        console.error("监听 LSP 请求超时失败", error);
+      }
     };
 
     void attach();
@@ -1758,6 +1877,7 @@ function ProjectWorkspace({
       invoke("save_project_file", {
         filePath: targetPath,
         contents: value,
+        projectPath,
       })
         .catch((error: unknown) => {
           console.error("保存文件失败", error);
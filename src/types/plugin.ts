@@ -1,11 +1,80 @@
-export type PluginKindSummary = {
-  type: "lsp";
-  languageIds: string[];
-  initializationOptions?: unknown;
-};
+export type WebExtensionPermission = "fs-read" | "run-tool" | "show-toast";
+
+export type PluginKindSummary =
+  | {
+      type: "lsp";
+      languageIds: string[];
+      /** Higher wins among plugins for the same language */
+      priority: number;
+      initializationOptions?: unknown;
+    }
+  | {
+      type: "web-extension";
+      /** URL of the extension entry page served via the plugin protocol */
+      entry: string;
+      permissions: WebExtensionPermission[];
+    }
+  | {
+      type: "dap";
+      debugTypes: string[];
+      languageIds: string[];
+    }
+  | {
+      type: "formatter";
+      languageIds: string[];
+    }
+  | {
+      type: "linter";
+      languageIds: string[];
+      /** Globs of files linted automatically on save */
+      filePatterns: string[];
+    }
+  | {
+      type: "snippets";
+      /** Empty when the snippets apply to every language */
+      languageIds: string[];
+    }
+  | {
+      type: "grammar";
+      languageIds: string[];
+    }
+  | {
+      type: "theme";
+      themes: {
+        id: string;
+        label: string;
+        appearance: "light" | "dark";
+        /** Plugin protocol URL of the theme JSON */
+        url: string;
+      }[];
+      iconThemes: { id: string; label: string; url: string }[];
+    }
+  | {
+      type: "runner";
+      languageIds: string[];
+      filePatterns: string[];
+    };
 
 export type PluginLocation = "builtIn" | "user";
 
+export type PluginStatus =
+  | { state: "ready" }
+  | { state: "incompatible"; reason: string }
+  /** Compatible, but its last start failed (e.g. the runtime is missing) */
+  | { state: "failed"; reason: string };
+
+export type PluginHealth = {
+  lastError?: string;
+  crashCount: number;
+  /** Last stderr lines of the plugin's processes, oldest first */
+  recentStderr: string[];
+};
+
+export type PluginResolution =
+  | { kind: "userOverride"; builtInVersion: string }
+  | { kind: "builtInNewer"; userVersion: string }
+  | { kind: "builtInChanged"; previousVersion: string; upgraded: boolean };
+
 export type PluginSummary = {
   id: string;
   name: string;
@@ -16,8 +85,70 @@ export type PluginSummary = {
   tags: string[];
   location: PluginLocation;
   kind: PluginKindSummary;
+  apiVersion: number;
+  status: PluginStatus;
+  /** Deprecation notices collected while loading the manifest */
+  warnings?: string[];
+  /** Which copy is active when user and bundled versions coexist */
+  resolution?: PluginResolution;
+  /** Unsatisfied dependencies; the plugin stays inactive until resolved */
+  missingDependencies?: string[];
+  /** Declared permissions, or full access for manifests without them */
+  permissions: PluginPermissions;
+  /** Absent until one of the plugin's processes was started */
+  health?: PluginHealth;
+};
+
+export type PluginPermissions = {
+  /** `workspace`: workspace and plugin directory only; `full`: whole Linux environment */
+  filesystem: "workspace" | "full";
+  network: boolean;
+  spawnProcesses: boolean;
+};
+
+export type ManifestDiagnostic = {
+  severity: "error" | "warning";
+  /** Dotted path such as `kind.env.PATH`; absent for the whole manifest */
+  field?: string;
+  message: string;
+};
+
+export type ManifestValidation = {
+  /** No errors; warnings do not block an import */
+  valid: boolean;
+  pluginId?: string;
+  manifestPath?: string;
+  diagnostics: ManifestDiagnostic[];
 };
 
+export type ImportPluginError =
+  | {
+      kind: "permissions-required";
+      pluginId: string;
+      name: string;
+      permissions: PluginPermissions;
+    }
+  | { kind: "failed"; message: string };
+
+/** Delta broadcast on `truidide://plugins/updated` */
+export type PluginsUpdatedEvent = {
+  upserted: PluginSummary[];
+  removed: string[];
+};
+
+export type PluginSessionInfo = {
+  sessionId: string;
+  kind: "lsp" | "dap";
+  /** Language id for LSP sessions, debug type for DAP sessions */
+  languageId: string;
+  workspacePath: string;
+};
+
+export type RemovePluginError =
+  | { kind: "in-use"; pluginId: string; sessions: PluginSessionInfo[] }
+  | { kind: "required-by"; pluginId: string; dependents: string[] }
+  | { kind: "failed"; message: string };
+
 export type PathMapping = {
   /** Host workspace path (e.g., /data/user/0/.../files/projects/myapp) */
   hostWorkspace: string;
@@ -27,6 +158,26 @@ export type PathMapping = {
   hostPlugin: string;
   /** Guest plugin path inside proot (e.g., /opt/truidide/plugins/plugin-id) */
   guestPlugin: string;
+  /** Mounts of the additional roots of a multi-root workspace */
+  additionalWorkspaces?: { hostWorkspace: string; guestWorkspace: string }[];
+};
+
+/** A row of `list_lsp_sessions` */
+export type LspSessionStatus = {
+  sessionId: string;
+  pluginId: string;
+  languageId: string;
+  workspacePath: string;
+  additionalWorkspacePaths?: string[];
+  /** Milliseconds since the current server process was spawned */
+  uptimeMs: number;
+  pid?: number;
+  /** False while a crashed server waits to be restarted */
+  alive: boolean;
+  restarting: boolean;
+  /** Waiting for `initialize`; messages sent now are queued by the host */
+  initializing: boolean;
+  clients: number;
 };
 
 export type StartLspSessionResult = {
@@ -37,4 +188,214 @@ export type StartLspSessionResult = {
   clientCapabilities?: unknown;
   workspaceFolders?: unknown;
   pathMapping?: PathMapping | null;
+  /** `capabilities` from the server's initialize result; the host already completed the handshake */
+  serverCapabilities: Record<string, unknown>;
+  serverInfo?: { name: string; version?: string } | null;
+  /** The server was already running for this plugin and workspace */
+  reused: boolean;
+};
+
+/** Payload of `truidide://lsp/request-timeout` */
+export type LspRequestTimeoutEvent = {
+  sessionId: string;
+  id: number | string;
+  method: string;
+  timeoutMs: number;
+};
+
+export type StartDapSessionResult = {
+  sessionId: string;
+  pluginId: string;
+  debugType: string;
+  pathMapping?: PathMapping | null;
+};
+
+export type FormatDocumentResult = {
+  pluginId: string;
+  formatted: string;
+  /** Minimal replacement in UTF-16 offsets; null when nothing changed */
+  edit: { from: number; to: number; insert: string } | null;
+};
+
+export type DiagnosticSeverity = "error" | "warning" | "info" | "hint";
+
+export type LintDiagnostic = {
+  /** 1-based, as reported by the linter */
+  line: number;
+  column?: number;
+  endLine?: number;
+  endColumn?: number;
+  severity: DiagnosticSeverity;
+  message: string;
+  code?: string;
+};
+
+/** Payload of `truidide://lint/diagnostics`; replaces earlier results of the same linter for `file` */
+export type LintDiagnosticsEvent = {
+  pluginId: string;
+  file: string;
+  diagnostics: LintDiagnostic[];
+};
+
+export type Snippet = {
+  pluginId: string;
+  name: string;
+  prefixes: string[];
+  /** VSCode/TextMate snippet syntax */
+  body: string;
+  description?: string;
+};
+
+/** Tree-sitter grammar served over the plugin protocol, for web-tree-sitter */
+export type GrammarInfo = {
+  pluginId: string;
+  languageId: string;
+  fileExtensions: string[];
+  wasmUrl: string;
+  highlightsUrl?: string;
+  localsUrl?: string;
+  injectionsUrl?: string;
+  tagsUrl?: string;
+};
+
+export type PluginAsset = {
+  mimeType: string;
+  encoding: "utf8" | "base64";
+  content: string;
+};
+
+/** Returned by `load_web_extension`; the token stays with the host page */
+export type LoadedWebExtension = {
+  pluginId: string;
+  entry: string;
+  token: string;
+};
+
+export type BrokerRequest =
+  | { type: "read-file"; workspacePath: string; path: string }
+  | { type: "run-tool"; program: string; args?: string[] }
+  | { type: "show-toast"; message: string }
+  | { type: "publish"; topic: string; payload?: unknown }
+  | { type: "subscribe"; topic: string }
+  | { type: "unsubscribe"; topic: string };
+
+export type BrokerResponse =
+  | { type: "file"; contents: string }
+  | {
+      type: "tool-output";
+      exitCode?: number | null;
+      stdout: string;
+      stderr: string;
+    }
+  | { type: "published"; delivered: number }
+  | { type: "done" };
+
+/** Payload of `truidide://plugins/bus` */
+export type PluginBusMessageEvent = {
+  targetPluginId: string;
+  topic: string;
+  sourcePluginId: string;
+  payload: unknown;
+};
+
+/** Payload of `truidide://plugins/toast` */
+export type PluginToastEvent = {
+  pluginId: string;
+  message: string;
+};
+
+export type RunCurrentFileResult = {
+  /** Terminal session the command was typed into */
+  sessionId: string;
+  pluginId: string;
+  commandLine: string;
+};
+
+export type SettingType =
+  | "string"
+  | "number"
+  | "integer"
+  | "boolean"
+  | "array"
+  | "object";
+
+export type SettingSchema = {
+  type: SettingType;
+  default?: unknown;
+  description?: string | null;
+  enum?: unknown[] | null;
+};
+
+export type PluginConfiguration = {
+  title?: string | null;
+  properties: Record<string, SettingSchema>;
+};
+
+export type PluginSettings = {
+  pluginId: string;
+  configuration: PluginConfiguration;
+  user: Record<string, unknown>;
+  project?: Record<string, unknown>;
+  effective: Record<string, unknown>;
+};
+
+export type MarketplaceListing = {
+  id: string;
+  name: string;
+  version: string;
+  description?: string | null;
+  author?: string | null;
+  tags: string[];
+  kind?: string | null;
+  apiVersion?: number | null;
+  homepage?: string | null;
+  downloadUrl: string;
+  sha256?: string | null;
+  installedVersion?: string | null;
+};
+
+export type MarketplaceSettings = {
+  indexUrl?: string | null;
+};
+
+export type LspTraceSettings = {
+  enabled: boolean;
+};
+
+/** Language id -> plugin id the user picked for that language */
+export type LanguagePluginDefaults = {
+  defaults: Record<string, string>;
+};
+
+export type LspIdleSettings = {
+  /** 空闲多少分钟后停止语言服务器；未设置或 0 表示不停止 */
+  idleTimeoutMinutes?: number | null;
+  /** 插件 ID -> 分钟数，覆盖全局值；0 表示该插件不自动停止 */
+  pluginOverrides?: Record<string, number>;
+};
+
+export type InstallProgressEvent = {
+  url: string;
+  downloaded: number;
+  total?: number;
+  percentage?: number;
+};
+
+export type UnsignedPluginPolicy = "allow" | "warn" | "refuse";
+
+export type TrustedPluginKey = {
+  label: string;
+  /** Base64 encoded ed25519 public key */
+  publicKey: string;
+};
+
+export type PluginTrustSettings = {
+  unsignedPolicy: UnsignedPluginPolicy;
+  trustedKeys: TrustedPluginKey[];
+};
+
+/** Plugin activation lists from `.truidide/project.json`; deny wins */
+export type ProjectPluginActivation = {
+  allow: string[];
+  deny: string[];
 };
@@ -1,13 +1,26 @@
 package com.plugin.filepicker
 
 import android.content.Context
+import android.content.Intent
 import android.net.Uri
+import android.os.Environment
+import android.provider.DocumentsContract
+import android.provider.OpenableColumns
+import android.webkit.MimeTypeMap
+import androidx.core.content.FileProvider
 import android.util.Log
+import app.tauri.plugin.JSObject
 import java.io.File
+import java.io.FileInputStream
 import java.io.FileOutputStream
 import java.io.InputStream
 
 class Example {
+    companion object {
+        private const val BUFFER_SIZE = 256 * 1024
+        private const val PROGRESS_STEP = 4L * 1024 * 1024
+    }
+
     fun pong(value: String): String {
         Log.i("Pong", value)
         return value
@@ -18,9 +31,15 @@ class Example {
      * @param context Android Context
      * @param contentUri Content URI (例如 content://...)
      * @param targetFile 目标文件路径
+     * @param onProgress 进度回调 (已读取字节数, 总字节数)，按 PROGRESS_STEP 节流
      * @return 成功返回 true
      */
-    fun copyContentUriToFile(context: Context, contentUri: String, targetFile: File): Boolean {
+    fun copyContentUriToFile(
+        context: Context,
+        contentUri: String,
+        targetFile: File,
+        onProgress: ((Long, Long?) -> Unit)? = null
+    ): Boolean {
         return try {
             val uri = Uri.parse(contentUri)
             val inputStream: InputStream? = context.contentResolver.openInputStream(uri)
@@ -30,11 +49,25 @@ class Example {
                 return false
             }
 
+            val totalBytes = queryOpenableInfo(context, uri).second
+            var bytesRead = 0L
+            var lastReported = 0L
             FileOutputStream(targetFile).use { outputStream ->
                 inputStream.use { input ->
-                    input.copyTo(outputStream)
+                    val buffer = ByteArray(BUFFER_SIZE)
+                    while (true) {
+                        val count = input.read(buffer)
+                        if (count < 0) break
+                        outputStream.write(buffer, 0, count)
+                        bytesRead += count
+                        if (bytesRead - lastReported >= PROGRESS_STEP) {
+                            lastReported = bytesRead
+                            onProgress?.invoke(bytesRead, totalBytes)
+                        }
+                    }
                 }
             }
+            onProgress?.invoke(bytesRead, totalBytes)
             
             Log.i("FilePicker", "成功从 Content URI 复制文件: $contentUri -> ${targetFile.absolutePath}")
             true
@@ -44,6 +77,220 @@ class Example {
         }
     }
 
+    /**
+     * 查询 Content URI 对应的文件名和大小
+     * @return 查询失败的字段为 null
+     */
+    fun queryOpenableInfo(context: Context, uri: Uri): Pair<String?, Long?> {
+        return try {
+            context.contentResolver.query(
+                uri,
+                arrayOf(OpenableColumns.DISPLAY_NAME, OpenableColumns.SIZE),
+                null,
+                null,
+                null
+            )?.use { cursor ->
+                if (!cursor.moveToFirst()) {
+                    return Pair(null, null)
+                }
+                val nameIndex = cursor.getColumnIndex(OpenableColumns.DISPLAY_NAME)
+                val sizeIndex = cursor.getColumnIndex(OpenableColumns.SIZE)
+                val name = if (nameIndex >= 0 && !cursor.isNull(nameIndex)) cursor.getString(nameIndex) else null
+                val size = if (sizeIndex >= 0 && !cursor.isNull(sizeIndex)) cursor.getLong(sizeIndex) else null
+                Pair(name, size)
+            } ?: Pair(null, null)
+        } catch (e: Exception) {
+            Log.e("FilePicker", "查询 Content URI 信息失败: ${e.message}", e)
+            Pair(null, null)
+        }
+    }
+
+    /**
+     * 持久化 URI 授权，优先读写，提供方不允许写入时退回只读
+     * @return 成功返回 true
+     */
+    fun takePersistablePermission(context: Context, uri: Uri): Boolean {
+        val read = Intent.FLAG_GRANT_READ_URI_PERMISSION
+        val readWrite = read or Intent.FLAG_GRANT_WRITE_URI_PERMISSION
+        for (flags in intArrayOf(readWrite, read)) {
+            try {
+                context.contentResolver.takePersistableUriPermission(uri, flags)
+                return true
+            } catch (e: SecurityException) {
+                Log.w("FilePicker", "无法持久化 URI 授权 ($flags): ${e.message}")
+            }
+        }
+        return false
+    }
+
+    /**
+     * 列出树 URI 下某个目录的直接子项
+     * @param uri pickDirectory 返回的树 URI，或上一次列出的子目录 URI
+     * @return 每个子项的 name/uri/isDirectory/size/mimeType/lastModified，失败返回 null
+     */
+    fun listTreeChildren(context: Context, uri: Uri): List<JSObject>? {
+        val parentId = if (DocumentsContract.isDocumentUri(context, uri)) {
+            DocumentsContract.getDocumentId(uri)
+        } else {
+            DocumentsContract.getTreeDocumentId(uri)
+        }
+        val childrenUri = DocumentsContract.buildChildDocumentsUriUsingTree(uri, parentId)
+        val projection = arrayOf(
+            DocumentsContract.Document.COLUMN_DOCUMENT_ID,
+            DocumentsContract.Document.COLUMN_DISPLAY_NAME,
+            DocumentsContract.Document.COLUMN_MIME_TYPE,
+            DocumentsContract.Document.COLUMN_SIZE,
+            DocumentsContract.Document.COLUMN_LAST_MODIFIED
+        )
+
+        return try {
+            context.contentResolver.query(childrenUri, projection, null, null, null)?.use { cursor ->
+                val entries = mutableListOf<JSObject>()
+                while (cursor.moveToNext()) {
+                    val documentId = cursor.getString(0)
+                    val mimeType = cursor.getString(2)
+                    val isDirectory = mimeType == DocumentsContract.Document.MIME_TYPE_DIR
+
+                    val entry = JSObject()
+                    entry.put("name", cursor.getString(1) ?: documentId)
+                    entry.put("uri", DocumentsContract.buildDocumentUriUsingTree(uri, documentId).toString())
+                    entry.put("isDirectory", isDirectory)
+                    entry.put("size", if (isDirectory || cursor.isNull(3)) null else cursor.getLong(3))
+                    entry.put("mimeType", if (isDirectory) null else mimeType)
+                    entry.put("lastModified", if (cursor.isNull(4)) null else cursor.getLong(4))
+                    entries.add(entry)
+                }
+                entries
+            }
+        } catch (e: Exception) {
+            Log.e("FilePicker", "列出目录失败: ${e.message}", e)
+            null
+        }
+    }
+
+    /**
+     * 获取可以交给其他应用读取的 URI
+     * Content URI 原样返回；本地文件通过 FileProvider 暴露，不在其路径配置内的文件先复制到缓存目录
+     * @return 文件不存在时返回 null
+     */
+    fun shareableUri(context: Context, pathOrUri: String): Uri? {
+        if (pathOrUri.startsWith("content://")) {
+            return Uri.parse(pathOrUri)
+        }
+
+        val file = File(pathOrUri)
+        if (!file.isFile) {
+            return null
+        }
+
+        val authority = "${context.packageName}.fileprovider"
+        return try {
+            FileProvider.getUriForFile(context, authority, file)
+        } catch (e: IllegalArgumentException) {
+            val shareDir = File(context.cacheDir, "share")
+            shareDir.mkdirs()
+            val copy = File(shareDir, file.name)
+            file.copyTo(copy, overwrite = true)
+            FileProvider.getUriForFile(context, authority, copy)
+        }
+    }
+
+    /**
+     * 根据扩展名或 ContentResolver 推断 MIME 类型
+     */
+    fun guessMimeType(context: Context, uri: Uri, pathOrUri: String): String {
+        val extension = MimeTypeMap.getFileExtensionFromUrl(pathOrUri)
+        val fromExtension = extension?.let {
+            MimeTypeMap.getSingleton().getMimeTypeFromExtension(it.lowercase())
+        }
+        return fromExtension ?: context.contentResolver.getType(uri) ?: "application/octet-stream"
+    }
+
+    /**
+     * 将 SAF 树 URI 转换为便于展示的路径
+     * 例如 primary:Projects/demo -> /storage/emulated/0/Projects/demo
+     * @return 无法识别时返回 URI 本身
+     */
+    fun treeUriDisplayPath(treeUri: Uri): String {
+        val documentId = try {
+            DocumentsContract.getTreeDocumentId(treeUri)
+        } catch (e: IllegalArgumentException) {
+            return treeUri.toString()
+        }
+
+        val parts = documentId.split(":", limit = 2)
+        if (parts.size != 2) {
+            return treeUri.toString()
+        }
+
+        val (volume, relative) = parts
+        val root = if (volume.equals("primary", ignoreCase = true)) {
+            Environment.getExternalStorageDirectory().absolutePath
+        } else {
+            "/storage/$volume"
+        }
+
+        return if (relative.isEmpty()) root else "$root/$relative"
+    }
+
+    /**
+     * 将本地文件写入 Content URI
+     * @param context Android Context
+     * @param sourceFile 要写入的本地文件
+     * @param contentUri 目标 Content URI
+     * @return 写入的字节数，失败返回 null
+     */
+    fun copyFileToContentUri(context: Context, sourceFile: File, contentUri: String): Long? {
+        return try {
+            val uri = Uri.parse(contentUri)
+            // "wt" 截断已有内容
+            val outputStream = context.contentResolver.openOutputStream(uri, "wt")
+
+            if (outputStream == null) {
+                Log.e("FilePicker", "无法打开 Content URI: $contentUri")
+                return null
+            }
+
+            val size = outputStream.use { output ->
+                FileInputStream(sourceFile).use { input ->
+                    input.copyTo(output)
+                }
+            }
+
+            Log.i("FilePicker", "成功写入 Content URI: ${sourceFile.absolutePath} -> $contentUri")
+            size
+        } catch (e: Exception) {
+            Log.e("FilePicker", "写入 Content URI 失败: ${e.message}", e)
+            null
+        }
+    }
+
+    /**
+     * 将字节内容写入 Content URI
+     * @param context Android Context
+     * @param bytes 要写入的内容
+     * @param contentUri 目标 Content URI
+     * @return 写入的字节数，失败返回 null
+     */
+    fun writeBytesToContentUri(context: Context, bytes: ByteArray, contentUri: String): Long? {
+        return try {
+            val uri = Uri.parse(contentUri)
+            val outputStream = context.contentResolver.openOutputStream(uri, "wt")
+
+            if (outputStream == null) {
+                Log.e("FilePicker", "无法打开 Content URI: $contentUri")
+                return null
+            }
+
+            outputStream.use { it.write(bytes) }
+            Log.i("FilePicker", "成功写入 Content URI: ${bytes.size} 字节 -> $contentUri")
+            bytes.size.toLong()
+        } catch (e: Exception) {
+            Log.e("FilePicker", "写入 Content URI 失败: ${e.message}", e)
+            null
+        }
+    }
+
     /**
      * 从 Content URI 读取文件内容
      * @param context Android Context
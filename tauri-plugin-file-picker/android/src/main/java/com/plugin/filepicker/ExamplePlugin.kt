@@ -1,14 +1,22 @@
 package com.plugin.filepicker
 
 import android.app.Activity
+import android.content.Intent
+import android.net.Uri
+import android.provider.DocumentsContract
 import android.util.Base64
+import android.webkit.WebView
+import androidx.activity.result.ActivityResult
+import app.tauri.annotation.ActivityCallback
 import app.tauri.annotation.Command
 import app.tauri.annotation.InvokeArg
 import app.tauri.annotation.TauriPlugin
+import app.tauri.plugin.JSArray
 import app.tauri.plugin.JSObject
 import app.tauri.plugin.Plugin
 import app.tauri.plugin.Invoke
 import java.io.File
+import java.util.concurrent.Executors
 
 @InvokeArg
 class PingArgs {
@@ -21,9 +29,109 @@ class ReadContentUriArgs {
   var targetPath: String? = null
 }
 
+@InvokeArg
+class PickFilesArgs {
+  var multiple: Boolean = false
+  var mimeTypes: Array<String>? = null
+}
+
+@InvokeArg
+class CreateDocumentArgs {
+  lateinit var suggestedName: String
+  var mimeType: String? = null
+}
+
+@InvokeArg
+class UriPermissionArgs {
+  lateinit var uri: String
+}
+
+@InvokeArg
+class ListTreeUriArgs {
+  lateinit var uri: String
+}
+
+@InvokeArg
+class ShareFileArgs {
+  lateinit var pathOrUri: String
+  var mimeType: String? = null
+  var title: String? = null
+}
+
+@InvokeArg
+class WriteContentUriArgs {
+  lateinit var contentUri: String
+  var sourcePath: String? = null
+  var content: String? = null
+}
+
 @TauriPlugin
 class ExamplePlugin(private val activity: Activity): Plugin(activity) {
     private val implementation = Example()
+    // 大文件的读写放到后台线程，避免阻塞调用线程导致 ANR
+    private val ioExecutor = Executors.newCachedThreadPool()
+    // 通过“打开方式”或分享传入、尚未被前端取走的文件
+    private var pendingIncoming: JSObject? = null
+
+    override fun load(webView: WebView) {
+        super.load(webView)
+        // 冷启动时前端还没有注册监听，只记录下来等待 takeIncomingIntent
+        pendingIncoming = parseIncomingIntent(activity.intent)
+    }
+
+    override fun onNewIntent(intent: Intent) {
+        val incoming = parseIncomingIntent(intent) ?: return
+        pendingIncoming = incoming
+        trigger("incomingIntent", incoming)
+    }
+
+    /**
+     * 取走待处理的传入文件，没有时 incoming 为 null
+     */
+    @Command
+    fun takeIncomingIntent(invoke: Invoke) {
+        val ret = JSObject()
+        ret.put("incoming", pendingIncoming)
+        pendingIncoming = null
+        invoke.resolve(ret)
+    }
+
+    private fun parseIncomingIntent(intent: Intent?): JSObject? {
+        if (intent == null) return null
+        val uri = when (intent.action) {
+            Intent.ACTION_VIEW -> intent.data
+            Intent.ACTION_SEND -> {
+                @Suppress("DEPRECATION")
+                intent.getParcelableExtra<Uri>(Intent.EXTRA_STREAM)
+            }
+            else -> null
+        } ?: return null
+
+        val isTree = try {
+            DocumentsContract.isTreeUri(uri)
+        } catch (e: Exception) {
+            false
+        }
+        val mimeType = intent.type ?: try {
+            activity.contentResolver.getType(uri)
+        } catch (e: Exception) {
+            null
+        }
+        val name = if (uri.scheme == "content" && !isTree) {
+            implementation.queryOpenableInfo(activity, uri).first
+        } else {
+            uri.lastPathSegment
+        }
+
+        val incoming = JSObject()
+        incoming.put("uri", uri.toString())
+        incoming.put("name", name ?: uri.lastPathSegment)
+        incoming.put("mimeType", mimeType)
+        // 只有树 URI 和本地目录可以逐项导入
+        val isLocalDirectory = uri.scheme == "file" && uri.path?.let { File(it).isDirectory } == true
+        incoming.put("isDirectory", isTree || isLocalDirectory)
+        return incoming
+    }
 
     @Command
     fun ping(invoke: Invoke) {
@@ -37,9 +145,14 @@ class ExamplePlugin(private val activity: Activity): Plugin(activity) {
     /**
      * 从 Content URI 读取文件并保存到指定路径
      * 如果未指定 targetPath，则返回 Base64 编码的文件内容
+     * 复制到 targetPath 时分块写入，并通过 readProgress 事件报告进度
      */
     @Command
     fun readContentUri(invoke: Invoke) {
+        ioExecutor.execute { readContentUriInBackground(invoke) }
+    }
+
+    private fun readContentUriInBackground(invoke: Invoke) {
         try {
             val args = invoke.parseArgs(ReadContentUriArgs::class.java)
             val contentUri = args.contentUri
@@ -61,7 +174,13 @@ class ExamplePlugin(private val activity: Activity): Plugin(activity) {
                     parentDir.mkdirs()
                 }
 
-                val success = implementation.copyContentUriToFile(activity, contentUri, targetFile)
+                val success = implementation.copyContentUriToFile(activity, contentUri, targetFile) { bytesRead, totalBytes ->
+                    val progress = JSObject()
+                    progress.put("contentUri", contentUri)
+                    progress.put("bytesRead", bytesRead)
+                    progress.put("totalBytes", totalBytes)
+                    trigger("readProgress", progress)
+                }
                 
                 if (success) {
                     ret.put("path", targetFile.absolutePath)
@@ -88,5 +207,271 @@ class ExamplePlugin(private val activity: Activity): Plugin(activity) {
             invoke.reject("读取 Content URI 时出错: ${e.message}")
         }
     }
+
+    /**
+     * 通过 SAF 选择一个目录，返回树 URI 和可读路径；用户取消时两者都为 null
+     */
+    @Command
+    fun pickDirectory(invoke: Invoke) {
+        try {
+            val intent = Intent(Intent.ACTION_OPEN_DOCUMENT_TREE)
+            startActivityForResult(invoke, intent, "pickDirectoryResult")
+        } catch (e: Exception) {
+            invoke.reject("无法打开目录选择器: ${e.message}")
+        }
+    }
+
+    @ActivityCallback
+    private fun pickDirectoryResult(invoke: Invoke, result: ActivityResult) {
+        val ret = JSObject()
+        val uri = result.data?.data
+        if (result.resultCode != Activity.RESULT_OK || uri == null) {
+            ret.put("uri", null)
+            ret.put("displayPath", null)
+            invoke.resolve(ret)
+            return
+        }
+
+        // 持久化授权，重启应用后仍可访问该目录
+        implementation.takePersistablePermission(activity, uri)
+
+        ret.put("uri", uri.toString())
+        ret.put("displayPath", implementation.treeUriDisplayPath(uri))
+        invoke.resolve(ret)
+    }
+
+    /**
+     * 通过 SAF 选择一个或多个文件，返回每个文件的 Content URI、名称和大小
+     */
+    @Command
+    fun pickFiles(invoke: Invoke) {
+        try {
+            val args = invoke.parseArgs(PickFilesArgs::class.java)
+            val mimeTypes = args.mimeTypes?.filter { it.isNotBlank() } ?: emptyList()
+
+            val intent = Intent(Intent.ACTION_OPEN_DOCUMENT).apply {
+                addCategory(Intent.CATEGORY_OPENABLE)
+                type = mimeTypes.singleOrNull() ?: "*/*"
+                if (mimeTypes.size > 1) {
+                    putExtra(Intent.EXTRA_MIME_TYPES, mimeTypes.toTypedArray())
+                }
+                putExtra(Intent.EXTRA_ALLOW_MULTIPLE, args.multiple)
+            }
+            startActivityForResult(invoke, intent, "pickFilesResult")
+        } catch (e: Exception) {
+            invoke.reject("无法打开文件选择器: ${e.message}")
+        }
+    }
+
+    @ActivityCallback
+    private fun pickFilesResult(invoke: Invoke, result: ActivityResult) {
+        val files = JSArray()
+        val data = result.data
+        if (result.resultCode == Activity.RESULT_OK && data != null) {
+            val uris = mutableListOf<Uri>()
+            val clipData = data.clipData
+            if (clipData != null) {
+                for (i in 0 until clipData.itemCount) {
+                    clipData.getItemAt(i).uri?.let { uris.add(it) }
+                }
+            } else {
+                data.data?.let { uris.add(it) }
+            }
+
+            for (uri in uris) {
+                val (name, size) = implementation.queryOpenableInfo(activity, uri)
+                val file = JSObject()
+                file.put("uri", uri.toString())
+                file.put("name", name)
+                file.put("size", size)
+                files.put(file)
+            }
+        }
+
+        val ret = JSObject()
+        ret.put("files", files)
+        invoke.resolve(ret)
+    }
+
+    /**
+     * 通过 SAF 让用户选择保存位置，返回可写入的 Content URI；用户取消时为 null
+     */
+    @Command
+    fun createDocument(invoke: Invoke) {
+        try {
+            val args = invoke.parseArgs(CreateDocumentArgs::class.java)
+            val intent = Intent(Intent.ACTION_CREATE_DOCUMENT).apply {
+                addCategory(Intent.CATEGORY_OPENABLE)
+                type = args.mimeType ?: "application/octet-stream"
+                putExtra(Intent.EXTRA_TITLE, args.suggestedName)
+            }
+            startActivityForResult(invoke, intent, "createDocumentResult")
+        } catch (e: Exception) {
+            invoke.reject("无法打开保存对话框: ${e.message}")
+        }
+    }
+
+    @ActivityCallback
+    private fun createDocumentResult(invoke: Invoke, result: ActivityResult) {
+        val ret = JSObject()
+        val uri = result.data?.data
+        if (result.resultCode == Activity.RESULT_OK && uri != null) {
+            ret.put("uri", uri.toString())
+        } else {
+            ret.put("uri", null)
+        }
+        invoke.resolve(ret)
+    }
+
+    /**
+     * 持久化 URI 授权，使应用重启后仍可访问（例如作为工作区打开的外部目录）
+     */
+    @Command
+    fun persistUriPermission(invoke: Invoke) {
+        val args = invoke.parseArgs(UriPermissionArgs::class.java)
+        val ret = JSObject()
+        ret.put("success", implementation.takePersistablePermission(activity, Uri.parse(args.uri)))
+        invoke.resolve(ret)
+    }
+
+    @Command
+    fun listPersistedUris(invoke: Invoke) {
+        val uris = JSArray()
+        for (permission in activity.contentResolver.persistedUriPermissions) {
+            val item = JSObject()
+            item.put("uri", permission.uri.toString())
+            item.put("read", permission.isReadPermission)
+            item.put("write", permission.isWritePermission)
+            item.put("persistedTime", permission.persistedTime)
+            uris.put(item)
+        }
+
+        val ret = JSObject()
+        ret.put("uris", uris)
+        invoke.resolve(ret)
+    }
+
+    @Command
+    fun releaseUriPermission(invoke: Invoke) {
+        val args = invoke.parseArgs(UriPermissionArgs::class.java)
+        val uri = Uri.parse(args.uri)
+        val permission = activity.contentResolver.persistedUriPermissions.find { it.uri == uri }
+
+        val ret = JSObject()
+        if (permission == null) {
+            // 本来就没有持久化授权
+            ret.put("success", true)
+            invoke.resolve(ret)
+            return
+        }
+
+        var flags = 0
+        if (permission.isReadPermission) flags = flags or Intent.FLAG_GRANT_READ_URI_PERMISSION
+        if (permission.isWritePermission) flags = flags or Intent.FLAG_GRANT_WRITE_URI_PERMISSION
+        try {
+            activity.contentResolver.releasePersistableUriPermission(uri, flags)
+            ret.put("success", true)
+            invoke.resolve(ret)
+        } catch (e: SecurityException) {
+            invoke.reject("释放 URI 授权失败: ${e.message}")
+        }
+    }
+
+    /**
+     * 列出 SAF 树 URI（或其中某个子目录）的直接子项
+     */
+    @Command
+    fun listTreeUri(invoke: Invoke) {
+        ioExecutor.execute {
+            try {
+                val args = invoke.parseArgs(ListTreeUriArgs::class.java)
+                val entries = implementation.listTreeChildren(activity, Uri.parse(args.uri))
+                if (entries == null) {
+                    invoke.reject("无法列出目录: ${args.uri}")
+                    return@execute
+                }
+
+                val array = JSArray()
+                for (entry in entries) {
+                    array.put(entry)
+                }
+                val ret = JSObject()
+                ret.put("entries", array)
+                invoke.resolve(ret)
+            } catch (e: Exception) {
+                invoke.reject("列出目录时出错: ${e.message}")
+            }
+        }
+    }
+
+    /**
+     * 通过系统分享面板把文件发送到其他应用
+     * 本地路径通过应用的 FileProvider 转换为 Content URI
+     */
+    @Command
+    fun shareFile(invoke: Invoke) {
+        try {
+            val args = invoke.parseArgs(ShareFileArgs::class.java)
+            val uri = implementation.shareableUri(activity, args.pathOrUri)
+            if (uri == null) {
+                invoke.reject("文件不存在: ${args.pathOrUri}")
+                return
+            }
+
+            val mimeType = args.mimeType ?: implementation.guessMimeType(activity, uri, args.pathOrUri)
+            val intent = Intent(Intent.ACTION_SEND).apply {
+                type = mimeType
+                putExtra(Intent.EXTRA_STREAM, uri)
+                addFlags(Intent.FLAG_GRANT_READ_URI_PERMISSION)
+            }
+            activity.startActivity(Intent.createChooser(intent, args.title ?: "分享"))
+            invoke.resolve()
+        } catch (e: Exception) {
+            invoke.reject("分享文件失败: ${e.message}")
+        }
+    }
+
+    /**
+     * 将本地文件或 Base64 内容写入 Content URI（例如 SAF 创建的文档），覆盖原有内容
+     */
+    @Command
+    fun writeContentUri(invoke: Invoke) {
+        try {
+            val args = invoke.parseArgs(WriteContentUriArgs::class.java)
+            val contentUri = args.contentUri
+
+            if (!contentUri.startsWith("content://")) {
+                invoke.reject("无效的 Content URI: $contentUri")
+                return
+            }
+
+            val sourcePath = args.sourcePath
+            val content = args.content
+            val size = if (sourcePath != null) {
+                val sourceFile = File(sourcePath)
+                if (!sourceFile.isFile) {
+                    invoke.reject("源文件不存在: $sourcePath")
+                    return
+                }
+                implementation.copyFileToContentUri(activity, sourceFile, contentUri)
+            } else if (content != null) {
+                val bytes = Base64.decode(content, Base64.DEFAULT)
+                implementation.writeBytesToContentUri(activity, bytes, contentUri)
+            } else {
+                invoke.reject("必须提供 sourcePath 或 content")
+                return
+            }
+            if (size != null) {
+                val ret = JSObject()
+                ret.put("success", true)
+                ret.put("size", size)
+                invoke.resolve(ret)
+            } else {
+                invoke.reject("写入 Content URI 失败")
+            }
+        } catch (e: Exception) {
+            invoke.reject("写入 Content URI 时出错: ${e.message}")
+        }
+    }
 }
 
@@ -1,4 +1,20 @@
-const COMMANDS: &[&str] = &["ping", "read_content_uri"];
+const COMMANDS: &[&str] = &[
+    "ping",
+    "read_content_uri",
+    "pick_directory",
+    "pick_files",
+    "create_document",
+    "write_content_uri",
+    "persist_uri_permission",
+    "list_persisted_uris",
+    "release_uri_permission",
+    "list_tree_uri",
+    "share_file",
+    "take_incoming_intent",
+    // Kotlin 端通过 trigger 推送读取进度，前端监听需要这两个命令
+    "register_listener",
+    "remove_listener",
+];
 
 fn main() {
     tauri_plugin::Builder::new(COMMANDS)
@@ -1,4 +1,4 @@
-import { invoke } from '@tauri-apps/api/core'
+import { addPluginListener, invoke, type PluginListener } from '@tauri-apps/api/core'
 
 export async function ping(value: string): Promise<string | null> {
   return await invoke<{value?: string}>('plugin:file-picker|ping', {
@@ -7,3 +7,182 @@ export async function ping(value: string): Promise<string | null> {
     },
   }).then((r) => (r.value ? r.value : null));
 }
+
+export interface PickDirectoryResponse {
+  /** Tree URI on Android, the folder path on desktop; null if cancelled. */
+  uri: string | null;
+  /** Human-readable location of the folder. */
+  displayPath: string | null;
+}
+
+export async function pickDirectory(): Promise<PickDirectoryResponse> {
+  return await invoke<PickDirectoryResponse>('plugin:file-picker|pick_directory');
+}
+
+export interface PickFilesOptions {
+  multiple?: boolean;
+  /** MIME filters such as `application/zip`; only common types filter on desktop. */
+  mimeTypes?: string[];
+}
+
+export interface PickedFile {
+  /** Content URI on Android, the file path on desktop. */
+  uri: string;
+  name: string | null;
+  size: number | null;
+}
+
+export async function pickFiles(options: PickFilesOptions = {}): Promise<PickedFile[]> {
+  return await invoke<{ files: PickedFile[] }>('plugin:file-picker|pick_files', {
+    payload: {
+      multiple: options.multiple ?? false,
+      mimeTypes: options.mimeTypes ?? [],
+    },
+  }).then((r) => r.files);
+}
+
+/**
+ * Ask the user where to save a new file. Resolves to a writable URI (a path on
+ * desktop), or null if cancelled.
+ */
+export async function createDocument(
+  suggestedName: string,
+  mimeType?: string,
+): Promise<string | null> {
+  return await invoke<{ uri: string | null }>('plugin:file-picker|create_document', {
+    payload: { suggestedName, mimeType },
+  }).then((r) => r.uri);
+}
+
+export type WriteSource = { sourcePath: string } | { content: Uint8Array };
+
+/**
+ * Replace the content of a URI returned by `createDocument`, either with a
+ * local file or with raw bytes. Resolves to the number of bytes written.
+ */
+export async function writeContentUri(
+  contentUri: string,
+  source: WriteSource,
+): Promise<number | null> {
+  const payload =
+    'sourcePath' in source
+      ? { contentUri, sourcePath: source.sourcePath }
+      : { contentUri, content: toBase64(source.content) };
+  return await invoke<{ success: boolean; size: number | null }>(
+    'plugin:file-picker|write_content_uri',
+    { payload },
+  ).then((r) => r.size);
+}
+
+function toBase64(bytes: Uint8Array): string {
+  let binary = '';
+  const chunk = 0x8000;
+  for (let i = 0; i < bytes.length; i += chunk) {
+    binary += String.fromCharCode(...bytes.subarray(i, i + chunk));
+  }
+  return btoa(binary);
+}
+
+export interface PersistedUri {
+  uri: string;
+  read: boolean;
+  write: boolean;
+  /** Milliseconds since the epoch when the grant was taken. */
+  persistedTime: number | null;
+}
+
+/** Keep access to a URI (e.g. an external workspace folder) across restarts. */
+export async function persistUriPermission(uri: string): Promise<boolean> {
+  return await invoke<{ success: boolean }>('plugin:file-picker|persist_uri_permission', {
+    payload: { uri },
+  }).then((r) => r.success);
+}
+
+export async function listPersistedUris(): Promise<PersistedUri[]> {
+  return await invoke<{ uris: PersistedUri[] }>('plugin:file-picker|list_persisted_uris').then(
+    (r) => r.uris,
+  );
+}
+
+export async function releaseUriPermission(uri: string): Promise<boolean> {
+  return await invoke<{ success: boolean }>('plugin:file-picker|release_uri_permission', {
+    payload: { uri },
+  }).then((r) => r.success);
+}
+
+export interface ReadProgress {
+  contentUri: string;
+  bytesRead: number;
+  /** null when the provider doesn't report a size. */
+  totalBytes: number | null;
+}
+
+/**
+ * Subscribe to progress while `read_content_uri` copies into a target path
+ * (Android only; reads are reported every few megabytes).
+ */
+export async function onReadProgress(
+  handler: (progress: ReadProgress) => void,
+): Promise<PluginListener> {
+  return await addPluginListener('file-picker', 'readProgress', handler);
+}
+
+export interface TreeEntry {
+  name: string;
+  /** Pass to `listTreeUri` for directories, or read it for files. */
+  uri: string;
+  isDirectory: boolean;
+  size: number | null;
+  mimeType: string | null;
+  /** Milliseconds since the epoch. */
+  lastModified: number | null;
+}
+
+/** List the direct children of a directory from `pickDirectory` (or a child). */
+export async function listTreeUri(uri: string): Promise<TreeEntry[]> {
+  return await invoke<{ entries: TreeEntry[] }>('plugin:file-picker|list_tree_uri', {
+    payload: { uri },
+  }).then((r) => r.entries);
+}
+
+export interface ShareFileOptions {
+  /** Guessed from the file name when omitted. */
+  mimeType?: string;
+  /** Title of the Android share sheet. */
+  title?: string;
+}
+
+/**
+ * Send a file (local path or content URI) to another app through the Android
+ * share sheet. On desktop the file is revealed in the system file manager.
+ */
+export async function shareFile(pathOrUri: string, options: ShareFileOptions = {}): Promise<void> {
+  await invoke('plugin:file-picker|share_file', {
+    payload: { pathOrUri, ...options },
+  });
+}
+
+export interface IncomingIntent {
+  /** `content://` or `file://` URI. */
+  uri: string;
+  name: string | null;
+  mimeType: string | null;
+  /** A SAF tree (see `listTreeUri`) or a local directory. */
+  isDirectory: boolean;
+}
+
+/** Take the file or folder the app was opened with ("Open with" / share), if any. */
+export async function takeIncomingIntent(): Promise<IncomingIntent | null> {
+  return await invoke<{ incoming: IncomingIntent | null }>(
+    'plugin:file-picker|take_incoming_intent',
+  ).then((r) => r.incoming);
+}
+
+/**
+ * Fires when the running app receives a new file; call `takeIncomingIntent`
+ * to fetch it. Files received at cold start are only available through
+ * `takeIncomingIntent`.
+ */
+export async function onIncomingIntent(handler: () => void): Promise<PluginListener> {
+  return await addPluginListener('file-picker', 'incomingIntent', handler);
+}
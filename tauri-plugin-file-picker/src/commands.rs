@@ -12,6 +12,81 @@ pub(crate) async fn ping<R: Runtime>(
     app.file_picker().ping(payload)
 }
 
+#[command]
+pub(crate) async fn pick_directory<R: Runtime>(app: AppHandle<R>) -> Result<PickDirectoryResponse> {
+    app.file_picker().pick_directory()
+}
+
+#[command]
+pub(crate) async fn pick_files<R: Runtime>(
+    app: AppHandle<R>,
+    payload: PickFilesRequest,
+) -> Result<PickFilesResponse> {
+    app.file_picker().pick_files(payload)
+}
+
+#[command]
+pub(crate) async fn create_document<R: Runtime>(
+    app: AppHandle<R>,
+    payload: CreateDocumentRequest,
+) -> Result<CreateDocumentResponse> {
+    app.file_picker().create_document(payload)
+}
+
+#[command]
+pub(crate) async fn write_content_uri<R: Runtime>(
+    app: AppHandle<R>,
+    payload: WriteContentUriRequest,
+) -> Result<WriteContentUriResponse> {
+    app.file_picker().write_content_uri(payload)
+}
+
+#[command]
+pub(crate) async fn persist_uri_permission<R: Runtime>(
+    app: AppHandle<R>,
+    payload: UriPermissionRequest,
+) -> Result<UriPermissionResponse> {
+    app.file_picker().persist_uri_permission(payload)
+}
+
+#[command]
+pub(crate) async fn list_persisted_uris<R: Runtime>(
+    app: AppHandle<R>,
+) -> Result<ListPersistedUrisResponse> {
+    app.file_picker().list_persisted_uris()
+}
+
+#[command]
+pub(crate) async fn release_uri_permission<R: Runtime>(
+    app: AppHandle<R>,
+    payload: UriPermissionRequest,
+) -> Result<UriPermissionResponse> {
+    app.file_picker().release_uri_permission(payload)
+}
+
+#[command]
+pub(crate) async fn list_tree_uri<R: Runtime>(
+    app: AppHandle<R>,
+    payload: ListTreeUriRequest,
+) -> Result<ListTreeUriResponse> {
+    app.file_picker().list_tree_uri(payload)
+}
+
+#[command]
+pub(crate) async fn share_file<R: Runtime>(
+    app: AppHandle<R>,
+    payload: ShareFileRequest,
+) -> Result<()> {
+    app.file_picker().share_file(payload)
+}
+
+#[command]
+pub(crate) async fn take_incoming_intent<R: Runtime>(
+    app: AppHandle<R>,
+) -> Result<TakeIncomingIntentResponse> {
+    app.file_picker().take_incoming_intent()
+}
+
 #[command]
 pub(crate) async fn read_content_uri<R: Runtime>(
     app: AppHandle<R>,
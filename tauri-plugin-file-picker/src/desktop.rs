@@ -1,3 +1,5 @@
+use std::path::Path;
+
 use serde::de::DeserializeOwned;
 use tauri::{plugin::PluginApi, AppHandle, Runtime};
 
@@ -20,13 +22,251 @@ impl<R: Runtime> FilePicker<R> {
         })
     }
 
+    /// The desktop pickers return plain paths, so "reading a content URI"
+    /// is an ordinary file copy or read there.
     pub fn read_content_uri(
         &self,
-        _payload: ReadContentUriRequest,
+        payload: ReadContentUriRequest,
     ) -> crate::Result<ReadContentUriResponse> {
-        // Desktop 平台不需要处理 Content URI
-        Err(crate::Error::Custom(
-            "Desktop platforms do not support Content URI".into(),
-        ))
+        use base64::Engine as _;
+
+        if payload.content_uri.starts_with("content://") {
+            return Err(crate::Error::Custom(
+                "Desktop platforms do not support Content URI".into(),
+            ));
+        }
+
+        match payload.target_path {
+            Some(target) => {
+                if let Some(parent) = Path::new(&target).parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                let size = std::fs::copy(&payload.content_uri, &target)?;
+                Ok(ReadContentUriResponse {
+                    success: true,
+                    path: Some(target),
+                    content: None,
+                    size: Some(size as usize),
+                })
+            }
+            None => {
+                let bytes = std::fs::read(&payload.content_uri)?;
+                Ok(ReadContentUriResponse {
+                    success: true,
+                    path: None,
+                    content: Some(base64::engine::general_purpose::STANDARD.encode(&bytes)),
+                    size: Some(bytes.len()),
+                })
+            }
+        }
+    }
+
+    /// rfd dialogs must be shown on the main thread; run one there and wait
+    /// for the user's choice.
+    fn show_dialog<T: Send + 'static>(
+        &self,
+        dialog: impl FnOnce(rfd::FileDialog) -> T + Send + 'static,
+    ) -> crate::Result<T> {
+        let (tx, rx) = std::sync::mpsc::channel();
+        self.0
+            .run_on_main_thread(move || {
+                let _ = tx.send(dialog(rfd::FileDialog::new()));
+            })
+            .map_err(|e| crate::Error::Custom(e.to_string()))?;
+        rx.recv().map_err(|e| crate::Error::Custom(e.to_string()))
+    }
+
+    pub fn pick_directory(&self) -> crate::Result<PickDirectoryResponse> {
+        let folder = self.show_dialog(|dialog| dialog.pick_folder())?;
+        let path = folder.map(|folder| path_to_string(&folder));
+        Ok(PickDirectoryResponse {
+            uri: path.clone(),
+            display_path: path,
+        })
+    }
+
+    pub fn pick_files(&self, payload: PickFilesRequest) -> crate::Result<PickFilesResponse> {
+        let extensions = extensions_for_mime_types(&payload.mime_types);
+        let paths = self.show_dialog(move |mut dialog| {
+            if !extensions.is_empty() {
+                dialog = dialog.add_filter("", &extensions);
+            }
+            let picked = if payload.multiple {
+                dialog.pick_files()
+            } else {
+                dialog.pick_file().map(|file| vec![file])
+            };
+            picked.unwrap_or_default()
+        })?;
+
+        let files = paths
+            .into_iter()
+            .map(|path| PickedFile {
+                name: path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned()),
+                size: std::fs::metadata(&path).ok().map(|meta| meta.len()),
+                uri: path_to_string(&path),
+            })
+            .collect();
+        Ok(PickFilesResponse { files })
+    }
+
+    pub fn create_document(
+        &self,
+        payload: CreateDocumentRequest,
+    ) -> crate::Result<CreateDocumentResponse> {
+        let extensions = extensions_for_mime_types(payload.mime_type.as_slice());
+        let path = self.show_dialog(move |mut dialog| {
+            if !extensions.is_empty() {
+                dialog = dialog.add_filter("", &extensions);
+            }
+            dialog.set_file_name(payload.suggested_name).save_file()
+        })?;
+        Ok(CreateDocumentResponse {
+            uri: path.map(|path| path_to_string(&path)),
+        })
+    }
+
+    // Desktop 上的路径没有授权过期的问题
+    pub fn persist_uri_permission(
+        &self,
+        _payload: UriPermissionRequest,
+    ) -> crate::Result<UriPermissionResponse> {
+        Ok(UriPermissionResponse { success: true })
+    }
+
+    pub fn list_persisted_uris(&self) -> crate::Result<ListPersistedUrisResponse> {
+        Ok(ListPersistedUrisResponse::default())
+    }
+
+    pub fn release_uri_permission(
+        &self,
+        _payload: UriPermissionRequest,
+    ) -> crate::Result<UriPermissionResponse> {
+        Ok(UriPermissionResponse { success: true })
+    }
+
+    pub fn list_tree_uri(&self, payload: ListTreeUriRequest) -> crate::Result<ListTreeUriResponse> {
+        let mut entries = Vec::new();
+        for entry in std::fs::read_dir(&payload.uri)? {
+            let entry = entry?;
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            let last_modified = metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|duration| duration.as_millis() as i64);
+            entries.push(TreeEntry {
+                name: entry.file_name().to_string_lossy().into_owned(),
+                uri: path_to_string(&entry.path()),
+                is_directory: metadata.is_dir(),
+                size: metadata.is_file().then(|| metadata.len()),
+                mime_type: None,
+                last_modified,
+            });
+        }
+        Ok(ListTreeUriResponse { entries })
+    }
+
+    /// Desktops have no common share sheet; reveal the file in the system
+    /// file manager so it can be dragged or attached from there.
+    pub fn share_file(&self, payload: ShareFileRequest) -> crate::Result<()> {
+        let path = Path::new(&payload.path_or_uri);
+        if !path.exists() {
+            return Err(crate::Error::Custom(format!(
+                "File not found: {}",
+                payload.path_or_uri
+            )));
+        }
+
+        #[cfg(target_os = "macos")]
+        let status = std::process::Command::new("open")
+            .arg("-R")
+            .arg(path)
+            .status()?;
+        #[cfg(target_os = "windows")]
+        let status = std::process::Command::new("explorer")
+            .arg(format!("/select,{}", path.display()))
+            .status()?;
+        #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+        let status = std::process::Command::new("xdg-open")
+            .arg(path.parent().unwrap_or(path))
+            .status()?;
+
+        // explorer 即使成功也会返回非零退出码
+        if !status.success() && !cfg!(target_os = "windows") {
+            return Err(crate::Error::Custom(format!(
+                "Failed to open file manager: {status}"
+            )));
+        }
+        Ok(())
+    }
+
+    // 桌面端不接收 Android Intent
+    pub fn take_incoming_intent(&self) -> crate::Result<TakeIncomingIntentResponse> {
+        Ok(TakeIncomingIntentResponse::default())
+    }
+
+    /// `create_document` hands out plain paths on desktop, so "writing a
+    /// content URI" is an ordinary file write there.
+    pub fn write_content_uri(
+        &self,
+        payload: WriteContentUriRequest,
+    ) -> crate::Result<WriteContentUriResponse> {
+        use base64::Engine as _;
+
+        if payload.content_uri.starts_with("content://") {
+            return Err(crate::Error::Custom(
+                "Desktop platforms do not support Content URI".into(),
+            ));
+        }
+
+        let size = match (payload.source_path, payload.content) {
+            (Some(source), _) => std::fs::copy(&source, &payload.content_uri)?,
+            (None, Some(content)) => {
+                let bytes = base64::engine::general_purpose::STANDARD
+                    .decode(content.as_bytes())
+                    .map_err(|e| crate::Error::Custom(e.to_string()))?;
+                std::fs::write(&payload.content_uri, &bytes)?;
+                bytes.len() as u64
+            }
+            (None, None) => {
+                return Err(crate::Error::Custom(
+                    "sourcePath or content is required".into(),
+                ))
+            }
+        };
+
+        Ok(WriteContentUriResponse {
+            success: true,
+            size: Some(size),
+        })
+    }
+}
+
+fn path_to_string(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+/// Map MIME filters to rfd extension filters. Any type without a known
+/// mapping (including wildcards) disables filtering.
+fn extensions_for_mime_types(mime_types: &[String]) -> Vec<&'static str> {
+    let mut extensions = Vec::new();
+    for mime in mime_types {
+        let mapped: &[&str] = match mime.as_str() {
+            "application/zip" | "application/x-zip-compressed" => &["zip"],
+            "application/x-xz" => &["xz"],
+            "application/gzip" | "application/x-gzip" => &["gz", "tgz"],
+            "application/x-tar" => &["tar"],
+            "application/json" => &["json"],
+            "text/plain" => &["txt", "log"],
+            // 出现无法映射的类型时放开过滤，避免把合法文件挡在外面
+            _ => return Vec::new(),
+        };
+        extensions.extend_from_slice(mapped);
     }
+    extensions
 }
@@ -37,7 +37,17 @@ pub fn init<R: Runtime>() -> TauriPlugin<R> {
     Builder::new("file-picker")
         .invoke_handler(tauri::generate_handler![
             commands::ping,
-            commands::read_content_uri
+            commands::read_content_uri,
+            commands::pick_directory,
+            commands::pick_files,
+            commands::create_document,
+            commands::write_content_uri,
+            commands::persist_uri_permission,
+            commands::list_persisted_uris,
+            commands::release_uri_permission,
+            commands::list_tree_uri,
+            commands::share_file,
+            commands::take_incoming_intent
         ])
         .setup(|app, api| {
             #[cfg(mobile)]
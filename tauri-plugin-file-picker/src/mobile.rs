@@ -39,4 +39,81 @@ impl<R: Runtime> FilePicker<R> {
             .run_mobile_plugin("readContentUri", payload)
             .map_err(Into::into)
     }
+
+    /// Let the user pick a folder through the Storage Access Framework. Read
+    /// and write access to the tree is persisted across restarts.
+    pub fn pick_directory(&self) -> crate::Result<PickDirectoryResponse> {
+        self.0
+            .run_mobile_plugin("pickDirectory", ())
+            .map_err(Into::into)
+    }
+
+    pub fn pick_files(&self, payload: PickFilesRequest) -> crate::Result<PickFilesResponse> {
+        self.0
+            .run_mobile_plugin("pickFiles", payload)
+            .map_err(Into::into)
+    }
+
+    pub fn create_document(
+        &self,
+        payload: CreateDocumentRequest,
+    ) -> crate::Result<CreateDocumentResponse> {
+        self.0
+            .run_mobile_plugin("createDocument", payload)
+            .map_err(Into::into)
+    }
+
+    /// Keep access to `uri` across app restarts.
+    pub fn persist_uri_permission(
+        &self,
+        payload: UriPermissionRequest,
+    ) -> crate::Result<UriPermissionResponse> {
+        self.0
+            .run_mobile_plugin("persistUriPermission", payload)
+            .map_err(Into::into)
+    }
+
+    pub fn list_persisted_uris(&self) -> crate::Result<ListPersistedUrisResponse> {
+        self.0
+            .run_mobile_plugin("listPersistedUris", ())
+            .map_err(Into::into)
+    }
+
+    pub fn release_uri_permission(
+        &self,
+        payload: UriPermissionRequest,
+    ) -> crate::Result<UriPermissionResponse> {
+        self.0
+            .run_mobile_plugin("releaseUriPermission", payload)
+            .map_err(Into::into)
+    }
+
+    pub fn list_tree_uri(&self, payload: ListTreeUriRequest) -> crate::Result<ListTreeUriResponse> {
+        self.0
+            .run_mobile_plugin("listTreeUri", payload)
+            .map_err(Into::into)
+    }
+
+    /// Open the Android share sheet for a file.
+    pub fn share_file(&self, payload: ShareFileRequest) -> crate::Result<()> {
+        self.0
+            .run_mobile_plugin("shareFile", payload)
+            .map_err(Into::into)
+    }
+
+    /// Take the file or folder the app was last opened with, if any.
+    pub fn take_incoming_intent(&self) -> crate::Result<TakeIncomingIntentResponse> {
+        self.0
+            .run_mobile_plugin("takeIncomingIntent", ())
+            .map_err(Into::into)
+    }
+
+    pub fn write_content_uri(
+        &self,
+        payload: WriteContentUriRequest,
+    ) -> crate::Result<WriteContentUriResponse> {
+        self.0
+            .run_mobile_plugin("writeContentUri", payload)
+            .map_err(Into::into)
+    }
 }
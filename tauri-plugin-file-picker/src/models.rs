@@ -1,5 +1,9 @@
 use serde::{Deserialize, Serialize};
 
+/// Plugin event (see `addPluginListener`) fired when the running app receives
+/// a VIEW/SEND intent. Call `take_incoming_intent` to fetch it.
+pub const INCOMING_INTENT_EVENT: &str = "incomingIntent";
+
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PingRequest {
@@ -19,6 +23,17 @@ pub struct ReadContentUriRequest {
     pub target_path: Option<String>,
 }
 
+/// Payload of the `readProgress` plugin event emitted while
+/// `read_content_uri` streams into `target_path`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadProgress {
+    pub content_uri: String,
+    pub bytes_read: u64,
+    /// `None` when the provider doesn't report a size.
+    pub total_bytes: Option<u64>,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ReadContentUriResponse {
@@ -27,3 +42,160 @@ pub struct ReadContentUriResponse {
     pub content: Option<String>,
     pub size: Option<usize>,
 }
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PickDirectoryResponse {
+    /// Tree URI on Android (`content://...`), the folder path on desktop.
+    /// `None` when the user cancelled.
+    pub uri: Option<String>,
+    /// Human-readable location, e.g. `/storage/emulated/0/Projects/demo`.
+    pub display_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PickFilesRequest {
+    #[serde(default)]
+    pub multiple: bool,
+    /// MIME filters such as `application/zip`. Empty means any type. On
+    /// desktop only common archive and text types are mapped to extensions.
+    #[serde(default)]
+    pub mime_types: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PickedFile {
+    /// Content URI on Android, the file path on desktop.
+    pub uri: String,
+    pub name: Option<String>,
+    pub size: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PickFilesResponse {
+    /// Empty when the user cancelled.
+    pub files: Vec<PickedFile>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateDocumentRequest {
+    pub suggested_name: String,
+    /// Defaults to `application/octet-stream`.
+    pub mime_type: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateDocumentResponse {
+    /// Writable content URI on Android, the chosen path on desktop. `None`
+    /// when the user cancelled.
+    pub uri: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UriPermissionRequest {
+    pub uri: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UriPermissionResponse {
+    pub success: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PersistedUri {
+    pub uri: String,
+    pub read: bool,
+    pub write: bool,
+    /// Milliseconds since the epoch when the grant was taken.
+    pub persisted_time: Option<i64>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListPersistedUrisResponse {
+    pub uris: Vec<PersistedUri>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListTreeUriRequest {
+    /// A tree URI from `pick_directory`, or a child `uri` returned by a
+    /// previous listing.
+    pub uri: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TreeEntry {
+    pub name: String,
+    /// Pass back to `list_tree_uri` for directories, or to
+    /// `read_content_uri` for files.
+    pub uri: String,
+    pub is_directory: bool,
+    pub size: Option<u64>,
+    pub mime_type: Option<String>,
+    /// Milliseconds since the epoch.
+    pub last_modified: Option<i64>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListTreeUriResponse {
+    pub entries: Vec<TreeEntry>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShareFileRequest {
+    /// A local path or a content URI.
+    pub path_or_uri: String,
+    /// Guessed from the file name when `None`.
+    pub mime_type: Option<String>,
+    /// Title of the Android share sheet.
+    pub title: Option<String>,
+}
+
+/// A file or folder another app asked us to open ("Open with" / share).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IncomingIntent {
+    /// `content://` or `file://` URI.
+    pub uri: String,
+    pub name: Option<String>,
+    pub mime_type: Option<String>,
+    /// `uri` is a SAF tree (list it with `list_tree_uri`) or a `file://`
+    /// directory.
+    pub is_directory: bool,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TakeIncomingIntentResponse {
+    /// The pending intent, cleared by this call. `None` when there is none.
+    pub incoming: Option<IncomingIntent>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WriteContentUriRequest {
+    pub content_uri: String,
+    /// Local file whose bytes replace the document's content.
+    pub source_path: Option<String>,
+    /// Base64-encoded bytes to write instead of `source_path`.
+    pub content: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WriteContentUriResponse {
+    pub success: bool,
+    pub size: Option<u64>,
+}
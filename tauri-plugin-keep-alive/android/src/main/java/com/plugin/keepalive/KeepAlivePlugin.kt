@@ -0,0 +1,50 @@
+package com.plugin.keepalive
+
+import android.app.Activity
+import android.content.Intent
+import androidx.core.content.ContextCompat
+import app.tauri.annotation.Command
+import app.tauri.annotation.InvokeArg
+import app.tauri.annotation.TauriPlugin
+import app.tauri.plugin.JSObject
+import app.tauri.plugin.Plugin
+import app.tauri.plugin.Invoke
+
+@InvokeArg
+class StartArgs {
+  lateinit var title: String
+  lateinit var text: String
+}
+
+@TauriPlugin
+class KeepAlivePlugin(private val activity: Activity): Plugin(activity) {
+
+    /**
+     * 启动前台服务；服务已在运行时只更新通知内容
+     */
+    @Command
+    fun start(invoke: Invoke) {
+        try {
+            val args = invoke.parseArgs(StartArgs::class.java)
+            val intent = Intent(activity, KeepAliveService::class.java)
+                .putExtra(KeepAliveService.EXTRA_TITLE, args.title)
+                .putExtra(KeepAliveService.EXTRA_TEXT, args.text)
+            ContextCompat.startForegroundService(activity, intent)
+
+            val ret = JSObject()
+            ret.put("running", true)
+            invoke.resolve(ret)
+        } catch (e: Exception) {
+            invoke.reject("启动前台服务失败: ${e.message}")
+        }
+    }
+
+    @Command
+    fun stop(invoke: Invoke) {
+        activity.stopService(Intent(activity, KeepAliveService::class.java))
+
+        val ret = JSObject()
+        ret.put("running", false)
+        invoke.resolve(ret)
+    }
+}
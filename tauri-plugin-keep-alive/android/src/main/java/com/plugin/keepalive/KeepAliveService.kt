@@ -0,0 +1,87 @@
+package com.plugin.keepalive
+
+import android.app.NotificationChannel
+import android.app.NotificationManager
+import android.app.PendingIntent
+import android.app.Service
+import android.content.Intent
+import android.content.pm.ServiceInfo
+import android.os.Build
+import android.os.IBinder
+import androidx.core.app.NotificationCompat
+import androidx.core.app.ServiceCompat
+
+/**
+ * 终端或语言服务器会话运行期间保持进程存活的前台服务
+ *
+ * 服务本身不做任何工作，只是让系统不在后台冻结或回收应用进程。
+ */
+class KeepAliveService : Service() {
+    companion object {
+        const val EXTRA_TITLE = "title"
+        const val EXTRA_TEXT = "text"
+        private const val CHANNEL_ID = "truidide_sessions"
+        private const val NOTIFICATION_ID = 1001
+    }
+
+    override fun onBind(intent: Intent?): IBinder? = null
+
+    override fun onStartCommand(intent: Intent?, flags: Int, startId: Int): Int {
+        createChannel()
+
+        val title = intent?.getStringExtra(EXTRA_TITLE) ?: "TruidIDE"
+        val text = intent?.getStringExtra(EXTRA_TEXT) ?: ""
+        val builder = NotificationCompat.Builder(this, CHANNEL_ID)
+            .setContentTitle(title)
+            .setContentText(text)
+            .setSmallIcon(applicationInfo.icon)
+            .setOngoing(true)
+            .setOnlyAlertOnce(true)
+            .setPriority(NotificationCompat.PRIORITY_LOW)
+            .setCategory(NotificationCompat.CATEGORY_SERVICE)
+
+        // 点击通知回到应用
+        packageManager.getLaunchIntentForPackage(packageName)?.let { launch ->
+            launch.flags = Intent.FLAG_ACTIVITY_SINGLE_TOP
+            val pending = PendingIntent.getActivity(
+                this,
+                0,
+                launch,
+                PendingIntent.FLAG_UPDATE_CURRENT or PendingIntent.FLAG_IMMUTABLE
+            )
+            builder.setContentIntent(pending)
+        }
+
+        val type = if (Build.VERSION.SDK_INT >= Build.VERSION_CODES.UPSIDE_DOWN_CAKE) {
+            ServiceInfo.FOREGROUND_SERVICE_TYPE_SPECIAL_USE
+        } else {
+            0
+        }
+        ServiceCompat.startForeground(this, NOTIFICATION_ID, builder.build(), type)
+
+        // 进程被回收后会话已经不在了，不需要系统重启服务
+        return START_NOT_STICKY
+    }
+
+    override fun onDestroy() {
+        ServiceCompat.stopForeground(this, ServiceCompat.STOP_FOREGROUND_REMOVE)
+        super.onDestroy()
+    }
+
+    private fun createChannel() {
+        if (Build.VERSION.SDK_INT < Build.VERSION_CODES.O) {
+            return
+        }
+        val manager = getSystemService(NotificationManager::class.java)
+        if (manager.getNotificationChannel(CHANNEL_ID) == null) {
+            val channel = NotificationChannel(
+                CHANNEL_ID,
+                "后台会话",
+                NotificationManager.IMPORTANCE_LOW
+            ).apply {
+                description = "终端和语言服务器在后台运行时显示"
+            }
+            manager.createNotificationChannel(channel)
+        }
+    }
+}
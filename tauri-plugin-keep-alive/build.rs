@@ -0,0 +1,8 @@
+// The service is driven from Rust only, the plugin exposes no commands.
+const COMMANDS: &[&str] = &[];
+
+fn main() {
+    tauri_plugin::Builder::new(COMMANDS)
+        .android_path("android")
+        .build()
+}
@@ -0,0 +1,25 @@
+use serde::de::DeserializeOwned;
+use tauri::{plugin::PluginApi, AppHandle, Runtime};
+
+use crate::models::*;
+
+pub fn init<R: Runtime, C: DeserializeOwned>(
+    app: &AppHandle<R>,
+    _api: PluginApi<R, C>,
+) -> crate::Result<KeepAlive<R>> {
+    Ok(KeepAlive(app.clone()))
+}
+
+/// Access to the keep-alive APIs.
+pub struct KeepAlive<R: Runtime>(AppHandle<R>);
+
+impl<R: Runtime> KeepAlive<R> {
+    // 桌面端不会冻结后台进程，无需前台服务
+    pub fn start(&self, _payload: StartServiceRequest) -> crate::Result<ServiceStatus> {
+        Ok(ServiceStatus { running: false })
+    }
+
+    pub fn stop(&self) -> crate::Result<ServiceStatus> {
+        Ok(ServiceStatus { running: false })
+    }
+}
@@ -0,0 +1,46 @@
+use tauri::{
+    plugin::{Builder, TauriPlugin},
+    Manager, Runtime,
+};
+
+pub use models::*;
+
+#[cfg(not(target_os = "android"))]
+mod desktop;
+#[cfg(target_os = "android")]
+mod mobile;
+
+mod error;
+mod models;
+
+pub use error::{Error, Result};
+
+#[cfg(not(target_os = "android"))]
+use desktop::KeepAlive;
+#[cfg(target_os = "android")]
+use mobile::KeepAlive;
+
+/// Extensions to [`tauri::App`], [`tauri::AppHandle`] and [`tauri::Window`] to access the keep-alive APIs.
+pub trait KeepAliveExt<R: Runtime> {
+    fn keep_alive(&self) -> &KeepAlive<R>;
+}
+
+impl<R: Runtime, T: Manager<R>> crate::KeepAliveExt<R> for T {
+    fn keep_alive(&self) -> &KeepAlive<R> {
+        self.state::<KeepAlive<R>>().inner()
+    }
+}
+
+/// Initializes the plugin.
+pub fn init<R: Runtime>() -> TauriPlugin<R> {
+    Builder::new("keep-alive")
+        .setup(|app, api| {
+            #[cfg(target_os = "android")]
+            let keep_alive = mobile::init(app, api)?;
+            #[cfg(not(target_os = "android"))]
+            let keep_alive = desktop::init(app, api)?;
+            app.manage(keep_alive);
+            Ok(())
+        })
+        .build()
+}
@@ -0,0 +1,33 @@
+use serde::de::DeserializeOwned;
+use tauri::{
+    plugin::{PluginApi, PluginHandle},
+    AppHandle, Runtime,
+};
+
+use crate::models::*;
+
+// initializes the Kotlin plugin class
+pub fn init<R: Runtime, C: DeserializeOwned>(
+    _app: &AppHandle<R>,
+    api: PluginApi<R, C>,
+) -> crate::Result<KeepAlive<R>> {
+    let handle = api.register_android_plugin("com.plugin.keepalive", "KeepAlivePlugin")?;
+    Ok(KeepAlive(handle))
+}
+
+/// Access to the keep-alive APIs.
+pub struct KeepAlive<R: Runtime>(PluginHandle<R>);
+
+impl<R: Runtime> KeepAlive<R> {
+    /// Start the foreground service, or update its notification if it is
+    /// already running.
+    pub fn start(&self, payload: StartServiceRequest) -> crate::Result<ServiceStatus> {
+        self.0
+            .run_mobile_plugin("start", payload)
+            .map_err(Into::into)
+    }
+
+    pub fn stop(&self) -> crate::Result<ServiceStatus> {
+        self.0.run_mobile_plugin("stop", ()).map_err(Into::into)
+    }
+}
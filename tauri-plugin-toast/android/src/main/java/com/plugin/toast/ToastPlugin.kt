@@ -0,0 +1,223 @@
+package com.plugin.toast
+
+import android.app.Activity
+import android.app.NotificationChannel
+import android.app.NotificationManager
+import android.app.PendingIntent
+import android.content.Context
+import android.content.Intent
+import android.graphics.Color
+import android.graphics.drawable.GradientDrawable
+import android.os.Build
+import android.util.TypedValue
+import android.view.Gravity
+import android.widget.TextView
+import android.widget.Toast
+import androidx.core.app.NotificationCompat
+import androidx.core.app.NotificationManagerCompat
+import com.google.android.material.snackbar.Snackbar
+import app.tauri.annotation.Command
+import app.tauri.annotation.InvokeArg
+import app.tauri.annotation.TauriPlugin
+import app.tauri.plugin.Invoke
+import app.tauri.plugin.JSObject
+import app.tauri.plugin.Plugin
+
+@InvokeArg
+class ToastArgs {
+  lateinit var text: String
+  var duration: String = "short"
+  var gravity: String? = null
+  var xOffset: Int = 0
+  var yOffset: Int = 0
+  var type: String = "info"
+}
+
+@InvokeArg
+class SnackbarArgs {
+  lateinit var text: String
+  var actionLabel: String? = null
+  var duration: String = "short"
+}
+
+@InvokeArg
+class NotificationProgressArgs {
+  var current: Int = 0
+  var max: Int = 0
+  var indeterminate: Boolean = false
+}
+
+@InvokeArg
+class NotifyArgs {
+  var id: Int = 0
+  lateinit var title: String
+  lateinit var text: String
+  var progress: NotificationProgressArgs? = null
+  var ongoing: Boolean = false
+  var deepLink: String? = null
+}
+
+@InvokeArg
+class CancelNotificationArgs {
+  var id: Int = 0
+}
+
+@TauriPlugin
+class ToastPlugin(private val activity: Activity): Plugin(activity) {
+
+    /**
+     * 显示 Toast，按 type 使用不同的背景色
+     */
+    @Command
+    fun toast(invoke: Invoke) {
+        val args = invoke.parseArgs(ToastArgs::class.java)
+        activity.runOnUiThread {
+            try {
+                val duration = if (args.duration == "long") Toast.LENGTH_LONG else Toast.LENGTH_SHORT
+                val toast = Toast(activity)
+                toast.duration = duration
+                @Suppress("DEPRECATION")
+                toast.view = createToastView(args.text, args.type)
+
+                val gravity = when (args.gravity) {
+                    "top" -> Gravity.TOP or Gravity.CENTER_HORIZONTAL
+                    "center" -> Gravity.CENTER
+                    "bottom" -> Gravity.BOTTOM or Gravity.CENTER_HORIZONTAL
+                    else -> null
+                }
+                if (gravity != null) {
+                    toast.setGravity(gravity, args.xOffset, args.yOffset)
+                }
+
+                toast.show()
+                invoke.resolve()
+            } catch (e: Exception) {
+                invoke.reject("显示 Toast 失败: ${e.message}")
+            }
+        }
+    }
+
+    /**
+     * 显示 Snackbar，消失后返回是否点击了操作按钮
+     */
+    @Command
+    fun snackbar(invoke: Invoke) {
+        val args = invoke.parseArgs(SnackbarArgs::class.java)
+        activity.runOnUiThread {
+            try {
+                val root = activity.findViewById<android.view.View>(android.R.id.content)
+                val duration = if (args.duration == "long") Snackbar.LENGTH_LONG else Snackbar.LENGTH_SHORT
+                val snackbar = Snackbar.make(root, args.text, duration)
+                val actionLabel = args.actionLabel
+                if (actionLabel != null) {
+                    snackbar.setAction(actionLabel) {}
+                }
+                snackbar.addCallback(object : Snackbar.Callback() {
+                    override fun onDismissed(transientBottomBar: Snackbar?, event: Int) {
+                        val ret = JSObject()
+                        ret.put("actionTapped", event == DISMISS_EVENT_ACTION)
+                        invoke.resolve(ret)
+                    }
+                })
+                snackbar.show()
+            } catch (e: Exception) {
+                invoke.reject("显示 Snackbar 失败: ${e.message}")
+            }
+        }
+    }
+
+    /**
+     * 发布或更新系统通知；相同 id 的通知会被原地替换
+     */
+    @Command
+    fun notify(invoke: Invoke) {
+        try {
+            val args = invoke.parseArgs(NotifyArgs::class.java)
+            ensureChannel()
+
+            val builder = NotificationCompat.Builder(activity, CHANNEL_ID)
+                .setSmallIcon(activity.applicationInfo.icon)
+                .setContentTitle(args.title)
+                .setContentText(args.text)
+                .setOngoing(args.ongoing)
+                .setAutoCancel(!args.ongoing)
+                .setOnlyAlertOnce(true)
+                .setPriority(NotificationCompat.PRIORITY_LOW)
+
+            val progress = args.progress
+            if (progress != null) {
+                builder.setProgress(progress.max, progress.current, progress.indeterminate)
+            }
+
+            val deepLink = args.deepLink
+            if (deepLink != null) {
+                // 点击通知回到应用，并在 onNewIntent 中把 deepLink 交给前端
+                val intent = Intent(activity, activity.javaClass)
+                    .addFlags(Intent.FLAG_ACTIVITY_SINGLE_TOP or Intent.FLAG_ACTIVITY_CLEAR_TOP)
+                    .putExtra(EXTRA_DEEP_LINK, deepLink)
+                val flags = PendingIntent.FLAG_UPDATE_CURRENT or PendingIntent.FLAG_IMMUTABLE
+                builder.setContentIntent(PendingIntent.getActivity(activity, args.id, intent, flags))
+            }
+
+            NotificationManagerCompat.from(activity).notify(args.id, builder.build())
+            invoke.resolve()
+        } catch (e: SecurityException) {
+            invoke.reject("没有发送通知的权限: ${e.message}")
+        } catch (e: Exception) {
+            invoke.reject("发送通知失败: ${e.message}")
+        }
+    }
+
+    @Command
+    fun cancelNotification(invoke: Invoke) {
+        val args = invoke.parseArgs(CancelNotificationArgs::class.java)
+        NotificationManagerCompat.from(activity).cancel(args.id)
+        invoke.resolve()
+    }
+
+    override fun onNewIntent(intent: Intent) {
+        val deepLink = intent.getStringExtra(EXTRA_DEEP_LINK) ?: return
+        val data = JSObject()
+        data.put("deepLink", deepLink)
+        trigger("notificationTapped", data)
+    }
+
+    private fun ensureChannel() {
+        if (Build.VERSION.SDK_INT < Build.VERSION_CODES.O) return
+        val manager = activity.getSystemService(Context.NOTIFICATION_SERVICE) as NotificationManager
+        if (manager.getNotificationChannel(CHANNEL_ID) != null) return
+        val channel = NotificationChannel(CHANNEL_ID, "任务进度", NotificationManager.IMPORTANCE_LOW)
+        channel.description = "下载、导出等耗时任务的进度与结果"
+        manager.createNotificationChannel(channel)
+    }
+
+    private fun createToastView(text: String, type: String): TextView {
+        val background = GradientDrawable().apply {
+            cornerRadius = dp(20f)
+            setColor(backgroundColor(type))
+        }
+        return TextView(activity).apply {
+            this.text = text
+            setTextColor(Color.WHITE)
+            setTextSize(TypedValue.COMPLEX_UNIT_SP, 14f)
+            val horizontal = dp(16f).toInt()
+            val vertical = dp(10f).toInt()
+            setPadding(horizontal, vertical, horizontal, vertical)
+            this.background = background
+        }
+    }
+
+    private fun backgroundColor(type: String): Int = when (type) {
+        "success" -> Color.parseColor("#2E7D32")
+        "error" -> Color.parseColor("#C62828")
+        else -> Color.parseColor("#323232")
+    }
+
+    private fun dp(value: Float): Float =
+        TypedValue.applyDimension(TypedValue.COMPLEX_UNIT_DIP, value, activity.resources.displayMetrics)
+
+    companion object {
+        private const val CHANNEL_ID = "truidide_tasks"
+        private const val EXTRA_DEEP_LINK = "com.plugin.toast.DEEP_LINK"
+    }
+}
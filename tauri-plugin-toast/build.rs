@@ -0,0 +1,15 @@
+const COMMANDS: &[&str] = &[
+    "toast",
+    "snackbar",
+    "notify",
+    "cancel_notification",
+    // 前端通过插件事件监听通知点击
+    "register_listener",
+    "remove_listener",
+];
+
+fn main() {
+    tauri_plugin::Builder::new(COMMANDS)
+        .android_path("android")
+        .build()
+}
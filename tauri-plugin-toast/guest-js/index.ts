@@ -0,0 +1,93 @@
+import { addPluginListener, invoke, type PluginListener } from '@tauri-apps/api/core'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
+
+/** Emitted instead of a native toast on desktop. */
+export const TOAST_EVENT = 'toast://show';
+
+export type ToastDuration = 'short' | 'long';
+export type ToastGravity = 'top' | 'center' | 'bottom';
+export type ToastType = 'info' | 'success' | 'error';
+
+export interface ToastOptions {
+  duration?: ToastDuration;
+  /** Omit to keep the system default position. */
+  gravity?: ToastGravity;
+  /** Offsets in pixels, only applied together with `gravity`. */
+  xOffset?: number;
+  yOffset?: number;
+  type?: ToastType;
+}
+
+export async function toast(text: string, options: ToastOptions = {}): Promise<void> {
+  await invoke('plugin:toast|toast', {
+    payload: { text, ...options },
+  });
+}
+
+export interface ToastEvent extends ToastOptions {
+  text: string;
+}
+
+/**
+ * Render toasts yourself on desktop, where `toast` only emits an event.
+ * Never fires on Android.
+ */
+export async function onToast(handler: (toast: ToastEvent) => void): Promise<UnlistenFn> {
+  return await listen<ToastEvent>(TOAST_EVENT, (event) => handler(event.payload));
+}
+
+/** Emitted instead of a native snackbar on desktop. */
+export const SNACKBAR_EVENT = 'toast://snackbar';
+
+export interface SnackbarOptions {
+  /** Label of the action button, e.g. "撤销". */
+  actionLabel?: string;
+  duration?: ToastDuration;
+}
+
+/**
+ * Show a snackbar and resolve once it is dismissed, with whether its action
+ * was tapped. On desktop it only emits `SNACKBAR_EVENT` and resolves false.
+ */
+export async function snackbar(text: string, options: SnackbarOptions = {}): Promise<boolean> {
+  return await invoke<{ actionTapped: boolean }>('plugin:toast|snackbar', {
+    payload: { text, ...options },
+  }).then((r) => r.actionTapped);
+}
+
+export interface NotificationProgress {
+  current: number;
+  max: number;
+  /** Show an animated bar instead of `current / max`. */
+  indeterminate?: boolean;
+}
+
+export interface NotifyOptions {
+  /** Posting again with the same id updates the notification in place. */
+  id: number;
+  title: string;
+  text: string;
+  progress?: NotificationProgress;
+  /** Ongoing notifications can't be swiped away. */
+  ongoing?: boolean;
+  /** Handed back through `onNotificationTapped`, e.g. a frontend route. */
+  deepLink?: string;
+}
+
+/** Post or update a system notification (Android only, no-op on desktop). */
+export async function notify(options: NotifyOptions): Promise<void> {
+  await invoke('plugin:toast|notify', { payload: options });
+}
+
+export async function cancelNotification(id: number): Promise<void> {
+  await invoke('plugin:toast|cancel_notification', { payload: { id } });
+}
+
+/** Fires when a notification with a `deepLink` is tapped. */
+export async function onNotificationTapped(
+  handler: (deepLink: string) => void,
+): Promise<PluginListener> {
+  return await addPluginListener<{ deepLink: string }>('toast', 'notificationTapped', (event) =>
+    handler(event.deepLink),
+  );
+}
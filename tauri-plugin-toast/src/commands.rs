@@ -0,0 +1,32 @@
+use tauri::{command, AppHandle, Runtime};
+
+use crate::models::*;
+use crate::Result;
+use crate::ToastExt;
+
+#[command]
+pub(crate) async fn toast<R: Runtime>(app: AppHandle<R>, payload: ToastRequest) -> Result<()> {
+    app.toast().show(payload)
+}
+
+/// Resolves once the snackbar is dismissed.
+#[command]
+pub(crate) async fn snackbar<R: Runtime>(
+    app: AppHandle<R>,
+    payload: SnackbarRequest,
+) -> Result<SnackbarResponse> {
+    app.toast().snackbar(payload)
+}
+
+#[command]
+pub(crate) async fn notify<R: Runtime>(app: AppHandle<R>, payload: NotifyRequest) -> Result<()> {
+    app.toast().notify(payload)
+}
+
+#[command]
+pub(crate) async fn cancel_notification<R: Runtime>(
+    app: AppHandle<R>,
+    payload: CancelNotificationRequest,
+) -> Result<()> {
+    app.toast().cancel_notification(payload)
+}
@@ -0,0 +1,42 @@
+use serde::de::DeserializeOwned;
+use tauri::{plugin::PluginApi, AppHandle, Emitter, Runtime};
+
+use crate::models::*;
+
+pub fn init<R: Runtime, C: DeserializeOwned>(
+    app: &AppHandle<R>,
+    _api: PluginApi<R, C>,
+) -> crate::Result<Toast<R>> {
+    Ok(Toast(app.clone()))
+}
+
+/// Access to the toast APIs.
+pub struct Toast<R: Runtime>(AppHandle<R>);
+
+impl<R: Runtime> Toast<R> {
+    // 桌面端没有系统 Toast，转发给前端自行渲染
+    pub fn show(&self, payload: ToastRequest) -> crate::Result<()> {
+        self.0
+            .emit(TOAST_EVENT, &payload)
+            .map_err(|e| crate::Error::Custom(e.to_string()))
+    }
+
+    // 同样交给前端渲染，操作按钮的回调由前端自行处理
+    pub fn snackbar(&self, payload: SnackbarRequest) -> crate::Result<SnackbarResponse> {
+        self.0
+            .emit(SNACKBAR_EVENT, &payload)
+            .map_err(|e| crate::Error::Custom(e.to_string()))?;
+        Ok(SnackbarResponse {
+            action_tapped: false,
+        })
+    }
+
+    // 桌面端的进度已经在界面内展示，不再发系统通知
+    pub fn notify(&self, _payload: NotifyRequest) -> crate::Result<()> {
+        Ok(())
+    }
+
+    pub fn cancel_notification(&self, _payload: CancelNotificationRequest) -> crate::Result<()> {
+        Ok(())
+    }
+}
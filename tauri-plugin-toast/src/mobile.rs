@@ -0,0 +1,47 @@
+use serde::de::DeserializeOwned;
+use tauri::{
+    plugin::{PluginApi, PluginHandle},
+    AppHandle, Runtime,
+};
+
+use crate::models::*;
+
+// initializes the Kotlin plugin class
+pub fn init<R: Runtime, C: DeserializeOwned>(
+    _app: &AppHandle<R>,
+    api: PluginApi<R, C>,
+) -> crate::Result<Toast<R>> {
+    let handle = api.register_android_plugin("com.plugin.toast", "ToastPlugin")?;
+    Ok(Toast(handle))
+}
+
+/// Access to the toast APIs.
+pub struct Toast<R: Runtime>(PluginHandle<R>);
+
+impl<R: Runtime> Toast<R> {
+    pub fn show(&self, payload: ToastRequest) -> crate::Result<()> {
+        self.0
+            .run_mobile_plugin("toast", payload)
+            .map_err(Into::into)
+    }
+
+    /// Show a snackbar and wait until it is dismissed.
+    pub fn snackbar(&self, payload: SnackbarRequest) -> crate::Result<SnackbarResponse> {
+        self.0
+            .run_mobile_plugin("snackbar", payload)
+            .map_err(Into::into)
+    }
+
+    /// Post or update a system notification.
+    pub fn notify(&self, payload: NotifyRequest) -> crate::Result<()> {
+        self.0
+            .run_mobile_plugin("notify", payload)
+            .map_err(Into::into)
+    }
+
+    pub fn cancel_notification(&self, payload: CancelNotificationRequest) -> crate::Result<()> {
+        self.0
+            .run_mobile_plugin("cancelNotification", payload)
+            .map_err(Into::into)
+    }
+}
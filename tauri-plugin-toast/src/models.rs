@@ -0,0 +1,116 @@
+use serde::{Deserialize, Serialize};
+
+/// Event carrying a [`ToastRequest`] on platforms without native toasts, so
+/// the frontend can render it as a snackbar.
+pub const TOAST_EVENT: &str = "toast://show";
+
+/// Event carrying a [`SnackbarRequest`] on platforms without native
+/// snackbars. The command resolves immediately with `action_tapped: false`,
+/// the frontend handles the action itself.
+pub const SNACKBAR_EVENT: &str = "toast://snackbar";
+
+/// Plugin event (see `addPluginListener`) fired with a [`NotificationTapped`]
+/// payload when the user taps a notification that carries a deep link.
+pub const NOTIFICATION_TAPPED_EVENT: &str = "notificationTapped";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ToastDuration {
+    #[default]
+    Short,
+    Long,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ToastGravity {
+    Top,
+    Center,
+    Bottom,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ToastKind {
+    #[default]
+    Info,
+    Success,
+    Error,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToastRequest {
+    pub text: String,
+    #[serde(default)]
+    pub duration: ToastDuration,
+    /// `None` keeps the system default position.
+    #[serde(default)]
+    pub gravity: Option<ToastGravity>,
+    /// Offsets in pixels, only applied together with `gravity`.
+    #[serde(default)]
+    pub x_offset: i32,
+    #[serde(default)]
+    pub y_offset: i32,
+    /// Selects the background colour of the toast.
+    #[serde(default, rename = "type")]
+    pub kind: ToastKind,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnackbarRequest {
+    pub text: String,
+    /// Label of the action button, e.g. "撤销". No button when `None`.
+    #[serde(default)]
+    pub action_label: Option<String>,
+    #[serde(default)]
+    pub duration: ToastDuration,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnackbarResponse {
+    /// Whether the snackbar was dismissed by tapping its action.
+    pub action_tapped: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationProgress {
+    pub current: u32,
+    pub max: u32,
+    /// Show an animated bar instead of `current / max`.
+    #[serde(default)]
+    pub indeterminate: bool,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotifyRequest {
+    /// Posting again with the same id updates the notification in place.
+    pub id: i32,
+    pub title: String,
+    pub text: String,
+    #[serde(default)]
+    pub progress: Option<NotificationProgress>,
+    /// Ongoing notifications can't be swiped away.
+    #[serde(default)]
+    pub ongoing: bool,
+    /// Handed back through [`NOTIFICATION_TAPPED_EVENT`] when tapped, e.g. a
+    /// frontend route.
+    #[serde(default)]
+    pub deep_link: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CancelNotificationRequest {
+    pub id: i32,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationTapped {
+    pub deep_link: String,
+}